@@ -7,10 +7,15 @@ use edge_frontend::{
   setup::Setup,
   CompressedSNARK, Scalar,
 };
+use edge_prover::{
+  daemon::Daemon,
+  inspect::{self, ProofTree},
+};
 use tracing::{debug, error, info, trace, Level};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter, Layer};
 
 mod counter;
+mod serve;
 
 /// Creates a Noir program that is the even case of the function in the Collatz conjecture.
 pub fn collatz_even() -> NoirProgram {
@@ -101,6 +106,30 @@ enum Commands {
     #[arg(short, long, default_value = "proof.bytes")]
     proof: PathBuf,
   },
+  /// Serve prove and verify jobs over a unix socket, with the setup and keys kept in memory
+  Serve {
+    /// Path to the setup file
+    #[arg(short, long, default_value = "setup.bytes")]
+    setup: PathBuf,
+
+    /// Path of the socket to listen on
+    #[arg(long, default_value = "prover.sock")]
+    socket: PathBuf,
+  },
+  /// Print a compressed proof as a labeled tree
+  Inspect {
+    /// Path to the proof file
+    #[arg(short, long, default_value = "proof.bytes")]
+    proof: PathBuf,
+  },
+  /// Print the differences between two compressed proofs
+  Diff {
+    /// Path to the first proof file
+    a: PathBuf,
+
+    /// Path to the second proof file
+    b: PathBuf,
+  },
 }
 
 fn setup_logging(verbosity: u8) {
@@ -314,5 +343,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
       }
     },
+    Commands::Serve { setup, socket } => {
+      info!("🛰️ Starting prover daemon...");
+
+      // Step 1: Load the setup and its keys, once for all the jobs
+      let prover = serve::Prover::load(&setup, vec![collatz_even(), collatz_odd()])?;
+      info!("✅ Loaded setup and keys from file: {}", setup.display());
+
+      // Step 2: Serve jobs until a client requests a shutdown
+      let daemon = Daemon::bind(&socket)?;
+      info!("✅ Listening on socket: {}", socket.display());
+      daemon.serve(std::sync::Arc::new(prover))?;
+      info!("✅ Stopped prover daemon");
+
+      Ok(())
+    },
+    Commands::Inspect { proof } => {
+      let tree = ProofTree::from_bytes::<CompressedSNARK>(&fs::read(&proof)?)?;
+      println!("{tree}");
+      Ok(())
+    },
+    Commands::Diff { a, b } => {
+      let tree_a = ProofTree::from_bytes::<CompressedSNARK>(&fs::read(&a)?)?;
+      let tree_b = ProofTree::from_bytes::<CompressedSNARK>(&fs::read(&b)?)?;
+      let differences = inspect::diff_trees(&tree_a, &tree_b);
+      for difference in &differences {
+        println!("{difference}");
+      }
+      info!("📊 {} differences between {} and {}", differences.len(), a.display(), b.display());
+      Ok(())
+    },
   }
 }
@@ -0,0 +1,114 @@
+use std::{
+  fs,
+  path::PathBuf,
+  sync::{atomic::Ordering, Mutex},
+};
+
+use edge_frontend::{
+  noir::NoirProgram,
+  program::{self, Switchboard, RAM, Z0_SECONDARY},
+  setup::{Ready, Setup},
+  CompressedSNARK, ProverKey, Scalar, VerifierKey,
+};
+use edge_prover::daemon::{Handler, RpcError, JOB_FAILED, METHOD_NOT_FOUND};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::counter;
+
+/// The parameters of a `prove` job
+#[derive(Deserialize)]
+struct ProveParams {
+  input:  u64,
+  output: PathBuf,
+}
+
+/// The parameters of a `verify` job
+#[derive(Deserialize)]
+struct VerifyParams {
+  input: u64,
+  proof: PathBuf,
+}
+
+/// The jobs of `demo serve`: the setup, the programs and the keys are loaded
+/// once, and each job only runs the program of its input
+pub struct Prover {
+  programs: Vec<NoirProgram>,
+  // a job swaps the switchboard of its input in, so jobs run one at a time
+  setup:    Mutex<Setup<Ready<RAM>>>,
+  pk:       ProverKey,
+  vk:       VerifierKey,
+}
+
+fn job_failed(err: impl std::fmt::Display) -> RpcError {
+  RpcError::new(JOB_FAILED, err.to_string())
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+  serde_json::from_value(params).map_err(|err| RpcError::invalid_params(err.to_string()))
+}
+
+impl Prover {
+  /// Loads the setup of the file `setup`, and initializes its keys
+  pub fn load(
+    setup: &PathBuf,
+    programs: Vec<NoirProgram>,
+  ) -> Result<Self, Box<dyn std::error::Error>> {
+    let switchboard = Switchboard::<RAM>::new(programs.clone(), vec![Scalar::from(0)], 0);
+    let setup = Setup::load_file(setup)?.into_ready(switchboard);
+    let pk = CompressedSNARK::initialize_pk(
+      &setup.params,
+      setup.vk_digest_primary,
+      setup.vk_digest_secondary,
+    )?;
+    let vk = setup.verifier_key()?;
+    Ok(Self { programs, setup: Mutex::new(setup), pk, vk })
+  }
+
+  fn prove(&self, params: ProveParams) -> Result<Value, RpcError> {
+    let mut setup = self.setup.lock().map_err(job_failed)?;
+    setup.switchboard = Switchboard::<RAM>::new(
+      self.programs.clone(),
+      vec![Scalar::from(params.input)],
+      (params.input % 2) as usize,
+    );
+
+    counter::STEP_COUNTER.store(0, Ordering::SeqCst);
+    counter::reset_sequence();
+    let recursive_snark = program::run(&setup).map_err(job_failed)?;
+    let compressed_proof =
+      CompressedSNARK::prove(&setup.params, &self.pk, &recursive_snark).map_err(job_failed)?;
+
+    let serialized_proof = bincode::serialize(&compressed_proof).map_err(job_failed)?;
+    fs::write(&params.output, &serialized_proof).map_err(job_failed)?;
+    info!("✅ Saved proof to file: {}", params.output.display());
+    Ok(json!({
+      "proof": params.output,
+      "steps": counter::STEP_COUNTER.load(Ordering::SeqCst),
+      "sequence": counter::get_sequence(),
+    }))
+  }
+
+  fn verify(&self, params: VerifyParams) -> Result<Value, RpcError> {
+    let proof_bytes = fs::read(&params.proof).map_err(job_failed)?;
+    let compressed_proof: CompressedSNARK =
+      bincode::deserialize(&proof_bytes).map_err(job_failed)?;
+    let setup = self.setup.lock().map_err(job_failed)?;
+    compressed_proof
+      .verify(&setup.params, &self.vk, &[Scalar::from(params.input)], Z0_SECONDARY)
+      .map_err(job_failed)?;
+    info!("✅ Verified proof: {}", params.proof.display());
+    Ok(Value::Bool(true))
+  }
+}
+
+impl Handler for Prover {
+  fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+      "prove" => self.prove(parse(params)?),
+      "verify" => self.verify(parse(params)?),
+      _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method {method}"))),
+    }
+  }
+}
@@ -0,0 +1,101 @@
+//! Derive macros for `edge-prover`.
+//!
+//! `#[derive(TranscriptRepr)]` implements `TranscriptReprTrait` for a struct
+//! whose fields implement it, e.g. scalars, commitments and vectors of them.
+//! The transcript bytes are the bytes of the fields in declaration order, each
+//! prefixed by its length (see `edge_prover::traits::length_prefixed`), so
+//! that no two structs of the same type absorb the same bytes, unlike plain
+//! concatenations of variable-length fields.
+//!
+//! - `#[transcript(group = E::GE)]` on the struct implements the trait for that group only. Without
+//!   it, the trait is implemented for any group the fields implement it for.
+//! - `#[transcript(skip)]` on a field leaves it out of the transcript, e.g. a point derived from
+//!   the transcript itself.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+  parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Index, Member, Result, Type,
+};
+
+/// Derives `TranscriptReprTrait` with length-prefixed fields
+#[proc_macro_derive(TranscriptRepr, attributes(transcript))]
+pub fn derive_transcript_repr(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+  let fields = match &input.data {
+    Data::Struct(data) => &data.fields,
+    _ => return Err(Error::new_spanned(&input.ident, "TranscriptRepr only derives for structs")),
+  };
+  if let Fields::Unit = fields {
+    return Err(Error::new_spanned(&input.ident, "TranscriptRepr needs fields to absorb"));
+  }
+
+  let mut members = Vec::new();
+  let mut types = Vec::new();
+  for (i, field) in fields.iter().enumerate() {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("transcript")) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("skip") {
+          skip = true;
+          Ok(())
+        } else {
+          Err(meta.error("expected `skip`"))
+        }
+      })?;
+    }
+    if !skip {
+      members.push(match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(i)),
+      });
+      types.push(field.ty.clone());
+    }
+  }
+
+  let mut group: Option<Type> = None;
+  for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("transcript")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("group") {
+        group = Some(meta.value()?.parse()?);
+        Ok(())
+      } else {
+        Err(meta.error("expected `group = ...`"))
+      }
+    })?;
+  }
+
+  let trait_path = quote!(::edge_prover::traits::TranscriptReprTrait);
+  let name = &input.ident;
+
+  // without a group, the impl is generic over the groups of the fields
+  let mut generics = input.generics.clone();
+  let group = match group {
+    Some(group) => quote!(#group),
+    None => {
+      let param = format_ident!("__G");
+      generics.params.push(parse_quote!(#param: ::edge_prover::traits::Group));
+      let where_clause = generics.make_where_clause();
+      for ty in &types {
+        where_clause.predicates.push(parse_quote!(#ty: #trait_path<#param>));
+      }
+      quote!(#param)
+    },
+  };
+  let (impl_generics, _, where_clause) = generics.split_for_impl();
+  let (_, ty_generics, _) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics #trait_path<#group> for #name #ty_generics #where_clause {
+      fn to_transcript_bytes(&self) -> ::std::vec::Vec<u8> {
+        ::edge_prover::traits::length_prefixed([
+          #( <#types as #trait_path<#group>>::to_transcript_bytes(&self.#members), )*
+        ])
+      }
+    }
+  })
+}
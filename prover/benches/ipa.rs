@@ -0,0 +1,55 @@
+//! Benchmarks the prover of an IPA evaluation argument over a polynomial with
+//! 2^20 evaluations, on a single thread and on all of them: each round of the
+//! argument commits to its two cross terms concurrently, and folds the
+//! generators alongside the vectors.
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use edge_prover::{
+  provider::{ipa_pc::EvaluationEngine, Bn256EngineIPA},
+  spartan::polys::multilinear::MultilinearPolynomial,
+  traits::{
+    commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine,
+    TranscriptEngineTrait,
+  },
+};
+use ff::Field;
+
+type E = Bn256EngineIPA;
+type EE = EvaluationEngine<E>;
+type Scalar = <E as Engine>::Scalar;
+
+const NUM_VARS: usize = 20;
+
+fn bench_ipa_prove(c: &mut Criterion) {
+  let mut rng = rand::thread_rng();
+  let poly = (0..1 << NUM_VARS).map(|_| Scalar::random(&mut rng)).collect::<Vec<_>>();
+  let point = (0..NUM_VARS).map(|_| Scalar::random(&mut rng)).collect::<Vec<_>>();
+  let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+
+  let ck = Arc::new(<E as Engine>::CE::setup(b"ipa bench", poly.len()));
+  let comm = <E as Engine>::CE::commit(&ck, &poly);
+  let (pk, _vk) = EE::setup(ck.clone());
+
+  let mut group = c.benchmark_group("ipa_prove");
+  group.sample_size(10);
+  for num_threads in [1, rayon::current_num_threads()] {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build().unwrap();
+    group.bench_with_input(
+      BenchmarkId::new(format!("2^{NUM_VARS}"), format!("{num_threads} threads")),
+      &pool,
+      |b, pool| {
+        b.iter(|| {
+          pool.install(|| {
+            let mut transcript = <E as Engine>::TE::new(b"ipa bench");
+            EE::prove(&ck, &pk, &mut transcript, &comm, &poly, &point, &eval).unwrap()
+          })
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_ipa_prove);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Benchmarks folding steps of the MinRoot delay function, from a few
+//! thousand constraints per step to the size of real-world circuits: the
+//! number of iterations of a step, of three constraints each, scales the
+//! circuit folded by `prove_step`.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use edge_prover::{
+  minroot::MinRootCircuit,
+  provider::{ipa_pc::EvaluationEngine, Bn256EngineIPA},
+  spartan::snark::RelaxedR1CSSNARK,
+  supernova::TrivialCircuit,
+  traits::{snark::RelaxedR1CSSNARKTrait, Dual, Engine},
+  PublicParams, RecursiveSNARK,
+};
+use ff::Field;
+
+type E1 = Bn256EngineIPA;
+type E2 = Dual<E1>;
+type S<E> = RelaxedR1CSSNARK<E, EvaluationEngine<E>>;
+
+const NUM_STEPS: usize = 5;
+
+fn bench_minroot_prove_step(c: &mut Criterion) {
+  let mut group = c.benchmark_group("minroot_prove_step");
+  group.sample_size(10);
+  for num_iters_per_step in [1 << 10, 1 << 12, 1 << 14] {
+    let (z0, circuits) = MinRootCircuit::new(
+      <E1 as Engine>::Scalar::from(3),
+      <E1 as Engine>::Scalar::from(4),
+      num_iters_per_step,
+      NUM_STEPS,
+    )
+    .unwrap();
+    let secondary = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuits[0],
+      &secondary,
+      &*S::<E1>::ck_floor(),
+      &*S::<E2>::ck_floor(),
+    )
+    .unwrap();
+    let z0_secondary = vec![<E2 as Engine>::Scalar::ZERO];
+
+    group.bench_with_input(
+      BenchmarkId::new(format!("{NUM_STEPS} steps"), format!("{num_iters_per_step} iterations")),
+      &circuits,
+      |b, circuits| {
+        b.iter(|| {
+          let mut recursive_snark =
+            RecursiveSNARK::new(&pp, &circuits[0], &secondary, &z0, &z0_secondary).unwrap();
+          for circuit in circuits {
+            recursive_snark.prove_step(&pp, circuit, &secondary).unwrap();
+          }
+          recursive_snark
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_minroot_prove_step);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Benchmarks synthesizing Poseidon hashes of a few to a hundred elements,
+//! with the adaptive width of `sponge::hash_circuit` and with the width of 25
+//! of the sponge of the crate; the constraints of each are printed along,
+//! since they, rather than the time of synthesis, size the folded circuit.
+use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use edge_prover::provider::sponge::{hash_arity, hash_circuit, hash_circuit_with};
+use halo2curves::bn256::Fr;
+
+/// Synthesizes a hash of `len` elements, of the adaptive width without
+/// `arity`, and returns its number of constraints
+fn synthesize(len: usize, arity: Option<usize>) -> usize {
+  let mut cs = TestConstraintSystem::<Fr>::new();
+  let elements = (0..len as u64)
+    .map(|i| AllocatedNum::alloc_infallible(cs.namespace(|| format!("e{i}")), || Fr::from(i)))
+    .collect::<Vec<_>>();
+  match arity {
+    Some(arity) => hash_circuit_with(cs.namespace(|| "hash"), &elements, arity).unwrap(),
+    None => hash_circuit(cs.namespace(|| "hash"), &elements).unwrap(),
+  };
+  cs.num_constraints()
+}
+
+fn bench_poseidon_synthesize(c: &mut Criterion) {
+  let mut group = c.benchmark_group("poseidon_synthesize");
+  for len in [2, 5, 16, 50, 100] {
+    println!(
+      "{len} elements: {} constraints of arity {}, {} constraints of arity 24",
+      synthesize(len, None),
+      hash_arity::<Fr>(len),
+      synthesize(len, Some(24)),
+    );
+    group.bench_with_input(BenchmarkId::new("adaptive", len), &len, |b, len| {
+      b.iter(|| synthesize(*len, None))
+    });
+    group.bench_with_input(BenchmarkId::new("arity 24", len), &len, |b, len| {
+      b.iter(|| synthesize(*len, Some(24)))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_poseidon_synthesize);
+criterion_main!(benches);
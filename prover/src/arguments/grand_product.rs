@@ -0,0 +1,379 @@
+//! A grand-product argument: a proof that the product of a vector is a
+//! value, reduced to a claim on the multilinear extension of the vector.
+//!
+//! The prover builds the binary tree of products whose layer `k + 1` holds
+//! `V_{k+1}(x) = V_k(0, x) V_k(1, x)`, and reduces a claim on a layer to a
+//! claim on the layer below with a sum-check of degree 3,
+//!
+//! `V_{k+1}(r) = sum_x eq(r, x) V_k(0, x) V_k(1, x)`,
+//!
+//! from the root, the product, down to the vector.
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "prove")]
+use super::transcript::AllocatedPoseidonTranscript;
+use crate::{
+  constants::SecurityLevel,
+  errors::NovaError,
+  spartan::{polys::eq::EqPolynomial, ExtSumcheckProof, SumcheckTranscript},
+};
+
+/// The degree of the sum-checks of the argument
+const DEGREE: usize = 3;
+
+/// A claim that the multilinear extension of the vector of a grand product
+/// evaluates to `eval` at `point`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrandProductClaim<F> {
+  /// The point of the claim
+  pub point: Vec<F>,
+  /// The claimed evaluation
+  pub eval:  F,
+}
+
+/// A proof of the product of a vector of `2^num_vars` elements: for each
+/// layer of its tree, from the root, the sum-check reducing to its halves,
+/// and the claims on these halves
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrandProductProof<F> {
+  layers: Vec<(ExtSumcheckProof<F>, F, F)>,
+}
+
+impl<F: PrimeField> GrandProductProof<F> {
+  /// Proves the product of `values`, whose length is a power of two, and
+  /// returns it along with the claim on `values` the proof reduces to
+  pub fn prove<T: SumcheckTranscript<F, F>>(
+    values: &[F],
+    transcript: &mut T,
+  ) -> Result<(Self, F, GrandProductClaim<F>), NovaError> {
+    if !values.len().is_power_of_two() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut tree = vec![values.to_vec()];
+    while let Some(layer) = tree.last().filter(|layer| layer.len() > 1) {
+      let (left, right) = layer.split_at(layer.len() / 2);
+      let next = left.iter().zip(right).map(|(l, r)| *l * r).collect();
+      tree.push(next);
+    }
+    let product = tree[tree.len() - 1][0];
+    transcript.absorb_round(&[product]);
+
+    let (mut point, mut claim) = (Vec::new(), product);
+    let mut layers = Vec::with_capacity(tree.len() - 1);
+    for layer in tree[..tree.len() - 1].iter().rev() {
+      let (left, right) = layer.split_at(layer.len() / 2);
+      let eq = EqPolynomial::evals_from_points(&point);
+      let (sumcheck, r, evals) = ExtSumcheckProof::prove(
+        claim,
+        &[eq.as_slice(), left, right],
+        DEGREE,
+        |v: &[F]| v[0] * v[1] * v[2],
+        transcript,
+      )?;
+      let (eval_left, eval_right) = (evals[1], evals[2]);
+      transcript.absorb_round(&[eval_left, eval_right]);
+      let rho = transcript.squeeze_challenge()?;
+
+      claim = eval_left + rho * (eval_right - eval_left);
+      point = core::iter::once(rho).chain(r).collect();
+      layers.push((sumcheck, eval_left, eval_right));
+    }
+    Ok((Self { layers }, product, GrandProductClaim { point, eval: claim }))
+  }
+
+  /// Verifies the proof that the product of a vector of `2^num_vars`
+  /// elements is `product`, the sum-checks reaching `level`, and returns the
+  /// claim on the vector to check
+  pub fn verify<T: SumcheckTranscript<F, F>>(
+    &self,
+    product: F,
+    num_vars: usize,
+    level: SecurityLevel,
+    transcript: &mut T,
+  ) -> Result<GrandProductClaim<F>, NovaError> {
+    if self.layers.len() != num_vars {
+      return Err(NovaError::InvalidProductProof);
+    }
+    transcript.absorb_round(&[product]);
+
+    let (mut point, mut claim) = (Vec::new(), product);
+    for (sumcheck, eval_left, eval_right) in &self.layers {
+      let (e, r) = sumcheck.verify::<F, T>(claim, point.len(), DEGREE, level, transcript)?;
+      if e != EqPolynomial::new(point).evaluate(&r) * eval_left * eval_right {
+        return Err(NovaError::InvalidProductProof);
+      }
+      transcript.absorb_round(&[*eval_left, *eval_right]);
+      let rho = transcript.squeeze_challenge()?;
+
+      claim = *eval_left + rho * (*eval_right - eval_left);
+      point = core::iter::once(rho).chain(r).collect();
+    }
+    Ok(GrandProductClaim { point, eval: claim })
+  }
+}
+
+/// Allocates `value`
+#[cfg(feature = "prove")]
+fn alloc<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  value: Option<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  AllocatedNum::alloc(cs, || value.ok_or(SynthesisError::AssignmentMissing))
+}
+
+/// The coefficients, lowest degree first, of the polynomials of degree
+/// `degree` that are 1 at one of `0, 1, ..., degree` and 0 at the others
+#[cfg(feature = "prove")]
+fn lagrange_coeffs<F: PrimeField>(degree: usize) -> Vec<Vec<F>> {
+  (0..=degree as u64)
+    .map(|i| {
+      let (mut coeffs, mut den) = (vec![F::ONE], F::ONE);
+      for j in (0..=degree as u64).filter(|j| *j != i) {
+        // multiply by `x - j`
+        let mut next = vec![F::ZERO; coeffs.len() + 1];
+        for (k, c) in coeffs.iter().enumerate() {
+          next[k + 1] += c;
+          next[k] -= F::from(j) * c;
+        }
+        coeffs = next;
+        den *= F::from(i) - F::from(j);
+      }
+      // the interpolation points are distinct
+      #[allow(clippy::expect_used)]
+      let den_inv = Option::<F>::from(den.invert()).expect("the points are distinct");
+      coeffs.into_iter().map(|c| c * den_inv).collect()
+    })
+    .collect()
+}
+
+/// Evaluates at `r`, inside a circuit, the polynomial of evaluations `evals`
+/// at `0, 1, ...`, with Horner's rule on its coefficients
+#[cfg(feature = "prove")]
+fn interpolate_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  evals: &[AllocatedNum<F>],
+  r: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let basis = lagrange_coeffs::<F>(evals.len() - 1);
+  let coeff = |k: usize| {
+    let lc = evals
+      .iter()
+      .zip(&basis)
+      .fold(LinearCombination::zero(), |lc, (y, l)| lc + (l[k], y.get_variable()));
+    let value =
+      evals.iter().zip(&basis).map(|(y, l)| y.get_value().map(|y| y * l[k])).sum::<Option<F>>();
+    (lc, value)
+  };
+
+  let (top, top_value) = coeff(evals.len() - 1);
+  let mut acc = alloc(cs.namespace(|| "top"), top_value)?;
+  cs.enforce(|| "top", |_| top, |lc| lc + CS::one(), |lc| lc + acc.get_variable());
+  for k in (0..evals.len() - 1).rev() {
+    let (c, c_value) = coeff(k);
+    let next = alloc(
+      cs.namespace(|| format!("horner {k}")),
+      c_value.zip(r.get_value()).zip(acc.get_value()).map(|((c, r), acc)| c + r * acc),
+    )?;
+    cs.enforce(
+      || format!("horner {k}"),
+      |lc| lc + r.get_variable(),
+      |lc| lc + acc.get_variable(),
+      |lc| lc + next.get_variable() - &c,
+    );
+    acc = next;
+  }
+  Ok(acc)
+}
+
+/// Verifies inside a circuit a proof that the product of a vector of
+/// `2^num_vars` elements is `product`, whose transcript is a
+/// `PoseidonTranscript` that `transcript` follows, and returns the point and
+/// the evaluation of the claim on the vector to check. `proof` is `None`
+/// when synthesizing the shape of the circuit.
+#[cfg(feature = "prove")]
+pub fn verify_grand_product_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  proof: Option<&GrandProductProof<F>>,
+  num_vars: usize,
+  product: &AllocatedNum<F>,
+  transcript: &mut AllocatedPoseidonTranscript<F>,
+) -> Result<(Vec<AllocatedNum<F>>, AllocatedNum<F>), SynthesisError> {
+  if proof.is_some_and(|proof| proof.layers.len() != num_vars) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  transcript.absorb(cs.namespace(|| "absorb product"), &[product.clone()])?;
+
+  let (mut point, mut claim) = (Vec::<AllocatedNum<F>>::new(), product.clone());
+  for k in 0..num_vars {
+    let mut cs = cs.namespace(|| format!("layer {k}"));
+    let layer = proof.map(|proof| &proof.layers[k]);
+
+    // the sum-check over the variables of the layer above
+    let mut r = Vec::with_capacity(k);
+    for round in 0..k {
+      let mut cs = cs.namespace(|| format!("round {round}"));
+      let round_evals = layer
+        .map(|(sumcheck, ..)| {
+          let evals = sumcheck.round_evals().get(round).filter(|evals| evals.len() == DEGREE);
+          evals.ok_or(SynthesisError::Unsatisfiable)
+        })
+        .transpose()?;
+      let sent = (0..DEGREE)
+        .map(|i| alloc(cs.namespace(|| format!("eval {i}")), round_evals.map(|evals| evals[i])))
+        .collect::<Result<Vec<_>, _>>()?;
+      transcript.absorb(cs.namespace(|| "absorb"), &sent)?;
+      let r_i = transcript.squeeze(cs.namespace(|| "squeeze"))?;
+
+      // the evaluation at 1 follows from the claim
+      let eval_1 = alloc(
+        cs.namespace(|| "eval at 1"),
+        claim.get_value().zip(sent[0].get_value()).map(|(claim, e_0)| claim - e_0),
+      )?;
+      cs.enforce(
+        || "eval at 1",
+        |lc| lc + eval_1.get_variable() + sent[0].get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + claim.get_variable(),
+      );
+      let evals =
+        [sent[0].clone(), eval_1].into_iter().chain(sent[1..].iter().cloned()).collect::<Vec<_>>();
+      claim = interpolate_circuit(cs.namespace(|| "interpolate"), &evals, &r_i)?;
+      r.push(r_i);
+    }
+
+    // the final claim of the sum-check, on the halves of the layer
+    let eval_left = alloc(cs.namespace(|| "eval left"), layer.map(|(_, l, _)| *l))?;
+    let eval_right = alloc(cs.namespace(|| "eval right"), layer.map(|(_, _, r)| *r))?;
+    let mut eq = None::<AllocatedNum<F>>;
+    for (i, (p_i, r_i)) in point.iter().zip(&r).enumerate() {
+      // eq(p_i, r_i) = 2 p_i r_i - p_i - r_i + 1
+      let pr = p_i.mul(cs.namespace(|| format!("p r {i}")), r_i)?;
+      let factor = alloc(
+        cs.namespace(|| format!("eq {i}")),
+        pr.get_value()
+          .zip(p_i.get_value())
+          .zip(r_i.get_value())
+          .map(|((pr, p), r)| pr.double() - p - r + F::ONE),
+      )?;
+      cs.enforce(
+        || format!("eq {i}"),
+        |lc| {
+          lc + (F::from(2), pr.get_variable()) - p_i.get_variable() - r_i.get_variable() + CS::one()
+        },
+        |lc| lc + CS::one(),
+        |lc| lc + factor.get_variable(),
+      );
+      eq = Some(match eq {
+        None => factor,
+        Some(eq) => eq.mul(cs.namespace(|| format!("eq product {i}")), &factor)?,
+      });
+    }
+    let halves = eval_left.mul(cs.namespace(|| "left right"), &eval_right)?;
+    match eq {
+      None => cs.enforce(
+        || "claim",
+        |lc| lc + halves.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + claim.get_variable(),
+      ),
+      Some(eq) => cs.enforce(
+        || "claim",
+        |lc| lc + eq.get_variable(),
+        |lc| lc + halves.get_variable(),
+        |lc| lc + claim.get_variable(),
+      ),
+    }
+
+    // the claim on the layer, from the claims on its halves
+    transcript
+      .absorb(cs.namespace(|| "absorb halves"), &[eval_left.clone(), eval_right.clone()])?;
+    let rho = transcript.squeeze(cs.namespace(|| "rho"))?;
+    let next = alloc(
+      cs.namespace(|| "next claim"),
+      eval_left
+        .get_value()
+        .zip(eval_right.get_value())
+        .zip(rho.get_value())
+        .map(|((l, r), rho)| l + rho * (r - l)),
+    )?;
+    cs.enforce(
+      || "next claim",
+      |lc| lc + rho.get_variable(),
+      |lc| lc + eval_right.get_variable() - eval_left.get_variable(),
+      |lc| lc + next.get_variable() - eval_left.get_variable(),
+    );
+    claim = next;
+    point = core::iter::once(rho).chain(r).collect();
+  }
+  Ok((point, claim))
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    arguments::PoseidonTranscript,
+    provider::Bn256EngineIPA,
+    spartan::polys::multilinear::MultilinearPolynomial,
+    traits::{Engine, TranscriptEngineTrait},
+  };
+
+  type E = Bn256EngineIPA;
+  type F = <E as Engine>::Scalar;
+
+  #[test]
+  fn test_grand_product() {
+    let num_vars = 5;
+    let values = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let transcript = || <E as Engine>::TE::new(b"test");
+    let level = <E as Engine>::SECURITY_LEVEL;
+
+    let (proof, product, claim) = GrandProductProof::prove(&values, &mut transcript()).unwrap();
+    assert_eq!(product, values.iter().product::<F>());
+    assert_eq!(MultilinearPolynomial::evaluate_with(&values, &claim.point), claim.eval);
+    assert_eq!(proof.verify(product, num_vars, level, &mut transcript()), Ok(claim));
+
+    // another product does not reduce to the claim of the prover
+    let wrong = proof.verify(product + F::ONE, num_vars, level, &mut transcript());
+    assert!(wrong.map_or(true, |claim| {
+      MultilinearPolynomial::evaluate_with(&values, &claim.point) != claim.eval
+    }));
+  }
+
+  #[test]
+  fn test_grand_product_circuit() {
+    let num_vars = 3;
+    let values = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let (proof, product, claim) =
+      GrandProductProof::prove(&values, &mut PoseidonTranscript::new()).unwrap();
+
+    let verify = |product: F| {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let product = AllocatedNum::alloc_infallible(cs.namespace(|| "product"), || product);
+      let mut transcript = AllocatedPoseidonTranscript::new(cs.namespace(|| "transcript"));
+      let (point, eval) = verify_grand_product_circuit(
+        cs.namespace(|| "verify"),
+        Some(&proof),
+        num_vars,
+        &product,
+        &mut transcript,
+      )
+      .unwrap();
+      let point = point.iter().map(|p| p.get_value().unwrap()).collect::<Vec<_>>();
+      (cs.is_satisfied(), GrandProductClaim { point, eval: eval.get_value().unwrap() })
+    };
+
+    // the circuit reduces to the claim of the prover
+    assert_eq!(verify(product), (true, claim));
+
+    // and rejects another product
+    assert!(!verify(product + F::ONE).0);
+  }
+}
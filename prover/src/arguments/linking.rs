@@ -0,0 +1,457 @@
+//! A linking argument: a proof that two Pedersen commitments, over the groups
+//! of two engines, commit to the same value, e.g. to carry a public input of
+//! a circuit across the curve cycle, or to an external system.
+//!
+//! A value commitment over an engine is the commitment to `[value, blind]`
+//! under a key of at least two generators (see [`commit_value`]). The groups
+//! have different orders, so the argument is a sigma protocol over the
+//! integers, for values of [`VALUE_BITS`] bits: the prover commits to a mask
+//! `k` of [`MASK_BITS`] bits in both groups, and answers the challenge `c` of
+//! [`CHALLENGE_BITS`] bits with the integer `z = k + c * value`, which each
+//! group reduces modulo its order. The mask is wider than `c * value` by 48
+//! bits, which bounds what `z` tells about the value, and `z` is below both
+//! orders, which binds the value the commitments open to as an integer.
+//!
+//! The response alone does not bound the value: a scalar such as `a / 2` is a
+//! different integer modulo each order. So the prover also commits to each of
+//! the [`VALUE_BITS`] bits of the value in both groups, with blinds of which
+//! the commitments to the bits sum, weighted by their powers of two, to the
+//! value commitments, and proves for each bit that its two commitments commit
+//! to 0 in both groups or to 1 in both groups, an OR of two sigma protocols of
+//! which the prover simulates the false one. Both commitments then open to the
+//! same integer below `2^VALUE_BITS`.
+use ff::{Field, PrimeField};
+use num_bigint::BigUint;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  scalar_bytes::ScalarBytesLe,
+  secret::Zeroize,
+  traits::{
+    commitment::{CommitmentEngineTrait, Len},
+    Engine, Group, TranscriptEngineTrait, TranscriptReprTrait,
+  },
+  Commitment, CommitmentKey,
+};
+
+/// The number of bits of the values the argument links
+pub const VALUE_BITS: usize = 64;
+
+/// The number of bits of the challenge of the argument, its soundness
+pub const CHALLENGE_BITS: usize = 128;
+
+/// The number of bits of the mask of the value
+pub const MASK_BITS: usize = VALUE_BITS + CHALLENGE_BITS + 48;
+
+/// The number of bytes of the response, which is below `2^(MASK_BITS + 1)`
+const RESPONSE_BYTES: usize = MASK_BITS / 8 + 1;
+
+/// The commitment to `value` blinded by `blind`, the commitment to `[value,
+/// blind]`
+pub fn commit_value<E: Engine>(
+  ck: &CommitmentKey<E>,
+  value: u64,
+  blind: &E::Scalar,
+) -> Result<Commitment<E>, NovaError> {
+  commit_bytes::<E>(ck, &value.to_le_bytes(), blind)
+}
+
+/// The value commitment to the integer of the little-endian bytes `bytes`
+fn commit_bytes<E: Engine>(
+  ck: &CommitmentKey<E>,
+  bytes: &[u8],
+  blind: &E::Scalar,
+) -> Result<Commitment<E>, NovaError> {
+  if ck.length() < 2 {
+    return Err(NovaError::InvalidCommitmentKeyLength);
+  }
+  Ok(E::CE::commit(ck, &[reduce(bytes), *blind]))
+}
+
+/// The integer of the little-endian bytes `bytes`, reduced into `F`
+fn reduce<F: PrimeField>(bytes: &[u8]) -> F {
+  let radix = F::from(256);
+  bytes.iter().rev().fold(F::ZERO, |acc, b| acc * radix + F::from(u64::from(*b)))
+}
+
+/// Fails unless the integers below `2^(MASK_BITS + 1)` are below the order of
+/// `F`, which the binding of the argument relies on
+fn check_field<F: PrimeField>() -> Result<(), NovaError> {
+  if (F::NUM_BITS as usize) <= MASK_BITS + 1 {
+    return Err(NovaError::InsufficientSecurity(0));
+  }
+  Ok(())
+}
+
+/// The bytes of a message over the group of the other engine
+struct OtherGroupBytes(Vec<u8>);
+
+impl<G: Group> TranscriptReprTrait<G> for OtherGroupBytes {
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.0.clone() }
+}
+
+/// The powers of two in `F`, from 1
+fn powers_of_two<F: PrimeField>() -> impl Iterator<Item = F> {
+  std::iter::successors(Some(F::ONE), |p| Some(p.double()))
+}
+
+/// The commitments to the bits of `value`, with their blinds, of which the
+/// sum weighted by the powers of two is the value commitment blinded by
+/// `blind`
+fn commit_bits<E: Engine>(
+  ck: &CommitmentKey<E>,
+  value: u64,
+  blind: &E::Scalar,
+  rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<(Commitment<E>, E::Scalar)> {
+  let mut blinds = (1..VALUE_BITS).map(|_| E::Scalar::random(&mut *rng)).collect::<Vec<_>>();
+  let rest: E::Scalar =
+    blinds.iter().zip(powers_of_two::<E::Scalar>().skip(1)).map(|(r, p)| *r * p).sum();
+  blinds.insert(0, *blind - rest);
+  blinds
+    .into_iter()
+    .enumerate()
+    .map(|(i, r)| (E::CE::commit(ck, &[E::Scalar::from((value >> i) & 1), r]), r))
+    .collect()
+}
+
+/// Whether the commitments to the bits sum, weighted by the powers of two, to
+/// `comm`
+fn check_bits_sum<E: Engine>(
+  comm: &Commitment<E>,
+  bits: impl Iterator<Item = Commitment<E>>,
+) -> bool {
+  bits.zip(powers_of_two::<E::Scalar>()).map(|(b, p)| b * p).reduce(|acc, b| acc + b) == Some(*comm)
+}
+
+/// The commitment of the branch of the proof that `comm` commits to `bit`,
+/// simulated for the challenge `c` and the response `s`
+fn simulate_bit<E: Engine>(
+  ck: &CommitmentKey<E>,
+  comm: &Commitment<E>,
+  bit: u64,
+  c: u128,
+  s: &E::Scalar,
+) -> Commitment<E> {
+  let c = E::Scalar::from_u128(c);
+  E::CE::commit(ck, &[c * E::Scalar::from(bit), *s]) + *comm * -c
+}
+
+/// Whether the commitment `a` and the response `s` prove, for the challenge
+/// `c`, that `comm` commits to `bit`: `s * H + c * bit * G = a + c * comm`
+fn check_bit<E: Engine>(
+  ck: &CommitmentKey<E>,
+  comm: &Commitment<E>,
+  a: &Commitment<E>,
+  bit: u64,
+  c: u128,
+  s: &E::Scalar,
+) -> bool {
+  let c = E::Scalar::from_u128(c);
+  E::CE::commit(ck, &[c * E::Scalar::from(bit), *s]) == *a + *comm * c
+}
+
+/// A proof that the commitments to a bit of the value, over `E1` and `E2`,
+/// commit to the same bit; the branch `b` proves that both commit to `b`,
+/// under the challenge `c_0` for the branch 0 and `c ^ c_0` for the branch 1
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct BitProof<E1: Engine, E2: Engine> {
+  comm_1: Commitment<E1>,
+  comm_2: Commitment<E2>,
+  a_1:    [Commitment<E1>; 2],
+  a_2:    [Commitment<E2>; 2],
+  c_0:    u128,
+  s_1:    [E1::Scalar; 2],
+  s_2:    [E2::Scalar; 2],
+}
+
+/// A proof that two value commitments, over `E1` and `E2`, commit to the same
+/// value
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LinkingProof<E1: Engine, E2: Engine> {
+  comm_k_1: Commitment<E1>,
+  comm_k_2: Commitment<E2>,
+  z:        Vec<u8>,
+  s_1:      E1::Scalar,
+  s_2:      E2::Scalar,
+  bits:     Vec<BitProof<E1, E2>>,
+}
+
+impl<E1: Engine, E2: Engine> LinkingProof<E1, E2> {
+  /// The challenge of the argument, from a transcript of its commitments
+  fn challenge(
+    transcript: &mut E1::TE,
+    comm_1: &Commitment<E1>,
+    comm_2: &Commitment<E2>,
+    comm_k_1: &Commitment<E1>,
+    comm_k_2: &Commitment<E2>,
+    bits: &[BitProof<E1, E2>],
+  ) -> Result<u128, NovaError> {
+    transcript.dom_sep(b"linking");
+    transcript.absorb(b"C1", comm_1);
+    transcript.absorb(b"C2", &OtherGroupBytes(comm_2.to_transcript_bytes()));
+    transcript.absorb(b"K1", comm_k_1);
+    transcript.absorb(b"K2", &OtherGroupBytes(comm_k_2.to_transcript_bytes()));
+    for bit in bits {
+      transcript.absorb(b"B1", &bit.comm_1);
+      transcript.absorb(b"B2", &OtherGroupBytes(bit.comm_2.to_transcript_bytes()));
+      for (a_1, a_2) in bit.a_1.iter().zip(&bit.a_2) {
+        transcript.absorb(b"A1", a_1);
+        transcript.absorb(b"A2", &OtherGroupBytes(a_2.to_transcript_bytes()));
+      }
+    }
+    let c = ScalarBytesLe::new(&transcript.squeeze(b"c")?);
+    let mut bytes = [0u8; CHALLENGE_BITS / 8];
+    bytes.copy_from_slice(&c.as_bytes()[..CHALLENGE_BITS / 8]);
+    Ok(u128::from_le_bytes(bytes))
+  }
+
+  /// Proves that `comm_1` and `comm_2`, the value commitments to `value`
+  /// blinded by `blind_1` and `blind_2`, commit to the same value
+  pub fn prove(
+    ck_1: &CommitmentKey<E1>,
+    ck_2: &CommitmentKey<E2>,
+    comm_1: &Commitment<E1>,
+    comm_2: &Commitment<E2>,
+    value: u64,
+    blind_1: &E1::Scalar,
+    blind_2: &E2::Scalar,
+    transcript: &mut E1::TE,
+    mut rng: impl RngCore + CryptoRng,
+  ) -> Result<Self, NovaError> {
+    check_field::<E1::Scalar>()?;
+    check_field::<E2::Scalar>()?;
+
+    let mut k = [0u8; MASK_BITS / 8];
+    rng.fill_bytes(&mut k);
+    let (r_1, r_2) = (E1::Scalar::random(&mut rng), E2::Scalar::random(&mut rng));
+    let comm_k_1 = commit_bytes::<E1>(ck_1, &k, &r_1)?;
+    let comm_k_2 = commit_bytes::<E2>(ck_2, &k, &r_2)?;
+
+    // the branch of the bit is proven with the nonces `w`, the other simulated
+    let bits_1 = commit_bits::<E1>(ck_1, value, blind_1, &mut rng);
+    let bits_2 = commit_bits::<E2>(ck_2, value, blind_2, &mut rng);
+    let mut nonces = Vec::with_capacity(VALUE_BITS);
+    let mut bits = Vec::with_capacity(VALUE_BITS);
+    for (i, ((bit_comm_1, b_1), (bit_comm_2, b_2))) in bits_1.into_iter().zip(bits_2).enumerate() {
+      let bit = ((value >> i) & 1) as usize;
+      let other = 1 - bit;
+      let mut c_other = [0u8; CHALLENGE_BITS / 8];
+      rng.fill_bytes(&mut c_other);
+      let c_other = u128::from_le_bytes(c_other);
+      let (w_1, w_2) = (E1::Scalar::random(&mut rng), E2::Scalar::random(&mut rng));
+
+      let mut proof = BitProof {
+        comm_1: bit_comm_1,
+        comm_2: bit_comm_2,
+        a_1:    [E1::CE::commit(ck_1, &[E1::Scalar::ZERO, w_1]); 2],
+        a_2:    [E2::CE::commit(ck_2, &[E2::Scalar::ZERO, w_2]); 2],
+        c_0:    c_other,
+        s_1:    [E1::Scalar::random(&mut rng); 2],
+        s_2:    [E2::Scalar::random(&mut rng); 2],
+      };
+      proof.a_1[other] =
+        simulate_bit::<E1>(ck_1, &bit_comm_1, other as u64, c_other, &proof.s_1[other]);
+      proof.a_2[other] =
+        simulate_bit::<E2>(ck_2, &bit_comm_2, other as u64, c_other, &proof.s_2[other]);
+      nonces.push((c_other, w_1, w_2, b_1, b_2));
+      bits.push(proof);
+    }
+
+    let c = Self::challenge(transcript, comm_1, comm_2, &comm_k_1, &comm_k_2, &bits)?;
+    let mut z = (BigUint::from_bytes_le(&k) + BigUint::from(c) * value).to_bytes_le();
+    z.resize(RESPONSE_BYTES, 0);
+    k.zeroize();
+
+    for (i, (proof, (c_other, w_1, w_2, b_1, b_2))) in bits.iter_mut().zip(nonces).enumerate() {
+      let bit = ((value >> i) & 1) as usize;
+      let c_bit = c ^ c_other;
+      if bit == 0 {
+        proof.c_0 = c_bit;
+      }
+      proof.s_1[bit] = w_1 + E1::Scalar::from_u128(c_bit) * b_1;
+      proof.s_2[bit] = w_2 + E2::Scalar::from_u128(c_bit) * b_2;
+    }
+
+    Ok(Self {
+      comm_k_1,
+      comm_k_2,
+      z,
+      s_1: r_1 + E1::Scalar::from_u128(c) * blind_1,
+      s_2: r_2 + E2::Scalar::from_u128(c) * blind_2,
+      bits,
+    })
+  }
+
+  /// Verifies that `comm_1` and `comm_2` commit to the same value
+  pub fn verify(
+    &self,
+    ck_1: &CommitmentKey<E1>,
+    ck_2: &CommitmentKey<E2>,
+    comm_1: &Commitment<E1>,
+    comm_2: &Commitment<E2>,
+    transcript: &mut E1::TE,
+  ) -> Result<(), NovaError> {
+    check_field::<E1::Scalar>()?;
+    check_field::<E2::Scalar>()?;
+    if self.z.len() != RESPONSE_BYTES || self.bits.len() != VALUE_BITS {
+      return Err(NovaError::InvalidInputLength);
+    }
+    // the response is the same integer in both groups
+    if BigUint::from_bytes_le(&self.z).bits() > (MASK_BITS + 1) as u64 {
+      return Err(NovaError::ProofVerifyError);
+    }
+
+    let c =
+      Self::challenge(transcript, comm_1, comm_2, &self.comm_k_1, &self.comm_k_2, &self.bits)?;
+    let lhs_1 = commit_bytes::<E1>(ck_1, &self.z, &self.s_1)?;
+    let lhs_2 = commit_bytes::<E2>(ck_2, &self.z, &self.s_2)?;
+    if lhs_1 != self.comm_k_1 + *comm_1 * E1::Scalar::from_u128(c)
+      || lhs_2 != self.comm_k_2 + *comm_2 * E2::Scalar::from_u128(c)
+    {
+      return Err(NovaError::ProofVerifyError);
+    }
+
+    // the value commitments open to the same integer below `2^VALUE_BITS`
+    if !check_bits_sum::<E1>(comm_1, self.bits.iter().map(|bit| bit.comm_1))
+      || !check_bits_sum::<E2>(comm_2, self.bits.iter().map(|bit| bit.comm_2))
+    {
+      return Err(NovaError::ProofVerifyError);
+    }
+    for bit in &self.bits {
+      for (b, c_b) in [(0, bit.c_0), (1, c ^ bit.c_0)] {
+        if !check_bit::<E1>(ck_1, &bit.comm_1, &bit.a_1[b], b as u64, c_b, &bit.s_1[b])
+          || !check_bit::<E2>(ck_2, &bit.comm_2, &bit.a_2[b], b as u64, c_b, &bit.s_2[b])
+        {
+          return Err(NovaError::ProofVerifyError);
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::{Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine};
+
+  fn test_linking_with<E1: Engine, E2: Engine>() {
+    let ck_1 = E1::CE::setup(b"linking", 2);
+    let ck_2 = E2::CE::setup(b"linking", 2);
+    let value = u64::MAX - 7;
+    let (blind_1, blind_2) = (E1::Scalar::random(&mut OsRng), E2::Scalar::random(&mut OsRng));
+    let comm_1 = commit_value::<E1>(&ck_1, value, &blind_1).unwrap();
+    let comm_2 = commit_value::<E2>(&ck_2, value, &blind_2).unwrap();
+
+    let prove = |comm_2: &Commitment<E2>, value: u64| {
+      let mut transcript = E1::TE::new(b"test");
+      LinkingProof::<E1, E2>::prove(
+        &ck_1,
+        &ck_2,
+        &comm_1,
+        comm_2,
+        value,
+        &blind_1,
+        &blind_2,
+        &mut transcript,
+        OsRng,
+      )
+      .unwrap()
+    };
+    let verify = |proof: &LinkingProof<E1, E2>, comm_2: &Commitment<E2>| {
+      let mut transcript = E1::TE::new(b"test");
+      proof.verify(&ck_1, &ck_2, &comm_1, comm_2, &mut transcript)
+    };
+
+    let proof = prove(&comm_2, value);
+    assert_eq!(verify(&proof, &comm_2), Ok(()));
+    let bytes = bincode::serialize(&proof).unwrap();
+    assert_eq!(bincode::deserialize::<LinkingProof<E1, E2>>(&bytes).unwrap(), proof);
+
+    // commitments to different values are not linked
+    let other = commit_value::<E2>(&ck_2, value + 1, &blind_2).unwrap();
+    assert_eq!(verify(&prove(&other, value), &other), Err(NovaError::ProofVerifyError));
+    assert_eq!(verify(&proof, &other), Err(NovaError::ProofVerifyError));
+
+    // nor is a proof with another response
+    let mut wrong = proof.clone();
+    wrong.z[0] ^= 1;
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::ProofVerifyError));
+    wrong.z.push(0);
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::InvalidInputLength));
+    let mut wrong = proof.clone();
+    wrong.z[RESPONSE_BYTES - 1] |= 2;
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::ProofVerifyError));
+
+    // nor is a proof with other bits
+    let mut wrong = proof.clone();
+    wrong.bits[3].c_0 ^= 1;
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::ProofVerifyError));
+    let mut wrong = proof.clone();
+    wrong.bits.swap(0, 3);
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::ProofVerifyError));
+    wrong.bits.pop();
+    assert_eq!(verify(&wrong, &comm_2), Err(NovaError::InvalidInputLength));
+
+    // a scalar such as `3 / 2` in both groups passes the response for even
+    // challenges, but has no bits below `2^VALUE_BITS`
+    let half_1 = E1::Scalar::from(3) * E1::Scalar::from(2).invert().unwrap();
+    let half_2 = E2::Scalar::from(3) * E2::Scalar::from(2).invert().unwrap();
+    let comm_half_1 = E1::CE::commit(&ck_1, &[half_1, blind_1]);
+    let comm_half_2 = E2::CE::commit(&ck_2, &[half_2, blind_2]);
+    let forged = loop {
+      let mut k = [0u8; MASK_BITS / 8];
+      OsRng.fill_bytes(&mut k);
+      let (r_1, r_2) = (E1::Scalar::random(&mut OsRng), E2::Scalar::random(&mut OsRng));
+      let comm_k_1 = commit_bytes::<E1>(&ck_1, &k, &r_1).unwrap();
+      let comm_k_2 = commit_bytes::<E2>(&ck_2, &k, &r_2).unwrap();
+      let mut transcript = E1::TE::new(b"test");
+      let c = LinkingProof::<E1, E2>::challenge(
+        &mut transcript,
+        &comm_half_1,
+        &comm_half_2,
+        &comm_k_1,
+        &comm_k_2,
+        &proof.bits,
+      )
+      .unwrap();
+      if c % 2 == 0 {
+        let mut z = (BigUint::from_bytes_le(&k) + BigUint::from(c / 2) * 3u32).to_bytes_le();
+        z.resize(RESPONSE_BYTES, 0);
+        break LinkingProof::<E1, E2> {
+          comm_k_1,
+          comm_k_2,
+          z,
+          s_1: r_1 + E1::Scalar::from_u128(c) * blind_1,
+          s_2: r_2 + E2::Scalar::from_u128(c) * blind_2,
+          bits: proof.bits.clone(),
+        };
+      }
+    };
+    let mut transcript = E1::TE::new(b"test");
+    assert_eq!(
+      forged.verify(&ck_1, &ck_2, &comm_half_1, &comm_half_2, &mut transcript),
+      Err(NovaError::ProofVerifyError)
+    );
+
+    // a value commitment takes two generators
+    let ck = E1::CE::setup(b"linking", 1);
+    assert_eq!(
+      commit_value::<E1>(&ck, value, &blind_1),
+      Err(NovaError::InvalidCommitmentKeyLength)
+    );
+  }
+
+  #[test]
+  fn test_linking() {
+    test_linking_with::<Bn256EngineIPA, GrumpkinEngine>();
+    test_linking_with::<Bn256EngineKZG, GrumpkinEngine>();
+  }
+}
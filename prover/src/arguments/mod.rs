@@ -0,0 +1,22 @@
+//! Arguments that lookups, memory checking and multiset equality across steps
+//! are built from, with provers and verifiers both outside and inside
+//! circuits.
+//!
+//! The arguments reduce to claims on multilinear extensions, which the caller
+//! checks e.g. with the evaluation engine of a commitment. Their transcripts
+//! are the transcripts of sum-checks: a verifier inside a circuit follows a
+//! [`PoseidonTranscript`] with an [`AllocatedPoseidonTranscript`].
+//!
+//! The [`linking`] argument stands apart: it links value commitments over the
+//! groups of two engines.
+pub mod grand_product;
+pub mod linking;
+mod transcript;
+
+#[cfg(feature = "prove")]
+pub use grand_product::verify_grand_product_circuit;
+pub use grand_product::{GrandProductClaim, GrandProductProof};
+pub use linking::LinkingProof;
+#[cfg(feature = "prove")]
+pub use transcript::AllocatedPoseidonTranscript;
+pub use transcript::PoseidonTranscript;
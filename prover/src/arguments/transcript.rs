@@ -0,0 +1,95 @@
+//! A Poseidon transcript for arguments verified inside circuits, natively
+//! and in-circuit.
+//!
+//! Its state is a single field element: absorbing elements hashes them with
+//! the state, and squeezing a challenge hashes the state alone, so that the
+//! two are separated by the length of what is hashed.
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+#[cfg(feature = "prove")] use crate::gadgets::alloc_zero;
+use crate::{
+  errors::NovaError, provider::poseidon::PoseidonConstantsCircuit, spartan::SumcheckTranscript,
+};
+
+/// A Poseidon transcript outside circuits
+#[derive(Clone, Debug)]
+pub struct PoseidonTranscript<F: PrimeField> {
+  state:     F,
+  constants: PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> Default for PoseidonTranscript<F> {
+  fn default() -> Self { Self::new() }
+}
+
+impl<F: PrimeField> PoseidonTranscript<F> {
+  /// Creates an empty transcript
+  pub fn new() -> Self { Self { state: F::ZERO, constants: PoseidonConstantsCircuit::default() } }
+
+  /// Absorbs `elements`
+  pub fn absorb(&mut self, elements: &[F]) {
+    let elements = core::iter::once(self.state).chain(elements.iter().copied()).collect::<Vec<_>>();
+    self.state = self.constants.hash(&elements);
+  }
+
+  /// Squeezes a challenge
+  pub fn squeeze(&mut self) -> F {
+    self.state = self.constants.hash(&[self.state]);
+    self.state
+  }
+}
+
+impl<F: PrimeField> SumcheckTranscript<F, F> for PoseidonTranscript<F> {
+  fn absorb_round(&mut self, evals: &[F]) { self.absorb(evals) }
+
+  fn squeeze_challenge(&mut self) -> Result<F, NovaError> { Ok(self.squeeze()) }
+}
+
+/// A Poseidon transcript inside circuits, which squeezes the challenges of
+/// a `PoseidonTranscript` that absorbs the same elements
+#[cfg(feature = "prove")]
+pub struct AllocatedPoseidonTranscript<F: PrimeField> {
+  state:     AllocatedNum<F>,
+  constants: PoseidonConstantsCircuit<F>,
+  count:     usize,
+}
+
+#[cfg(feature = "prove")]
+impl<F: PrimeField> AllocatedPoseidonTranscript<F> {
+  /// Creates an empty transcript
+  pub fn new<CS: ConstraintSystem<F>>(mut cs: CS) -> Self {
+    Self {
+      state:     alloc_zero(cs.namespace(|| "state")),
+      constants: PoseidonConstantsCircuit::default(),
+      count:     0,
+    }
+  }
+
+  /// Absorbs `elements`
+  pub fn absorb<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    elements: &[AllocatedNum<F>],
+  ) -> Result<(), SynthesisError> {
+    let elements =
+      core::iter::once(self.state.clone()).chain(elements.iter().cloned()).collect::<Vec<_>>();
+    self.state =
+      self.constants.hash_circuit(cs.namespace(|| format!("absorb {}", self.count)), &elements)?;
+    self.count += 1;
+    Ok(())
+  }
+
+  /// Squeezes a challenge
+  pub fn squeeze<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    self.state = self
+      .constants
+      .hash_circuit(cs.namespace(|| format!("squeeze {}", self.count)), &[self.state.clone()])?;
+    self.count += 1;
+    Ok(self.state.clone())
+  }
+}
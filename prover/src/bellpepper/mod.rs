@@ -5,7 +5,8 @@
 pub mod r1cs;
 pub mod shape_cs;
 pub mod solver;
-pub mod test_shape_cs;
+// only formats constraints into a `String` for debugging, which does not fail
+#[allow(clippy::unwrap_used)] pub mod test_shape_cs;
 
 #[cfg(test)]
 mod tests {
@@ -64,6 +64,8 @@ macro_rules! impl_nova_shape {
     impl<E: Engine> NovaShape<E> for $name<E>
     where E::Scalar: PrimeField
     {
+      // the constraints only refer to allocated variables
+      #[allow(clippy::expect_used)]
       fn r1cs_shape(&self) -> R1CSShape<E> {
         let mut A = SparseMatrix::<E::Scalar>::empty();
         let mut B = SparseMatrix::<E::Scalar>::empty();
@@ -85,8 +87,8 @@ macro_rules! impl_nova_shape {
         C.cols = num_vars + num_inputs;
 
         // Don't count One as an input for shape's purposes.
-        let res = R1CSShape::new(num_constraints, num_vars, num_inputs - 1, A, B, C);
-        res.unwrap()
+        R1CSShape::new(num_constraints, num_vars, num_inputs - 1, A, B, C)
+          .expect("the constraints stay within the allocated variables")
       }
     }
   };
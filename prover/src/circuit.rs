@@ -5,15 +5,18 @@
 //! ck), i, z0, zi, U). Each circuit folds the last invocation of the other into
 //! the running instance
 
+#[cfg(feature = "prove")]
 use bellpepper::gadgets::{boolean_utils::conditionally_select_slice, Assignment};
+#[cfg(feature = "prove")]
 use bellpepper_core::{
   boolean::{AllocatedBit, Boolean},
   num::AllocatedNum,
   ConstraintSystem, SynthesisError,
 };
-use ff::Field;
+#[cfg(feature = "prove")] use ff::Field;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "prove")]
 use crate::{
   constants::{NIO_NOVA_FOLD, NUM_FE_WITHOUT_IO_FOR_CRHF, NUM_HASH_BITS},
   gadgets::{
@@ -42,6 +45,7 @@ impl NovaAugmentedCircuitParams {
 // NOTES: All these options here seem to point towards using a typestate pattern
 // or something.
 
+#[cfg(feature = "prove")]
 #[derive(Debug, Serialize)]
 #[serde(bound = "")]
 pub struct NovaAugmentedCircuitInputs<E: Engine> {
@@ -54,6 +58,7 @@ pub struct NovaAugmentedCircuitInputs<E: Engine> {
   T:      Option<Commitment<E>>,
 }
 
+#[cfg(feature = "prove")]
 impl<E: Engine> NovaAugmentedCircuitInputs<E> {
   /// Create new inputs/witness for the verification circuit
   pub fn new(
@@ -69,6 +74,7 @@ impl<E: Engine> NovaAugmentedCircuitInputs<E> {
   }
 }
 
+#[cfg(feature = "prove")]
 /// The augmented circuit F' in Nova that includes a step circuit F
 /// and the circuit for the verifier in Nova's non-interactive folding scheme
 pub struct NovaAugmentedCircuit<'a, E: Engine, SC: StepCircuit<E::Base>> {
@@ -78,6 +84,7 @@ pub struct NovaAugmentedCircuit<'a, E: Engine, SC: StepCircuit<E::Base>> {
   step_circuit: &'a SC, // The function that is applied for each step
 }
 
+#[cfg(feature = "prove")]
 impl<'a, E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'a, E, SC> {
   /// Create a new verification circuit for the input relaxed r1cs instances
   pub const fn new(
@@ -232,6 +239,7 @@ impl<'a, E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'a, E, SC> {
   }
 }
 
+#[cfg(feature = "prove")]
 impl<E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'_, E, SC> {
   /// synthesize circuit giving constraint system
   pub fn synthesize<CS: ConstraintSystem<<E as Engine>::Base>>(
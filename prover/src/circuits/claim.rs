@@ -0,0 +1,437 @@
+//! Step circuits proving common claims about a private value extracted by
+//! another circuit, e.g. that a balance is at least a threshold, that a
+//! country is in a set, or that a birth date is before a date, configured by
+//! a [`Claim`] rather than written per deployment.
+//!
+//! A [`ClaimStep`] opens the commitment to the value in its `z`, of
+//! [`value_commitment`], and enforces the claim on it, so that the verifier
+//! learns that the claim holds of the committed value and nothing else. The
+//! claims are over the value as text: numbers are the ASCII decimals of
+//! `parse_decimal`, and dates the ISO 8601 dates `YYYY-MM-DD` the value
+//! starts with, e.g. of a timestamp, compared as `YYYYMMDD` without checking
+//! that the month and the day exist.
+use bellpepper_core::{
+  boolean::Boolean, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  gadgets::{
+    alloc_bytes, alloc_packed, constant_bytes, decimal_bits, pack_bytes, padded_equal,
+    parse_decimal, parse_integer, range_check,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+  supernova::StepCircuit,
+};
+
+/// The number of elements of the `z` of a [`ClaimStep`]: the commitment to
+/// the value
+pub const ARITY: usize = 1;
+
+/// The length of the dates `YYYY-MM-DD`
+const DATE_LEN: usize = 10;
+
+/// The number of bits of the dates `YYYYMMDD`
+const DATE_BITS: usize = 27;
+
+/// The maximal number of bits of the numbers compared, so that their
+/// differences with the thresholds are range-checked well below the moduli
+/// of the scalar fields
+const MAX_NUMBER_BITS: usize = 248;
+
+/// A claim about a value
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "claim", rename_all = "snake_case")]
+pub enum Claim {
+  /// The value is a decimal of at most `scale` digits after its point, and
+  /// is at least `threshold / 10^scale`
+  AtLeast {
+    /// the threshold, times `10^scale`
+    threshold: u64,
+    /// the number of digits after the point
+    #[serde(default)]
+    scale:     usize,
+  },
+  /// The value is a decimal of at most `scale` digits after its point, and
+  /// is at most `threshold / 10^scale`
+  AtMost {
+    /// the threshold, times `10^scale`
+    threshold: u64,
+    /// the number of digits after the point
+    #[serde(default)]
+    scale:     usize,
+  },
+  /// The value is one of `values`
+  OneOf {
+    /// the values, distinct
+    values: Vec<String>,
+  },
+  /// The value starts with a date before `date`, a `YYYY-MM-DD`
+  Before {
+    /// the date, excluded
+    date: String,
+  },
+  /// The value starts with a date after `date`, a `YYYY-MM-DD`
+  After {
+    /// the date, excluded
+    date: String,
+  },
+}
+
+/// The value times `10^scale` of the decimal `value`, of at most `scale`
+/// digits after its point
+fn parse_scaled(value: &[u8], scale: usize) -> Option<u128> {
+  let (integer, fraction) = match value.iter().position(|b| *b == b'.') {
+    Some(point) => (&value[..point], Some(&value[point + 1..])),
+    None => (value, None),
+  };
+  let digits = |digits: &[u8]| {
+    (!digits.is_empty() && digits.iter().all(u8::is_ascii_digit)).then(|| {
+      digits.iter().try_fold(0u128, |acc, d| acc.checked_mul(10)?.checked_add(u128::from(d - b'0')))
+    })?
+  };
+  let power = |exponent: usize| 10u128.checked_pow(u32::try_from(exponent).ok()?);
+  let mut scaled = digits(integer)?.checked_mul(power(scale)?)?;
+  if let Some(fraction) = fraction {
+    let shift = scale.checked_sub(fraction.len())?;
+    scaled = scaled.checked_add(digits(fraction)?.checked_mul(power(shift)?)?)?;
+  }
+  Some(scaled)
+}
+
+/// The date `YYYYMMDD` of the `YYYY-MM-DD` `value` starts with
+fn parse_date(value: &[u8]) -> Option<u64> {
+  let date = value.get(..DATE_LEN)?;
+  if date[4] != b'-' || date[7] != b'-' {
+    return None;
+  }
+  [&date[..4], &date[5..7], &date[8..]]
+    .concat()
+    .iter()
+    .try_fold(0u64, |acc, d| d.is_ascii_digit().then(|| 10 * acc + u64::from(d - b'0')))
+}
+
+impl Claim {
+  /// Checks that the claim is well-formed for values of at most `max_len`
+  /// bytes
+  pub fn validate(&self, max_len: usize) -> Result<(), NovaError> {
+    let invalid = |reason: &str| Err(NovaError::InvalidClaim(reason.into()));
+    match self {
+      Self::AtLeast { scale, .. } | Self::AtMost { scale, .. } => {
+        if max_len == 0 || decimal_bits(max_len + scale) > MAX_NUMBER_BITS {
+          return invalid("the values do not fit in a field element");
+        }
+      },
+      Self::OneOf { values } => {
+        let mut sorted = values.iter().collect::<Vec<_>>();
+        sorted.sort();
+        sorted.dedup();
+        if values.is_empty() || sorted.len() != values.len() {
+          return invalid("the values are not distinct");
+        }
+        if values.iter().any(|value| value.len() > max_len) {
+          return invalid("a value is longer than the values");
+        }
+      },
+      Self::Before { date } | Self::After { date } => {
+        if date.len() != DATE_LEN || parse_date(date.as_bytes()).is_none() {
+          return invalid("the date is not a YYYY-MM-DD");
+        }
+        if max_len < DATE_LEN {
+          return invalid("the values are shorter than dates");
+        }
+      },
+    }
+    Ok(())
+  }
+
+  /// Whether the claim holds of `value`
+  pub fn holds(&self, value: &[u8]) -> bool {
+    match self {
+      Self::AtLeast { threshold, scale } =>
+        parse_scaled(value, *scale).is_some_and(|v| v >= u128::from(*threshold)),
+      Self::AtMost { threshold, scale } =>
+        parse_scaled(value, *scale).is_some_and(|v| v <= u128::from(*threshold)),
+      Self::OneOf { values } => values.iter().any(|v| v.as_bytes() == value),
+      Self::Before { date } | Self::After { date } => {
+        let Some((value, date)) = parse_date(value).zip(parse_date(date.as_bytes())) else {
+          return false;
+        };
+        if matches!(self, Self::Before { .. }) {
+          value < date
+        } else {
+          value > date
+        }
+      },
+    }
+  }
+}
+
+/// The commitment to `value`, padded with zeros to `max_len` bytes: the hash
+/// of its packing, then of its length
+pub fn value_commitment<F: PrimeField>(value: &[u8], max_len: usize) -> Result<F, NovaError> {
+  if value.len() > max_len {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let mut padded = value.to_vec();
+  padded.resize(max_len, 0);
+  let elements = [pack_bytes(&padded), vec![F::from(value.len() as u64)]].concat();
+  Ok(PoseidonConstantsCircuit::<F>::default().hash(&elements))
+}
+
+/// The step proving a claim about a committed value
+#[derive(Clone, Debug)]
+pub struct ClaimStep {
+  claim:   Claim,
+  max_len: usize,
+  value:   Vec<u8>,
+}
+
+impl ClaimStep {
+  /// The step proving `claim` about `value`, of at most `max_len` bytes; the
+  /// shape of the step depends on the claim and on `max_len` only
+  pub fn new(claim: Claim, max_len: usize, value: &[u8]) -> Result<Self, NovaError> {
+    claim.validate(max_len)?;
+    if value.len() > max_len {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if !claim.holds(value) {
+      return Err(NovaError::InvalidClaim("the claim does not hold".into()));
+    }
+    Ok(Self { claim, max_len, value: value.to_vec() })
+  }
+}
+
+/// Allocates the constant `value`
+fn alloc_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: u64,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let num = AllocatedNum::alloc(cs.namespace(|| "alloc"), || Ok(F::from(value)))?;
+  cs.enforce(
+    || "constant",
+    |lc| lc + num.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + (F::from(value), CS::one()),
+  );
+  Ok(num)
+}
+
+/// Enforces that `lc`, of value `value`, is not negative, as an integer of
+/// `bits` bits
+fn enforce_non_negative<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  lc: LinearCombination<F>,
+  value: Option<F>,
+  bits: usize,
+) -> Result<(), SynthesisError> {
+  range_check(cs, lc, value, bits).map(|_| ())
+}
+
+/// The date `YYYYMMDD` of the `YYYY-MM-DD` the bytes `bytes`, of length
+/// `len`, start with
+fn date<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[Boolean],
+  len: &AllocatedNum<F>,
+) -> Result<(LinearCombination<F>, Option<F>), SynthesisError> {
+  let max_len = bytes.len() / 8;
+  enforce_non_negative(
+    cs.namespace(|| "long enough"),
+    LinearCombination::zero() + len.get_variable() - (F::from(DATE_LEN as u64), CS::one()),
+    len.get_value().map(|len| len - F::from(DATE_LEN as u64)),
+    (usize::BITS - max_len.leading_zeros()) as usize,
+  )?;
+  let dash = constant_bytes(b"-");
+  for at in [4, 7] {
+    for (i, bit) in bytes[8 * at..8 * (at + 1)].iter().enumerate() {
+      Boolean::enforce_equal(cs.namespace(|| format!("dash {at} bit {i}")), bit, &dash[i])?;
+    }
+  }
+
+  let mut lc = LinearCombination::zero();
+  let mut value = Some(F::ZERO);
+  for (name, range, weight) in [("year", 0..4, 10_000), ("month", 5..7, 100), ("day", 8..10, 1)] {
+    let n = alloc_constant(cs.namespace(|| format!("{name} len")), range.len() as u64)?;
+    let bits = &bytes[8 * range.start..8 * range.end];
+    let part = parse_integer(cs.namespace(|| name), bits, &n)?;
+    lc = lc + (F::from(weight), part.get_variable());
+    value = value.zip(part.get_value()).map(|(v, p)| v + F::from(weight) * p);
+  }
+  Ok((lc, value))
+}
+
+impl<F: PrimeField> StepCircuit<F> for ClaimStep {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    if z.len() != ARITY || self.value.len() > self.max_len {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut padded = self.value.clone();
+    padded.resize(self.max_len, 0);
+    let bytes = alloc_bytes(cs.namespace(|| "value"), Some(&padded), self.max_len)?;
+    let len = AllocatedNum::alloc(cs.namespace(|| "len"), || Ok(F::from(self.value.len() as u64)))?;
+
+    // the value opens the commitment
+    let elements = [alloc_packed(cs.namespace(|| "pack"), &bytes)?, vec![len.clone()]].concat();
+    let commitment = PoseidonConstantsCircuit::<F>::default()
+      .hash_circuit(cs.namespace(|| "commit"), &elements)?;
+    cs.enforce(
+      || "commitment",
+      |lc| lc + commitment.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + z[0].get_variable(),
+    );
+
+    match &self.claim {
+      Claim::AtLeast { threshold, scale } => {
+        let value = parse_decimal(cs.namespace(|| "parse"), &bytes, &len, *scale)?;
+        let threshold = F::from(*threshold);
+        enforce_non_negative(
+          cs.namespace(|| "at least"),
+          LinearCombination::zero() + value.get_variable() - (threshold, CS::one()),
+          value.get_value().map(|v| v - threshold),
+          decimal_bits(self.max_len + scale),
+        )?;
+      },
+      Claim::AtMost { threshold, scale } => {
+        let value = parse_decimal(cs.namespace(|| "parse"), &bytes, &len, *scale)?;
+        let threshold = F::from(*threshold);
+        enforce_non_negative(
+          cs.namespace(|| "at most"),
+          LinearCombination::zero() + (threshold, CS::one()) - value.get_variable(),
+          value.get_value().map(|v| threshold - v),
+          64,
+        )?;
+      },
+      Claim::OneOf { values } => {
+        // the values are distinct, so that the value is at most one of them
+        let mut sum = LinearCombination::zero();
+        for (i, value) in values.iter().enumerate() {
+          let mut cs = cs.namespace(|| format!("value {i}"));
+          let mut constant = value.as_bytes().to_vec();
+          constant.resize(self.max_len, 0);
+          let constant_len = alloc_constant(cs.namespace(|| "len"), value.len() as u64)?;
+          let equal = padded_equal(
+            cs.namespace(|| "equal"),
+            &bytes,
+            &len,
+            &constant_bytes(&constant),
+            &constant_len,
+          )?;
+          sum = sum + &equal.lc(CS::one(), F::ONE);
+        }
+        cs.enforce(|| "one of", |_| sum, |lc| lc + CS::one(), |lc| lc + CS::one());
+      },
+      Claim::Before { date: bound } | Claim::After { date: bound } => {
+        let (date_lc, date_value) = date(cs.namespace(|| "date"), &bytes, &len)?;
+        let bound = F::from(parse_date(bound.as_bytes()).ok_or(SynthesisError::Unsatisfiable)?);
+        // before: bound - 1 - date >= 0, after: date - bound - 1 >= 0
+        let before = matches!(self.claim, Claim::Before { .. });
+        let (lc, value) = if before {
+          (
+            LinearCombination::zero() + (bound - F::ONE, CS::one()) - &date_lc,
+            date_value.map(|d| bound - F::ONE - d),
+          )
+        } else {
+          (date_lc - (bound + F::ONE, CS::one()), date_value.map(|d| d - bound - F::ONE))
+        };
+        enforce_non_negative(cs.namespace(|| "order"), lc, value, DATE_BITS)?;
+      },
+    }
+    Ok((pc.cloned(), z.to_vec()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  const MAX_LEN: usize = 24;
+
+  /// Whether the step proving `claim` about `value` is satisfied, whether or
+  /// not the claim holds
+  fn is_satisfied(claim: &Claim, value: &[u8]) -> bool {
+    let step = ClaimStep { claim: claim.clone(), max_len: MAX_LEN, value: value.to_vec() };
+    let mut cs = TestConstraintSystem::<F>::new();
+    let commitment = value_commitment::<F>(value, MAX_LEN).unwrap();
+    let z = AllocatedNum::alloc_infallible(cs.namespace(|| "z"), || commitment);
+    match step.synthesize(&mut cs, None, &[z]) {
+      Ok(_) => cs.is_satisfied(),
+      Err(_) => false,
+    }
+  }
+
+  #[test]
+  fn test_claims() {
+    let claims: [(Claim, &[&str], &[&str]); 5] = [
+      (Claim::AtLeast { threshold: 100_000, scale: 2 }, &["1000", "1000.0", "2500.75"], &[
+        "999.99", "abc", "", "1e6",
+      ]),
+      (Claim::AtMost { threshold: 17, scale: 0 }, &["17", "0", "00017"], &["18", "17.5", "-1"]),
+      (Claim::OneOf { values: vec!["CH".into(), "FR".into(), "DE".into()] }, &["CH", "DE"], &[
+        "GB", "C", "CHE", "",
+      ]),
+      (Claim::Before { date: "2008-10-14".into() }, &["2008-10-13", "1990-01-01T00:00:00Z"], &[
+        "2008-10-14",
+        "2026-01-01",
+        "2008/01/01",
+        "2008-1-1",
+      ]),
+      (Claim::After { date: "2024-02-29".into() }, &["2024-03-01", "2030-12-31 23:59"], &[
+        "2024-02-29",
+        "2023-12-31",
+      ]),
+    ];
+    for (claim, holding, failing) in claims {
+      claim.validate(MAX_LEN).unwrap();
+      for value in holding {
+        assert!(claim.holds(value.as_bytes()), "{claim:?} {value}");
+        assert!(is_satisfied(&claim, value.as_bytes()), "{claim:?} {value}");
+      }
+      for value in failing {
+        assert!(!claim.holds(value.as_bytes()), "{claim:?} {value}");
+        assert!(!is_satisfied(&claim, value.as_bytes()), "{claim:?} {value}");
+      }
+    }
+  }
+
+  #[test]
+  fn test_claim_step() {
+    let claim: Claim = serde_json::from_str(r#"{"claim":"at_least","threshold":18}"#).unwrap();
+    assert_eq!(claim, Claim::AtLeast { threshold: 18, scale: 0 });
+    assert!(ClaimStep::new(claim.clone(), MAX_LEN, b"42").is_ok());
+    assert!(matches!(ClaimStep::new(claim, MAX_LEN, b"12"), Err(NovaError::InvalidClaim(_))));
+
+    // the value must open the commitment
+    let step = ClaimStep::new(Claim::AtMost { threshold: 5, scale: 0 }, MAX_LEN, b"3").unwrap();
+    let mut cs = TestConstraintSystem::<F>::new();
+    let commitment = value_commitment::<F>(b"4", MAX_LEN).unwrap();
+    let z = AllocatedNum::alloc_infallible(cs.namespace(|| "z"), || commitment);
+    step.synthesize(&mut cs, None, &[z]).unwrap();
+    assert!(!cs.is_satisfied());
+
+    let invalid = [
+      Claim::OneOf { values: vec!["a".into(), "a".into()] },
+      Claim::Before { date: "2008-13".into() },
+      Claim::AtLeast { threshold: 0, scale: 60 },
+    ];
+    for claim in invalid {
+      assert!(matches!(claim.validate(MAX_LEN), Err(NovaError::InvalidClaim(_))), "{claim:?}");
+    }
+  }
+}
@@ -0,0 +1,510 @@
+//! A step circuit extracting a claim from a JWT (RFC 7519), e.g. the email of
+//! an ID token, to its public output while keeping the rest of the token
+//! private.
+//!
+//! A [`JwtStep`] hashes the signing input of the token, `header.payload`,
+//! with SHA-256, verifies its RS256 or ES256 signature under the key of the
+//! issuer, a witness, decodes its base64url payload, and extracts the string
+//! value of a claim, found at an offset of the decoded payload the prover
+//! chooses: the member `"claim":"value"` must be preceded by `{` or `,`, so
+//! that it is not inside a string, whose quotes are escaped, and the value
+//! must have no escapes. The outputs are laid out by [`JwtOutput`]: the
+//! commitment to the key (see [`JwtKey::commitment`]), then the length of the
+//! value and its bytes, packed.
+//!
+//! The verifier checks the commitment against the keys of the issuers it
+//! trusts, e.g. the key a manifest pins or the key an X.509 chain certifies,
+//! whose commitments to RSA keys are the same. Neither the token nor its
+//! digest is output, so that the proofs of a token are not linked by it.
+use bellpepper::gadgets::{boolean_utils::conditionally_select, sha256::sha256};
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use super::x509::key_commitment;
+use crate::{
+  errors::NovaError,
+  gadgets::{
+    alloc_bytes, alloc_packed, alloc_zero, base64_decode, bytes_value, constant_bytes,
+    ecdsa::{self, verify_ecdsa_p256_sha256, P256PublicKey},
+    rsa::{verify_pkcs1v15_sha256, RsaModulus},
+    unpack_bytes, Alphabet, BYTES_PER_ELEMENT,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+  supernova::{remote::hex_bytes, StepCircuit},
+};
+
+/// The least length of the moduli of RSA keys, in bytes
+pub const MIN_MODULUS_LEN: usize = 256;
+
+/// The key of the issuer of tokens, by the algorithm of their signatures
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "alg", deny_unknown_fields)]
+pub enum JwtKey {
+  /// An RSA key of the public exponent 65537, for RSASSA-PKCS1-v1_5 with
+  /// SHA-256
+  #[serde(rename = "RS256")]
+  Rs256 {
+    /// The big-endian modulus, of at least [`MIN_MODULUS_LEN`] bytes
+    #[serde(with = "hex_bytes")]
+    modulus: Vec<u8>,
+  },
+  /// A P-256 key, for ECDSA with SHA-256
+  #[serde(rename = "ES256")]
+  Es256 {
+    /// The big-endian coordinates `x || y`
+    #[serde(with = "hex_bytes")]
+    point: Vec<u8>,
+  },
+}
+
+impl JwtKey {
+  /// The bytes the key is committed to: the modulus of RSA keys, and the
+  /// coordinates of P-256 keys
+  pub fn bytes(&self) -> &[u8] {
+    match self {
+      Self::Rs256 { modulus } => modulus,
+      Self::Es256 { point } => point,
+    }
+  }
+
+  /// The commitment to the key, which the steps verifying its tokens output:
+  /// the [`key_commitment`] of its bytes, as for the keys of certificates
+  pub fn commitment<F: PrimeField>(&self) -> F { key_commitment(self.bytes()) }
+
+  /// Whether the key has the lengths of its algorithm, and RSA moduli their
+  /// most significant bit set
+  pub fn is_well_formed(&self) -> bool {
+    match self {
+      Self::Rs256 { modulus } =>
+        modulus.len() >= MIN_MODULUS_LEN && modulus.first().is_some_and(|b| b & 0x80 != 0),
+      Self::Es256 { point } => point.len() == ecdsa::KEY_LEN,
+    }
+  }
+
+  /// The length of the signatures of the key
+  fn signature_len(&self) -> usize {
+    match self {
+      Self::Rs256 { modulus } => modulus.len(),
+      Self::Es256 { .. } => ecdsa::SIGNATURE_LEN,
+    }
+  }
+}
+
+/// The bytes of the unpadded base64url `encoded`, if it is canonical
+fn base64url_decode(encoded: &str) -> Option<Vec<u8>> {
+  let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+  let (mut acc, mut n_bits) = (0u32, 0);
+  for c in encoded.bytes() {
+    let value = match c {
+      b'A'..=b'Z' => c - b'A',
+      b'a'..=b'z' => c - b'a' + 26,
+      b'0'..=b'9' => c - b'0' + 52,
+      b'-' => 62,
+      b'_' => 63,
+      _ => return None,
+    };
+    acc = (acc << 6) | u32::from(value);
+    n_bits += 6;
+    if n_bits >= 8 {
+      n_bits -= 8;
+      bytes.push((acc >> n_bits) as u8);
+      acc &= (1 << n_bits) - 1;
+    }
+  }
+  // a lone last character, or trailing bits set, is not canonical
+  (n_bits < 6 && acc == 0).then_some(bytes)
+}
+
+/// The step extracting a claim from a JWT
+#[derive(Clone, Debug)]
+pub struct JwtStep {
+  header:        Vec<u8>,
+  payload:       Vec<u8>,
+  signature:     Vec<u8>,
+  key:           JwtKey,
+  claim:         Vec<u8>,
+  max_value_len: usize,
+}
+
+impl JwtStep {
+  /// The step extracting the string claim `claim`, of at most `max_value_len`
+  /// bytes, from the compact serialization of `token`, signed by `key`; the
+  /// shape of the step depends on the lengths of the header and the payload
+  /// of the token, on the algorithm of the key and the length of RSA moduli,
+  /// on the claim and on `max_value_len` only
+  pub fn new(
+    token: &str,
+    claim: &str,
+    max_value_len: usize,
+    key: &JwtKey,
+  ) -> Result<Self, NovaError> {
+    let parts = token.split('.').collect::<Vec<_>>();
+    let [header, payload, signature] = parts[..] else {
+      return Err(NovaError::InvalidInputLength);
+    };
+    let signature = base64url_decode(signature)
+      .filter(|s| key.is_well_formed() && s.len() == key.signature_len())
+      .ok_or(NovaError::InvalidInputLength)?;
+    Ok(Self {
+      header: header.as_bytes().to_vec(),
+      payload: payload.as_bytes().to_vec(),
+      signature,
+      key: key.clone(),
+      claim: claim.as_bytes().to_vec(),
+      max_value_len,
+    })
+  }
+
+  /// The number of elements of the `z` of the step
+  pub fn arity_for(max_value_len: usize) -> usize { 2 + max_value_len.div_ceil(BYTES_PER_ELEMENT) }
+
+  /// The member of the claim up to its value: `"claim":"`
+  fn pattern(&self) -> Vec<u8> { [b"\"", &self.claim[..], b"\":\""].concat() }
+}
+
+/// The public output of a [`JwtStep`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JwtOutput<F: PrimeField> {
+  /// the commitment to the key of the issuer, see [`JwtKey::commitment`]
+  pub key:   F,
+  /// the value of the claim
+  pub value: Vec<u8>,
+}
+
+impl<F: PrimeField> JwtOutput<F> {
+  /// Reads the output of a step with values of at most `max_value_len` bytes
+  /// from its `z`
+  pub fn from_io(z: &[F], max_value_len: usize) -> Result<Self, NovaError> {
+    if z.len() != JwtStep::arity_for(max_value_len) {
+      return Err(NovaError::InvalidStepCircuitIO);
+    }
+    let len = (0..=max_value_len)
+      .find(|len| F::from(*len as u64) == z[1])
+      .ok_or(NovaError::InvalidStepCircuitIO)?;
+    let mut value = unpack_bytes(&z[2..], max_value_len).ok_or(NovaError::InvalidStepCircuitIO)?;
+    value.truncate(len);
+    Ok(Self { key: z[0], value })
+  }
+}
+
+/// Whether `num` is `value`
+fn equals_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  num: &AllocatedNum<F>,
+  value: u8,
+) -> Result<AllocatedBit, SynthesisError> {
+  let value = F::from(u64::from(value));
+  let diff = num.get_value().map(|v| v - value);
+  let equal = AllocatedBit::alloc(cs.namespace(|| "equal"), diff.map(|d| d == F::ZERO))?;
+  let inverse = AllocatedNum::alloc(cs.namespace(|| "inverse"), || {
+    let diff = diff.ok_or(SynthesisError::AssignmentMissing)?;
+    Ok(diff.invert().unwrap_or(F::ZERO))
+  })?;
+  // diff * inverse = 1 - equal, and diff * equal = 0
+  cs.enforce(
+    || "unequal",
+    |lc| lc + num.get_variable() - (value, CS::one()),
+    |lc| lc + inverse.get_variable(),
+    |lc| lc + CS::one() - equal.get_variable(),
+  );
+  cs.enforce(
+    || "equal",
+    |lc| lc + num.get_variable() - (value, CS::one()),
+    |lc| lc + equal.get_variable(),
+    |lc| lc,
+  );
+  Ok(equal)
+}
+
+impl<F: PrimeField> StepCircuit<F> for JwtStep {
+  fn arity(&self) -> usize { Self::arity_for(self.max_value_len) }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    _z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let header = alloc_bytes(cs.namespace(|| "header"), Some(&self.header), self.header.len())?;
+    let payload = alloc_bytes(cs.namespace(|| "payload"), Some(&self.payload), self.payload.len())?;
+    let signing_input = [header, constant_bytes(b"."), payload.clone()].concat();
+    let digest = sha256(cs.namespace(|| "digest"), &signing_input)?;
+
+    // the signature of the digest under the key, which is committed to
+    let signature = Some(&self.signature[..]);
+    let key = match &self.key {
+      JwtKey::Rs256 { modulus } => {
+        let modulus = RsaModulus::alloc(cs.namespace(|| "modulus"), Some(modulus), modulus.len())?;
+        verify_pkcs1v15_sha256(cs.namespace(|| "rs256"), &modulus, signature, &digest)?;
+        modulus.bytes().to_vec()
+      },
+      JwtKey::Es256 { point } => {
+        let key = P256PublicKey::alloc(cs.namespace(|| "point"), Some(point))?;
+        verify_ecdsa_p256_sha256(cs.namespace(|| "es256"), &key, signature, &digest)?;
+        key.bytes().to_vec()
+      },
+    };
+    let elements = alloc_packed(cs.namespace(|| "key elements"), &key)?;
+    let poseidon = PoseidonConstantsCircuit::<F>::default();
+    let key = poseidon.hash_circuit(cs.namespace(|| "key"), &elements)?;
+
+    let decoded = base64_decode(cs.namespace(|| "decode"), &payload, Alphabet::UrlSafe)?;
+    let bytes = decoded
+      .bytes
+      .chunks(8)
+      .enumerate()
+      .map(|(i, bits)| {
+        let value = bits.iter().enumerate().fold(LinearCombination::zero(), |lc, (j, bit)| {
+          lc + &bit.lc(CS::one(), F::from(1 << (7 - j)))
+        });
+        let byte = AllocatedNum::alloc(cs.namespace(|| format!("byte {i}")), || {
+          let byte = bytes_value(bits).ok_or(SynthesisError::AssignmentMissing)?;
+          Ok(F::from(u64::from(byte[0])))
+        })?;
+        cs.enforce(
+          || format!("byte {i} of bits"),
+          |_| value,
+          |lc| lc + CS::one(),
+          |lc| lc + byte.get_variable(),
+        );
+        Ok(byte)
+      })
+      .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    // the offset of the member of the claim, preceded by `{` or `,`
+    let pattern = self.pattern();
+    let offset = bytes_value(&decoded.bytes).map(|payload| {
+      payload
+        .windows(1 + pattern.len())
+        .position(|w| matches!(w[0], b'{' | b',') && w[1..] == pattern[..])
+        .unwrap_or(0)
+    });
+    let n_bits = (usize::BITS - bytes.len().max(1).leading_zeros()) as usize;
+    let offset_bits = (0..n_bits)
+      .map(|i| {
+        let bit = offset.map(|o| (o >> i) & 1 == 1);
+        AllocatedBit::alloc(cs.namespace(|| format!("offset bit {i}")), bit)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    // shifts the payload by the offset, one bit of the offset at a time
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let window_len = 1 + pattern.len() + self.max_value_len + 1;
+    let mut shifted = bytes;
+    shifted.resize(shifted.len().max(window_len), zero.clone());
+    for (i, bit) in offset_bits.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("shift {i}"));
+      shifted = (0..shifted.len())
+        .map(|j| {
+          let moved = shifted.get(j + (1 << i)).unwrap_or(&zero);
+          let bit = Boolean::from(bit.clone());
+          conditionally_select(cs.namespace(|| format!("byte {j}")), moved, &shifted[j], &bit)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    }
+    let window = &shifted[..window_len];
+
+    cs.enforce(
+      || "member follows { or ,",
+      |lc| lc + window[0].get_variable() - (F::from(u64::from(b'{')), CS::one()),
+      |lc| lc + window[0].get_variable() - (F::from(u64::from(b',')), CS::one()),
+      |lc| lc,
+    );
+    for (i, (byte, expected)) in window[1..].iter().zip(&pattern).enumerate() {
+      cs.enforce(
+        || format!("pattern {i}"),
+        |lc| lc + byte.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + (F::from(u64::from(*expected)), CS::one()),
+      );
+    }
+
+    // the value runs up to the first quote, without escapes
+    let mut inside = Boolean::constant(true);
+    let mut value = Vec::with_capacity(self.max_value_len);
+    let mut len = LinearCombination::zero();
+    for (i, byte) in window[1 + pattern.len()..].iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("value {i}"));
+      let quote = equals_constant(cs.namespace(|| "quote"), byte, b'"')?;
+      let backslash = equals_constant(cs.namespace(|| "backslash"), byte, b'\\')?;
+      inside = Boolean::and(cs.namespace(|| "inside"), &inside, &Boolean::from(quote).not())?;
+      cs.enforce(
+        || "no escape",
+        |_| inside.lc(CS::one(), F::ONE),
+        |lc| lc + backslash.get_variable(),
+        |lc| lc,
+      );
+      if i == self.max_value_len {
+        // the last byte of the window closes the value if no other byte did
+        cs.enforce(|| "closed", |_| inside.lc(CS::one(), F::ONE), |lc| lc + CS::one(), |lc| lc);
+        break;
+      }
+      let masked = AllocatedNum::alloc(cs.namespace(|| "masked"), || {
+        let inside = inside.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+        let byte = byte.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+        Ok(if inside { byte } else { F::ZERO })
+      })?;
+      cs.enforce(
+        || "mask",
+        |_| inside.lc(CS::one(), F::ONE),
+        |lc| lc + byte.get_variable(),
+        |lc| lc + masked.get_variable(),
+      );
+      len = len + &inside.lc(CS::one(), F::ONE);
+      value.push(masked);
+    }
+
+    let mut z_out = vec![key];
+    let len_num = AllocatedNum::alloc(cs.namespace(|| "len"), || {
+      let len = value
+        .iter()
+        .zip(window[1 + pattern.len()..].iter())
+        .take_while(|(_, byte)| byte.get_value() != Some(F::from(u64::from(b'"'))))
+        .count();
+      Ok(F::from(len as u64))
+    })?;
+    cs.enforce(|| "len", |_| len, |lc| lc + CS::one(), |lc| lc + len_num.get_variable());
+    z_out.push(len_num);
+    for (i, chunk) in value.chunks(BYTES_PER_ELEMENT).enumerate() {
+      let packed = AllocatedNum::alloc(cs.namespace(|| format!("value {i}")), || {
+        chunk.iter().try_fold(F::ZERO, |acc, byte| {
+          Ok(acc * F::from(256) + byte.get_value().ok_or(SynthesisError::AssignmentMissing)?)
+        })
+      })?;
+      let mut coeff = F::ONE;
+      let mut lc = LinearCombination::zero();
+      for byte in chunk.iter().rev() {
+        lc = lc + (coeff, byte.get_variable());
+        coeff *= F::from(256);
+      }
+      cs.enforce(
+        || format!("pack value {i}"),
+        |_| lc,
+        |lc| lc + CS::one(),
+        |lc| lc + packed.get_variable(),
+      );
+      z_out.push(packed);
+    }
+    Ok((pc.cloned(), z_out))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  // the keys of an issuer, and an RS256 and an ES256 token it signed, of the
+  // payload {"sub":"1234567890","email":"alice@example.com","admin":true}
+  const MODULUS: &str = concat!(
+    "bafa782eb745fc8199ca8e2758bb816e14a69734cc7180a92ca5f00f2e33fcf9",
+    "65a256c63031e2d32ceb3df6b88d96565c8674990af7fd6b429c37100943a232",
+    "a9ad061df91904d416bcb25b3096c6d1acf01d3b9f6b57f371b3dd188ccdd767",
+    "0b56653c6727c642b5548c254d82dba3c03e9e59227e9bce41f4cfaed349d46c",
+    "735153be9c928ac2e929dc64ec81aecd29ceb64d6e4adbbbd535d027e3ed46cc",
+    "483bfc8aa302337e70a7b929d03cfd6a5b576387d6505acbe0f72e6c7b568d02",
+    "4184be3fcfec114fe87a62e25ef694d2e2e49ad67060b33e6ef50a80d4f6f9f9",
+    "c01786b38cc3136437e96679bda2e690c07dbfe1744c9e259864ede4b5a996cb",
+  );
+  const POINT: &str = concat!(
+    "2bb78e50c7ea1fd18874ae815d9083e945d032bbf25423b5a20431b2a4d7841e",
+    "26651b28f26d95162b80319e10bad39219355b7371e5be93126ea12d91637982",
+  );
+  const RS256_TOKEN: &str =
+    "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.\
+     eyJzdWIiOiIxMjM0NTY3ODkwIiwiZW1haWwiOiJhbGljZUBleGFtcGxlLmNvbSIsImFkbWluIjp0cnVlfQ.\
+     MRPmwzXOnBq9WM7NaIDxyNyIAm0f4pX0M8qorNASvmadffOQ-6_Z2xrgoRlKUcOFvc7KSl0XNV__rpCX\
+     kCQ19inBzJJHBta2t0AVtSSTPvuCz729TkYWJFv4Ir8AzJLqN1orzdkU-qk5vPSMdlchrUXdNadrwHy9\
+     W9qY7zqF_D5xdz2DJ83RN3ZOzcvfwyGWGEiYYnRFXhEKVgwcnEoXq1ncj5McqSToTT5ahXAJ1YoDeMbH\
+     VsEoMpuPq-055ZBjvzvelitT17urIFGTDRuqK_oHFzgmDvGGhZAEmj3Sini5r3yCKw7pwy-1ouxWk0cz\
+     o_Shg7vQuIRvefbNSGTf8w";
+  const ES256_TOKEN: &str =
+    "eyJhbGciOiJFUzI1NiIsInR5cCI6IkpXVCJ9.\
+     eyJzdWIiOiIxMjM0NTY3ODkwIiwiZW1haWwiOiJhbGljZUBleGFtcGxlLmNvbSIsImFkbWluIjp0cnVlfQ.\
+     GITUPMi-z7cFyCSA5544rNiOhNJUXZj4mfVNTGfqAhcJmaVRqdOhNfFfh_1TB1YQDL17PagSV_r36FoSw7qp4A";
+  // an RS256 token of {"sub":"1234567890","email":"bob@example.com","admin":true}
+  const BOB_TOKEN: &str =
+    "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.\
+     eyJzdWIiOiIxMjM0NTY3ODkwIiwiZW1haWwiOiJib2JAZXhhbXBsZS5jb20iLCJhZG1pbiI6dHJ1ZX0.\
+     qJr3r8kMKnzKqKBUGeTL4_Ppt2FqO7dqO6ndl3YY3X7HoWKWlBlw3Glh4UbyVk4xTK_8O7GZBR8MeZ4V\
+     aiYUMM-PLQWKb9G-acA0XGktNx6H_T-pb0DYpi0JnRg2gw1FpGjLfMVe3awqn3RIdjUYJCBk9Mt1B6E5\
+     iL_EfOp-UTkoT-CrCiEip60f4pSWPEmCO4sAhLzKoom68DOwiQqOIn2lr9mb2wQ5MduBIXLFb_xdiE7F\
+     ZYoQncP4jE4V62YQQp1dcCgsohDjWuWSOWoz55zGYOUqCxvwuNAVt0g5OX01D5sbGRTFHtMy9HE-hRU-\
+     3tANbR_dyrDzzAQLwwFYCw";
+
+  fn rs256_key() -> JwtKey { JwtKey::Rs256 { modulus: hex::decode(MODULUS).unwrap() } }
+
+  fn es256_key() -> JwtKey { JwtKey::Es256 { point: hex::decode(POINT).unwrap() } }
+
+  fn extract(token: &str, claim: &str, key: &JwtKey) -> Option<JwtOutput<F>> {
+    let step = JwtStep::new(token, claim, 20, key).unwrap();
+    let arity = JwtStep::arity_for(20);
+    let mut cs = TestConstraintSystem::<F>::new();
+    let z = (0..arity)
+      .map(|i| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(F::ZERO)))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    let (_, z_out) = step.synthesize(&mut cs.namespace(|| "step"), None, &z).unwrap();
+    let z_out = z_out.iter().map(|v| v.get_value().unwrap()).collect::<Vec<_>>();
+    cs.is_satisfied().then(|| JwtOutput::from_io(&z_out, 20).unwrap())
+  }
+
+  #[test]
+  fn test_jwt_claim() {
+    let key = rs256_key();
+    let output = extract(RS256_TOKEN, "email", &key).unwrap();
+    assert_eq!(output.value, b"alice@example.com");
+    assert_eq!(output.key, key.commitment::<F>());
+
+    assert_eq!(extract(RS256_TOKEN, "sub", &key).unwrap().value, b"1234567890");
+    // the claim is not a string, or not in the payload
+    assert_eq!(extract(RS256_TOKEN, "admin", &key), None);
+    assert_eq!(extract(RS256_TOKEN, "name", &key), None);
+    assert!(JwtStep::new("header.payload", "email", 20, &key).is_err());
+  }
+
+  #[test]
+  fn test_jwt_signature() {
+    let key = rs256_key();
+    // the signature of another payload, or another key
+    let (signing_input, _) = BOB_TOKEN.rsplit_once('.').unwrap();
+    let (_, signature) = RS256_TOKEN.rsplit_once('.').unwrap();
+    let forged = format!("{signing_input}.{signature}");
+    assert_eq!(extract(&forged, "email", &key), None);
+    let mut modulus = hex::decode(MODULUS).unwrap();
+    modulus[MIN_MODULUS_LEN - 1] ^= 2;
+    assert_eq!(extract(RS256_TOKEN, "email", &JwtKey::Rs256 { modulus }), None);
+
+    // signatures of another algorithm than the key, and malformed keys
+    assert!(JwtStep::new(RS256_TOKEN, "email", 20, &es256_key()).is_err());
+    assert!(JwtStep::new(ES256_TOKEN, "email", 20, &key).is_err());
+    let short = JwtKey::Rs256 { modulus: hex::decode(&MODULUS[..256]).unwrap() };
+    assert!(JwtStep::new(RS256_TOKEN, "email", 20, &short).is_err());
+
+    assert_eq!(base64url_decode("aGk"), Some(b"hi".to_vec()));
+    assert_eq!(base64url_decode("aGl"), None);
+    assert_eq!(base64url_decode("aGk="), None);
+  }
+
+  // The verification of ES256 signatures allocates about 1.6M constraints:
+  // cargo test --release test_jwt_es256 -- --ignored
+  #[test]
+  #[ignore]
+  fn test_jwt_es256() {
+    let key = es256_key();
+    let output = extract(ES256_TOKEN, "email", &key).unwrap();
+    assert_eq!(output.value, b"alice@example.com");
+    assert_eq!(output.key, key.commitment::<F>());
+    assert_ne!(output.key, rs256_key().commitment::<F>());
+  }
+}
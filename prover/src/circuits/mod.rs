@@ -0,0 +1,7 @@
+//! Circuits for the statements web proofs are made of, to be composed into
+//! the steps of a `RecursiveSNARK` or of a SuperNova program.
+pub mod claim;
+pub mod jwt;
+pub mod regex;
+pub mod tls;
+pub mod x509;
@@ -0,0 +1,385 @@
+//! The compilation of regular expressions into the automata of
+//! [`Dfa`](super::Dfa): a parser into an abstract syntax, and the position
+//! automaton of Glushkov, determinized by the subset construction.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::NovaError;
+
+/// The bounds of the counts of `{m,n}` repetitions
+const MAX_REPEAT: usize = 255;
+
+/// A set of bytes
+pub(crate) type ByteSet = [bool; 256];
+
+/// The abstract syntax of a regular expression
+#[derive(Clone, Debug)]
+enum Node {
+  Empty,
+  Class(Box<ByteSet>),
+  Concat(Vec<Node>),
+  Alt(Vec<Node>),
+  Star(Box<Node>),
+  Plus(Box<Node>),
+  Optional(Box<Node>),
+  Group { reveal: bool, node: Box<Node> },
+}
+
+/// A recursive-descent parser over the bytes of a pattern
+struct Parser<'a> {
+  pattern: &'a [u8],
+  pos:     usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> NovaError {
+    NovaError::InvalidRegex(format!("{message} at {}", self.pos))
+  }
+
+  fn peek(&self) -> Option<u8> { self.pattern.get(self.pos).copied() }
+
+  fn eat(&mut self, byte: u8) -> bool {
+    let found = self.peek() == Some(byte);
+    self.pos += usize::from(found);
+    found
+  }
+
+  fn next(&mut self) -> Result<u8, NovaError> {
+    let byte = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+    self.pos += 1;
+    Ok(byte)
+  }
+
+  /// `alt := concat ('|' concat)*`
+  fn alt(&mut self) -> Result<Node, NovaError> {
+    let mut branches = vec![self.concat()?];
+    while self.eat(b'|') {
+      branches.push(self.concat()?);
+    }
+    Ok(if branches.len() == 1 { branches.remove(0) } else { Node::Alt(branches) })
+  }
+
+  /// `concat := repeat*`
+  fn concat(&mut self) -> Result<Node, NovaError> {
+    let mut nodes = Vec::new();
+    while !matches!(self.peek(), None | Some(b'|' | b')')) {
+      nodes.push(self.repeat()?);
+    }
+    Ok(match nodes.len() {
+      0 => Node::Empty,
+      1 => nodes.remove(0),
+      _ => Node::Concat(nodes),
+    })
+  }
+
+  /// `repeat := atom ('*' | '+' | '?' | '{m}' | '{m,}' | '{m,n}')*`
+  fn repeat(&mut self) -> Result<Node, NovaError> {
+    let mut node = self.atom()?;
+    loop {
+      node = match self.peek() {
+        Some(b'*') => Node::Star(Box::new(node)),
+        Some(b'+') => Node::Plus(Box::new(node)),
+        Some(b'?') => Node::Optional(Box::new(node)),
+        Some(b'{') => {
+          self.pos += 1;
+          let min = self.count()?;
+          let max = if self.eat(b',') {
+            if self.peek() == Some(b'}') {
+              None
+            } else {
+              Some(self.count()?)
+            }
+          } else {
+            Some(min)
+          };
+          if self.peek() != Some(b'}') || max.is_some_and(|max| max < min) {
+            return Err(self.error("invalid repetition"));
+          }
+          repetition(node, min, max)
+        },
+        _ => return Ok(node),
+      };
+      self.pos += 1;
+    }
+  }
+
+  /// A decimal count of at most [`MAX_REPEAT`]
+  fn count(&mut self) -> Result<usize, NovaError> {
+    let start = self.pos;
+    while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    std::str::from_utf8(&self.pattern[start..self.pos])
+      .ok()
+      .and_then(|digits| digits.parse().ok())
+      .filter(|count| *count <= MAX_REPEAT)
+      .ok_or_else(|| self.error("invalid count"))
+  }
+
+  /// `atom := '(' alt ')' | '(?:' alt ')' | '[' class ']' | '.' | escape |
+  /// byte`
+  fn atom(&mut self) -> Result<Node, NovaError> {
+    match self.next()? {
+      b'(' => {
+        let reveal = !self.eat(b'?');
+        if !reveal && !self.eat(b':') {
+          return Err(self.error("unsupported group"));
+        }
+        let node = self.alt()?;
+        if !self.eat(b')') {
+          return Err(self.error("unclosed group"));
+        }
+        Ok(Node::Group { reveal, node: Box::new(node) })
+      },
+      b'[' => self.class(),
+      b'.' => Ok(Node::Class(Box::new([true; 256]))),
+      b'\\' => self.escape(),
+      b'*' | b'+' | b'?' | b'{' => Err(self.error("nothing to repeat")),
+      b'^' | b'$' => Err(self.error("anchors are unsupported, patterns match whole inputs")),
+      byte => Ok(Node::Class(Box::new(singleton(byte)))),
+    }
+  }
+
+  /// The class of an escape, after its `\`
+  fn escape(&mut self) -> Result<Node, NovaError> {
+    let byte = self.next()?;
+    let class = match byte {
+      b'd' | b'D' | b'w' | b'W' | b's' | b'S' => {
+        let mut class = [false; 256];
+        for (b, member) in class.iter_mut().enumerate() {
+          let b = b as u8;
+          *member = match byte.to_ascii_lowercase() {
+            b'd' => b.is_ascii_digit(),
+            b'w' => b.is_ascii_alphanumeric() || b == b'_',
+            _ => b.is_ascii_whitespace() || b == 0x0b,
+          } != byte.is_ascii_uppercase();
+        }
+        class
+      },
+      _ => singleton(self.escaped_byte(byte)?),
+    };
+    Ok(Node::Class(Box::new(class)))
+  }
+
+  /// The byte of the escape `\byte`, other than a class
+  fn escaped_byte(&mut self, byte: u8) -> Result<u8, NovaError> {
+    match byte {
+      b'n' => Ok(b'\n'),
+      b'r' => Ok(b'\r'),
+      b't' => Ok(b'\t'),
+      b'x' => {
+        let digits = [self.next()?, self.next()?];
+        std::str::from_utf8(&digits)
+          .ok()
+          .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+          .ok_or_else(|| self.error("invalid hexadecimal escape"))
+      },
+      _ if byte.is_ascii_punctuation() => Ok(byte),
+      _ => Err(self.error("unsupported escape")),
+    }
+  }
+
+  /// `class := '^'? (item ('-' item)?)+`, after its `[`
+  fn class(&mut self) -> Result<Node, NovaError> {
+    let negated = self.eat(b'^');
+    let mut class = [false; 256];
+    let mut first = true;
+    while first || self.peek() != Some(b']') {
+      first = false;
+      let lo = match self.next()? {
+        b'\\' => {
+          let byte = self.next()?;
+          if matches!(byte, b'd' | b'D' | b'w' | b'W' | b's' | b'S') {
+            self.pos -= 1;
+            let Node::Class(escaped) = self.escape()? else { unreachable!("escapes are classes") };
+            class.iter_mut().zip(escaped.iter()).for_each(|(c, e)| *c |= e);
+            continue;
+          }
+          self.escaped_byte(byte)?
+        },
+        byte if byte.is_ascii() => byte,
+        _ => return Err(self.error("non-ASCII bytes in classes are unsupported")),
+      };
+      let hi = if self.peek() == Some(b'-') && self.pattern.get(self.pos + 1) != Some(&b']') {
+        self.pos += 1;
+        match self.next()? {
+          b'\\' => {
+            let byte = self.next()?;
+            self.escaped_byte(byte)?
+          },
+          byte if byte.is_ascii() => byte,
+          _ => return Err(self.error("non-ASCII bytes in classes are unsupported")),
+        }
+      } else {
+        lo
+      };
+      if hi < lo {
+        return Err(self.error("invalid range"));
+      }
+      class[usize::from(lo)..=usize::from(hi)].iter_mut().for_each(|c| *c = true);
+    }
+    self.pos += 1;
+    if negated {
+      class.iter_mut().for_each(|c| *c = !*c);
+    }
+    Ok(Node::Class(Box::new(class)))
+  }
+}
+
+/// The class of `byte` alone
+fn singleton(byte: u8) -> ByteSet {
+  let mut class = [false; 256];
+  class[usize::from(byte)] = true;
+  class
+}
+
+/// `node{min,max}`, `max` being `None` if unbounded, as a concatenation
+fn repetition(node: Node, min: usize, max: Option<usize>) -> Node {
+  let mut nodes = vec![node.clone(); min];
+  match max {
+    None => nodes.push(Node::Star(Box::new(node))),
+    Some(max) => {
+      // the optional copies are nested, as in `(a(a)?)?`, so that they are
+      // not ambiguous
+      let optional = (min..max).fold(Node::Empty, |inner, _| {
+        Node::Optional(Box::new(Node::Concat(vec![node.clone(), inner])))
+      });
+      nodes.push(optional);
+    },
+  }
+  Node::Concat(nodes)
+}
+
+/// The positions of a pattern, as classes of bytes revealed or not, with the
+/// positions following each of them
+#[derive(Default)]
+struct Positions {
+  classes: Vec<(ByteSet, bool)>,
+  follow:  Vec<BTreeSet<usize>>,
+}
+
+/// Whether a node matches the empty string, with its first and last
+/// positions
+type Summary = (bool, BTreeSet<usize>, BTreeSet<usize>);
+
+impl Positions {
+  /// Adds the positions of `node`, revealed if `reveal`, and returns its
+  /// summary
+  fn add(&mut self, node: &Node, reveal: bool) -> Summary {
+    match node {
+      Node::Empty => (true, BTreeSet::new(), BTreeSet::new()),
+      Node::Class(class) => {
+        let p = self.classes.len();
+        self.classes.push((**class, reveal));
+        self.follow.push(BTreeSet::new());
+        (false, BTreeSet::from([p]), BTreeSet::from([p]))
+      },
+      Node::Concat(nodes) =>
+        nodes.iter().fold((true, BTreeSet::new(), BTreeSet::new()), |(n1, f1, l1), node| {
+          let (n2, f2, l2) = self.add(node, reveal);
+          for p in &l1 {
+            self.follow[*p].extend(&f2);
+          }
+          let first = if n1 { &f1 | &f2 } else { f1 };
+          let last = if n2 { &l1 | &l2 } else { l2 };
+          (n1 && n2, first, last)
+        }),
+      Node::Alt(nodes) =>
+        nodes.iter().fold((false, BTreeSet::new(), BTreeSet::new()), |(n1, f1, l1), node| {
+          let (n2, f2, l2) = self.add(node, reveal);
+          (n1 || n2, &f1 | &f2, &l1 | &l2)
+        }),
+      Node::Star(node) => {
+        let (_, first, last) = self.repeat(node, reveal);
+        (true, first, last)
+      },
+      Node::Plus(node) => self.repeat(node, reveal),
+      Node::Optional(node) => {
+        let (_, first, last) = self.add(node, reveal);
+        (true, first, last)
+      },
+      Node::Group { reveal: group, node } => self.add(node, reveal || *group),
+    }
+  }
+
+  /// Adds the positions of `node`, whose last positions are followed by its
+  /// first ones
+  fn repeat(&mut self, node: &Node, reveal: bool) -> Summary {
+    let (nullable, first, last) = self.add(node, reveal);
+    for p in &last {
+      self.follow[*p].extend(&first);
+    }
+    (nullable, first, last)
+  }
+}
+
+/// The dead state of an automaton, which rejects every input
+pub(crate) const DEAD: usize = 0;
+
+/// The start state of an automaton
+pub(crate) const START: usize = 1;
+
+/// The next states of each state of an automaton, on each byte read not
+/// revealed, then revealed
+pub(crate) type Transitions = Vec<[[u8; 256]; 2]>;
+
+/// Compiles `pattern` into its transitions and whether each of its states is
+/// accepting, with at most `max_states` states
+pub(crate) fn compile(
+  pattern: &str,
+  max_states: usize,
+) -> Result<(Transitions, Vec<bool>), NovaError> {
+  let mut parser = Parser { pattern: pattern.as_bytes(), pos: 0 };
+  let node = parser.alt()?;
+  if parser.pos != pattern.len() {
+    return Err(parser.error("unmatched parenthesis"));
+  }
+  let mut positions = Positions::default();
+  let (nullable, first, last) = positions.add(&node, false);
+
+  // the states are sets of positions, the start state being marked by the
+  // position past the last one
+  let start = positions.classes.len();
+  let mut sets = vec![BTreeSet::new(), BTreeSet::from([start])];
+  let mut ids = BTreeMap::from([(sets[DEAD].clone(), DEAD), (sets[START].clone(), START)]);
+  let mut transitions = Transitions::new();
+  while transitions.len() < sets.len() {
+    let next = sets[transitions.len()]
+      .iter()
+      .flat_map(|p| if *p == start { &first } else { &positions.follow[*p] })
+      .copied()
+      .collect::<BTreeSet<_>>();
+    let mut row = [[DEAD as u8; 256]; 2];
+    for (reveal, row) in row.iter_mut().enumerate() {
+      for (byte, target) in row.iter_mut().enumerate() {
+        let set = next
+          .iter()
+          .copied()
+          .filter(|p| {
+            let (class, revealed) = &positions.classes[*p];
+            class[byte] && *revealed == (reveal == 1)
+          })
+          .collect::<BTreeSet<_>>();
+        let id = match ids.get(&set).copied() {
+          Some(id) => id,
+          None if sets.len() < max_states => {
+            ids.insert(set.clone(), sets.len());
+            sets.push(set);
+            sets.len() - 1
+          },
+          None => {
+            return Err(NovaError::InvalidRegex(format!("more than {max_states} states")));
+          },
+        };
+        *target = u8::try_from(id)
+          .map_err(|_| NovaError::InvalidRegex(format!("more than {max_states} states")))?;
+      }
+    }
+    transitions.push(row);
+  }
+
+  let accepting = sets
+    .iter()
+    .map(|set| set.iter().any(|p| last.contains(p) || (*p == start && nullable)))
+    .collect();
+  Ok((transitions, accepting))
+}
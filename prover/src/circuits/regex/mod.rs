@@ -0,0 +1,468 @@
+//! A step circuit matching an input against a regular expression, compiled
+//! into a deterministic automaton whose transitions are looked up, so that
+//! the parsers of the bytes a proof extracts are written as patterns.
+//!
+//! A pattern is compiled by [`Dfa::compile`]; the patterns are those of the
+//! usual syntax restricted to bytes: literals, `.`, classes such as `[^"]` or
+//! `[a-z0-9_]`, the escapes `\d`, `\w`, `\s`, their negations, `\n`, `\r`,
+//! `\t`, `\xHH` and `\` before a punctuation, the groups `(?:...)`, the
+//! alternatives `|` and the repetitions `*`, `+`, `?`, `{m}`, `{m,}` and
+//! `{m,n}`. A pattern matches whole inputs, so that there are no anchors,
+//! and a search for a pattern `p` is the match of `.*p.*`.
+//!
+//! The groups `(...)` are revealed: the bytes they read are output, the
+//! others being masked. Each byte is read by the automaton along with
+//! whether it is revealed, which the prover chooses, so that the automaton
+//! is deterministic even where the groups of the pattern are not, e.g. in
+//! `.*"(\w+)".*`; in that case the prover chooses among the matches of the
+//! group, and a pattern delimiting its groups, such as `\{"sub":"(\w+)".*`,
+//! has a single one.
+//!
+//! A [`RegexStep`] reads a chunk of the input per step, with a lookup per
+//! byte into the transitions of the automaton, run through a
+//! [`LookupStep`](crate::lookup::LookupStep). Its `z` carries, at the
+//! indices below, the state of the automaton, the number of bytes read,
+//! and the commitments to the input and to its masked bytes, chained over
+//! the chunks by [`commitment`]; the verifier checks the outputs with
+//! [`verify_io`], after the lookups with [`LookupClaim::verify`] over the
+//! automaton.
+//!
+//! [`LookupClaim::verify`]: crate::lookup::LookupClaim::verify
+use std::sync::Arc;
+
+use bellpepper_core::{
+  boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use super::x509::pack_nums;
+use crate::{
+  errors::NovaError,
+  gadgets::pack_bytes,
+  lookup::{DecomposableTable, LookupCircuit, Lookups},
+  provider::poseidon::PoseidonConstantsCircuit,
+};
+
+mod compile;
+
+use compile::{compile, Transitions, DEAD, START};
+
+/// The number of elements of the `z` of a [`RegexStep`]
+pub const ARITY: usize = 4;
+
+/// The index of the state of the automaton in `z`
+pub const STATE_INDEX: usize = 0;
+
+/// The index of the number of bytes read in `z`
+pub const LEN_INDEX: usize = 1;
+
+/// The index of the commitment to the input in `z`
+pub const INPUT_INDEX: usize = 2;
+
+/// The index of the commitment to the masked input in `z`
+pub const REVEAL_INDEX: usize = 3;
+
+/// The maximal number of states of an automaton, so that a state and
+/// whether a byte is revealed fit in the byte of an operand of its lookups
+pub const MAX_STATES: usize = 128;
+
+/// The tag of the table of an automaton, unless set by [`Dfa::with_id`]
+pub const DFA_TABLE_ID: u8 = 3;
+
+/// The deterministic automaton of a pattern, over the bytes read revealed
+/// or not, as the decomposable table that maps `2 state + revealed` and a
+/// byte to the next state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dfa {
+  id:          u8,
+  transitions: Transitions,
+  accepting:   Vec<bool>,
+}
+
+impl Dfa {
+  /// Compiles `pattern`, of at most [`MAX_STATES`] states
+  pub fn compile(pattern: &str) -> Result<Self, NovaError> {
+    let (transitions, accepting) = compile(pattern, MAX_STATES)?;
+    Ok(Self { id: DFA_TABLE_ID, transitions, accepting })
+  }
+
+  /// Sets the tag of the table of the automaton, to look up into several
+  /// automata in a computation
+  pub fn with_id(mut self, id: u8) -> Self {
+    self.id = id;
+    self
+  }
+
+  /// The number of states of the automaton
+  pub fn num_states(&self) -> usize { self.transitions.len() }
+
+  /// The state the automaton starts in
+  pub fn start(&self) -> usize { START }
+
+  /// Whether `state` is accepting
+  pub fn is_accepting(&self, state: usize) -> bool {
+    self.accepting.get(state).copied().unwrap_or(false)
+  }
+
+  /// The state after reading `byte` in `state`, revealed if `revealed`
+  pub fn next(&self, state: usize, byte: u8, revealed: bool) -> usize {
+    self
+      .transitions
+      .get(state)
+      .map_or(DEAD, |row| usize::from(row[usize::from(revealed)][usize::from(byte)]))
+  }
+
+  /// The bytes of `input` a match of the automaton reveals, or `None` if
+  /// it does not match
+  pub fn parse(&self, input: &[u8]) -> Option<Vec<bool>> {
+    // the states from which each suffix of the input is matched
+    let mut matching = vec![self.accepting.clone()];
+    for byte in input.iter().rev() {
+      // `matching` starts with the accepting states
+      #[allow(clippy::expect_used)]
+      let next = matching.last().expect("there is a set per suffix");
+      let states = (0..self.num_states())
+        .map(|state| [false, true].iter().any(|r| next[self.next(state, *byte, *r)]))
+        .collect();
+      matching.push(states);
+    }
+    matching.reverse();
+    if !matching[0][START] {
+      return None;
+    }
+
+    let mut state = START;
+    let mut revealed = Vec::with_capacity(input.len());
+    for (byte, next) in input.iter().zip(&matching[1..]) {
+      let r = !next[self.next(state, *byte, false)];
+      state = self.next(state, *byte, r);
+      revealed.push(r);
+    }
+    Some(revealed)
+  }
+
+  /// Whether the automaton matches `input`
+  pub fn is_match(&self, input: &[u8]) -> bool { self.parse(input).is_some() }
+
+  /// The masked bytes of `input`, those a match does not reveal being zero,
+  /// or `None` if the automaton does not match it
+  pub fn reveal(&self, input: &[u8]) -> Option<Vec<u8>> {
+    let revealed = self.parse(input)?;
+    Some(input.iter().zip(revealed).map(|(byte, r)| if r { *byte } else { 0 }).collect())
+  }
+}
+
+impl DecomposableTable for Dfa {
+  fn id(&self) -> u8 { self.id }
+
+  fn subtable(&self, x: u64, y: u64) -> u64 {
+    let byte = u8::try_from(y).unwrap_or(0);
+    self.next((x >> 1) as usize, byte, x & 1 == 1) as u64
+  }
+}
+
+/// The commitment to `bytes`, read in chunks of `chunk_len` bytes, the last
+/// one padded with zeros: the hash chain of their packings
+pub fn commitment<F: PrimeField>(bytes: &[u8], chunk_len: usize) -> F {
+  let constants = PoseidonConstantsCircuit::<F>::default();
+  let chunks = bytes.len().div_ceil(chunk_len).max(1);
+  (0..chunks).fold(F::ZERO, |hash, i| {
+    let mut chunk = bytes[(i * chunk_len).min(bytes.len())..].to_vec();
+    chunk.resize(chunk_len, 0);
+    constants.hash(&[vec![hash], pack_bytes(&chunk)].concat())
+  })
+}
+
+/// The initial `z` of a computation of [`RegexStep`]s, to which the lookup
+/// accumulators are appended
+pub fn initial_io<F: PrimeField>() -> Vec<F> {
+  vec![F::from(START as u64), F::ZERO, F::ZERO, F::ZERO]
+}
+
+/// Checks that the final `z` of a computation of [`RegexStep`]s over
+/// `chunk_len` bytes per step, followed by the lookup accumulators, is of a
+/// match of `dfa` whose masked input is `masked`, and returns the
+/// commitment to the input
+pub fn verify_io<F: PrimeField>(
+  dfa: &Dfa,
+  z: &[F],
+  masked: &[u8],
+  chunk_len: usize,
+) -> Result<F, NovaError> {
+  if z.len() < ARITY {
+    return Err(NovaError::InvalidStepCircuitIO);
+  }
+  let accepting = (0..dfa.num_states())
+    .any(|state| dfa.is_accepting(state) && z[STATE_INDEX] == F::from(state as u64));
+  if !accepting
+    || z[LEN_INDEX] != F::from(masked.len() as u64)
+    || z[REVEAL_INDEX] != commitment(masked, chunk_len)
+  {
+    return Err(NovaError::InvalidStepCircuitIO);
+  }
+  Ok(z[INPUT_INDEX])
+}
+
+/// Chains the packing of the numbers of the bytes `bytes` into the hash
+/// `hash`, as [`commitment`] does
+fn chain<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  hash: &AllocatedNum<F>,
+  bytes: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let packed = pack_nums(cs.namespace(|| "pack"), bytes)?;
+  let elements = [vec![hash.clone()], packed].concat();
+  PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &elements)
+}
+
+/// The step reading a chunk of an input into an automaton
+#[derive(Clone, Debug)]
+pub struct RegexStep {
+  dfa:       Arc<Dfa>,
+  chunk_len: usize,
+  bytes:     Vec<u8>,
+  revealed:  Vec<bool>,
+}
+
+impl RegexStep {
+  /// The steps reading `input` into `dfa`, `chunk_len` bytes per step, along
+  /// a match of the automaton; the shape of the steps depends on the
+  /// automaton and on `chunk_len` only
+  pub fn steps(dfa: &Arc<Dfa>, input: &[u8], chunk_len: usize) -> Result<Vec<Self>, NovaError> {
+    if chunk_len == 0 {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let revealed =
+      dfa.parse(input).ok_or_else(|| NovaError::InvalidRegex("the input does not match".into()))?;
+    let chunks = input.len().div_ceil(chunk_len).max(1);
+    Ok(
+      (0..chunks)
+        .map(|i| {
+          let range = i * chunk_len..((i + 1) * chunk_len).min(input.len());
+          Self {
+            dfa: dfa.clone(),
+            chunk_len,
+            bytes: input[range.clone()].to_vec(),
+            revealed: revealed[range].to_vec(),
+          }
+        })
+        .collect(),
+    )
+  }
+}
+
+impl<F: PrimeField> LookupCircuit<F> for RegexStep {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+    lookups: &mut Lookups<F>,
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    if z.len() != ARITY || self.bytes.len() > self.chunk_len {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut state = z[STATE_INDEX].clone();
+    let mut read = LinearCombination::zero() + z[LEN_INDEX].get_variable();
+    let mut len = z[LEN_INDEX].get_value();
+    let (mut bytes, mut masked) = (Vec::new(), Vec::new());
+    let mut previous_pad: Option<AllocatedBit> = None;
+    for i in 0..self.chunk_len {
+      let mut cs = cs.namespace(|| format!("byte {i}"));
+      let value = self.bytes.get(i).copied().unwrap_or(0);
+      let byte = AllocatedNum::alloc(cs.namespace(|| "byte"), || Ok(F::from(u64::from(value))))?;
+      let pad = AllocatedBit::alloc(cs.namespace(|| "pad"), Some(i >= self.bytes.len()))?;
+      let revealed = AllocatedBit::alloc(
+        cs.namespace(|| "revealed"),
+        Some(self.revealed.get(i) == Some(&true)),
+      )?;
+
+      // the padding is zeros, not revealed, at the end of the chunk
+      cs.enforce(
+        || "padding is zero",
+        |lc| lc + pad.get_variable(),
+        |lc| lc + byte.get_variable(),
+        |lc| lc,
+      );
+      cs.enforce(
+        || "padding is not revealed",
+        |lc| lc + pad.get_variable(),
+        |lc| lc + revealed.get_variable(),
+        |lc| lc,
+      );
+      if let Some(previous_pad) = &previous_pad {
+        cs.enforce(
+          || "padding is at the end",
+          |lc| lc + previous_pad.get_variable(),
+          |lc| lc + CS::one() - pad.get_variable(),
+          |lc| lc,
+        );
+      }
+
+      // the states are outputs of the lookups, or the initial state, so that
+      // they are below `MAX_STATES` and `x` encodes them with `revealed`
+      let x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+        let state = state.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+        Ok(state.double() + F::from(u64::from(revealed.get_value() == Some(true))))
+      })?;
+      cs.enforce(
+        || "x",
+        |lc| lc + (F::from(2), state.get_variable()) + revealed.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + x.get_variable(),
+      );
+      let next = lookups.lookup(cs.namespace(|| "transition"), self.dfa.as_ref(), &x, &byte, 8)?;
+
+      // the padding does not move the automaton
+      let selected = AllocatedNum::alloc(cs.namespace(|| "next"), || {
+        if pad.get_value().ok_or(SynthesisError::AssignmentMissing)? {
+          state.get_value().ok_or(SynthesisError::AssignmentMissing)
+        } else {
+          next.get_value().ok_or(SynthesisError::AssignmentMissing)
+        }
+      })?;
+      cs.enforce(
+        || "select next",
+        |lc| lc + pad.get_variable(),
+        |lc| lc + state.get_variable() - next.get_variable(),
+        |lc| lc + selected.get_variable() - next.get_variable(),
+      );
+
+      let mask = AllocatedNum::alloc(cs.namespace(|| "masked"), || {
+        Ok(if revealed.get_value() == Some(true) { F::from(u64::from(value)) } else { F::ZERO })
+      })?;
+      cs.enforce(
+        || "mask",
+        |lc| lc + revealed.get_variable(),
+        |lc| lc + byte.get_variable(),
+        |lc| lc + mask.get_variable(),
+      );
+
+      read = read + CS::one() - pad.get_variable();
+      len = len.map(|len| if i < self.bytes.len() { len + F::ONE } else { len });
+      state = selected;
+      bytes.push(byte);
+      masked.push(mask);
+      previous_pad = Some(pad);
+    }
+
+    let len =
+      AllocatedNum::alloc(cs.namespace(|| "len"), || len.ok_or(SynthesisError::AssignmentMissing))?;
+    cs.enforce(|| "len", |_| read, |lc| lc + CS::one(), |lc| lc + len.get_variable());
+
+    let input = chain(cs.namespace(|| "commit input"), &z[INPUT_INDEX], &bytes)?;
+    let reveal = chain(cs.namespace(|| "commit masked"), &z[REVEAL_INDEX], &masked)?;
+    Ok((pc.cloned(), vec![state, len, input, reveal]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::{
+    lookup::{LookupClaim, LookupStep, LookupTrace},
+    provider::Bn256EngineIPA,
+    supernova::StepCircuit,
+    traits::Engine,
+  };
+
+  type F = <Bn256EngineIPA as Engine>::Scalar;
+
+  #[test]
+  fn test_compile() {
+    let dfa = Dfa::compile(r"[a-z]+@(?:[a-z0-9]+\.)+(?:com|org)").unwrap();
+    assert!(dfa.is_match(b"alice@mail.example.com"));
+    assert!(dfa.is_match(b"bob@web3.org"));
+    assert!(!dfa.is_match(b"alice@example.net"));
+    assert!(!dfa.is_match(b"Alice@example.com"));
+    assert!(!dfa.is_match(b"@example.com"));
+
+    let dfa = Dfa::compile(r"\d{4}-\d{2}(?:-\d{2})?|[^\s]{0,3}").unwrap();
+    assert!(dfa.is_match(b"2026-10"));
+    assert!(dfa.is_match(b"2026-10-14"));
+    assert!(dfa.is_match(b""));
+    assert!(dfa.is_match(b"a-"));
+    assert!(!dfa.is_match(b"2026-1"));
+    assert!(!dfa.is_match(b"a b"));
+
+    for pattern in ["(a", "a)", "a{3,1}", "^a", "*", "[b-a]", r"\q", "(?=a)", "a{256}"] {
+      assert!(matches!(Dfa::compile(pattern), Err(NovaError::InvalidRegex(_))), "{pattern}");
+    }
+    assert!(matches!(Dfa::compile("a{200}"), Err(NovaError::InvalidRegex(_))));
+  }
+
+  #[test]
+  fn test_reveal() {
+    let dfa = Dfa::compile(r#"\{(?:"\w+":"[^"]*",)*"sub":"([^"]*)".*"#).unwrap();
+    let input = br#"{"iss":"example.com","sub":"alice","aud":"bob"}"#;
+    let masked = dfa.reveal(input).unwrap();
+    let expected =
+      input.iter().enumerate().map(|(i, b)| if (28..33).contains(&i) { *b } else { 0 });
+    assert_eq!(masked, expected.collect::<Vec<_>>());
+    assert_eq!(dfa.reveal(br#"{"iss":"example.com"}"#), None);
+  }
+
+  /// Runs `steps` through the lookup trace then the circuits,
+  /// returning the initial and final `z`, and the claim of the lookups
+  fn run(steps: &[RegexStep]) -> (Vec<F>, Vec<F>, LookupClaim<F>) {
+    let mut trace = LookupTrace::new();
+    let mut z = initial_io::<F>();
+    for step in steps {
+      z = trace.step(step, None, &z).unwrap().1;
+    }
+    let claim = trace.finish();
+
+    let z0 = [initial_io(), claim.initial_accumulators().to_vec()].concat();
+    let mut z = z0.clone();
+    for step in steps {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let z_alloc = z
+        .iter()
+        .enumerate()
+        .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+        .collect::<Vec<_>>();
+      let (_, z_next) = LookupStep::new(step.clone()).synthesize(&mut cs, None, &z_alloc).unwrap();
+      assert!(cs.is_satisfied());
+      z = z_next.iter().map(|z_i| z_i.get_value().unwrap()).collect();
+    }
+    (z0, z, claim)
+  }
+
+  #[test]
+  fn test_regex_step() {
+    const CHUNK_LEN: usize = 16;
+    let dfa = Arc::new(Dfa::compile(r#"\{(?:"\w+":"[^"]*",)*"sub":"([^"]*)".*"#).unwrap());
+    let input = br#"{"iss":"example.com","sub":"alice","aud":"bob"}"#;
+    let steps = RegexStep::steps(&dfa, input, CHUNK_LEN).unwrap();
+    assert_eq!(steps.len(), 3);
+
+    let tables: [&dyn DecomposableTable; 1] = [dfa.as_ref()];
+    let (z0, z, claim) = run(&steps);
+    assert_eq!(claim.verify(&tables, &z0, &z), Ok(()));
+    let masked = dfa.reveal(input).unwrap();
+    assert_eq!(verify_io(&dfa, &z, &masked, CHUNK_LEN), Ok(commitment(input, CHUNK_LEN)));
+
+    // the masked input must be the one read
+    let mut wrong_masked = masked.clone();
+    wrong_masked[28] = b'b';
+    assert_eq!(verify_io(&dfa, &z, &wrong_masked, CHUNK_LEN), Err(NovaError::InvalidStepCircuitIO));
+
+    // revealing bytes outside the group leaves the automaton rejecting
+    let mut wrong_steps = steps.clone();
+    wrong_steps[0].revealed[1] = true;
+    let (z0, z, claim) = run(&wrong_steps);
+    assert_eq!(claim.verify(&tables, &z0, &z), Ok(()));
+    let mut wrong_masked = masked.clone();
+    wrong_masked[1] = input[1];
+    assert_eq!(verify_io(&dfa, &z, &wrong_masked, CHUNK_LEN), Err(NovaError::InvalidStepCircuitIO));
+
+    // and the input must match
+    assert!(RegexStep::steps(&dfa, br#"{"iss":"example.com"}"#, CHUNK_LEN).is_err());
+  }
+}
@@ -0,0 +1,131 @@
+//! The ChaCha20 stream cipher (RFC 8439), decrypting the records of
+//! `TLS_CHACHA20_POLY1305_SHA256`.
+use bellpepper::gadgets::{multieq::MultiEq, uint32::UInt32};
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::gadgets::xor_bytes;
+
+/// The length of the blocks of the keystream
+pub const BLOCK_LEN: usize = 64;
+
+/// The length of ChaCha20 nonces
+pub const NONCE_LEN: usize = 12;
+
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// The little-endian words of the bytes `bits`
+fn words(bits: &[Boolean]) -> Vec<UInt32> {
+  bits
+    .chunks(32)
+    .map(|word| {
+      // bytes are most significant bit first, words least significant bit first
+      let le = word.chunks(8).flat_map(|byte| byte.iter().rev().cloned()).collect::<Vec<_>>();
+      UInt32::from_bits(&le)
+    })
+    .collect()
+}
+
+/// The bytes of the little-endian words `words`
+fn bytes(words: &[UInt32]) -> Vec<Boolean> {
+  words
+    .iter()
+    .flat_map(|word| {
+      let le = word.clone().into_bits();
+      le.chunks(8).flat_map(|byte| byte.iter().rev().cloned()).collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// `x <<< n`
+fn rotl(x: &UInt32, n: usize) -> UInt32 { x.rotr(32 - n) }
+
+fn quarter_round<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  state: &mut [UInt32],
+  [a, b, c, d]: [usize; 4],
+) -> Result<(), SynthesisError> {
+  let steps = [(a, b, d, 16), (c, d, b, 12), (a, b, d, 8), (c, d, b, 7)];
+  for (i, (x, y, z, n)) in steps.into_iter().enumerate() {
+    // x += y; z ^= x; z <<<= n
+    let sum = {
+      let mut cs = MultiEq::new(cs.namespace(|| format!("add {i}")));
+      UInt32::addmany(cs.namespace(|| "addmany"), &[state[x].clone(), state[y].clone()])?
+    };
+    let xored = state[z].xor(cs.namespace(|| format!("xor {i}")), &sum)?;
+    state[x] = sum;
+    state[z] = rotl(&xored, n);
+  }
+  Ok(())
+}
+
+/// The block `counter` of the keystream of `key` and `nonce`
+pub fn block<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[Boolean],
+  nonce: &[Boolean],
+  counter: u32,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if key.len() != 256 || nonce.len() != 8 * NONCE_LEN {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let initial = CONSTANTS
+    .iter()
+    .map(|c| UInt32::constant(*c))
+    .chain(words(key))
+    .chain(std::iter::once(UInt32::constant(counter)))
+    .chain(words(nonce))
+    .collect::<Vec<_>>();
+
+  let mut state = initial.clone();
+  for round in 0..10 {
+    let mut cs = cs.namespace(|| format!("double round {round}"));
+    let quarters = [
+      [0, 4, 8, 12],
+      [1, 5, 9, 13],
+      [2, 6, 10, 14],
+      [3, 7, 11, 15],
+      [0, 5, 10, 15],
+      [1, 6, 11, 12],
+      [2, 7, 8, 13],
+      [3, 4, 9, 14],
+    ];
+    for (i, quarter) in quarters.into_iter().enumerate() {
+      quarter_round(cs.namespace(|| format!("quarter {i}")), &mut state, quarter)?;
+    }
+  }
+
+  let mut cs = MultiEq::new(cs.namespace(|| "add initial state"));
+  let state = state
+    .iter()
+    .zip(&initial)
+    .enumerate()
+    .map(|(i, (x, y))| {
+      UInt32::addmany(cs.namespace(|| format!("word {i}")), &[x.clone(), y.clone()])
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(bytes(&state))
+}
+
+/// Decrypts (or encrypts) `data` under `key` and `nonce`, with the keystream
+/// from the block `counter` on, which fails if the counter of a block of
+/// `data` overflows, as it would reuse the keystream
+pub fn apply_keystream<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[Boolean],
+  nonce: &[Boolean],
+  counter: u32,
+  data: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let mut out = Vec::with_capacity(data.len());
+  for (i, chunk) in data.chunks(8 * BLOCK_LEN).enumerate() {
+    let mut cs = cs.namespace(|| format!("block {i}"));
+    let counter = u32::try_from(i)
+      .ok()
+      .and_then(|i| counter.checked_add(i))
+      .ok_or(SynthesisError::Unsatisfiable)?;
+    let keystream = block(cs.namespace(|| "keystream"), key, nonce, counter)?;
+    out.extend(xor_bytes(cs.namespace(|| "xor"), chunk, &keystream[..chunk.len()])?);
+  }
+  Ok(out)
+}
@@ -0,0 +1,88 @@
+//! The TLS 1.3 key schedule over SHA-256 (RFC 8446, section 7.1), from the
+//! handshake secret to the traffic keys of the application data.
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::gadgets::{
+  constant_bytes,
+  kdf::{derive_secret, hkdf_expand_label, hkdf_extract, hmac_sha256, HASH_LEN},
+};
+
+/// The length of the keys of `TLS_CHACHA20_POLY1305_SHA256`
+pub const KEY_LEN: usize = 32;
+
+/// The length of the IVs of `TLS_CHACHA20_POLY1305_SHA256`
+pub const IV_LEN: usize = 12;
+
+/// SHA-256 of the empty string, the context of the `"derived"` secrets
+pub const EMPTY_HASH: [u8; HASH_LEN] = [
+  0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+  0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+];
+
+/// The label of the secrets salting the next stage of the schedule
+pub const DERIVED: &[u8] = b"derived";
+/// The label of the client handshake traffic secret
+pub const CLIENT_HANDSHAKE_TRAFFIC: &[u8] = b"c hs traffic";
+/// The label of the server handshake traffic secret
+pub const SERVER_HANDSHAKE_TRAFFIC: &[u8] = b"s hs traffic";
+/// The label of the keys of the `Finished` messages
+pub const FINISHED: &[u8] = b"finished";
+/// The label of the client application traffic secret
+pub const CLIENT_APPLICATION_TRAFFIC: &[u8] = b"c ap traffic";
+/// The label of the server application traffic secret
+pub const SERVER_APPLICATION_TRAFFIC: &[u8] = b"s ap traffic";
+
+/// The master secret following `handshake_secret`
+pub fn master_secret<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  handshake_secret: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let derived = derive_secret(
+    cs.namespace(|| "derived"),
+    handshake_secret,
+    DERIVED,
+    &constant_bytes(&EMPTY_HASH),
+  )?;
+  hkdf_extract(cs.namespace(|| "extract"), &derived, &constant_bytes(&[0; HASH_LEN]))
+}
+
+/// The `verify_data` of the server `Finished` following `handshake_secret`,
+/// from the hash of the messages up to the `ServerHello`, `hello_hash`, and
+/// up to the `CertificateVerify`, `transcript_hash`
+pub fn server_finished<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  handshake_secret: &[Boolean],
+  hello_hash: &[Boolean],
+  transcript_hash: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let traffic_secret = derive_secret(
+    cs.namespace(|| "server handshake traffic secret"),
+    handshake_secret,
+    SERVER_HANDSHAKE_TRAFFIC,
+    hello_hash,
+  )?;
+  let finished_key =
+    hkdf_expand_label(cs.namespace(|| "finished key"), &traffic_secret, FINISHED, &[], HASH_LEN)?;
+  hmac_sha256(cs.namespace(|| "verify data"), &finished_key, transcript_hash)
+}
+
+/// The key and the IV of a traffic secret
+#[derive(Clone, Debug)]
+pub struct TrafficKeys {
+  /// the key, of [`KEY_LEN`] bytes
+  pub key: Vec<Boolean>,
+  /// the IV, of [`IV_LEN`] bytes
+  pub iv:  Vec<Boolean>,
+}
+
+/// The key and the IV of `traffic_secret`
+pub fn traffic_keys<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  traffic_secret: &[Boolean],
+) -> Result<TrafficKeys, SynthesisError> {
+  Ok(TrafficKeys {
+    key: hkdf_expand_label(cs.namespace(|| "key"), traffic_secret, b"key", &[], KEY_LEN)?,
+    iv:  hkdf_expand_label(cs.namespace(|| "iv"), traffic_secret, b"iv", &[], IV_LEN)?,
+  })
+}
@@ -0,0 +1,525 @@
+//! Circuits proving the contents of a TLS 1.3 session, for web proofs.
+//!
+//! The session is proven in foldable steps of a SuperNova program, for the
+//! `TLS_CHACHA20_POLY1305_SHA256` cipher suite:
+//!
+//! - a [`HandshakeStep`] hashes the handshake transcript, from the `ClientHello` to the server
+//!   `Finished`, checks the server `Finished` under the handshake secret, and runs the key
+//!   schedule from the handshake secret to the application traffic keys of a [`Direction`];
+//! - a [`RecordStep`] decrypts the next record of that direction under these keys, with the nonce
+//!   of its sequence number.
+//!
+//! The steps share the `z` of [`ARITY`] elements laid out by the `*_INDEX`
+//! constants: the transcript hash, packed by [`transcript_io`], the
+//! commitment to the traffic keys (see [`keys_commitment`]), the sequence
+//! number of the next record, and the accumulators (see [`accumulate`]) of
+//! the ciphertexts and of the plaintexts of the records decrypted so far.
+//! The verifier checks the transcript hash and the ciphertext accumulator
+//! against the session it observed, e.g. through a notary, and the proof
+//! then binds the plaintext accumulator to the records the server sent.
+//!
+//! The handshake secret is a witness, bound to the session by the
+//! `verify_data` of the server `Finished`: the HMAC of the transcript under a
+//! key derived from the handshake secret, which only the secret of the session
+//! reproduces. The key exchange, the certificate of the server and the
+//! Poly1305 tags of the records are not verified in the circuit but by the TLS
+//! client, and a record is the encrypted payload of the record without its
+//! tag, the content type and padding of its inner plaintext included.
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, SynthesisError,
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+use crate::{
+  gadgets::{
+    alloc_bytes, alloc_one, alloc_packed, alloc_zero, constant_bytes,
+    kdf::{derive_secret, HASH_LEN},
+    le_bits_to_num, pack_bytes, xor_bytes,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+  supernova::StepCircuit,
+};
+
+pub mod chacha20;
+pub mod key_schedule;
+
+use key_schedule::{IV_LEN, KEY_LEN};
+
+/// The number of elements of the `z` of the steps
+pub const ARITY: usize = 6;
+/// The index in `z` of the transcript hash, in two elements
+pub const TRANSCRIPT_INDEX: usize = 0;
+/// The index in `z` of the commitment to the traffic keys
+pub const KEYS_INDEX: usize = 2;
+/// The index in `z` of the sequence number of the next record
+pub const SEQ_INDEX: usize = 3;
+/// The index in `z` of the accumulator of the ciphertexts
+pub const CIPHERTEXT_INDEX: usize = 4;
+/// The index in `z` of the accumulator of the plaintexts
+pub const PLAINTEXT_INDEX: usize = 5;
+
+/// The handshake header of the server `Finished`: its type and the length of
+/// its `verify_data`
+pub const FINISHED_HEADER: [u8; 4] = [0x14, 0x00, 0x00, HASH_LEN as u8];
+
+/// The direction of the traffic a session is proven for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+  /// The records sent by the client
+  Client,
+  /// The records sent by the server, e.g. the HTTP responses
+  Server,
+}
+
+impl Direction {
+  /// The label of the application traffic secret of the direction
+  pub fn application_label(self) -> &'static [u8] {
+    match self {
+      Self::Client => key_schedule::CLIENT_APPLICATION_TRAFFIC,
+      Self::Server => key_schedule::SERVER_APPLICATION_TRAFFIC,
+    }
+  }
+}
+
+/// The two elements of `z` holding the transcript hash `hash`
+pub fn transcript_io<F: PrimeField>(hash: &[u8; HASH_LEN]) -> [F; 2] {
+  let packed = pack_bytes(hash);
+  [packed[0], packed[1]]
+}
+
+/// The commitment to the traffic keys `key` and `iv`
+pub fn keys_commitment<F: PrimeField>(key: &[u8; KEY_LEN], iv: &[u8; IV_LEN]) -> F {
+  PoseidonConstantsCircuit::<F>::default().hash(&pack_bytes(&[&key[..], &iv[..]].concat()))
+}
+
+/// The accumulator `acc` absorbing the record `bytes`
+pub fn accumulate<F: PrimeField>(acc: F, bytes: &[u8]) -> F {
+  let elements = [vec![acc], pack_bytes(bytes)].concat();
+  PoseidonConstantsCircuit::<F>::default().hash(&elements)
+}
+
+/// Absorbs the record `bits` into `acc` inside the circuit, as [`accumulate`]
+fn accumulate_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  acc: &AllocatedNum<F>,
+  bits: &[Boolean],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let packed = alloc_packed(cs.namespace(|| "pack"), bits)?;
+  let elements = [vec![acc.clone()], packed].concat();
+  PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &elements)
+}
+
+/// The commitment to the traffic keys `key` and `iv` inside the circuit, as
+/// [`keys_commitment`]
+fn keys_commitment_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[Boolean],
+  iv: &[Boolean],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let packed = alloc_packed(cs.namespace(|| "pack"), &[key, iv].concat())?;
+  PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &packed)
+}
+
+/// The step hashing the handshake transcript and deriving the traffic keys,
+/// the first step of a session
+#[derive(Clone, Debug)]
+pub struct HandshakeStep {
+  direction:        Direction,
+  handshake_secret: [u8; HASH_LEN],
+  messages:         Vec<u8>,
+  server_hello_len: usize,
+}
+
+impl HandshakeStep {
+  /// The step of the handshake whose messages, from the `ClientHello` to the
+  /// server `Finished`, are `messages`, the first `server_hello_len` bytes of
+  /// which run up to the end of the `ServerHello`, and whose handshake secret
+  /// is `handshake_secret`; the shape of the step depends on the length of
+  /// the messages and on `server_hello_len` only
+  pub fn new(
+    direction: Direction,
+    handshake_secret: [u8; HASH_LEN],
+    messages: Vec<u8>,
+    server_hello_len: usize,
+  ) -> Self {
+    Self { direction, handshake_secret, messages, server_hello_len }
+  }
+}
+
+impl<F: PrimeField + PrimeFieldBits> StepCircuit<F> for HandshakeStep {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    // the server `Finished` closes the messages, after the `ServerHello`
+    let finished_len = FINISHED_HEADER.len() + HASH_LEN;
+    let Some(finished_at) = self.messages.len().checked_sub(finished_len) else {
+      return Err(SynthesisError::Unsatisfiable);
+    };
+    if self.server_hello_len == 0 || self.server_hello_len > finished_at {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let messages =
+      alloc_bytes(cs.namespace(|| "messages"), Some(&self.messages), self.messages.len())?;
+    let secret =
+      alloc_bytes(cs.namespace(|| "handshake secret"), Some(&self.handshake_secret), HASH_LEN)?;
+
+    let sha256 = bellpepper::gadgets::sha256::sha256;
+    let hello_hash =
+      sha256(cs.namespace(|| "hello hash"), &messages[..8 * self.server_hello_len])?;
+    let verify_hash = sha256(cs.namespace(|| "verify hash"), &messages[..8 * finished_at])?;
+    let transcript_hash = sha256(cs.namespace(|| "transcript hash"), &messages)?;
+
+    let verify_data = key_schedule::server_finished(
+      cs.namespace(|| "server finished"),
+      &secret,
+      &hello_hash,
+      &verify_hash,
+    )?;
+    let expected = [constant_bytes(&FINISHED_HEADER), verify_data].concat();
+    for (i, (bit, expected)) in messages[8 * finished_at..].iter().zip(&expected).enumerate() {
+      Boolean::enforce_equal(cs.namespace(|| format!("finished bit {i}")), bit, expected)?;
+    }
+
+    let master = key_schedule::master_secret(cs.namespace(|| "master secret"), &secret)?;
+    let traffic_secret = derive_secret(
+      cs.namespace(|| "traffic secret"),
+      &master,
+      self.direction.application_label(),
+      &transcript_hash,
+    )?;
+    let keys = key_schedule::traffic_keys(cs.namespace(|| "traffic keys"), &traffic_secret)?;
+
+    let mut z_out = alloc_packed(cs.namespace(|| "transcript io"), &transcript_hash)?;
+    z_out.push(keys_commitment_circuit(cs.namespace(|| "keys commitment"), &keys.key, &keys.iv)?);
+    z_out.push(alloc_zero(cs.namespace(|| "seq")));
+    z_out.extend_from_slice(&z[CIPHERTEXT_INDEX..]);
+
+    let pc_next = pc.map(|_| alloc_one(cs.namespace(|| "next pc")));
+    Ok((pc_next, z_out))
+  }
+}
+
+/// The step decrypting the next record under the traffic keys
+#[derive(Clone, Debug)]
+pub struct RecordStep {
+  key:        [u8; KEY_LEN],
+  iv:         [u8; IV_LEN],
+  ciphertext: Vec<u8>,
+}
+
+impl RecordStep {
+  /// The step of the record `ciphertext`, without its tag, under the traffic
+  /// keys `key` and `iv`; the shape of the step depends on the length of the
+  /// record only
+  pub fn new(key: [u8; KEY_LEN], iv: [u8; IV_LEN], ciphertext: Vec<u8>) -> Self {
+    Self { key, iv, ciphertext }
+  }
+}
+
+impl<F: PrimeField + PrimeFieldBits> StepCircuit<F> for RecordStep {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize { 1 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let key = alloc_bytes(cs.namespace(|| "key"), Some(&self.key), KEY_LEN)?;
+    let iv = alloc_bytes(cs.namespace(|| "iv"), Some(&self.iv), IV_LEN)?;
+    let keys = keys_commitment_circuit(cs.namespace(|| "keys commitment"), &key, &iv)?;
+    cs.enforce(
+      || "keys match",
+      |lc| lc + keys.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + z[KEYS_INDEX].get_variable(),
+    );
+
+    // the nonce is the IV XOR the big-endian sequence number, on 64 bits
+    let seq = &z[SEQ_INDEX];
+    let seq_value = seq.get_value().map(|v| {
+      let repr = v.to_repr();
+      let mut bytes = [0u8; 8];
+      bytes.copy_from_slice(&repr.as_ref()[..8]);
+      u64::from_le_bytes(bytes)
+    });
+    let seq_bits = (0..64)
+      .map(|i| {
+        let bit = seq_value.map(|v| (v >> i) & 1 == 1);
+        AllocatedBit::alloc(cs.namespace(|| format!("seq bit {i}")), bit)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let packed_seq = le_bits_to_num(cs.namespace(|| "pack seq"), &seq_bits)?;
+    cs.enforce(
+      || "seq fits in 64 bits",
+      |lc| lc + packed_seq.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + seq.get_variable(),
+    );
+    let padded_seq = (0..8 * IV_LEN)
+      .map(|i| match i.checked_sub(32) {
+        // byte j of the sequence number holds its bits 8 * (7 - j) to 8 * (7 - j) + 7
+        Some(i) => Boolean::from(seq_bits[8 * (7 - i / 8) + 7 - i % 8].clone()),
+        None => Boolean::constant(false),
+      })
+      .collect::<Vec<_>>();
+    let nonce = xor_bytes(cs.namespace(|| "nonce"), &iv, &padded_seq)?;
+
+    let ciphertext =
+      alloc_bytes(cs.namespace(|| "ciphertext"), Some(&self.ciphertext), self.ciphertext.len())?;
+    // the block 0 of the keystream is the Poly1305 key
+    let plaintext =
+      chacha20::apply_keystream(cs.namespace(|| "decrypt"), &key, &nonce, 1, &ciphertext)?;
+
+    let seq_next = AllocatedNum::alloc(cs.namespace(|| "next seq"), || {
+      seq.get_value().map(|v| v + F::ONE).ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "increment seq",
+      |lc| lc + seq.get_variable() + CS::one(),
+      |lc| lc + CS::one(),
+      |lc| lc + seq_next.get_variable(),
+    );
+
+    let mut z_out = z[..SEQ_INDEX].to_vec();
+    z_out.push(seq_next);
+    z_out.push(accumulate_circuit(
+      cs.namespace(|| "ciphertext accumulator"),
+      &z[CIPHERTEXT_INDEX],
+      &ciphertext,
+    )?);
+    z_out.push(accumulate_circuit(
+      cs.namespace(|| "plaintext accumulator"),
+      &z[PLAINTEXT_INDEX],
+      &plaintext,
+    )?);
+
+    let pc_next = pc.map(|_| alloc_one(cs.namespace(|| "next pc")));
+    Ok((pc_next, z_out))
+  }
+}
+
+/// The steps of a session, for the circuits of a `NonUniformCircuit`
+#[derive(Clone, Debug)]
+pub enum TlsStep {
+  /// The handshake, the first step
+  Handshake(HandshakeStep),
+  /// A record, every step after the first
+  Record(RecordStep),
+}
+
+impl<F: PrimeField + PrimeFieldBits> StepCircuit<F> for TlsStep {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize {
+    match self {
+      Self::Handshake(step) => StepCircuit::<F>::circuit_index(step),
+      Self::Record(step) => StepCircuit::<F>::circuit_index(step),
+    }
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    match self {
+      Self::Handshake(step) => step.synthesize(cs, pc, z),
+      Self::Record(step) => step.synthesize(cs, pc, z),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+  use ::chacha20::{
+    cipher::{KeyIvInit, StreamCipher, StreamCipherSeek},
+    ChaCha20,
+  };
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut padded = key.to_vec();
+    padded.resize(64, 0);
+    let pad = |byte: u8| padded.iter().map(|k| k ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::digest([pad(0x36), message.to_vec()].concat());
+    Sha256::digest([pad(0x5c), inner.to_vec()].concat()).into()
+  }
+
+  fn expand_label(secret: &[u8], label: &[u8], context: &[u8], len: usize) -> Vec<u8> {
+    let full_label = [b"tls13 ", label].concat();
+    let mut info = (len as u16).to_be_bytes().to_vec();
+    info.push(full_label.len() as u8);
+    info.extend_from_slice(&full_label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    let (mut okm, mut block) = (Vec::new(), Vec::new());
+    for i in 1..=len.div_ceil(32) as u8 {
+      block = hmac(secret, &[block, info.clone(), vec![i]].concat()).to_vec();
+      okm.extend_from_slice(&block);
+    }
+    okm.truncate(len);
+    okm
+  }
+
+  fn value(bits: &[Boolean]) -> Vec<u8> { crate::gadgets::bytes_value(bits).unwrap() }
+
+  #[test]
+  fn test_key_schedule() {
+    let secret = (0..32u8).collect::<Vec<_>>();
+    let context = Sha256::digest(b"handshake").to_vec();
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let secret_bits = alloc_bytes(cs.namespace(|| "secret"), Some(&secret), 32).unwrap();
+    let context_bits = alloc_bytes(cs.namespace(|| "context"), Some(&context), 32).unwrap();
+    let derived = derive_secret(
+      cs.namespace(|| "derive"),
+      &secret_bits,
+      b"s ap traffic",
+      &context_bits,
+    )
+    .unwrap();
+    let keys = key_schedule::traffic_keys(cs.namespace(|| "keys"), &derived).unwrap();
+    assert!(cs.is_satisfied());
+
+    let expected = expand_label(&secret, b"s ap traffic", &context, 32);
+    assert_eq!(value(&derived), expected);
+    assert_eq!(value(&keys.key), expand_label(&expected, b"key", &[], KEY_LEN));
+    assert_eq!(value(&keys.iv), expand_label(&expected, b"iv", &[], IV_LEN));
+  }
+
+  #[test]
+  fn test_chacha20() {
+    let (key, nonce) = ([7u8; 32], [9u8; 12]);
+    let data = (0..100u8).collect::<Vec<_>>();
+    let mut expected = data.clone();
+    let mut cipher = ChaCha20::new(&key.into(), &nonce.into());
+    cipher.seek(64u64);
+    cipher.apply_keystream(&mut expected);
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let key = alloc_bytes(cs.namespace(|| "key"), Some(&key), 32).unwrap();
+    let nonce = alloc_bytes(cs.namespace(|| "nonce"), Some(&nonce), 12).unwrap();
+    let data = alloc_bytes(cs.namespace(|| "data"), Some(&data), data.len()).unwrap();
+    let out = super::chacha20::apply_keystream(cs.namespace(|| "apply"), &key, &nonce, 1, &data);
+    assert!(cs.is_satisfied());
+    assert_eq!(value(&out.unwrap()), expected);
+  }
+
+  #[test]
+  fn test_chacha20_counter_overflow() {
+    // two blocks, the second of which overflows a counter of `u32::MAX`
+    let apply = |counter| {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let key = alloc_bytes(cs.namespace(|| "key"), Some(&[7u8; 32]), 32)?;
+      let nonce = alloc_bytes(cs.namespace(|| "nonce"), Some(&[9u8; 12]), 12)?;
+      let data = alloc_bytes(cs.namespace(|| "data"), Some(&[0u8; 100]), 100)?;
+      super::chacha20::apply_keystream(cs.namespace(|| "apply"), &key, &nonce, counter, &data)
+    };
+    assert!(apply(u32::MAX - 1).is_ok());
+    assert!(matches!(apply(u32::MAX), Err(SynthesisError::Unsatisfiable)));
+  }
+
+  #[test]
+  fn test_handshake_step() {
+    let secret = [11u8; HASH_LEN];
+    let hello = [&[0x01, 0, 0, 4, 1, 2, 3, 4][..], &[0x02, 0, 0, 4, 5, 6, 7, 8]].concat();
+    let before_finished = [&hello[..], &[0x08, 0, 0, 2, 0, 0]].concat();
+    let server_secret =
+      expand_label(&secret, b"s hs traffic", &Sha256::digest(&hello), HASH_LEN);
+    let finished_key = expand_label(&server_secret, b"finished", &[], HASH_LEN);
+    let verify_data = hmac(&finished_key, &Sha256::digest(&before_finished));
+    let messages = [&before_finished[..], &FINISHED_HEADER, &verify_data].concat();
+
+    let synthesize = |secret, messages: &[u8]| {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let z_in = (0..ARITY)
+        .map(|i| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(F::from(0))).unwrap())
+        .collect::<Vec<_>>();
+      let step = HandshakeStep::new(Direction::Server, secret, messages.to_vec(), hello.len());
+      let (_, z_out) = step.synthesize(&mut cs.namespace(|| "step"), None, &z_in).unwrap();
+      (cs.is_satisfied(), z_out.iter().map(|v| v.get_value().unwrap()).collect::<Vec<_>>())
+    };
+    let (satisfied, z_out) = synthesize(secret, &messages);
+    assert!(satisfied);
+
+    let transcript_hash: [u8; HASH_LEN] = Sha256::digest(&messages).into();
+    let derived =
+      expand_label(&secret, b"derived", &key_schedule::EMPTY_HASH, HASH_LEN);
+    let master = hmac(&derived, &[0; HASH_LEN]);
+    let traffic = expand_label(&master, b"s ap traffic", &transcript_hash, HASH_LEN);
+    let key = expand_label(&traffic, b"key", &[], KEY_LEN).try_into().unwrap();
+    let iv = expand_label(&traffic, b"iv", &[], IV_LEN).try_into().unwrap();
+    assert_eq!(z_out[..KEYS_INDEX], transcript_io::<F>(&transcript_hash));
+    assert_eq!(z_out[KEYS_INDEX], keys_commitment::<F>(&key, &iv));
+
+    // another secret does not reproduce the server `Finished`
+    assert!(!synthesize([12u8; HASH_LEN], &messages).0);
+    // nor does another transcript
+    let mut tampered = messages.clone();
+    tampered[5] ^= 1;
+    assert!(!synthesize(secret, &tampered).0);
+  }
+
+  #[test]
+  fn test_record_step() {
+    let (key, iv) = ([3u8; KEY_LEN], [5u8; IV_LEN]);
+    let plaintext = b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{}\x17".to_vec();
+    let seq = 3u64;
+    let mut nonce = iv;
+    for (n, s) in nonce[4..].iter_mut().zip(seq.to_be_bytes()) {
+      *n ^= s;
+    }
+    let mut ciphertext = plaintext.clone();
+    let mut cipher = ChaCha20::new(&key.into(), &nonce.into());
+    cipher.seek(64u64);
+    cipher.apply_keystream(&mut ciphertext);
+
+    let keys = keys_commitment(&key, &iv);
+    let z = [F::from(1), F::from(2), keys, F::from(seq), F::from(4), F::from(5)];
+    let mut cs = TestConstraintSystem::<F>::new();
+    let z_in = z
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(*v)).unwrap())
+      .collect::<Vec<_>>();
+    let step = TlsStep::Record(RecordStep::new(key, iv, ciphertext.clone()));
+    let (_, z_out) = step.synthesize(&mut cs.namespace(|| "step"), None, &z_in).unwrap();
+    assert!(cs.is_satisfied());
+
+    let z_out = z_out.iter().map(|v| v.get_value().unwrap()).collect::<Vec<_>>();
+    assert_eq!(z_out[..KEYS_INDEX + 1], z[..KEYS_INDEX + 1]);
+    assert_eq!(z_out[SEQ_INDEX], F::from(seq + 1));
+    assert_eq!(z_out[CIPHERTEXT_INDEX], accumulate(F::from(4), &ciphertext));
+    assert_eq!(z_out[PLAINTEXT_INDEX], accumulate(F::from(5), &plaintext));
+
+    // other keys do not match the commitment
+    let step = TlsStep::Record(RecordStep::new([4u8; KEY_LEN], iv, ciphertext));
+    let mut cs = TestConstraintSystem::<F>::new();
+    let z_in = z
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(*v)).unwrap())
+      .collect::<Vec<_>>();
+    let _ = step.synthesize(&mut cs.namespace(|| "step"), None, &z_in).unwrap();
+    assert!(!cs.is_satisfied());
+  }
+}
@@ -0,0 +1,917 @@
+//! A step circuit verifying a chain of X.509 certificates (RFC 5280) from a
+//! pinned set of roots, one certificate per step, so that a client proves
+//! that the key of a server is certified by one of the roots without
+//! revealing the chain.
+//!
+//! The state of the chain is laid out by the `*_INDEX` constants: the
+//! commitment to the key of the last certificate (see [`key_commitment`]),
+//! the time the certificates are valid at (see [`utc_time`]), whether the
+//! last certificate is a CA, and the number of certificates already
+//! verified. The chain starts at [`initial_io`]: the first step verifies a
+//! certificate signed by one of the roots, and each other step a certificate
+//! signed by the key of the last one, which must be a CA.
+//!
+//! An [`X509Step`] hashes the DER `TBSCertificate` of a certificate with
+//! SHA-256, verifies its signature and parses its fields in the circuit,
+//! up to bounds fixing the shape of the step:
+//! - version 3 certificates with serial numbers of at most [`MAX_SERIAL_LEN`] bytes, signed with
+//!   `sha256WithRSAEncryption`;
+//! - issuer and subject names of at most [`MAX_NAME_LEN`] bytes;
+//! - validity periods of `UTCTime`s, i.e. until 2050;
+//! - RSA keys of [`MODULUS_LEN`] bytes, with the exponent 65537.
+//!
+//! A certificate is a CA if one of its first [`MAX_CA_INDEX`] + 1 extensions
+//! is the critical `basicConstraints` extension with `cA` set, found by
+//! walking the headers of the extensions, which must then end the
+//! `TBSCertificate`, after its key; the other extensions are not parsed.
+//! Names are not chained, as the certificates are chained by their keys, and
+//! path lengths, key usages, name constraints and revocation are not checked.
+use bellpepper::gadgets::boolean_utils::conditionally_select;
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use crate::{
+  errors::NovaError,
+  gadgets::{
+    alloc_bytes, alloc_num_equals, alloc_packed, alloc_zero, pack_bytes,
+    rsa::{verify_pkcs1v15_sha256, RsaModulus},
+    select_num_or_zero, sha256_var, BYTES_PER_ELEMENT,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+  supernova::StepCircuit,
+};
+
+/// The number of elements of the `z` of the step
+pub const ARITY: usize = 4;
+/// The index of the commitment to the key of the last certificate in `z`
+pub const KEY_INDEX: usize = 0;
+/// The index of the time the certificates are valid at in `z`
+pub const TIME_INDEX: usize = 1;
+/// The index of whether the last certificate is a CA in `z`
+pub const CA_INDEX: usize = 2;
+/// The index of the number of verified certificates in `z`
+pub const DEPTH_INDEX: usize = 3;
+
+/// The length of the moduli of the keys
+pub const MODULUS_LEN: usize = 256;
+/// The maximum length of serial numbers
+pub const MAX_SERIAL_LEN: usize = 31;
+/// The maximum length of the DER encoding of names
+pub const MAX_NAME_LEN: usize = 1023;
+/// The maximum index of the `basicConstraints` extension among the
+/// extensions of a CA
+pub const MAX_CA_INDEX: usize = 7;
+
+/// The `version` of version 3 certificates
+const VERSION: [u8; 5] = [0xa0, 0x03, 0x02, 0x01, 0x02];
+
+/// The `AlgorithmIdentifier` of `sha256WithRSAEncryption`
+const SHA256_WITH_RSA: [u8; 15] =
+  [0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b, 0x05, 0x00];
+
+/// The `BIT STRING` header of the signatures
+const SIGNATURE_HEADER: [u8; 5] = [0x03, 0x82, 0x01, 0x01, 0x00];
+
+/// The `SubjectPublicKeyInfo` of RSA keys up to their modulus
+const KEY_PREFIX: [u8; 33] = [
+  0x30, 0x82, 0x01, 0x22, 0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
+  0x01, 0x05, 0x00, 0x03, 0x82, 0x01, 0x0f, 0x00, 0x30, 0x82, 0x01, 0x0a, 0x02, 0x82, 0x01, 0x01,
+  0x00,
+];
+
+/// The `SubjectPublicKeyInfo` of RSA keys after their modulus: the exponent
+const KEY_SUFFIX: [u8; 5] = [0x02, 0x03, 0x01, 0x00, 0x01];
+
+const KEY_LEN: usize = KEY_PREFIX.len() + MODULUS_LEN + KEY_SUFFIX.len();
+
+/// The `Validity` of two `UTCTime`s, `None` being their digits
+const VALIDITY_LEN: usize = 32;
+
+/// The critical `basicConstraints` extension with `cA` set, `None` being the
+/// lengths, which depend on the path length
+const CA_EXTENSION: [Option<u8>; 17] = [
+  Some(0x30),
+  None,
+  Some(0x06),
+  Some(0x03),
+  Some(0x55),
+  Some(0x1d),
+  Some(0x13),
+  Some(0x01),
+  Some(0x01),
+  Some(0xff),
+  Some(0x04),
+  None,
+  Some(0x30),
+  None,
+  Some(0x01),
+  Some(0x01),
+  Some(0xff),
+];
+
+/// The time at a second, as the number of digits `YYYYMMDDHHMMSS`
+pub fn utc_time(year: u64, month: u64, day: u64, hour: u64, minute: u64, second: u64) -> u64 {
+  [month, day, hour, minute, second].iter().fold(year, |acc, x| 100 * acc + x)
+}
+
+/// The commitment to the key of modulus `modulus`
+pub fn key_commitment<F: PrimeField>(modulus: &[u8]) -> F {
+  PoseidonConstantsCircuit::<F>::default().hash(&pack_bytes(modulus))
+}
+
+/// The `z` a chain of certificates valid at `time` starts at
+pub fn initial_io<F: PrimeField>(time: u64) -> Vec<F> {
+  vec![F::ZERO, F::from(time), F::ONE, F::ZERO]
+}
+
+/// The lengths of the header and of the content of the DER element at the
+/// start of `der`, of a short length or of a length of one or two bytes
+fn element_lens(der: &[u8]) -> Option<(usize, usize)> {
+  match der {
+    [_, len, ..] if *len < 0x80 => Some((2, usize::from(*len))),
+    [_, 0x81, len, ..] => Some((3, usize::from(*len))),
+    [_, 0x82, high, low, ..] => Some((4, (usize::from(*high) << 8) + usize::from(*low))),
+    _ => None,
+  }
+}
+
+/// The length of the DER `SEQUENCE` at the start of `der`, with its header
+fn sequence_len(der: &[u8]) -> Option<usize> {
+  let (header, content) = element_lens(der).filter(|_| der[0] == 0x30)?;
+  Some(header + content)
+}
+
+/// Whether `der` starts with the `basicConstraints` extension with `cA` set
+fn is_ca_extension(der: &[u8]) -> bool {
+  der.len() >= CA_EXTENSION.len()
+    && der.iter().zip(&CA_EXTENSION).all(|(byte, expected)| expected.is_none_or(|e| e == *byte))
+}
+
+/// The index of the `basicConstraints` extension with `cA` set among the
+/// extensions `der`, the `[3]` element ending a `TBSCertificate`, if it is at
+/// most [`MAX_CA_INDEX`]
+fn ca_index(der: &[u8]) -> Option<usize> {
+  let (header, content) = element_lens(der).filter(|_| der[0] == 0xa3)?;
+  let extensions = der.get(header..).filter(|e| e.len() == content)?;
+  let (header, content) = element_lens(extensions).filter(|_| extensions[0] == 0x30)?;
+  let mut rest = extensions.get(header..).filter(|e| e.len() == content)?;
+  for i in 0..=MAX_CA_INDEX {
+    if is_ca_extension(rest) {
+      return Some(i);
+    }
+    rest = rest.get(sequence_len(rest)?..)?;
+  }
+  None
+}
+
+/// The `TBSCertificate` and the signature of the DER `certificate`
+fn split(certificate: &[u8]) -> Result<(&[u8], &[u8]), NovaError> {
+  let invalid = || NovaError::InvalidCertificate("certificate".into());
+  if certificate.get(1) != Some(&0x82) || sequence_len(certificate) != Some(certificate.len()) {
+    return Err(invalid());
+  }
+  let tbs_len = sequence_len(&certificate[4..]).ok_or_else(invalid)?;
+  let (tbs, rest) = certificate[4..].split_at_checked(tbs_len).ok_or_else(invalid)?;
+  let signature = rest
+    .strip_prefix(&[&SHA256_WITH_RSA[..], &SIGNATURE_HEADER].concat()[..])
+    .filter(|signature| signature.len() == MODULUS_LEN)
+    .ok_or_else(invalid)?;
+  Ok((tbs, signature))
+}
+
+/// The fields of a `TBSCertificate` a step needs the offsets of
+#[derive(Clone, Debug)]
+struct Layout {
+  serial_len:  usize,
+  issuer_len:  usize,
+  subject_len: usize,
+  modulus:     Vec<u8>,
+  ca_index:    Option<usize>,
+}
+
+/// Parses the DER `tbs` within the schema of the step
+fn parse(tbs: &[u8]) -> Result<Layout, NovaError> {
+  let invalid = |field: &str| NovaError::InvalidCertificate(field.into());
+  if tbs.get(1) != Some(&0x82) || sequence_len(tbs) != Some(tbs.len()) {
+    return Err(invalid("tbs"));
+  }
+  if tbs.get(4..9) != Some(&VERSION[..]) {
+    return Err(invalid("version"));
+  }
+  let serial_len = match tbs.get(9..11) {
+    Some([0x02, len]) if (1..=MAX_SERIAL_LEN).contains(&usize::from(*len)) => usize::from(*len),
+    _ => return Err(invalid("serial number")),
+  };
+  let rest = tbs.get(11 + serial_len..).ok_or_else(|| invalid("serial number"))?;
+  let rest = rest.strip_prefix(&SHA256_WITH_RSA[..]).ok_or_else(|| invalid("signature"))?;
+
+  let name_len = |der: &[u8], field| {
+    sequence_len(der)
+      .filter(|len| *len <= MAX_NAME_LEN && *len <= der.len())
+      .ok_or_else(|| invalid(field))
+  };
+  let issuer_len = name_len(rest, "issuer")?;
+  let rest = &rest[issuer_len..];
+
+  let time = |time: &[u8]| {
+    time.len() == 15
+      && time[..2] == [0x17, 0x0d]
+      && time[2..14].iter().all(u8::is_ascii_digit)
+      && time[14] == b'Z'
+  };
+  match rest.get(..VALIDITY_LEN) {
+    Some([0x30, 0x1e, times @ ..]) if time(&times[..15]) && time(&times[15..]) => (),
+    _ => return Err(invalid("validity")),
+  }
+  let rest = &rest[VALIDITY_LEN..];
+
+  let subject_len = name_len(rest, "subject")?;
+  let rest = &rest[subject_len..];
+
+  let invalid_key = || invalid("subject public key");
+  let key = rest.get(..KEY_LEN).ok_or_else(invalid_key)?;
+  let modulus = key[KEY_PREFIX.len()..KEY_PREFIX.len() + MODULUS_LEN].to_vec();
+  if key[..KEY_PREFIX.len()] != KEY_PREFIX
+    || key[KEY_PREFIX.len() + MODULUS_LEN..] != KEY_SUFFIX
+    || modulus[0] & 0x80 == 0
+  {
+    return Err(invalid_key());
+  }
+
+  let ca_index = ca_index(&rest[KEY_LEN..]);
+  Ok(Layout { serial_len, issuer_len, subject_len, modulus, ca_index })
+}
+
+/// The modulus of the key of the DER `certificate`, e.g. of a root
+pub fn public_key(certificate: &[u8]) -> Result<Vec<u8>, NovaError> {
+  let (tbs, _) = split(certificate)?;
+  Ok(parse(tbs)?.modulus)
+}
+
+/// The step verifying a certificate of the chain
+#[derive(Clone, Debug)]
+pub struct X509Step {
+  roots:       Vec<Vec<u8>>,
+  issuer:      Vec<u8>,
+  tbs:         Vec<u8>,
+  signature:   Vec<u8>,
+  max_tbs_len: usize,
+  layout:      Layout,
+}
+
+impl X509Step {
+  /// The step verifying the DER `certificate`, signed by the key of modulus
+  /// `issuer`, in a chain from the roots of moduli `roots`; the shape of the
+  /// step depends on the number of roots and on `max_tbs_len`, the maximum
+  /// length of the `TBSCertificate`s, only
+  pub fn new(
+    roots: &[Vec<u8>],
+    issuer: &[u8],
+    certificate: &[u8],
+    max_tbs_len: usize,
+  ) -> Result<Self, NovaError> {
+    if issuer.len() != MODULUS_LEN || roots.iter().any(|root| root.len() != MODULUS_LEN) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (tbs, signature) = split(certificate)?;
+    if tbs.len() > max_tbs_len {
+      return Err(NovaError::InvalidCertificate("tbs".into()));
+    }
+    Ok(Self {
+      roots: roots.to_vec(),
+      issuer: issuer.to_vec(),
+      tbs: tbs.to_vec(),
+      signature: signature.to_vec(),
+      max_tbs_len,
+      layout: parse(tbs)?,
+    })
+  }
+}
+
+/// The value of the small `num`
+fn to_u64<F: PrimeField>(num: &AllocatedNum<F>) -> Option<u64> {
+  let value = num.get_value()?;
+  let repr = value.to_repr();
+  let low = u64::from_le_bytes(repr.as_ref()[..8].try_into().ok()?);
+  (F::from(low) == value).then_some(low)
+}
+
+/// Allocates the `n` bits of `lc`, least significant first, so that `lc` is
+/// less than `2^n`
+fn decompose<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  lc: LinearCombination<F>,
+  value: Option<u64>,
+  n: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  let bits = (0..n)
+    .map(|i| {
+      let bit = value.map(|v| (v >> i) & 1 == 1);
+      AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  cs.enforce(|| "decomposition", |_| bits_lc(&bits), |lc| lc + CS::one(), |_| lc);
+  Ok(bits)
+}
+
+/// The number of the bits `bits`, least significant first
+fn bits_lc<F: PrimeField>(bits: &[AllocatedBit]) -> LinearCombination<F> {
+  bits
+    .iter()
+    .enumerate()
+    .fold(LinearCombination::zero(), |acc, (i, bit)| acc + (F::from(1u64 << i), bit.get_variable()))
+}
+
+/// The bytes of `bits` as numbers
+fn byte_nums<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[Boolean],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  bits
+    .chunks(8)
+    .enumerate()
+    .map(|(i, bits)| {
+      let value = bits.iter().enumerate().fold(LinearCombination::zero(), |lc, (j, bit)| {
+        lc + &bit.lc(CS::one(), F::from(1 << (7 - j)))
+      });
+      let byte = AllocatedNum::alloc(cs.namespace(|| format!("byte {i}")), || {
+        bits.iter().try_fold(F::ZERO, |acc, bit| {
+          let bit = bit.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+          Ok(acc.double() + F::from(u64::from(bit)))
+        })
+      })?;
+      cs.enforce(
+        || format!("byte {i} of bits"),
+        |_| value,
+        |lc| lc + CS::one(),
+        |lc| lc + byte.get_variable(),
+      );
+      Ok(byte)
+    })
+    .collect()
+}
+
+/// Packs the numbers of the bytes `bytes` as [`pack_bytes`] does
+pub(crate) fn pack_nums<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[AllocatedNum<F>],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  bytes
+    .chunks(BYTES_PER_ELEMENT)
+    .enumerate()
+    .map(|(i, chunk)| {
+      let packed = AllocatedNum::alloc(cs.namespace(|| format!("element {i}")), || {
+        chunk.iter().try_fold(F::ZERO, |acc, byte| {
+          Ok(acc * F::from(256) + byte.get_value().ok_or(SynthesisError::AssignmentMissing)?)
+        })
+      })?;
+      let mut coeff = F::ONE;
+      let mut lc = LinearCombination::zero();
+      for byte in chunk.iter().rev() {
+        lc = lc + (coeff, byte.get_variable());
+        coeff *= F::from(256);
+      }
+      cs.enforce(
+        || format!("pack {i}"),
+        |_| lc,
+        |lc| lc + CS::one(),
+        |lc| lc + packed.get_variable(),
+      );
+      Ok(packed)
+    })
+    .collect()
+}
+
+/// Enforces that `bytes` are `expected`, a `None` matching any byte, if
+/// `condition` holds
+fn enforce_bytes<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[AllocatedNum<F>],
+  expected: &[Option<u8>],
+  condition: &Boolean,
+) {
+  for (i, (byte, expected)) in bytes.iter().zip(expected).enumerate() {
+    if let Some(expected) = expected {
+      cs.enforce(
+        || format!("byte {i}"),
+        |lc| lc + byte.get_variable() - (F::from(u64::from(*expected)), CS::one()),
+        |_| condition.lc(CS::one(), F::ONE),
+        |lc| lc,
+      );
+    }
+  }
+}
+
+/// Enforces that `bytes` are the bytes `expected`
+fn enforce_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  bytes: &[AllocatedNum<F>],
+  expected: &[u8],
+) {
+  let expected = expected.iter().copied().map(Some).collect::<Vec<_>>();
+  enforce_bytes::<F, _>(cs, bytes, &expected, &Boolean::constant(true));
+}
+
+/// Shifts `bytes` left by the number of bits `bits`, filling with `zero`
+fn shift<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[AllocatedNum<F>],
+  bits: &[AllocatedBit],
+  zero: &AllocatedNum<F>,
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  let mut shifted = bytes.to_vec();
+  for (i, bit) in bits.iter().enumerate() {
+    let mut cs = cs.namespace(|| format!("shift {i}"));
+    let bit = Boolean::from(bit.clone());
+    shifted = (0..shifted.len())
+      .map(|j| {
+        let moved = shifted.get(j + (1 << i)).unwrap_or(zero);
+        conditionally_select(cs.namespace(|| format!("byte {j}")), moved, &shifted[j], &bit)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+  }
+  Ok(shifted)
+}
+
+/// The lengths of the header and of the content of the DER element of tag
+/// `tag` at the start of `der`, of a short length or of a length of one or
+/// two bytes, with their values, the tag being enforced if `condition` holds
+#[allow(clippy::type_complexity)]
+fn element_lens_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  der: &[AllocatedNum<F>],
+  tag: u8,
+  condition: &Boolean,
+) -> Result<[(LinearCombination<F>, Option<u64>); 2], SynthesisError> {
+  let [tag_byte, first, second, third, ..] = der else {
+    return Err(SynthesisError::Unsatisfiable);
+  };
+  enforce_bytes(cs.namespace(|| "tag"), &[tag_byte.clone()], &[Some(tag)], condition);
+
+  let form = to_u64(first);
+  let short = AllocatedBit::alloc(cs.namespace(|| "short"), form.map(|f| f < 0x80))?;
+  let one = AllocatedBit::alloc(cs.namespace(|| "one byte"), form.map(|f| f == 0x81))?;
+  let two = AllocatedBit::alloc(cs.namespace(|| "two bytes"), form.map(|f| f == 0x82))?;
+  cs.enforce(
+    || "form",
+    |lc| lc + short.get_variable() + one.get_variable() + two.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + CS::one(),
+  );
+  for (form, value) in [(&one, 0x81), (&two, 0x82)] {
+    cs.enforce(
+      || format!("form {value}"),
+      |lc| lc + first.get_variable() - (F::from(value), CS::one()),
+      |lc| lc + form.get_variable(),
+      |lc| lc,
+    );
+  }
+
+  // the terms of the length of each form: short * first, one * second and
+  // two * (256 * second + third)
+  let [first_value, second_value, third_value] = [first, second, third].map(to_u64);
+  let terms = [
+    (&short, LinearCombination::zero() + first.get_variable(), first_value),
+    (&one, LinearCombination::zero() + second.get_variable(), second_value),
+    (
+      &two,
+      LinearCombination::zero() + (F::from(256), second.get_variable()) + third.get_variable(),
+      second_value.zip(third_value).map(|(second, third)| 256 * second + third),
+    ),
+  ]
+  .into_iter()
+  .enumerate()
+  .map(|(i, (form, lc, value))| {
+    let term = AllocatedNum::alloc(cs.namespace(|| format!("term {i}")), || {
+      let value = value.ok_or(SynthesisError::AssignmentMissing)?;
+      let form = form.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(if form { F::from(value) } else { F::ZERO })
+    })?;
+    cs.enforce(
+      || format!("term {i}"),
+      |_| lc,
+      |lc| lc + form.get_variable(),
+      |lc| lc + term.get_variable(),
+    );
+    Ok(term)
+  })
+  .collect::<Result<Vec<_>, SynthesisError>>()?;
+  // a short length is less than 0x80
+  let short_len = LinearCombination::zero() + terms[0].get_variable();
+  decompose(cs.namespace(|| "short range"), short_len, to_u64(&terms[0]), 7)?;
+
+  let header = LinearCombination::zero()
+    + (F::from(2), short.get_variable())
+    + (F::from(3), one.get_variable())
+    + (F::from(4), two.get_variable());
+  let header_value = [(&short, 2), (&one, 3), (&two, 4)]
+    .into_iter()
+    .try_fold(0, |acc, (form, n)| form.get_value().map(|form| if form { acc + n } else { acc }));
+  let content = terms.iter().fold(LinearCombination::zero(), |lc, term| lc + term.get_variable());
+  let content_value = terms.iter().try_fold(0, |acc, term| to_u64(term).map(|term| acc + term));
+  Ok([(header, header_value), (content, content_value)])
+}
+
+/// The bits of the length `len` of the DER `SEQUENCE` of at most
+/// [`MAX_NAME_LEN`] bytes at the start of `der`, with its header, of a short
+/// length or of a length of one or two bytes
+fn sequence_len_bits<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  der: &[AllocatedNum<F>],
+  len: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  let [(header, _), (content, _)] =
+    element_lens_circuit(cs.namespace(|| "lens"), der, 0x30, &Boolean::constant(true))?;
+  let n_bits = (usize::BITS - MAX_NAME_LEN.leading_zeros()) as usize;
+  decompose(cs.namespace(|| "len"), header + &content, Some(len as u64), n_bits)
+}
+
+/// The time of the `UTCTime` `der`, as [`utc_time`] does, and its value
+fn time_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  der: &[AllocatedNum<F>],
+) -> Result<(LinearCombination<F>, Option<u64>), SynthesisError> {
+  enforce_constant(cs.namespace(|| "header"), &der[..2], &[0x17, 0x0d]);
+  enforce_constant(cs.namespace(|| "zone"), &der[14..15], b"Z");
+  // `weight * digit` with the digit `k`
+  let digit = |lc: LinearCombination<F>, k: usize, weight: u64| {
+    lc + (F::from(weight), der[2 + k].get_variable())
+      - (F::from(u64::from(b'0') * weight), CS::one())
+  };
+  let digits = (0..12)
+    .map(|k| {
+      let mut cs = cs.namespace(|| format!("digit {k}"));
+      let value = to_u64(&der[2 + k]).map(|b| b.wrapping_sub(u64::from(b'0')));
+      // 0 <= digit < 10
+      decompose(cs.namespace(|| "low"), digit(LinearCombination::zero(), k, 1), value, 4)?;
+      let high = digit(LinearCombination::zero() + (F::from(6), CS::one()), k, 1);
+      decompose(cs.namespace(|| "high"), high, value.map(|v| v.wrapping_add(6)), 4)?;
+      Ok(value)
+    })
+    .collect::<Result<Option<Vec<_>>, SynthesisError>>()?;
+
+  // YY is the year 19YY from 50 on, 20YY before
+  let yy = digit(digit(LinearCombination::zero(), 0, 10), 1, 1);
+  let yy_value = digits.as_ref().map(|d| 10 * d[0] + d[1]);
+  let recent = AllocatedBit::alloc(cs.namespace(|| "recent"), yy_value.map(|yy| yy < 50))?;
+  // distance = 49 - YY if recent, YY - 50 otherwise
+  let distance_value =
+    yy_value.map(|yy| if yy < 50 { 49u64.wrapping_sub(yy) } else { yy.wrapping_sub(50) });
+  let distance = AllocatedNum::alloc(cs.namespace(|| "distance"), || {
+    distance_value.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(
+    || "distance",
+    |lc| lc + recent.get_variable(),
+    |lc| lc + (F::from(99), CS::one()) - &yy - &yy,
+    |lc| lc + distance.get_variable() - &yy + (F::from(50), CS::one()),
+  );
+  let distance_lc = LinearCombination::zero() + distance.get_variable();
+  decompose(cs.namespace(|| "distance range"), distance_lc, distance_value, 7)?;
+
+  let year_weight = 10u64.pow(10);
+  let mut time = digit(digit(LinearCombination::zero(), 0, 10 * year_weight), 1, year_weight)
+    + (F::from(100 * year_weight), recent.get_variable())
+    + (F::from(1900 * year_weight), CS::one());
+  for k in 2..12 {
+    time = digit(time, k, 10u64.pow(11 - k as u32));
+  }
+  let value = digits.zip(yy_value).map(|(digits, yy)| {
+    let year = if yy < 50 { 2000 + yy } else { 1900 + yy };
+    digits[2..].iter().fold(year, |acc, digit| 10 * acc + digit)
+  });
+  Ok((time, value))
+}
+
+impl<F: PrimeField> StepCircuit<F> for X509Step {
+  fn arity(&self) -> usize { ARITY }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let [key, time, ca, depth] = z else {
+      return Err(SynthesisError::Unsatisfiable);
+    };
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let poseidon = PoseidonConstantsCircuit::<F>::default();
+
+    // the issuer is the last certificate, a CA, or one of the roots for the
+    // first certificate
+    let issuer = RsaModulus::alloc(cs.namespace(|| "issuer"), Some(&self.issuer), MODULUS_LEN)?;
+    let elements = alloc_packed(cs.namespace(|| "issuer elements"), issuer.bytes())?;
+    let issuer_key = poseidon.hash_circuit(cs.namespace(|| "issuer key"), &elements)?;
+    let first = alloc_num_equals(cs.namespace(|| "first"), depth, &zero)?;
+    cs.enforce(
+      || "issuer key",
+      |lc| lc + issuer_key.get_variable() - key.get_variable(),
+      |lc| lc + CS::one() - first.get_variable(),
+      |lc| lc,
+    );
+    cs.enforce(
+      || "issuer is a CA",
+      |lc| lc + ca.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + CS::one(),
+    );
+    let mut product = LinearCombination::zero() + CS::one();
+    let mut product_value = Some(F::ONE);
+    for (i, root) in self.roots.iter().enumerate() {
+      let root = key_commitment::<F>(root);
+      product_value = product_value.zip(issuer_key.get_value()).map(|(p, key)| p * (key - root));
+      let next = AllocatedNum::alloc(cs.namespace(|| format!("root {i}")), || {
+        product_value.ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      cs.enforce(
+        || format!("root {i}"),
+        |_| product,
+        |lc| lc + issuer_key.get_variable() - (root, CS::one()),
+        |lc| lc + next.get_variable(),
+      );
+      product = LinearCombination::zero() + next.get_variable();
+    }
+    cs.enforce(|| "pinned root", |_| product, |lc| lc + first.get_variable(), |lc| lc);
+
+    // the TBS, of length `4 + 256 * tbs[2] + tbs[3]`, signed by the issuer
+    let mut tbs = self.tbs.clone();
+    tbs.resize(self.max_tbs_len, 0);
+    let tbs = alloc_bytes(cs.namespace(|| "tbs"), Some(&tbs), self.max_tbs_len)?;
+    let mut bytes = byte_nums(cs.namespace(|| "tbs bytes"), &tbs)?;
+    // the extensions take at least two headers of up to 4 bytes and the
+    // `basicConstraints` extension
+    let min_len = 11 + SHA256_WITH_RSA.len() + VALIDITY_LEN + KEY_LEN + 8 + CA_EXTENSION.len();
+    bytes.resize(bytes.len().max(min_len), zero.clone());
+    enforce_constant(cs.namespace(|| "tbs header"), &bytes[..2], &[0x30, 0x82]);
+    let len =
+      AllocatedNum::alloc(cs.namespace(|| "tbs len"), || Ok(F::from(self.tbs.len() as u64)))?;
+    cs.enforce(
+      || "tbs len",
+      |lc| {
+        lc + (F::from(256), bytes[2].get_variable())
+          + bytes[3].get_variable()
+          + (F::from(4), CS::one())
+      },
+      |lc| lc + CS::one(),
+      |lc| lc + len.get_variable(),
+    );
+    let digest = sha256_var(cs.namespace(|| "digest"), &tbs, &len)?;
+    verify_pkcs1v15_sha256(cs.namespace(|| "signature"), &issuer, Some(&self.signature), &digest)?;
+
+    // the version and the serial number, skipped
+    enforce_constant(cs.namespace(|| "version"), &bytes[4..9], &VERSION);
+    enforce_constant(cs.namespace(|| "serial tag"), &bytes[9..10], &[0x02]);
+    let serial_len = LinearCombination::zero() + bytes[10].get_variable();
+    let serial_value = Some(self.layout.serial_len as u64);
+    let n_serial_bits = (usize::BITS - MAX_SERIAL_LEN.leading_zeros()) as usize;
+    let serial_bits =
+      decompose(cs.namespace(|| "serial len"), serial_len, serial_value, n_serial_bits)?;
+    let fields = shift(cs.namespace(|| "skip serial"), &bytes[11..], &serial_bits, &zero)?;
+
+    // the signature algorithm and the issuer, skipped
+    enforce_constant(cs.namespace(|| "signature algorithm"), &fields[..15], &SHA256_WITH_RSA);
+    let fields = &fields[SHA256_WITH_RSA.len()..];
+    let issuer_bits =
+      sequence_len_bits(cs.namespace(|| "issuer len"), fields, self.layout.issuer_len)?;
+    let fields = shift(cs.namespace(|| "skip issuer"), fields, &issuer_bits, &zero)?;
+
+    // the validity, around the time of the chain
+    enforce_constant(cs.namespace(|| "validity"), &fields[..2], &[0x30, 0x1e]);
+    let (not_before, not_before_value) =
+      time_circuit(cs.namespace(|| "not before"), &fields[2..17])?;
+    let (not_after, not_after_value) = time_circuit(cs.namespace(|| "not after"), &fields[17..32])?;
+    let time_value = to_u64(time);
+    let after = LinearCombination::zero() + time.get_variable() - &not_before;
+    let after_value = time_value.zip(not_before_value).map(|(t, nb)| t.wrapping_sub(nb));
+    decompose(cs.namespace(|| "after not before"), after, after_value, 48)?;
+    let before = not_after - time.get_variable();
+    let before_value = not_after_value.zip(time_value).map(|(na, t)| na.wrapping_sub(t));
+    decompose(cs.namespace(|| "before not after"), before, before_value, 48)?;
+
+    // the subject, skipped, and its key
+    let fields = &fields[VALIDITY_LEN..];
+    let subject_bits =
+      sequence_len_bits(cs.namespace(|| "subject len"), fields, self.layout.subject_len)?;
+    let fields = shift(cs.namespace(|| "skip subject"), fields, &subject_bits, &zero)?;
+    let (key_prefix, rest) = fields.split_at(KEY_PREFIX.len());
+    let (modulus, rest) = rest.split_at(MODULUS_LEN);
+    enforce_constant(cs.namespace(|| "key prefix"), key_prefix, &KEY_PREFIX);
+    enforce_constant(cs.namespace(|| "key suffix"), &rest[..KEY_SUFFIX.len()], &KEY_SUFFIX);
+    let elements = pack_nums(cs.namespace(|| "subject elements"), modulus)?;
+    let subject_key = poseidon.hash_circuit(cs.namespace(|| "subject key"), &elements)?;
+
+    // the certificate is a CA if one of its extensions is the
+    // `basicConstraints` extension, found by skipping the extensions before
+    // it; the extensions are zero for other certificates, which the walk
+    // does not constrain
+    let is_ca =
+      AllocatedBit::alloc(cs.namespace(|| "is ca"), Some(self.layout.ca_index.is_some()))?;
+    let condition = Boolean::from(is_ca.clone());
+    let extensions = rest[KEY_SUFFIX.len()..]
+      .iter()
+      .enumerate()
+      .map(|(i, byte)| {
+        select_num_or_zero(cs.namespace(|| format!("extension byte {i}")), byte, &condition)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let n_bits = (usize::BITS - extensions.len().leading_zeros()) as usize;
+
+    // the `[3]` element of the extensions ends the TBS, after the key
+    let [(header, header_value), (content, content_value)] =
+      element_lens_circuit(cs.namespace(|| "tagged extensions"), &extensions, 0xa3, &condition)?;
+    let position = bits_lc::<F>(&serial_bits)
+      + &bits_lc(&issuer_bits)
+      + &bits_lc(&subject_bits)
+      + (F::from((11 + SHA256_WITH_RSA.len() + VALIDITY_LEN + KEY_LEN) as u64), CS::one());
+    cs.enforce(
+      || "extensions end the tbs",
+      |_| position + &header + &content - len.get_variable(),
+      |lc| lc + is_ca.get_variable(),
+      |lc| lc,
+    );
+    let skip = header - (F::from(2), CS::one());
+    let skip_bits =
+      decompose(cs.namespace(|| "tagged header"), skip, header_value.map(|h| h - 2), 2)?;
+    let extensions = shift(cs.namespace(|| "skip tagged header"), &extensions, &skip_bits, &zero)?;
+
+    // the `SEQUENCE` of the extensions fills the `[3]` element
+    let tagged_content = content;
+    let [(header, header_value), (content, content_value)] =
+      element_lens_circuit(cs.namespace(|| "extensions"), &extensions, 0x30, &condition)?;
+    cs.enforce(
+      || "extensions fill the tagged extensions",
+      |_| header.clone() + &content - &tagged_content,
+      |lc| lc + is_ca.get_variable(),
+      |lc| lc,
+    );
+    let skip = header - (F::from(2), CS::one());
+    let skip_bits =
+      decompose(cs.namespace(|| "extensions header"), skip, header_value.map(|h| h - 2), 2)?;
+    let mut extension =
+      shift(cs.namespace(|| "skip extensions header"), &extensions, &skip_bits, &zero)?;
+
+    // each step skips the extension at the start of `extension` or stays on
+    // it, so that `extension` is at the start of an extension
+    let mut skipped = LinearCombination::zero();
+    let mut skipped_value = Some(0);
+    for i in 0..MAX_CA_INDEX {
+      let mut cs = cs.namespace(|| format!("extension {i}"));
+      let skip = self.layout.ca_index.is_some_and(|index| i < index);
+      let skip = AllocatedBit::alloc(cs.namespace(|| "skip"), Some(skip))?;
+      let [(header, header_value), (len, len_value)] =
+        element_lens_circuit(cs.namespace(|| "lens"), &extension, 0x30, &condition)?;
+      let len_value = header_value.zip(len_value).map(|(header, len)| header + len);
+      let len_value = len_value.zip(skip.get_value()).map(|(len, skip)| if skip { len } else { 0 });
+      let skipped_len = AllocatedNum::alloc(cs.namespace(|| "skipped len"), || {
+        len_value.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      cs.enforce(
+        || "skipped len",
+        |_| header + &len,
+        |lc| lc + skip.get_variable(),
+        |lc| lc + skipped_len.get_variable(),
+      );
+      let skipped_lc = LinearCombination::zero() + skipped_len.get_variable();
+      let len_bits = decompose(cs.namespace(|| "len"), skipped_lc, len_value, n_bits)?;
+      extension = shift(cs.namespace(|| "skip"), &extension, &len_bits, &zero)?;
+      skipped = skipped + skipped_len.get_variable();
+      skipped_value = skipped_value.zip(len_value).map(|(skipped, len)| skipped + len);
+    }
+    enforce_bytes(cs.namespace(|| "ca extension"), &extension, &CA_EXTENSION, &condition);
+
+    // and the extension is within the extensions
+    let room_value = content_value
+      .zip(skipped_value)
+      .map(|(content, skipped)| content.wrapping_sub(skipped + CA_EXTENSION.len() as u64));
+    let room_value =
+      room_value.zip(is_ca.get_value()).map(|(room, is_ca)| if is_ca { room } else { 0 });
+    let room = AllocatedNum::alloc(cs.namespace(|| "room"), || {
+      room_value.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "room",
+      |_| {
+        content - &skipped - (F::from(CA_EXTENSION.len() as u64), CS::one()) - room.get_variable()
+      },
+      |lc| lc + is_ca.get_variable(),
+      |lc| lc,
+    );
+    let room_lc = LinearCombination::zero() + room.get_variable();
+    decompose(cs.namespace(|| "room range"), room_lc, room_value, n_bits)?;
+
+    let ca_out = AllocatedNum::alloc(cs.namespace(|| "ca out"), || {
+      let is_ca = is_ca.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(if is_ca { F::ONE } else { F::ZERO })
+    })?;
+    cs.enforce(
+      || "ca out",
+      |lc| lc + is_ca.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + ca_out.get_variable(),
+    );
+    let depth_out = AllocatedNum::alloc(cs.namespace(|| "depth out"), || {
+      Ok(depth.get_value().ok_or(SynthesisError::AssignmentMissing)? + F::ONE)
+    })?;
+    cs.enforce(
+      || "depth out",
+      |lc| lc + depth.get_variable() + CS::one(),
+      |lc| lc + CS::one(),
+      |lc| lc + depth_out.get_variable(),
+    );
+    Ok((pc.cloned(), vec![subject_key, time.clone(), ca_out, depth_out]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  // a root, an intermediate CA it certifies and a leaf for example.com the
+  // intermediate certifies, valid in 2026
+  const ROOT: &[u8] = include_bytes!("testdata/root.der");
+  const INTERMEDIATE: &[u8] = include_bytes!("testdata/intermediate.der");
+  const LEAF: &[u8] = include_bytes!("testdata/leaf.der");
+  // a self-signed certificate, not a CA, whose extensions embed the
+  // `basicConstraints` extension of a CA in the value of another extension
+  const DISGUISED: &[u8] = include_bytes!("testdata/disguised.der");
+  const MAX_TBS_LEN: usize = 512;
+
+  fn step(issuer: &[u8], certificate: &[u8], z: &[F]) -> Option<Vec<F>> {
+    let roots = [public_key(ROOT).unwrap()];
+    let issuer = public_key(issuer).unwrap();
+    let step = X509Step::new(&roots, &issuer, certificate, MAX_TBS_LEN).unwrap();
+    synthesize(&step, z)
+  }
+
+  fn synthesize(step: &X509Step, z: &[F]) -> Option<Vec<F>> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let z = z
+      .iter()
+      .enumerate()
+      .map(|(i, x)| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(*x)).unwrap())
+      .collect::<Vec<_>>();
+    let (_, z_out) = step.synthesize(&mut cs, None, &z).ok()?;
+    cs.is_satisfied().then(|| z_out.iter().map(|x| x.get_value().unwrap()).collect())
+  }
+
+  #[test]
+  fn test_parse() {
+    let (tbs, _) = split(INTERMEDIATE).unwrap();
+    let layout = parse(tbs).unwrap();
+    assert_eq!((layout.serial_len, layout.issuer_len, layout.subject_len), (20, 25, 33));
+    assert_eq!(layout.ca_index, Some(0));
+    assert_eq!(parse(split(LEAF).unwrap().0).unwrap().ca_index, None);
+
+    assert!(split(&INTERMEDIATE[..INTERMEDIATE.len() - 1]).is_err());
+    let mut v1 = tbs.to_vec();
+    v1[8] = 0x00;
+    assert!(parse(&v1).is_err());
+  }
+
+  #[test]
+  fn test_chain() {
+    let time = utc_time(2026, 10, 14, 12, 0, 0);
+    let z = step(ROOT, INTERMEDIATE, &initial_io(time)).unwrap();
+    let intermediate = key_commitment(&public_key(INTERMEDIATE).unwrap());
+    assert_eq!(z, [intermediate, F::from(time), F::ONE, F::ONE]);
+    let leaf = step(INTERMEDIATE, LEAF, &z).unwrap();
+    let leaf_key = key_commitment(&public_key(LEAF).unwrap());
+    assert_eq!(leaf, [leaf_key, F::from(time), F::ZERO, F::from(2)]);
+
+    // the leaf has expired in 2027
+    let expired = utc_time(2027, 6, 1, 0, 0, 0);
+    assert_eq!(step(INTERMEDIATE, LEAF, &[intermediate, F::from(expired), F::ONE, F::ONE]), None);
+    // the intermediate is not a root
+    assert_eq!(step(INTERMEDIATE, LEAF, &initial_io(time)), None);
+  }
+
+  #[test]
+  fn test_ca_extension_in_another_extension() {
+    let time = utc_time(2026, 10, 14, 12, 0, 0);
+    let (tbs, _) = split(DISGUISED).unwrap();
+    assert!(tbs.windows(CA_EXTENSION.len()).any(is_ca_extension));
+    let key = public_key(DISGUISED).unwrap();
+    let step = X509Step::new(&[key.clone()], &key, DISGUISED, MAX_TBS_LEN).unwrap();
+    assert_eq!(step.layout.ca_index, None);
+    let z = synthesize(&step, &initial_io(time)).unwrap();
+    assert_eq!(z, [key_commitment(&key), F::from(time), F::ZERO, F::ONE]);
+
+    // the embedded extension is at the start of none of the extensions
+    for index in 0..=MAX_CA_INDEX {
+      let mut step = step.clone();
+      step.layout.ca_index = Some(index);
+      assert_eq!(synthesize(&step, &initial_io(time)), None);
+    }
+  }
+}
@@ -1,6 +1,12 @@
 //! Global Nova constants
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
 
-pub(crate) const NUM_CHALLENGE_BITS: usize = 128;
+use crate::errors::NovaError;
+
+// the folding challenges are allocated in the circuits, so their size does not
+// depend on the security level of the engine
+pub(crate) const NUM_CHALLENGE_BITS: usize = SecurityLevel::Bits128.bits();
 pub(crate) const BN_LIMB_WIDTH: usize = 64;
 pub(crate) const BN_N_LIMBS: usize = 4;
 pub(crate) const NUM_FE_WITHOUT_IO_FOR_CRHF: usize = 9 + NIO_NOVA_FOLD * BN_N_LIMBS;
@@ -12,3 +18,108 @@ pub(crate) const NIO_CYCLE_FOLD: usize = 4; // 1 per point (3) + scalar
 
 /// Bit size of Nova field element hashes
 pub const NUM_HASH_BITS: usize = 250;
+
+/// The security level the Fiat-Shamir challenges of an engine target, see
+/// `Engine::SECURITY_LEVEL`.
+///
+/// A single challenge in a 254-bit field reaches any of them, but one in a
+/// small field (e.g. the 64-bit Goldilocks field) does not: a protocol then
+/// needs several challenges, or several repetitions, to reach the level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SecurityLevel {
+  /// 100 bits of security
+  Bits100,
+  /// 128 bits of security
+  Bits128,
+}
+
+impl SecurityLevel {
+  /// The number of bits of security
+  pub const fn bits(self) -> usize {
+    match self {
+      Self::Bits100 => 100,
+      Self::Bits128 => 128,
+    }
+  }
+
+  /// The number of independent challenges in `F` that together reach the
+  /// level, for a protocol that a single challenge makes sound up to the size
+  /// of the field
+  pub fn challenge_repetitions<F: PrimeField>(self) -> usize {
+    self.bits().div_ceil(F::CAPACITY as usize)
+  }
+
+  /// The number of bits of security of a sum-check over `F` with
+  /// `num_rounds` rounds of degree `degree`, whose soundness error is
+  /// `num_rounds * degree / |F|`
+  pub fn sumcheck_bits<F: PrimeField>(num_rounds: usize, degree: usize) -> usize {
+    Self::sumcheck_bits_over(F::CAPACITY as usize, num_rounds, degree)
+  }
+
+  /// Same as [`SecurityLevel::sumcheck_bits`], over a field of more than
+  /// `2^field_bits` elements, e.g. an extension of a small field
+  pub fn sumcheck_bits_over(field_bits: usize, num_rounds: usize, degree: usize) -> usize {
+    let error = num_rounds.saturating_mul(degree).max(1);
+    let log_error = (usize::BITS - (error - 1).leading_zeros()) as usize;
+    field_bits.saturating_sub(log_error)
+  }
+
+  /// The number of independent repetitions of a sum-check over `F` with
+  /// `num_rounds` rounds of degree `degree` that together reach the level,
+  /// or `None` if none do
+  pub fn sumcheck_repetitions<F: PrimeField>(
+    self,
+    num_rounds: usize,
+    degree: usize,
+  ) -> Option<usize> {
+    match Self::sumcheck_bits::<F>(num_rounds, degree) {
+      0 => None,
+      bits => Some(self.bits().div_ceil(bits)),
+    }
+  }
+
+  /// Checks that a single sum-check over `F` with `num_rounds` rounds of
+  /// degree `degree` reaches the level
+  pub fn check_sumcheck<F: PrimeField>(
+    self,
+    num_rounds: usize,
+    degree: usize,
+  ) -> Result<(), NovaError> {
+    self.check_sumcheck_over(F::CAPACITY as usize, num_rounds, degree)
+  }
+
+  /// Same as [`SecurityLevel::check_sumcheck`], over a field of more than
+  /// `2^field_bits` elements
+  pub fn check_sumcheck_over(
+    self,
+    field_bits: usize,
+    num_rounds: usize,
+    degree: usize,
+  ) -> Result<(), NovaError> {
+    let bits = Self::sumcheck_bits_over(field_bits, num_rounds, degree);
+    if bits < self.bits() {
+      return Err(NovaError::InsufficientSecurity(bits));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use halo2curves::bn256;
+
+  use super::*;
+
+  #[test]
+  fn test_security_level() {
+    // a single challenge in a 254-bit field reaches either level
+    assert_eq!(SecurityLevel::Bits128.challenge_repetitions::<bn256::Scalar>(), 1);
+    assert_eq!(SecurityLevel::Bits128.sumcheck_repetitions::<bn256::Scalar>(30, 3), Some(1));
+    SecurityLevel::Bits128.check_sumcheck::<bn256::Scalar>(30, 3).unwrap();
+
+    // the soundness error of 30 rounds of degree 3 costs 7 bits
+    assert_eq!(SecurityLevel::sumcheck_bits::<bn256::Scalar>(30, 3), 253 - 7);
+    assert_eq!(SecurityLevel::sumcheck_bits::<bn256::Scalar>(0, 3), 253);
+    assert_eq!(SecurityLevel::sumcheck_bits::<bn256::Scalar>(1, 1), 253);
+  }
+}
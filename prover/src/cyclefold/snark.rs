@@ -24,6 +24,7 @@ use crate::{
   cyclefold::circuit::CycleFoldCircuit,
   errors::NovaError,
   gadgets::scalar_as_base,
+  parallel,
   r1cs::{
     self, CommitmentKeyHint, R1CSInstance, R1CSResult, R1CSWitness, RelaxedR1CSInstance,
     RelaxedR1CSWitness,
@@ -108,6 +109,8 @@ where E1: CurveCycleEquipped
   }
 
   /// Calculate the digest of the public parameters.
+  // serializing the parameters into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E1::Scalar {
     self
       .digest
@@ -273,7 +276,7 @@ where E1: CurveCycleEquipped
       .map(|b| Some(*b))
       .take(NUM_CHALLENGE_BITS)
       .collect::<Option<Vec<_>>>()
-      .map(|v| v.try_into().unwrap());
+      .and_then(|v| v.try_into().ok());
 
     let comm_T = Commitment::<E1>::decompress(&nifs_primary.comm_T)?;
     let E_new = self.r_U_primary.comm_E + comm_T * r;
@@ -455,7 +458,7 @@ where E1: CurveCycleEquipped
 
     // Verify the satisfiability of running relaxed instances, and the final primary
     // instance.
-    let (res_r_primary, (res_l_primary, res_r_cyclefold)) = rayon::join(
+    let (res_r_primary, (res_l_primary, res_r_cyclefold)) = parallel::join(
       || {
         pp.circuit_shape_primary.r1cs_shape.is_sat_relaxed(
           &pp.ck_primary,
@@ -464,7 +467,7 @@ where E1: CurveCycleEquipped
         )
       },
       || {
-        rayon::join(
+        parallel::join(
           || {
             pp.circuit_shape_primary.r1cs_shape.is_sat(
               &pp.ck_primary,
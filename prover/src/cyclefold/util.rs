@@ -23,8 +23,14 @@ pub(super) fn absorb_primary_commitment<E1, E2>(
 {
   let (x, y, is_infinity) = comm.to_coordinates();
 
-  let x_limbs = nat_to_limbs(&f_to_nat(&x), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
-  let y_limbs = nat_to_limbs(&f_to_nat(&y), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
+  // a field element is narrower than its limbs
+  #[allow(clippy::expect_used)]
+  let x_limbs = nat_to_limbs(&f_to_nat(&x), BN_LIMB_WIDTH, BN_N_LIMBS)
+    .expect("a field element fits in the limbs");
+  // a field element is narrower than its limbs
+  #[allow(clippy::expect_used)]
+  let y_limbs = nat_to_limbs(&f_to_nat(&y), BN_LIMB_WIDTH, BN_N_LIMBS)
+    .expect("a field element fits in the limbs");
 
   for limb in x_limbs {
     ro.absorb(scalar_as_base::<E2>(limb));
@@ -55,7 +61,10 @@ pub(super) fn absorb_primary_r1cs<E1, E2>(
 pub(super) fn absorb_cyclefold_r1cs<E: Engine>(u: &R1CSInstance<E>, ro: &mut E::RO) {
   u.comm_W.absorb_in_ro(ro);
   u.X.iter().for_each(|x| {
-    let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
+    // a field element is narrower than its limbs
+    #[allow(clippy::expect_used)]
+    let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS)
+      .expect("a field element fits in the limbs");
     limbs.into_iter().for_each(|limb| ro.absorb(scalar_as_base::<E>(limb)));
   });
 }
@@ -0,0 +1,336 @@
+//! A long-running prover, serving jobs over a unix socket, so that the
+//! parameters and keys of an application are loaded once and stay warm in
+//! memory across the proving tasks that it runs.
+//!
+//! The protocol is JSON-RPC 2.0, one JSON object per line: a client writes a
+//! request `{"jsonrpc": "2.0", "id": 1, "method": "prove", "params": {..}}`
+//! and reads the response of the same `id`, its `result` or its `error`. The
+//! methods are those of the [`Handler`] of the daemon, e.g. `prove` and
+//! `verify` jobs on the parameters it holds, but for `shutdown`, which stops
+//! the daemon once it answered. A connection runs its requests in order, and
+//! each connection has its own thread, so that a slow job does not hold the
+//! other clients.
+//!
+//! A [`Client`] is the other side of the socket, for applications in Rust.
+use std::{
+  io::{self, BufRead, BufReader, Write},
+  os::unix::net::{UnixListener, UnixStream},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::errors::NovaError;
+
+/// The version of JSON-RPC of the protocol
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// The method that stops the daemon
+pub const SHUTDOWN_METHOD: &str = "shutdown";
+
+/// The code of a request that is not valid JSON
+pub const PARSE_ERROR: i64 = -32700;
+
+/// The code of a JSON value that is not a valid request
+pub const INVALID_REQUEST: i64 = -32600;
+
+/// The code of a method the daemon does not serve
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The code of parameters the method does not accept
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// The code of a job that failed
+pub const JOB_FAILED: i64 = -32000;
+
+/// A request to the daemon
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+  /// The version of JSON-RPC, [`JSONRPC_VERSION`]
+  pub jsonrpc: String,
+  /// The id of the request, repeated in its response
+  pub id:      Value,
+  /// The method of the request
+  pub method:  String,
+  /// The parameters of the method
+  #[serde(default)]
+  pub params:  Value,
+}
+
+/// The error of a request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+  /// The code of the error, one of the codes of this module
+  pub code:    i64,
+  /// The description of the error
+  pub message: String,
+}
+
+impl RpcError {
+  /// The error of code `code` described by `message`
+  pub fn new(code: i64, message: impl Into<String>) -> Self {
+    Self { code, message: message.into() }
+  }
+
+  /// The error of parameters the method does not accept
+  pub fn invalid_params(message: impl Into<String>) -> Self { Self::new(INVALID_PARAMS, message) }
+}
+
+impl From<NovaError> for RpcError {
+  fn from(err: NovaError) -> Self { Self::new(JOB_FAILED, err.to_string()) }
+}
+
+impl std::fmt::Display for RpcError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} (code {})", self.message, self.code)
+  }
+}
+
+impl std::error::Error for RpcError {}
+
+/// The response to a request
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+  /// The version of JSON-RPC, [`JSONRPC_VERSION`]
+  pub jsonrpc: String,
+  /// The id of the request, or `null` if it could not be read
+  pub id:      Value,
+  /// The result of the request, if it succeeded
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub result:  Option<Value>,
+  /// The error of the request, if it failed
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error:   Option<RpcError>,
+}
+
+impl Response {
+  fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+    let (result, error) = match outcome {
+      Ok(result) => (Some(result), None),
+      Err(error) => (None, Some(error)),
+    };
+    Self { jsonrpc: JSONRPC_VERSION.to_string(), id, result, error }
+  }
+}
+
+/// The jobs a daemon serves, on the state it holds warm
+pub trait Handler: Send + Sync + 'static {
+  /// Runs the job of `method` on `params`, and returns its result, or an
+  /// error of code [`METHOD_NOT_FOUND`] for a method it does not serve
+  fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+/// A daemon bound to a unix socket
+#[derive(Debug)]
+pub struct Daemon {
+  listener: UnixListener,
+  path:     PathBuf,
+}
+
+impl Daemon {
+  /// Binds a daemon to the socket at `path`, which replaces the socket a
+  /// previous daemon left there
+  pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref().to_path_buf();
+    if path.exists() && UnixStream::connect(&path).is_err() {
+      std::fs::remove_file(&path)?;
+    }
+    Ok(Self { listener: UnixListener::bind(&path)?, path })
+  }
+
+  /// The path of the socket of the daemon
+  pub fn path(&self) -> &Path { &self.path }
+
+  /// Serves the requests of clients with `handler`, until a client requests
+  /// a [`SHUTDOWN_METHOD`], then removes the socket
+  pub fn serve<H: Handler>(self, handler: Arc<H>) -> io::Result<()> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    for stream in self.listener.incoming() {
+      if shutdown.load(Ordering::SeqCst) {
+        break;
+      }
+      let stream = match stream {
+        Ok(stream) => stream,
+        Err(err) => {
+          warn!("failed to accept a connection: {err}");
+          continue;
+        },
+      };
+      let (handler, shutdown, path) = (handler.clone(), shutdown.clone(), self.path.clone());
+      thread::spawn(move || {
+        if let Err(err) = serve_connection(stream, &*handler, &shutdown, &path) {
+          debug!("connection closed: {err}");
+        }
+      });
+    }
+    std::fs::remove_file(&self.path)
+  }
+}
+
+/// Answers the requests of a connection, one line at a time
+fn serve_connection<H: Handler>(
+  stream: UnixStream,
+  handler: &H,
+  shutdown: &AtomicBool,
+  path: &Path,
+) -> io::Result<()> {
+  let mut writer = stream.try_clone()?;
+  for line in BufReader::new(stream).lines() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let (response, stop) = respond(handler, &line);
+    writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+    writer.flush()?;
+    if stop {
+      shutdown.store(true, Ordering::SeqCst);
+      // wakes the accepting loop, which then sees the flag
+      let _ = UnixStream::connect(path);
+      break;
+    }
+  }
+  Ok(())
+}
+
+/// The response to the request `line`, and whether it stops the daemon
+fn respond<H: Handler>(handler: &H, line: &str) -> (Response, bool) {
+  let value = match serde_json::from_str::<Value>(line) {
+    Ok(value) => value,
+    Err(err) =>
+      return (Response::new(Value::Null, Err(RpcError::new(PARSE_ERROR, err.to_string()))), false),
+  };
+  let id = value.get("id").cloned().unwrap_or(Value::Null);
+  let request = match serde_json::from_value::<Request>(value) {
+    Ok(request) if request.jsonrpc == JSONRPC_VERSION => request,
+    Ok(_) =>
+      return (Response::new(id, Err(RpcError::new(INVALID_REQUEST, "unsupported version"))), false),
+    Err(err) =>
+      return (Response::new(id, Err(RpcError::new(INVALID_REQUEST, err.to_string()))), false),
+  };
+  if request.method == SHUTDOWN_METHOD {
+    return (Response::new(request.id, Ok(Value::Null)), true);
+  }
+  debug!("running job {}", request.method);
+  (Response::new(request.id, handler.handle(&request.method, request.params)), false)
+}
+
+/// A client of a daemon
+#[derive(Debug)]
+pub struct Client {
+  reader:  BufReader<UnixStream>,
+  writer:  UnixStream,
+  next_id: u64,
+}
+
+impl Client {
+  /// Connects to the daemon of the socket at `path`
+  pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+    let writer = UnixStream::connect(path)?;
+    Ok(Self { reader: BufReader::new(writer.try_clone()?), writer, next_id: 0 })
+  }
+
+  /// Runs the job of `method` on `params`, and returns its result, or the
+  /// error of the daemon
+  pub fn call(&mut self, method: &str, params: Value) -> io::Result<Result<Value, RpcError>> {
+    self.next_id += 1;
+    let request = Request {
+      jsonrpc: JSONRPC_VERSION.to_string(),
+      id: Value::from(self.next_id),
+      method: method.to_string(),
+      params,
+    };
+    writeln!(self.writer, "{}", serde_json::to_string(&request)?)?;
+    self.writer.flush()?;
+
+    let mut line = String::new();
+    if self.reader.read_line(&mut line)? == 0 {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let response: Response = serde_json::from_str(&line)?;
+    if response.id != request.id {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "response to another request"));
+    }
+    Ok(match (response.result, response.error) {
+      (_, Some(error)) => Err(error),
+      (result, None) => Ok(result.unwrap_or(Value::Null)),
+    })
+  }
+
+  /// Stops the daemon
+  pub fn shutdown(mut self) -> io::Result<()> {
+    self.call(SHUTDOWN_METHOD, Value::Null)?.map_err(io::Error::other)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::AtomicUsize;
+
+  use super::*;
+
+  /// A handler that adds numbers, and counts the jobs it ran
+  #[derive(Default)]
+  struct Adder {
+    jobs: AtomicUsize,
+  }
+
+  impl Handler for Adder {
+    fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+      self.jobs.fetch_add(1, Ordering::SeqCst);
+      match method {
+        "add" => {
+          let terms: Vec<u64> = serde_json::from_value(params)
+            .map_err(|err| RpcError::invalid_params(err.to_string()))?;
+          Ok(Value::from(terms.iter().sum::<u64>()))
+        },
+        "fail" => Err(NovaError::ProofVerifyError.into()),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, method)),
+      }
+    }
+  }
+
+  #[test]
+  fn test_daemon() {
+    let path = std::env::temp_dir().join(format!("edge-daemon-{}.sock", std::process::id()));
+    let daemon = Daemon::bind(&path).unwrap();
+    let handler = Arc::new(Adder::default());
+    let server = thread::spawn({
+      let handler = handler.clone();
+      move || daemon.serve(handler)
+    });
+
+    // the daemon holds its state across clients and jobs
+    let mut client = Client::connect(&path).unwrap();
+    assert_eq!(client.call("add", serde_json::json!([1, 2, 3])).unwrap(), Ok(Value::from(6)));
+    let mut other = Client::connect(&path).unwrap();
+    assert_eq!(other.call("add", serde_json::json!([4])).unwrap(), Ok(Value::from(4)));
+    assert_eq!(handler.jobs.load(Ordering::SeqCst), 2);
+
+    // errors are answered, and the connection stays open
+    assert_eq!(
+      client.call("add", serde_json::json!("one")).unwrap().unwrap_err().code,
+      INVALID_PARAMS
+    );
+    assert_eq!(client.call("fail", Value::Null).unwrap().unwrap_err().code, JOB_FAILED);
+    assert_eq!(client.call("mul", Value::Null).unwrap().unwrap_err().code, METHOD_NOT_FOUND);
+
+    let (response, _) = respond(&*handler, "{");
+    assert_eq!(response.error.unwrap().code, PARSE_ERROR);
+    let (response, _) = respond(&*handler, r#"{"jsonrpc": "1.0", "id": 7, "method": "add"}"#);
+    assert_eq!((response.id, response.error.unwrap().code), (Value::from(7), INVALID_REQUEST));
+
+    client.shutdown().unwrap();
+    server.join().unwrap().unwrap();
+    assert!(!path.exists());
+    assert!(Client::connect(&path).is_err());
+  }
+}
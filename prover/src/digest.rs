@@ -1,4 +1,24 @@
-use std::{io, marker::PhantomData};
+//! Digests of the public parameters, circuit shapes and keys.
+//!
+//! A digest is computed in the same way for every [`Digestible`] value, and
+//! deployed verifiers pin it (e.g. `PublicParams::digest`, `R1CSShape::digest`
+//! or `VerifierKey::digest`), so the following is part of the stable interface
+//! of the crate:
+//! - the value is encoded with bincode, with little-endian, fixed-width integers, every sequence
+//!   being prefixed with its length as a `u64`, and every field of a struct being encoded in
+//!   declaration order, except the cached digests which are skipped;
+//! - the encoding is hashed with SHA3-256;
+//! - the first [`NUM_HASH_BITS`] bits of the hash, taken from the least significant bit of its
+//!   first byte up, are the little-endian bits of the digest, as a scalar.
+//!
+//! Changing any of them, or the layout of a digested type, changes the
+//! digests: the golden tests below catch it, so that it only happens in a
+//! release that says so.
+//!
+//! The same encoding hashed with Blake3 is the content hash of the value,
+//! which addresses it in content-addressed stores, e.g. to fetch parameters
+//! by their hash; verifiers pin the digest, not the content hash.
+use std::{fmt::Write, io, marker::PhantomData};
 
 use bincode::Options;
 use ff::PrimeField;
@@ -28,6 +48,17 @@ impl<T: SimpleDigestible> Digestible for T {
   }
 }
 
+/// The hexadecimal encoding of the canonical representation of a digest, to
+/// publish or pin it
+pub fn digest_hex<F: PrimeField>(digest: &F) -> String {
+  digest.to_repr().as_ref().iter().fold(String::new(), |mut output, b| {
+    let _ = write!(output, "{b:02x}");
+    output
+  })
+}
+
+/// Computes the digest of a `Digestible` value, as described in the module
+/// documentation
 pub struct DigestComputer<'a, F, T> {
   inner:    &'a T,
   _phantom: PhantomData<F>,
@@ -65,16 +96,29 @@ impl<'a, F: PrimeField, T: Digestible> DigestComputer<'a, F, T> {
     let bytes: [u8; 32] = hasher.finalize().into();
     Ok(Self::map_to_field(&bytes))
   }
+
+  /// Compute the content hash of a `Digestible` instance, the Blake3 hash of
+  /// its encoding
+  pub fn content_hash(&self) -> Result<[u8; 32], io::Error> {
+    let mut hasher = blake3::Hasher::new();
+    self.inner.write_bytes(&mut hasher)?;
+    Ok(hasher.finalize().into())
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use expect_test::expect;
   use ff::Field;
   use once_cell::sync::OnceCell;
   use serde::{Deserialize, Serialize};
 
-  use super::{DigestComputer, SimpleDigestible};
-  use crate::{provider::GrumpkinEngine, traits::Engine};
+  use super::{digest_hex, DigestComputer, SimpleDigestible};
+  use crate::{
+    provider::{Bn256EngineIPA, GrumpkinEngine},
+    r1cs::{sparse::SparseMatrix, R1CSShape},
+    traits::Engine,
+  };
 
   type E = GrumpkinEngine;
 
@@ -133,4 +177,36 @@ mod tests {
     let retrieved_s: S<E> = bincode::deserialize(&naughty_bytes).unwrap();
     assert_eq!(good_s.digest(), retrieved_s.digest())
   }
+
+  #[test]
+  fn test_content_hash() {
+    // the content hash is Blake3 of the encoding the digest hashes
+    let (s, other) = (S::<E>::new(42), S::<E>::new(43));
+    let computer = DigestComputer::<<E as Engine>::Scalar, _>::new(&s);
+    assert_eq!(computer.content_hash().unwrap(), *blake3::hash(&42u64.to_le_bytes()).as_bytes());
+    let other = DigestComputer::<<E as Engine>::Scalar, _>::new(&other);
+    assert_ne!(computer.content_hash().unwrap(), other.content_hash().unwrap());
+  }
+
+  #[test]
+  fn test_digest_golden() {
+    // the digest of a `u64`, which pins the hash and the mapping to the field
+    expect!["8406f502d31e02131d063a7ba816bfbe61ffcef6c516a612cac2f32eb0495302"]
+      .assert_eq(&digest_hex(&S::<E>::new(42).digest()));
+
+    // the digest of a shape, which pins its layout and that of its matrices
+    let empty =
+      || SparseMatrix { data: vec![], indices: vec![], indptr: vec![0, 0], cols: 3 };
+    let shape = R1CSShape::<Bn256EngineIPA> {
+      num_cons: 1,
+      num_vars: 1,
+      num_io:   1,
+      A:        empty(),
+      B:        empty(),
+      C:        empty(),
+      digest:   OnceCell::new(),
+    };
+    expect!["82084b8fb1175eb6538f5ee1b0b1f2fb31c8981eed5e746b8128e0c0ba5cc301"]
+      .assert_eq(&digest_hex(&shape.digest()));
+  }
 }
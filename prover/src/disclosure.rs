@@ -0,0 +1,195 @@
+//! Selective disclosure of the fields of a committed record.
+//!
+//! A [`Record`] of named fields, e.g. the parsed headers of an HTTP response,
+//! is committed to by the Merkle root of its salted fields: each leaf is the
+//! hash of the name, the value and a random salt of a field. The commitment
+//! is meant to be part of the public IO of a proof about the record, e.g. the
+//! final `z` of a `RecursiveSNARK` that parsed it, with `PoseidonHasher`
+//! roots being field elements. A holder of the [`RecordOpener`] then
+//! discloses single fields with [`Disclosure`]s, whose Merkle paths verify
+//! against the commitment alone, without verifying the proof again, and
+//! whose salts keep the other fields hidden.
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  provider::merkle::{
+    MerkleCommitment, MerkleCommitmentEngine, MerkleCommitmentKey, MerkleHasher, MerkleOpening,
+    MerkleTree,
+  },
+  traits::{Engine, PrimeFieldExt},
+};
+
+/// A record of named fields, whose names are distinct
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+  fields: Vec<(String, Vec<u8>)>,
+}
+
+impl Record {
+  /// The record of `fields`, in order
+  pub fn new(fields: Vec<(String, Vec<u8>)>) -> Result<Self, NovaError> {
+    for (i, (name, _)) in fields.iter().enumerate() {
+      if fields[..i].iter().any(|(n, _)| n == name) {
+        return Err(NovaError::InvalidIndex);
+      }
+    }
+    Ok(Self { fields })
+  }
+
+  /// The fields of the record
+  pub fn fields(&self) -> &[(String, Vec<u8>)] { &self.fields }
+
+  /// The number of fields of the record
+  pub fn len(&self) -> usize { self.fields.len() }
+
+  /// Whether the record has no fields
+  pub fn is_empty(&self) -> bool { self.fields.is_empty() }
+}
+
+/// The leaf of the field `name` with `value` salted by `salt`: the bytes of
+/// the field, length-prefixed, hashed to the scalar field
+fn leaf<E: Engine>(name: &str, value: &[u8], salt: &[u8; 32]) -> E::Scalar {
+  let mut hasher = blake3::Hasher::new_derive_key("edge-prover record field");
+  hasher.update(&(name.len() as u64).to_le_bytes());
+  hasher.update(name.as_bytes());
+  hasher.update(&(value.len() as u64).to_le_bytes());
+  hasher.update(value);
+  hasher.update(salt);
+  let mut uniform = [0u8; 64];
+  hasher.finalize_xof().fill(&mut uniform);
+  E::Scalar::from_uniform(&uniform)
+}
+
+/// The commitment to a record
+pub type RecordCommitment<E, H> =
+  MerkleCommitment<<H as MerkleHasher<<E as Engine>::Scalar>>::Digest>;
+
+/// The salts and the tree of a committed record, kept by its holder to
+/// disclose its fields
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecordOpener<E: Engine, H: MerkleHasher<E::Scalar>> {
+  record: Record,
+  salts:  Vec<[u8; 32]>,
+  tree:   MerkleTree<H::Digest>,
+}
+
+/// Commits to `record` under `ck`, with salts drawn from `rng`
+pub fn commit_record<E: Engine, H: MerkleHasher<E::Scalar>>(
+  ck: &MerkleCommitmentKey<E::Scalar, H>,
+  record: Record,
+  mut rng: impl RngCore + CryptoRng,
+) -> Result<(RecordCommitment<E, H>, RecordOpener<E, H>), NovaError> {
+  let salts = (0..record.len())
+    .map(|_| {
+      let mut salt = [0u8; 32];
+      rng.fill_bytes(&mut salt);
+      salt
+    })
+    .collect::<Vec<_>>();
+  let leaves = record
+    .fields
+    .iter()
+    .zip(&salts)
+    .map(|((name, value), salt)| leaf::<E>(name, value, salt))
+    .collect::<Vec<_>>();
+  let (comm, tree) = MerkleCommitmentEngine::<E, H>::commit_with_tree(ck, &leaves)?;
+  Ok((comm, RecordOpener { record, salts, tree }))
+}
+
+impl<E: Engine, H: MerkleHasher<E::Scalar>> RecordOpener<E, H> {
+  /// The committed record
+  pub fn record(&self) -> &Record { &self.record }
+
+  /// Discloses the field `name`
+  pub fn disclose(&self, name: &str) -> Result<Disclosure<E, H>, NovaError> {
+    let index =
+      self.record.fields.iter().position(|(n, _)| n == name).ok_or(NovaError::InvalidIndex)?;
+    Ok(Disclosure {
+      name:    name.into(),
+      value:   self.record.fields[index].1.clone(),
+      salt:    self.salts[index],
+      opening: self.tree.open(index)?,
+    })
+  }
+}
+
+/// A field of a committed record, disclosed with its path to the commitment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Disclosure<E: Engine, H: MerkleHasher<E::Scalar>> {
+  /// the name of the field
+  pub name:  String,
+  /// the value of the field
+  pub value: Vec<u8>,
+  salt:      [u8; 32],
+  opening:   MerkleOpening<H::Digest>,
+}
+
+impl<E: Engine, H: MerkleHasher<E::Scalar>> Disclosure<E, H> {
+  /// The index of the field in the record
+  pub fn index(&self) -> usize { self.opening.index() }
+
+  /// Verifies that the field is in the record committed to by `comm`
+  pub fn verify(
+    &self,
+    ck: &MerkleCommitmentKey<E::Scalar, H>,
+    comm: &RecordCommitment<E, H>,
+  ) -> Result<(), NovaError> {
+    let leaf = leaf::<E>(&self.name, &self.value, &self.salt);
+    MerkleCommitmentEngine::<E, H>::verify_opening(ck, comm, &leaf, &self.opening)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::{
+    merkle::{Blake3Hasher, PoseidonHasher},
+    Bn256EngineKZG,
+  };
+
+  type E = Bn256EngineKZG;
+
+  fn test_disclosure_with<H: MerkleHasher<<E as Engine>::Scalar>>() {
+    let record = Record::new(vec![
+      ("content-type".into(), b"application/json".to_vec()),
+      ("date".into(), b"Wed, 14 Oct 2026 10:00:00 GMT".to_vec()),
+      ("server".into(), b"nginx".to_vec()),
+    ])
+    .unwrap();
+    let ck = MerkleCommitmentEngine::<E, H>::setup(b"record", 4);
+    let (comm, opener) = commit_record::<E, H>(&ck, record.clone(), OsRng).unwrap();
+
+    for (i, (name, value)) in record.fields().iter().enumerate() {
+      let disclosure = opener.disclose(name).unwrap();
+      assert_eq!((disclosure.index(), &disclosure.value), (i, value));
+      assert_eq!(disclosure.verify(&ck, &comm), Ok(()));
+    }
+
+    // a disclosure binds the name and the value of its field
+    let mut wrong = opener.disclose("server").unwrap();
+    wrong.value = b"apache".to_vec();
+    assert!(wrong.verify(&ck, &comm).is_err());
+    let mut renamed = opener.disclose("server").unwrap();
+    renamed.name = "date".into();
+    assert!(renamed.verify(&ck, &comm).is_err());
+
+    // the salts hide the record: the same record commits differently
+    let (other, _) = commit_record::<E, H>(&ck, record, OsRng).unwrap();
+    assert_ne!(comm, other);
+
+    assert!(opener.disclose("cookie").is_err());
+    assert!(Record::new(vec![("a".into(), vec![]), ("a".into(), vec![1])]).is_err());
+  }
+
+  #[test]
+  fn test_disclosure() {
+    test_disclosure_with::<Blake3Hasher>();
+    test_disclosure_with::<PoseidonHasher<<E as Engine>::Scalar>>();
+  }
+}
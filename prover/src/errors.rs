@@ -78,6 +78,92 @@ pub enum NovaError {
   /// completeness error
   #[error("InternalError")]
   InternalError,
+  /// returned in `debug-fold` mode when folding two satisfying instances
+  /// yields an unsatisfying one, with the failing constraint rows
+  #[error("InvalidFold: unsatisfied rows {0:?}")]
+  InvalidFold(Vec<usize>),
+  /// returned when a setup is interrupted by its `SetupProgress`
+  #[error("Interrupted")]
+  Interrupted,
+  /// returned when a commitment key or its setup log does not match the log,
+  /// with the first chunk of generators that differs, if any
+  #[error("InvalidSetupLog: chunk {0:?}")]
+  InvalidSetupLog(Option<usize>),
+  /// returned when a protocol over the scalar field of an engine does not
+  /// reach the security level of the engine, with the number of bits it
+  /// reaches
+  #[error("InsufficientSecurity: {0} bits")]
+  InsufficientSecurity(usize),
+  /// returned when challenges of a small-field protocol are sampled in a
+  /// field too wide for it, with the number of bits of the field
+  #[error("UnsupportedField: {0} bits")]
+  UnsupportedField(u32),
+  /// returned when the lookups of an incremental computation are not into
+  /// their tables, or do not match its lookup accumulators
+  #[error("InvalidLookups")]
+  InvalidLookups,
+  /// returned when the accesses of an incremental computation to its memory
+  /// do not match its memory accumulators
+  #[error("InvalidMemoryAccesses")]
+  InvalidMemoryAccesses,
+  /// returned when a proof cannot be encoded or decoded, e.g. for inspection
+  #[error("SerializationError: {0}")]
+  SerializationError(String),
+  /// returned when a segment of a witness is unknown, duplicated, or does
+  /// not open to its commitment, by its name
+  #[error("InvalidSegment: {0}")]
+  InvalidSegment(String),
+  /// returned when a certificate is outside the schema a circuit parses, by
+  /// the field that is
+  #[error("InvalidCertificate: {0}")]
+  InvalidCertificate(String),
+  /// returned when a regular expression is outside the syntax the regex
+  /// circuits compile, or an input does not match it, with the reason
+  #[error("InvalidRegex: {0}")]
+  InvalidRegex(String),
+  /// returned when a claim is malformed, or does not hold of its value, with
+  /// the reason
+  #[error("InvalidClaim: {0}")]
+  InvalidClaim(String),
+  /// returned when a manifest describes no task the circuits prove, with
+  /// the reason
+  #[error("InvalidManifest: {0}")]
+  InvalidManifest(String),
+  /// returned when the advice of a step does not decode, or is above the
+  /// bound of its circuit, with the reason
+  #[error("InvalidAdvice: {0}")]
+  InvalidAdvice(String),
+  /// returned when the bytes of a scalar are not those of an integer below
+  /// the modulus of its field
+  #[error("NonCanonicalScalar")]
+  NonCanonicalScalar,
+  /// returned when a proof is addressed to a verifier key that is not held
+  #[error("UnknownVerifierKey")]
+  UnknownVerifierKey,
+  /// returned when a remote prover cannot be reached, answers outside the
+  /// protocol, or fails a job, with the reason
+  #[error("RemoteProverError: {0}")]
+  RemoteProverError(String),
+  /// returned when serialized bytes are of a format version this version of
+  /// the crate does not read, with that version
+  #[error("UnsupportedFormatVersion: {0}")]
+  UnsupportedFormatVersion(u32),
+  /// returned when the attestation a proof carries is not the one whose
+  /// digest is among its public inputs
+  #[error("InvalidAttestation")]
+  InvalidAttestation,
+  /// returned when a proof is not of a challenge its verifier issued, or of
+  /// one that expired or was redeemed
+  #[error("StaleChallenge")]
+  StaleChallenge,
+  /// returned when parameters are set up for a commitment backend that does
+  /// not fold, or whose decision does not bind their digest
+  #[error("UnsupportedBackend")]
+  UnsupportedBackend,
+  /// returned when the outputs of a gadget on an input depend on its
+  /// padding, with the padding and the reason
+  #[error("InvalidPadding: {0}")]
+  InvalidPadding(String),
 }
 
 /// Errors specific to the Polynomial commitment scheme
@@ -94,6 +180,7 @@ pub enum PCSError {
   LengthError,
 }
 
+#[cfg(feature = "prove")]
 impl From<bellpepper_core::SynthesisError> for NovaError {
   fn from(err: bellpepper_core::SynthesisError) -> Self { Self::SynthesisError(err.to_string()) }
 }
@@ -0,0 +1,229 @@
+//! Freshness of proofs: binding a challenge of the verifier into the public
+//! IO of a proof, so that a proof made for an old challenge is not replayed.
+//!
+//! A verifier issues a [`Challenge`] with a [`ChallengeIssuer`], a random
+//! nonce and the time it was issued at, and sends it to the client. By
+//! convention, the client proves from an initial `z` of the primary circuit
+//! whose first element is the [`Challenge::scalar`], carried unchanged by the
+//! steps with [`carry_challenge`], so that it is also the first output. A
+//! proof is made from its initial `z`, so one made before the challenge was
+//! known does not verify with it.
+//! [`CompressedSNARK::verify_fresh`](crate::CompressedSNARK::verify_fresh)
+//! checks that the initial `z` holds the challenge, and redeems it with the
+//! issuer: a challenge is only redeemed once, before it expires.
+//!
+//! The times are seconds, e.g. since the Unix epoch, given by the caller, so
+//! that the verifier runs where there is no clock.
+use std::collections::HashMap;
+
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  traits::{snark::RelaxedR1CSSNARKTrait, CurveCycleEquipped, Dual, Engine, PrimeFieldExt},
+  CompressedSNARK, VerifierKey,
+};
+
+/// A challenge of a verifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Challenge {
+  /// the random nonce of the challenge
+  pub nonce:     [u8; 32],
+  /// the time the challenge was issued at, in seconds
+  pub issued_at: u64,
+}
+
+impl Challenge {
+  /// The challenge as a scalar, the first element of the initial `z` of the
+  /// primary circuit of the proofs made for it
+  pub fn scalar<F: PrimeFieldExt>(&self) -> F {
+    let mut hasher = blake3::Hasher::new_derive_key("edge-prover freshness challenge");
+    hasher.update(&self.nonce);
+    hasher.update(&self.issued_at.to_le_bytes());
+    let mut uniform = [0u8; 64];
+    hasher.finalize_xof().fill(&mut uniform);
+    F::from_uniform(&uniform)
+  }
+}
+
+/// The challenges a verifier issued and has not redeemed
+#[derive(Clone, Debug, Default)]
+pub struct ChallengeIssuer {
+  ttl:         u64,
+  outstanding: HashMap<[u8; 32], u64>,
+}
+
+impl ChallengeIssuer {
+  /// An issuer of challenges that expire `ttl` seconds after they are issued
+  pub fn new(ttl: u64) -> Self { Self { ttl, outstanding: HashMap::new() } }
+
+  /// Issues a challenge at the time `now`, with a nonce drawn from `rng`
+  pub fn issue(&mut self, mut rng: impl RngCore + CryptoRng, now: u64) -> Challenge {
+    let mut nonce = [0u8; 32];
+    rng.fill_bytes(&mut nonce);
+    self.outstanding.insert(nonce, now);
+    Challenge { nonce, issued_at: now }
+  }
+
+  /// Redeems `challenge` at the time `now`. Fails with
+  /// [`NovaError::StaleChallenge`] unless the issuer issued it, it has not
+  /// been redeemed, and it has not expired.
+  pub fn redeem(&mut self, challenge: &Challenge, now: u64) -> Result<(), NovaError> {
+    match self.outstanding.get(&challenge.nonce) {
+      Some(issued_at) if *issued_at == challenge.issued_at => {
+        self.outstanding.remove(&challenge.nonce);
+        if now.saturating_sub(challenge.issued_at) > self.ttl {
+          return Err(NovaError::StaleChallenge);
+        }
+        Ok(())
+      },
+      _ => Err(NovaError::StaleChallenge),
+    }
+  }
+
+  /// Forgets the challenges that expired by the time `now`
+  pub fn prune(&mut self, now: u64) {
+    let ttl = self.ttl;
+    self.outstanding.retain(|_, issued_at| now.saturating_sub(*issued_at) <= ttl);
+  }
+
+  /// The number of challenges issued and not redeemed
+  pub fn outstanding(&self) -> usize { self.outstanding.len() }
+}
+
+/// Outputs the challenge of the initial `z` of the primary circuit, its
+/// first element, unchanged, as the first output of a step
+#[cfg(feature = "prove")]
+pub fn carry_challenge<F: ff::PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  z: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let challenge = z.first().ok_or(SynthesisError::Unsatisfiable)?;
+  let output = AllocatedNum::alloc(cs.namespace(|| "challenge"), || {
+    challenge.get_value().ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(
+    || "challenge is carried",
+    |lc| lc + output.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + challenge.get_variable(),
+  );
+  Ok(output)
+}
+
+impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  /// Same as [`CompressedSNARK::verify`], for a proof made for `challenge`,
+  /// redeemed with `issuer` at the time `now`: fails with
+  /// [`NovaError::StaleChallenge`] unless the first of `z0_primary` is the
+  /// challenge, and the issuer redeems it. The challenge is redeemed before
+  /// the proof is verified, so that a proof that does not verify spends it.
+  #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+  pub fn verify_fresh(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    challenge: &Challenge,
+    issuer: &mut ChallengeIssuer,
+    now: u64,
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError> {
+    if z0_primary.first() != Some(&challenge.scalar()) {
+      return Err(NovaError::StaleChallenge);
+    }
+    issuer.redeem(challenge, now)?;
+    self.verify(vk, num_steps, z0_primary, z0_secondary)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, Bn256EngineIPA},
+    spartan::snark,
+    supernova::TrivialCircuit,
+    PublicParams, RecursiveSNARK,
+  };
+
+  type E1 = Bn256EngineIPA;
+  type E2 = Dual<E1>;
+  type S<E> = snark::RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  #[test]
+  fn test_challenge_issuer() {
+    let mut issuer = ChallengeIssuer::new(60);
+    let challenge = issuer.issue(OsRng, 1000);
+    let other = issuer.issue(OsRng, 1000);
+    assert_ne!(challenge.scalar::<<E1 as Engine>::Scalar>(), other.scalar());
+    assert_eq!(issuer.outstanding(), 2);
+
+    // a challenge is redeemed once, before it expires
+    issuer.redeem(&challenge, 1060).unwrap();
+    assert_eq!(issuer.redeem(&challenge, 1060), Err(NovaError::StaleChallenge));
+    assert_eq!(issuer.redeem(&other, 1061), Err(NovaError::StaleChallenge));
+    let forged = Challenge { issued_at: 1050, ..issuer.issue(OsRng, 1000) };
+    assert_eq!(issuer.redeem(&forged, 1050), Err(NovaError::StaleChallenge));
+
+    issuer.prune(1061);
+    assert_eq!(issuer.outstanding(), 0);
+  }
+
+  #[test]
+  fn test_verify_fresh() {
+    let circuit = TrivialCircuit::default();
+    let pp =
+      PublicParams::<E1>::setup(&circuit, &circuit, &*S::<E1>::ck_floor(), &*S::<E2>::ck_floor())
+        .unwrap();
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<E2>>::setup(&pp).unwrap();
+    let mut issuer = ChallengeIssuer::new(60);
+    let challenge = issuer.issue(OsRng, 1000);
+
+    let z0_primary = vec![challenge.scalar()];
+    let z0_secondary = vec![<E2 as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    // the proof is of its challenge, and is not replayed
+    let other = issuer.issue(OsRng, 1000);
+    assert_eq!(
+      snark.verify_fresh(&vk, 1, &z0_primary, &z0_secondary, &other, &mut issuer, 1010),
+      Err(NovaError::StaleChallenge)
+    );
+    let (zn_primary, _) = snark
+      .verify_fresh(&vk, 1, &z0_primary, &z0_secondary, &challenge, &mut issuer, 1010)
+      .unwrap();
+    assert_eq!(zn_primary, z0_primary);
+    assert_eq!(
+      snark.verify_fresh(&vk, 1, &z0_primary, &z0_secondary, &challenge, &mut issuer, 1010),
+      Err(NovaError::StaleChallenge)
+    );
+  }
+
+  #[cfg(feature = "prove")]
+  #[test]
+  fn test_carry_challenge() {
+    use bellpepper_core::test_cs::TestConstraintSystem;
+
+    type F = <E1 as Engine>::Scalar;
+    let mut cs = TestConstraintSystem::<F>::new();
+    let z = AllocatedNum::alloc(cs.namespace(|| "z"), || Ok(F::from(7))).unwrap();
+    let output = carry_challenge(cs.namespace(|| "carry"), &[z]).unwrap();
+    assert_eq!(output.get_value(), Some(F::from(7)));
+    assert!(cs.is_satisfied());
+    assert!(carry_challenge::<F, _>(cs.namespace(|| "empty"), &[]).is_err());
+  }
+}
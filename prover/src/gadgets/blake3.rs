@@ -0,0 +1,246 @@
+//! BLAKE3 of messages of fixed length inside circuits, with the digests of
+//! the `blake3` crate, so that circuits check the content addresses of data
+//! availability and content-addressed stores, and the nodes of the Merkle
+//! trees of `provider::merkle::Blake3Hasher`.
+//!
+//! The message is split into chunks of 1024 bytes, each of blocks of 64
+//! bytes chained through the compression, and the chaining values of the
+//! chunks are merged into a binary tree, whose left subtrees are of a power
+//! of two chunks. Since the length of the message fixes the shape of the
+//! circuit, the counters, the lengths and the flags of the blocks are
+//! constants. Byte strings are the bits of their bytes, most significant bit
+//! first (see `alloc_bytes`), as for the SHA-256 gadgets.
+use bellpepper::gadgets::{multieq::MultiEq, uint32::UInt32};
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+/// The length of BLAKE3 blocks
+const BLOCK_LEN: usize = 64;
+
+/// The length of BLAKE3 chunks
+const CHUNK_LEN: usize = 1024;
+
+const IV: [u32; 8] = [
+  0x6a09_e667,
+  0xbb67_ae85,
+  0x3c6e_f372,
+  0xa54f_f53a,
+  0x510e_527f,
+  0x9b05_688c,
+  0x1f83_d9ab,
+  0x5be0_cd19,
+];
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+
+/// The words of the state each `G` of a round mixes: the columns, then the
+/// diagonals
+const G_INDICES: [[usize; 4]; 8] = [
+  [0, 4, 8, 12],
+  [1, 5, 9, 13],
+  [2, 6, 10, 14],
+  [3, 7, 11, 15],
+  [0, 5, 10, 15],
+  [1, 6, 11, 12],
+  [2, 7, 8, 13],
+  [3, 4, 9, 14],
+];
+
+/// The permutation of the message words between rounds
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+/// The word of the 4 little-endian bytes `bytes`
+fn word(bytes: &[Boolean]) -> UInt32 {
+  let bits = bytes.chunks(8).rev().flatten().cloned().collect::<Vec<_>>();
+  UInt32::from_bits_be(&bits)
+}
+
+/// The 4 little-endian bytes of `word`
+fn word_bytes(word: UInt32) -> Vec<Boolean> {
+  word.into_bits_be().chunks(8).rev().flatten().cloned().collect()
+}
+
+/// The compression of the 16 words `m` into the chaining value `cv`
+fn compress<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  cv: &[UInt32],
+  mut m: Vec<UInt32>,
+  counter: u64,
+  block_len: usize,
+  flags: u32,
+) -> Result<Vec<UInt32>, SynthesisError> {
+  let mut cs = MultiEq::new(cs);
+  let mut v = cv.to_vec();
+  v.extend(IV[..4].iter().map(|w| UInt32::constant(*w)));
+  v.extend([counter as u32, (counter >> 32) as u32, block_len as u32, flags].map(UInt32::constant));
+
+  for round in 0..7 {
+    let mut cs = cs.namespace(|| format!("round {round}"));
+    for (i, [a, b, c, d]) in G_INDICES.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("g {i}"));
+      let (mx, my) = (&m[2 * i], &m[2 * i + 1]);
+      v[*a] = UInt32::addmany(cs.namespace(|| "a0"), &[v[*a].clone(), v[*b].clone(), mx.clone()])?;
+      v[*d] = v[*d].xor(cs.namespace(|| "d0"), &v[*a])?.rotr(16);
+      v[*c] = UInt32::addmany(cs.namespace(|| "c0"), &[v[*c].clone(), v[*d].clone()])?;
+      v[*b] = v[*b].xor(cs.namespace(|| "b0"), &v[*c])?.rotr(12);
+      v[*a] = UInt32::addmany(cs.namespace(|| "a1"), &[v[*a].clone(), v[*b].clone(), my.clone()])?;
+      v[*d] = v[*d].xor(cs.namespace(|| "d1"), &v[*a])?.rotr(8);
+      v[*c] = UInt32::addmany(cs.namespace(|| "c1"), &[v[*c].clone(), v[*d].clone()])?;
+      v[*b] = v[*b].xor(cs.namespace(|| "b1"), &v[*c])?.rotr(7);
+    }
+    m = MSG_PERMUTATION.iter().map(|i| m[*i].clone()).collect();
+  }
+
+  (0..8).map(|i| v[i].xor(cs.namespace(|| format!("output {i}")), &v[i + 8])).collect()
+}
+
+/// The chaining value of the chunk `chunk`, the `counter`-th of the message,
+/// or the root of the message if it is the only chunk
+fn chunk_cv<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[UInt32],
+  chunk: &[Boolean],
+  counter: u64,
+  flags: u32,
+  root: bool,
+) -> Result<Vec<UInt32>, SynthesisError> {
+  let blocks = if chunk.is_empty() { vec![chunk] } else { chunk.chunks(8 * BLOCK_LEN).collect() };
+  let mut cv = key.to_vec();
+  for (i, block) in blocks.iter().enumerate() {
+    let mut block_flags = flags;
+    if i == 0 {
+      block_flags |= CHUNK_START;
+    }
+    if i == blocks.len() - 1 {
+      block_flags |= CHUNK_END | if root { ROOT } else { 0 };
+    }
+    let mut padded = block.to_vec();
+    padded.resize(8 * BLOCK_LEN, Boolean::constant(false));
+    let m = padded.chunks(32).map(word).collect();
+    let len = block.len() / 8;
+    cv = compress(cs.namespace(|| format!("block {i}")), &cv, m, counter, len, block_flags)?;
+  }
+  Ok(cv)
+}
+
+/// The chaining value of the subtree of the chunks `chunks`, of which the
+/// first is the `counter`-th of the message, or the root of the message
+fn subtree_cv<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[UInt32],
+  chunks: &[&[Boolean]],
+  counter: u64,
+  flags: u32,
+  root: bool,
+) -> Result<Vec<UInt32>, SynthesisError> {
+  if chunks.len() == 1 {
+    return chunk_cv(cs, key, chunks[0], counter, flags, root);
+  }
+  let left_len = chunks.len().next_power_of_two() / 2;
+  let left = subtree_cv(cs.namespace(|| "left"), key, &chunks[..left_len], counter, flags, false)?;
+  let right = subtree_cv(
+    cs.namespace(|| "right"),
+    key,
+    &chunks[left_len..],
+    counter + left_len as u64,
+    flags,
+    false,
+  )?;
+  let flags = flags | PARENT | if root { ROOT } else { 0 };
+  compress(cs.namespace(|| "parent"), key, [left, right].concat(), 0, BLOCK_LEN, flags)
+}
+
+/// The hash of `bytes`, with the key words `key` and the mode `flags`
+fn hash<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  key: &[UInt32],
+  bytes: &[Boolean],
+  flags: u32,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if bytes.len() % 8 != 0 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let chunks = if bytes.is_empty() { vec![bytes] } else { bytes.chunks(8 * CHUNK_LEN).collect() };
+  let root = subtree_cv(cs, key, &chunks, 0, flags, true)?;
+  Ok(root.into_iter().flat_map(word_bytes).collect())
+}
+
+/// BLAKE3 of `bytes`, the 32 bytes of `blake3::hash`
+pub fn blake3<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  bytes: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  hash(cs, &IV.map(UInt32::constant), bytes, 0)
+}
+
+/// BLAKE3 of `bytes` keyed by the 32 bytes `key`, the 32 bytes of
+/// `blake3::keyed_hash`; a public key is passed as `constant_bytes(key)`
+pub fn blake3_keyed<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  key: &[Boolean],
+  bytes: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if key.len() != 256 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let key = key.chunks(32).map(word).collect::<Vec<_>>();
+  hash(cs, &key, bytes, KEYED_HASH)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::{
+    gadgets::{alloc_bytes, bytes_value, constant_bytes},
+    provider::merkle::{Blake3Hasher, MerkleHasher},
+  };
+
+  type F = halo2curves::bn256::Fr;
+
+  fn digest(key: Option<&[u8; 32]>, message: &[u8]) -> Vec<u8> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bytes = alloc_bytes(cs.namespace(|| "bytes"), Some(message), message.len()).unwrap();
+    let digest = match key {
+      Some(key) => blake3_keyed(cs.namespace(|| "blake3"), &constant_bytes(key), &bytes),
+      None => blake3(cs.namespace(|| "blake3"), &bytes),
+    }
+    .unwrap();
+    assert!(cs.is_satisfied());
+    bytes_value(&digest).unwrap()
+  }
+
+  #[test]
+  fn test_blake3() {
+    let message = (0..2049).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    for len in [0, 1, 64, 65, 1024, 1025, 2049] {
+      let expected = blake3::hash(&message[..len]);
+      assert_eq!(digest(None, &message[..len]), expected.as_bytes(), "{len}");
+    }
+
+    let key = [7u8; 32];
+    for len in [0, 65] {
+      let expected = blake3::keyed_hash(&key, &message[..len]);
+      assert_eq!(digest(Some(&key), &message[..len]), expected.as_bytes(), "{len}");
+    }
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    assert!(blake3(cs.namespace(|| "bits"), &[Boolean::constant(true)]).is_err());
+    assert!(blake3_keyed(cs.namespace(|| "key"), &constant_bytes(&[0; 16]), &[]).is_err());
+  }
+
+  #[test]
+  fn test_blake3_merkle_node() {
+    // the nodes of the Merkle trees of `Blake3Hasher` are checked in circuits
+    let hasher = <Blake3Hasher as MerkleHasher<F>>::new(b"test");
+    let (left, right) = ([1u8; 32], [2u8; 32]);
+    let node = <Blake3Hasher as MerkleHasher<F>>::hash_node(&hasher, &left, &right);
+    let message = [&[1u8][..], &left, &right].concat();
+    assert_eq!(digest(Some(hasher.key()), &message), node);
+  }
+}
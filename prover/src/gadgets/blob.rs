@@ -0,0 +1,203 @@
+//! This module binds external byte blobs, e.g. a chunk of a TLS transcript,
+//! to the inputs of a step circuit.
+//!
+//! A blob is committed to with the commitment engine of an `Engine` `E` whose
+//! base field is the field of the circuit, e.g. the secondary engine of the
+//! cycle for the primary circuit. Such a commitment is a point with
+//! coordinates in that field, so [`BlobKey::commitment_io`] can be passed in
+//! the public IO of the circuit (e.g. as part of `z0`), and
+//! [`BlobKey::alloc_opening`] allocates the bytes of the blob inside the
+//! circuit, constrained to open that commitment.
+//!
+//! The committed vector is the length of the blob followed by its bytes,
+//! packed little-endian into scalars, so that blobs differing only by
+//! trailing zeros have different commitments.
+use bellpepper_core::{boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::{Field, PrimeField};
+
+use crate::{
+  errors::NovaError,
+  gadgets::{le_bits_to_num, AllocatedPoint},
+  traits::{
+    commitment::{CommitmentEngineTrait, CommitmentTrait},
+    Engine,
+  },
+  Commitment, CommitmentKey,
+};
+
+/// The number of bytes packed in a scalar, such that a packed scalar fits in
+/// both fields of `E`
+fn bytes_per_scalar<E: Engine>() -> usize {
+  (E::Scalar::CAPACITY.min(E::Base::CAPACITY) / 8) as usize
+}
+
+/// The committed vector for `blob`: its length, then its packed bytes
+fn blob_to_scalars<E: Engine>(blob: &[u8]) -> Vec<E::Scalar> {
+  let radix = E::Scalar::from(256);
+  let chunks = blob.chunks(bytes_per_scalar::<E>()).map(|chunk| {
+    chunk.iter().rev().fold(E::Scalar::ZERO, |acc, b| acc * radix + E::Scalar::from(u64::from(*b)))
+  });
+  std::iter::once(E::Scalar::from(blob.len() as u64)).chain(chunks).collect()
+}
+
+/// The commitment key for blobs of up to a given length, along with the
+/// generators the in-circuit opening needs
+#[derive(Clone, Debug)]
+pub struct BlobKey<E: Engine> {
+  ck:         CommitmentKey<E>,
+  generators: Vec<(E::Base, E::Base, bool)>,
+}
+
+impl<E: Engine> BlobKey<E> {
+  /// Creates a key for blobs of up to `max_len` bytes
+  pub fn new(max_len: usize) -> Self {
+    let n = 1 + max_len.div_ceil(bytes_per_scalar::<E>());
+    let ck = E::CE::setup(b"blob", n);
+    // the i-th generator is the commitment to the i-th unit vector
+    let generators = (0..n)
+      .map(|i| {
+        let mut unit = vec![E::Scalar::ZERO; i + 1];
+        unit[i] = E::Scalar::ONE;
+        E::CE::commit(&ck, &unit).to_coordinates()
+      })
+      .collect();
+    Self { ck, generators }
+  }
+
+  /// The maximum length of the blobs this key commits to
+  pub fn max_len(&self) -> usize { (self.generators.len() - 1) * bytes_per_scalar::<E>() }
+
+  /// Commits to `blob`
+  pub fn commit(&self, blob: &[u8]) -> Result<Commitment<E>, NovaError> {
+    if blob.len() > self.max_len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(E::CE::commit(&self.ck, &blob_to_scalars::<E>(blob)))
+  }
+
+  /// The representation of `comm` in public IO: its coordinates `x`, `y`
+  /// and whether it is the point at infinity
+  pub fn commitment_io(comm: &Commitment<E>) -> [E::Base; 3] {
+    let (x, y, is_infinity) = comm.to_coordinates();
+    [x, y, if is_infinity { E::Base::ONE } else { E::Base::ZERO }]
+  }
+
+  /// Allocates the bytes of a blob of `len` bytes, constrained to open the
+  /// commitment allocated as `comm` (in the form of
+  /// [`BlobKey::commitment_io`]), and returns them. `blob` is `None` at
+  /// shape synthesis.
+  ///
+  /// Each byte is range-checked, and each packed scalar costs a scalar
+  /// multiplication on the curve of `E`.
+  pub fn alloc_opening<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    comm: &[AllocatedNum<E::Base>],
+    len: usize,
+    blob: Option<&[u8]>,
+  ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
+    if len > self.max_len() || comm.len() != 3 || blob.is_some_and(|b| b.len() != len) {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    // allocate the bytes as little-endian bits
+    let bits = (0..8 * len)
+      .map(|i| {
+        AllocatedBit::alloc(
+          cs.namespace(|| format!("bit {i}")),
+          blob.map(|b| (b[i / 8] >> (i % 8)) & 1 == 1),
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let bytes = bits
+      .chunks(8)
+      .enumerate()
+      .map(|(i, bits)| le_bits_to_num(cs.namespace(|| format!("byte {i}")), bits))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    // recompute the commitment, whose length term is a constant
+    let length_term = E::CE::commit(&self.ck, &[E::Scalar::from(len as u64)]).to_coordinates();
+    let mut acc =
+      AllocatedPoint::<E::GE>::alloc_constant(cs.namespace(|| "length term"), length_term)?;
+    for (i, chunk_bits) in bits.chunks(8 * bytes_per_scalar::<E>()).enumerate() {
+      let generator = AllocatedPoint::alloc_constant(
+        cs.namespace(|| format!("generator {i}")),
+        self.generators[i + 1],
+      )?;
+      let term = generator.scalar_mul(cs.namespace(|| format!("chunk {i}")), chunk_bits)?;
+      acc = acc.add(cs.namespace(|| format!("add chunk {i}")), &term)?;
+    }
+
+    acc.enforce_coordinates(cs.namespace(|| "opening matches"), comm)?;
+
+    Ok(bytes)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    bellpepper::{
+      r1cs::{NovaShape, NovaWitness},
+      solver::SatisfyingAssignment,
+      test_shape_cs::TestShapeCS,
+    },
+    provider::{Bn256EngineKZG, GrumpkinEngine},
+    traits::snark::default_ck_hint,
+  };
+
+  fn synthesize_opening<E: Engine, CS: ConstraintSystem<E::Base>>(
+    mut cs: CS,
+    key: &BlobKey<E>,
+    io: &[E::Base; 3],
+    len: usize,
+    blob: Option<&[u8]>,
+  ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
+    let comm = io
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc_input(cs.namespace(|| format!("comm {i}")), || Ok(*v)))
+      .collect::<Result<Vec<_>, _>>()?;
+    key.alloc_opening(cs.namespace(|| "open blob"), &comm, len, blob)
+  }
+
+  fn test_blob_opening_with<E1, E2>()
+  where
+    E1: Engine<Scalar = <E2 as Engine>::Base>,
+    E2: Engine, {
+    let blob = (0..70u8).collect::<Vec<_>>();
+    let key = BlobKey::<E2>::new(blob.len());
+    assert!(key.max_len() >= blob.len());
+    let io = BlobKey::<E2>::commitment_io(&key.commit(&blob).unwrap());
+
+    // blobs of different lengths have different commitments
+    let padded = [&blob[..60], &[0u8; 10][..]].concat();
+    assert_ne!(key.commit(&blob[..60]).unwrap(), key.commit(&padded).unwrap());
+    assert_eq!(key.commit(&[0; 100]), Err(NovaError::InvalidInputLength));
+
+    let mut cs: TestShapeCS<E1> = TestShapeCS::new();
+    let _ = synthesize_opening(cs.namespace(|| "synthesize"), &key, &io, blob.len(), None);
+    let (shape, ck) = cs.r1cs_shape_and_key(&*default_ck_hint());
+
+    let mut cs = SatisfyingAssignment::<E1>::new();
+    let bytes =
+      synthesize_opening(cs.namespace(|| "synthesize"), &key, &io, blob.len(), Some(&blob))
+        .unwrap();
+    let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck).unwrap();
+    shape.is_sat(&ck, &inst, &witness).unwrap();
+    let values = bytes.iter().map(|b| b.get_value().unwrap()).collect::<Vec<_>>();
+    assert_eq!(values, blob.iter().map(|b| E2::Base::from(u64::from(*b))).collect::<Vec<_>>());
+
+    // another blob does not open the commitment
+    let mut other = blob.clone();
+    other[42] ^= 1;
+    let mut cs = SatisfyingAssignment::<E1>::new();
+    let _ = synthesize_opening(cs.namespace(|| "synthesize"), &key, &io, blob.len(), Some(&other));
+    let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck).unwrap();
+    assert!(shape.is_sat(&ck, &inst, &witness).is_err());
+  }
+
+  #[test]
+  fn test_blob_opening() { test_blob_opening_with::<Bn256EngineKZG, GrumpkinEngine>(); }
+}
@@ -0,0 +1,348 @@
+//! Byte strings inside circuits, as the bits of their bytes, most significant
+//! bit first, the order the SHA-256 gadget hashes them in.
+//!
+//! Strings of variable lengths are padded to a bound, and their lengths are
+//! allocated numbers; the gadgets over them take the same constraints
+//! whatever the lengths, so that the lengths and offsets stay private.
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::{AllocatedNum, Num},
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use crate::lookup::field_to_u64;
+
+/// The number of bytes packed in a field element
+pub const BYTES_PER_ELEMENT: usize = 16;
+
+/// Allocates `len` bytes, `bytes` being `None` at shape synthesis
+pub fn alloc_bytes<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: Option<&[u8]>,
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if bytes.is_some_and(|b| b.len() != len) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  (0..8 * len)
+    .map(|i| {
+      let bit = bytes.map(|b| (b[i / 8] >> (7 - i % 8)) & 1 == 1);
+      Ok(Boolean::from(AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)?))
+    })
+    .collect()
+}
+
+/// The constant `bytes`
+pub fn constant_bytes(bytes: &[u8]) -> Vec<Boolean> {
+  let bit = |b: u8, i: usize| Boolean::constant((b >> i) & 1 == 1);
+  bytes.iter().flat_map(|b| (0..8).rev().map(move |i| bit(*b, i))).collect()
+}
+
+/// The value of the bytes `bits`, if they are assigned
+pub fn bytes_value(bits: &[Boolean]) -> Option<Vec<u8>> {
+  bits
+    .chunks(8)
+    .map(|byte| byte.iter().try_fold(0u8, |acc, bit| Some((acc << 1) | u8::from(bit.get_value()?))))
+    .collect()
+}
+
+/// The bytes of `a` XOR `b`, of the same length
+pub fn xor_bytes<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &[Boolean],
+  b: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if a.len() != b.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  a.iter()
+    .zip(b)
+    .enumerate()
+    .map(|(i, (a, b))| Boolean::xor(cs.namespace(|| format!("xor {i}")), a, b))
+    .collect()
+}
+
+/// Packs `bytes` into field elements of [`BYTES_PER_ELEMENT`] bytes each, as
+/// big-endian integers, the last one possibly shorter
+pub fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+  bytes
+    .chunks(BYTES_PER_ELEMENT)
+    .map(|chunk| F::from_u128(chunk.iter().fold(0u128, |acc, b| (acc << 8) | u128::from(*b))))
+    .collect()
+}
+
+/// Unpacks `len` bytes from `elements`, packed by [`pack_bytes`], or `None`
+/// if `elements` do not pack that many bytes
+pub fn unpack_bytes<F: PrimeField>(elements: &[F], len: usize) -> Option<Vec<u8>> {
+  if elements.len() != len.div_ceil(BYTES_PER_ELEMENT) {
+    return None;
+  }
+  let mut bytes = Vec::with_capacity(len);
+  for (i, element) in elements.iter().enumerate() {
+    let n = (len - i * BYTES_PER_ELEMENT).min(BYTES_PER_ELEMENT);
+    // the value is read from a little-endian representation, and checked
+    let repr = element.to_repr();
+    let mut le = [0u8; 16];
+    le.copy_from_slice(repr.as_ref().get(..16)?);
+    let value = u128::from_le_bytes(le);
+    if F::from_u128(value) != *element || (n < 16 && value >> (8 * n) != 0) {
+      return None;
+    }
+    bytes.extend_from_slice(&value.to_be_bytes()[16 - n..]);
+  }
+  Some(bytes)
+}
+
+/// Packs the bytes `bits` into allocated field elements, as [`pack_bytes`]
+pub fn alloc_packed<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[Boolean],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  bits
+    .chunks(8 * BYTES_PER_ELEMENT)
+    .enumerate()
+    .map(|(i, chunk)| {
+      let mut num = Num::<F>::zero();
+      let mut coeff = F::ONE;
+      for bit in chunk.iter().rev() {
+        num = num.add_bool_with_coeff(CS::one(), bit, coeff);
+        coeff = coeff.double();
+      }
+      let packed = AllocatedNum::alloc(cs.namespace(|| format!("element {i}")), || {
+        num.get_value().ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      cs.enforce(
+        || format!("pack {i}"),
+        |_| num.lc(F::ONE),
+        |lc| lc + CS::one(),
+        |lc| lc + packed.get_variable(),
+      );
+      Ok(packed)
+    })
+    .collect()
+}
+
+/// The value of `v` as an integer, saturated to `u64::MAX`, so that values
+/// out of range fail the range checks they are assigned to
+fn saturated_u64<F: PrimeField>(v: &F) -> u64 { field_to_u64(v).unwrap_or(u64::MAX) }
+
+/// Enforces that `lc`, of value `value`, fits in `bits` bits, and returns
+/// these bits, least significant first, for a field whose representation is
+/// little-endian
+pub(crate) fn range_check<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  lc: LinearCombination<F>,
+  value: Option<F>,
+  bits: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let repr = value.map(|v| v.to_repr());
+  let mut sum = LinearCombination::zero();
+  let mut coeff = F::ONE;
+  let mut le_bits = Vec::with_capacity(bits);
+  for i in 0..bits {
+    let bit = repr.as_ref().map(|r| r.as_ref().get(i / 8).is_some_and(|b| (b >> (i % 8)) & 1 == 1));
+    let bit = AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)?;
+    sum = sum + (coeff, bit.get_variable());
+    coeff = coeff.double();
+    le_bits.push(Boolean::from(bit));
+  }
+  cs.enforce(|| "range", |_| sum, |lc| lc + CS::one(), |_| lc);
+  Ok(le_bits)
+}
+
+/// The mask of the first `len` bytes of `max_len`, as the bit `i < len` per
+/// byte, which also enforces `len <= max_len`
+pub fn prefix_mask<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  len: &AllocatedNum<F>,
+  max_len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let len_value = len.get_value().map(|v| saturated_u64(&v));
+  let mut sum = LinearCombination::zero();
+  let mut mask: Vec<AllocatedBit> = Vec::with_capacity(max_len);
+  for i in 0..max_len {
+    let bit = len_value.map(|len| (i as u64) < len);
+    let bit = AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)?;
+    // the mask is ones then zeros
+    if let Some(previous) = mask.last() {
+      cs.enforce(
+        || format!("decreasing {i}"),
+        |lc| lc + bit.get_variable(),
+        |lc| lc + CS::one() - previous.get_variable(),
+        |lc| lc,
+      );
+    }
+    sum = sum + bit.get_variable();
+    mask.push(bit);
+  }
+  cs.enforce(|| "len", |_| sum, |lc| lc + CS::one(), |lc| lc + len.get_variable());
+  Ok(mask.into_iter().map(Boolean::from).collect())
+}
+
+/// The bytes `bytes`, those whose bit of `mask` is unset being zeroed
+pub fn mask_bytes<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[Boolean],
+  mask: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if bytes.len() != 8 * mask.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  bytes
+    .iter()
+    .enumerate()
+    .map(|(i, bit)| Boolean::and(cs.namespace(|| format!("mask {i}")), bit, &mask[i / 8]))
+    .collect()
+}
+
+/// Whether the first `a_len` bytes of `a` are the first `b_len` bytes of
+/// `b`, both padded to the same length, whatever their padding
+pub fn padded_equal<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &[Boolean],
+  a_len: &AllocatedNum<F>,
+  b: &[Boolean],
+  b_len: &AllocatedNum<F>,
+) -> Result<Boolean, SynthesisError> {
+  if a.len() != b.len() || a.len() % 8 != 0 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let a_mask = prefix_mask(cs.namespace(|| "a mask"), a_len, a.len() / 8)?;
+  let b_mask = prefix_mask(cs.namespace(|| "b mask"), b_len, b.len() / 8)?;
+  let a = mask_bytes(cs.namespace(|| "mask a"), a, &a_mask)?;
+  let b = mask_bytes(cs.namespace(|| "mask b"), b, &b_mask)?;
+
+  // the masked strings and their masks are equal if their differences are
+  // all zero
+  let diff = [
+    xor_bytes(cs.namespace(|| "bytes diff"), &a, &b)?,
+    xor_bytes(cs.namespace(|| "mask diff"), &a_mask, &b_mask)?,
+  ]
+  .concat();
+  let sum = diff.iter().fold(LinearCombination::zero(), |lc, bit| lc + &bit.lc(CS::one(), F::ONE));
+  let sum_value =
+    diff.iter().try_fold(F::ZERO, |acc, bit| Some(acc + F::from(u64::from(bit.get_value()?))));
+
+  let equal = AllocatedBit::alloc(cs.namespace(|| "equal"), sum_value.map(|s| s == F::ZERO))?;
+  let inverse = AllocatedNum::alloc(cs.namespace(|| "inverse"), || {
+    let sum = sum_value.ok_or(SynthesisError::AssignmentMissing)?;
+    Ok(sum.invert().unwrap_or(F::ZERO))
+  })?;
+  // sum * inverse = 1 - equal, and sum * equal = 0
+  cs.enforce(
+    || "unequal",
+    |_| sum.clone(),
+    |lc| lc + inverse.get_variable(),
+    |lc| lc + CS::one() - equal.get_variable(),
+  );
+  cs.enforce(|| "equal", |_| sum, |lc| lc + equal.get_variable(), |lc| lc);
+  Ok(Boolean::from(equal))
+}
+
+/// The `len` bytes of `haystack` from the byte `offset`, which is enforced
+/// to be at most `haystack.len() / 8 - len`
+pub fn substring_at<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  haystack: &[Boolean],
+  offset: &AllocatedNum<F>,
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if haystack.len() % 8 != 0 || 8 * len > haystack.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let max_offset = haystack.len() / 8 - len;
+  let bits = (usize::BITS - max_offset.leading_zeros()) as usize;
+  let offset_bits = range_check(
+    cs.namespace(|| "offset"),
+    LinearCombination::zero() + offset.get_variable(),
+    offset.get_value(),
+    bits,
+  )?;
+  range_check(
+    cs.namespace(|| "offset bound"),
+    LinearCombination::zero() + (F::from(max_offset as u64), CS::one()) - offset.get_variable(),
+    offset.get_value().map(|offset| F::from(max_offset as u64) - offset),
+    bits,
+  )?;
+
+  // a barrel shifter, from the largest shift, after which fewer bytes are
+  // needed
+  let mut window = haystack.to_vec();
+  for (j, bit) in offset_bits.iter().enumerate().rev() {
+    let mut cs = cs.namespace(|| format!("shift {j}"));
+    let shift = 8 << j;
+    let needed = (8 * (len + (1 << j) - 1)).min(window.len());
+    window = (0..needed)
+      .map(|i| {
+        let shifted = window.get(i + shift).cloned().unwrap_or(Boolean::constant(false));
+        Boolean::sha256_ch(cs.namespace(|| format!("bit {i}")), bit, &shifted, &window[i])
+      })
+      .collect::<Result<_, _>>()?;
+  }
+  window.truncate(8 * len);
+  Ok(window)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  fn alloc(cs: &mut TestConstraintSystem<F>, name: &str, bytes: &[u8]) -> Vec<Boolean> {
+    alloc_bytes(cs.namespace(|| name.to_string()), Some(bytes), bytes.len()).unwrap()
+  }
+
+  fn alloc_num(cs: &mut TestConstraintSystem<F>, name: &str, v: u64) -> AllocatedNum<F> {
+    AllocatedNum::alloc_infallible(cs.namespace(|| name.to_string()), || F::from(v))
+  }
+
+  fn padded_equal_value(a: &[u8], a_len: u64, b: &[u8], b_len: u64) -> Option<bool> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let (a, b) = (alloc(&mut cs, "a", a), alloc(&mut cs, "b", b));
+    let (a_len, b_len) = (alloc_num(&mut cs, "a len", a_len), alloc_num(&mut cs, "b len", b_len));
+    let equal = padded_equal(cs.namespace(|| "equal"), &a, &a_len, &b, &b_len).unwrap();
+    cs.is_satisfied().then(|| equal.get_value().unwrap())
+  }
+
+  #[test]
+  fn test_padded_equal() {
+    assert_eq!(padded_equal_value(b"abc\0\0", 3, b"abcxy", 3), Some(true));
+    assert_eq!(padded_equal_value(b"\0\0\0\0\0", 0, b"xyzzy", 0), Some(true));
+    assert_eq!(padded_equal_value(b"abcde", 5, b"abcde", 5), Some(true));
+    assert_eq!(padded_equal_value(b"abc\0\0", 3, b"abc\0\0", 4), Some(false));
+    assert_eq!(padded_equal_value(b"abd\0\0", 3, b"abc\0\0", 3), Some(false));
+    // the lengths are bounded by the padding
+    assert_eq!(padded_equal_value(b"abcde", 6, b"abcde", 6), None);
+  }
+
+  #[test]
+  fn test_mask_bytes() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bytes = alloc(&mut cs, "bytes", b"secret");
+    let len = alloc_num(&mut cs, "len", 2);
+    let mask = prefix_mask(cs.namespace(|| "mask"), &len, 6).unwrap();
+    let masked = mask_bytes(cs.namespace(|| "masked"), &bytes, &mask).unwrap();
+    assert!(cs.is_satisfied());
+    assert_eq!(bytes_value(&masked).unwrap(), b"se\0\0\0\0");
+  }
+
+  #[test]
+  fn test_substring_at() {
+    let haystack = b"GET /index.html HTTP/1.1";
+    for (offset, satisfied) in [(0, true), (4, true), (13, true), (14, false), (1 << 20, false)] {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let bytes = alloc(&mut cs, "haystack", haystack);
+      let offset_num = alloc_num(&mut cs, "offset", offset);
+      let window = substring_at(cs.namespace(|| "window"), &bytes, &offset_num, 11).unwrap();
+      assert_eq!(cs.is_satisfied(), satisfied, "{offset}");
+      if satisfied {
+        let offset = offset as usize;
+        assert_eq!(bytes_value(&window).unwrap(), haystack[offset..offset + 11]);
+      }
+    }
+  }
+}
@@ -0,0 +1,277 @@
+//! The parsing of ASCII decimal numbers inside circuits, e.g. of the values
+//! of JSON members, into the field elements claims compare.
+//!
+//! A number is the first `len` bytes of a string of `n` bytes, most
+//! significant bit first (see `alloc_bytes`), whose length `len` is private
+//! (see `prefix_mask`): an integer is `[0-9]+`, and a fixed-point decimal
+//! `[0-9]+(\.[0-9]+)?` with at most `scale` digits after its point, whose
+//! value is its value times `10^scale`. Leading zeros are allowed, and the
+//! values are below `10^(n + scale)`, so that they fit in [`decimal_bits`]
+//! of this many digits, bounding the comparisons on them.
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+use num_bigint::BigUint;
+
+use super::{
+  bytes::{bytes_value, prefix_mask},
+  encoding::byte_lc,
+  utils::alloc_zero,
+};
+use crate::lookup::field_to_u64;
+
+/// The number of bits of the values below `10^digits`
+pub fn decimal_bits(digits: usize) -> usize {
+  // no field element has 2^32 decimal digits
+  #[allow(clippy::expect_used)]
+  let digits = u32::try_from(digits).expect("the number of digits fits in 32 bits");
+  (BigUint::from(10u32).pow(digits) - 1u32).bits() as usize
+}
+
+/// The integer of the first `len` bytes of `bytes`
+pub fn parse_integer<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  bytes: &[Boolean],
+  len: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  parse(cs, bytes, len, None)
+}
+
+/// The value times `10^scale` of the fixed-point decimal of the first `len`
+/// bytes of `bytes`
+pub fn parse_decimal<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  bytes: &[Boolean],
+  len: &AllocatedNum<F>,
+  scale: usize,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  parse(cs, bytes, len, Some(scale))
+}
+
+/// Parses an integer, or a decimal of `scale` digits after its point
+fn parse<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[Boolean],
+  len: &AllocatedNum<F>,
+  scale: Option<usize>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let n = bytes.len() / 8;
+  if n == 0 || bytes.len() % 8 != 0 || decimal_bits(n + scale.unwrap_or(0)) >= F::CAPACITY as usize
+  {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  // a number has a digit at least
+  let mask = prefix_mask(cs.namespace(|| "mask"), len, n)?;
+  cs.enforce(
+    || "a digit",
+    |_| mask[0].lc(CS::one(), F::ONE),
+    |lc| lc + CS::one(),
+    |lc| lc + CS::one(),
+  );
+
+  // the point is the first `.` of the number, if any
+  let values = bytes_value(bytes);
+  let len_value = len.get_value().and_then(|len| field_to_u64(&len));
+  let point_at = values
+    .as_ref()
+    .zip(len_value)
+    .and_then(|(values, len)| values.iter().take(len as usize).position(|b| *b == b'.'));
+
+  let mut value = alloc_zero(cs.namespace(|| "zero"));
+  let mut seen = LinearCombination::<F>::zero();
+  let mut fraction = LinearCombination::<F>::zero();
+  let mut fraction_value = Some(0u64);
+  let mut previous_point: Option<Boolean> = None;
+  for (i, bits) in bytes.chunks(8).enumerate() {
+    let mut cs = cs.namespace(|| format!("byte {i}"));
+    let c = byte_lc::<F, CS>(bits);
+    let c_value = values.as_ref().map(|values| values[i]);
+    let point = match scale {
+      // a number does not start with its point
+      Some(_) if i > 0 =>
+        Boolean::from(AllocatedBit::alloc(cs.namespace(|| "point"), point_at.map(|p| p == i))?),
+      _ => Boolean::constant(false),
+    };
+    let is_digit = mask[i].lc(CS::one(), F::ONE) - &point.lc(CS::one(), F::ONE);
+
+    // the point is a `.` of the number, followed by a digit
+    cs.enforce(
+      || "point in the number",
+      |_| point.lc(CS::one(), F::ONE),
+      |lc| lc + CS::one() - &mask[i].lc(CS::one(), F::ONE),
+      |lc| lc,
+    );
+    cs.enforce(
+      || "point is a '.'",
+      |_| point.lc(CS::one(), F::ONE),
+      |_| c.clone() - (F::from(u64::from(b'.')), CS::one()),
+      |lc| lc,
+    );
+    if let Some(previous_point) = &previous_point {
+      cs.enforce(
+        || "point before a digit",
+        |_| previous_point.lc(CS::one(), F::ONE),
+        |lc| lc + CS::one() - &is_digit,
+        |lc| lc,
+      );
+    }
+
+    // the digit, of 4 bits below 10
+    let digit_value = c_value.map(|c| if c.is_ascii_digit() { u64::from(c - b'0') } else { 0 });
+    let digit = (0..4)
+      .map(|j| {
+        let bit = digit_value.map(|d| (d >> (3 - j)) & 1 == 1);
+        AllocatedBit::alloc(cs.namespace(|| format!("digit bit {j}")), bit)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let digit_lc = digit.iter().enumerate().fold(LinearCombination::zero(), |lc, (j, bit)| {
+      lc + (F::from(1 << (3 - j)), bit.get_variable())
+    });
+    cs.enforce(
+      || "digit below 10",
+      |lc| lc + digit[0].get_variable(),
+      |lc| lc + digit[1].get_variable() + digit[2].get_variable(),
+      |lc| lc,
+    );
+    cs.enforce(
+      || "digit is the byte",
+      |_| is_digit.clone(),
+      |_| c - (F::from(u64::from(b'0')), CS::one()) - &digit_lc,
+      |lc| lc,
+    );
+
+    // value = 10 value + digit, for the digits of the number
+    let digit_f = digit_value.map(F::from);
+    let is_digit_value = mask[i].get_value().zip(point.get_value()).map(|(m, p)| m && !p);
+    let next = AllocatedNum::alloc(cs.namespace(|| "value"), || {
+      let (v, d) = value.get_value().zip(digit_f).ok_or(SynthesisError::AssignmentMissing)?;
+      let is_digit = is_digit_value.ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(if is_digit { v * F::from(10) + d } else { v })
+    })?;
+    cs.enforce(
+      || "next value",
+      |_| is_digit.clone(),
+      |lc| lc + (F::from(9), value.get_variable()) + &digit_lc,
+      |lc| lc + next.get_variable() - value.get_variable(),
+    );
+    value = next;
+
+    // the digits past the point
+    if scale.is_some() {
+      let seen_value = point_at.map(|p| p < i);
+      let after = AllocatedBit::alloc(
+        cs.namespace(|| "fractional"),
+        seen_value.zip(is_digit_value).map(|(s, d)| s && d),
+      )?;
+      cs.enforce(
+        || "fractional",
+        |_| is_digit.clone(),
+        |_| seen.clone(),
+        |lc| lc + after.get_variable(),
+      );
+      fraction = fraction + after.get_variable();
+      fraction_value = fraction_value.zip(after.get_value()).map(|(f, a)| f + u64::from(a));
+      seen = seen + &point.lc(CS::one(), F::ONE);
+    }
+    previous_point = Some(point);
+  }
+  // the last byte is not a point, and there is at most one point
+  if let Some(point) = &previous_point {
+    cs.enforce(|| "last", |_| point.lc(CS::one(), F::ONE), |lc| lc + CS::one(), |lc| lc);
+  }
+  cs.enforce(|| "one point", |_| seen.clone(), |lc| lc + CS::one() - &seen, |lc| lc);
+
+  let Some(scale) = scale else { return Ok(value) };
+
+  // value 10^(scale - fraction), for a fraction of at most `scale` digits
+  let selectors = (0..=scale)
+    .map(|k| {
+      let selected = fraction_value.map(|f| f == k as u64);
+      AllocatedBit::alloc(cs.namespace(|| format!("fraction {k}")), selected)
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let (one, count, factor) = selectors.iter().enumerate().fold(
+    (LinearCombination::zero(), LinearCombination::zero(), LinearCombination::zero()),
+    |(one, count, factor), (k, s)| {
+      let power = F::from(10).pow_vartime([(scale - k) as u64]);
+      (
+        one + s.get_variable(),
+        count + (F::from(k as u64), s.get_variable()),
+        factor + (power, s.get_variable()),
+      )
+    },
+  );
+  cs.enforce(|| "one fraction", |_| one, |lc| lc + CS::one(), |lc| lc + CS::one());
+  cs.enforce(|| "fraction", |_| count, |lc| lc + CS::one(), |_| fraction);
+
+  let factor_value = fraction_value
+    .filter(|f| *f <= scale as u64)
+    .map(|f| F::from(10).pow_vartime([scale as u64 - f]));
+  let scaled = AllocatedNum::alloc(cs.namespace(|| "scaled"), || {
+    let (v, factor) =
+      value.get_value().zip(factor_value).ok_or(SynthesisError::AssignmentMissing)?;
+    Ok(v * factor)
+  })?;
+  cs.enforce(
+    || "scale",
+    |lc| lc + value.get_variable(),
+    |_| factor,
+    |lc| lc + scaled.get_variable(),
+  );
+  Ok(scaled)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::gadgets::alloc_bytes;
+
+  type F = halo2curves::bn256::Fr;
+
+  fn parse_value(input: &[u8], len: u64, scale: Option<usize>) -> Option<F> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bits = alloc_bytes(cs.namespace(|| "input"), Some(input), input.len()).unwrap();
+    let len = AllocatedNum::alloc_infallible(cs.namespace(|| "len"), || F::from(len));
+    let value = match scale {
+      None => parse_integer(cs.namespace(|| "parse"), &bits, &len),
+      Some(scale) => parse_decimal(cs.namespace(|| "parse"), &bits, &len, scale),
+    };
+    // a witness may be missing for an input that does not parse
+    let value = value.ok()?.get_value();
+    cs.is_satisfied().then_some(value).flatten()
+  }
+
+  #[test]
+  fn test_parse_integer() {
+    assert_eq!(parse_value(b"12345", 5, None), Some(F::from(12345)));
+    assert_eq!(parse_value(b"0042\"}", 4, None), Some(F::from(42)));
+    assert_eq!(parse_value(b"7", 1, None), Some(F::from(7)));
+    assert_eq!(parse_value(b"12", 0, None), None);
+    assert_eq!(parse_value(b"12", 3, None), None);
+    assert_eq!(parse_value(b"1a", 2, None), None);
+    assert_eq!(parse_value(b"1.5", 3, None), None);
+    assert_eq!(parse_value(b"1:", 2, None), None);
+
+    assert_eq!(decimal_bits(0), 0);
+    assert_eq!(decimal_bits(3), 10);
+    assert_eq!(decimal_bits(19), 64);
+  }
+
+  #[test]
+  fn test_parse_decimal() {
+    assert_eq!(parse_value(b"123.45", 6, Some(2)), Some(F::from(12345)));
+    assert_eq!(parse_value(b"1000,\"x\"", 4, Some(2)), Some(F::from(100_000)));
+    assert_eq!(parse_value(b"0.5xx", 3, Some(2)), Some(F::from(50)));
+    assert_eq!(parse_value(b"3.25", 4, Some(0)), None);
+    assert_eq!(parse_value(b"1.234", 5, Some(2)), None);
+    assert_eq!(parse_value(b".5", 2, Some(2)), None);
+    assert_eq!(parse_value(b"5.", 2, Some(2)), None);
+    assert_eq!(parse_value(b"5.1", 2, Some(2)), None);
+    assert_eq!(parse_value(b"1.2.3", 5, Some(2)), None);
+  }
+}
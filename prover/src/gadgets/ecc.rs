@@ -49,6 +49,51 @@ impl<G: Group> AllocatedPoint<G> {
     Ok(Self { x, y, is_infinity })
   }
 
+  /// Allocates the point with coordinates `coords` as a constant
+  pub fn alloc_constant<CS: ConstraintSystem<G::Base>>(
+    mut cs: CS,
+    coords: (G::Base, G::Base, bool),
+  ) -> Result<Self, SynthesisError> {
+    let p = Self::alloc(cs.namespace(|| "point"), Some(coords))?;
+    let is_infinity = if coords.2 { G::Base::ONE } else { G::Base::ZERO };
+    for (name, v, c) in
+      [("x", &p.x, coords.0), ("y", &p.y, coords.1), ("is_infinity", &p.is_infinity, is_infinity)]
+    {
+      cs.enforce(
+        || format!("{name} is constant"),
+        |lc| lc + v.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + (c, CS::one()),
+      );
+    }
+    Ok(p)
+  }
+
+  /// Enforces that the coordinates `x`, `y` and `is_infinity` of `self` are
+  /// the numbers in `coords`
+  pub fn enforce_coordinates<CS: ConstraintSystem<G::Base>>(
+    &self,
+    mut cs: CS,
+    coords: &[AllocatedNum<G::Base>],
+  ) -> Result<(), SynthesisError> {
+    if coords.len() != 3 {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    for (name, a, b) in [
+      ("x", &self.x, &coords[0]),
+      ("y", &self.y, &coords[1]),
+      ("is_infinity", &self.is_infinity, &coords[2]),
+    ] {
+      cs.enforce(
+        || format!("{name} matches"),
+        |lc| lc + a.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + b.get_variable(),
+      );
+    }
+    Ok(())
+  }
+
   /// checks if `self` is on the curve or if it is infinity
   pub fn check_on_curve<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
   where CS: ConstraintSystem<G::Base> {
@@ -224,7 +269,8 @@ impl<G: Group> AllocatedPoint<G> {
         G::Base::ONE
       } else {
         // Set to the actual inverse
-        (*other.x.get_value().get()? - *self.x.get_value().get()?).invert().unwrap()
+        Option::<G::Base>::from((*other.x.get_value().get()? - *self.x.get_value().get()?).invert())
+          .ok_or(SynthesisError::DivisionByZero)?
       };
 
       Ok((*other.y.get_value().get()? - *self.y.get_value().get()?) * x_diff_inv)
@@ -362,7 +408,8 @@ impl<G: Group> AllocatedPoint<G> {
         G::Base::ONE
       } else {
         // Return the actual inverse
-        (*tmp.get_value().get()?).invert().unwrap()
+        Option::<G::Base>::from(tmp.get_value().get()?.invert())
+          .ok_or(SynthesisError::DivisionByZero)?
       };
 
       Ok(tmp_inv * (*prod_1.get_value().get()? + G::group_params().0))
@@ -612,10 +659,11 @@ impl<G: Group> AllocatedPointNonInfinity<G> {
       if *other.x.get_value().get()? == *self.x.get_value().get()? {
         Ok(G::Base::ONE)
       } else {
-        Ok(
-          (*other.y.get_value().get()? - *self.y.get_value().get()?)
-            * (*other.x.get_value().get()? - *self.x.get_value().get()?).invert().unwrap(),
+        let x_diff_inv = Option::<G::Base>::from(
+          (*other.x.get_value().get()? - *self.x.get_value().get()?).invert(),
         )
+        .ok_or(SynthesisError::DivisionByZero)?;
+        Ok((*other.y.get_value().get()? - *self.y.get_value().get()?) * x_diff_inv)
       }
     })?;
     cs.enforce(
@@ -678,7 +726,7 @@ impl<G: Group> AllocatedPointNonInfinity<G> {
       if d == G::Base::ZERO {
         Ok(G::Base::ONE)
       } else {
-        Ok(n * d.invert().unwrap())
+        Ok(n * Option::<G::Base>::from(d.invert()).ok_or(SynthesisError::DivisionByZero)?)
       }
     })?;
     cs.enforce(
@@ -0,0 +1,618 @@
+//! ECDSA signature verification over P-256 (FIPS 186-5) inside circuits, for
+//! SHA-256 digests, as in ES256 tokens.
+//!
+//! Coordinates and scalars are big integers of 32-bit limbs, non-native to the
+//! field. The arithmetic on them is a set of congruences between sums of
+//! products of witnesses, each proven by a witness quotient, and the points
+//! are added with the incomplete affine formulas, whose exceptional cases are
+//! unsatisfiable rather than unsound: `u1 G + u2 Q` is computed with Shamir's
+//! trick from an offset of `G`, which valid signatures only hit with
+//! negligible probability. Keys and signatures are big-endian bytes, as in
+//! JWS: `x || y` and `r || s`.
+use std::cmp::max;
+
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+use num_bigint::BigInt;
+use num_traits::{Num as _, One};
+
+use super::{
+  bytes::alloc_bytes,
+  kdf::HASH_LEN,
+  nat_to_limbs,
+  nonnative::bignat::{limbs_to_nat, BigNatParams, Polynomial},
+  BigNat,
+};
+
+/// The length of coordinates and scalars, in bytes
+pub const SCALAR_LEN: usize = 32;
+
+/// The length of keys, `x || y`
+pub const KEY_LEN: usize = 2 * SCALAR_LEN;
+
+/// The length of signatures, `r || s`
+pub const SIGNATURE_LEN: usize = 2 * SCALAR_LEN;
+
+/// The width of the limbs of coordinates and scalars
+const LIMB_WIDTH: usize = 32;
+
+/// The number of limbs of coordinates and scalars
+const N_LIMBS: usize = 8 * SCALAR_LEN / LIMB_WIDTH;
+
+/// The order of the base field, `2^256 - 2^224 + 2^192 + 2^96 - 1`
+const P: &str = "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff";
+
+/// The order of the group
+const N: &str = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+
+/// The coefficient `b` of the curve `y^2 = x^3 - 3x + b`
+const B: &str = "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b";
+
+/// The base point `G`
+const G: [&str; 2] = [
+  "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+  "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+];
+
+/// `-(2^256 mod n) G`, which cancels the offset `G` of the double scalar
+/// multiplication after its 256 doublings
+const OFFSET: [&str; 2] = [
+  "0b197a2e1e67a44b5afb62de48adde6400b60867487cab5739912513c420924a",
+  "5b5fc4ce0c6bac1ea990c7a29839be03f0a0ab1147404cbf461ac4c72efba5a0",
+];
+
+// only called on the hex constants of this module
+#[allow(clippy::expect_used)]
+fn hex_nat(hex: &str) -> BigInt { BigInt::from_str_radix(hex, 16).expect("the constant is in hex") }
+
+/// `a mod m`, in `[0, m)`
+fn modulo(a: BigInt, m: &BigInt) -> BigInt { ((a % m) + m) % m }
+
+/// The inverse of `a` modulo the prime `m`, or zero if there is none
+fn inverse(a: &BigInt, m: &BigInt) -> BigInt { modulo(a.clone(), m).modpow(&(m - 2u32), m) }
+
+/// A constant of at least [`N_LIMBS`] limbs, which are linear combinations of
+/// the constant one
+fn constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  value: &BigInt,
+) -> Result<BigNat<F>, SynthesisError> {
+  let n_limbs = max(N_LIMBS, (value.bits() as usize).div_ceil(LIMB_WIDTH));
+  let limb_values =
+    nat_to_limbs::<F>(value, LIMB_WIDTH, n_limbs).ok_or(SynthesisError::Unsatisfiable)?;
+  Ok(BigNat {
+    limbs:       limb_values.iter().map(|v| LinearCombination::zero() + (*v, CS::one())).collect(),
+    limb_values: Some(limb_values),
+    value:       Some(value.clone()),
+    params:      BigNatParams::new(LIMB_WIDTH, n_limbs),
+  })
+}
+
+/// A prime modulus of 256 bits, which sizes the quotients of congruences
+fn modulus<F: PrimeField, CS: ConstraintSystem<F>>(
+  value: &BigInt,
+) -> Result<BigNat<F>, SynthesisError> {
+  let mut nat = constant::<F, CS>(value)?;
+  nat.params.min_bits = 8 * SCALAR_LEN - 1;
+  Ok(nat)
+}
+
+/// Allocates a number of [`N_LIMBS`] limbs, range-checked
+fn alloc_nat<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: Option<BigInt>,
+) -> Result<BigNat<F>, SynthesisError> {
+  let nat = BigNat::alloc_from_nat(
+    cs.namespace(|| "nat"),
+    || value.ok_or(SynthesisError::AssignmentMissing),
+    LIMB_WIDTH,
+    N_LIMBS,
+  )?;
+  nat.assert_well_formed(cs.namespace(|| "rangecheck"))?;
+  Ok(nat)
+}
+
+/// Allocates the little-endian bits of a scalar
+fn alloc_bits<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: Option<BigInt>,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  (0..8 * SCALAR_LEN)
+    .map(|i| {
+      let bit = value.as_ref().map(|v| v.bit(i as u64));
+      Ok(Boolean::from(AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)?))
+    })
+    .collect()
+}
+
+/// The number of the little-endian `bits`, whose limbs are linear
+/// combinations of the bits
+fn from_bits<F: PrimeField, CS: ConstraintSystem<F>>(bits: &[Boolean]) -> BigNat<F> {
+  let chunks = bits.chunks(LIMB_WIDTH);
+  let limbs = chunks
+    .clone()
+    .map(|chunk| {
+      let mut coeff = F::ONE;
+      chunk.iter().fold(LinearCombination::zero(), |lc, bit| {
+        let lc = lc + &bit.lc(CS::one(), coeff);
+        coeff = coeff.double();
+        lc
+      })
+    })
+    .collect::<Vec<_>>();
+  let limb_values = chunks
+    .map(|chunk| {
+      chunk
+        .iter()
+        .rev()
+        .try_fold(F::ZERO, |acc, bit| Some(acc.double() + F::from(u64::from(bit.get_value()?))))
+    })
+    .collect::<Option<Vec<_>>>();
+  BigNat {
+    params: BigNatParams::new(LIMB_WIDTH, limbs.len()),
+    value: limb_values.as_ref().map(|vs| limbs_to_nat::<F, _, _>(vs.iter(), LIMB_WIDTH)),
+    limb_values,
+    limbs,
+  }
+}
+
+/// The number of the big-endian bytes `bytes`, as bits with the most
+/// significant bit first
+fn from_be_bits<F: PrimeField, CS: ConstraintSystem<F>>(bytes: &[Boolean]) -> BigNat<F> {
+  from_bits::<F, CS>(&bytes.iter().rev().cloned().collect::<Vec<_>>())
+}
+
+/// The product of `a` and `b`, as the product of the polynomials of their
+/// limbs
+fn product<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  a: &BigNat<F>,
+  b: &BigNat<F>,
+) -> Result<BigNat<F>, SynthesisError> {
+  let poly = Polynomial::from(a.clone()).alloc_product(cs, &Polynomial::from(b.clone()))?;
+  let max_word =
+    BigInt::from(a.limbs.len().min(b.limbs.len())) * &a.params.max_word * &b.params.max_word;
+  Ok(BigNat::from_poly(poly, LIMB_WIDTH, max_word))
+}
+
+/// `k a`, for a small constant `k`
+fn scale<F: PrimeField>(a: &BigNat<F>, k: u32) -> BigNat<F> {
+  let coeff = F::from(u64::from(k));
+  BigNat {
+    limbs:       a.limbs.iter().map(|limb| LinearCombination::zero() + (coeff, limb)).collect(),
+    limb_values: a.limb_values.as_ref().map(|vs| vs.iter().map(|v| *v * coeff).collect()),
+    value:       a.value.as_ref().map(|v| v * k),
+    params:      BigNatParams { max_word: &a.params.max_word * k, ..a.params.clone() },
+  }
+}
+
+/// `a` with zero limbs up to `n_limbs` limbs
+fn pad<F: PrimeField>(mut a: BigNat<F>, n_limbs: usize) -> BigNat<F> {
+  a.limbs.resize(n_limbs, LinearCombination::zero());
+  if let Some(vs) = a.limb_values.as_mut() {
+    vs.resize(n_limbs, F::ZERO);
+  }
+  a.params.n_limbs = n_limbs;
+  a
+}
+
+/// Enforces `left = right (mod modulus)`: `left + c m = right + q m`, where
+/// the constant `c m` exceeds `right`, so that the witness quotient `q` is a
+/// natural number
+fn congruent<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  left: &BigNat<F>,
+  right: &BigNat<F>,
+  modulus: &BigNat<F>,
+) -> Result<(), SynthesisError> {
+  let m = modulus.value.as_ref().ok_or(SynthesisError::Unsatisfiable)?;
+  let offset = ((BigInt::one() << right.n_bits()) / m + 1u32) * m;
+  let left = left.add(&constant::<F, CS>(&offset)?)?;
+  let quotient_bits = left.n_bits().saturating_sub(modulus.params.min_bits).max(1);
+  let quotient = BigNat::alloc_from_nat(
+    cs.namespace(|| "quotient"),
+    || {
+      let left = left.value.as_ref().ok_or(SynthesisError::AssignmentMissing)?;
+      let right = right.value.as_ref().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok((left - right) / m)
+    },
+    LIMB_WIDTH,
+    quotient_bits.div_ceil(LIMB_WIDTH),
+  )?;
+  quotient.assert_well_formed(cs.namespace(|| "quotient rangecheck"))?;
+  let right = product(cs.namespace(|| "product"), &quotient, modulus)?.add(right)?;
+  let n_limbs = max(left.limbs.len(), right.limbs.len());
+  pad(left, n_limbs).equal_when_carried_regroup(cs.namespace(|| "carry"), &pad(right, n_limbs))
+}
+
+/// Enforces that the number `a` of [`N_LIMBS`] limbs is less than `bound`,
+/// by the range of `a + 2^256 - bound`
+fn enforce_less_than<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &BigNat<F>,
+  bound: &BigInt,
+) -> Result<(), SynthesisError> {
+  let offset = (BigInt::one() << (LIMB_WIDTH * N_LIMBS)) - bound;
+  let sum = a.add(&constant::<F, CS>(&offset)?)?;
+  let difference = alloc_nat(cs.namespace(|| "difference"), sum.value.clone())?;
+  sum.equal_when_carried_regroup(cs.namespace(|| "carry"), &difference)
+}
+
+/// `condition ? b : a`, limb by limb
+fn select<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  condition: &Boolean,
+  a: &BigNat<F>,
+  b: &BigNat<F>,
+) -> Result<BigNat<F>, SynthesisError> {
+  if a.limbs.len() != b.limbs.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let chosen = condition.get_value().map(|c| if c { b } else { a });
+  let limbs = (0..a.limbs.len())
+    .map(|i| {
+      let limb = cs.alloc(
+        || format!("limb {i}"),
+        || {
+          let values = chosen.and_then(|n| n.limb_values.as_ref());
+          values.map(|vs| vs[i]).ok_or(SynthesisError::AssignmentMissing)
+        },
+      )?;
+      cs.enforce(
+        || format!("select {i}"),
+        |_| condition.lc(CS::one(), F::ONE),
+        |lc| lc + &b.limbs[i] - &a.limbs[i],
+        |lc| lc + limb - &a.limbs[i],
+      );
+      Ok(LinearCombination::zero() + limb)
+    })
+    .collect::<Result<Vec<_>, SynthesisError>>()?;
+  Ok(BigNat {
+    limbs,
+    limb_values: chosen.and_then(|n| n.limb_values.clone()),
+    value: chosen.and_then(|n| n.value.clone()),
+    params: BigNatParams::new(LIMB_WIDTH, a.limbs.len()),
+  })
+}
+
+/// A point of the curve in affine coordinates, of [`N_LIMBS`] limbs, which
+/// are congruent modulo `p` to the coordinates but not necessarily reduced
+#[derive(Clone)]
+struct Point<F: PrimeField> {
+  x: BigNat<F>,
+  y: BigNat<F>,
+}
+
+impl<F: PrimeField> Point<F> {
+  fn constant<CS: ConstraintSystem<F>>(point: [&str; 2]) -> Result<Self, SynthesisError> {
+    Ok(Self {
+      x: constant::<F, CS>(&hex_nat(point[0]))?,
+      y: constant::<F, CS>(&hex_nat(point[1]))?,
+    })
+  }
+
+  fn value(&self) -> Option<(BigInt, BigInt)> { self.x.value.clone().zip(self.y.value.clone()) }
+
+  /// Allocates the coordinates of `value`, reduced
+  fn alloc<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    value: Option<(BigInt, BigInt)>,
+  ) -> Result<Self, SynthesisError> {
+    let (x, y) = value.unzip();
+    Ok(Self { x: alloc_nat(cs.namespace(|| "x"), x)?, y: alloc_nat(cs.namespace(|| "y"), y)? })
+  }
+
+  /// Enforces `y^2 + 3x = x^3 + b (mod p)`
+  fn enforce_on_curve<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    p: &BigNat<F>,
+  ) -> Result<(), SynthesisError> {
+    let y_y = product(cs.namespace(|| "y y"), &self.y, &self.y)?;
+    let x_x = product(cs.namespace(|| "x x"), &self.x, &self.x)?;
+    let x_x_x = product(cs.namespace(|| "x x x"), &x_x, &self.x)?;
+    let left = y_y.add(&scale(&self.x, 3))?;
+    let right = x_x_x.add(&constant::<F, CS>(&hex_nat(B))?)?;
+    congruent(cs.namespace(|| "equation"), &left, &right, p)
+  }
+
+  /// `2 self`, for a point whose `y` is not zero, which is every point of
+  /// the curve as its order is odd
+  fn double<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    p: &BigNat<F>,
+  ) -> Result<Self, SynthesisError> {
+    let m = p.value.as_ref().ok_or(SynthesisError::Unsatisfiable)?;
+    let values = self.value().map(|(x, y)| {
+      let lambda = modulo((&x * &x * 3u32 - 3u32) * inverse(&(&y * 2u32), m), m);
+      let x3 = modulo(&lambda * &lambda - &x * 2u32, m);
+      let y3 = modulo(&lambda * (&x - &x3) - &y, m);
+      (lambda, (x3, y3))
+    });
+    let (lambda, point) = values.unzip();
+    let lambda = alloc_nat(cs.namespace(|| "lambda"), lambda)?;
+    let double = Self::alloc(cs.namespace(|| "double"), point)?;
+
+    // 2 lambda y + 3 = 3 x^2
+    let lambda_y = product(cs.namespace(|| "lambda y"), &lambda, &self.y)?;
+    let x_x = product(cs.namespace(|| "x x"), &self.x, &self.x)?;
+    let left = scale(&lambda_y, 2).add(&constant::<F, CS>(&BigInt::from(3u32))?)?;
+    congruent(cs.namespace(|| "slope"), &left, &scale(&x_x, 3), p)?;
+    // x3 + 2 x = lambda^2
+    let lambda_lambda = product(cs.namespace(|| "lambda lambda"), &lambda, &lambda)?;
+    let left = double.x.add(&scale(&self.x, 2))?;
+    congruent(cs.namespace(|| "x"), &left, &lambda_lambda, p)?;
+    // y3 + y + lambda x3 = lambda x
+    let lambda_x3 = product(cs.namespace(|| "lambda x3"), &lambda, &double.x)?;
+    let lambda_x = product(cs.namespace(|| "lambda x"), &lambda, &self.x)?;
+    let left = double.y.add(&self.y)?.add(&lambda_x3)?;
+    congruent(cs.namespace(|| "y"), &left, &lambda_x, p)?;
+    Ok(double)
+  }
+
+  /// `self + other`, for points of distinct `x`: other points are
+  /// unsatisfiable
+  fn add<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    p: &BigNat<F>,
+  ) -> Result<Self, SynthesisError> {
+    let m = p.value.as_ref().ok_or(SynthesisError::Unsatisfiable)?;
+    let values = self.value().zip(other.value()).map(|((x1, y1), (x2, y2))| {
+      let inv = inverse(&(&x2 - &x1), m);
+      let lambda = modulo((&y2 - &y1) * &inv, m);
+      let x3 = modulo(&lambda * &lambda - &x1 - &x2, m);
+      let y3 = modulo(&lambda * (&x1 - &x3) - &y1, m);
+      ((inv, lambda), (x3, y3))
+    });
+    let (witnesses, point) = values.unzip();
+    let (inv, lambda) = witnesses.unzip();
+    let inv = alloc_nat(cs.namespace(|| "inverse"), inv)?;
+    let lambda = alloc_nat(cs.namespace(|| "lambda"), lambda)?;
+    let sum = Self::alloc(cs.namespace(|| "sum"), point)?;
+
+    // inv x2 = inv x1 + 1, so that x1 and x2 are distinct
+    let inv_x2 = product(cs.namespace(|| "inv x2"), &inv, &other.x)?;
+    let inv_x1 = product(cs.namespace(|| "inv x1"), &inv, &self.x)?;
+    let right = inv_x1.add(&constant::<F, CS>(&BigInt::one())?)?;
+    congruent(cs.namespace(|| "distinct"), &inv_x2, &right, p)?;
+    // lambda x2 + y1 = lambda x1 + y2
+    let lambda_x2 = product(cs.namespace(|| "lambda x2"), &lambda, &other.x)?;
+    let lambda_x1 = product(cs.namespace(|| "lambda x1"), &lambda, &self.x)?;
+    let left = lambda_x2.add(&self.y)?;
+    congruent(cs.namespace(|| "slope"), &left, &lambda_x1.add(&other.y)?, p)?;
+    // x3 + x1 + x2 = lambda^2
+    let lambda_lambda = product(cs.namespace(|| "lambda lambda"), &lambda, &lambda)?;
+    let left = sum.x.add(&self.x)?.add(&other.x)?;
+    congruent(cs.namespace(|| "x"), &left, &lambda_lambda, p)?;
+    // y3 + y1 + lambda x3 = lambda x1
+    let lambda_x3 = product(cs.namespace(|| "lambda x3"), &lambda, &sum.x)?;
+    let left = sum.y.add(&self.y)?.add(&lambda_x3)?;
+    congruent(cs.namespace(|| "y"), &left, &lambda_x1, p)?;
+    Ok(sum)
+  }
+
+  /// `condition ? b : a`
+  fn select<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    condition: &Boolean,
+    a: &Self,
+    b: &Self,
+  ) -> Result<Self, SynthesisError> {
+    Ok(Self {
+      x: select(cs.namespace(|| "x"), condition, &a.x, &b.x)?,
+      y: select(cs.namespace(|| "y"), condition, &a.y, &b.y)?,
+    })
+  }
+}
+
+/// `u1 G + u2 Q`, for the little-endian bits of `u1` and `u2`, with Shamir's
+/// trick: the accumulator starts at `G`, so that it is never the identity,
+/// and the offset is cancelled at the end
+fn double_scalar_mul<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  u1: &[Boolean],
+  u2: &[Boolean],
+  q: &Point<F>,
+  p: &BigNat<F>,
+) -> Result<Point<F>, SynthesisError> {
+  let g = Point::constant::<CS>(G)?;
+  let g_q = g.add(cs.namespace(|| "G + Q"), q, p)?;
+  let mut acc = g.clone();
+  for (i, (b1, b2)) in u1.iter().zip(u2).enumerate().rev() {
+    let mut cs = cs.namespace(|| format!("bit {i}"));
+    acc = acc.double(cs.namespace(|| "double"), p)?;
+    let q_or_g_q = Point::select(cs.namespace(|| "Q or G + Q"), b1, q, &g_q)?;
+    let term = Point::select(cs.namespace(|| "term"), b2, &g, &q_or_g_q)?;
+    let sum = acc.add(cs.namespace(|| "add"), &term, p)?;
+    let any = Boolean::or(cs.namespace(|| "any"), b1, b2)?;
+    acc = Point::select(cs.namespace(|| "select"), &any, &acc, &sum)?;
+  }
+  acc.add(cs.namespace(|| "offset"), &Point::constant::<CS>(OFFSET)?, p)
+}
+
+/// A P-256 public key, with the big-endian bytes of its coordinates
+#[derive(Clone)]
+pub struct P256PublicKey<F: PrimeField> {
+  bytes: Vec<Boolean>,
+  point: Point<F>,
+}
+
+impl<F: PrimeField> P256PublicKey<F> {
+  /// Allocates the key of the [`KEY_LEN`] bytes `x || y`, which are reduced
+  /// coordinates of a point of the curve
+  pub fn alloc<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    key: Option<&[u8]>,
+  ) -> Result<Self, SynthesisError> {
+    if key.is_some_and(|k| k.len() != KEY_LEN) {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let bytes = alloc_bytes(cs.namespace(|| "bytes"), key, KEY_LEN)?;
+    let (x, y) = bytes.split_at(8 * SCALAR_LEN);
+    let point = Point { x: from_be_bits::<F, CS>(x), y: from_be_bits::<F, CS>(y) };
+    let p = hex_nat(P);
+    enforce_less_than(cs.namespace(|| "x reduced"), &point.x, &p)?;
+    enforce_less_than(cs.namespace(|| "y reduced"), &point.y, &p)?;
+    point.enforce_on_curve(cs.namespace(|| "on curve"), &modulus::<F, CS>(&p)?)?;
+    Ok(Self { bytes, point })
+  }
+
+  /// The bytes of the key
+  pub fn bytes(&self) -> &[Boolean] { &self.bytes }
+}
+
+/// Enforces that `signature`, the [`SIGNATURE_LEN`] bytes `r || s`, is an
+/// ECDSA signature of the SHA-256 `digest` under `key`. As in FIPS 186-5,
+/// `r` and `s` must be in `[1, n)`, and `r` is the `x` of `u1 G + u2 Q`
+/// modulo `n`, for `u1 = e / s` and `u2 = r / s` modulo `n`.
+pub fn verify_ecdsa_p256_sha256<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &P256PublicKey<F>,
+  signature: Option<&[u8]>,
+  digest: &[Boolean],
+) -> Result<(), SynthesisError> {
+  if digest.len() != 8 * HASH_LEN || signature.is_some_and(|s| s.len() != SIGNATURE_LEN) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let p_value = hex_nat(P);
+  let n_value = hex_nat(N);
+  let p = modulus::<F, CS>(&p_value)?;
+  let n = modulus::<F, CS>(&n_value)?;
+  let one = constant::<F, CS>(&BigInt::one())?;
+
+  let bytes = alloc_bytes(cs.namespace(|| "signature"), signature, SIGNATURE_LEN)?;
+  let (r, s) = bytes.split_at(8 * SCALAR_LEN);
+  let (r, s) = (from_be_bits::<F, CS>(r), from_be_bits::<F, CS>(s));
+  enforce_less_than(cs.namespace(|| "r < n"), &r, &n_value)?;
+  enforce_less_than(cs.namespace(|| "s < n"), &s, &n_value)?;
+
+  // r and s are invertible, i.e. not zero, and w = 1 / s
+  let r_inv =
+    alloc_nat(cs.namespace(|| "r inverse"), r.value.as_ref().map(|r| inverse(r, &n_value)))?;
+  let r_r_inv = product(cs.namespace(|| "r r_inv"), &r, &r_inv)?;
+  congruent(cs.namespace(|| "r invertible"), &r_r_inv, &one, &n)?;
+  let w = alloc_nat(cs.namespace(|| "w"), s.value.as_ref().map(|s| inverse(s, &n_value)))?;
+  let s_w = product(cs.namespace(|| "s w"), &s, &w)?;
+  congruent(cs.namespace(|| "s invertible"), &s_w, &one, &n)?;
+
+  // u1 = e w and u2 = r w, in bits
+  let e = from_be_bits::<F, CS>(digest);
+  let mut u = Vec::with_capacity(2);
+  for (name, factor) in [("u1", &e), ("u2", &r)] {
+    let mut cs = cs.namespace(|| name);
+    let value = factor.value.as_ref().zip(w.value.as_ref()).map(|(f, w)| modulo(f * w, &n_value));
+    let bits = alloc_bits(cs.namespace(|| "bits"), value)?;
+    let factor_w = product(cs.namespace(|| "product"), factor, &w)?;
+    congruent(cs.namespace(|| "congruent"), &factor_w, &from_bits::<F, CS>(&bits), &n)?;
+    u.push(bits);
+  }
+
+  // x(u1 G + u2 Q) mod n = r, for the reduced x in [0, p), as p < 2n
+  let point = double_scalar_mul(cs.namespace(|| "u1 G + u2 Q"), &u[0], &u[1], &key.point, &p)?;
+  enforce_less_than(cs.namespace(|| "x reduced"), &point.x, &p_value)?;
+  congruent(cs.namespace(|| "r"), &point.x, &r, &n)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  // a key, signing MESSAGE
+  const MESSAGE: &[u8] = b"a message signed by a P-256 key";
+  const KEY: &str = concat!(
+    "0984d700b93ab686488d03fcba04f6e83db1c6e65ef018df0e40d2b39bf15fe4",
+    "f1647d8624716103d3e9ec9ea67c5c3aeb36a12c47c321ceac60141c0fb8684f",
+  );
+  const SIGNATURE: &str = concat!(
+    "ba39747704813f34edaf54ceaeb0b4fd8b96388986ed709a525d0b2494beaa60",
+    "154989070e8edcf935c57a78e091219cc1b17240d683058f1cb41c97b83c050b",
+  );
+
+  // 2G and 3G
+  const G2: [&str; 2] = [
+    "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+    "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+  ];
+  const G3: [&str; 2] = [
+    "5ecbe4d1a6330a44c8f7ef951d4bf165e6c6b721efada985fb41661bc6e7fd6c",
+    "8734640c4998ff7e374b06ce1a64a2ecd82ab036384fb83d9a79b127a27d5032",
+  ];
+
+  fn alloc_key(key: &[u8]) -> bool {
+    let mut cs = TestConstraintSystem::<F>::new();
+    P256PublicKey::alloc(cs.namespace(|| "key"), Some(key)).is_ok() && cs.is_satisfied()
+  }
+
+  #[test]
+  fn test_point_arithmetic() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let p = modulus::<F, TestConstraintSystem<F>>(&hex_nat(P)).unwrap();
+    let g = Point::constant::<TestConstraintSystem<F>>(G).unwrap();
+    let g2 = g.double(cs.namespace(|| "double"), &p).unwrap();
+    let g3 = g2.add(cs.namespace(|| "add"), &g, &p).unwrap();
+    assert!(cs.is_satisfied());
+    assert_eq!(g2.value(), Some((hex_nat(G2[0]), hex_nat(G2[1]))));
+    assert_eq!(g3.value(), Some((hex_nat(G3[0]), hex_nat(G3[1]))));
+
+    // the sum of a point and itself is unsatisfiable
+    let mut cs = TestConstraintSystem::<F>::new();
+    let _ = g.add(cs.namespace(|| "add"), &g, &p);
+    assert!(!cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_public_key() {
+    let key = hex::decode(KEY).unwrap();
+    assert!(alloc_key(&key));
+    // off the curve
+    let mut off_curve = key.clone();
+    off_curve[KEY_LEN - 1] ^= 1;
+    assert!(!alloc_key(&off_curve));
+    let g = [hex_nat(G[0]).to_bytes_be().1, hex_nat(G[1]).to_bytes_be().1].concat();
+    assert!(alloc_key(&g));
+    // a coordinate of at least p
+    assert!(!alloc_key(&[0xff; KEY_LEN]));
+  }
+
+  fn verify(signature: &str, message: &[u8]) -> bool {
+    let key = hex::decode(KEY).unwrap();
+    let signature = hex::decode(signature).unwrap();
+    let digest = Sha256::digest(message);
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let key = P256PublicKey::alloc(cs.namespace(|| "key"), Some(&key)).unwrap();
+    let digest = alloc_bytes(cs.namespace(|| "digest"), Some(&digest[..]), HASH_LEN).unwrap();
+    let verified =
+      verify_ecdsa_p256_sha256(cs.namespace(|| "verify"), &key, Some(&signature), &digest);
+    verified.is_ok() && cs.is_satisfied()
+  }
+
+  // The verification allocates about 1.6M constraints:
+  // cargo test --release test_ecdsa -- --ignored
+  #[test]
+  #[ignore]
+  fn test_ecdsa() {
+    assert!(verify(SIGNATURE, MESSAGE));
+    assert!(!verify(SIGNATURE, b"another message"));
+    // s replaced by n - s, which is also a signature, of -R
+    let mut signature = hex::decode(SIGNATURE).unwrap();
+    let s = BigInt::from_bytes_be(num_bigint::Sign::Plus, &signature[SCALAR_LEN..]);
+    let neg = (hex_nat(N) - s).to_bytes_be().1;
+    signature[SCALAR_LEN..].copy_from_slice(&[vec![0; SCALAR_LEN - neg.len()], neg].concat());
+    assert!(verify(&hex::encode(signature), MESSAGE));
+    // r replaced by r + 1
+    let mut signature = hex::decode(SIGNATURE).unwrap();
+    signature[SCALAR_LEN - 1] ^= 1;
+    assert!(!verify(&hex::encode(signature), MESSAGE));
+  }
+}
@@ -0,0 +1,501 @@
+//! Decoding of base64 (RFC 4648) and percent-encoded (RFC 3986) strings
+//! inside circuits, e.g. the segments of a JWT or the query parameters of a
+//! URL in a web response.
+//!
+//! Strings are the bits of their bytes, most significant bit first (see
+//! `alloc_bytes`). A character decodes to its value through one-hot
+//! selectors of its class, e.g. the uppercase letters of base64, and range
+//! checks of its value within the class, so that a valid character has a
+//! single decoding and an invalid one has none.
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use super::bytes::bytes_value;
+
+/// The alphabet of a base64 encoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alphabet {
+  /// The standard alphabet, with `+` and `/`
+  Standard,
+  /// The URL-safe alphabet, with `-` and `_`, e.g. of JWTs
+  UrlSafe,
+}
+
+impl Alphabet {
+  /// The characters of the values 62 and 63
+  fn last_chars(self) -> [u8; 2] {
+    match self {
+      Self::Standard => [b'+', b'/'],
+      Self::UrlSafe => [b'-', b'_'],
+    }
+  }
+}
+
+// the classes of base64 characters, by their index in the selectors of a
+// character
+const UPPER: usize = 0;
+const LOWER: usize = 1;
+const DIGIT: usize = 2;
+const VALUE_62: usize = 3;
+const VALUE_63: usize = 4;
+const PAD: usize = 5;
+
+/// The class and the value of the base64 character `c`, if it is one
+fn classify_base64(c: u8, alphabet: Alphabet) -> Option<(usize, u64)> {
+  let [c62, c63] = alphabet.last_chars();
+  match c {
+    b'A'..=b'Z' => Some((UPPER, u64::from(c - b'A'))),
+    b'a'..=b'z' => Some((LOWER, u64::from(c - b'a') + 26)),
+    b'0'..=b'9' => Some((DIGIT, u64::from(c - b'0') + 52)),
+    b'=' => Some((PAD, 0)),
+    _ if c == c62 => Some((VALUE_62, 62)),
+    _ if c == c63 => Some((VALUE_63, 63)),
+    _ => None,
+  }
+}
+
+/// The value of the byte `bits` as a linear combination
+pub(super) fn byte_lc<F: PrimeField, CS: ConstraintSystem<F>>(
+  bits: &[Boolean],
+) -> LinearCombination<F> {
+  bits
+    .iter()
+    .enumerate()
+    .fold(LinearCombination::zero(), |lc, (i, bit)| lc + &bit.lc(CS::one(), F::from(1 << (7 - i))))
+}
+
+/// The value of the bits `bits`, most significant first, as a linear
+/// combination
+fn bits_lc<F: PrimeField>(bits: &[AllocatedBit]) -> LinearCombination<F> {
+  let n = bits.len();
+  bits.iter().enumerate().fold(LinearCombination::zero(), |lc, (i, bit)| {
+    lc + (F::from(1 << (n - 1 - i)), bit.get_variable())
+  })
+}
+
+/// Allocates the `n` bits of `value`, most significant first
+fn alloc_bits<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: Option<u64>,
+  n: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  (0..n)
+    .map(|i| {
+      let bit = value.map(|v| (v >> (n - 1 - i)) & 1 == 1);
+      AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bit)
+    })
+    .collect()
+}
+
+/// Enforces that `lc`, of value `value`, fits in `n` bits
+fn enforce_range<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  lc: LinearCombination<F>,
+  value: Option<u64>,
+  n: usize,
+) -> Result<(), SynthesisError> {
+  let bits = alloc_bits(cs.namespace(|| "bits"), value, n)?;
+  cs.enforce(|| "range", |_| bits_lc(&bits), |l| l + CS::one(), |_| lc);
+  Ok(())
+}
+
+/// The selectors of the classes of a character and its value
+struct Decoded {
+  selectors: Vec<AllocatedBit>,
+  value:     Vec<AllocatedBit>,
+}
+
+impl Decoded {
+  /// The linear combination of the selectors of `terms`, skipping those the
+  /// character does not have
+  fn selectors_lc<F: PrimeField>(&self, terms: &[(usize, F)]) -> LinearCombination<F> {
+    terms
+      .iter()
+      .filter(|(class, _)| *class < self.selectors.len())
+      .fold(LinearCombination::zero(), |lc, (class, coeff)| {
+        lc + (*coeff, self.selectors[*class].get_variable())
+      })
+  }
+}
+
+/// Decodes the base64 character `c` into its 6 bits, where `padding` is
+/// whether `c` may be padding
+fn decode_base64_char<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  c: &[Boolean],
+  alphabet: Alphabet,
+  padding: bool,
+) -> Result<Decoded, SynthesisError> {
+  let classes = if padding { PAD + 1 } else { PAD };
+  let class =
+    bytes_value(c).map(|c| classify_base64(c[0], alphabet).filter(|(class, _)| *class < classes));
+  let is = |k: usize| class.map(|c| c.is_some_and(|(class, _)| class == k));
+  let selectors = (0..classes)
+    .map(|k| AllocatedBit::alloc(cs.namespace(|| format!("class {k}")), is(k)))
+    .collect::<Result<Vec<_>, _>>()?;
+  let value = class.map(|c| c.map_or(0, |(_, v)| v));
+  let decoded = Decoded { selectors, value: alloc_bits(cs.namespace(|| "value"), value, 6)? };
+  let v = bits_lc::<F>(&decoded.value);
+  let one = F::ONE;
+
+  cs.enforce(
+    || "one class",
+    |_| decoded.selectors_lc(&(0..classes).map(|k| (k, one)).collect::<Vec<_>>()),
+    |lc| lc + CS::one(),
+    |lc| lc + CS::one(),
+  );
+
+  // the offset of the value to the character in the ranges of letters and digits
+  let ranged = decoded.selectors_lc(&[(UPPER, one), (LOWER, one), (DIGIT, one)]);
+  let offset = AllocatedNum::alloc(cs.namespace(|| "offset"), || {
+    let class = class.ok_or(SynthesisError::AssignmentMissing)?;
+    Ok(F::from(class.filter(|(class, _)| *class <= DIGIT).map_or(0, |(_, v)| v)))
+  })?;
+  cs.enforce(|| "offset", |_| ranged, |_| v.clone(), |lc| lc + offset.get_variable());
+
+  let [c62, c63] = alphabet.last_chars();
+  let chars = decoded.selectors_lc(&[
+    (UPPER, F::from(u64::from(b'A'))),
+    (LOWER, F::from(u64::from(b'a') - 26)),
+    (DIGIT, -F::from(52 - u64::from(b'0'))),
+    (VALUE_62, F::from(u64::from(c62))),
+    (VALUE_63, F::from(u64::from(c63))),
+    (PAD, F::from(u64::from(b'='))),
+  ]);
+  cs.enforce(
+    || "character",
+    |_| byte_lc::<F, CS>(c),
+    |lc| lc + CS::one(),
+    |_| chars + offset.get_variable(),
+  );
+
+  // the other classes have a single value
+  let fixed = decoded.selectors_lc(&[(VALUE_62, one), (VALUE_63, one), (PAD, one)]);
+  let fixed_values = decoded.selectors_lc(&[(VALUE_62, F::from(62)), (VALUE_63, F::from(63))]);
+  cs.enforce(|| "fixed value", |_| fixed, |_| v.clone() - &fixed_values, |lc| lc);
+
+  // the value is within the range of its class: 0 to 25 for uppercase letters,
+  // 26 to 51 for lowercase letters and 52 to 61 for digits
+  let checks = [(UPPER, 38i64), (LOWER, 12), (LOWER, -26), (DIGIT, 2), (DIGIT, -52)];
+  for (i, (class, shift)) in checks.into_iter().enumerate() {
+    let coeff = if shift < 0 { -F::from(shift.unsigned_abs()) } else { F::from(shift as u64) };
+    let shifted = v.clone() + &decoded.selectors_lc(&[(class, coeff)]);
+    let value =
+      value.zip(is(class)).map(|(v, is)| if is { v.wrapping_add_signed(shift) } else { v });
+    enforce_range(cs.namespace(|| format!("range {i}")), shifted, value, 6)?;
+  }
+  Ok(decoded)
+}
+
+/// Enforces that `bit` is zero if `condition` is set
+fn enforce_zero_if<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  condition: Option<&AllocatedBit>,
+  bit: &AllocatedBit,
+) {
+  cs.enforce(
+    || "zero",
+    |lc| match condition {
+      Some(condition) => lc + condition.get_variable(),
+      None => lc + CS::one(),
+    },
+    |lc| lc + bit.get_variable(),
+    |lc| lc,
+  );
+}
+
+/// A decoded base64 string
+#[derive(Clone, Debug)]
+pub struct Base64Decoded<F: PrimeField> {
+  /// the decoded bytes, three for every four characters of the input, those
+  /// past `len` being zero
+  pub bytes: Vec<Boolean>,
+  /// the number of decoded bytes, less than the length of `bytes` by the
+  /// padding of the input
+  pub len:   AllocatedNum<F>,
+}
+
+/// Decodes the base64 string `input` in `alphabet`, either padded with `=` to
+/// a multiple of four characters or unpadded, e.g. the segments of a JWT;
+/// non-canonical encodings, whose unused bits are set, have no decoding
+pub fn base64_decode<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  input: &[Boolean],
+  alphabet: Alphabet,
+) -> Result<Base64Decoded<F>, SynthesisError> {
+  let n = input.len() / 8;
+  if input.len() % 8 != 0 || n % 4 == 1 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  // only the last two characters of a string of whole blocks may be padding
+  let padded = n > 0 && n % 4 == 0;
+  let chars = input
+    .chunks(8)
+    .enumerate()
+    .map(|(i, c)| {
+      let padding = padded && i + 2 >= n;
+      decode_base64_char(cs.namespace(|| format!("char {i}")), c, alphabet, padding)
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let bits = chars.iter().flat_map(|c| c.value.iter().cloned()).collect::<Vec<_>>();
+
+  // the bits past the last whole byte are unused, and zero
+  let n_bytes = 6 * n / 8;
+  for (i, bit) in bits[8 * n_bytes..].iter().enumerate() {
+    enforce_zero_if(cs.namespace(|| format!("unused bit {i}")), None, bit);
+  }
+
+  let mut len_lc = LinearCombination::zero() + (F::from(n_bytes as u64), CS::one());
+  let mut len_value = Some(n_bytes as u64);
+  if padded {
+    let last = &chars[n - 1].selectors[PAD];
+    let second = &chars[n - 2].selectors[PAD];
+    cs.enforce(
+      || "padding ends the string",
+      |lc| lc + second.get_variable(),
+      |lc| lc + CS::one() - last.get_variable(),
+      |lc| lc,
+    );
+    // the bits of the padded bytes are unused, and zero
+    for (i, bit) in chars[n - 2].value[4..].iter().enumerate() {
+      enforce_zero_if(cs.namespace(|| format!("unused bit {i} of one pad")), Some(last), bit);
+    }
+    for (i, bit) in chars[n - 3].value[2..].iter().enumerate() {
+      enforce_zero_if(cs.namespace(|| format!("unused bit {i} of two pads")), Some(second), bit);
+    }
+    len_lc = len_lc - last.get_variable() - second.get_variable();
+    len_value = len_value
+      .zip(last.get_value().zip(second.get_value()))
+      .map(|(len, (last, second))| len - u64::from(last) - u64::from(second));
+  }
+  let len = AllocatedNum::alloc(cs.namespace(|| "len"), || {
+    len_value.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(|| "len", |_| len_lc, |lc| lc + CS::one(), |lc| lc + len.get_variable());
+
+  let mut bytes = bits.into_iter().map(Boolean::from).collect::<Vec<_>>();
+  bytes.truncate(8 * n_bytes);
+  Ok(Base64Decoded { bytes, len })
+}
+
+/// Whether the byte `bits` is `byte`
+fn is_byte<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[Boolean],
+  byte: u8,
+) -> Result<Boolean, SynthesisError> {
+  let literal = |i: usize| {
+    if (byte >> (7 - i)) & 1 == 1 {
+      bits[i].clone()
+    } else {
+      bits[i].not()
+    }
+  };
+  (1..8).try_fold(literal(0), |acc, i| {
+    Boolean::and(cs.namespace(|| format!("and {i}")), &acc, &literal(i))
+  })
+}
+
+/// Decodes the hex digit `c` into its 4 bits if `condition` is set, and into
+/// zero otherwise
+fn decode_hex_digit<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  c: &[Boolean],
+  condition: &Boolean,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  let class = bytes_value(c).zip(condition.get_value()).map(|(c, condition)| match c[0] {
+    _ if !condition => None,
+    b'0'..=b'9' => Some((0, u64::from(c[0] - b'0'))),
+    b'A'..=b'F' => Some((1, u64::from(c[0] - b'A') + 10)),
+    b'a'..=b'f' => Some((2, u64::from(c[0] - b'a') + 10)),
+    _ => None,
+  });
+  let is = |k: usize| class.map(|c| c.is_some_and(|(class, _)| class == k));
+  let selectors = (0..3)
+    .map(|k| AllocatedBit::alloc(cs.namespace(|| format!("class {k}")), is(k)))
+    .collect::<Result<Vec<_>, _>>()?;
+  let value = class.map(|c| c.map_or(0, |(_, v)| v));
+  let decoded = Decoded { selectors, value: alloc_bits(cs.namespace(|| "value"), value, 4)? };
+  let v = bits_lc::<F>(&decoded.value);
+  let one = F::ONE;
+  let condition_lc = || condition.lc(CS::one(), one);
+
+  cs.enforce(
+    || "one class if set",
+    |_| decoded.selectors_lc(&[(0, one), (1, one), (2, one)]),
+    |lc| lc + CS::one(),
+    |_| condition_lc(),
+  );
+  cs.enforce(|| "zero if unset", |lc| lc + CS::one() - &condition_lc(), |_| v.clone(), |lc| lc);
+
+  let offset = AllocatedNum::alloc(cs.namespace(|| "offset"), || {
+    value.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(|| "offset", |_| condition_lc(), |_| v.clone(), |lc| lc + offset.get_variable());
+  let chars = decoded.selectors_lc(&[
+    (0, F::from(u64::from(b'0'))),
+    (1, F::from(u64::from(b'A') - 10)),
+    (2, F::from(u64::from(b'a') - 10)),
+  ]);
+  cs.enforce(
+    || "character",
+    |_| condition_lc(),
+    |_| byte_lc::<F, CS>(c),
+    |_| chars + offset.get_variable(),
+  );
+
+  // digits are at most 9, letters at least 10
+  let digit = v.clone() + &decoded.selectors_lc(&[(0, F::from(6))]);
+  let digit_value = value.zip(is(0)).map(|(v, is)| v + 6 * u64::from(is));
+  enforce_range(cs.namespace(|| "digit range"), digit, digit_value, 4)?;
+  let letter = v - &decoded.selectors_lc(&[(1, F::from(10)), (2, F::from(10))]);
+  let letter_value = value.zip(is(1).zip(is(2))).map(|(v, (u, l))| v - 10 * u64::from(u || l));
+  enforce_range(cs.namespace(|| "letter range"), letter, letter_value, 4)?;
+  Ok(decoded.value)
+}
+
+/// A decoded percent-encoded string, aligned with the input
+#[derive(Clone, Debug)]
+pub struct PercentDecoded {
+  /// the decoded bytes, one for every character of the input: the byte of an
+  /// escape `%XY` in place of its `%`, and zero in place of its hex digits
+  pub bytes: Vec<Boolean>,
+  /// whether each byte is in the decoded string, i.e. is not in place of the
+  /// hex digits of an escape
+  pub kept:  Vec<Boolean>,
+}
+
+/// Decodes the percent-encoded string `input`, where a `%` must be followed by
+/// two hex digits
+pub fn percent_decode<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  input: &[Boolean],
+) -> Result<PercentDecoded, SynthesisError> {
+  if input.len() % 8 != 0 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let chars = input.chunks(8).collect::<Vec<_>>();
+  let n = chars.len();
+  let escapes = chars
+    .iter()
+    .enumerate()
+    .map(|(i, c)| is_byte(cs.namespace(|| format!("escape {i}")), c, b'%'))
+    .collect::<Result<Vec<_>, _>>()?;
+  let escape = |i: Option<usize>| i.map_or(Boolean::constant(false), |i| escapes[i].clone());
+
+  // the escapes of the last two characters have no hex digits
+  for (i, escape) in escapes.iter().enumerate().skip(n.saturating_sub(2)) {
+    cs.enforce(
+      || format!("no escape at {i}"),
+      |_| escape.lc(CS::one(), F::ONE),
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+  }
+
+  let mut kept = Vec::with_capacity(n);
+  let mut digits = Vec::with_capacity(n);
+  for (i, c) in chars.iter().enumerate() {
+    let mut cs = cs.namespace(|| format!("char {i}"));
+    let is_kept = Boolean::and(
+      cs.namespace(|| "kept"),
+      &escape(i.checked_sub(1)).not(),
+      &escape(i.checked_sub(2)).not(),
+    )?;
+    digits.push(decode_hex_digit(cs.namespace(|| "hex digit"), c, &is_kept.not())?);
+    kept.push(is_kept);
+  }
+
+  let mut bytes = Vec::with_capacity(8 * n);
+  for (i, c) in chars.iter().enumerate() {
+    let mut cs = cs.namespace(|| format!("byte {i}"));
+    let escaped = if i + 2 < n {
+      digits[i + 1].iter().chain(&digits[i + 2]).cloned().map(Boolean::from).collect()
+    } else {
+      vec![Boolean::constant(false); 8]
+    };
+    for (j, (escaped, bit)) in escaped.iter().zip(c.iter()).enumerate() {
+      let literal = Boolean::and(cs.namespace(|| format!("literal {j}")), bit, &kept[i])?;
+      // the escaped byte if the character is an escape, and the character otherwise
+      bytes.push(Boolean::sha256_ch(
+        cs.namespace(|| format!("bit {j}")),
+        &escapes[i],
+        escaped,
+        &literal,
+      )?);
+    }
+  }
+  Ok(PercentDecoded { bytes, kept })
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::gadgets::alloc_bytes;
+
+  type F = halo2curves::bn256::Fr;
+
+  fn decode_base64(input: &[u8], alphabet: Alphabet) -> Option<Vec<u8>> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bits = alloc_bytes(cs.namespace(|| "input"), Some(input), input.len()).unwrap();
+    let decoded = base64_decode(cs.namespace(|| "decode"), &bits, alphabet).unwrap();
+    let len = decoded.len.get_value().unwrap();
+    let bytes = bytes_value(&decoded.bytes).unwrap();
+    let n = (0..=bytes.len()).find(|n| F::from(*n as u64) == len).unwrap();
+    cs.is_satisfied().then(|| {
+      assert!(bytes[n..].iter().all(|b| *b == 0));
+      bytes[..n].to_vec()
+    })
+  }
+
+  #[test]
+  fn test_base64_decode() {
+    assert_eq!(
+      decode_base64(b"SGVsbG8sIHdvcmxkIQ==", Alphabet::Standard),
+      Some(b"Hello, world!".to_vec())
+    );
+    assert_eq!(decode_base64(b"SGVsbG8=", Alphabet::Standard), Some(b"Hello".to_vec()));
+    assert_eq!(decode_base64(b"SGVsbG8h", Alphabet::Standard), Some(b"Hello!".to_vec()));
+    // unpadded, as in JWTs
+    assert_eq!(decode_base64(b"SGVsbG8", Alphabet::UrlSafe), Some(b"Hello".to_vec()));
+    assert_eq!(decode_base64(b"-_8", Alphabet::UrlSafe), Some(vec![0xfb, 0xff]));
+    assert_eq!(decode_base64(b"+/8=", Alphabet::Standard), Some(vec![0xfb, 0xff]));
+
+    // characters of the other alphabet, padding out of place and unused bits
+    // set have no decoding
+    assert_eq!(decode_base64(b"-_8=", Alphabet::Standard), None);
+    assert_eq!(decode_base64(b"SG=sbG8=", Alphabet::Standard), None);
+    assert_eq!(decode_base64(b"SGVsbG=8", Alphabet::Standard), None);
+    assert_eq!(decode_base64(b"SGVsbG9=", Alphabet::Standard), None);
+    assert_eq!(decode_base64(b"SGVsbG9", Alphabet::UrlSafe), None);
+    assert_eq!(decode_base64(b"SGV*bG8=", Alphabet::Standard), None);
+  }
+
+  fn decode_percent(input: &[u8]) -> Option<Vec<u8>> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bits = alloc_bytes(cs.namespace(|| "input"), Some(input), input.len()).unwrap();
+    let decoded = percent_decode(cs.namespace(|| "decode"), &bits).unwrap();
+    let bytes = bytes_value(&decoded.bytes).unwrap();
+    let kept = decoded.kept.iter().map(|k| k.get_value().unwrap());
+    let decoded = bytes.iter().zip(kept).filter(|(_, k)| *k).map(|(b, _)| *b).collect();
+    cs.is_satisfied().then_some(decoded)
+  }
+
+  #[test]
+  fn test_percent_decode() {
+    assert_eq!(decode_percent(b"a%20b%2Fc%2f"), Some(b"a b/c/".to_vec()));
+    assert_eq!(decode_percent(b"plain"), Some(b"plain".to_vec()));
+    assert_eq!(decode_percent(b"%E2%82%AC"), Some("\u{20ac}".as_bytes().to_vec()));
+
+    // escapes must be followed by two hex digits
+    assert_eq!(decode_percent(b"a%2"), None);
+    assert_eq!(decode_percent(b"a%zz"), None);
+    assert_eq!(decode_percent(b"%%41"), None);
+  }
+}
@@ -0,0 +1,355 @@
+//! This module implements the verifier of an `InnerProductArgument` (see
+//! `provider::ipa_pc`) inside a circuit, for proof composition.
+//!
+//! The circuit is over `E::Base`, so that the group of `E` is native to it:
+//! e.g. arguments over Grumpkin are checked in a circuit over the scalar
+//! field of BN254, on the other side of the cycle. The argument is proven
+//! with `EvaluationEngine::prove_for_circuit`, whose challenges are squeezed
+//! from the random oracle of `E` over the coordinates of the instance and of
+//! the cross terms L and R, so that [`check_ipa_msm`] squeezes them again
+//! from `E::ROCircuit`. The challenges are bits of scalars of the non-native
+//! field `E::Scalar`, so the circuit does not compute with scalars but only
+//! multiplies points by bits: the products of the challenges that the
+//! verifier needs, the tensor `s` of the challenges and `b_hat`, are applied
+//! to the generators by folding them round by round, as the prover does,
+//! scaled by the product `rho` of the `r_i^2` so that no challenge is
+//! inverted. The points that the inverses scale are hints, of
+//! `EvaluationEngine::verifier_msm`, checked by multiplying them back by the
+//! challenges.
+use std::iter;
+
+use bellpepper_core::{boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::{Field, PrimeField, PrimeFieldBits};
+
+use crate::{
+  constants::NUM_CHALLENGE_BITS,
+  gadgets::{le_bits_to_num, AllocatedPoint},
+  provider::ipa_pc::IPAVerifierMSM,
+  traits::{Engine, Group, ROCircuitTrait, ROConstantsCircuit},
+};
+
+/// Allocates the little-endian bits of `scalar`
+fn alloc_scalar_bits<E: Engine, CS: ConstraintSystem<E::Base>>(
+  mut cs: CS,
+  scalar: Option<E::Scalar>,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  let bits = scalar.map(|s| s.to_le_bits());
+  (0..E::Scalar::NUM_BITS as usize)
+    .map(|i| AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), bits.as_ref().map(|b| b[i])))
+    .collect()
+}
+
+/// The limbs of [`NUM_CHALLENGE_BITS`] of `bits`, in which the random oracle
+/// absorbs a scalar
+fn pack_limbs<F: PrimeField + PrimeFieldBits, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[AllocatedBit],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  bits
+    .chunks(NUM_CHALLENGE_BITS)
+    .enumerate()
+    .map(|(i, limb)| le_bits_to_num(cs.namespace(|| format!("limb {i}")), limb))
+    .collect()
+}
+
+/// `r^2 * point`, for the bits `r` of a challenge
+fn mul_square<G: Group, CS: ConstraintSystem<G::Base>>(
+  mut cs: CS,
+  point: &AllocatedPoint<G>,
+  r: &[AllocatedBit],
+) -> Result<AllocatedPoint<G>, SynthesisError> {
+  let p = point.scalar_mul(cs.namespace(|| "r"), r)?;
+  p.scalar_mul(cs.namespace(|| "r^2"), r)
+}
+
+/// Allocates the hint `coords`, and checks that `r^2` times it is `expected`
+/// for each challenge `r` of `challenges`
+fn alloc_hint<G: Group, CS: ConstraintSystem<G::Base>>(
+  mut cs: CS,
+  coords: Option<(G::Base, G::Base, bool)>,
+  challenges: &[Vec<AllocatedBit>],
+  expected: &AllocatedPoint<G>,
+) -> Result<AllocatedPoint<G>, SynthesisError> {
+  let hint = AllocatedPoint::alloc(cs.namespace(|| "hint"), coords)?;
+  hint.check_on_curve(cs.namespace(|| "on curve"))?;
+  let mut scaled = hint.clone();
+  for (i, r) in challenges.iter().enumerate() {
+    scaled = mul_square(cs.namespace(|| format!("scale {i}")), &scaled, r)?;
+  }
+  let (x, y, is_infinity) = expected.get_coordinates();
+  scaled.enforce_coordinates(cs.namespace(|| "matches"), &[
+    x.clone(),
+    y.clone(),
+    is_infinity.clone(),
+  ])?;
+  Ok(hint)
+}
+
+/// Verifies an `InnerProductArgument` with `point.len()` rounds that the
+/// commitment allocated as `comm` (its coordinates `x`, `y` and
+/// `is_infinity`) opens to `eval` at `point`, given by the little-endian bits
+/// of their scalars, uniquely: the caller binds them to canonical scalars.
+/// `generators` are given by `EvaluationEngine::verifier_generators`, and
+/// `msm` by `EvaluationEngine::verifier_msm`, `None` at shape synthesis.
+///
+/// The check of the verifier, `P_hat = a_hat * (<s, G> + b_hat * r_c * H)`
+/// for `P_hat = comm + sum_i r_i^2 * L_i + sum_i r_i^-2 * R_i + eval * r_c *
+/// H`, is checked for the challenges squeezed inside the circuit, with
+/// `R_hat_i = r_i^-2 * R_i`, `G_hat = <s, G>` and `H_hat = b_hat * r_c * H`
+/// checked as the hints of `msm`.
+pub fn check_ipa_msm<E: Engine, CS: ConstraintSystem<E::Base>>(
+  mut cs: CS,
+  generators: &[(E::Base, E::Base, bool)],
+  comm: &[AllocatedNum<E::Base>],
+  point: &[Vec<AllocatedBit>],
+  eval: &[AllocatedBit],
+  msm: Option<&IPAVerifierMSM<E>>,
+) -> Result<(), SynthesisError> {
+  let num_rounds = point.len();
+  let num_bits = E::Scalar::NUM_BITS as usize;
+  if comm.len() != 3
+    || num_rounds >= usize::BITS as usize
+    || generators.len() != (1 << num_rounds) + 1
+    || eval.len() != num_bits
+    || point.iter().any(|x| x.len() != num_bits)
+    || msm.is_some_and(|msm| {
+      msm.L_vec.len() != num_rounds
+        || msm.R_vec.len() != num_rounds
+        || msm.R_hat.len() != num_rounds
+    })
+  {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let (G, H) = generators.split_at(1 << num_rounds);
+
+  // the commitment, as a point on the curve
+  let comm_coords = comm
+    .iter()
+    .map(|c| c.get_value())
+    .collect::<Option<Vec<_>>>()
+    .map(|c| (c[0], c[1], c[2] == E::Base::ONE));
+  let comm_point = AllocatedPoint::<E::GE>::alloc(cs.namespace(|| "comm"), comm_coords)?;
+  comm_point.enforce_coordinates(cs.namespace(|| "comm matches"), comm)?;
+  comm_point.check_on_curve(cs.namespace(|| "comm on curve"))?;
+
+  // the challenges, the first from the instance, and each next one from the
+  // previous one and the cross terms of its round
+  let constants = ROConstantsCircuit::<E>::default();
+  let limbs = num_bits.div_ceil(NUM_CHALLENGE_BITS);
+  let mut ro = E::ROCircuit::new(constants.clone(), comm.len() + (num_rounds + 1) * limbs);
+  comm.iter().for_each(|c| ro.absorb(c));
+  for (i, bits) in point.iter().map(Vec::as_slice).chain(iter::once(eval)).enumerate() {
+    pack_limbs(cs.namespace(|| format!("instance scalar {i}")), bits)?
+      .iter()
+      .for_each(|limb| ro.absorb(limb));
+  }
+  let r_c = ro.squeeze(cs.namespace(|| "r_c"), NUM_CHALLENGE_BITS)?;
+
+  let mut r: Vec<Vec<AllocatedBit>> = Vec::with_capacity(num_rounds);
+  let mut cross_terms = Vec::with_capacity(num_rounds);
+  for i in 0..num_rounds {
+    let mut ns = cs.namespace(|| format!("round {i}"));
+    let L = AllocatedPoint::<E::GE>::alloc(ns.namespace(|| "L"), msm.map(|msm| msm.L_vec[i]))?;
+    L.check_on_curve(ns.namespace(|| "L on curve"))?;
+    let R = AllocatedPoint::<E::GE>::alloc(ns.namespace(|| "R"), msm.map(|msm| msm.R_vec[i]))?;
+    R.check_on_curve(ns.namespace(|| "R on curve"))?;
+
+    let mut ro = E::ROCircuit::new(constants.clone(), 7);
+    let previous = r.last().unwrap_or(&r_c);
+    ro.absorb(&le_bits_to_num(ns.namespace(|| "previous challenge"), previous)?);
+    for p in [&L, &R] {
+      let (x, y, is_infinity) = p.get_coordinates();
+      [x, y, is_infinity].into_iter().for_each(|c| ro.absorb(c));
+    }
+    r.push(ro.squeeze(ns.namespace(|| "r"), NUM_CHALLENGE_BITS)?);
+    cross_terms.push((L, R));
+  }
+
+  // P_hat, with the hints R_hat_i
+  let mut P_hat = comm_point;
+  for (i, ((L, R), r_i)) in cross_terms.iter().zip(&r).enumerate() {
+    let mut ns = cs.namespace(|| format!("cross terms {i}"));
+    let L_term = mul_square(ns.namespace(|| "r^2 * L"), L, r_i)?;
+    P_hat = P_hat.add(ns.namespace(|| "add L"), &L_term)?;
+    let R_hat = alloc_hint(
+      ns.namespace(|| "R_hat"),
+      msm.map(|msm| msm.R_hat[i]),
+      std::slice::from_ref(r_i),
+      R,
+    )?;
+    P_hat = P_hat.add(ns.namespace(|| "add R_hat"), &R_hat)?;
+  }
+  let H = AllocatedPoint::<E::GE>::alloc_constant(cs.namespace(|| "H"), H[0])?;
+  let H_c = H.scalar_mul(cs.namespace(|| "r_c * H"), &r_c)?;
+  let eval_term = H_c.scalar_mul(cs.namespace(|| "eval * r_c * H"), eval)?;
+  P_hat = P_hat.add(cs.namespace(|| "add eval"), &eval_term)?;
+
+  // rho * <s, G>: the halves of the generators fold into r_i * G_L + r_i^3 *
+  // G_R, as the prover folds them into r_i^-1 * G_L + r_i * G_R
+  let mut folded = G
+    .iter()
+    .enumerate()
+    .map(|(j, g)| AllocatedPoint::alloc_constant(cs.namespace(|| format!("G_{j}")), *g))
+    .collect::<Result<Vec<_>, _>>()?;
+  for (i, r_i) in r.iter().enumerate() {
+    let (left, right) = folded.split_at(folded.len() / 2);
+    folded = left
+      .iter()
+      .zip(right)
+      .enumerate()
+      .map(|(j, (g_L, g_R))| {
+        let mut ns = cs.namespace(|| format!("fold {i} of G_{j}"));
+        let g_R = mul_square(ns.namespace(|| "r^2 * G_R"), g_R, r_i)?;
+        let sum = g_L.add(ns.namespace(|| "G_L + r^2 * G_R"), &g_R)?;
+        sum.scalar_mul(ns.namespace(|| "r"), r_i)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+  }
+  let G_hat = alloc_hint(cs.namespace(|| "G_hat"), msm.map(|msm| msm.G_hat), &r, &folded[0])?;
+
+  // rho * b_hat * r_c * H: b_hat is the product of the (1 - x_i) * r_i^-1 +
+  // x_i * r_i, so each round scales by r_i * (1 + x_i * (r_i^2 - 1))
+  let mut B = H_c;
+  for (i, (r_i, x_i)) in r.iter().zip(point).enumerate() {
+    let mut ns = cs.namespace(|| format!("fold {i} of H"));
+    let shifted = mul_square(ns.namespace(|| "r^2 * B"), &B, r_i)?;
+    let minus_B = B.negate(ns.namespace(|| "-B"))?;
+    let diff = shifted.add(ns.namespace(|| "r^2 * B - B"), &minus_B)?;
+    let diff = diff.scalar_mul(ns.namespace(|| "x * (r^2 * B - B)"), x_i)?;
+    let sum = B.add(ns.namespace(|| "B + x * (r^2 * B - B)"), &diff)?;
+    B = sum.scalar_mul(ns.namespace(|| "r"), r_i)?;
+  }
+  let H_hat = alloc_hint(cs.namespace(|| "H_hat"), msm.map(|msm| msm.H_hat), &r, &B)?;
+
+  // P_hat - a_hat * (G_hat + H_hat) is the identity
+  let a_hat = alloc_scalar_bits::<E, _>(cs.namespace(|| "a_hat"), msm.map(|msm| msm.a_hat))?;
+  let rhs = G_hat.add(cs.namespace(|| "G_hat + H_hat"), &H_hat)?;
+  let rhs = rhs.scalar_mul(cs.namespace(|| "a_hat * (G_hat + H_hat)"), &a_hat)?;
+  let minus_rhs = rhs.negate(cs.namespace(|| "-rhs"))?;
+  let diff = P_hat.add(cs.namespace(|| "P_hat - rhs"), &minus_rhs)?;
+  cs.enforce(
+    || "check is the identity",
+    |lc| lc + diff.is_infinity.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + CS::one(),
+  );
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    bellpepper::{
+      r1cs::{NovaShape, NovaWitness},
+      solver::SatisfyingAssignment,
+      test_shape_cs::TestShapeCS,
+    },
+    provider::{
+      ipa_pc::EvaluationEngine, util::test_utils::random_poly_with_eval, Bn256EngineKZG,
+      GrumpkinEngine,
+    },
+    traits::{
+      commitment::{CommitmentEngineTrait, CommitmentTrait},
+      evaluation::EvaluationEngineTrait,
+      snark::default_ck_hint,
+      TranscriptEngineTrait,
+    },
+  };
+
+  // arguments over Grumpkin are verified in a circuit over the scalar field of BN254
+  type E = GrumpkinEngine;
+  type EE = EvaluationEngine<E>;
+  type Scalar = <E as Engine>::Scalar;
+
+  fn synthesize_verifier<CS: ConstraintSystem<<E as Engine>::Base>>(
+    mut cs: CS,
+    generators: &[(<E as Engine>::Base, <E as Engine>::Base, bool)],
+    comm: (<E as Engine>::Base, <E as Engine>::Base, bool),
+    point: &[Scalar],
+    eval: &Scalar,
+    msm: Option<&IPAVerifierMSM<E>>,
+  ) -> Result<(), SynthesisError> {
+    let is_infinity = if comm.2 { <E as Engine>::Base::ONE } else { <E as Engine>::Base::ZERO };
+    let comm = [comm.0, comm.1, is_infinity]
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc_input(cs.namespace(|| format!("comm {i}")), || Ok(*v)))
+      .collect::<Result<Vec<_>, _>>()?;
+    let point = point
+      .iter()
+      .enumerate()
+      .map(|(i, x)| alloc_scalar_bits::<E, _>(cs.namespace(|| format!("point {i}")), Some(*x)))
+      .collect::<Result<Vec<_>, _>>()?;
+    let eval = alloc_scalar_bits::<E, _>(cs.namespace(|| "eval"), Some(*eval))?;
+    check_ipa_msm(cs.namespace(|| "verify"), generators, &comm, &point, &eval, msm)
+  }
+
+  #[test]
+  fn test_check_ipa_msm() {
+    let num_vars = 2;
+    let (poly, point, eval) = random_poly_with_eval::<E, _>(num_vars, &mut OsRng);
+    let ck = Arc::new(<E as Engine>::CE::setup(b"test", 1 << num_vars));
+    let comm = <E as Engine>::CE::commit(&ck, poly.evaluations());
+    let (pk, vk) = EE::setup(ck.clone());
+
+    let arg = EE::prove_for_circuit(&ck, &pk, &comm, poly.evaluations(), &point, &eval).unwrap();
+    let generators = EE::verifier_generators(&vk, num_vars);
+    let msm = EE::verifier_msm(&vk, &comm, &point, &eval, &arg).unwrap();
+    msm.check(&generators, comm.to_coordinates()).unwrap();
+
+    let mut cs: TestShapeCS<Bn256EngineKZG> = TestShapeCS::new();
+    let _ = synthesize_verifier(
+      cs.namespace(|| "synthesize"),
+      &generators,
+      comm.to_coordinates(),
+      &point,
+      &eval,
+      None,
+    );
+    let (shape, ck_circuit) = cs.r1cs_shape_and_key(&*default_ck_hint());
+    let is_sat = |eval: &Scalar, msm: &IPAVerifierMSM<E>| {
+      let mut cs = SatisfyingAssignment::<Bn256EngineKZG>::new();
+      synthesize_verifier(
+        cs.namespace(|| "synthesize"),
+        &generators,
+        comm.to_coordinates(),
+        &point,
+        eval,
+        Some(msm),
+      )
+      .unwrap();
+      let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck_circuit).unwrap();
+      shape.is_sat(&ck_circuit, &inst, &witness).is_ok()
+    };
+    assert!(is_sat(&eval, &msm));
+
+    // the argument does not check for a wrong evaluation
+    let wrong_eval = eval + Scalar::ONE;
+    let wrong_msm = EE::verifier_msm(&vk, &comm, &point, &wrong_eval, &arg).unwrap();
+    assert!(wrong_msm.check(&generators, comm.to_coordinates()).is_err());
+    assert!(!is_sat(&wrong_eval, &msm));
+    assert!(!is_sat(&wrong_eval, &wrong_msm));
+
+    // nor with a wrong final message or wrong hints
+    let wrong_a_hat = IPAVerifierMSM { a_hat: msm.a_hat + Scalar::ONE, ..msm.clone() };
+    let wrong_R_hat = IPAVerifierMSM { R_hat: vec![msm.R_hat[1], msm.R_hat[0]], ..msm.clone() };
+    let wrong_G_hat = IPAVerifierMSM { G_hat: msm.H_hat, H_hat: msm.G_hat, ..msm.clone() };
+    for msm in &[wrong_a_hat, wrong_R_hat, wrong_G_hat] {
+      assert!(!is_sat(&eval, msm));
+    }
+
+    // nor for an argument proven with the challenges of a transcript
+    let mut transcript = <E as Engine>::TE::new(b"test");
+    let arg =
+      EE::prove(&ck, &pk, &mut transcript, &comm, poly.evaluations(), &point, &eval).unwrap();
+    let msm = EE::verifier_msm(&vk, &comm, &point, &eval, &arg).unwrap();
+    assert!(msm.check(&generators, comm.to_coordinates()).is_err());
+    assert!(!is_sat(&eval, &msm));
+  }
+}
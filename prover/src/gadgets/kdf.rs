@@ -0,0 +1,163 @@
+//! HMAC-SHA256 and HKDF (RFC 5869) over byte strings inside circuits, with
+//! the `HKDF-Expand-Label` of TLS 1.3 (RFC 8446, section 7.1), so that keys
+//! derived from a secret are proven to be derived from it.
+//!
+//! Byte strings are the bits of their bytes, most significant bit first (see
+//! `alloc_bytes`), as the SHA-256 gadget hashes them.
+use bellpepper::gadgets::sha256::sha256;
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use super::bytes::{constant_bytes, xor_bytes};
+
+/// The length of SHA-256 digests
+pub const HASH_LEN: usize = 32;
+
+/// The length of the SHA-256 blocks HMAC pads its keys to
+const BLOCK_LEN: usize = 64;
+
+/// The prefix of the labels of the TLS 1.3 key schedule
+pub const TLS13_LABEL_PREFIX: &[u8] = b"tls13 ";
+
+/// HMAC-SHA256 of `message` under `key`, of at most 64 bytes
+pub fn hmac_sha256<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  key: &[Boolean],
+  message: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if key.len() > 8 * BLOCK_LEN {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let mut padded = key.to_vec();
+  padded.resize(8 * BLOCK_LEN, Boolean::constant(false));
+
+  // XORs with constants are free
+  let inner_key = xor_bytes(cs.namespace(|| "ipad"), &padded, &constant_bytes(&[0x36; BLOCK_LEN]))?;
+  let outer_key = xor_bytes(cs.namespace(|| "opad"), &padded, &constant_bytes(&[0x5c; BLOCK_LEN]))?;
+
+  let inner = sha256(cs.namespace(|| "inner hash"), &[inner_key, message.to_vec()].concat())?;
+  sha256(cs.namespace(|| "outer hash"), &[outer_key, inner].concat())
+}
+
+/// `HKDF-Extract(salt, ikm)`
+pub fn hkdf_extract<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  salt: &[Boolean],
+  ikm: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  hmac_sha256(cs, salt, ikm)
+}
+
+/// `HKDF-Expand(prk, info, len)`
+pub fn hkdf_expand<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  prk: &[Boolean],
+  info: &[Boolean],
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if len > 255 * HASH_LEN {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  // T(i) = HMAC(prk, T(i - 1) || info || i)
+  let mut okm = Vec::with_capacity(8 * len);
+  let mut block = Vec::new();
+  for i in 1..=len.div_ceil(HASH_LEN) {
+    let message = [block, info.to_vec(), constant_bytes(&[i as u8])].concat();
+    block = hmac_sha256(cs.namespace(|| format!("block {i}")), prk, &message)?;
+    okm.extend_from_slice(&block);
+  }
+  okm.truncate(8 * len);
+  Ok(okm)
+}
+
+/// The `HkdfLabel` of `HKDF-Expand-Label`: the length of the output, then
+/// the label, prefixed by `prefix`, and the context, both length-prefixed
+pub fn hkdf_label(
+  prefix: &[u8],
+  label: &[u8],
+  context: &[Boolean],
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let full_label = [prefix, label].concat();
+  let len = u16::try_from(len).map_err(|_| SynthesisError::Unsatisfiable)?;
+  let label_len = u8::try_from(full_label.len()).map_err(|_| SynthesisError::Unsatisfiable)?;
+  let context_len = u8::try_from(context.len() / 8).map_err(|_| SynthesisError::Unsatisfiable)?;
+
+  let mut prefix = len.to_be_bytes().to_vec();
+  prefix.push(label_len);
+  prefix.extend_from_slice(&full_label);
+  prefix.push(context_len);
+  Ok([constant_bytes(&prefix), context.to_vec()].concat())
+}
+
+/// `HKDF-Expand-Label(secret, label, context, len)` of TLS 1.3
+pub fn hkdf_expand_label<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  secret: &[Boolean],
+  label: &[u8],
+  context: &[Boolean],
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let info = hkdf_label(TLS13_LABEL_PREFIX, label, context, len)?;
+  hkdf_expand(cs, secret, &info, len)
+}
+
+/// `Derive-Secret(secret, label, messages)` of TLS 1.3, from the hash of the
+/// messages
+pub fn derive_secret<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  secret: &[Boolean],
+  label: &[u8],
+  transcript_hash: &[Boolean],
+) -> Result<Vec<Boolean>, SynthesisError> {
+  hkdf_expand_label(cs, secret, label, transcript_hash, HASH_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::gadgets::{alloc_bytes, bytes_value};
+
+  type F = halo2curves::bn256::Fr;
+
+  fn alloc(cs: &mut TestConstraintSystem<F>, name: &str, bytes: &[u8]) -> Vec<Boolean> {
+    alloc_bytes(cs.namespace(|| name.to_string()), Some(bytes), bytes.len()).unwrap()
+  }
+
+  // test case 1 of RFC 5869
+  #[test]
+  fn test_hkdf() {
+    let ikm = [0x0b; 22];
+    let salt = (0x00..=0x0c).collect::<Vec<u8>>();
+    let info = (0xf0..=0xf9).collect::<Vec<u8>>();
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let (ikm, salt, info) =
+      (alloc(&mut cs, "ikm", &ikm), alloc(&mut cs, "salt", &salt), alloc(&mut cs, "info", &info));
+    let prk = hkdf_extract(cs.namespace(|| "extract"), &salt, &ikm).unwrap();
+    let okm = hkdf_expand(cs.namespace(|| "expand"), &prk, &info, 42).unwrap();
+    assert!(cs.is_satisfied());
+
+    assert_eq!(
+      hex::encode(bytes_value(&prk).unwrap()),
+      "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+    );
+    assert_eq!(
+      hex::encode(bytes_value(&okm).unwrap()),
+      "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+    );
+  }
+
+  #[test]
+  fn test_hkdf_label() {
+    let context = constant_bytes(&[0xaa; 32]);
+    let info = hkdf_label(TLS13_LABEL_PREFIX, b"key", &context, 32).unwrap();
+    let expected = [&[0, 32, 9][..], b"tls13 key", &[32], &[0xaa; 32]].concat();
+    assert_eq!(bytes_value(&info).unwrap(), expected);
+
+    assert!(hkdf_label(TLS13_LABEL_PREFIX, &[b'a'; 250], &[], 32).is_err());
+    assert!(hkdf_label(TLS13_LABEL_PREFIX, b"key", &constant_bytes(&[0; 256]), 32).is_err());
+  }
+}
@@ -1,24 +1,79 @@
 //! This module implements various gadgets necessary for Nova and applications
-//! built with Nova.
-mod ecc;
+//! built with Nova. Without the `prove` feature, only their native
+//! conversions, which verifiers share, are compiled.
+#[cfg(feature = "prove")] mod blake3;
+#[cfg(feature = "prove")]
+pub use self::blake3::{blake3, blake3_keyed};
+
+#[cfg(feature = "prove")] mod blob;
+#[cfg(feature = "prove")] pub use blob::BlobKey;
+
+#[cfg(feature = "prove")] mod bytes;
+#[cfg(feature = "prove")] pub(crate) use bytes::range_check;
+#[cfg(feature = "prove")]
+pub use bytes::{
+  alloc_bytes, alloc_packed, bytes_value, constant_bytes, mask_bytes, pack_bytes, padded_equal,
+  prefix_mask, substring_at, unpack_bytes, xor_bytes, BYTES_PER_ELEMENT,
+};
+
+#[cfg(feature = "prove")] mod decimal;
+#[cfg(feature = "prove")]
+pub use decimal::{decimal_bits, parse_decimal, parse_integer};
+
+#[cfg(feature = "prove")] pub mod ecdsa;
+
+#[cfg(feature = "prove")] mod ecc;
+#[cfg(feature = "prove")]
 pub(crate) use ecc::AllocatedPoint;
 
+#[cfg(feature = "prove")] mod encoding;
+#[cfg(feature = "prove")]
+pub use encoding::{base64_decode, percent_decode, Alphabet, Base64Decoded, PercentDecoded};
+
+#[cfg(feature = "prove")] mod ipa;
+#[cfg(feature = "prove")] pub use ipa::check_ipa_msm;
+
+#[cfg(feature = "prove")] pub mod kdf;
+
+#[cfg(feature = "prove")] mod padding;
+#[cfg(all(feature = "prove", any(test, feature = "test-utils")))]
+pub use padding::check_padding;
+#[cfg(feature = "prove")]
+pub use padding::{enforce_equal_if, select_if};
+
+// adapted from bellman-bignat, whose limb arithmetic unwraps values it has just
+// range-checked
+#[cfg(feature = "prove")]
+#[allow(clippy::unwrap_used)]
 mod nonnative;
-pub(crate) use nonnative::{
-  bignat::{nat_to_limbs, BigNat},
-  util::{f_to_nat, Num},
-};
+#[cfg(feature = "prove")]
+pub(crate) use nonnative::{bignat::BigNat, util::Num};
+
+pub mod nullifier;
 
-mod r1cs;
+#[cfg(feature = "prove")] mod r1cs;
+#[cfg(feature = "prove")]
 pub(crate) use r1cs::{
   conditionally_select_alloc_relaxed_r1cs,
   conditionally_select_vec_allocated_relaxed_r1cs_instance, AllocatedR1CSInstance,
   AllocatedRelaxedR1CSInstance,
 };
 
-mod utils;
-#[cfg(test)] pub(crate) use utils::alloc_one;
+#[cfg(feature = "prove")] pub mod rsa;
+
+#[cfg(feature = "prove")] mod sha256;
+#[cfg(feature = "prove")] pub use sha256::sha256_var;
+
+#[cfg(feature = "prove")] mod utf8;
+#[cfg(feature = "prove")] pub use utf8::enforce_utf8;
+
+#[cfg(feature = "prove")] mod utils;
+#[cfg(feature = "prove")]
 pub(crate) use utils::{
-  alloc_bignat_constant, alloc_num_equals, alloc_scalar_as_base, alloc_zero,
-  conditionally_select_allocated_bit, conditionally_select_bignat, le_bits_to_num, scalar_as_base,
+  alloc_bignat_constant, alloc_num_equals, alloc_one, alloc_scalar_as_base, alloc_zero,
+  conditionally_select_allocated_bit, conditionally_select_bignat, le_bits_to_num,
+  select_num_or_zero,
 };
+
+mod native;
+pub(crate) use native::{f_to_nat, nat_to_limbs, scalar_as_base};
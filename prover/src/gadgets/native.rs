@@ -0,0 +1,46 @@
+//! The conversions of field elements the gadgets share with the verifiers,
+//! which absorb instances outside of circuits as the circuits do.
+use ff::{Field, PrimeField, PrimeFieldBits};
+use num_bigint::{BigInt, Sign};
+
+use crate::{scalar_bytes::ScalarBytesLe, traits::Engine};
+
+/// interpret scalar as base
+pub fn scalar_as_base<E: Engine>(input: E::Scalar) -> E::Base {
+  let input_bits = input.to_le_bits();
+  let mut mult = E::Base::ONE;
+  let mut val = E::Base::ZERO;
+  for bit in input_bits {
+    if bit {
+      val += mult;
+    }
+    mult = mult + mult;
+  }
+  val
+}
+
+/// Convert a field element to a natural number
+pub fn f_to_nat<Scalar: PrimeField>(f: &Scalar) -> BigInt {
+  BigInt::from_bytes_le(Sign::Plus, ScalarBytesLe::new(f).as_bytes())
+}
+
+/// Convert a natural number to a field element.
+/// Returns `None` if the number is too big for the field.
+pub fn nat_to_f<Scalar: PrimeField>(n: &BigInt) -> Option<Scalar> {
+  Scalar::from_str_vartime(&format!("{n}"))
+}
+
+/// Compute the limbs encoding a natural number, least significant first.
+/// The limbs are assumed to be based the `limb_width` power of 2; returns
+/// `None` if the number does not fit in `n_limbs` of them.
+pub fn nat_to_limbs<Scalar: PrimeField>(
+  nat: &BigInt,
+  limb_width: usize,
+  n_limbs: usize,
+) -> Option<Vec<Scalar>> {
+  if nat.bits() as usize > n_limbs * limb_width {
+    return None;
+  }
+  let mask = (BigInt::from(1) << limb_width) - 1;
+  (0..n_limbs).map(|i| nat_to_f(&((nat >> (i * limb_width)) & &mask))).collect()
+}
@@ -11,9 +11,10 @@ use num_bigint::BigInt;
 use num_traits::cast::ToPrimitive;
 
 use super::{
-  util::{f_to_nat, nat_to_f, Bitvector, Num},
+  util::{Bitvector, Num},
   OptionExt,
 };
+use crate::gadgets::native::{f_to_nat, nat_to_f, nat_to_limbs};
 
 /// Compute the natural number represented by an array of limbs.
 /// The limbs are assumed to be based the `limb_width` power of 2.
@@ -35,31 +36,6 @@ fn int_with_n_ones(n: usize) -> BigInt {
   m
 }
 
-/// Compute the limbs encoding a natural number.
-/// The limbs are assumed to be based the `limb_width` power of 2.
-pub fn nat_to_limbs<Scalar: PrimeField>(
-  nat: &BigInt,
-  limb_width: usize,
-  n_limbs: usize,
-) -> Result<Vec<Scalar>, SynthesisError> {
-  let mask = int_with_n_ones(limb_width);
-  let mut nat = nat.clone();
-  if nat.bits() as usize <= n_limbs * limb_width {
-    Ok(
-      (0..n_limbs)
-        .map(|_| {
-          let r = &nat & &mask;
-          nat >>= limb_width as u32;
-          nat_to_f(&r).unwrap()
-        })
-        .collect(),
-    )
-  } else {
-    eprintln!("nat {nat} does not fit in {n_limbs} limbs of width {limb_width}");
-    Err(SynthesisError::Unsatisfiable)
-  }
-}
-
 #[derive(Clone, PartialEq, Eq)]
 pub struct BigNatParams {
   pub min_bits:   usize,
@@ -175,8 +151,10 @@ impl<Scalar: PrimeField> BigNat<Scalar> {
     CS: ConstraintSystem<Scalar>,
     F: FnOnce() -> Result<BigInt, SynthesisError>,
   {
-    let all_values_cell =
-      f().and_then(|v| Ok((nat_to_limbs::<Scalar>(&v, limb_width, n_limbs)?, v)));
+    let all_values_cell = f().and_then(|v| {
+      let limbs = nat_to_limbs::<Scalar>(&v, limb_width, n_limbs);
+      Ok((limbs.ok_or(SynthesisError::Unsatisfiable)?, v))
+    });
     let mut value = None;
     let mut limb_values = Vec::new();
     let limbs = (0..n_limbs)
@@ -1,14 +1,9 @@
-use std::{
-  convert::From,
-  io::{self, Write},
-};
+use std::convert::From;
 
 use bellpepper_core::{
   num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError, Variable,
 };
-use byteorder::WriteBytesExt;
 use ff::PrimeField;
-use num_bigint::{BigInt, Sign};
 
 use super::{BitAccess, OptionExt};
 
@@ -197,27 +192,6 @@ impl<Scalar: PrimeField> From<AllocatedNum<Scalar>> for Num<Scalar> {
   }
 }
 
-fn write_be<F: PrimeField, W: Write>(f: &F, mut writer: W) -> io::Result<()> {
-  for digit in f.to_repr().as_ref().iter().rev() {
-    writer.write_u8(*digit)?;
-  }
-
-  Ok(())
-}
-
-/// Convert a field element to a natural number
-pub fn f_to_nat<Scalar: PrimeField>(f: &Scalar) -> BigInt {
-  let mut s = Vec::new();
-  write_be(f, &mut s).unwrap();
-  BigInt::from_bytes_le(Sign::Plus, f.to_repr().as_ref())
-}
-
-/// Convert a natural number to a field element.
-/// Returns `None` if the number is too big for the field.
-pub fn nat_to_f<Scalar: PrimeField>(n: &BigInt) -> Option<Scalar> {
-  Scalar::from_str_vartime(&format!("{n}"))
-}
-
 #[cfg(test)]
 mod tests {
   use bitvec::field::BitField as _;
@@ -225,7 +199,7 @@ mod tests {
   use rand::SeedableRng;
   use rand_chacha::ChaCha20Rng;
 
-  // the write_be function above assumes Field::to_repr() outputs a representation
+  // `f_to_nat` assumes Field::to_repr() outputs a representation
   // that's an instance of `AsRef<[u8]>` in lower endian. We test that here,
   // as this is not what the I2OSP standard recommends and may change in some
   // implementations.
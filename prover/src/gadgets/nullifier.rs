@@ -0,0 +1,91 @@
+//! Nullifiers: tags of a client secret in an application context, revealed
+//! in the public IO of a proof so that a verifier accepts one proof per
+//! secret and context, without learning the secret.
+//!
+//! A nullifier is a PRF of the secret keyed to the context, the Poseidon hash
+//! of a domain tag, the secret and the [`nullifier_context`] of the
+//! application, e.g. of its name and of a poll or an epoch. By convention, a
+//! step circuit deriving one takes the context in its `z`, unchanged, and
+//! outputs the nullifier in its `z`, with [`alloc_nullifier`]. The verifier
+//! checks that the initial `z` holds the context of its own application, so
+//! that a nullifier of another context is not replayed, and records the
+//! nullifier of the outputs, rejecting the proofs of a nullifier it has seen.
+//!
+//! The circuit must also bind the secret to the credential it proves, e.g.
+//! as the key of a signature it verifies: the gadget only derives the
+//! nullifier of the secret it is given.
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::{provider::poseidon::PoseidonConstantsCircuit, traits::PrimeFieldExt};
+
+/// The domain tag of nullifiers, the integer of the bytes
+/// `edge-nullifier-1`, so that they differ from the other hashes of the
+/// secret
+fn domain<F: PrimeField>() -> F { F::from_u128(u128::from_be_bytes(*b"edge-nullifier-1")) }
+
+/// The context of the application `context` as a scalar, e.g. of
+/// `b"my-app/poll-42"`
+pub fn nullifier_context<F: PrimeFieldExt>(context: &[u8]) -> F {
+  let mut hasher = blake3::Hasher::new_derive_key("edge-prover nullifier context");
+  hasher.update(context);
+  let mut uniform = [0u8; 64];
+  hasher.finalize_xof().fill(&mut uniform);
+  F::from_uniform(&uniform)
+}
+
+/// The nullifier of `secret` in `context`
+pub fn nullifier<F: PrimeField>(secret: &F, context: &F) -> F {
+  PoseidonConstantsCircuit::<F>::default().hash(&[domain(), *secret, *context])
+}
+
+/// Allocates the nullifier of `secret` in `context`, the same as
+/// [`nullifier`]
+#[cfg(feature = "prove")]
+pub fn alloc_nullifier<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  secret: &AllocatedNum<F>,
+  context: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let tag = AllocatedNum::alloc(cs.namespace(|| "domain"), || Ok(domain()))?;
+  cs.enforce(
+    || "domain is constant",
+    |lc| lc + tag.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + (domain(), CS::one()),
+  );
+  PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &[
+    tag,
+    secret.clone(),
+    context.clone(),
+  ])
+}
+
+#[cfg(all(test, feature = "prove"))]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  #[test]
+  fn test_nullifier() {
+    let secret = F::from(1234);
+    let context = nullifier_context::<F>(b"app/poll-1");
+    assert_ne!(context, nullifier_context::<F>(b"app/poll-2"));
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let s = AllocatedNum::alloc(cs.namespace(|| "secret"), || Ok(secret)).unwrap();
+    let c = AllocatedNum::alloc(cs.namespace(|| "context"), || Ok(context)).unwrap();
+    let n = alloc_nullifier(cs.namespace(|| "nullifier"), &s, &c).unwrap();
+    assert!(cs.is_satisfied());
+    assert_eq!(n.get_value(), Some(nullifier(&secret, &context)));
+
+    // a nullifier differs with the secret, and with the context
+    assert_ne!(nullifier(&F::from(1235), &context), nullifier(&secret, &context));
+    let other = nullifier_context::<F>(b"app/poll-2");
+    assert_ne!(nullifier(&secret, &other), nullifier(&secret, &context));
+  }
+}
@@ -0,0 +1,208 @@
+//! The convention for inputs of variable lengths padded to a bound, and the
+//! gadgets following it.
+//!
+//! An input of `len` elements, e.g. bytes or the units of a batch step, is
+//! padded to a bound `max_len`, and its slot `i` is enabled by the bit
+//! `i < len` of [`prefix_mask`], which enforces that the enabled slots are the
+//! first ones, and that they number `len`. A gadget then makes the same
+//! constraints for every slot, enabled or not, whatever the padding: a
+//! constraint over a slot holds only if the slot is enabled, with
+//! [`enforce_equal_if`], and the state a slot updates keeps its value if the
+//! slot is disabled, with [`select_if`]. Its outputs are then those of the
+//! input unpadded, which `check_padding` tests for any padding, with the
+//! `test-utils` feature.
+//!
+//! [`prefix_mask`]: super::prefix_mask
+use bellpepper::gadgets::boolean_utils::conditionally_select;
+#[cfg(any(test, feature = "test-utils"))]
+use bellpepper_core::test_cs::TestConstraintSystem;
+use bellpepper_core::{boolean::Boolean, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+#[cfg(any(test, feature = "test-utils"))]
+use super::bytes::alloc_bytes;
+#[cfg(any(test, feature = "test-utils"))]
+use crate::errors::NovaError;
+
+/// Enforces `a = b` if `enabled` is set
+pub fn enforce_equal_if<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  enabled: &Boolean,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+) {
+  cs.enforce(
+    || "equal if enabled",
+    |_| enabled.lc(CS::one(), F::ONE),
+    |lc| lc + a.get_variable() - b.get_variable(),
+    |lc| lc,
+  );
+}
+
+/// The state `next` if `enabled` is set, the state `current` otherwise
+pub fn select_if<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  enabled: &Boolean,
+  next: &[AllocatedNum<F>],
+  current: &[AllocatedNum<F>],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  if next.len() != current.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  next
+    .iter()
+    .zip(current)
+    .enumerate()
+    .map(|(i, (next, current))| {
+      conditionally_select(cs.namespace(|| format!("select {i}")), next, current, enabled)
+    })
+    .collect()
+}
+
+/// Checks that `gadget`, synthesized on the bytes of `input` followed by any
+/// padding up to `max_len`, and on the length of `input`, is satisfied with
+/// the outputs it has on `input` unpadded, and returns the case that is not
+/// otherwise, as a [`NovaError::InvalidPadding`].
+///
+/// The padding is zeros, ones, and arbitrary bytes.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn check_padding<F, G>(input: &[u8], max_len: usize, gadget: G) -> Result<(), NovaError>
+where
+  F: PrimeField,
+  G: Fn(
+    &mut TestConstraintSystem<F>,
+    &[Boolean],
+    &AllocatedNum<F>,
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError>, {
+  if input.len() > max_len {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let run = |padding: &[u8], name: &str| -> Result<Vec<F>, NovaError> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bytes = [input, padding].concat();
+    let bits = alloc_bytes(cs.namespace(|| "input"), Some(&bytes), bytes.len())?;
+    let len =
+      AllocatedNum::alloc_infallible(cs.namespace(|| "len"), || F::from(input.len() as u64));
+    let outputs = gadget(&mut cs, &bits, &len)?;
+    if let Some(constraint) = cs.which_is_unsatisfied() {
+      return Err(NovaError::InvalidPadding(format!(
+        "{name}, the constraint {constraint} is unsatisfied"
+      )));
+    }
+    outputs
+      .iter()
+      .map(|output| {
+        output
+          .get_value()
+          .ok_or_else(|| NovaError::InvalidPadding(format!("{name}, an output is unassigned")))
+      })
+      .collect()
+  };
+
+  let unpadded = run(&[], "unpadded")?;
+  let n = max_len - input.len();
+  let paddings = [
+    ("zeros", vec![0; n]),
+    ("ones", vec![0xff; n]),
+    ("arbitrary bytes", (0..n).map(|i| (i as u8).wrapping_mul(0x9d) ^ 0x5a).collect()),
+  ];
+  for (name, padding) in paddings {
+    let name = format!("padded with {name}");
+    let padded = run(&padding, &name)?;
+    if padded != unpadded {
+      return Err(NovaError::InvalidPadding(format!("{name}, the outputs differ")));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::num::Num;
+  use ff::Field;
+
+  use super::*;
+  use crate::gadgets::{alloc_packed, parse_integer, prefix_mask, sha256_var};
+
+  type F = halo2curves::bn256::Fr;
+
+  /// The sum of the first `len` bytes of `bytes`, following the convention
+  /// if `masked`
+  fn byte_sum(
+    cs: &mut TestConstraintSystem<F>,
+    bytes: &[Boolean],
+    len: &AllocatedNum<F>,
+    masked: bool,
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let mask = prefix_mask(cs.namespace(|| "mask"), len, bytes.len() / 8)?;
+    let mut sum = AllocatedNum::alloc_infallible(cs.namespace(|| "zero"), || F::ZERO);
+    for (i, byte) in bytes.chunks(8).enumerate() {
+      let mut cs = cs.namespace(|| format!("byte {i}"));
+      let one = TestConstraintSystem::<F>::one();
+      let value = byte.iter().enumerate().fold(Num::zero(), |num, (j, bit)| {
+        num.add_bool_with_coeff(one, bit, F::from(1 << (7 - j)))
+      });
+      let next = AllocatedNum::alloc(cs.namespace(|| "sum"), || {
+        sum
+          .get_value()
+          .zip(value.get_value())
+          .map(|(s, v)| s + v)
+          .ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      cs.enforce(
+        || "add",
+        |lc| lc + sum.get_variable() + &value.lc(F::ONE),
+        |lc| lc + one,
+        |lc| lc + next.get_variable(),
+      );
+      sum = match masked {
+        true => select_if(cs.namespace(|| "select"), &mask[i], &[next], &[sum])?.remove(0),
+        false => next,
+      };
+    }
+    Ok(vec![sum])
+  }
+
+  #[test]
+  fn test_check_padding() {
+    assert_eq!(
+      check_padding::<F, _>(b"abc", 8, |cs, bytes, len| byte_sum(cs, bytes, len, true)),
+      Ok(())
+    );
+    // the sum of the padding too is not that of the input
+    assert_eq!(
+      check_padding::<F, _>(b"abc", 8, |cs, bytes, len| byte_sum(cs, bytes, len, false)),
+      Err(NovaError::InvalidPadding("padded with ones, the outputs differ".into()))
+    );
+    assert_eq!(
+      check_padding::<F, _>(b"abc", 2, |cs, bytes, len| byte_sum(cs, bytes, len, true)),
+      Err(NovaError::InvalidInputLength)
+    );
+
+    // the gadgets of variable lengths follow the convention
+    assert_eq!(
+      check_padding::<F, _>(b"hello", 70, |cs, bytes, len| {
+        let digest = sha256_var(cs.namespace(|| "sha256"), bytes, len)?;
+        alloc_packed(cs.namespace(|| "digest"), &digest)
+      }),
+      Ok(())
+    );
+    assert_eq!(
+      check_padding::<F, _>(b"1234", 9, |cs, bytes, len| {
+        Ok(vec![parse_integer(cs.namespace(|| "integer"), bytes, len)?])
+      }),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_enforce_equal_if() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = AllocatedNum::alloc_infallible(cs.namespace(|| "a"), || F::from(1));
+    let b = AllocatedNum::alloc_infallible(cs.namespace(|| "b"), || F::from(2));
+    enforce_equal_if(cs.namespace(|| "disabled"), &Boolean::constant(false), &a, &b);
+    assert!(cs.is_satisfied());
+    enforce_equal_if(cs.namespace(|| "enabled"), &Boolean::constant(true), &a, &b);
+    assert!(!cs.is_satisfied());
+  }
+}
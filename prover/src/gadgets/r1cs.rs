@@ -6,9 +6,9 @@ use bellpepper_core::{ConstraintSystem, SynthesisError};
 use ff::Field;
 use itertools::Itertools as _;
 
-use super::nonnative::{
-  bignat::BigNat,
-  util::{f_to_nat, Num},
+use super::{
+  native::f_to_nat,
+  nonnative::{bignat::BigNat, util::Num},
 };
 use crate::{
   constants::{NUM_CHALLENGE_BITS, NUM_FE_WITHOUT_IO_FOR_NOVA_FOLD},
@@ -0,0 +1,329 @@
+//! RSA signature verification (RFC 8017) inside circuits, for the public
+//! exponent 65537 of nearly every key of the web PKI and SHA-256 digests:
+//! RSASSA-PKCS1-v1_5, as in RS256 tokens and most certificates, and
+//! RSASSA-PSS with salts of the length of the digest, as in PS256 tokens.
+//!
+//! Moduli and signatures are big integers of 32-bit limbs, non-native to the
+//! field, and `s^65537 mod n` takes 16 modular squarings and a modular
+//! multiplication, each proven by a witness quotient and remainder. Byte
+//! strings are the bits of their bytes, most significant bit first (see
+//! `alloc_bytes`), as the SHA-256 gadget hashes them.
+use bellpepper::gadgets::sha256::sha256;
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, LinearCombination, SynthesisError};
+use ff::PrimeField;
+use num_bigint::{BigInt, Sign};
+
+use super::{
+  alloc_bignat_constant,
+  bytes::{alloc_bytes, constant_bytes, xor_bytes},
+  kdf::HASH_LEN,
+  BigNat,
+};
+
+/// The public exponent the verification is specialized to
+pub const PUBLIC_EXPONENT: u32 = 65_537;
+
+/// The length of the salts of PSS signatures
+pub const PSS_SALT_LEN: usize = HASH_LEN;
+
+/// The width of the limbs of moduli and signatures
+const LIMB_WIDTH: usize = 32;
+
+/// The DER encoding of the `DigestInfo` of SHA-256, which precedes the digest
+/// in PKCS#1 v1.5 signatures
+const SHA256_DIGEST_INFO: [u8; 19] = [
+  0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+  0x00, 0x04, 0x20,
+];
+
+/// An RSA modulus, with its big-endian bytes
+#[derive(Clone)]
+pub struct RsaModulus<F: PrimeField> {
+  bytes: Vec<Boolean>,
+  nat:   BigNat<F>,
+}
+
+impl<F: PrimeField> RsaModulus<F> {
+  /// Allocates a modulus of `len` bytes, whose most significant bit is set
+  pub fn alloc<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    modulus: Option<&[u8]>,
+    len: usize,
+  ) -> Result<Self, SynthesisError> {
+    let bytes = alloc_bytes(cs.namespace(|| "bytes"), modulus, len)?;
+    let top = bytes.first().ok_or(SynthesisError::Unsatisfiable)?;
+    Boolean::enforce_equal(cs.namespace(|| "top bit"), top, &Boolean::constant(true))?;
+    let nat = BigNat::alloc_from_nat(
+      cs.namespace(|| "nat"),
+      || modulus.map(to_nat).ok_or(SynthesisError::AssignmentMissing),
+      LIMB_WIDTH,
+      n_limbs(len),
+    )?;
+    enforce_limbs(cs.namespace(|| "limbs"), &nat, &bytes)?;
+    Ok(Self::new(bytes, nat))
+  }
+
+  /// A pinned modulus
+  pub fn constant<CS: ConstraintSystem<F>>(cs: CS, modulus: &[u8]) -> Result<Self, SynthesisError> {
+    if !modulus.first().is_some_and(|b| b & 0x80 != 0) {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let nat = alloc_bignat_constant(cs, &to_nat(modulus), LIMB_WIDTH, n_limbs(modulus.len()))?;
+    Ok(Self::new(constant_bytes(modulus), nat))
+  }
+
+  fn new(bytes: Vec<Boolean>, mut nat: BigNat<F>) -> Self {
+    // the quotients of the reductions are sized after the least modulus
+    nat.params.min_bits = bytes.len() - 1;
+    Self { bytes, nat }
+  }
+
+  /// The bytes of the modulus
+  pub fn bytes(&self) -> &[Boolean] { &self.bytes }
+
+  /// The length of the modulus, and of its signatures, in bytes
+  pub fn byte_len(&self) -> usize { self.bytes.len() / 8 }
+}
+
+fn n_limbs(len: usize) -> usize { (8 * len).div_ceil(LIMB_WIDTH) }
+
+fn to_nat(bytes: &[u8]) -> BigInt { BigInt::from_bytes_be(Sign::Plus, bytes) }
+
+/// Enforces that the limbs of `nat` are the big-endian `bytes`
+fn enforce_limbs<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  nat: &BigNat<F>,
+  bytes: &[Boolean],
+) -> Result<(), SynthesisError> {
+  if bytes.len() > LIMB_WIDTH * nat.limbs.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let bits = bytes.iter().rev().collect::<Vec<_>>();
+  for (i, limb) in nat.limbs.iter().enumerate() {
+    let mut coeff = F::ONE;
+    let mut sum = LinearCombination::zero();
+    for bit in bits.iter().skip(i * LIMB_WIDTH).take(LIMB_WIDTH) {
+      sum = sum + &bit.lc(CS::one(), coeff);
+      coeff = coeff.double();
+    }
+    cs.enforce(|| format!("limb {i}"), |lc| lc, |lc| lc, |_| sum - limb);
+  }
+  Ok(())
+}
+
+fn enforce_equal_bits<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &[Boolean],
+  b: &[Boolean],
+) -> Result<(), SynthesisError> {
+  if a.len() != b.len() {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  for (i, (a, b)) in a.iter().zip(b).enumerate() {
+    Boolean::enforce_equal(cs.namespace(|| format!("bit {i}")), a, b)?;
+  }
+  Ok(())
+}
+
+/// `base^65537 mod modulus`
+fn pow_65537<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  base: &BigNat<F>,
+  modulus: &BigNat<F>,
+) -> Result<BigNat<F>, SynthesisError> {
+  let mut acc = base.clone();
+  for i in 0..16 {
+    acc = acc.mult_mod(cs.namespace(|| format!("square {i}")), &acc, modulus)?.1;
+  }
+  Ok(acc.mult_mod(cs.namespace(|| "multiply"), base, modulus)?.1)
+}
+
+/// RSAVP1: the message representative `s^65537 mod n` of the signature `s`
+/// under the modulus `n`, in bytes of the length of the modulus. Unlike RFC
+/// 8017, `s` is not required to be less than `n`, which only admits other
+/// signatures of the same messages.
+pub fn rsavp1<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  modulus: &RsaModulus<F>,
+  signature: Option<&[u8]>,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let len = modulus.byte_len();
+  if signature.is_some_and(|s| s.len() != len) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let s = BigNat::alloc_from_nat(
+    cs.namespace(|| "signature"),
+    || signature.map(to_nat).ok_or(SynthesisError::AssignmentMissing),
+    LIMB_WIDTH,
+    n_limbs(len),
+  )?;
+  s.assert_well_formed(cs.namespace(|| "signature rangecheck"))?;
+
+  let m = pow_65537(cs.namespace(|| "pow"), &s, &modulus.nat)?;
+  let value = m.value.as_ref().map(|m| {
+    let (_, bytes) = m.to_bytes_be();
+    [vec![0; len.saturating_sub(bytes.len())], bytes].concat()
+  });
+  let em = alloc_bytes(cs.namespace(|| "message"), value.as_deref(), len)?;
+  enforce_limbs(cs.namespace(|| "message limbs"), &m, &em)?;
+  Ok(em)
+}
+
+/// MGF1 over SHA-256 of `seed`, to `len` bytes
+fn mgf1<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  seed: &[Boolean],
+  len: usize,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  let mut mask = Vec::with_capacity(8 * len);
+  for counter in 0..len.div_ceil(HASH_LEN) as u32 {
+    let input = [seed.to_vec(), constant_bytes(&counter.to_be_bytes())].concat();
+    mask.extend(sha256(cs.namespace(|| format!("block {counter}")), &input)?);
+  }
+  mask.truncate(8 * len);
+  Ok(mask)
+}
+
+/// Enforces that `signature` is an RSASSA-PKCS1-v1_5 signature of the
+/// SHA-256 `digest` under `modulus`
+pub fn verify_pkcs1v15_sha256<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  modulus: &RsaModulus<F>,
+  signature: Option<&[u8]>,
+  digest: &[Boolean],
+) -> Result<(), SynthesisError> {
+  let len = modulus.byte_len();
+  let t_len = SHA256_DIGEST_INFO.len() + HASH_LEN;
+  if digest.len() != 8 * HASH_LEN || len < t_len + 11 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let em = rsavp1(cs.namespace(|| "rsavp1"), modulus, signature)?;
+
+  // EM = 0x00 || 0x01 || PS || 0x00 || DigestInfo || digest, PS of 0xff bytes
+  let mut prefix = vec![0x00, 0x01];
+  prefix.resize(len - t_len - 1, 0xff);
+  prefix.push(0x00);
+  prefix.extend_from_slice(&SHA256_DIGEST_INFO);
+  let expected = [constant_bytes(&prefix), digest.to_vec()].concat();
+  enforce_equal_bits(cs.namespace(|| "encoding"), &em, &expected)
+}
+
+/// Enforces that `signature` is an RSASSA-PSS signature of the SHA-256
+/// `digest` under `modulus`, with MGF1 over SHA-256 and a salt of
+/// [`PSS_SALT_LEN`] bytes
+pub fn verify_pss_sha256<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  modulus: &RsaModulus<F>,
+  signature: Option<&[u8]>,
+  digest: &[Boolean],
+) -> Result<(), SynthesisError> {
+  let len = modulus.byte_len();
+  if digest.len() != 8 * HASH_LEN || len < HASH_LEN + PSS_SALT_LEN + 2 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let em = rsavp1(cs.namespace(|| "rsavp1"), modulus, signature)?;
+
+  // the modulus has 8 * len bits, so EM has len bytes and its leftmost bit is
+  // zero: EM = maskedDB || H || 0xbc
+  let db_len = len - HASH_LEN - 1;
+  let (masked_db, rest) = em.split_at(8 * db_len);
+  let (hash, trailer) = rest.split_at(8 * HASH_LEN);
+  Boolean::enforce_equal(cs.namespace(|| "leftmost bit"), &em[0], &Boolean::constant(false))?;
+  enforce_equal_bits(cs.namespace(|| "trailer"), trailer, &constant_bytes(&[0xbc]))?;
+
+  // DB = PS || 0x01 || salt, PS of zero bytes, with its leftmost bit cleared
+  let db_mask = mgf1(cs.namespace(|| "mgf1"), hash, db_len)?;
+  let mut padding = vec![0; db_len - PSS_SALT_LEN - 1];
+  padding.push(0x01);
+  let n = 8 * padding.len();
+  // XORs with constants are free
+  let masked_padding =
+    xor_bytes(cs.namespace(|| "mask padding"), &db_mask[..n], &constant_bytes(&padding))?;
+  enforce_equal_bits(cs.namespace(|| "padding"), &masked_db[1..n], &masked_padding[1..])?;
+  let salt = xor_bytes(cs.namespace(|| "unmask salt"), &masked_db[n..], &db_mask[n..])?;
+
+  // H = SHA-256(0x00 * 8 || digest || salt)
+  let message = [constant_bytes(&[0; 8]), digest.to_vec(), salt].concat();
+  let expected = sha256(cs.namespace(|| "hash"), &message)?;
+  enforce_equal_bits(cs.namespace(|| "hash check"), hash, &expected)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  // a 2048-bit key, signing MESSAGE
+  const MESSAGE: &[u8] = b"a message signed by an RSA key";
+  const MODULUS: &str = concat!(
+    "eedd7f1fcd3b41c5e4632e8667fceba522c8a608c91bad61f4c8458e9e32bbab",
+    "e0e5dd7b734207ffabd324c31a5b4388c5118c3ae4ae861848b360ea18e65cfe",
+    "fe1002bf8ce24d7fe23f6fb75501ac1c98d8ad9a22eda24fdda162bd4b5c4b44",
+    "1c880dcd51fd3712e27723c403fc3b177f43e23bf5369ae7ee46bb96850a69e2",
+    "097c556b3239ae305fdb3a08ae675d927fb3ae92839898b4291d824065b13832",
+    "b14c988122503363d816babfa658a1fea248f468678246a799433a8eab68fadc",
+    "fdbf18b4e6c175cda5bf135938a8e570266593b694428fc48b8525a8e2d32073",
+    "e48fd1ecbd91ce51c583681f5044a858a5b1674f0ac93a877baed23912728221",
+  );
+  const PKCS1V15_SIGNATURE: &str = concat!(
+    "19ae2c032ba3872e8f5606dbb1a5c4d4a176d0fecc9e77ab5ee3f6052af66137",
+    "82f1a456ec0ad68def983e0346253d8c22191b543440cecba4e05d8bbbb62583",
+    "c65387fe812baac509a5485a98fb4b50f7a7bd8a8ff6efd94ac2abe45028efce",
+    "484552415e35a9ad40c67f426c9092105ba7b9858be4e2892b4d8570c9b64df6",
+    "8594bf5ae1db4c326648ec57faa6a23a06b31187017b6977ffcfd23d443ebe3e",
+    "d590d348cefbb608beb888598af043537b89a292d355dd49c855a7453f4d2d2e",
+    "7ee647f9fa42346385ce84f706dc70b64da6cf80805f248b33d2496352d1df86",
+    "a20eabff1f0b3c825a12be079fa74a119b5466cdafa27eca7ff95d5ef419ffac",
+  );
+  const PSS_SIGNATURE: &str = concat!(
+    "28d65f3568fd85decd68d193c58e86bca1f8390c1f4177962217483229689d8a",
+    "35e93e880cfa1bf8eb6c0e42c9ef3ce476a3ee5bbc88293ec840bb4cf2138de3",
+    "2ff956db369cc0dbed1e0406c1c020ca814f9c5fd8d64f7f7cb7690fbe988883",
+    "bd025cef14cc2069d7a5e2ec04483da9a0070d6c2cea2578af4000d523564143",
+    "5e73361881aee5875083c6d015e37d836e853e024467de41aa1b9b30b56bee23",
+    "e01644b11887b2bc1f13b07dcd8e35d40761dcefba9499216afb140a4a208ad3",
+    "3f4de1c54a718748a31ed1632dbc265dd5a7a5025183c42188d91afe48f5099f",
+    "29afbaa660e6c170a937a912180abf34094cce94f372f5d7668617229df978c0",
+  );
+
+  fn verify(pss: bool, pinned: bool, signature: &str, message: &[u8]) -> bool {
+    let modulus = hex::decode(MODULUS).unwrap();
+    let signature = hex::decode(signature).unwrap();
+    let digest = Sha256::digest(message);
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let modulus = if pinned {
+      RsaModulus::constant(cs.namespace(|| "modulus"), &modulus)
+    } else {
+      RsaModulus::alloc(cs.namespace(|| "modulus"), Some(&modulus[..]), modulus.len())
+    }
+    .unwrap();
+    let digest = alloc_bytes(cs.namespace(|| "digest"), Some(&digest[..]), HASH_LEN).unwrap();
+    let cs_verify = cs.namespace(|| "verify");
+    let verified = if pss {
+      verify_pss_sha256(cs_verify, &modulus, Some(&signature), &digest)
+    } else {
+      verify_pkcs1v15_sha256(cs_verify, &modulus, Some(&signature), &digest)
+    };
+    verified.is_ok() && cs.is_satisfied()
+  }
+
+  #[test]
+  fn test_pkcs1v15() {
+    assert!(verify(false, false, PKCS1V15_SIGNATURE, MESSAGE));
+    assert!(verify(false, true, PKCS1V15_SIGNATURE, MESSAGE));
+    assert!(!verify(false, true, PKCS1V15_SIGNATURE, b"another message"));
+    assert!(!verify(false, true, PSS_SIGNATURE, MESSAGE));
+  }
+
+  #[test]
+  fn test_pss() {
+    assert!(verify(true, false, PSS_SIGNATURE, MESSAGE));
+    assert!(!verify(true, true, PSS_SIGNATURE, b"another message"));
+    assert!(!verify(true, true, PKCS1V15_SIGNATURE, MESSAGE));
+  }
+}
@@ -0,0 +1,354 @@
+//! SHA-256 of messages of variable length inside circuits: the prefix of a
+//! byte string whose length is a witness, up to the length of the string,
+//! which fixes the shape of the circuit, e.g. for DER structures.
+//!
+//! The message is padded in the circuit and every block of the padded string
+//! is compressed, the digest being the state after the block that ends the
+//! padding of the message. Byte strings are the bits of their bytes, most
+//! significant bit first (see `alloc_bytes`), as the SHA-256 gadget hashes
+//! them.
+use bellpepper::gadgets::{multieq::MultiEq, uint32::UInt32};
+use bellpepper_core::{
+  boolean::{AllocatedBit, Boolean},
+  num::AllocatedNum,
+  ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+/// The length of SHA-256 blocks
+const BLOCK_LEN: usize = 64;
+
+const IV: [u32; 8] = [
+  0x6a09_e667,
+  0xbb67_ae85,
+  0x3c6e_f372,
+  0xa54f_f53a,
+  0x510e_527f,
+  0x9b05_688c,
+  0x1f83_d9ab,
+  0x5be0_cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+  0x428a_2f98,
+  0x7137_4491,
+  0xb5c0_fbcf,
+  0xe9b5_dba5,
+  0x3956_c25b,
+  0x59f1_11f1,
+  0x923f_82a4,
+  0xab1c_5ed5,
+  0xd807_aa98,
+  0x1283_5b01,
+  0x2431_85be,
+  0x550c_7dc3,
+  0x72be_5d74,
+  0x80de_b1fe,
+  0x9bdc_06a7,
+  0xc19b_f174,
+  0xe49b_69c1,
+  0xefbe_4786,
+  0x0fc1_9dc6,
+  0x240c_a1cc,
+  0x2de9_2c6f,
+  0x4a74_84aa,
+  0x5cb0_a9dc,
+  0x76f9_88da,
+  0x983e_5152,
+  0xa831_c66d,
+  0xb003_27c8,
+  0xbf59_7fc7,
+  0xc6e0_0bf3,
+  0xd5a7_9147,
+  0x06ca_6351,
+  0x1429_2967,
+  0x27b7_0a85,
+  0x2e1b_2138,
+  0x4d2c_6dfc,
+  0x5338_0d13,
+  0x650a_7354,
+  0x766a_0abb,
+  0x81c2_c92e,
+  0x9272_2c85,
+  0xa2bf_e8a1,
+  0xa81a_664b,
+  0xc24b_8b70,
+  0xc76c_51a3,
+  0xd192_e819,
+  0xd699_0624,
+  0xf40e_3585,
+  0x106a_a070,
+  0x19a4_c116,
+  0x1e37_6c08,
+  0x2748_774c,
+  0x34b0_bcb5,
+  0x391c_0cb3,
+  0x4ed8_aa4a,
+  0x5b9c_ca4f,
+  0x682e_6ff3,
+  0x748f_82ee,
+  0x78a5_636f,
+  0x84c8_7814,
+  0x8cc7_0208,
+  0x90be_fffa,
+  0xa450_6ceb,
+  0xbef9_a3f7,
+  0xc671_78f2,
+];
+
+/// `x >>> a ^ x >>> b ^ x >> c`, or `x >>> c` if `shift` is false
+fn sigma<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  x: &UInt32,
+  [a, b, c]: [usize; 3],
+  shift: bool,
+) -> Result<UInt32, SynthesisError> {
+  let last = if shift { x.shr(c) } else { x.rotr(c) };
+  x.rotr(a).xor(cs.namespace(|| "first"), &x.rotr(b))?.xor(cs.namespace(|| "second"), &last)
+}
+
+/// The compression of the 512-bit `block` into `state`
+fn compress<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  state: &[UInt32],
+  block: &[Boolean],
+) -> Result<Vec<UInt32>, SynthesisError> {
+  let mut cs = MultiEq::new(cs);
+  let mut w = block.chunks(32).map(UInt32::from_bits_be).collect::<Vec<_>>();
+  for i in 16..64 {
+    let mut cs = cs.namespace(|| format!("schedule {i}"));
+    let s0 = sigma(cs.namespace(|| "s0"), &w[i - 15], [7, 18, 3], true)?;
+    let s1 = sigma(cs.namespace(|| "s1"), &w[i - 2], [17, 19, 10], true)?;
+    let word =
+      UInt32::addmany(cs.namespace(|| "sum"), &[w[i - 16].clone(), s0, w[i - 7].clone(), s1])?;
+    w.push(word);
+  }
+
+  let mut v = state.to_vec();
+  for (i, (k, w)) in ROUND_CONSTANTS.iter().zip(&w).enumerate() {
+    let mut cs = cs.namespace(|| format!("round {i}"));
+    let [a, b, c, d, e, f, g, h] = &v[..] else { unreachable!() };
+    let s1 = sigma(cs.namespace(|| "s1"), e, [6, 11, 25], false)?;
+    let ch = UInt32::sha256_ch(cs.namespace(|| "ch"), e, f, g)?;
+    let s0 = sigma(cs.namespace(|| "s0"), a, [2, 13, 22], false)?;
+    let maj = UInt32::sha256_maj(cs.namespace(|| "maj"), a, b, c)?;
+    // temp1 = h + S1 + ch + k + w, e = d + temp1 and a = temp1 + S0 + maj
+    let temp1 = [h.clone(), s1, ch, UInt32::constant(*k), w.clone()];
+    let new_e = UInt32::addmany(cs.namespace(|| "e"), &[&temp1[..], &[d.clone()]].concat())?;
+    let new_a = UInt32::addmany(cs.namespace(|| "a"), &[&temp1[..], &[s0, maj]].concat())?;
+    v = vec![new_a, a.clone(), b.clone(), c.clone(), new_e, e.clone(), f.clone(), g.clone()];
+  }
+
+  state
+    .iter()
+    .zip(v)
+    .enumerate()
+    .map(|(i, (x, y))| UInt32::addmany(cs.namespace(|| format!("state {i}")), &[x.clone(), y]))
+    .collect()
+}
+
+/// The sum of the bits `terms`, of which at most one is set
+fn alloc_sum<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  terms: &[Boolean],
+) -> Result<Boolean, SynthesisError> {
+  match terms {
+    [] => Ok(Boolean::constant(false)),
+    [term] => Ok(term.clone()),
+    _ => {
+      let value = terms.iter().try_fold(false, |acc, t| t.get_value().map(|t| acc | t));
+      let bit = AllocatedBit::alloc(cs.namespace(|| "bit"), value)?;
+      let sum = terms.iter().fold(LinearCombination::zero(), |lc, t| lc + &t.lc(CS::one(), F::ONE));
+      cs.enforce(|| "sum", |_| sum, |lc| lc + CS::one(), |lc| lc + bit.get_variable());
+      Ok(Boolean::from(bit))
+    },
+  }
+}
+
+/// Allocates the bit `value == i` for each `i < n`, of which one is set
+fn alloc_one_hot<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: Option<usize>,
+  n: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  let bits = (0..n)
+    .map(|i| AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), value.map(|v| v == i)))
+    .collect::<Result<Vec<_>, _>>()?;
+  let sum = bits.iter().fold(LinearCombination::zero(), |lc, bit| lc + bit.get_variable());
+  cs.enforce(|| "one", |_| sum, |lc| lc + CS::one(), |lc| lc + CS::one());
+  Ok(bits)
+}
+
+/// The index of the set bit of `bits`
+fn index_lc<F: PrimeField>(bits: &[AllocatedBit]) -> LinearCombination<F> {
+  bits
+    .iter()
+    .enumerate()
+    .fold(LinearCombination::zero(), |lc, (i, bit)| lc + (F::from(i as u64), bit.get_variable()))
+}
+
+/// Allocates the `n` bits of `value`, least significant first
+fn alloc_bits<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: Option<usize>,
+  n: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError> {
+  (0..n)
+    .map(|i| {
+      AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), value.map(|v| (v >> i) & 1 == 1))
+    })
+    .collect()
+}
+
+fn bits_lc<F: PrimeField>(bits: &[AllocatedBit]) -> LinearCombination<F> {
+  bits
+    .iter()
+    .enumerate()
+    .fold(LinearCombination::zero(), |lc, (i, bit)| lc + (F::from(1 << i), bit.get_variable()))
+}
+
+/// SHA-256 of the first `len` bytes of `bytes`, whose other bytes must be zero
+pub fn sha256_var<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[Boolean],
+  len: &AllocatedNum<F>,
+) -> Result<Vec<Boolean>, SynthesisError> {
+  if bytes.len() % 8 != 0 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let max_len = bytes.len() / 8;
+  let n_blocks = (max_len + 9).div_ceil(BLOCK_LEN);
+  let len_value = len
+    .get_value()
+    .map(|len| {
+      (0..=max_len).find(|i| F::from(*i as u64) == len).ok_or(SynthesisError::Unsatisfiable)
+    })
+    .transpose()?;
+
+  // the message ends at `len`, and is followed by zeros
+  let ends = alloc_one_hot(cs.namespace(|| "end"), len_value, max_len + 1)?;
+  cs.enforce(|| "end", |_| index_lc(&ends), |lc| lc + CS::one(), |lc| lc + len.get_variable());
+  let mut past = LinearCombination::zero();
+  for (i, (byte, end)) in bytes.chunks(8).zip(&ends).enumerate() {
+    // past = 1 if i >= len, 0 otherwise
+    let value = len_value.map(|len| if i >= len { F::ONE } else { F::ZERO });
+    let num = AllocatedNum::alloc(cs.namespace(|| format!("past {i}")), || {
+      value.ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || format!("past {i}"),
+      |_| past + end.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + num.get_variable(),
+    );
+    past = LinearCombination::zero() + num.get_variable();
+    for (j, bit) in byte.iter().enumerate() {
+      cs.enforce(
+        || format!("zero {i} {j}"),
+        |_| bit.lc(CS::one(), F::ONE),
+        |_| past.clone(),
+        |lc| lc,
+      );
+    }
+  }
+
+  // the padding ends with the block `(len + 8) / 64`, with the bit length
+  let n_len_bits = (usize::BITS - max_len.leading_zeros()) as usize;
+  let len_bits = alloc_bits(cs.namespace(|| "len"), len_value, n_len_bits)?;
+  cs.enforce(|| "len", |_| bits_lc(&len_bits), |lc| lc + CS::one(), |lc| lc + len.get_variable());
+  let remainder = alloc_bits(cs.namespace(|| "remainder"), len_value.map(|len| len + 8), 6)?;
+  let lasts =
+    alloc_one_hot(cs.namespace(|| "last"), len_value.map(|len| (len + 8) / BLOCK_LEN), n_blocks)?;
+  cs.enforce(
+    || "last",
+    |_| {
+      let blocks = lasts.iter().enumerate().fold(LinearCombination::zero(), |lc, (i, bit)| {
+        lc + (F::from((BLOCK_LEN * i) as u64), bit.get_variable())
+      });
+      blocks + &bits_lc(&remainder)
+    },
+    |lc| lc + CS::one(),
+    |lc| lc + len.get_variable() + (F::from(8), CS::one()),
+  );
+
+  let mut padded = Vec::with_capacity(8 * BLOCK_LEN * n_blocks);
+  for i in 0..BLOCK_LEN * n_blocks {
+    let mut cs = cs.namespace(|| format!("padded {i}"));
+    for j in 0..8 {
+      let mut terms = Vec::new();
+      if i < max_len {
+        terms.push(bytes[8 * i + j].clone());
+      }
+      if j == 0 && i <= max_len {
+        terms.push(Boolean::from(ends[i].clone()));
+      }
+      // the bit length `8 * len` is big-endian in the last 8 bytes of the block
+      let offset = i % BLOCK_LEN;
+      if offset >= BLOCK_LEN - 8 {
+        let bit = 63 - (8 * (offset + 8 - BLOCK_LEN) + j);
+        if let Some(len_bit) = bit.checked_sub(3).and_then(|b| len_bits.get(b)) {
+          let last = &lasts[i / BLOCK_LEN];
+          terms.push(Boolean::from(AllocatedBit::and(
+            cs.namespace(|| format!("len {j}")),
+            last,
+            len_bit,
+          )?));
+        }
+      }
+      padded.push(alloc_sum(cs.namespace(|| format!("bit {j}")), &terms)?);
+    }
+  }
+
+  let mut state = IV.iter().map(|v| UInt32::constant(*v)).collect::<Vec<_>>();
+  let mut digest = Vec::new();
+  for (i, (block, last)) in padded.chunks(8 * BLOCK_LEN).zip(&lasts).enumerate() {
+    state = compress(cs.namespace(|| format!("block {i}")), &state, block)?;
+    let bits = state.iter().flat_map(|word| word.clone().into_bits_be()).collect::<Vec<_>>();
+    digest = if i == 0 {
+      bits
+    } else {
+      let last = Boolean::from(last.clone());
+      let mut cs = cs.namespace(|| format!("select {i}"));
+      bits
+        .iter()
+        .zip(&digest)
+        .enumerate()
+        .map(|(j, (bit, acc))| {
+          Boolean::sha256_ch(cs.namespace(|| format!("bit {j}")), &last, bit, acc)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+    };
+  }
+  Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+  use crate::gadgets::{alloc_bytes, bytes_value};
+
+  type F = halo2curves::bn256::Fr;
+
+  fn digest(message: &[u8], max_len: usize) -> Option<Vec<u8>> {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let mut padded = message.to_vec();
+    padded.resize(max_len, 0);
+    let bytes = alloc_bytes(cs.namespace(|| "bytes"), Some(&padded), max_len).unwrap();
+    let len =
+      AllocatedNum::alloc(cs.namespace(|| "len"), || Ok(F::from(message.len() as u64))).unwrap();
+    let digest = sha256_var(cs.namespace(|| "sha256"), &bytes, &len).unwrap();
+    cs.is_satisfied().then(|| bytes_value(&digest).unwrap())
+  }
+
+  #[test]
+  fn test_sha256_var() {
+    let message = (0..130).map(|i| i as u8 + 1).collect::<Vec<_>>();
+    for len in [0, 1, 55, 56, 63, 64, 100, 119, 120, 130] {
+      let expected = Sha256::digest(&message[..len]).to_vec();
+      assert_eq!(digest(&message[..len], 130), Some(expected), "{len}");
+    }
+  }
+}
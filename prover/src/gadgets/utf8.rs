@@ -0,0 +1,185 @@
+//! The validity of UTF-8 strings (RFC 3629) inside circuits, so that the
+//! bytes a circuit outputs are text.
+//!
+//! Strings are the bits of their bytes, most significant bit first (see
+//! `alloc_bytes`), up to a bound, and padded with zeros, which are valid
+//! characters; the padding of a string padded otherwise is zeroed with
+//! `mask_bytes` first. The leading bits of a byte select its kind, a lead
+//! of a sequence of 1 to 4 bytes or a continuation, and the continuations
+//! are the bytes following the leads by less than the lengths of their
+//! sequences, as `std::str::from_utf8` checks them: no overlong sequences,
+//! surrogates, or code points past U+10FFFF.
+use bellpepper_core::{boolean::Boolean, ConstraintSystem, LinearCombination, SynthesisError};
+use ff::PrimeField;
+
+/// The kinds of a byte, by its leading bits
+struct Kind {
+  continuation: Boolean,
+  lead_2:       Boolean,
+  lead_3:       Boolean,
+  lead_4:       Boolean,
+}
+
+/// Enforces that `a` and `b` are not both set
+fn forbid<F: PrimeField, CS: ConstraintSystem<F>>(mut cs: CS, a: &Boolean, b: &Boolean) {
+  cs.enforce(|| "forbid", |_| a.lc(CS::one(), F::ONE), |_| b.lc(CS::one(), F::ONE), |lc| lc);
+}
+
+/// Whether the bits `bits` are `pattern`, most significant first
+fn is_pattern<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[Boolean],
+  pattern: &[bool],
+) -> Result<Boolean, SynthesisError> {
+  let literal = |(bit, set): (&Boolean, &bool)| if *set { bit.clone() } else { bit.not() };
+  let mut literals = bits.iter().zip(pattern).map(literal);
+  let first = literals.next().unwrap_or(Boolean::constant(true));
+  literals.enumerate().try_fold(first, |acc, (i, literal)| {
+    Boolean::and(cs.namespace(|| format!("and {i}")), &acc, &literal)
+  })
+}
+
+/// The kind of the byte `bits`, and the constraints on the leads alone: a
+/// lead of 2 bytes is not `C0` or `C1`, of 4 bytes not above `F4`, and no
+/// byte is above `F7`
+fn kind<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bits: &[Boolean],
+) -> Result<Kind, SynthesisError> {
+  let b = |i: usize| &bits[7 - i];
+  let top_2 = Boolean::and(cs.namespace(|| "11"), b(7), b(6))?;
+  let top_3 = Boolean::and(cs.namespace(|| "111"), &top_2, b(5))?;
+  let top_4 = Boolean::and(cs.namespace(|| "1111"), &top_3, b(4))?;
+  let kind = Kind {
+    continuation: Boolean::and(cs.namespace(|| "10"), b(7), &b(6).not())?,
+    lead_2:       Boolean::and(cs.namespace(|| "110"), &top_2, &b(5).not())?,
+    lead_3:       Boolean::and(cs.namespace(|| "1110"), &top_3, &b(4).not())?,
+    lead_4:       Boolean::and(cs.namespace(|| "11110"), &top_4, &b(3).not())?,
+  };
+  forbid(cs.namespace(|| "11111"), &top_4, b(3));
+
+  let overlong_2 = is_pattern(cs.namespace(|| "110 0000"), &bits[3..7], &[false; 4])?;
+  forbid(cs.namespace(|| "C0 or C1"), &kind.lead_2, &overlong_2);
+  // F5 to F7 are the leads of 4 bytes with a bit 2 and a bit 1 or 0
+  let above_f4 = Boolean::and(cs.namespace(|| "1111 01"), &kind.lead_4, b(2))?;
+  let low = Boolean::and(cs.namespace(|| "00"), &b(1).not(), &b(0).not())?;
+  forbid(cs.namespace(|| "F5 to F7"), &above_f4, &low.not());
+  Ok(kind)
+}
+
+/// Enforces that the bytes `bytes` are a valid UTF-8 string
+pub fn enforce_utf8<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  bytes: &[Boolean],
+) -> Result<(), SynthesisError> {
+  if bytes.len() % 8 != 0 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let bytes = bytes.chunks(8).collect::<Vec<_>>();
+  let kinds = bytes
+    .iter()
+    .enumerate()
+    .map(|(i, bits)| kind(cs.namespace(|| format!("kind {i}")), bits))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  // the byte `i` is a continuation if and only if it follows a lead by
+  // less than the length of its sequence, for the bytes past the string too
+  for i in 0..bytes.len() + 3 {
+    let mut leads = LinearCombination::zero();
+    for distance in 1..=3 {
+      let Some(lead) = i.checked_sub(distance).and_then(|j| kinds.get(j)) else { continue };
+      let lengths = [(2, &lead.lead_2), (3, &lead.lead_3), (4, &lead.lead_4)];
+      for (_, bit) in lengths.iter().filter(|(len, _)| distance < *len) {
+        leads = leads + &bit.lc(CS::one(), F::ONE);
+      }
+    }
+    let continuation = kinds
+      .get(i)
+      .map_or(LinearCombination::zero(), |kind| kind.continuation.lc(CS::one(), F::ONE));
+    cs.enforce(
+      || format!("continuation {i}"),
+      |_| leads - &continuation,
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+  }
+
+  // the second bytes of the leads E0, ED, F0 and F4 are restricted, against
+  // overlong sequences, surrogates and code points past U+10FFFF
+  for (i, next) in bytes.iter().enumerate().skip(1) {
+    let mut cs = cs.namespace(|| format!("second byte {i}"));
+    let (lead, bits) = (&kinds[i - 1], bytes[i - 1]);
+    let e0 = is_pattern(cs.namespace(|| "E0"), &bits[4..], &[false, false, false, false])?;
+    let e0 = Boolean::and(cs.namespace(|| "lead E0"), &lead.lead_3, &e0)?;
+    forbid(cs.namespace(|| "E0 80 to E0 9F"), &e0, &next[2].not());
+
+    let ed = is_pattern(cs.namespace(|| "ED"), &bits[4..], &[true, true, false, true])?;
+    let ed = Boolean::and(cs.namespace(|| "lead ED"), &lead.lead_3, &ed)?;
+    forbid(cs.namespace(|| "ED A0 to ED BF"), &ed, &next[2]);
+
+    let f0 = is_pattern(cs.namespace(|| "F0"), &bits[5..], &[false, false, false])?;
+    let f0 = Boolean::and(cs.namespace(|| "lead F0"), &lead.lead_4, &f0)?;
+    let below_90 = Boolean::and(cs.namespace(|| "10 00"), &next[2].not(), &next[3].not())?;
+    forbid(cs.namespace(|| "F0 80 to F0 8F"), &f0, &below_90);
+
+    let f4 = is_pattern(cs.namespace(|| "F4"), &bits[5..], &[true, false, false])?;
+    let f4 = Boolean::and(cs.namespace(|| "lead F4"), &lead.lead_4, &f4)?;
+    forbid(cs.namespace(|| "F4 A0 to F4 BF"), &f4, &next[2]);
+    forbid(cs.namespace(|| "F4 90 to F4 9F"), &f4, &next[3]);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::gadgets::alloc_bytes;
+
+  type F = halo2curves::bn256::Fr;
+
+  fn is_valid(bytes: &[u8]) -> bool {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bits = alloc_bytes(cs.namespace(|| "bytes"), Some(bytes), bytes.len()).unwrap();
+    enforce_utf8(cs.namespace(|| "utf8"), &bits).unwrap();
+    cs.is_satisfied()
+  }
+
+  #[test]
+  fn test_utf8() {
+    let mut padded = "héllo wörld ✓ 😀".as_bytes().to_vec();
+    padded.resize(32, 0);
+    assert!(is_valid(&padded));
+
+    let cases: [&[u8]; 24] = [
+      b"",
+      b"plain ascii",
+      &[0xc2, 0x80],
+      &[0xdf, 0xbf],
+      &[0xe0, 0xa0, 0x80],
+      &[0xed, 0x9f, 0xbf],
+      &[0xee, 0x80, 0x80],
+      &[0xf0, 0x90, 0x80, 0x80],
+      &[0xf4, 0x8f, 0xbf, 0xbf],
+      &[0x80],
+      &[b'a', 0xbf, b'b'],
+      &[0xc0, 0x80],
+      &[0xc1, 0xbf],
+      &[0xc2],
+      &[0xc2, b'a'],
+      &[0xe0, 0x80, 0x80],
+      &[0xe0, 0x9f, 0xbf],
+      &[0xed, 0xa0, 0x80],
+      &[0xe2, 0x9c],
+      &[0xf0, 0x8f, 0xbf, 0xbf],
+      &[0xf4, 0x90, 0x80, 0x80],
+      &[0xf5, 0x80, 0x80, 0x80],
+      &[0xf8, 0x80, 0x80, 0x80, 0x80],
+      &[0xe2, 0xc2, 0x80, 0x9c],
+    ];
+    for bytes in cases {
+      assert_eq!(is_valid(bytes), std::str::from_utf8(bytes).is_ok(), "{bytes:02x?}");
+    }
+  }
+}
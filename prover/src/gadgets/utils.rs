@@ -8,7 +8,10 @@ use bellpepper_core::{
 use ff::{Field, PrimeField, PrimeFieldBits};
 use num_bigint::BigInt;
 
-use super::nonnative::bignat::{nat_to_limbs, BigNat};
+use super::{
+  native::{nat_to_limbs, scalar_as_base},
+  nonnative::bignat::BigNat,
+};
 use crate::traits::Engine;
 
 /// Gets as input the little indian representation of a number and spits out the
@@ -28,7 +31,7 @@ where
   let mut fe = Some(Scalar::ZERO);
   for bit in bits.iter() {
     lc = lc + (coeff, bit.get_variable());
-    fe = bit.get_value().map(|val| if val { fe.unwrap() + coeff } else { fe.unwrap() });
+    fe = fe.zip(bit.get_value()).map(|(fe, val)| if val { fe + coeff } else { fe });
     coeff = coeff.double();
   }
   let num = AllocatedNum::alloc(cs.namespace(|| "Field element"), || {
@@ -74,20 +77,6 @@ where
   })
 }
 
-/// interpret scalar as base
-pub fn scalar_as_base<E: Engine>(input: E::Scalar) -> E::Base {
-  let input_bits = input.to_le_bits();
-  let mut mult = E::Base::ONE;
-  let mut val = E::Base::ZERO;
-  for bit in input_bits {
-    if bit {
-      val += mult;
-    }
-    mult = mult + mult;
-  }
-  val
-}
-
 /// Allocate bignat a constant
 pub fn alloc_bignat_constant<F: PrimeField, CS: ConstraintSystem<F>>(
   mut cs: CS,
@@ -95,7 +84,7 @@ pub fn alloc_bignat_constant<F: PrimeField, CS: ConstraintSystem<F>>(
   limb_width: usize,
   n_limbs: usize,
 ) -> Result<BigNat<F>, SynthesisError> {
-  let limbs = nat_to_limbs(val, limb_width, n_limbs).unwrap();
+  let limbs = nat_to_limbs(val, limb_width, n_limbs).ok_or(SynthesisError::Unsatisfiable)?;
   let bignat = BigNat::alloc_from_limbs(
     cs.namespace(|| "alloc bignat"),
     || Ok(limbs.clone()),
@@ -135,7 +124,11 @@ pub fn alloc_num_equals<F: PrimeField, CS: ConstraintSystem<F>>(
   let t = AllocatedNum::alloc(cs.namespace(|| "t"), || {
     let a_val = *a.get_value().get()?;
     let b_val = *b.get_value().get()?;
-    Ok(if a_val == b_val { F::ONE } else { (a_val - b_val).invert().unwrap() })
+    if a_val == b_val {
+      Ok(F::ONE)
+    } else {
+      Option::<F>::from((a_val - b_val).invert()).ok_or(SynthesisError::DivisionByZero)
+    }
   })?;
 
   cs.enforce(
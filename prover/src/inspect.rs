@@ -0,0 +1,450 @@
+//! Inspection of serialized proofs, to debug why two runs that should produce
+//! the same proof do not.
+//!
+//! A [`ProofTree`] decodes a proof into a tree labeled by the names of its
+//! fields, down to the rounds of its sum-checks, its commitments and its
+//! scalars, which are leaves holding the hex of their encodings. Its
+//! `Display` pretty-prints it, and [`diff`] lists the leaves where two proofs
+//! differ, by their paths in the tree.
+//!
+//! A [`VerifyTrace`] records the checks a verifier makes, e.g. with
+//! [`CompressedSNARK::verify_with_trace`](crate::CompressedSNARK::verify_with_trace),
+//! down to the rounds of its sum-checks: a proof that does not verify reports
+//! the [`Check`] that failed, serialized for a bug report, instead of a single
+//! error.
+//!
+//! A [`FailureReport`] identifies a proof that failed to verify by a stable
+//! [`fingerprint`], with the digests of the keys it was verified against and
+//! the check that failed, so that reports of the same bad proof from the
+//! field are deduplicated. With the `log-failures` feature, the verifiers of
+//! compressed proofs log the report of each failure.
+use core::fmt;
+
+use ff::PrimeField;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::NovaError;
+
+/// A proof, as a tree labeled by the names of its fields
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofTree {
+  /// A scalar, a commitment or any other value, as an integer or as the hex
+  /// of its encoding
+  Leaf(String),
+  /// A sequence, e.g. of the rounds of a sum-check
+  List(Vec<ProofTree>),
+  /// A struct, by the names of its fields
+  Struct(Vec<(String, ProofTree)>),
+}
+
+impl ProofTree {
+  /// Decodes `proof` into a tree
+  pub fn new<T: Serialize + ?Sized>(proof: &T) -> Result<Self, NovaError> {
+    let value =
+      serde_json::to_value(proof).map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    Ok(Self::from_value(value))
+  }
+
+  /// Decodes a proof of type `T` serialized with `bincode` into a tree
+  pub fn from_bytes<T: Serialize + DeserializeOwned>(bytes: &[u8]) -> Result<Self, NovaError> {
+    let proof: T =
+      bincode::deserialize(bytes).map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    Self::new(&proof)
+  }
+
+  fn from_value(value: Value) -> Self {
+    match value {
+      Value::Null => Self::Leaf("none".to_string()),
+      Value::Bool(b) => Self::Leaf(b.to_string()),
+      Value::Number(n) => Self::Leaf(n.to_string()),
+      Value::String(s) => Self::Leaf(format!("0x{s}")),
+      // byte arrays, e.g. the encodings of points, are leaves
+      Value::Array(values) if !values.is_empty() && values.iter().all(|v| as_byte(v).is_some()) => {
+        let bytes = values.iter().filter_map(as_byte).collect::<Vec<_>>();
+        Self::Leaf(format!("0x{}", hex(&bytes)))
+      },
+      Value::Array(values) => Self::List(values.into_iter().map(Self::from_value).collect()),
+      Value::Object(fields) =>
+        Self::Struct(fields.into_iter().map(|(name, v)| (name, Self::from_value(v))).collect()),
+    }
+  }
+
+  /// The leaves of the tree, by their paths, e.g.
+  /// `snark.sc_proof.compressed_polys[2].coeffs_except_linear_term[0]`
+  pub fn leaves(&self) -> Vec<(String, &str)> {
+    let mut leaves = Vec::new();
+    self.collect_leaves(String::new(), &mut leaves);
+    leaves
+  }
+
+  fn collect_leaves<'a>(&'a self, path: String, leaves: &mut Vec<(String, &'a str)>) {
+    match self {
+      Self::Leaf(value) => leaves.push((path, value)),
+      Self::List(items) =>
+        for (i, item) in items.iter().enumerate() {
+          item.collect_leaves(format!("{path}[{i}]"), leaves);
+        },
+      Self::Struct(fields) =>
+        for (name, field) in fields {
+          field.collect_leaves(join(&path, name), leaves);
+        },
+    }
+  }
+
+  /// A one-line summary of the node, for the differences between nodes of
+  /// different kinds
+  fn summary(&self) -> String {
+    match self {
+      Self::Leaf(value) => value.clone(),
+      Self::List(items) => format!("<list of {}>", items.len()),
+      Self::Struct(fields) => format!("<struct of {} fields>", fields.len()),
+    }
+  }
+
+  fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+    let children: Box<dyn Iterator<Item = (String, &ProofTree)> + '_> = match self {
+      Self::Leaf(value) => return writeln!(f, " {value}"),
+      Self::List(items) => {
+        writeln!(f, " [{}]", items.len())?;
+        Box::new(items.iter().enumerate().map(|(i, item)| (i.to_string(), item)))
+      },
+      Self::Struct(fields) => {
+        writeln!(f)?;
+        Box::new(fields.iter().map(|(name, field)| (name.clone(), field)))
+      },
+    };
+    for (label, child) in children {
+      write!(f, "{:indent$}{label}:", "", indent = indent + 2)?;
+      child.fmt_indented(f, indent + 2)?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for ProofTree {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "proof:")?;
+    self.fmt_indented(f, 0)
+  }
+}
+
+/// The field of `fields` named `name`
+fn field<'a>(fields: &'a [(String, ProofTree)], name: &str) -> Option<&'a ProofTree> {
+  fields.iter().find(|(n, _)| n == name).map(|(_, field)| field)
+}
+
+fn as_byte(value: &Value) -> Option<u8> { value.as_u64().and_then(|v| u8::try_from(v).ok()) }
+
+fn hex(bytes: &[u8]) -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() }
+
+fn join(path: &str, name: &str) -> String {
+  if path.is_empty() {
+    name.to_string()
+  } else {
+    format!("{path}.{name}")
+  }
+}
+
+/// A leaf, or a subtree, where two proofs differ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+  /// the path of the node in the trees
+  pub path:  String,
+  /// the node in the first proof, if it has one at `path`
+  pub left:  Option<String>,
+  /// the node in the second proof, if it has one at `path`
+  pub right: Option<String>,
+}
+
+impl fmt::Display for Difference {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let side = |node: &Option<String>| node.clone().unwrap_or_else(|| "<missing>".to_string());
+    write!(f, "{}:\n  - {}\n  + {}", self.path, side(&self.left), side(&self.right))
+  }
+}
+
+/// The differences between `proof_a` and `proof_b`, in the order of the
+/// fields of `proof_a`
+pub fn diff<T: Serialize + ?Sized>(proof_a: &T, proof_b: &T) -> Result<Vec<Difference>, NovaError> {
+  Ok(diff_trees(&ProofTree::new(proof_a)?, &ProofTree::new(proof_b)?))
+}
+
+/// The differences between the trees `a` and `b`
+pub fn diff_trees(a: &ProofTree, b: &ProofTree) -> Vec<Difference> {
+  let mut differences = Vec::new();
+  diff_at(String::new(), Some(a), Some(b), &mut differences);
+  differences
+}
+
+fn diff_at(
+  path: String,
+  a: Option<&ProofTree>,
+  b: Option<&ProofTree>,
+  differences: &mut Vec<Difference>,
+) {
+  match (a, b) {
+    (Some(ProofTree::List(a)), Some(ProofTree::List(b))) =>
+      for i in 0..a.len().max(b.len()) {
+        diff_at(format!("{path}[{i}]"), a.get(i), b.get(i), differences);
+      },
+    (Some(ProofTree::Struct(a)), Some(ProofTree::Struct(b))) => {
+      for (name, field_a) in a {
+        diff_at(join(&path, name), Some(field_a), field(b, name), differences);
+      }
+      for (name, field_b) in b.iter().filter(|(name, _)| field(a, name).is_none()) {
+        diff_at(join(&path, name), None, Some(field_b), differences);
+      }
+    },
+    (a, b) if a != b => {
+      differences.push(Difference {
+        path,
+        left: a.map(ProofTree::summary),
+        right: b.map(ProofTree::summary),
+      });
+    },
+    _ => {},
+  }
+}
+
+/// A check of a verifier
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Check {
+  /// A sum-check has a polynomial per round
+  SumcheckRounds {
+    /// the name of the sum-check
+    sumcheck: String,
+  },
+  /// The polynomial of a round of a sum-check is of the degree bound
+  SumcheckDegree {
+    /// the name of the sum-check
+    sumcheck: String,
+    /// the index of the round
+    round:    usize,
+  },
+  /// The final claim of a sum-check is the one the claimed evaluations imply
+  SumcheckFinalClaim {
+    /// the name of the sum-check
+    sumcheck: String,
+  },
+  /// A commitment opens to its claimed evaluation
+  Opening {
+    /// the name of the opening
+    opening: String,
+  },
+  /// Two values the verifier computes are equal, e.g. a hash and its claim
+  Equality {
+    /// the name of the equality
+    equality: String,
+  },
+  /// A step that fails with an error, e.g. a fold, a transcript, or a
+  /// verifier that does not trace its checks
+  Step {
+    /// the name of the step
+    step: String,
+  },
+}
+
+impl Check {
+  /// A [`Check::Step`] named `step`
+  pub fn step(step: &str) -> Self { Self::Step { step: step.to_string() } }
+
+  /// A [`Check::Equality`] named `equality`
+  pub fn equality(equality: &str) -> Self { Self::Equality { equality: equality.to_string() } }
+}
+
+/// The checks of a verification, in the order they were made, up to the first
+/// that failed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyTrace {
+  /// the checks that passed
+  pub passed: Vec<Check>,
+  /// the check that failed, with its error
+  pub failed: Option<(Check, String)>,
+}
+
+impl VerifyTrace {
+  /// An empty trace
+  pub fn new() -> Self { Self::default() }
+
+  /// Records `check`, which passed if `ok`, and fails with `error` otherwise
+  pub fn check(&mut self, check: Check, ok: bool, error: NovaError) -> Result<(), NovaError> {
+    self.check_result(check, if ok { Ok(()) } else { Err(error) })
+  }
+
+  /// Records `check`, which passed if `result` is a value
+  pub fn check_result<T>(
+    &mut self,
+    check: Check,
+    result: Result<T, NovaError>,
+  ) -> Result<T, NovaError> {
+    match &result {
+      Ok(_) => self.passed.push(check),
+      Err(e) if self.failed.is_none() => self.failed = Some((check, e.to_string())),
+      Err(_) => {},
+    }
+    result
+  }
+
+  /// Ends the trace of a verification that returned `result`, recording an
+  /// error no check reported as a failed [`Check::Step`]
+  pub fn finish<T>(mut self, result: Result<T, NovaError>) -> (Result<T, NovaError>, Self) {
+    if let Err(e) = &result {
+      self.failed.get_or_insert_with(|| (Check::step("untraced"), e.to_string()));
+    }
+    (result, self)
+  }
+
+  /// Whether no check failed
+  pub fn is_ok(&self) -> bool { self.failed.is_none() }
+
+  /// The trace as JSON, for a bug report
+  pub fn to_json(&self) -> Result<String, NovaError> {
+    serde_json::to_string_pretty(self).map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+}
+
+/// The fingerprint of `proof`: the first 16 bytes, in hex, of a hash of its
+/// serialization with `bincode`, the same for the same proof on any platform
+pub fn fingerprint<T: Serialize + ?Sized>(proof: &T) -> Result<String, NovaError> {
+  let bytes =
+    bincode::serialize(proof).map_err(|e| NovaError::SerializationError(e.to_string()))?;
+  let mut hasher = blake3::Hasher::new_derive_key("edge-prover proof fingerprint");
+  hasher.update(&bytes);
+  Ok(hex(&hasher.finalize().as_bytes()[..16]))
+}
+
+/// The report of a proof that failed to verify
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailureReport {
+  /// the proof system of the proof, e.g. `nova`
+  pub scheme:      String,
+  /// the [`fingerprint`] of the proof
+  pub fingerprint: String,
+  /// the digests of the keys the proof was verified against, in hex, by
+  /// their names
+  pub key_digests: Vec<(String, String)>,
+  /// the check that failed, if the verification was traced
+  pub check:       Option<Check>,
+  /// the error of the verification
+  pub error:       String,
+}
+
+impl FailureReport {
+  /// The report of `proof`, of the proof system `scheme`, which failed to
+  /// verify with `error`
+  pub fn new<T: Serialize + ?Sized>(scheme: &str, proof: &T, error: &impl fmt::Display) -> Self {
+    Self {
+      scheme:      scheme.to_string(),
+      fingerprint: fingerprint(proof).unwrap_or_else(|e| format!("<{e}>")),
+      key_digests: Vec::new(),
+      check:       None,
+      error:       error.to_string(),
+    }
+  }
+
+  /// The report, with the digest of the key `name`
+  pub fn with_key_digest<F: PrimeField>(mut self, name: &str, digest: &F) -> Self {
+    self.key_digests.push((name.to_string(), hex(digest.to_repr().as_ref())));
+    self
+  }
+
+  /// The report, with the check that failed in `trace`
+  pub fn with_trace(mut self, trace: &VerifyTrace) -> Self {
+    self.check = trace.failed.as_ref().map(|(check, _)| check.clone());
+    self
+  }
+
+  /// Logs the report, as a warning whose fields are those of the report
+  pub fn log(&self) {
+    let key_digests =
+      self.key_digests.iter().map(|(name, digest)| format!("{name}={digest}")).collect::<Vec<_>>();
+    let check = match &self.check {
+      Some(check) => serde_json::to_string(check).unwrap_or_default(),
+      None => "untraced".to_string(),
+    };
+    tracing::warn!(
+      scheme = %self.scheme,
+      fingerprint = %self.fingerprint,
+      key_digests = %key_digests.join(","),
+      check = %check,
+      error = %self.error,
+      "proof failed to verify"
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::spartan::polys::univariate::{CompressedUniPoly, UniPoly};
+
+  type F = halo2curves::bn256::Fr;
+
+  #[derive(Serialize, serde::Deserialize)]
+  struct Proof {
+    rounds: Vec<CompressedUniPoly<F>>,
+    claim:  F,
+  }
+
+  fn proof(claim: u64) -> Proof {
+    let poly =
+      |c: u64| UniPoly::from_evals(&[F::from(c), F::from(c + 1), F::from(c + 3)]).compress();
+    Proof { rounds: vec![poly(1), poly(2)], claim: F::from(claim) }
+  }
+
+  #[test]
+  fn test_proof_tree() {
+    let bytes = bincode::serialize(&proof(5)).unwrap();
+    let tree = ProofTree::from_bytes::<Proof>(&bytes).unwrap();
+    assert_eq!(tree, ProofTree::new(&proof(5)).unwrap());
+
+    // the two rounds of two coefficients, and the claim
+    let leaves = tree.leaves();
+    assert_eq!(leaves.len(), 5);
+    let paths = leaves.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>();
+    assert!(paths.contains(&"rounds[1].coeffs_except_linear_term[1]"));
+    assert!(paths.contains(&"claim"));
+    assert!(tree.to_string().contains("\n  rounds: [2]\n    0:\n"));
+  }
+
+  #[test]
+  fn test_diff() {
+    assert_eq!(diff(&proof(5), &proof(5)).unwrap(), vec![]);
+
+    let differences = diff(&proof(5), &proof(6)).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path, "claim");
+    assert_ne!(differences[0].left, differences[0].right);
+
+    // a missing round is reported as a whole
+    let mut short = proof(5);
+    short.rounds.pop();
+    let differences = diff(&proof(5), &short).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path, "rounds[1]");
+    assert_eq!(differences[0].right, None);
+  }
+
+  #[test]
+  #[tracing_test::traced_test]
+  fn test_failure_report() {
+    let fp = fingerprint(&proof(5)).unwrap();
+    assert_eq!(fp.len(), 32);
+    assert_eq!(fingerprint(&proof(5)).unwrap(), fp);
+    assert_ne!(fingerprint(&proof(6)).unwrap(), fp);
+
+    let mut trace = VerifyTrace::new();
+    let _ = trace.check(Check::equality("claim"), false, NovaError::ProofVerifyError);
+    let report = FailureReport::new("nova", &proof(5), &NovaError::ProofVerifyError)
+      .with_key_digest("vk", &F::from(255))
+      .with_trace(&trace);
+    assert_eq!(report.fingerprint, fp);
+    assert_eq!(report.key_digests, vec![("vk".to_string(), format!("ff{}", "00".repeat(31)))]);
+    assert_eq!(report.check, Some(Check::equality("claim")));
+
+    report.log();
+    assert!(logs_contain(&format!("fingerprint={fp}")));
+    assert!(logs_contain("proof failed to verify"));
+  }
+}
@@ -0,0 +1,252 @@
+//! The public IO of a recursive proof, packed into the 32-byte words of EVM
+//! verifiers.
+//!
+//! The words of a [`PublicIo`] are, in this order, the number of steps, the
+//! initial and final inputs of the primary circuit, then those of the
+//! secondary circuit: `[num_steps, z0_primary, zn_primary, z0_secondary,
+//! zn_secondary]`. Each word is a `uint256` in the EVM's big-endian order:
+//! a scalar is its canonical integer, left-padded with zeros, and the number
+//! of steps its integer. An IO of more than [`MAX_INLINE_WORDS`] words is
+//! passed to a verifier as its digest instead, the Keccak-256 of its words,
+//! `keccak256(abi.encodePacked(words))` in Solidity.
+//!
+//! [`solidity_library`] emits the Solidity library that reads and digests
+//! the words as this module packs them, so that contracts and this crate
+//! agree byte-for-byte.
+use ff::PrimeField;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+  errors::NovaError,
+  scalar_bytes::ScalarBytesBe,
+  traits::{CurveCycleEquipped, Dual, Engine},
+};
+
+/// A 32-byte EVM word, big-endian
+pub type Word = [u8; 32];
+
+/// The largest number of words of an IO passed to a verifier as is
+pub const MAX_INLINE_WORDS: usize = 16;
+
+/// Packs `scalars` into words, which fails if a scalar does not fit a word
+pub fn pack_words<F: PrimeField>(scalars: &[F]) -> Result<Vec<Word>, NovaError> {
+  scalars
+    .iter()
+    .map(|scalar| {
+      let bytes = ScalarBytesBe::new(scalar);
+      let offset =
+        32usize.checked_sub(bytes.as_bytes().len()).ok_or(NovaError::InvalidInputLength)?;
+      let mut word = [0u8; 32];
+      word[offset..].copy_from_slice(bytes.as_bytes());
+      Ok(word)
+    })
+    .collect()
+}
+
+/// Unpacks `words` into scalars, which fails on non-canonical scalars
+pub fn unpack_words<F: PrimeField>(words: &[Word]) -> Result<Vec<F>, NovaError> {
+  let len = F::Repr::default().as_ref().len();
+  let offset = 32usize.checked_sub(len).ok_or(NovaError::InvalidInputLength)?;
+  words
+    .iter()
+    .map(|word| {
+      if word[..offset].iter().any(|b| *b != 0) {
+        return Err(NovaError::NonCanonicalScalar);
+      }
+      ScalarBytesBe::<F>::from_slice(&word[offset..])?.to_scalar()
+    })
+    .collect()
+}
+
+/// The Keccak-256 of the concatenation of `words`
+pub fn keccak_words(words: &[Word]) -> Word {
+  let mut hasher = Keccak256::new();
+  words.iter().for_each(|word| hasher.update(word));
+  hasher.finalize().into()
+}
+
+/// The public IO of a recursive proof of `num_steps` steps
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicIo<E1: CurveCycleEquipped> {
+  /// The number of steps
+  pub num_steps:    usize,
+  /// The initial inputs of the primary circuit
+  pub z0_primary:   Vec<E1::Scalar>,
+  /// The final outputs of the primary circuit
+  pub zn_primary:   Vec<E1::Scalar>,
+  /// The initial inputs of the secondary circuit
+  pub z0_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
+  /// The final outputs of the secondary circuit
+  pub zn_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
+}
+
+impl<E1: CurveCycleEquipped> PublicIo<E1> {
+  /// The words of the IO, in the order of the module
+  pub fn to_words(&self) -> Result<Vec<Word>, NovaError> {
+    let mut num_steps = [0u8; 32];
+    num_steps[24..].copy_from_slice(&(self.num_steps as u64).to_be_bytes());
+    let mut words = vec![num_steps];
+    words.extend(pack_words(&self.z0_primary)?);
+    words.extend(pack_words(&self.zn_primary)?);
+    words.extend(pack_words(&self.z0_secondary)?);
+    words.extend(pack_words(&self.zn_secondary)?);
+    Ok(words)
+  }
+
+  /// The IO of the words `words`, for circuits of arities `arity_primary`
+  /// and `arity_secondary`
+  pub fn from_words(
+    words: &[Word],
+    arity_primary: usize,
+    arity_secondary: usize,
+  ) -> Result<Self, NovaError> {
+    if words.len() != 1 + 2 * (arity_primary + arity_secondary) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if words[0][..24].iter().any(|b| *b != 0) {
+      return Err(NovaError::InvalidNumSteps);
+    }
+    let mut num_steps = [0u8; 8];
+    num_steps.copy_from_slice(&words[0][24..]);
+    let num_steps =
+      usize::try_from(u64::from_be_bytes(num_steps)).map_err(|_| NovaError::InvalidNumSteps)?;
+
+    let (z0_primary, words) = words[1..].split_at(arity_primary);
+    let (zn_primary, words) = words.split_at(arity_primary);
+    let (z0_secondary, zn_secondary) = words.split_at(arity_secondary);
+    Ok(Self {
+      num_steps,
+      z0_primary: unpack_words(z0_primary)?,
+      zn_primary: unpack_words(zn_primary)?,
+      z0_secondary: unpack_words(z0_secondary)?,
+      zn_secondary: unpack_words(zn_secondary)?,
+    })
+  }
+
+  /// The digest of the IO, the Keccak-256 of its words
+  pub fn digest(&self) -> Result<Word, NovaError> { Ok(keccak_words(&self.to_words()?)) }
+
+  /// The words passed to a verifier: the words of the IO, or its digest if
+  /// they are more than [`MAX_INLINE_WORDS`]
+  pub fn verifier_words(&self) -> Result<Vec<Word>, NovaError> {
+    let words = self.to_words()?;
+    if words.len() > MAX_INLINE_WORDS {
+      return Ok(vec![keccak_words(&words)]);
+    }
+    Ok(words)
+  }
+}
+
+/// Emits the Solidity library `PublicIo`, which reads the words of the IO of
+/// circuits of arities `arity_primary` and `arity_secondary`, and computes
+/// the words passed to a verifier as [`PublicIo::verifier_words`] does
+pub fn solidity_library(arity_primary: usize, arity_secondary: usize) -> String {
+  format!(
+    r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.16;
+
+/// The public IO of a recursive proof, packed in the words
+/// [num_steps, z0_primary, zn_primary, z0_secondary, zn_secondary]
+library PublicIo {{
+    uint256 internal constant ARITY_PRIMARY = {arity_primary};
+    uint256 internal constant ARITY_SECONDARY = {arity_secondary};
+    uint256 internal constant NUM_WORDS = 1 + 2 * (ARITY_PRIMARY + ARITY_SECONDARY);
+    uint256 internal constant MAX_INLINE_WORDS = {MAX_INLINE_WORDS};
+
+    function numSteps(uint256[] calldata words) internal pure returns (uint256) {{
+        require(words.length == NUM_WORDS, "PublicIo: length");
+        return words[0];
+    }}
+
+    function z0Primary(uint256[] calldata words, uint256 i) internal pure returns (uint256) {{
+        require(words.length == NUM_WORDS && i < ARITY_PRIMARY, "PublicIo: index");
+        return words[1 + i];
+    }}
+
+    function znPrimary(uint256[] calldata words, uint256 i) internal pure returns (uint256) {{
+        require(words.length == NUM_WORDS && i < ARITY_PRIMARY, "PublicIo: index");
+        return words[1 + ARITY_PRIMARY + i];
+    }}
+
+    function z0Secondary(uint256[] calldata words, uint256 i) internal pure returns (uint256) {{
+        require(words.length == NUM_WORDS && i < ARITY_SECONDARY, "PublicIo: index");
+        return words[1 + 2 * ARITY_PRIMARY + i];
+    }}
+
+    function znSecondary(uint256[] calldata words, uint256 i) internal pure returns (uint256) {{
+        require(words.length == NUM_WORDS && i < ARITY_SECONDARY, "PublicIo: index");
+        return words[1 + 2 * ARITY_PRIMARY + ARITY_SECONDARY + i];
+    }}
+
+    function digest(uint256[] calldata words) internal pure returns (bytes32) {{
+        require(words.length == NUM_WORDS, "PublicIo: length");
+        return keccak256(abi.encodePacked(words));
+    }}
+
+    function verifierWords(uint256[] calldata words) internal pure returns (uint256[] memory out) {{
+        if (NUM_WORDS > MAX_INLINE_WORDS) {{
+            out = new uint256[](1);
+            out[0] = uint256(digest(words));
+        }} else {{
+            require(words.length == NUM_WORDS, "PublicIo: length");
+            out = words;
+        }}
+    }}
+}}
+"#
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+
+  use super::*;
+  use crate::provider::Bn256EngineKZG;
+
+  type E1 = Bn256EngineKZG;
+  type F1 = <E1 as Engine>::Scalar;
+  type F2 = <Dual<E1> as Engine>::Scalar;
+
+  #[test]
+  fn test_public_io() {
+    let io = PublicIo::<E1> {
+      num_steps:    3,
+      z0_primary:   vec![F1::ONE, F1::from(2)],
+      zn_primary:   vec![-F1::ONE, F1::ZERO],
+      z0_secondary: vec![F2::ZERO],
+      zn_secondary: vec![F2::from(0x0102)],
+    };
+    let words = io.to_words().unwrap();
+    assert_eq!(words.len(), 7);
+    assert_eq!(words[0][31], 3);
+    assert_eq!(words[1][31], 1);
+    assert_eq!(&words[6][30..], &[1, 2]);
+    assert_eq!(PublicIo::<E1>::from_words(&words, 2, 1), Ok(io.clone()));
+    assert_eq!(io.verifier_words(), Ok(words.clone()));
+
+    // the digest is the Keccak-256 of the words, as `abi.encodePacked` lays them
+    // out
+    assert_eq!(
+      hex::encode(keccak_words(&[[0u8; 32]])),
+      "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+    );
+    let bytes = words.iter().flatten().copied().collect::<Vec<u8>>();
+    assert_eq!(io.digest().unwrap(), <[u8; 32]>::from(Keccak256::digest(&bytes)));
+
+    // a long IO is passed as its digest
+    let long =
+      PublicIo::<E1> { zn_primary: vec![F1::ONE; 20], z0_primary: vec![F1::ONE; 20], ..io };
+    assert_eq!(long.verifier_words(), Ok(vec![long.digest().unwrap()]));
+
+    // non-canonical words and wrong lengths are rejected
+    let mut wrong = words.clone();
+    wrong[1] = [0xff; 32];
+    assert_eq!(PublicIo::<E1>::from_words(&wrong, 2, 1), Err(NovaError::NonCanonicalScalar));
+    assert_eq!(PublicIo::<E1>::from_words(&words, 2, 2), Err(NovaError::InvalidInputLength));
+
+    let library = solidity_library(2, 1);
+    assert!(library.contains("uint256 internal constant ARITY_PRIMARY = 2;"));
+    assert!(library.contains(&format!("MAX_INLINE_WORDS = {MAX_INLINE_WORDS};")));
+  }
+}
@@ -0,0 +1,618 @@
+//! Verifier crates for blockchains whose programs are compiled from Rust.
+//!
+//! [`generate`] emits the sources of a crate deploying a verifier of the
+//! compressed proofs of one circuit on a [`Chain`], a Solana program or a
+//! NEAR contract. The emitted program is a thin entrypoint around
+//! [`verify_compressed`], which verifies a `CompressedSNARK` against the
+//! verifier key embedded in the program, so that the chain runs the verifier
+//! of this crate, built with its `verify` feature only, rather than a port of
+//! it. The pairings of HyperKZG are checked by the alt_bn128 syscalls of the
+//! chain, with the `solana` and `near` features (see [`Packing::pack_pairing`]
+//! for their input); the rest of the verifier, including the inner-product
+//! argument of the secondary circuit over Grumpkin, which has no syscalls,
+//! runs in the program.
+//!
+//! The input of the program is packed by [`pack_compressed`]: the number of
+//! steps, the initial inputs of the primary and secondary circuits, and the
+//! encoding of the proof, and it returns the packed final outputs of the two
+//! circuits. Scalars and the number of steps are packed in the byte order of
+//! the alt_bn128 syscalls of the chain: big-endian on Solana, little-endian
+//! on NEAR. [`verify_packed`] similarly verifies a bare `FixedSumcheckProof`
+//! without allocating, for programs that reduce their own claims.
+//!
+//! The EVM runs Solidity verifiers instead: [`evm`] packs the public IO of a
+//! recursive proof into their words, along with the Solidity that reads them.
+use std::{
+  fs, io,
+  mem::size_of,
+  path::{Path, PathBuf},
+};
+
+use bincode::Options;
+use ff::{Field, PrimeField};
+use halo2curves::{
+  bn256::{Fq, G1Affine, G2Affine},
+  Coordinates, CurveAffine,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  provider::backend::{PcsBackend, PcsEngine},
+  scalar_bytes::{ScalarBytesBe, ScalarBytesLe},
+  spartan::{
+    embedded::{FixedSumcheckProof, FixedTranscript},
+    snark::RelaxedR1CSSNARK,
+  },
+  traits::{Dual, Engine},
+  CompressedSNARK, VerifierKey, VerifierKeyHeader, VerifierKeyOpening,
+};
+
+pub mod evm;
+mod near;
+mod solana;
+pub(crate) mod syscalls;
+
+/// The compressing SNARK of the primary circuit of the emitted verifiers, over
+/// the backend of `E1`
+pub type PrimarySNARK<E1> = RelaxedR1CSSNARK<E1, <E1 as PcsEngine>::EE>;
+
+/// The compressing SNARK of the secondary circuit of the emitted verifiers
+pub type SecondarySNARK<E1> = RelaxedR1CSSNARK<Dual<E1>, <E1 as PcsEngine>::SecondaryEE>;
+
+/// The compressed proofs the emitted verifiers verify
+pub type ChainSNARK<E1> = CompressedSNARK<E1, PrimarySNARK<E1>, SecondarySNARK<E1>>;
+
+/// The verifier keys of the emitted verifiers
+pub type ChainVerifierKey<E1> = VerifierKey<E1, PrimarySNARK<E1>, SecondarySNARK<E1>>;
+
+/// The label of the transcripts of [`verify_packed`], under which the public
+/// inputs are absorbed with `b"x"`
+pub const TRANSCRIPT_LABEL: &[u8] = b"edge-verifier";
+
+/// A chain to emit a verifier for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chain {
+  /// A Solana program, compiled to BPF
+  Solana,
+  /// A NEAR contract, compiled to wasm32
+  Near,
+}
+
+impl Chain {
+  /// The byte order of the scalars passed to and returned by programs on the
+  /// chain
+  pub const fn packing(self) -> Packing {
+    match self {
+      Self::Solana => Packing::BigEndian,
+      Self::Near => Packing::LittleEndian,
+    }
+  }
+}
+
+/// The byte order of a packed scalar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Packing {
+  /// the most significant byte first
+  BigEndian,
+  /// the least significant byte first, as in the representations of the
+  /// fields of this crate
+  LittleEndian,
+}
+
+impl Packing {
+  /// Packs `scalars` into `out`, of the length of their representations
+  pub fn pack_into<F: PrimeField>(self, scalars: &[F], out: &mut [u8]) -> Result<(), NovaError> {
+    if out.len() != scalars.len() * size_of::<F::Repr>() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    for (chunk, scalar) in out.chunks_exact_mut(size_of::<F::Repr>()).zip(scalars) {
+      match self {
+        Self::BigEndian => chunk.copy_from_slice(ScalarBytesBe::new(scalar).as_bytes()),
+        Self::LittleEndian => chunk.copy_from_slice(ScalarBytesLe::new(scalar).as_bytes()),
+      }
+    }
+    Ok(())
+  }
+
+  /// Packs `scalars`
+  // the output is sized to the scalars
+  #[allow(clippy::expect_used)]
+  pub fn pack<F: PrimeField>(self, scalars: &[F]) -> Vec<u8> {
+    let mut out = vec![0u8; scalars.len() * size_of::<F::Repr>()];
+    self.pack_into(scalars, &mut out).expect("the output fits the scalars");
+    out
+  }
+
+  /// Unpacks `bytes` into `out`, which fails on non-canonical scalars
+  pub fn unpack_into<F: PrimeField>(self, bytes: &[u8], out: &mut [F]) -> Result<(), NovaError> {
+    if bytes.len() != out.len() * size_of::<F::Repr>() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    for (scalar, chunk) in out.iter_mut().zip(bytes.chunks_exact(size_of::<F::Repr>())) {
+      let unpacked = match self {
+        Self::BigEndian => ScalarBytesBe::<F>::from_slice(chunk)?.to_scalar(),
+        Self::LittleEndian => ScalarBytesLe::<F>::from_slice(chunk)?.to_scalar(),
+      };
+      *scalar = unpacked?;
+    }
+    Ok(())
+  }
+
+  /// Unpacks `bytes`, which fails on non-canonical scalars
+  pub fn unpack<F: PrimeField>(self, bytes: &[u8]) -> Result<Vec<F>, NovaError> {
+    if bytes.len() % size_of::<F::Repr>() != 0 {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut out = vec![F::ZERO; bytes.len() / size_of::<F::Repr>()];
+    self.unpack_into(bytes, &mut out)?;
+    Ok(out)
+  }
+
+  /// Packs `len` into 8 bytes
+  pub const fn pack_len(self, len: u64) -> [u8; 8] {
+    match self {
+      Self::BigEndian => len.to_be_bytes(),
+      Self::LittleEndian => len.to_le_bytes(),
+    }
+  }
+
+  /// Unpacks a length packed by [`Packing::pack_len`]
+  pub const fn unpack_len(self, bytes: [u8; 8]) -> u64 {
+    match self {
+      Self::BigEndian => u64::from_be_bytes(bytes),
+      Self::LittleEndian => u64::from_le_bytes(bytes),
+    }
+  }
+
+  /// Packs the input of the alt_bn128 pairing check of the chains, which
+  /// checks that the product of the pairings of `pairs` is the identity.
+  ///
+  /// Each pair is 192 bytes, the coordinates `x`, `y` of the point of G1,
+  /// then those of the point of G2, the point at infinity being all zeros.
+  /// Big-endian, this is the input of the precompile of the EVM and of the
+  /// syscall of Solana, whose coordinates in the quadratic extension field
+  /// are the imaginary part first; little-endian, that of the host function
+  /// of NEAR, whose are the real part first.
+  pub fn pack_pairing(self, pairs: &[(G1Affine, G2Affine)]) -> Vec<u8> {
+    pairs
+      .iter()
+      .flat_map(|(p, q)| {
+        let g1 = Option::<Coordinates<_>>::from(p.coordinates())
+          .map_or([Fq::ZERO; 2], |c| [*c.x(), *c.y()]);
+        let g2 = Option::<Coordinates<_>>::from(q.coordinates()).map_or([Fq::ZERO; 4], |c| {
+          let (x, y) = (c.x(), c.y());
+          match self {
+            Self::BigEndian => [x.c1, x.c0, y.c1, y.c0],
+            Self::LittleEndian => [x.c0, x.c1, y.c0, y.c1],
+          }
+        });
+        let mut pair = self.pack(&g1);
+        pair.extend(self.pack(&g2));
+        pair
+      })
+      .collect()
+  }
+}
+
+/// Packs the input of a verifier on `chain`, for the proof `proof` of
+/// `claim` about the public inputs `inputs`
+pub fn pack_instruction<F: PrimeField, const MAX_ROUNDS: usize, const DEGREE: usize>(
+  chain: Chain,
+  inputs: &[F],
+  claim: F,
+  proof: &FixedSumcheckProof<F, MAX_ROUNDS, DEGREE>,
+) -> Result<Vec<u8>, NovaError> {
+  let mut instruction = chain.packing().pack(inputs);
+  instruction.extend(chain.packing().pack(&[claim]));
+  let mut encoding = vec![0u8; FixedSumcheckProof::<F, MAX_ROUNDS, DEGREE>::ENCODED_LEN];
+  proof.encode(&mut encoding)?;
+  instruction.extend(encoding);
+  Ok(instruction)
+}
+
+/// Verifies the input `input` of a verifier on `chain`, for `INPUTS` public
+/// inputs and a proof of `num_rounds` rounds, without allocating, and writes
+/// its output into `output`: the packed reduced claim, followed by the
+/// packed challenges
+pub fn verify_packed<
+  E: Engine,
+  const INPUTS: usize,
+  const MAX_ROUNDS: usize,
+  const DEGREE: usize,
+>(
+  chain: Chain,
+  num_rounds: usize,
+  input: &[u8],
+  output: &mut [u8],
+) -> Result<(), NovaError> {
+  let len = size_of::<<E::Scalar as PrimeField>::Repr>();
+  if input.len()
+    != (INPUTS + 1) * len + FixedSumcheckProof::<E::Scalar, MAX_ROUNDS, DEGREE>::ENCODED_LEN
+    || num_rounds > MAX_ROUNDS
+    || output.len() != (num_rounds + 1) * len
+  {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let (inputs_bytes, input) = input.split_at(INPUTS * len);
+  let (claim_bytes, proof_bytes) = input.split_at(len);
+
+  let mut inputs = [E::Scalar::ZERO; INPUTS];
+  chain.packing().unpack_into(inputs_bytes, &mut inputs)?;
+  let mut claim = E::Scalar::ZERO;
+  chain.packing().unpack_into(claim_bytes, core::slice::from_mut(&mut claim))?;
+  let proof = FixedSumcheckProof::<E::Scalar, MAX_ROUNDS, DEGREE>::decode(proof_bytes)?;
+
+  let mut transcript = FixedTranscript::<E>::new(TRANSCRIPT_LABEL);
+  transcript.absorb(b"x", &inputs);
+  let (e, r) = proof.verify(claim, num_rounds, E::SECURITY_LEVEL, &mut transcript)?;
+
+  let (e_bytes, r_bytes) = output.split_at_mut(len);
+  chain.packing().pack_into(&[e], e_bytes)?;
+  chain.packing().pack_into(&r[..num_rounds], r_bytes)
+}
+
+/// Decodes the `bincode` encoding `bytes`, which must have no trailing bytes
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, NovaError> {
+  bincode::DefaultOptions::new()
+    .with_fixint_encoding()
+    .reject_trailing_bytes()
+    .deserialize(bytes)
+    .map_err(|e| NovaError::SerializationError(e.to_string()))
+}
+
+/// Encodes `value` with `bincode`
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, NovaError> {
+  bincode::serialize(value).map_err(|e| NovaError::SerializationError(e.to_string()))
+}
+
+/// The verifier key embedded in an emitted verifier. The verifier keys of
+/// the compressing SNARKs, the bulk of it, are encoded apart, so that they
+/// are only decoded once the instances of a proof check.
+#[derive(Serialize, Deserialize)]
+struct EmbeddedKey {
+  opening:      Vec<u8>,
+  header:       Vec<u8>,
+  vk_primary:   Vec<u8>,
+  vk_secondary: Vec<u8>,
+}
+
+impl EmbeddedKey {
+  fn new<E1: PcsEngine>(vk: &ChainVerifierKey<E1>) -> Result<Self, NovaError> {
+    Ok(Self {
+      opening:      encode(&vk.opening()?)?,
+      header:       encode(vk.header())?,
+      vk_primary:   encode(vk.vk_primary())?,
+      vk_secondary: encode(vk.vk_secondary())?,
+    })
+  }
+}
+
+/// Packs the input of a verifier on `chain`, for the proof `snark` of
+/// `num_steps` steps from the initial inputs `z0_primary` and `z0_secondary`
+pub fn pack_compressed<E1: PcsEngine>(
+  chain: Chain,
+  snark: &ChainSNARK<E1>,
+  num_steps: usize,
+  z0_primary: &[E1::Scalar],
+  z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+) -> Result<Vec<u8>, NovaError> {
+  let num_steps = u64::try_from(num_steps).map_err(|_| NovaError::InvalidInputLength)?;
+  let mut instruction = chain.packing().pack_len(num_steps).to_vec();
+  instruction.extend(chain.packing().pack(z0_primary));
+  instruction.extend(chain.packing().pack(z0_secondary));
+  instruction.extend(encode(snark)?);
+  Ok(instruction)
+}
+
+/// Verifies the input `input` of a verifier on `chain`, packed by
+/// [`pack_compressed`], against the key embedded by [`generate`] as `key`,
+/// which must have the digest `vk_digest` (the little-endian bytes of
+/// [`VerifierKey::digest`], as a `keyring::VerifierKeyring` addresses it).
+/// Returns the packed final outputs of the primary circuit, followed by those
+/// of the secondary circuit.
+pub fn verify_compressed<E1: PcsEngine>(
+  chain: Chain,
+  vk_digest: &[u8],
+  key: &[u8],
+  input: &[u8],
+) -> Result<Vec<u8>, NovaError> {
+  let vk_digest = ScalarBytesLe::<E1::Scalar>::from_slice(vk_digest)?.to_scalar()?;
+  let key: EmbeddedKey = decode(key)?;
+  let opening: VerifierKeyOpening<E1> = decode(&key.opening)?;
+  let header: VerifierKeyHeader<E1> = decode(&key.header)?;
+
+  let len_primary = header.arity_primary() * size_of::<<E1::Scalar as PrimeField>::Repr>();
+  let len_secondary =
+    header.arity_secondary() * size_of::<<<Dual<E1> as Engine>::Scalar as PrimeField>::Repr>();
+  if input.len() < 8 + len_primary + len_secondary {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let (num_steps, input) = input.split_at(8);
+  let (z0_primary, input) = input.split_at(len_primary);
+  let (z0_secondary, proof) = input.split_at(len_secondary);
+
+  let num_steps = num_steps.try_into().map_err(|_| NovaError::InvalidInputLength)?;
+  let num_steps = chain.packing().unpack_len(num_steps);
+  let num_steps = usize::try_from(num_steps).map_err(|_| NovaError::InvalidInputLength)?;
+  let z0_primary = chain.packing().unpack(z0_primary)?;
+  let z0_secondary = chain.packing().unpack(z0_secondary)?;
+  let snark: ChainSNARK<E1> = decode(proof)?;
+
+  let (zn_primary, zn_secondary) = snark.verify_with_digest(
+    &vk_digest,
+    &opening,
+    &header,
+    || decode(&key.vk_primary),
+    || decode(&key.vk_secondary),
+    num_steps,
+    &z0_primary,
+    &z0_secondary,
+  )?;
+  let mut output = chain.packing().pack(&zn_primary);
+  output.extend(chain.packing().pack(&zn_secondary));
+  Ok(output)
+}
+
+/// The sources of an emitted verifier crate, by path relative to its root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifierCrate {
+  /// the files of the crate
+  pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl VerifierCrate {
+  /// Writes the crate into `dir`
+  pub fn write(&self, dir: &Path) -> io::Result<()> {
+    for (path, contents) in &self.files {
+      let path = dir.join(path);
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::write(path, contents)?;
+    }
+    Ok(())
+  }
+}
+
+/// The path of the embedded verifier key in an emitted crate
+pub const KEY_PATH: &str = "key.bin";
+
+/// Emits the sources of a crate named `name` verifying, on `chain`, the
+/// compressed proofs of the circuit of `vk`
+pub fn generate<E1: PcsEngine>(
+  chain: Chain,
+  name: &str,
+  vk: &ChainVerifierKey<E1>,
+) -> Result<VerifierCrate, NovaError> {
+  if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let engine = match E1::BACKEND {
+    PcsBackend::Ipa => "Bn256EngineIPA",
+    PcsBackend::Kzg => "Bn256EngineKZG",
+    PcsBackend::Hash => return Err(NovaError::UnsupportedBackend),
+  };
+  let key = encode(&EmbeddedKey::new(vk)?)?;
+  let constants = key_constants(engine, &vk.digest()?);
+  let (cargo_toml, lib_rs) = match chain {
+    Chain::Solana => (solana::cargo_toml(name), solana::lib_rs(&constants)),
+    Chain::Near => (near::cargo_toml(name), near::lib_rs(&constants)),
+  };
+  Ok(VerifierCrate {
+    files: vec![
+      (PathBuf::from("Cargo.toml"), cargo_toml.into_bytes()),
+      (PathBuf::from("src/lib.rs"), lib_rs.into_bytes()),
+      (PathBuf::from(KEY_PATH), key),
+    ],
+  })
+}
+
+/// The engine and the verifier key of the proofs, shared by the emitted
+/// sources
+fn key_constants<F: PrimeField>(engine: &str, vk_digest: &F) -> String {
+  let digest = ScalarBytesLe::new(vk_digest)
+    .as_bytes()
+    .chunks(8)
+    .map(|chunk| {
+      let bytes = chunk.iter().map(|b| format!("0x{b:02x},")).collect::<Vec<_>>();
+      format!("  {}\n", bytes.join(" "))
+    })
+    .collect::<String>();
+  format!(
+    "/// The engine of the proofs\ntype E1 = edge_prover::provider::{engine};\n\n/// The digest \
+     of the verifier key, little-endian\nconst VK_DIGEST: &[u8] = &[\n{digest}];\n\n/// The \
+     verifier key, embedded by `generate`\nconst KEY: &[u8] = include_bytes!(\"../{KEY_PATH}\");\n"
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use std::process::Command;
+
+  use group::prime::PrimeCurveAffine;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    provider::Bn256EngineKZG,
+    spartan::ExtSumcheckProof,
+    supernova::TrivialCircuit,
+    traits::{snark::RelaxedR1CSSNARKTrait, TranscriptEngineTrait},
+    PublicParams, RecursiveSNARK,
+  };
+
+  type E = Bn256EngineKZG;
+  type F = <E as Engine>::Scalar;
+  type F2 = <Dual<E> as Engine>::Scalar;
+
+  /// The verifier key and a proof of one step of the trivial circuit, with
+  /// its initial inputs
+  fn prove() -> (ChainVerifierKey<E>, ChainSNARK<E>, Vec<F>, Vec<F2>) {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E>::setup(
+      &circuit,
+      &circuit,
+      &*PrimarySNARK::<E>::ck_floor(),
+      &*SecondarySNARK::<E>::ck_floor(),
+    )
+    .unwrap();
+    let (z0_primary, z0_secondary) = (vec![F::from(7)], vec![F2::from(11)]);
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+    let (pk, vk) = ChainSNARK::<E>::setup(&pp).unwrap();
+    let snark = ChainSNARK::<E>::prove(&pp, &pk, &recursive_snark).unwrap();
+    (vk, snark, z0_primary, z0_secondary)
+  }
+
+  #[test]
+  fn test_verify_packed() {
+    let num_vars = 4;
+    let inputs = [F::from(7), F::from(11)];
+    let a = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let b = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let claim = a.iter().zip(&b).map(|(a, b)| *a * b).sum::<F>();
+    let comb = |v: &[F]| v[0] * v[1];
+
+    let mut transcript = <E as Engine>::TE::new(TRANSCRIPT_LABEL);
+    transcript.absorb(b"x", &inputs.as_slice());
+    let (proof, r, final_evals) =
+      ExtSumcheckProof::prove(claim, &[a.as_slice(), b.as_slice()], 2, comb, &mut transcript)
+        .unwrap();
+    let proof = FixedSumcheckProof::<F, 8, 2>::from_proof(&proof).unwrap();
+
+    for chain in [Chain::Solana, Chain::Near] {
+      let instruction = pack_instruction(chain, &inputs, claim, &proof).unwrap();
+      let mut output = vec![0u8; 32 * (num_vars + 1)];
+      verify_packed::<E, 2, 8, 2>(chain, num_vars, &instruction, &mut output).unwrap();
+
+      let mut values = vec![F::ZERO; num_vars + 1];
+      chain.packing().unpack_into(&output, &mut values).unwrap();
+      assert_eq!(values[0], comb(&final_evals));
+      assert_eq!(values[1..], r[..]);
+
+      // the proof is bound to the public inputs
+      let other = pack_instruction(chain, &[F::from(7), F::from(12)], claim, &proof).unwrap();
+      verify_packed::<E, 2, 8, 2>(chain, num_vars, &other, &mut output).unwrap();
+      chain.packing().unpack_into(&output, &mut values).unwrap();
+      assert_ne!(values[0], comb(&final_evals));
+    }
+
+    // the chains disagree on the byte order
+    assert_eq!(Packing::BigEndian.pack(&[F::ONE])[31], 1);
+    assert_eq!(Packing::LittleEndian.pack(&[F::ONE])[0], 1);
+
+    // scalars above the modulus are rejected as such
+    assert_eq!(Packing::BigEndian.unpack::<F>(&[0xff; 32]), Err(NovaError::NonCanonicalScalar));
+    assert_eq!(Packing::LittleEndian.unpack::<F>(&[0; 31]), Err(NovaError::InvalidInputLength));
+  }
+
+  #[test]
+  fn test_pack_pairing() {
+    let pairs = [
+      (G1Affine::generator(), G2Affine::generator()),
+      (G1Affine::identity(), G2Affine::identity()),
+    ];
+    let be = Packing::BigEndian.pack_pairing(&pairs);
+    let le = Packing::LittleEndian.pack_pairing(&pairs);
+    assert_eq!((be.len(), le.len()), (2 * 192, 2 * 192));
+
+    // the generator of G1 is (1, 2)
+    assert_eq!((be[31], be[63]), (1, 2));
+    assert_eq!((le[0], le[32]), (1, 2));
+    // the real part of the abscissa of the generator of G2 starts with 0x1800,
+    // and its imaginary part with 0x198e, which is first big-endian only
+    assert_eq!(be[64..66], [0x19, 0x8e]);
+    assert_eq!(be[96..98], [0x18, 0x00]);
+    assert_eq!(le[94..96], [0x00, 0x18]);
+    assert_eq!(le[126..128], [0x8e, 0x19]);
+    // the points at infinity are zeros
+    assert!(be[192..].iter().chain(&le[192..]).all(|b| *b == 0));
+  }
+
+  #[test]
+  fn test_verify_compressed() {
+    let (vk, snark, z0_primary, z0_secondary) = prove();
+    let key = encode(&EmbeddedKey::new(&vk).unwrap()).unwrap();
+    let vk_digest = ScalarBytesLe::new(&vk.digest().unwrap()).as_bytes().to_vec();
+
+    for chain in [Chain::Solana, Chain::Near] {
+      let verify = |input: &[u8]| verify_compressed::<E>(chain, &vk_digest, &key, input);
+      let input = pack_compressed(chain, &snark, 1, &z0_primary, &z0_secondary).unwrap();
+      let mut outputs = chain.packing().pack(&z0_primary);
+      outputs.extend(chain.packing().pack(&z0_secondary));
+      assert_eq!(verify(&input), Ok(outputs));
+
+      // the proof is bound to its number of steps and initial inputs
+      let wrong = pack_compressed(chain, &snark, 2, &z0_primary, &z0_secondary).unwrap();
+      assert!(verify(&wrong).is_err());
+      let wrong = pack_compressed(chain, &snark, 1, &[F::from(8)], &z0_secondary).unwrap();
+      assert!(verify(&wrong).is_err());
+
+      // the inputs must be canonical, and the proof have no trailing bytes
+      let mut wrong = input.clone();
+      wrong[8..40].fill(0xff);
+      assert_eq!(verify(&wrong), Err(NovaError::NonCanonicalScalar));
+      let mut wrong = input.clone();
+      wrong.push(0);
+      assert!(matches!(verify(&wrong), Err(NovaError::SerializationError(_))));
+      assert_eq!(verify(&input[..40]), Err(NovaError::InvalidInputLength));
+
+      // the embedded key must be that of the digest
+      let mut wrong_digest = vk_digest.clone();
+      wrong_digest[0] ^= 1;
+      assert_eq!(
+        verify_compressed::<E>(chain, &wrong_digest, &key, &input),
+        Err(NovaError::ProofVerifyError)
+      );
+    }
+  }
+
+  #[test]
+  fn test_generate() {
+    let (vk, ..) = prove();
+    for (chain, entrypoint) in [(Chain::Solana, "entrypoint!"), (Chain::Near, "env::input")] {
+      let verifier = generate(chain, "verifier", &vk).unwrap();
+      let file = |path: &str| {
+        let (_, contents) = verifier.files.iter().find(|(p, _)| p == Path::new(path)).unwrap();
+        contents.clone()
+      };
+      let lib_rs = String::from_utf8(file("src/lib.rs")).unwrap();
+      assert!(lib_rs.contains(entrypoint));
+      assert!(lib_rs.contains("type E1 = edge_prover::provider::Bn256EngineKZG;"));
+      assert!(String::from_utf8(file("Cargo.toml")).unwrap().contains("\"verifier\""));
+      assert_eq!(file(KEY_PATH), encode(&EmbeddedKey::new(&vk).unwrap()).unwrap());
+    }
+
+    assert_eq!(generate(Chain::Solana, "a verifier", &vk), Err(NovaError::InvalidInputLength));
+  }
+
+  // cargo test --release test_generated_crates_build -- --ignored
+  #[test]
+  #[ignore]
+  fn test_generated_crates_build() {
+    let (vk, ..) = prove();
+    let dir = std::env::temp_dir().join(format!("edge-verifiers-{}", std::process::id()));
+    for (chain, name) in [(Chain::Solana, "solana-verifier"), (Chain::Near, "near-verifier")] {
+      let crate_dir = dir.join(name);
+      generate(chain, name, &vk).unwrap().write(&crate_dir).unwrap();
+
+      // the crate is built against this tree, off any workspace
+      let manifest = crate_dir.join("Cargo.toml");
+      let cargo_toml = fs::read_to_string(&manifest).unwrap().replace(
+        "git = \"https://github.com/pluto/edge\"",
+        &format!("path = {:?}", env!("CARGO_MANIFEST_DIR")),
+      );
+      fs::write(&manifest, cargo_toml + "\n[workspace]\n").unwrap();
+      let status = Command::new(env!("CARGO"))
+        .arg("check")
+        .current_dir(&crate_dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .status()
+        .unwrap();
+      assert!(status.success(), "the emitted {name} does not build");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
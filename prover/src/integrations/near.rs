@@ -0,0 +1,35 @@
+//! The sources of a NEAR verifier contract, whose `verify` method returns its
+//! output as the return value of the call.
+const CARGO_TOML: &str = r#"[package]
+edition = "2021"
+name    = "{name}"
+version = "0.1.0"
+
+[lib]
+crate-type = ["cdylib", "rlib"]
+
+[dependencies]
+edge-prover = { git = "https://github.com/pluto/edge", default-features = false, features = ["near"] }
+near-sdk    = "5"
+"#;
+
+const LIB_RS: &str = r#"//! A NEAR contract verifying the compressed proofs of one circuit, emitted
+//! by `edge_prover::integrations::generate`.
+use edge_prover::integrations::{verify_compressed, Chain};
+use near_sdk::env;
+
+{constants}
+/// Verifies the proof in the input of the call, and returns the final
+/// outputs of the circuits, little-endian
+#[no_mangle]
+pub extern "C" fn verify() {
+  let input = env::input().unwrap_or_else(|| env::panic_str("missing input"));
+  let output = verify_compressed::<E1>(Chain::Near, VK_DIGEST, KEY, &input)
+    .unwrap_or_else(|_| env::panic_str("invalid proof"));
+  env::value_return(&output);
+}
+"#;
+
+pub(super) fn cargo_toml(name: &str) -> String { CARGO_TOML.replace("{name}", name) }
+
+pub(super) fn lib_rs(constants: &str) -> String { LIB_RS.replace("{constants}", constants) }
@@ -0,0 +1,43 @@
+//! The sources of a Solana verifier program, which returns its output as the
+//! return data of the instruction.
+const CARGO_TOML: &str = r#"[package]
+edition = "2021"
+name    = "{name}"
+version = "0.1.0"
+
+[lib]
+crate-type = ["cdylib", "lib"]
+
+[dependencies]
+edge-prover    = { git = "https://github.com/pluto/edge", default-features = false, features = ["solana"] }
+solana-program = "1.18"
+"#;
+
+const LIB_RS: &str = r#"//! A Solana program verifying the compressed proofs of one circuit, emitted
+//! by `edge_prover::integrations::generate`.
+use edge_prover::integrations::{verify_compressed, Chain};
+use solana_program::{
+  account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, program::set_return_data,
+  program_error::ProgramError, pubkey::Pubkey,
+};
+
+{constants}
+entrypoint!(process_instruction);
+
+/// Verifies the proof in `instruction_data`, and returns the final outputs
+/// of the circuits, big-endian
+pub fn process_instruction(
+  _program_id: &Pubkey,
+  _accounts: &[AccountInfo],
+  instruction_data: &[u8],
+) -> ProgramResult {
+  let output = verify_compressed::<E1>(Chain::Solana, VK_DIGEST, KEY, instruction_data)
+    .map_err(|_| ProgramError::InvalidInstructionData)?;
+  set_return_data(&output);
+  Ok(())
+}
+"#;
+
+pub(super) fn cargo_toml(name: &str) -> String { CARGO_TOML.replace("{name}", name) }
+
+pub(super) fn lib_rs(constants: &str) -> String { LIB_RS.replace("{constants}", constants) }
@@ -0,0 +1,101 @@
+//! The alt_bn128 syscalls of the chains the emitted verifiers run on.
+//!
+//! The verifiers of HyperKZG over BN254 (see `provider::hyperkzg`) end with a
+//! check of two pairings, which is most of their cost in a program. With the
+//! `solana` or `near` feature, and compiled for that chain, the check is made
+//! by the chain instead, and [`pairing_check`] returns its result; elsewhere
+//! it returns `None`, and the verifier computes the pairings itself.
+#[cfg(any(
+  all(feature = "solana", target_os = "solana"),
+  all(feature = "near", target_arch = "wasm32")
+))]
+use std::any::Any;
+
+#[cfg(any(
+  all(feature = "solana", target_os = "solana"),
+  all(feature = "near", target_arch = "wasm32")
+))]
+use halo2curves::bn256::{G1Affine, G2Affine};
+use pairing::Engine;
+
+#[cfg(all(feature = "solana", target_os = "solana"))]
+extern "C" {
+  fn sol_alt_bn128_group_op(
+    group_op: u64,
+    input: *const u8,
+    input_size: u64,
+    result: *mut u8,
+  ) -> u64;
+}
+
+#[cfg(all(feature = "near", target_arch = "wasm32"))]
+extern "C" {
+  fn alt_bn128_pairing_check(value_len: u64, value_ptr: u64) -> u64;
+}
+
+/// The operation of `sol_alt_bn128_group_op` that checks pairings
+#[cfg(all(feature = "solana", target_os = "solana"))]
+const ALT_BN128_PAIRING: u64 = 3;
+
+/// The pairs of `pairs`, if `E` is BN254
+#[cfg(any(
+  all(feature = "solana", target_os = "solana"),
+  all(feature = "near", target_arch = "wasm32")
+))]
+fn bn256_pairs<E: Engine + 'static>(
+  pairs: &[(E::G1Affine, E::G2Affine)],
+) -> Option<Vec<(G1Affine, G2Affine)>> {
+  pairs
+    .iter()
+    .map(|(p, q)| {
+      let p = (p as &dyn Any).downcast_ref::<G1Affine>()?;
+      let q = (q as &dyn Any).downcast_ref::<G2Affine>()?;
+      Some((*p, *q))
+    })
+    .collect()
+}
+
+/// Whether the product of the pairings of `pairs` is the identity, as
+/// checked by the syscall of the chain, or `None` if there is none for `E`
+#[cfg(all(feature = "solana", target_os = "solana"))]
+pub(crate) fn pairing_check<E: Engine + 'static>(
+  pairs: &[(E::G1Affine, E::G2Affine)],
+) -> Option<bool> {
+  let input = super::Packing::BigEndian.pack_pairing(&bn256_pairs::<E>(pairs)?);
+  let mut result = [0u8; 32];
+  // SAFETY: `input` and `result` are valid for the lengths passed, and the
+  // syscall writes the 32 bytes of its result only
+  let status = unsafe {
+    sol_alt_bn128_group_op(
+      ALT_BN128_PAIRING,
+      input.as_ptr(),
+      input.len() as u64,
+      result.as_mut_ptr(),
+    )
+  };
+  Some(status == 0 && result[..31].iter().all(|b| *b == 0) && result[31] == 1)
+}
+
+/// Whether the product of the pairings of `pairs` is the identity, as
+/// checked by the host function of the chain, or `None` if there is none for
+/// `E`
+#[cfg(all(feature = "near", target_arch = "wasm32"))]
+pub(crate) fn pairing_check<E: Engine + 'static>(
+  pairs: &[(E::G1Affine, E::G2Affine)],
+) -> Option<bool> {
+  let input = super::Packing::LittleEndian.pack_pairing(&bn256_pairs::<E>(pairs)?);
+  // SAFETY: `input` is valid for the length passed, which the host reads only
+  let result = unsafe { alt_bn128_pairing_check(input.len() as u64, input.as_ptr() as u64) };
+  Some(result == 1)
+}
+
+/// Off-chain, there is no syscall to check pairings with
+#[cfg(not(any(
+  all(feature = "solana", target_os = "solana"),
+  all(feature = "near", target_arch = "wasm32")
+)))]
+pub(crate) fn pairing_check<E: Engine + 'static>(
+  _pairs: &[(E::G1Affine, E::G2Affine)],
+) -> Option<bool> {
+  None
+}
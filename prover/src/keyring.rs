@@ -0,0 +1,366 @@
+//! The verifier keys of several circuits, of any engines, addressed by digest.
+//!
+//! A [`ProofEnvelope`] carries a compressed proof along with the digest of the
+//! verifier key it verifies with (see [`VerifierKey::digest`]), and the inputs
+//! and application context it was made for, all as bytes. A
+//! [`VerifierKeyring`] holds the keys of the circuits a deployment accepts
+//! proofs of, and [`VerifierKeyring::verify`] verifies an envelope with the key
+//! of its digest, decoding the proof for the engines and SNARKs of that key, so
+//! that a gateway verifies the proofs of every circuit through one entry point.
+//!
+//! An envelope may carry an attestation of where its proof was made, e.g. a
+//! device attestation or a TEE quote, as opaque bytes: the proof is made in
+//! the [`attested_context`] of the attestation, which binds it into the
+//! transcripts, and the last initial input of its primary circuit is the
+//! [`attestation_digest`]. The keyring checks both, and returns the
+//! attestation with the outputs, for the deployment to enforce its policy of
+//! provenance on.
+//!
+//! The digests and scalars of envelopes are the little-endian bytes of their
+//! scalars (see [`crate::scalar_bytes`]), and the proofs are encoded with
+//! `bincode`.
+use std::{
+  fmt::{self, Write as _},
+  mem::size_of,
+  sync::Arc,
+};
+
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  scalar_bytes::ScalarBytesLe,
+  traits::{
+    length_prefixed,
+    snark::{AppContext, RelaxedR1CSSNARKTrait},
+    CurveCycleEquipped, Dual, Engine, PrimeFieldExt,
+  },
+  CompressedSNARK, VerifierKey,
+};
+
+/// A compressed proof, addressed to the verifier key it verifies with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofEnvelope {
+  /// The digest of the verifier key
+  pub vk_digest:    Vec<u8>,
+  /// The number of steps of the computation
+  pub num_steps:    usize,
+  /// The initial inputs of the primary circuit
+  pub z0_primary:   Vec<u8>,
+  /// The initial inputs of the secondary circuit
+  pub z0_secondary: Vec<u8>,
+  /// The application context the proof was made in, empty by default (see
+  /// [`AppContext`])
+  pub context:      Vec<u8>,
+  /// The encoding of the `CompressedSNARK`
+  pub proof:        Vec<u8>,
+  /// The attestation of where the proof was made, empty if none
+  #[serde(default)]
+  pub attestation:  Vec<u8>,
+}
+
+/// The outputs of a proof verified by a [`VerifierKeyring`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedOutputs {
+  /// The outputs of the primary circuit
+  pub zn_primary:   Vec<u8>,
+  /// The outputs of the secondary circuit
+  pub zn_secondary: Vec<u8>,
+  /// The attestation of the proof, empty if none
+  #[serde(default)]
+  pub attestation:  Vec<u8>,
+}
+
+/// The digest of `attestation`, hashed to the scalar field, which a proof
+/// carrying it has as the last initial input of its primary circuit
+pub fn attestation_digest<E: Engine>(attestation: &[u8]) -> E::Scalar {
+  let mut hasher = blake3::Hasher::new_derive_key("edge-prover attestation");
+  hasher.update(attestation);
+  let mut uniform = [0u8; 64];
+  hasher.finalize_xof().fill(&mut uniform);
+  E::Scalar::from_uniform(&uniform)
+}
+
+/// The application context a proof carrying `attestation` is made in, for
+/// an application of context `context`: `context` itself if there is no
+/// attestation
+pub fn attested_context(context: AppContext<'_>, attestation: &[u8]) -> Vec<u8> {
+  if attestation.is_empty() {
+    return context.0.to_vec();
+  }
+  let hash = blake3::hash(attestation).as_bytes().to_vec();
+  length_prefixed([context.0.to_vec(), b"attestation".to_vec(), hash])
+}
+
+/// The little-endian bytes of `scalars`
+fn pack<F: PrimeField>(scalars: &[F]) -> Vec<u8> {
+  scalars.iter().flat_map(|scalar| ScalarBytesLe::new(scalar).as_bytes().to_vec()).collect()
+}
+
+/// The scalars of the little-endian bytes `bytes`, which must be canonical
+fn unpack<F: PrimeField>(bytes: &[u8]) -> Result<Vec<F>, NovaError> {
+  let len = size_of::<F::Repr>();
+  if bytes.len() % len != 0 {
+    return Err(NovaError::InvalidInputLength);
+  }
+  bytes.chunks_exact(len).map(|chunk| ScalarBytesLe::<F>::from_slice(chunk)?.to_scalar()).collect()
+}
+
+impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  /// The envelope of this proof, made in the application context `context`,
+  /// addressed to `vk`
+  pub fn envelope(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> Result<ProofEnvelope, NovaError> {
+    Ok(ProofEnvelope {
+      vk_digest: ScalarBytesLe::new(&vk.digest()?).as_bytes().to_vec(),
+      num_steps,
+      z0_primary: pack(z0_primary),
+      z0_secondary: pack(z0_secondary),
+      context: context.0.to_vec(),
+      proof: bincode::serialize(self).map_err(|e| NovaError::SerializationError(e.to_string()))?,
+      attestation: Vec::new(),
+    })
+  }
+
+  /// The envelope of this proof, made in the [`attested_context`] of
+  /// `attestation` and `context`, carrying `attestation`. Fails with
+  /// [`NovaError::InvalidAttestation`] unless the last of `z0_primary` is the
+  /// [`attestation_digest`].
+  pub fn attested_envelope(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+    attestation: &[u8],
+  ) -> Result<ProofEnvelope, NovaError> {
+    if z0_primary.last() != Some(&attestation_digest::<E1>(attestation)) {
+      return Err(NovaError::InvalidAttestation);
+    }
+    let envelope = self.envelope(vk, num_steps, z0_primary, z0_secondary, context)?;
+    Ok(ProofEnvelope { attestation: attestation.to_vec(), ..envelope })
+  }
+}
+
+/// A verifier key with its engines and SNARKs erased
+trait EnvelopeVerifier: Send + Sync {
+  fn verify(&self, envelope: &ProofEnvelope) -> Result<VerifiedOutputs, NovaError>;
+}
+
+impl<E1, S1, S2> EnvelopeVerifier for VerifierKey<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  fn verify(&self, envelope: &ProofEnvelope) -> Result<VerifiedOutputs, NovaError> {
+    let snark: CompressedSNARK<E1, S1, S2> = bincode::deserialize(&envelope.proof)
+      .map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    let z0_primary = unpack(&envelope.z0_primary)?;
+    if !envelope.attestation.is_empty()
+      && z0_primary.last() != Some(&attestation_digest::<E1>(&envelope.attestation))
+    {
+      return Err(NovaError::InvalidAttestation);
+    }
+    let (zn_primary, zn_secondary) = snark.verify_with_context(
+      self,
+      envelope.num_steps,
+      &z0_primary,
+      &unpack(&envelope.z0_secondary)?,
+      AppContext(&attested_context(AppContext(&envelope.context), &envelope.attestation)),
+    )?;
+    Ok(VerifiedOutputs {
+      zn_primary:   pack(&zn_primary),
+      zn_secondary: pack(&zn_secondary),
+      attestation:  envelope.attestation.clone(),
+    })
+  }
+}
+
+/// The verifier keys of several circuits, keyed by digest
+#[derive(Default)]
+pub struct VerifierKeyring {
+  keys: Vec<(Vec<u8>, Arc<dyn EnvelopeVerifier>)>,
+}
+
+impl fmt::Debug for VerifierKeyring {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let digests = self.digests().map(|digest| {
+      digest.iter().fold(String::new(), |mut output, b| {
+        let _ = write!(output, "{b:02x}");
+        output
+      })
+    });
+    f.debug_list().entries(digests).finish()
+  }
+}
+
+impl VerifierKeyring {
+  /// Creates an empty keyring
+  pub fn new() -> Self { Self::default() }
+
+  /// Adds `vk`, replacing a key of the same digest, and returns its digest
+  pub fn insert<E1, S1, S2>(
+    &mut self,
+    vk: Arc<VerifierKey<E1, S1, S2>>,
+  ) -> Result<Vec<u8>, NovaError>
+  where
+    E1: CurveCycleEquipped + 'static,
+    S1: RelaxedR1CSSNARKTrait<E1> + 'static,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>> + 'static,
+  {
+    let digest = ScalarBytesLe::new(&vk.digest()?).as_bytes().to_vec();
+    self.keys.retain(|(d, _)| *d != digest);
+    self.keys.push((digest.clone(), vk));
+    Ok(digest)
+  }
+
+  /// Removes the key of `digest`, e.g. once its proofs are no longer
+  /// accepted, and returns whether it was held
+  pub fn remove(&mut self, digest: &[u8]) -> bool {
+    let len = self.keys.len();
+    self.keys.retain(|(d, _)| d != digest);
+    self.keys.len() != len
+  }
+
+  /// Whether the keyring holds the key of `digest`
+  pub fn contains(&self, digest: &[u8]) -> bool { self.keys.iter().any(|(d, _)| d == digest) }
+
+  /// The digests of the keys, in the order they were added
+  pub fn digests(&self) -> impl Iterator<Item = &[u8]> { self.keys.iter().map(|(d, _)| &d[..]) }
+
+  /// Verifies `envelope` with the key of its digest, and returns the outputs
+  /// of its proof. Fails with [`NovaError::UnknownVerifierKey`] if the
+  /// keyring does not hold that key.
+  pub fn verify(&self, envelope: &ProofEnvelope) -> Result<VerifiedOutputs, NovaError> {
+    let (_, vk) = self
+      .keys
+      .iter()
+      .find(|(d, _)| *d == envelope.vk_digest)
+      .ok_or(NovaError::UnknownVerifierKey)?;
+    vk.verify(envelope)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, Bn256EngineIPA},
+    spartan::{ppsnark, snark},
+    supernova::TrivialCircuit,
+    PublicParams, RecursiveSNARK,
+  };
+
+  type E1 = Bn256EngineIPA;
+  type E2 = Dual<E1>;
+  type EE<E> = ipa_pc::EvaluationEngine<E>;
+  type S<E> = snark::RelaxedR1CSSNARK<E, EE<E>>;
+  type SPP<E> = ppsnark::RelaxedR1CSSNARK<E, EE<E>>;
+
+  /// The verifier key and the envelope of a proof of one step, compressed
+  /// with `S1`, in `context`
+  fn prove<S1: RelaxedR1CSSNARKTrait<E1>>(
+    context: AppContext<'_>,
+  ) -> (Arc<VerifierKey<E1, S1, S<E2>>>, ProofEnvelope) {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(&circuit, &circuit, &*S1::ck_floor(), &*S::<E2>::ck_floor())
+      .unwrap();
+    let (z0_primary, z0_secondary) =
+      (vec![<E1 as Engine>::Scalar::ONE], vec![<E2 as Engine>::Scalar::ZERO]);
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+
+    let (pk, vk) = CompressedSNARK::<_, S1, S<E2>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove_with_context(&pp, &pk, &recursive_snark, context).unwrap();
+    let envelope = snark.envelope(&vk, 1, &z0_primary, &z0_secondary, context).unwrap();
+    (Arc::new(vk), envelope)
+  }
+
+  #[test]
+  fn test_verifier_keyring() {
+    let (vk, envelope) = prove::<S<E1>>(AppContext::default());
+    let (vk_pp, envelope_pp) = prove::<SPP<E1>>(AppContext(b"app"));
+
+    // the envelopes of both keys verify through the keyring
+    let mut keyring = VerifierKeyring::new();
+    assert_eq!(keyring.insert(vk.clone()).unwrap(), envelope.vk_digest);
+    assert_eq!(keyring.insert(vk_pp).unwrap(), envelope_pp.vk_digest);
+    assert_eq!(keyring.insert(vk).unwrap(), envelope.vk_digest);
+    assert_eq!(keyring.digests().count(), 2);
+    let outputs = keyring.verify(&envelope).unwrap();
+    assert_eq!(outputs.zn_primary, envelope.z0_primary);
+    keyring.verify(&envelope_pp).unwrap();
+
+    // the envelope of a proof must match it
+    let mut wrong = envelope_pp.clone();
+    wrong.context = b"other app".to_vec();
+    assert!(keyring.verify(&wrong).is_err());
+    let mut wrong = envelope.clone();
+    wrong.z0_primary = pack(&[<E1 as Engine>::Scalar::ZERO]);
+    assert!(keyring.verify(&wrong).is_err());
+    wrong.z0_primary.pop();
+    assert_eq!(keyring.verify(&wrong), Err(NovaError::InvalidInputLength));
+
+    // a proof of a removed key is not verified
+    assert!(keyring.remove(&envelope.vk_digest));
+    assert!(!keyring.contains(&envelope.vk_digest));
+    assert_eq!(keyring.verify(&envelope), Err(NovaError::UnknownVerifierKey));
+  }
+
+  #[test]
+  fn test_attested_envelope() {
+    let circuit = TrivialCircuit::default();
+    let pp =
+      PublicParams::<E1>::setup(&circuit, &circuit, &*S::<E1>::ck_floor(), &*S::<E2>::ck_floor())
+        .unwrap();
+    let attestation = b"quote of the enclave".to_vec();
+    let (z0_primary, z0_secondary) =
+      (vec![attestation_digest::<E1>(&attestation)], vec![<E2 as Engine>::Scalar::ZERO]);
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<E2>>::setup(&pp).unwrap();
+
+    let context = AppContext(b"app");
+    let attested = attested_context(context, &attestation);
+    let snark =
+      CompressedSNARK::prove_with_context(&pp, &pk, &recursive_snark, AppContext(&attested))
+        .unwrap();
+    let envelope =
+      snark.attested_envelope(&vk, 1, &z0_primary, &z0_secondary, context, &attestation).unwrap();
+    assert_eq!(
+      snark.attested_envelope(&vk, 1, &z0_primary, &z0_secondary, context, b"other"),
+      Err(NovaError::InvalidAttestation)
+    );
+
+    let mut keyring = VerifierKeyring::new();
+    keyring.insert(Arc::new(vk)).unwrap();
+    assert_eq!(keyring.verify(&envelope).unwrap().attestation, attestation);
+
+    // the attestation is bound to the proof, and to its public inputs
+    let mut wrong = envelope.clone();
+    wrong.attestation = b"quote of another enclave".to_vec();
+    assert_eq!(keyring.verify(&wrong), Err(NovaError::InvalidAttestation));
+    let mut wrong = envelope.clone();
+    wrong.attestation.clear();
+    assert!(keyring.verify(&wrong).is_err());
+    assert_eq!(attested_context(context, &[]), context.0);
+  }
+}
@@ -1,53 +1,96 @@
 #![allow(non_snake_case, clippy::type_complexity, clippy::too_many_arguments)]
+// a malformed proof or key must surface as an error, never as a panic
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+// the derive macros name this crate by its absolute path
+extern crate self as edge_prover;
 
 // private modules
-pub mod bellpepper;
+#[cfg(feature = "prove")] pub mod bellpepper;
 pub mod circuit;
 pub mod digest;
 pub mod nifs;
 
 // public modules
+pub mod arguments;
+#[cfg(feature = "prove")] pub mod circuits;
 pub mod constants;
+#[cfg(unix)] pub mod daemon;
+pub mod disclosure;
 pub mod errors;
 pub mod fast_serde;
+pub mod freshness;
 pub mod gadgets;
+pub mod inspect;
+pub mod integrations;
+pub mod keyring;
+#[cfg(feature = "prove")] pub mod lookup;
+#[cfg(feature = "prove")] pub mod manifest;
+#[cfg(feature = "prove")] pub mod memory;
+pub mod metrics;
+pub mod migrate;
+#[cfg(feature = "prove")] pub mod minroot;
+pub mod parallel;
+pub mod profiling;
 pub mod provider;
 pub mod r1cs;
+pub mod scalar_bytes;
+pub mod secret;
 pub mod spartan;
+#[cfg(feature = "prove")] pub mod test_circuits;
 pub mod traits;
 
-pub mod cyclefold;
+#[cfg(feature = "prove")] pub mod cyclefold;
 pub mod supernova;
 
 use std::sync::Arc;
 
-use bellpepper_core::{ConstraintSystem, SynthesisError};
-use circuit::{NovaAugmentedCircuit, NovaAugmentedCircuitInputs, NovaAugmentedCircuitParams};
-use constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_FE_WITHOUT_IO_FOR_CRHF, NUM_HASH_BITS};
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use circuit::NovaAugmentedCircuitParams;
+#[cfg(feature = "prove")]
+use circuit::{NovaAugmentedCircuit, NovaAugmentedCircuitInputs};
+#[cfg(feature = "prove")]
+use constants::{BN_LIMB_WIDTH, BN_N_LIMBS};
+use constants::{NUM_FE_WITHOUT_IO_FOR_CRHF, NUM_HASH_BITS};
 use errors::NovaError;
-use ff::Field;
+use ff::{Field, PrimeField};
 use gadgets::scalar_as_base;
+use inspect::{Check, VerifyTrace};
 use nifs::NIFS;
 use once_cell::sync::OnceCell;
+use provider::backend::BackendDecision;
+#[cfg(feature = "prove")]
+use r1cs::{commitment_key_size, keys::CommitmentKeyCache, CommitmentKeyHint};
 use r1cs::{
-  CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness,
+  keys::CommitmentKeys, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+  RelaxedR1CSWitness,
 };
-use serde::{Deserialize, Serialize};
-use supernova::StepCircuit;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spartan::deferred::{BatchedOpening, DeferredOpenings, OpeningClaim};
+#[cfg(feature = "prove")]
+use supernova::pipeline::{self, CommittedStep, Probe};
+#[cfg(feature = "prove")] use supernova::StepCircuit;
+#[cfg(feature = "prove")] use traits::SetupProgress;
 use traits::{
   commitment::{CommitmentEngineTrait, CommitmentTrait},
-  snark::RelaxedR1CSSNARKTrait,
-  AbsorbInROTrait, CurveCycleEquipped, Dual, Engine, ROConstants, ROConstantsCircuit, ROTrait,
+  evaluation::EvaluationEngineTrait,
+  snark::{serialized_size, AppContext, ProofSize, RelaxedR1CSSNARKTrait},
+  validated, AbsorbInROTrait, CurveCycleEquipped, Dual, Engine, ROConstants, ROConstantsCircuit,
+  ROTrait, TranscriptEngineTrait, Validate,
 };
 
+#[cfg(feature = "prove")]
+use crate::bellpepper::{
+  r1cs::{NovaShape, NovaWitness},
+  shape_cs::ShapeCS,
+  solver::SatisfyingAssignment,
+};
 use crate::{
-  bellpepper::{
-    r1cs::{NovaShape, NovaWitness},
-    shape_cs::ShapeCS,
-    solver::SatisfyingAssignment,
-  },
   digest::{DigestComputer, SimpleDigestible},
+  profiling::Phase,
   r1cs::R1CSResult,
+  secret::{wipe, Zeroize},
 };
 
 /// A type that holds parameters for the primary and secondary circuits of Nova
@@ -66,6 +109,8 @@ impl<E: Engine> R1CSWithArity<E> {
   pub fn new(r1cs_shape: R1CSShape<E>, F_arity: usize) -> Self { Self { F_arity, r1cs_shape } }
 
   /// Return the [`R1CSWithArity`]' digest.
+  // serializing the shape into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E::Scalar {
     let dc: DigestComputer<'_, <E as Engine>::Scalar, Self> = DigestComputer::new(self);
     dc.digest().expect("Failure in computing digest")
@@ -89,6 +134,7 @@ where E: CurveCycleEquipped {
   circuit_shape_secondary: R1CSWithArity<Dual<E>>,
   augmented_circuit_params_primary: NovaAugmentedCircuitParams,
   augmented_circuit_params_secondary: NovaAugmentedCircuitParams,
+  backend: Option<BackendDecision>,
   #[serde(skip, default = "OnceCell::new")]
   digest: OnceCell<E::Scalar>,
 }
@@ -98,6 +144,7 @@ impl<E1> SimpleDigestible for PublicParams<E1> where E1: CurveCycleEquipped {}
 impl<E1> PublicParams<E1>
 where E1: CurveCycleEquipped
 {
+  #[cfg(feature = "prove")]
   /// Set up builder to create `PublicParams` for a pair of circuits `C1` and
   /// `C2`.
   ///
@@ -153,6 +200,54 @@ where E1: CurveCycleEquipped
     ck_hint1: &CommitmentKeyHint<E1>,
     ck_hint2: &CommitmentKeyHint<Dual<E1>>,
   ) -> Result<Self, NovaError> {
+    Self::setup_with_progress(
+      c_primary,
+      c_secondary,
+      ck_hint1,
+      ck_hint2,
+      &|_: &str, _: usize, _: usize| true,
+    )
+  }
+
+  #[cfg(feature = "prove")]
+  /// Same as [`PublicParams::setup`], reporting its progress to `progress`,
+  /// which can also interrupt it. The stages are the synthesis of the
+  /// `"primary circuit"` and `"secondary circuit"`, and the computation of
+  /// the `"primary commitment key"` and `"secondary commitment key"`, whose
+  /// generators are computed in chunks, in parallel.
+  ///
+  /// This lets the setup run on a background thread, e.g. with
+  /// `std::thread::scope`, while the main thread shows its progress.
+  pub fn setup_with_progress<
+    C1: StepCircuit<E1::Scalar>,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar>,
+  >(
+    c_primary: &C1,
+    c_secondary: &C2,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<Dual<E1>>,
+    progress: &dyn SetupProgress,
+  ) -> Result<Self, NovaError> {
+    Self::setup_with_cache(c_primary, c_secondary, ck_hint1, ck_hint2, progress, &())
+  }
+
+  #[cfg(feature = "prove")]
+  /// Same as [`PublicParams::setup_with_progress`], loading the commitment
+  /// keys from `cache` if it holds them, and storing them in it otherwise
+  /// (see [`CommitmentKeys::setup`]). The circuits are synthesized before the
+  /// keys are computed.
+  pub fn setup_with_cache<
+    C1: StepCircuit<E1::Scalar>,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar>,
+  >(
+    c_primary: &C1,
+    c_secondary: &C2,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<Dual<E1>>,
+    progress: &dyn SetupProgress,
+    cache: &dyn CommitmentKeyCache,
+  ) -> Result<Self, NovaError> {
+    let _phase = Phase::enter("nova::PublicParams::setup");
     let augmented_circuit_params_primary =
       NovaAugmentedCircuitParams::new(BN_LIMB_WIDTH, BN_N_LIMBS, true);
     let augmented_circuit_params_secondary =
@@ -170,29 +265,45 @@ where E1: CurveCycleEquipped
       ROConstantsCircuit::<Dual<E1>>::default();
     let ro_consts_circuit_secondary: ROConstantsCircuit<E1> = ROConstantsCircuit::<E1>::default();
 
-    // Initialize ck for the primary
+    // Synthesize the primary circuit
     let circuit_primary: NovaAugmentedCircuit<'_, Dual<E1>, C1> = NovaAugmentedCircuit::new(
       &augmented_circuit_params_primary,
       None,
       c_primary,
       ro_consts_circuit_primary.clone(),
     );
+    if !progress.report("primary circuit", 0, 1) {
+      return Err(NovaError::Interrupted);
+    }
     let mut cs: ShapeCS<E1> = ShapeCS::new();
     let _ = circuit_primary.synthesize(&mut cs);
-    let (r1cs_shape_primary, ck_primary) = cs.r1cs_shape_and_key(ck_hint1);
-    let ck_primary = Arc::new(ck_primary);
+    let r1cs_shape_primary = cs.r1cs_shape();
+    if !progress.report("primary circuit", 1, 1) {
+      return Err(NovaError::Interrupted);
+    }
 
-    // Initialize ck for the secondary
+    // Synthesize the secondary circuit
     let circuit_secondary: NovaAugmentedCircuit<'_, E1, C2> = NovaAugmentedCircuit::new(
       &augmented_circuit_params_secondary,
       None,
       c_secondary,
       ro_consts_circuit_secondary.clone(),
     );
+    if !progress.report("secondary circuit", 0, 1) {
+      return Err(NovaError::Interrupted);
+    }
     let mut cs: ShapeCS<Dual<E1>> = ShapeCS::new();
     let _ = circuit_secondary.synthesize(&mut cs);
-    let (r1cs_shape_secondary, ck_secondary) = cs.r1cs_shape_and_key(ck_hint2);
-    let ck_secondary = Arc::new(ck_secondary);
+    let r1cs_shape_secondary = cs.r1cs_shape();
+    if !progress.report("secondary circuit", 1, 1) {
+      return Err(NovaError::Interrupted);
+    }
+    let CommitmentKeys { primary: ck_primary, secondary: ck_secondary } = CommitmentKeys::setup(
+      commitment_key_size(&r1cs_shape_primary, ck_hint1),
+      commitment_key_size(&r1cs_shape_secondary, ck_hint2),
+      progress,
+      cache,
+    )?;
 
     if r1cs_shape_primary.num_io != 2 || r1cs_shape_secondary.num_io != 2 {
       return Err(NovaError::InvalidStepCircuitIO);
@@ -214,11 +325,34 @@ where E1: CurveCycleEquipped
       circuit_shape_secondary,
       augmented_circuit_params_primary,
       augmented_circuit_params_secondary,
+      backend: None,
       digest: OnceCell::new(),
     })
   }
 
+  /// The commitment keys of the primary and secondary circuits
+  pub fn commitment_keys(&self) -> CommitmentKeys<E1> {
+    CommitmentKeys { primary: self.ck_primary.clone(), secondary: self.ck_secondary.clone() }
+  }
+
+  /// Checks the commitment keys against the digest binding them together
+  /// (see [`CommitmentKeys::digest`]), e.g. a published one
+  pub fn check_commitment_keys(&self, digest: &E1::Scalar) -> Result<(), NovaError> {
+    self.commitment_keys().check(digest)
+  }
+
+  /// The parameters bound to the commitment backend `decision`, of a new
+  /// digest, see [`BackendDecision::bind`]
+  pub(crate) fn with_backend(self, decision: BackendDecision) -> Self {
+    Self { backend: Some(decision), digest: OnceCell::new(), ..self }
+  }
+
+  /// The commitment backend the parameters are bound to, if any
+  pub fn backend(&self) -> Option<&BackendDecision> { self.backend.as_ref() }
+
   /// Retrieve the digest of the public parameters.
+  // serializing the parameters into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E1::Scalar {
     self
       .digest
@@ -260,6 +394,17 @@ pub struct ResourceBuffer<E: Engine> {
   T: Vec<E::Scalar>,
 }
 
+impl<E: Engine> Zeroize for ResourceBuffer<E> {
+  fn zeroize(&mut self) {
+    if let Some(l_w) = self.l_w.as_mut() {
+      l_w.zeroize();
+    }
+    self.ABC_Z_1.zeroize();
+    self.ABC_Z_2.zeroize();
+    wipe(&mut self.T);
+  }
+}
+
 /// A SNARK that proves the correct execution of an incremental computation
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -284,9 +429,51 @@ where E1: CurveCycleEquipped {
   zi_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
 }
 
+/// Wipes the witnesses, the buffers and the inputs and outputs of the
+/// computation, e.g. once it is compressed; the `RecursiveSNARK` no longer
+/// proves steps afterwards
+impl<E1: CurveCycleEquipped> Zeroize for RecursiveSNARK<E1> {
+  fn zeroize(&mut self) {
+    wipe(&mut self.z0_primary);
+    wipe(&mut self.z0_secondary);
+    self.r_W_primary.zeroize();
+    self.r_W_secondary.zeroize();
+    self.l_w_secondary.zeroize();
+    self.buffer_primary.zeroize();
+    self.buffer_secondary.zeroize();
+    wipe(&mut self.zi_primary);
+    wipe(&mut self.zi_secondary);
+  }
+}
+
+#[cfg(feature = "prove")]
+/// Synthesizes the primary augmented circuit of a step on `inputs`, with the
+/// step circuit `c_primary`, and returns the outputs of the step and the
+/// assignment
+#[allow(clippy::type_complexity)]
+fn synthesize_primary<E1: CurveCycleEquipped, C1: StepCircuit<E1::Scalar>>(
+  pp: &PublicParams<E1>,
+  inputs: NovaAugmentedCircuitInputs<Dual<E1>>,
+  c_primary: &C1,
+) -> Result<(Vec<AllocatedNum<E1::Scalar>>, SatisfyingAssignment<E1>), NovaError> {
+  let mut cs_primary = SatisfyingAssignment::<E1>::with_capacity(
+    pp.circuit_shape_primary.r1cs_shape.num_io + 1,
+    pp.circuit_shape_primary.r1cs_shape.num_vars,
+  );
+  let circuit_primary: NovaAugmentedCircuit<'_, Dual<E1>, C1> = NovaAugmentedCircuit::new(
+    &pp.augmented_circuit_params_primary,
+    Some(inputs),
+    c_primary,
+    pp.ro_consts_circuit_primary.clone(),
+  );
+  let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
+  Ok((zi_primary, cs_primary))
+}
+
 impl<E1> RecursiveSNARK<E1>
 where E1: CurveCycleEquipped
 {
+  #[cfg(feature = "prove")]
   /// Create new instance of recursive SNARK
   pub fn new<C1: StepCircuit<E1::Scalar>, C2: StepCircuit<<Dual<E1> as Engine>::Scalar>>(
     pp: &PublicParams<E1>,
@@ -416,6 +603,7 @@ where E1: CurveCycleEquipped
   /// Outputs of the primary circuits
   pub fn zi_primary(&self) -> &Vec<E1::Scalar> { &self.zi_primary }
 
+  #[cfg(feature = "prove")]
   /// Create a new `RecursiveSNARK` (or updates the provided `RecursiveSNARK`)
   /// by executing a step of the incremental computation
   #[tracing::instrument(skip_all, name = "nova::RecursiveSNARK::prove_step")]
@@ -425,98 +613,212 @@ where E1: CurveCycleEquipped
     c_primary: &C1,
     c_secondary: &C2,
   ) -> Result<(), NovaError> {
+    let _phase = Phase::enter("nova::RecursiveSNARK::prove_step");
     // first step was already done in the constructor
     if self.i == 0 {
       self.i = 1;
       return Ok(());
     }
+    self.fold_step(pp, c_primary, c_secondary, None)
+  }
+
+  #[cfg(feature = "prove")]
+  /// Executes the steps of `steps` in turn, as many calls to
+  /// [`RecursiveSNARK::prove_step`] would, but pipelined across steps (see
+  /// [`parallel::pipeline`]): the primary step circuit of a step runs while
+  /// the witness of the previous step is committed to, and that of the step
+  /// before is folded
+  #[tracing::instrument(skip_all, name = "nova::RecursiveSNARK::prove_steps")]
+  pub fn prove_steps<'a, C1, C2, I>(
+    &mut self,
+    pp: &PublicParams<E1>,
+    steps: I,
+  ) -> Result<(), NovaError>
+  where
+    C1: StepCircuit<E1::Scalar> + 'a,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar> + 'a,
+    I: IntoIterator<Item = (&'a C1, &'a C2)>,
+    I::IntoIter: Send,
+  {
+    let _phase = Phase::enter("nova::RecursiveSNARK::prove_steps");
+    let mut steps = steps.into_iter();
+    if self.i == 0 {
+      match steps.next() {
+        Some((c_primary, c_secondary)) => self.prove_step(pp, c_primary, c_secondary)?,
+        None => return Ok(()),
+      }
+    }
 
+    // where the witness of the step circuit starts in that of the augmented circuit
+    let probe = Probe::new(pp.F_arity_primary, 0);
+    let circuit_probe: NovaAugmentedCircuit<'_, Dual<E1>, Probe> = NovaAugmentedCircuit::new(
+      &pp.augmented_circuit_params_primary,
+      None,
+      &probe,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let _ = circuit_probe.synthesize(&mut ShapeCS::<E1>::new());
+    let offset = probe.offset()?;
+
+    let mut zi_primary = self.zi_primary.clone();
+    parallel::pipeline(
+      steps,
+      |(c_primary, c_secondary)| -> Result<_, NovaError> {
+        let (witness, _, zi_next) = pipeline::record(c_primary, None, &zi_primary)?;
+        zi_primary = zi_next;
+        Ok((c_primary, c_secondary, witness))
+      },
+      |(c_primary, c_secondary, witness)| -> Result<_, NovaError> {
+        Ok((c_primary, c_secondary, CommittedStep::new(&pp.ck_primary, witness, offset)))
+      },
+      |(c_primary, c_secondary, mut committed)| {
+        let folded = self.fold_step(pp, c_primary, c_secondary, Some(&committed));
+        committed.zeroize();
+        folded
+      },
+    )
+  }
+
+  #[cfg(feature = "prove")]
+  /// Folds the next step, its primary step circuit replaying the witness
+  /// of `committed` if any
+  fn fold_step<C1: StepCircuit<E1::Scalar>, C2: StepCircuit<<Dual<E1> as Engine>::Scalar>>(
+    &mut self,
+    pp: &PublicParams<E1>,
+    c_primary: &C1,
+    c_secondary: &C2,
+    committed: Option<&CommittedStep<E1>>,
+  ) -> Result<(), NovaError> {
     // save the inputs before proceeding to the `i+1`th step
     let r_U_primary_i = self.r_U_primary.clone();
     let r_U_secondary_i = self.r_U_secondary.clone();
     let l_u_secondary_i = self.l_u_secondary.clone();
 
+    // The step runs in two stages, each of which folds the witness of the
+    // previous stage while it synthesizes and commits: the folded witnesses
+    // are only read by the next fold of their circuit, whereas the circuits
+    // only read the folded instances. Steps themselves overlap in
+    // `prove_steps` only, which runs and commits to the primary step circuits
+    // of the next steps ahead of this.
+
     // fold the secondary circuit's instance
-    let (nifs_secondary, _) = NIFS::prove_mut(
+    let (nifs_secondary, fold_secondary) = NIFS::prove_instance_mut(
       &*pp.ck_secondary,
       &pp.ro_consts_secondary,
       &scalar_as_base::<E1>(pp.digest()),
       &pp.circuit_shape_secondary.r1cs_shape,
       &mut self.r_U_secondary,
-      &mut self.r_W_secondary,
+      &self.r_W_secondary,
       &self.l_u_secondary,
       &self.l_w_secondary,
       &mut self.buffer_secondary.T,
       &mut self.buffer_secondary.ABC_Z_1,
       &mut self.buffer_secondary.ABC_Z_2,
     )?;
+    let comm_T_secondary = Commitment::<Dual<E1>>::decompress(&nifs_secondary.comm_T)?;
 
-    let mut cs_primary = SatisfyingAssignment::<E1>::with_capacity(
-      pp.circuit_shape_primary.r1cs_shape.num_io + 1,
-      pp.circuit_shape_primary.r1cs_shape.num_vars,
-    );
-    let inputs_primary: NovaAugmentedCircuitInputs<Dual<E1>> = NovaAugmentedCircuitInputs::new(
-      scalar_as_base::<E1>(pp.digest()),
-      E1::Scalar::from(self.i as u64),
-      self.z0_primary.to_vec(),
-      Some(self.zi_primary.clone()),
-      Some(r_U_secondary_i),
-      Some(l_u_secondary_i),
-      Some(Commitment::<Dual<E1>>::decompress(&nifs_secondary.comm_T)?),
-    );
-
-    let circuit_primary: NovaAugmentedCircuit<'_, Dual<E1>, C1> = NovaAugmentedCircuit::new(
-      &pp.augmented_circuit_params_primary,
-      Some(inputs_primary),
-      c_primary,
-      pp.ro_consts_circuit_primary.clone(),
+    // synthesize the primary circuit, while folding the secondary witness
+    let (folded_secondary, primary) = parallel::join(
+      || {
+        fold_secondary.fold_witness(
+          &pp.ck_secondary,
+          &pp.circuit_shape_secondary.r1cs_shape,
+          &self.r_U_secondary,
+          &mut self.r_W_secondary,
+          &self.l_u_secondary,
+          &self.l_w_secondary,
+          &self.buffer_secondary.T,
+        )
+      },
+      || -> Result<_, NovaError> {
+        let inputs_primary: NovaAugmentedCircuitInputs<Dual<E1>> = NovaAugmentedCircuitInputs::new(
+          scalar_as_base::<E1>(pp.digest()),
+          E1::Scalar::from(self.i as u64),
+          self.z0_primary.to_vec(),
+          Some(self.zi_primary.clone()),
+          Some(r_U_secondary_i),
+          Some(l_u_secondary_i),
+          Some(comm_T_secondary),
+        );
+
+        let shape = &pp.circuit_shape_primary.r1cs_shape;
+        let (zi_primary, (l_u_primary, l_w_primary)) = match committed {
+          None => {
+            let (zi_primary, cs_primary) = synthesize_primary(pp, inputs_primary, c_primary)?;
+            (zi_primary, cs_primary.r1cs_instance_and_witness(shape, &pp.ck_primary)?)
+          },
+          Some(committed) => {
+            let (zi_primary, cs_primary) =
+              synthesize_primary(pp, inputs_primary, &committed.replay(c_primary))?;
+            (zi_primary, committed.r1cs_instance_and_witness(cs_primary, shape, &pp.ck_primary)?)
+          },
+        };
+        Ok((zi_primary, l_u_primary, l_w_primary))
+      },
     );
-
-    let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
-
-    let (l_u_primary, l_w_primary) =
-      cs_primary.r1cs_instance_and_witness(&pp.circuit_shape_primary.r1cs_shape, &pp.ck_primary)?;
+    folded_secondary?;
+    let (zi_primary, l_u_primary, l_w_primary) = primary?;
 
     // fold the primary circuit's instance
-    let (nifs_primary, _) = NIFS::prove_mut(
+    let (nifs_primary, fold_primary) = NIFS::prove_instance_mut(
       &*pp.ck_primary,
       &pp.ro_consts_primary,
       &pp.digest(),
       &pp.circuit_shape_primary.r1cs_shape,
       &mut self.r_U_primary,
-      &mut self.r_W_primary,
+      &self.r_W_primary,
       &l_u_primary,
       &l_w_primary,
       &mut self.buffer_primary.T,
       &mut self.buffer_primary.ABC_Z_1,
       &mut self.buffer_primary.ABC_Z_2,
     )?;
+    let comm_T_primary = Commitment::<E1>::decompress(&nifs_primary.comm_T)?;
 
-    let mut cs_secondary = SatisfyingAssignment::<Dual<E1>>::with_capacity(
-      pp.circuit_shape_secondary.r1cs_shape.num_io + 1,
-      pp.circuit_shape_secondary.r1cs_shape.num_vars,
-    );
-    let inputs_secondary: NovaAugmentedCircuitInputs<E1> = NovaAugmentedCircuitInputs::new(
-      pp.digest(),
-      <Dual<E1> as Engine>::Scalar::from(self.i as u64),
-      self.z0_secondary.to_vec(),
-      Some(self.zi_secondary.clone()),
-      Some(r_U_primary_i),
-      Some(l_u_primary),
-      Some(Commitment::<E1>::decompress(&nifs_primary.comm_T)?),
-    );
-
-    let circuit_secondary: NovaAugmentedCircuit<'_, E1, C2> = NovaAugmentedCircuit::new(
-      &pp.augmented_circuit_params_secondary,
-      Some(inputs_secondary),
-      c_secondary,
-      pp.ro_consts_circuit_secondary.clone(),
+    // synthesize the secondary circuit, while folding the primary witness
+    let (folded_primary, secondary) = parallel::join(
+      || {
+        fold_primary.fold_witness(
+          &pp.ck_primary,
+          &pp.circuit_shape_primary.r1cs_shape,
+          &self.r_U_primary,
+          &mut self.r_W_primary,
+          &l_u_primary,
+          &l_w_primary,
+          &self.buffer_primary.T,
+        )
+      },
+      || -> Result<_, NovaError> {
+        let mut cs_secondary = SatisfyingAssignment::<Dual<E1>>::with_capacity(
+          pp.circuit_shape_secondary.r1cs_shape.num_io + 1,
+          pp.circuit_shape_secondary.r1cs_shape.num_vars,
+        );
+        let inputs_secondary: NovaAugmentedCircuitInputs<E1> = NovaAugmentedCircuitInputs::new(
+          pp.digest(),
+          <Dual<E1> as Engine>::Scalar::from(self.i as u64),
+          self.z0_secondary.to_vec(),
+          Some(self.zi_secondary.clone()),
+          Some(r_U_primary_i),
+          Some(l_u_primary.clone()),
+          Some(comm_T_primary),
+        );
+
+        let circuit_secondary: NovaAugmentedCircuit<'_, E1, C2> = NovaAugmentedCircuit::new(
+          &pp.augmented_circuit_params_secondary,
+          Some(inputs_secondary),
+          c_secondary,
+          pp.ro_consts_circuit_secondary.clone(),
+        );
+        let zi_secondary = circuit_secondary.synthesize(&mut cs_secondary)?;
+
+        let (l_u_secondary, l_w_secondary) = cs_secondary
+          .r1cs_instance_and_witness(&pp.circuit_shape_secondary.r1cs_shape, &pp.ck_secondary)
+          .map_err(|_e| NovaError::UnSat)?;
+        Ok((zi_secondary, l_u_secondary, l_w_secondary))
+      },
     );
-    let zi_secondary = circuit_secondary.synthesize(&mut cs_secondary)?;
-
-    let (l_u_secondary, l_w_secondary) = cs_secondary
-      .r1cs_instance_and_witness(&pp.circuit_shape_secondary.r1cs_shape, &pp.ck_secondary)
-      .map_err(|_e| NovaError::UnSat)?;
+    folded_primary?;
+    let (zi_secondary, l_u_secondary, l_w_secondary) = secondary?;
 
     // update the running instances and witnesses
     self.zi_primary = zi_primary
@@ -608,7 +910,7 @@ where E1: CurveCycleEquipped
     }
 
     // check the satisfiability of the provided instances
-    let (res_r_primary, (res_r_secondary, res_l_secondary)) = rayon::join(
+    let (res_r_primary, (res_r_secondary, res_l_secondary)) = parallel::join(
       || {
         pp.circuit_shape_primary.r1cs_shape.is_sat_relaxed(
           &pp.ck_primary,
@@ -617,7 +919,7 @@ where E1: CurveCycleEquipped
         )
       },
       || {
-        rayon::join(
+        parallel::join(
           || {
             pp.circuit_shape_secondary.r1cs_shape.is_sat_relaxed(
               &pp.ck_secondary,
@@ -664,6 +966,26 @@ where
   pub pk_secondary: S2::ProverKey,
 }
 
+/// The part of the verifier key for `CompressedSNARK` that does not depend on
+/// the compressing SNARKs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierKeyHeader<E1: CurveCycleEquipped> {
+  F_arity_primary:     usize,
+  F_arity_secondary:   usize,
+  ro_consts_primary:   ROConstants<E1>,
+  ro_consts_secondary: ROConstants<Dual<E1>>,
+  pp_digest:           E1::Scalar,
+}
+
+impl<E1: CurveCycleEquipped> VerifierKeyHeader<E1> {
+  /// The number of inputs and outputs of the primary circuit
+  pub fn arity_primary(&self) -> usize { self.F_arity_primary }
+
+  /// The number of inputs and outputs of the secondary circuit
+  pub fn arity_secondary(&self) -> usize { self.F_arity_secondary }
+}
+
 /// A type that holds the verifier key for `CompressedSNARK`
 #[derive(Debug, Clone, Serialize)]
 #[serde(bound = "")]
@@ -672,18 +994,87 @@ where
   E1: CurveCycleEquipped,
   S1: RelaxedR1CSSNARKTrait<E1>,
   S2: RelaxedR1CSSNARKTrait<Dual<E1>>, {
-  F_arity_primary:     usize,
-  F_arity_secondary:   usize,
-  ro_consts_primary:   ROConstants<E1>,
-  ro_consts_secondary: ROConstants<Dual<E1>>,
-  pp_digest:           E1::Scalar,
-  vk_primary:          S1::VerifierKey,
-  vk_secondary:        S2::VerifierKey,
+  header:       VerifierKeyHeader<E1>,
+  vk_primary:   S1::VerifierKey,
+  vk_secondary: S2::VerifierKey,
 }
 
-/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// The digests of the parts of a `VerifierKey`: its header and the verifier
+/// keys of the two compressing SNARKs.
+///
+/// They open the digest of the key (see [`VerifierKey::digest`]), so that a
+/// light verifier embedding only that digest can check each part of the key
+/// as it fetches it, with [`CompressedSNARK::verify_with_digest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "")]
+pub struct VerifierKeyOpening<E1: CurveCycleEquipped> {
+  header:    E1::Scalar,
+  primary:   E1::Scalar,
+  secondary: E1::Scalar,
+}
+
+impl<E1: CurveCycleEquipped> SimpleDigestible for VerifierKeyOpening<E1> {}
+
+impl<E1: CurveCycleEquipped> VerifierKeyOpening<E1> {
+  /// The digest of the verifier key this opens
+  pub fn digest(&self) -> Result<E1::Scalar, NovaError> {
+    DigestComputer::<E1::Scalar, _>::new(self).digest().map_err(|_| NovaError::DigestError)
+  }
+}
+
+/// Wraps a part of a `VerifierKey` to compute its digest
+#[derive(Serialize)]
+struct VerifierKeyPart<'a, T: Serialize>(&'a T);
+
+impl<T: Serialize> SimpleDigestible for VerifierKeyPart<'_, T> {}
+
+/// The digest of a part of a `VerifierKey`
+fn part_digest<F: PrimeField, T: Serialize>(part: &T) -> Result<F, NovaError> {
+  DigestComputer::<F, _>::new(&VerifierKeyPart(part)).digest().map_err(|_| NovaError::DigestError)
+}
+
+/// Checks that `part` has the digest `expected`
+fn check_part_digest<F: PrimeField, T: Serialize>(part: &T, expected: &F) -> Result<(), NovaError> {
+  if part_digest::<F, T>(part)? == *expected {
+    Ok(())
+  } else {
+    Err(NovaError::ProofVerifyError)
+  }
+}
+
+impl<E1, S1, S2> VerifierKey<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  /// The header of this key
+  pub fn header(&self) -> &VerifierKeyHeader<E1> { &self.header }
+
+  /// The verifier key of the primary compressing SNARK
+  pub fn vk_primary(&self) -> &S1::VerifierKey { &self.vk_primary }
+
+  /// The verifier key of the secondary compressing SNARK
+  pub fn vk_secondary(&self) -> &S2::VerifierKey { &self.vk_secondary }
+
+  /// The digests of the parts of this key
+  pub fn opening(&self) -> Result<VerifierKeyOpening<E1>, NovaError> {
+    Ok(VerifierKeyOpening {
+      header:    part_digest(&self.header)?,
+      primary:   part_digest(&self.vk_primary)?,
+      secondary: part_digest(&self.vk_secondary)?,
+    })
+  }
+
+  /// The digest of this key, which is all a light verifier needs to embed
+  pub fn digest(&self) -> Result<E1::Scalar, NovaError> { self.opening()?.digest() }
+}
+
+/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`. Its
+/// instances, and the arguments of its SNARKs, are checked to be of the
+/// lengths the verifier assumes when it is deserialized.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(remote = "Self", bound = "")]
 pub struct CompressedSNARK<E1, S1, S2>
 where
   E1: CurveCycleEquipped,
@@ -701,6 +1092,46 @@ where
   zn_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
 }
 
+impl<E1, S1, S2> Validate for CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  fn validate(&self) -> Result<(), NovaError> {
+    // the (relaxed) R1CS instances have two public outputs
+    if self.l_u_secondary.X.len() != 2
+      || self.r_U_primary.X.len() != 2
+      || self.r_U_secondary.X.len() != 2
+    {
+      return Err(NovaError::ProofVerifyError);
+    }
+    Ok(())
+  }
+}
+
+impl<E1, S1, S2> Serialize for CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de, E1, S1, S2> Deserialize<'de> for CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    validated::<_, D>(Self::deserialize(deserializer)?)
+  }
+}
+
 impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
 where
   E1: CurveCycleEquipped,
@@ -720,11 +1151,13 @@ where
     let pk = ProverKey { pk_primary, pk_secondary };
 
     let vk = VerifierKey {
-      F_arity_primary: pp.F_arity_primary,
-      F_arity_secondary: pp.F_arity_secondary,
-      ro_consts_primary: pp.ro_consts_primary.clone(),
-      ro_consts_secondary: pp.ro_consts_secondary.clone(),
-      pp_digest: pp.digest(),
+      header: VerifierKeyHeader {
+        F_arity_primary:     pp.F_arity_primary,
+        F_arity_secondary:   pp.F_arity_secondary,
+        ro_consts_primary:   pp.ro_consts_primary.clone(),
+        ro_consts_secondary: pp.ro_consts_secondary.clone(),
+        pp_digest:           pp.digest(),
+      },
       vk_primary,
       vk_secondary,
     };
@@ -732,12 +1165,31 @@ where
     Ok((pk, vk))
   }
 
-  /// Create a new `CompressedSNARK`
+  /// Create a new `CompressedSNARK`.
+  ///
+  /// Proving is deterministic: the prover draws no randomness, as the SNARKs
+  /// are not zero-knowledge, and its parallel reductions are over exact field
+  /// and group arithmetic, so the same inputs yield byte-identical proofs,
+  /// whatever the number of threads.
   pub fn prove(
     pp: &PublicParams<E1>,
     pk: &ProverKey<E1, S1, S2>,
     recursive_snark: &RecursiveSNARK<E1>,
   ) -> Result<Self, NovaError> {
+    Self::prove_with_context(pp, pk, recursive_snark, AppContext::default())
+  }
+
+  /// Same as [`CompressedSNARK::prove`], binding the proof to the application
+  /// context `context`: it only verifies with
+  /// [`CompressedSNARK::verify_with_context`] in the same context, so that it
+  /// cannot be reused by another application sharing the parameters.
+  pub fn prove_with_context(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1>,
+    context: AppContext<'_>,
+  ) -> Result<Self, NovaError> {
+    let _phase = Phase::enter("nova::CompressedSNARK::prove");
     // fold the secondary circuit's instance with its running instance
     let (nifs_secondary, (f_U_secondary, f_W_secondary), _) = NIFS::prove(
       &*pp.ck_secondary,
@@ -751,28 +1203,30 @@ where
     )?;
 
     // create SNARKs proving the knowledge of f_W_primary and f_W_secondary
-    let (r_W_snark_primary, f_W_snark_secondary) = rayon::join(
+    let (r_W_snark_primary, f_W_snark_secondary) = parallel::join(
       || {
-        S1::prove(
+        S1::prove_with_context(
           &pp.ck_primary,
           &pk.pk_primary,
           &pp.circuit_shape_primary.r1cs_shape,
           &recursive_snark.r_U_primary,
           &recursive_snark.r_W_primary,
+          context,
         )
       },
       || {
-        S2::prove(
+        S2::prove_with_context(
           &pp.ck_secondary,
           &pk.pk_secondary,
           &pp.circuit_shape_secondary.r1cs_shape,
           &f_U_secondary,
           &f_W_secondary,
+          context,
         )
       },
     );
 
-    Ok(Self {
+    let snark = Self {
       r_U_primary: recursive_snark.r_U_primary.clone(),
       r_W_snark_primary: r_W_snark_primary?,
 
@@ -783,7 +1237,39 @@ where
 
       zn_primary: recursive_snark.zi_primary.clone(),
       zn_secondary: recursive_snark.zi_secondary.clone(),
-    })
+    };
+    metrics::record_proof("nova", &snark);
+    Ok(snark)
+  }
+
+  /// Same as [`CompressedSNARK::prove_with_context`], and discharges the
+  /// evaluation claims deferred along the steps of `recursive_snark` (see
+  /// [`spartan::deferred`]): they are proved at once, with one evaluation
+  /// argument of `EE` under `pk_deferred`, in a transcript bound to the
+  /// returned `CompressedSNARK`
+  pub fn prove_with_deferred<EE: EvaluationEngineTrait<E1>>(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    pk_deferred: &EE::ProverKey,
+    recursive_snark: &RecursiveSNARK<E1>,
+    deferred: DeferredOpenings<E1>,
+    context: AppContext<'_>,
+  ) -> Result<(Self, BatchedOpening<E1, EE>), NovaError> {
+    let snark = Self::prove_with_context(pp, pk, recursive_snark, context)?;
+    let mut transcript = snark.deferred_transcript(&pp.digest(), context);
+    let opening = deferred.prove::<EE>(&pp.ck_primary, pk_deferred, &mut transcript)?;
+    Ok((snark, opening))
+  }
+
+  /// The sizes in bytes of the components of the `CompressedSNARK` serialized
+  /// with `bincode`, e.g. to find the sub-protocol to optimize, or to show the
+  /// size of a proof before uploading it. The instances count the folded
+  /// instances, the folding proof of the last step and the outputs.
+  pub fn size_breakdown(&self) -> ProofSize {
+    let snarks =
+      self.r_W_snark_primary.size_breakdown() + self.f_W_snark_secondary.size_breakdown();
+    let instance = serialized_size(self).saturating_sub(snarks.total());
+    ProofSize { instance, ..snarks }
   }
 
   #[allow(clippy::type_complexity)]
@@ -795,27 +1281,189 @@ where
     z0_primary: &[E1::Scalar],
     z0_secondary: &[<Dual<E1> as Engine>::Scalar],
   ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError> {
-    // the number of steps cannot be zero
-    if num_steps == 0 {
-      return Err(NovaError::ProofVerifyError);
+    self.verify_with_context(vk, num_steps, z0_primary, z0_secondary, AppContext::default())
+  }
+
+  /// Same as [`CompressedSNARK::verify`], for a proof made in the application
+  /// context `context` with [`CompressedSNARK::prove_with_context`]
+  #[allow(clippy::type_complexity)]
+  pub fn verify_with_context(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError> {
+    let result = (|| {
+      let trace = &mut VerifyTrace::new();
+      let f_U_secondary =
+        self.verify_instances(&vk.header, num_steps, z0_primary, z0_secondary, trace)?;
+
+      // check the satisfiability of the folded instances using
+      // SNARKs proving the knowledge of their satisfying witnesses
+      let (res_primary, res_secondary) = parallel::join(
+        || self.r_W_snark_primary.verify_with_context(&vk.vk_primary, &self.r_U_primary, context),
+        || self.f_W_snark_secondary.verify_with_context(&vk.vk_secondary, &f_U_secondary, context),
+      );
+
+      res_primary?;
+      res_secondary?;
+
+      Ok((self.zn_primary.clone(), self.zn_secondary.clone()))
+    })();
+    metrics::record_verification("nova", &result);
+    #[cfg(feature = "log-failures")]
+    if let Err(e) = &result {
+      // the failing check is found by verifying again, in audit mode
+      let (_, trace) = self.verify_with_trace(vk, num_steps, z0_primary, z0_secondary, context);
+      let report = inspect::FailureReport::new("nova", self, e).with_trace(&trace);
+      match vk.digest() {
+        Ok(digest) => report.with_key_digest("vk", &digest),
+        Err(_) => report,
+      }
+      .log();
     }
+    result
+  }
 
-    // check if the (relaxed) R1CS instances have two public outputs
-    if self.l_u_secondary.X.len() != 2
-      || self.r_U_primary.X.len() != 2
-      || self.r_U_secondary.X.len() != 2
-    {
+  /// Same as [`CompressedSNARK::verify_with_context`], and verifies
+  /// `opening`, the proof of the deferred `claims` made with
+  /// [`CompressedSNARK::prove_with_deferred`], under `vk_deferred`
+  #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+  pub fn verify_with_deferred<EE: EvaluationEngineTrait<E1>>(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    vk_deferred: &EE::VerifierKey,
+    opening: &BatchedOpening<E1, EE>,
+    claims: &[OpeningClaim<E1>],
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError> {
+    let zn = self.verify_with_context(vk, num_steps, z0_primary, z0_secondary, context)?;
+    let mut transcript = self.deferred_transcript(&vk.header.pp_digest, context);
+    opening.verify(vk_deferred, &mut transcript, claims)?;
+    Ok(zn)
+  }
+
+  /// The transcript of the proof of the deferred claims, which absorbs the
+  /// parameters, the context and the folded instance of the primary circuit
+  fn deferred_transcript(&self, pp_digest: &E1::Scalar, context: AppContext<'_>) -> E1::TE {
+    let mut transcript = E1::TE::new(b"deferred");
+    transcript.absorb(b"pp", pp_digest);
+    context.absorb_in::<E1>(&mut transcript);
+    transcript.absorb(b"U", &self.r_U_primary);
+    transcript
+  }
+
+  /// Same as [`CompressedSNARK::verify_with_context`], in audit mode: the
+  /// checks are made in turn and recorded in the returned trace, which names
+  /// the check that failed if the proof does not verify, e.g. the round of a
+  /// sum-check or an opening, and serializes for a bug report.
+  #[allow(clippy::type_complexity)]
+  pub fn verify_with_trace(
+    &self,
+    vk: &VerifierKey<E1, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> (Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError>, VerifyTrace) {
+    let mut trace = VerifyTrace::new();
+    let result = (|| {
+      let f_U_secondary =
+        self.verify_instances(&vk.header, num_steps, z0_primary, z0_secondary, &mut trace)?;
+      let (vk_primary, vk_secondary) = (&vk.vk_primary, &vk.vk_secondary);
+      self.r_W_snark_primary.verify_with_trace(
+        vk_primary,
+        &self.r_U_primary,
+        context,
+        &mut trace,
+      )?;
+      self.f_W_snark_secondary.verify_with_trace(
+        vk_secondary,
+        &f_U_secondary,
+        context,
+        &mut trace,
+      )?;
+      Ok((self.zn_primary.clone(), self.zn_secondary.clone()))
+    })();
+    trace.finish(result)
+  }
+
+  /// Verify the correctness of the `CompressedSNARK` against a verifier key
+  /// given by its digest `vk_digest` (see [`VerifierKey::digest`]).
+  ///
+  /// The parts of the key are checked against `opening`, itself checked
+  /// against `vk_digest`. The verifier keys of the compressing SNARKs, which
+  /// are the bulk of the key, are only fetched, with `fetch_vk_primary` and
+  /// `fetch_vk_secondary`, once the checks that precede their use pass. The
+  /// proof is verified in the default application context.
+  #[allow(clippy::type_complexity)]
+  pub fn verify_with_digest(
+    &self,
+    vk_digest: &E1::Scalar,
+    opening: &VerifierKeyOpening<E1>,
+    header: &VerifierKeyHeader<E1>,
+    fetch_vk_primary: impl FnOnce() -> Result<S1::VerifierKey, NovaError>,
+    fetch_vk_secondary: impl FnOnce() -> Result<S2::VerifierKey, NovaError>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), NovaError> {
+    if opening.digest()? != *vk_digest {
       return Err(NovaError::ProofVerifyError);
     }
+    check_part_digest(header, &opening.header)?;
+
+    let trace = &mut VerifyTrace::new();
+    let f_U_secondary =
+      self.verify_instances(header, num_steps, z0_primary, z0_secondary, trace)?;
+
+    let vk_primary = fetch_vk_primary()?;
+    check_part_digest(&vk_primary, &opening.primary)?;
+    self.r_W_snark_primary.verify(&vk_primary, &self.r_U_primary)?;
+
+    let vk_secondary = fetch_vk_secondary()?;
+    check_part_digest(&vk_secondary, &opening.secondary)?;
+    self.f_W_snark_secondary.verify(&vk_secondary, &f_U_secondary)?;
+
+    Ok((self.zn_primary.clone(), self.zn_secondary.clone()))
+  }
+
+  /// Checks the instances of the `CompressedSNARK` against `header`, and
+  /// returns the folded instance of the secondary circuit, whose
+  /// satisfiability remains to be checked; the checks are recorded in `trace`
+  fn verify_instances(
+    &self,
+    header: &VerifierKeyHeader<E1>,
+    num_steps: usize,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    trace: &mut VerifyTrace,
+  ) -> Result<RelaxedR1CSInstance<Dual<E1>>, NovaError> {
+    // the number of steps cannot be zero
+    trace.check(Check::step("number of steps"), num_steps != 0, NovaError::ProofVerifyError)?;
+
+    // check if the (relaxed) R1CS instances have two public outputs
+    trace.check(
+      Check::equality("number of public outputs"),
+      self.l_u_secondary.X.len() == 2
+        && self.r_U_primary.X.len() == 2
+        && self.r_U_secondary.X.len() == 2,
+      NovaError::ProofVerifyError,
+    )?;
 
     // check if the output hashes in R1CS instances point to the right running
     // instances
     let (hash_primary, hash_secondary) = {
       let mut hasher = <Dual<E1> as Engine>::RO::new(
-        vk.ro_consts_secondary.clone(),
-        NUM_FE_WITHOUT_IO_FOR_CRHF + 2 * vk.F_arity_primary,
+        header.ro_consts_secondary.clone(),
+        NUM_FE_WITHOUT_IO_FOR_CRHF + 2 * header.F_arity_primary,
       );
-      hasher.absorb(vk.pp_digest);
+      hasher.absorb(header.pp_digest);
       hasher.absorb(E1::Scalar::from(num_steps as u64));
       for e in z0_primary {
         hasher.absorb(*e);
@@ -826,10 +1474,10 @@ where
       self.r_U_secondary.absorb_in_ro(&mut hasher);
 
       let mut hasher2 = <E1 as Engine>::RO::new(
-        vk.ro_consts_primary.clone(),
-        NUM_FE_WITHOUT_IO_FOR_CRHF + 2 * vk.F_arity_secondary,
+        header.ro_consts_primary.clone(),
+        NUM_FE_WITHOUT_IO_FOR_CRHF + 2 * header.F_arity_secondary,
       );
-      hasher2.absorb(scalar_as_base::<E1>(vk.pp_digest));
+      hasher2.absorb(scalar_as_base::<E1>(header.pp_digest));
       hasher2.absorb(<Dual<E1> as Engine>::Scalar::from(num_steps as u64));
       for e in z0_secondary {
         hasher2.absorb(*e);
@@ -842,35 +1490,32 @@ where
       (hasher.squeeze(NUM_HASH_BITS), hasher2.squeeze(NUM_HASH_BITS))
     };
 
-    if hash_primary != self.l_u_secondary.X[0]
-      || hash_secondary != scalar_as_base::<Dual<E1>>(self.l_u_secondary.X[1])
-    {
-      return Err(NovaError::ProofVerifyError);
-    }
+    trace.check(
+      Check::equality("primary output hash"),
+      hash_primary == self.l_u_secondary.X[0],
+      NovaError::ProofVerifyError,
+    )?;
+    trace.check(
+      Check::equality("secondary output hash"),
+      hash_secondary == scalar_as_base::<Dual<E1>>(self.l_u_secondary.X[1]),
+      NovaError::ProofVerifyError,
+    )?;
 
     // fold the secondary's running instance with the last instance to get a folded
     // instance
-    let f_U_secondary = self.nifs_secondary.verify(
-      &vk.ro_consts_secondary,
-      &scalar_as_base::<E1>(vk.pp_digest),
-      &self.r_U_secondary,
-      &self.l_u_secondary,
-    )?;
-
-    // check the satisfiability of the folded instances using
-    // SNARKs proving the knowledge of their satisfying witnesses
-    let (res_primary, res_secondary) = rayon::join(
-      || self.r_W_snark_primary.verify(&vk.vk_primary, &self.r_U_primary),
-      || self.f_W_snark_secondary.verify(&vk.vk_secondary, &f_U_secondary),
-    );
-
-    res_primary?;
-    res_secondary?;
-
-    Ok((self.zn_primary.clone(), self.zn_secondary.clone()))
+    trace.check_result(
+      Check::step("secondary fold"),
+      self.nifs_secondary.verify(
+        &header.ro_consts_secondary,
+        &scalar_as_base::<E1>(header.pp_digest),
+        &self.r_U_secondary,
+        &self.l_u_secondary,
+      ),
+    )
   }
 }
 
+#[cfg(feature = "prove")]
 /// Compute the circuit digest of a [`StepCircuit`].
 ///
 /// Note for callers: This function should be called with its performance
@@ -894,10 +1539,457 @@ pub fn circuit_digest<E1: CurveCycleEquipped, C: StepCircuit<E1::Scalar>>(
 }
 
 pub type CommitmentKey<E> = <<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey;
-type Commitment<E> = <<E as Engine>::CE as CommitmentEngineTrait<E>>::Commitment;
-type CompressedCommitment<E> = <<<E as Engine>::CE as CommitmentEngineTrait<E>>::Commitment as CommitmentTrait<E>>::CompressedCommitment;
+/// The commitments of the commitment engine of `E`, which add and scale, see
+/// `CommitmentTrait`
+pub type Commitment<E> = <<E as Engine>::CE as CommitmentEngineTrait<E>>::Commitment;
+/// The compressed commitments of the commitment engine of `E`
+pub type CompressedCommitment<E> = <<<E as Engine>::CE as CommitmentEngineTrait<E>>::Commitment as CommitmentTrait<E>>::CompressedCommitment;
 type CE<E> = <E as Engine>::CE;
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    digest::digest_hex,
+    provider::{ipa_pc, Bn256EngineIPA},
+    r1cs::commitment_key_digest,
+    spartan::snark::RelaxedR1CSSNARK,
+    supernova::TrivialCircuit,
+  };
+
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  fn test_verify_with_digest_with<E1: CurveCycleEquipped>() {
+    let primary_circuit = TrivialCircuit::default();
+    let secondary_circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &primary_circuit,
+      &secondary_circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let num_steps = 3;
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &primary_circuit, &secondary_circuit, &z0_primary, &z0_secondary)
+        .unwrap();
+    for _ in 0..num_steps {
+      recursive_snark.prove_step(&pp, &primary_circuit, &secondary_circuit).unwrap();
+    }
+
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    // a light verifier holds the digest, and fetches the rest of the key
+    let vk_digest = vk.digest().unwrap();
+    let opening = vk.opening().unwrap();
+    let verify = |opening: &VerifierKeyOpening<E1>, z0_primary: &[E1::Scalar]| {
+      snark.verify_with_digest(
+        &vk_digest,
+        opening,
+        vk.header(),
+        || Ok(vk.vk_primary().clone()),
+        || Ok(vk.vk_secondary().clone()),
+        num_steps,
+        z0_primary,
+        &z0_secondary,
+      )
+    };
+    assert_eq!(
+      verify(&opening, &z0_primary).unwrap(),
+      snark.verify(&vk, num_steps, &z0_primary, &z0_secondary).unwrap()
+    );
+
+    // an opening of another digest is rejected
+    let mut wrong_opening = opening.clone();
+    wrong_opening.primary += E1::Scalar::ONE;
+    assert_eq!(verify(&wrong_opening, &z0_primary), Err(NovaError::ProofVerifyError));
+
+    // the key is not fetched for a proof whose instances do not check
+    let wrong_z0_primary = vec![E1::Scalar::ZERO];
+    let res = snark.verify_with_digest(
+      &vk_digest,
+      &opening,
+      vk.header(),
+      || panic!("fetched the primary key"),
+      || panic!("fetched the secondary key"),
+      num_steps,
+      &wrong_z0_primary,
+      &z0_secondary,
+    );
+    assert_eq!(res, Err(NovaError::ProofVerifyError));
+  }
+
+  #[test]
+  fn test_verify_with_digest() { test_verify_with_digest_with::<Bn256EngineIPA>(); }
+
+  fn test_size_breakdown_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+
+    let (pk, _vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    let size = snark.size_breakdown();
+    assert_eq!(size.total(), bincode::serialize(&snark).unwrap().len());
+    assert!(size.sumcheck > 0 && size.pcs > 0 && size.claims > 0 && size.instance > 0);
+    assert_eq!(
+      size.sumcheck + size.pcs + size.claims,
+      snark.r_W_snark_primary.size_breakdown().total()
+        + snark.f_W_snark_secondary.size_breakdown().total()
+    );
+  }
+
+  fn test_verify_with_trace_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    let (res, trace) =
+      snark.verify_with_trace(&vk, 1, &z0_primary, &z0_secondary, AppContext::default());
+    assert_eq!(res, snark.verify(&vk, 1, &z0_primary, &z0_secondary));
+    assert!(trace.is_ok());
+    assert!(trace
+      .passed
+      .contains(&Check::SumcheckDegree { sumcheck: "outer sum-check".to_string(), round: 0 }));
+
+    // a wrong input fails the hash of the primary output
+    let (res, trace) =
+      snark.verify_with_trace(&vk, 1, &[E1::Scalar::ZERO], &z0_secondary, AppContext::default());
+    assert_eq!(res, Err(NovaError::ProofVerifyError));
+    assert_eq!(trace.failed.unwrap().0, Check::equality("primary output hash"));
+
+    // a wrong evaluation of the witness fails the final claim of the inner
+    // sum-check
+    let mut proof = serde_json::to_value(&snark.r_W_snark_primary).unwrap();
+    proof["eval_W"] = proof["eval_E"].clone();
+    let mut tampered = snark.clone();
+    tampered.r_W_snark_primary = serde_json::from_value(proof).unwrap();
+    let (res, trace) =
+      tampered.verify_with_trace(&vk, 1, &z0_primary, &z0_secondary, AppContext::default());
+    assert_eq!(res, Err(NovaError::InvalidSumcheckProof));
+    let failed = Check::SumcheckFinalClaim { sumcheck: "inner sum-check".to_string() };
+    assert_eq!(trace.failed.as_ref().unwrap().0, failed);
+    let json = trace.to_json().unwrap();
+    assert_eq!(serde_json::from_str::<VerifyTrace>(&json).unwrap(), trace);
+  }
+
+  #[test]
+  fn test_verify_with_trace() { test_verify_with_trace_with::<Bn256EngineIPA>(); }
+
+  fn test_validate_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+
+    let (pk, _vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    snark.validate().unwrap();
+
+    let proof = serde_json::to_value(&snark).unwrap();
+    let decodes = |tamper: &dyn Fn(&mut serde_json::Value)| {
+      let mut proof = proof.clone();
+      tamper(&mut proof);
+      serde_json::from_value::<CompressedSNARK<E1, S<E1>, S<Dual<E1>>>>(proof).is_ok()
+    };
+    assert!(decodes(&|_| {}));
+
+    // an instance with a third public output
+    assert!(!decodes(&|proof| {
+      let x = proof["l_u_secondary"]["X"][0].clone();
+      proof["l_u_secondary"]["X"].as_array_mut().unwrap().push(x);
+    }));
+    // an inner product argument with fewer right than left commitments
+    assert!(!decodes(&|proof| {
+      proof["r_W_snark_primary"]["eval_arg"]["R_vec"].as_array_mut().unwrap().pop();
+    }));
+    // a commitment that is not a point
+    assert!(!decodes(&|proof| {
+      for byte in proof["r_W_snark_primary"]["eval_arg"]["L_vec"][0]["comm"].as_array_mut().unwrap()
+      {
+        *byte = 0xff.into();
+      }
+    }));
+    // a round of a sum-check of a lower degree than the others
+    assert!(!decodes(&|proof| {
+      proof["f_W_snark_secondary"]["sc_proof_outer"]["compressed_polys"][0]
+        ["coeffs_except_linear_term"]
+        .as_array_mut()
+        .unwrap()
+        .pop();
+    }));
+  }
+
+  #[test]
+  fn test_validate() { test_validate_with::<Bn256EngineIPA>(); }
+
+  fn test_app_context_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+    recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark =
+      CompressedSNARK::prove_with_context(&pp, &pk, &recursive_snark, AppContext(b"app-a"))
+        .unwrap();
+    snark.verify_with_context(&vk, 1, &z0_primary, &z0_secondary, AppContext(b"app-a")).unwrap();
+
+    // the proof does not verify in another context, nor in the default one
+    assert!(snark
+      .verify_with_context(&vk, 1, &z0_primary, &z0_secondary, AppContext(b"app-b"))
+      .is_err());
+    assert!(snark.verify(&vk, 1, &z0_primary, &z0_secondary).is_err());
+
+    // the default context is that of proofs made without one
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    snark.verify_with_context(&vk, 1, &z0_primary, &z0_secondary, AppContext::default()).unwrap();
+  }
+
+  #[test]
+  fn test_app_context() { test_app_context_with::<Bn256EngineIPA>(); }
+
+  #[test]
+  fn test_size_breakdown() { test_size_breakdown_with::<Bn256EngineIPA>(); }
+
+  fn test_pp_digest_deterministic_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let setup = || {
+      PublicParams::<E1>::setup(
+        &circuit,
+        &circuit,
+        &*S::<E1>::ck_floor(),
+        &*S::<Dual<E1>>::ck_floor(),
+      )
+      .unwrap()
+    };
+    let (pp, other_pp) = (setup(), setup());
+
+    // two setups of the same circuits digest to the same values
+    assert_eq!(pp.digest(), other_pp.digest());
+    assert_eq!(
+      pp.circuit_shape_primary.r1cs_shape.digest(),
+      other_pp.circuit_shape_primary.r1cs_shape.digest()
+    );
+    assert_eq!(
+      commitment_key_digest::<E1>(&pp.ck_primary).unwrap(),
+      commitment_key_digest::<E1>(&other_pp.ck_primary).unwrap()
+    );
+
+    // the digest is recomputed, not deserialized, and does not change
+    let pp_bytes = bincode::serialize(&pp).unwrap();
+    let deserialized_pp: PublicParams<E1> = bincode::deserialize(&pp_bytes).unwrap();
+    assert_eq!(digest_hex(&pp.digest()), digest_hex(&deserialized_pp.digest()));
+
+    // and neither does the digest of the verifier key
+    let (_, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let (_, other_vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&other_pp).unwrap();
+    assert_eq!(vk.digest().unwrap(), other_vk.digest().unwrap());
+  }
+
+  #[test]
+  fn test_pp_digest_deterministic() { test_pp_digest_deterministic_with::<Bn256EngineIPA>(); }
+
+  fn test_proof_deterministic_with<E1: CurveCycleEquipped>() {
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+    let (pk, _vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+
+    let prove = || {
+      let z0_primary = vec![E1::Scalar::ONE];
+      let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+      let mut recursive_snark =
+        RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+      for _ in 0..2 {
+        recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+      }
+      let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+      bincode::serialize(&snark).unwrap()
+    };
+
+    // two runs on the same inputs produce the same bytes
+    let proof = prove();
+    assert_eq!(proof, prove());
+
+    // and so does a run on a single thread
+    #[cfg(feature = "parallel")]
+    {
+      let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+      assert_eq!(proof, pool.install(prove));
+    }
+  }
+
+  #[test]
+  fn test_proof_deterministic() { test_proof_deterministic_with::<Bn256EngineIPA>(); }
+
+  fn test_deferred_compression_with<E1: CurveCycleEquipped>() {
+    type EE<E> = ipa_pc::EvaluationEngine<E>;
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuit,
+      &circuit,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let (pk_deferred, vk_deferred) = EE::<E1>::setup(pp.ck_primary.clone());
+
+    let z0_primary = vec![E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let prove = |num_steps| {
+      let mut recursive_snark =
+        RecursiveSNARK::new(&pp, &circuit, &circuit, &z0_primary, &z0_secondary).unwrap();
+      for _ in 0..num_steps {
+        recursive_snark.prove_step(&pp, &circuit, &circuit).unwrap();
+      }
+      recursive_snark
+    };
+    let recursive_snark = prove(2);
+
+    // the steps defer evaluations of polynomials of different sizes
+    let mut deferred = DeferredOpenings::<E1>::new();
+    for num_vars in [2, 4, 3] {
+      let poly = (0..1u64 << num_vars).map(E1::Scalar::from).collect::<Vec<_>>();
+      let comm = E1::CE::commit(&pp.ck_primary, &poly);
+      let point = (0..num_vars).map(|j| E1::Scalar::from(j as u64 + 7)).collect();
+      deferred.defer(comm, poly, point).unwrap();
+    }
+    let claims = deferred.claims().to_vec();
+
+    let (snark, opening) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::prove_with_deferred::<EE<E1>>(
+      &pp,
+      &pk,
+      &pk_deferred,
+      &recursive_snark,
+      deferred,
+      AppContext::default(),
+    )
+    .unwrap();
+    let verify = |snark: &CompressedSNARK<_, _, _>, claims: &[_], num_steps| {
+      snark.verify_with_deferred::<EE<E1>>(
+        &vk,
+        &vk_deferred,
+        &opening,
+        claims,
+        num_steps,
+        &z0_primary,
+        &z0_secondary,
+        AppContext::default(),
+      )
+    };
+    verify(&snark, &claims, 2).unwrap();
+
+    // a tampered claim is rejected
+    let mut tampered = claims.clone();
+    tampered[1].eval += E1::Scalar::ONE;
+    assert!(verify(&snark, &tampered, 2).is_err());
+    assert!(verify(&snark, &claims[..2], 2).is_err());
+
+    // and so is the opening along another compressed proof
+    let other = CompressedSNARK::prove(&pp, &pk, &prove(3)).unwrap();
+    other.verify(&vk, 3, &z0_primary, &z0_secondary).unwrap();
+    assert!(verify(&other, &claims, 3).is_err());
+  }
+
+  #[test]
+  fn test_deferred_compression() { test_deferred_compression_with::<Bn256EngineIPA>(); }
+
+  fn test_prove_steps_with<E1: CurveCycleEquipped>() {
+    let primary = crate::test_circuits::FibonacciCircuit::new(3);
+    let secondary = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &primary,
+      &secondary,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+    let z0_primary = vec![E1::Scalar::ZERO, E1::Scalar::ONE];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let new =
+      || RecursiveSNARK::new(&pp, &primary, &secondary, &z0_primary, &z0_secondary).unwrap();
+    let steps = |n| std::iter::repeat((&primary, &secondary)).take(n);
+
+    let mut sequential = new();
+    for _ in 0..5 {
+      sequential.prove_step(&pp, &primary, &secondary).unwrap();
+    }
+
+    // the pipelined steps prove exactly what the steps one after another do
+    let mut pipelined = new();
+    pipelined.prove_steps(&pp, steps(5)).unwrap();
+    assert_eq!(bincode::serialize(&pipelined).unwrap(), bincode::serialize(&sequential).unwrap());
+    pipelined.verify(&pp, 5, &z0_primary, &z0_secondary).unwrap();
+
+    // and so do they after steps proved one at a time
+    let mut resumed = new();
+    resumed.prove_step(&pp, &primary, &secondary).unwrap();
+    resumed.prove_step(&pp, &primary, &secondary).unwrap();
+    resumed.prove_steps(&pp, steps(3)).unwrap();
+    assert_eq!(bincode::serialize(&resumed).unwrap(), bincode::serialize(&sequential).unwrap());
+  }
+
+  #[test]
+  fn test_prove_steps() { test_prove_steps_with::<Bn256EngineIPA>(); }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use core::{fmt::Write, marker::PhantomData};
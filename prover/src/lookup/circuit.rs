@@ -0,0 +1,238 @@
+//! Step circuits expressed as lookups, and the circuit that folds their
+//! lookups into the accumulators carried in `z`.
+use bellpepper::gadgets::Assignment;
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError};
+use ff::{Field, PrimeField};
+
+use super::table::{encode_entry, field_to_u64, DecomposableTable, CHUNK_BITS};
+use crate::{provider::poseidon::PoseidonConstantsCircuit, supernova::StepCircuit};
+
+/// The number of elements of `z` the lookup accumulators take: the
+/// challenges `alpha` and `beta`, the running sum of `1 / (alpha - e)` over
+/// the entries `e` looked up, encoded with `beta`, and the running hash of
+/// their chunks
+pub const ACCUMULATOR_ARITY: usize = 4;
+
+/// An entry looked up: the tag of its table, its chunks `(x, y, z)`, and its
+/// encoding by `encode_entry` when it is known
+struct Entry<F: PrimeField> {
+  id:     u8,
+  chunks: [AllocatedNum<F>; 3],
+  value:  Option<u64>,
+}
+
+/// The lookups a step makes, as the entries of the subtables they look up
+pub struct Lookups<F: PrimeField> {
+  entries: Vec<Entry<F>>,
+}
+
+impl<F: PrimeField> Lookups<F> {
+  pub(crate) fn new() -> Self { Self { entries: Vec::new() } }
+
+  /// The values of the entries looked up, if they are known
+  pub(crate) fn entry_values(&self) -> Option<Vec<u64>> {
+    self.entries.iter().map(|entry| entry.value).collect()
+  }
+
+  /// Returns `table(x, y)`, for operands `x` and `y` of `bits` bits, a
+  /// non-zero multiple of `CHUNK_BITS` of at most 64.
+  ///
+  /// The chunks of the operands and of the output are not range-checked
+  /// here: the lookup of their entry does it, as the entries are encoded with
+  /// the challenge `beta`, so that a chunk out of range encodes as the entry
+  /// of no subtable.
+  pub fn lookup<CS: ConstraintSystem<F>, T: DecomposableTable>(
+    &mut self,
+    mut cs: CS,
+    table: &T,
+    x: &AllocatedNum<F>,
+    y: &AllocatedNum<F>,
+    bits: usize,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    if bits == 0 || bits % CHUNK_BITS != 0 || bits > 64 || bits >= F::CAPACITY as usize {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let to_u64 = |v: &AllocatedNum<F>| {
+      v.get_value().map(|v| field_to_u64(&v).ok_or(SynthesisError::Unsatisfiable)).transpose()
+    };
+    let (x_value, y_value) = (to_u64(x)?, to_u64(y)?);
+
+    let mask = (1 << CHUNK_BITS) - 1;
+    let (mut x_lc, mut y_lc, mut z_lc) =
+      (LinearCombination::zero(), LinearCombination::zero(), LinearCombination::zero());
+    let mut z_value = Some(0u64);
+    for i in 0..bits / CHUNK_BITS {
+      let mut cs = cs.namespace(|| format!("chunk {i}"));
+      let shift = CHUNK_BITS * i;
+      let x_i = x_value.map(|x| (x >> shift) & mask);
+      let y_i = y_value.map(|y| (y >> shift) & mask);
+      let z_i = x_i.zip(y_i).map(|(x_i, y_i)| table.subtable(x_i, y_i));
+      let e_i =
+        x_i.zip(y_i).zip(z_i).map(|((x_i, y_i), z_i)| encode_entry(table.id(), x_i, y_i, z_i));
+      z_value = z_value.zip(z_i).map(|(z, z_i)| z | z_i << shift);
+
+      let x_i = alloc_u64(cs.namespace(|| "x"), x_i)?;
+      let y_i = alloc_u64(cs.namespace(|| "y"), y_i)?;
+      let z_i = alloc_u64(cs.namespace(|| "z"), z_i)?;
+
+      let weight = F::from(1 << shift);
+      x_lc = x_lc + (weight, x_i.get_variable());
+      y_lc = y_lc + (weight, y_i.get_variable());
+      z_lc = z_lc + (weight, z_i.get_variable());
+      self.entries.push(Entry { id: table.id(), chunks: [x_i, y_i, z_i], value: e_i });
+    }
+
+    let z = alloc_u64(cs.namespace(|| "output"), z_value)?;
+    cs.enforce(|| "x is its chunks", |_| x_lc, |lc| lc + CS::one(), |lc| lc + x.get_variable());
+    cs.enforce(|| "y is its chunks", |_| y_lc, |lc| lc + CS::one(), |lc| lc + y.get_variable());
+    cs.enforce(|| "z is its chunks", |_| z_lc, |lc| lc + CS::one(), |lc| lc + z.get_variable());
+    Ok(z)
+  }
+
+  /// Folds the entries looked up, encoded as `e = id + beta (x + beta (y +
+  /// beta z))`, into the running sum `acc` for the challenge `alpha`, and
+  /// their chunks into the running hash `hash`, returning their next values
+  fn accumulate<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    alpha: &AllocatedNum<F>,
+    beta: &AllocatedNum<F>,
+    acc: &AllocatedNum<F>,
+    hash: &AllocatedNum<F>,
+  ) -> Result<(AllocatedNum<F>, AllocatedNum<F>), SynthesisError> {
+    let mut sum_lc = LinearCombination::zero() + acc.get_variable();
+    let mut sum_value = acc.get_value();
+    for (i, entry) in self.entries.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("entry {i}"));
+      // `beta (x + beta (y + beta z))`, from the innermost product out
+      let mut e = LinearCombination::zero();
+      let mut e_value = Some(F::ZERO);
+      for (j, chunk) in entry.chunks.iter().enumerate().rev() {
+        let term = e.clone() + chunk.get_variable();
+        let term_value = e_value.zip(chunk.get_value()).map(|(e, c)| e + c);
+        let product = AllocatedNum::alloc(cs.namespace(|| format!("product {j}")), || {
+          Ok(*beta.get_value().get()? * *term_value.get()?)
+        })?;
+        cs.enforce(
+          || format!("product {j}"),
+          |lc| lc + beta.get_variable(),
+          |_| term,
+          |lc| lc + product.get_variable(),
+        );
+        e = LinearCombination::zero() + product.get_variable();
+        e_value = product.get_value();
+      }
+      let id = F::from(u64::from(entry.id));
+
+      let t = AllocatedNum::alloc(cs.namespace(|| "t"), || {
+        let diff = *alpha.get_value().get()? - id - *e_value.get()?;
+        Option::from(diff.invert()).ok_or(SynthesisError::DivisionByZero)
+      })?;
+      cs.enforce(
+        || "t (alpha - e) = 1",
+        |lc| lc + t.get_variable(),
+        |lc| lc + alpha.get_variable() - (id, CS::one()) - &e,
+        |lc| lc + CS::one(),
+      );
+      sum_lc = sum_lc + t.get_variable();
+      sum_value = sum_value.zip(t.get_value()).map(|(sum, t)| sum + t);
+    }
+
+    let acc_next = AllocatedNum::alloc(cs.namespace(|| "acc"), || {
+      sum_value.ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(|| "acc", |_| sum_lc, |lc| lc + CS::one(), |lc| lc + acc_next.get_variable());
+
+    let hash_next = if self.entries.is_empty() {
+      hash.clone()
+    } else {
+      let elements = core::iter::once(hash.clone())
+        .chain(self.entries.iter().flat_map(|entry| entry.chunks.clone()))
+        .collect::<Vec<_>>();
+      PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &elements)?
+    };
+    Ok((acc_next, hash_next))
+  }
+}
+
+/// Allocates the integer `v`
+fn alloc_u64<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  v: Option<u64>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  AllocatedNum::alloc(cs, || v.map(F::from).ok_or(SynthesisError::AssignmentMissing))
+}
+
+/// A step of an incremental computation expressed with lookups into
+/// decomposable tables -- to be implemented by applications, and run as a
+/// `StepCircuit` by wrapping it in a [`LookupStep`]
+pub trait LookupCircuit<F: PrimeField>: Send + Sync + Clone {
+  /// The number of inputs or outputs of each step, besides the lookup
+  /// accumulators
+  fn arity(&self) -> usize;
+
+  /// This circuit's assigned index, for use when enforcing the program
+  /// counter
+  fn circuit_index(&self) -> usize;
+
+  /// Synthesize the circuit for a computation step, making its lookups
+  /// through `lookups`, and return the output `pc_{i+1}` and `z_{i+1}`
+  #[allow(clippy::type_complexity)]
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+    lookups: &mut Lookups<F>,
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError>;
+
+  /// Return a digest of the non-deterministic inputs of this step, as for
+  /// `StepCircuit::witness_digest`
+  fn witness_digest(&self) -> Option<[u8; 32]> { None }
+}
+
+/// The `StepCircuit` of a [`LookupCircuit`], whose `z` is the `z` of the
+/// circuit followed by the [`ACCUMULATOR_ARITY`] lookup accumulators
+#[derive(Clone, Debug)]
+pub struct LookupStep<C> {
+  circuit: C,
+}
+
+impl<C> LookupStep<C> {
+  /// Wraps `circuit`
+  pub fn new(circuit: C) -> Self { Self { circuit } }
+
+  /// The wrapped circuit
+  pub fn circuit(&self) -> &C { &self.circuit }
+}
+
+impl<F: PrimeField, C: LookupCircuit<F>> StepCircuit<F> for LookupStep<C> {
+  fn arity(&self) -> usize { self.circuit.arity() + ACCUMULATOR_ARITY }
+
+  fn circuit_index(&self) -> usize { self.circuit.circuit_index() }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    if z.len() != self.arity() {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let (z, accumulators) = z.split_at(self.circuit.arity());
+    let [alpha, beta, acc, hash] = accumulators else {
+      unreachable!("z has the arity of the step")
+    };
+
+    let mut lookups = Lookups::new();
+    let (pc_next, mut z_next) =
+      self.circuit.synthesize(&mut cs.namespace(|| "step"), pc, z, &mut lookups)?;
+    let (acc_next, hash_next) =
+      lookups.accumulate(cs.namespace(|| "accumulate"), alpha, beta, acc, hash)?;
+    z_next.extend([alpha.clone(), beta.clone(), acc_next, hash_next]);
+    Ok((pc_next, z_next))
+  }
+
+  fn witness_digest(&self) -> Option<[u8; 32]> { self.circuit.witness_digest() }
+}
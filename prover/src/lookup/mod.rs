@@ -0,0 +1,329 @@
+//! A backend for step circuits expressed as lookups into decomposable tables,
+//! in the style of Lasso, whose lookups are carried through folding by
+//! accumulators in `z`.
+//!
+//! A [`LookupCircuit`] computes e.g. `x ^ y` on 32-bit operands with a lookup
+//! per byte into the subtable of the xor of two bytes, so that an operation on
+//! bytes is given as its table rather than as constraints, e.g. the
+//! transitions of an automaton. An entry `(x, y, z)` of the subtable of table
+//! `id` is encoded as `e = id + beta (x + beta (y + beta z))` for a random
+//! `beta`, which is injective whatever the range of the chunks, so that the
+//! lookups themselves range-check the chunks, rather than their bits. They are
+//! checked with the logarithmic derivative: the multiset of the entries `e`
+//! looked up is the multiset of the entries `e_j` of the subtables with
+//! multiplicities `m_j` if, for a random `alpha`,
+//!
+//! `sum_e 1 / (alpha - e) = sum_j m_j / (alpha - e_j)`.
+//!
+//! A [`LookupStep`] folds the left-hand side into a running sum in `z`,
+//! along with a running hash of the chunks looked up. Since `alpha` and
+//! `beta` must not depend on the chunks, a [`LookupTrace`] runs the
+//! computation natively first, and derives them from the hash of the chunks
+//! and the multiplicities of the entries, the [`LookupClaim`] of the
+//! computation. The verifier of
+//! the computation checks its final `z` against this claim with
+//! [`LookupClaim::verify`], which evaluates the right-hand side over the
+//! subtables.
+use std::collections::BTreeMap;
+
+use bellpepper::util_cs::witness_cs::WitnessCS;
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem};
+use ff::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::NovaError, provider::poseidon::PoseidonConstantsCircuit};
+
+mod circuit;
+mod table;
+
+pub use circuit::{LookupCircuit, LookupStep, Lookups, ACCUMULATOR_ARITY};
+pub(crate) use table::field_to_u64;
+use table::{decode_entry, fingerprint};
+pub use table::{BitwiseTable, DecomposableTable, CHUNK_BITS};
+
+/// The native execution of a computation with lookups, which records the
+/// entries it looks up to derive the challenges of its lookup accumulators
+pub struct LookupTrace<F: PrimeField> {
+  hash:           F,
+  multiplicities: BTreeMap<u64, u64>,
+  constants:      PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> Default for LookupTrace<F> {
+  fn default() -> Self { Self::new() }
+}
+
+impl<F: PrimeField> LookupTrace<F> {
+  /// Starts the trace of a computation
+  pub fn new() -> Self {
+    Self {
+      hash:           F::ZERO,
+      multiplicities: BTreeMap::new(),
+      constants:      PoseidonConstantsCircuit::default(),
+    }
+  }
+
+  /// Executes a step of `circuit` on `pc` and `z`, without the lookup
+  /// accumulators, and returns the next `pc` and `z`
+  pub fn step<C: LookupCircuit<F>>(
+    &mut self,
+    circuit: &C,
+    pc: Option<F>,
+    z: &[F],
+  ) -> Result<(Option<F>, Vec<F>), NovaError> {
+    if z.len() != circuit.arity() {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+    let mut cs = WitnessCS::<F>::new();
+    let pc = pc.map(|pc| AllocatedNum::alloc_infallible(cs.namespace(|| "pc"), || pc));
+    let z = z
+      .iter()
+      .enumerate()
+      .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+      .collect::<Vec<_>>();
+
+    let mut lookups = Lookups::new();
+    let (pc_next, z_next) = circuit
+      .synthesize(&mut cs, pc.as_ref(), &z, &mut lookups)
+      .map_err(|e| NovaError::SynthesisError(e.to_string()))?;
+    if z_next.len() != circuit.arity() {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+
+    let entries = lookups.entry_values().ok_or(NovaError::IncorrectWitness)?;
+    if !entries.is_empty() {
+      let chunks = entries
+        .iter()
+        .map(|e| decode_entry(*e).map(|(_, x, y, z)| [x, y, z].map(F::from)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(NovaError::IncorrectWitness)?;
+      let elements =
+        core::iter::once(self.hash).chain(chunks.into_iter().flatten()).collect::<Vec<_>>();
+      self.hash = self.constants.hash(&elements);
+    }
+    for e in entries {
+      *self.multiplicities.entry(e).or_default() += 1;
+    }
+
+    let pc_next =
+      pc_next.map(|pc| pc.get_value().ok_or(NovaError::IncorrectWitness)).transpose()?;
+    let z_next = z_next
+      .iter()
+      .map(|z_i| z_i.get_value().ok_or(NovaError::IncorrectWitness))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok((pc_next, z_next))
+  }
+
+  /// Finishes the trace into the claim about the lookups of the computation
+  pub fn finish(self) -> LookupClaim<F> {
+    let multiplicities = self.multiplicities.into_iter().collect::<Vec<_>>();
+    let (alpha, beta) = LookupClaim::challenges(&self.constants, self.hash, &multiplicities);
+    LookupClaim { alpha, beta, multiplicities }
+  }
+}
+
+/// The entries of the subtables a computation looks up, with their
+/// multiplicities, and the challenges of its lookup accumulators
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupClaim<F: PrimeField> {
+  alpha:          F,
+  beta:           F,
+  multiplicities: Vec<(u64, u64)>,
+}
+
+impl<F: PrimeField> LookupClaim<F> {
+  /// The challenges for the chunks looked up, of running hash `hash`, with
+  /// `multiplicities`
+  fn challenges(
+    constants: &PoseidonConstantsCircuit<F>,
+    hash: F,
+    multiplicities: &[(u64, u64)],
+  ) -> (F, F) {
+    let elements = core::iter::once(hash)
+      .chain(multiplicities.iter().flat_map(|(e, m)| [F::from(*e), F::from(*m)]))
+      .collect::<Vec<_>>();
+    let alpha = constants.hash(&elements);
+    (alpha, constants.hash(&[alpha]))
+  }
+
+  /// The initial lookup accumulators, to append to the initial `z` of the
+  /// computation
+  pub fn initial_accumulators(&self) -> [F; ACCUMULATOR_ARITY] {
+    [self.alpha, self.beta, F::ZERO, F::ZERO]
+  }
+
+  /// Checks that the lookups of the computation from `z0` to `z_final`,
+  /// both ending with the lookup accumulators, are into `tables`
+  pub fn verify(
+    &self,
+    tables: &[&dyn DecomposableTable],
+    z0: &[F],
+    z_final: &[F],
+  ) -> Result<(), NovaError> {
+    if z0.len() < ACCUMULATOR_ARITY || z_final.len() != z0.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let [alpha, beta, acc, hash] = z_final[z_final.len() - ACCUMULATOR_ARITY..] else {
+      unreachable!("z ends with the accumulators")
+    };
+    if z0[z0.len() - ACCUMULATOR_ARITY..] != self.initial_accumulators()
+      || (alpha, beta) != (self.alpha, self.beta)
+      || Self::challenges(&PoseidonConstantsCircuit::default(), hash, &self.multiplicities)
+        != (self.alpha, self.beta)
+    {
+      return Err(NovaError::InvalidLookups);
+    }
+
+    let mut expected = F::ZERO;
+    for (i, (e, m)) in self.multiplicities.iter().enumerate() {
+      // the entries are listed once each, in increasing order
+      if *m == 0 || (i > 0 && self.multiplicities[i - 1].0 >= *e) {
+        return Err(NovaError::InvalidLookups);
+      }
+      let (id, x, y, z) = decode_entry(*e).ok_or(NovaError::InvalidLookups)?;
+      if !tables.iter().any(|table| table.id() == id && table.subtable(x, y) == z) {
+        return Err(NovaError::InvalidLookups);
+      }
+      let e = fingerprint(self.beta, id, F::from(x), F::from(y), F::from(z));
+      let inv = Option::<F>::from((self.alpha - e).invert()).ok_or(NovaError::InvalidLookups)?;
+      expected += F::from(*m) * inv;
+    }
+    if acc != expected {
+      return Err(NovaError::InvalidLookups);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::{test_cs::TestConstraintSystem, SynthesisError};
+
+  use super::*;
+  use crate::{provider::Bn256EngineIPA, supernova::StepCircuit, traits::Engine};
+
+  type F = <Bn256EngineIPA as Engine>::Scalar;
+
+  /// A round of a toy cipher on 32-bit words: `(a, b) -> (a ^ b, a & b | k)`
+  #[derive(Clone)]
+  struct CipherRound {
+    key: u64,
+  }
+
+  impl LookupCircuit<F> for CipherRound {
+    fn arity(&self) -> usize { 2 }
+
+    fn circuit_index(&self) -> usize { 0 }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      pc: Option<&AllocatedNum<F>>,
+      z: &[AllocatedNum<F>],
+      lookups: &mut Lookups<F>,
+    ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+      let key = AllocatedNum::alloc(cs.namespace(|| "key"), || Ok(F::from(self.key)))?;
+      let xor = lookups.lookup(cs.namespace(|| "xor"), &BitwiseTable::Xor, &z[0], &z[1], 32)?;
+      let and = lookups.lookup(cs.namespace(|| "and"), &BitwiseTable::And, &z[0], &z[1], 32)?;
+      let or = lookups.lookup(cs.namespace(|| "or"), &BitwiseTable::Or, &and, &key, 32)?;
+      Ok((pc.cloned(), vec![xor, or]))
+    }
+  }
+
+  #[test]
+  fn test_lookup_step() {
+    const TABLES: [&dyn DecomposableTable; 3] =
+      [&BitwiseTable::And, &BitwiseTable::Or, &BitwiseTable::Xor];
+    let rounds = [0x0123_4567u64, 0x89ab_cdef, 0xdead_beef].map(|key| CipherRound { key });
+    let (mut a, mut b) = (0x1357_9bdfu64, 0x2468_ace0u64);
+
+    // the native execution computes the cipher, and the claim on its lookups
+    let mut trace = LookupTrace::new();
+    let mut z = vec![F::from(a), F::from(b)];
+    for round in &rounds {
+      z = trace.step(round, None, &z).unwrap().1;
+      (a, b) = (a ^ b, (a & b) | round.key);
+      assert_eq!(z, vec![F::from(a), F::from(b)]);
+    }
+    let claim = trace.finish();
+
+    // the steps are satisfied with the accumulators appended to `z`
+    let z0 = [F::from(0x1357_9bdf), F::from(0x2468_ace0)]
+      .into_iter()
+      .chain(claim.initial_accumulators())
+      .collect::<Vec<_>>();
+    let mut z = z0.clone();
+    for round in &rounds {
+      let step = LookupStep::new(round.clone());
+      let mut cs = TestConstraintSystem::<F>::new();
+      let z_alloc = z
+        .iter()
+        .enumerate()
+        .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+        .collect::<Vec<_>>();
+      let (_, z_next) = step.synthesize(&mut cs, None, &z_alloc).unwrap();
+      assert!(cs.is_satisfied());
+      z = z_next.iter().map(|z_i| z_i.get_value().unwrap()).collect();
+    }
+    assert_eq!(z[..2], [F::from(a), F::from(b)]);
+    assert_eq!(claim.verify(&TABLES, &z0, &z), Ok(()));
+
+    // the accumulators must match the claim
+    let mut wrong_z = z.clone();
+    wrong_z[4] += F::ONE;
+    assert_eq!(claim.verify(&TABLES, &z0, &wrong_z), Err(NovaError::InvalidLookups));
+
+    // the entries must be into the tables
+    assert_eq!(claim.verify(&TABLES[..2], &z0, &z), Err(NovaError::InvalidLookups));
+
+    // and the multiplicities are bound to the challenge
+    let mut wrong_claim = claim.clone();
+    wrong_claim.multiplicities[0].1 += 1;
+    assert_eq!(wrong_claim.verify(&TABLES, &z0, &z), Err(NovaError::InvalidLookups));
+  }
+
+  #[test]
+  fn test_lookup_range_check() {
+    // an operand wider than the lookup is rejected
+    let mut cs = TestConstraintSystem::<F>::new();
+    let x = AllocatedNum::alloc_infallible(cs.namespace(|| "x"), || F::from(1 << 16));
+    let y = AllocatedNum::alloc_infallible(cs.namespace(|| "y"), || F::ZERO);
+    let mut lookups = Lookups::new();
+    lookups.lookup(cs.namespace(|| "xor"), &BitwiseTable::Xor, &x, &y, 16).unwrap();
+    assert!(!cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_lookup_chunks_out_of_range() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let x = AllocatedNum::alloc_infallible(cs.namespace(|| "x"), || F::from(1 << 8));
+    let y = AllocatedNum::alloc_infallible(cs.namespace(|| "y"), || F::ZERO);
+    let mut lookups = Lookups::new();
+    lookups.lookup(cs.namespace(|| "xor"), &BitwiseTable::Xor, &x, &y, 16).unwrap();
+    assert!(cs.is_satisfied());
+
+    // a malicious witness splits x into the chunks (2^8, 0) rather than
+    // (0, 1), for chunks (1, 0) of the output 1 rather than 2^8, which the
+    // constraints of the lookup do not reject
+    cs.set("xor/chunk 0/x/num", F::from(1 << 8));
+    cs.set("xor/chunk 1/x/num", F::ZERO);
+    cs.set("xor/chunk 0/z/num", F::ONE);
+    cs.set("xor/chunk 1/z/num", F::ZERO);
+    cs.set("xor/output/num", F::ONE);
+    assert!(cs.is_satisfied());
+
+    // the entry (2^8, 0, 1) of the first chunk has the key of the entry
+    // (0, 1, 1) of the subtable, but is encoded as no entry of the subtable
+    let id = BitwiseTable::Xor.id();
+    assert_eq!(
+      F::from(table::encode_entry(id, 0, 1, 1)),
+      F::from(u64::from(id)) + F::from(1 << 16) + F::from(1 << 24)
+    );
+    let beta = F::from(0x1234_5678_9abc_def0);
+    let malicious = table::fingerprint(beta, id, F::from(1 << 8), F::ZERO, F::ONE);
+    for (x, y) in (0..1 << CHUNK_BITS).flat_map(|x| (0..1 << CHUNK_BITS).map(move |y| (x, y))) {
+      let z = BitwiseTable::Xor.subtable(x, y);
+      assert_ne!(table::fingerprint(beta, id, F::from(x), F::from(y), F::from(z)), malicious);
+    }
+  }
+}
@@ -0,0 +1,98 @@
+//! Decomposable tables, whose lookups split into lookups into a small
+//! subtable, one per chunk of their operands.
+use ff::PrimeField;
+
+/// The number of bits of a chunk of an operand, so that a subtable has
+/// `2^(2 CHUNK_BITS)` entries
+pub const CHUNK_BITS: usize = 8;
+
+/// The number of bits of the tag of a table in an encoded entry
+const ID_BITS: usize = 8;
+
+/// A table over pairs of operands of up to 64 bits, such that the output on
+/// `x` and `y` is `sum_i 2^(CHUNK_BITS i) g(x_i, y_i)`, for the chunks `x_i`
+/// and `y_i` of `CHUNK_BITS` bits of the operands and a subtable `g` whose
+/// outputs have at most `CHUNK_BITS` bits, e.g. a bitwise operation.
+///
+/// A lookup into the table is made of a lookup into `g` per chunk.
+pub trait DecomposableTable: Send + Sync {
+  /// The tag of the table, distinct among the tables a computation looks
+  /// up into
+  fn id(&self) -> u8;
+
+  /// The output of the subtable on chunks `x` and `y`
+  fn subtable(&self, x: u64, y: u64) -> u64;
+}
+
+/// The bitwise operations, as decomposable tables
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseTable {
+  /// `x & y`
+  And,
+  /// `x | y`
+  Or,
+  /// `x ^ y`
+  Xor,
+}
+
+impl DecomposableTable for BitwiseTable {
+  fn id(&self) -> u8 {
+    match self {
+      Self::And => 0,
+      Self::Or => 1,
+      Self::Xor => 2,
+    }
+  }
+
+  fn subtable(&self, x: u64, y: u64) -> u64 {
+    match self {
+      Self::And => x & y,
+      Self::Or => x | y,
+      Self::Xor => x ^ y,
+    }
+  }
+}
+
+/// Encodes the entry `(x, y, z)` of the subtable of table `id` into the
+/// integer `id + 2^8 (x + 2^CHUNK_BITS y + 2^(2 CHUNK_BITS) z)`, which keys
+/// the entry in a claim. It is injective over chunks of `CHUNK_BITS` bits
+/// only, so the circuits encode their entries with [`fingerprint`] instead.
+pub(crate) fn encode_entry(id: u8, x: u64, y: u64, z: u64) -> u64 {
+  u64::from(id) | x << ID_BITS | y << (ID_BITS + CHUNK_BITS) | z << (ID_BITS + 2 * CHUNK_BITS)
+}
+
+/// Encodes the entry `(x, y, z)` of the subtable of table `id` as `id + beta
+/// (x + beta (y + beta z))`, as the circuits do: for a random `beta`, two
+/// entries fixed before it encode alike only if they are equal, whatever the
+/// range of their chunks
+pub(crate) fn fingerprint<F: PrimeField>(beta: F, id: u8, x: F, y: F, z: F) -> F {
+  F::from(u64::from(id)) + beta * (x + beta * (y + beta * z))
+}
+
+/// Decodes an entry encoded by `encode_entry`, or returns `None` if it is
+/// not the encoding of an entry
+pub(crate) fn decode_entry(e: u64) -> Option<(u8, u64, u64, u64)> {
+  if e >> (ID_BITS + 3 * CHUNK_BITS) != 0 {
+    return None;
+  }
+  let mask = (1 << CHUNK_BITS) - 1;
+  Some((
+    (e & ((1 << ID_BITS) - 1)) as u8,
+    (e >> ID_BITS) & mask,
+    (e >> (ID_BITS + CHUNK_BITS)) & mask,
+    (e >> (ID_BITS + 2 * CHUNK_BITS)) & mask,
+  ))
+}
+
+/// The value of `v` as an integer, if it fits in 64 bits, for a field whose
+/// representation is little-endian
+pub(crate) fn field_to_u64<F: PrimeField>(v: &F) -> Option<u64> {
+  let repr = v.to_repr();
+  let (low, high) = repr.as_ref().split_at(8);
+  if high.iter().any(|b| *b != 0) {
+    return None;
+  }
+  let mut bytes = [0u8; 8];
+  bytes.copy_from_slice(low);
+  Some(u64::from_le_bytes(bytes))
+}
@@ -0,0 +1,322 @@
+//! Manifests: serializable descriptions of proving tasks, so that a server
+//! picks the circuit a client proves with, and its sizes, without a release
+//! of the client.
+//!
+//! A [`Manifest`] names a parser circuit of [`crate::circuits`] and the
+//! parameters its shape depends on: the claim of a JWT to extract, the
+//! lengths of the tokens and the key of their issuer, the pattern of a regex
+//! and its chunk length, or a claim about a committed value and the maximum
+//! length of the value. It is deserialized with any `serde` format, e.g.
+//! JSON with [`Manifest::from_json`] or TOML, and [`Manifest::task`] turns it
+//! and the input of the client into the [`ManifestStep`]s of the computation
+//! and its initial `z`. A step is a single-circuit [`NonUniformCircuit`], for
+//! [`Manifest::public_params`], so that the parameters are sized by the
+//! manifest: two inputs of a manifest have the same parameters.
+//!
+//! ```json
+//! { "circuit": "claim", "claim": { "claim": "at_least", "threshold": 18 }, "max_len": 3 }
+//! ```
+//!
+//! A verifier accepts the proofs of the manifests it approved, by their
+//! [`Manifest::digest`], with a [`ManifestRegistry`].
+use std::sync::Arc;
+
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  circuits::{
+    claim::{value_commitment, Claim, ClaimStep},
+    jwt::{JwtKey, JwtStep},
+    regex::{self, Dfa, RegexStep},
+  },
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  lookup::{LookupClaim, LookupStep, LookupTrace},
+  supernova::{NonUniformCircuit, PublicParams, StepCircuit, TrivialCircuit},
+  traits::{snark::default_ck_hint, CurveCycleEquipped, Dual, Engine},
+};
+
+mod registry;
+pub use registry::ManifestRegistry;
+
+/// The description of a proving task
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "circuit", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Manifest {
+  /// The extraction of the string claim `claim` of a JWT whose header and
+  /// payload are `header_len` and `payload_len` bytes long, signed by `key`,
+  /// with a [`JwtStep`]; the input is the compact serialization of the token
+  Jwt {
+    /// The key of the claim in the payload
+    claim:         String,
+    /// The maximum length of the value of the claim
+    max_value_len: usize,
+    /// The length of the encoded header
+    header_len:    usize,
+    /// The length of the encoded payload
+    payload_len:   usize,
+    /// The key of the issuer, whose commitment the step outputs
+    key:           JwtKey,
+  },
+  /// A match of `pattern`, read `chunk_len` bytes per step, with
+  /// [`RegexStep`]s; the input is the string matched
+  Regex {
+    /// The pattern, in the syntax of [`Dfa::compile`]
+    pattern:   String,
+    /// The number of bytes read per step
+    chunk_len: usize,
+  },
+  /// The proof of `claim` about a value of at most `max_len` bytes, with a
+  /// [`ClaimStep`]; the input is the value
+  Claim {
+    /// The claim, e.g. a threshold
+    claim:   Claim,
+    /// The maximum length of the value
+    max_len: usize,
+  },
+}
+
+impl SimpleDigestible for Manifest {}
+
+impl Manifest {
+  /// Deserializes and validates the JSON manifest `json`
+  pub fn from_json(json: &str) -> Result<Self, NovaError> {
+    let manifest: Self =
+      serde_json::from_str(json).map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    manifest.validate()?;
+    Ok(manifest)
+  }
+
+  /// The digest of the manifest, as for the digests of the public
+  /// parameters, which identifies it in a [`ManifestRegistry`]
+  // serializing the manifest into the hasher does not fail
+  #[allow(clippy::expect_used)]
+  pub fn digest<F: PrimeField>(&self) -> F {
+    DigestComputer::new(self).digest().expect("a manifest serializes with bincode")
+  }
+
+  /// Checks that the circuit of the manifest has a shape for its parameters
+  pub fn validate(&self) -> Result<(), NovaError> {
+    match self {
+      Self::Jwt { claim, max_value_len, header_len, payload_len, key } => {
+        if claim.is_empty() || claim.contains(['"', '\\']) {
+          return Err(NovaError::InvalidManifest("the claim is not a plain key".into()));
+        }
+        if *max_value_len == 0 || *header_len == 0 || *payload_len == 0 {
+          return Err(NovaError::InvalidManifest("a length of the token is zero".into()));
+        }
+        if !key.is_well_formed() {
+          return Err(NovaError::InvalidManifest("the key of the issuer is malformed".into()));
+        }
+      },
+      Self::Regex { pattern, chunk_len } => {
+        if *chunk_len == 0 {
+          return Err(NovaError::InvalidManifest("the chunk length is zero".into()));
+        }
+        Dfa::compile(pattern)?;
+      },
+      Self::Claim { claim, max_len } => claim.validate(*max_len)?,
+    }
+    Ok(())
+  }
+
+  /// The steps proving the manifest of `input`, and the initial `z` of the
+  /// computation
+  pub fn task<F: PrimeField>(&self, input: &[u8]) -> Result<ProvingTask<F>, NovaError> {
+    self.validate()?;
+    match self {
+      Self::Jwt { claim, max_value_len, header_len, payload_len, key } => {
+        let token = std::str::from_utf8(input).map_err(|_| NovaError::InvalidInputLength)?;
+        let mut parts = token.split('.');
+        if parts.next().map(str::len) != Some(*header_len)
+          || parts.next().map(str::len) != Some(*payload_len)
+        {
+          return Err(NovaError::InvalidManifest("the token is not of the manifest".into()));
+        }
+        Ok(ProvingTask {
+          steps:   vec![ManifestStep::Jwt(JwtStep::new(token, claim, *max_value_len, key)?)],
+          z0:      vec![F::ZERO; JwtStep::arity_for(*max_value_len)],
+          lookups: None,
+        })
+      },
+      Self::Regex { pattern, chunk_len } => {
+        let dfa = Arc::new(Dfa::compile(pattern)?);
+        let steps = RegexStep::steps(&dfa, input, *chunk_len)?;
+        let mut trace = LookupTrace::new();
+        let mut z = regex::initial_io::<F>();
+        for step in &steps {
+          z = trace.step(step, None, &z)?.1;
+        }
+        let lookups = trace.finish();
+        Ok(ProvingTask {
+          z0:      [regex::initial_io(), lookups.initial_accumulators().to_vec()].concat(),
+          steps:   steps
+            .into_iter()
+            .map(|step| ManifestStep::Regex(LookupStep::new(step)))
+            .collect(),
+          lookups: Some(lookups),
+        })
+      },
+      Self::Claim { claim, max_len } => Ok(ProvingTask {
+        steps:   vec![ManifestStep::Claim(ClaimStep::new(claim.clone(), *max_len, input)?)],
+        z0:      vec![value_commitment(input, *max_len)?],
+        lookups: None,
+      }),
+    }
+  }
+
+  /// The public parameters of the manifest, sized by the first step of
+  /// `task`, a task of the manifest
+  pub fn public_params<E1: CurveCycleEquipped>(
+    &self,
+    task: &ProvingTask<E1::Scalar>,
+  ) -> Result<PublicParams<E1>, NovaError> {
+    let step = task.steps.first().ok_or(NovaError::InvalidNumSteps)?;
+    Ok(PublicParams::setup(step, &*default_ck_hint(), &*default_ck_hint()))
+  }
+}
+
+/// The steps of a [`Manifest`] for an input, with the initial `z` of their
+/// computation
+#[derive(Clone, Debug)]
+pub struct ProvingTask<F: PrimeField> {
+  /// The steps, proved in order
+  pub steps:   Vec<ManifestStep>,
+  /// The initial `z`
+  pub z0:      Vec<F>,
+  /// The claim about the lookups of the steps, for the steps with lookups,
+  /// which the verifier checks with [`LookupClaim::verify`]
+  pub lookups: Option<LookupClaim<F>>,
+}
+
+/// The step circuit of a [`Manifest`]
+#[derive(Clone, Debug)]
+pub enum ManifestStep {
+  /// A step of a JWT manifest
+  Jwt(JwtStep),
+  /// A step of a regex manifest, with its lookup accumulators
+  Regex(LookupStep<RegexStep>),
+  /// A step of a claim manifest
+  Claim(ClaimStep),
+}
+
+impl<F: PrimeField> StepCircuit<F> for ManifestStep {
+  fn arity(&self) -> usize {
+    match self {
+      Self::Jwt(step) => StepCircuit::<F>::arity(step),
+      Self::Regex(step) => StepCircuit::<F>::arity(step),
+      Self::Claim(step) => StepCircuit::<F>::arity(step),
+    }
+  }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    match self {
+      Self::Jwt(step) => step.synthesize(cs, pc, z),
+      Self::Regex(step) => step.synthesize(cs, pc, z),
+      Self::Claim(step) => step.synthesize(cs, pc, z),
+    }
+  }
+}
+
+impl<E1: CurveCycleEquipped> NonUniformCircuit<E1> for ManifestStep {
+  type C1 = Self;
+  type C2 = TrivialCircuit<<Dual<E1> as Engine>::Scalar>;
+
+  fn num_circuits(&self) -> usize { 1 }
+
+  fn primary_circuit(&self, _circuit_index: usize) -> Self::C1 { self.clone() }
+
+  fn secondary_circuit(&self) -> Self::C2 { TrivialCircuit::default() }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::test_cs::TestConstraintSystem;
+
+  use super::*;
+  use crate::{lookup::DecomposableTable, provider::Bn256EngineIPA};
+
+  type E = Bn256EngineIPA;
+  type F = halo2curves::bn256::Fr;
+
+  fn run(task: &ProvingTask<F>) -> Vec<F> {
+    let mut z = task.z0.clone();
+    for step in &task.steps {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let z_alloc = z
+        .iter()
+        .enumerate()
+        .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+        .collect::<Vec<_>>();
+      let (_, z_next) = step.synthesize(&mut cs, None, &z_alloc).unwrap();
+      assert!(cs.is_satisfied());
+      z = z_next.iter().map(|z_i| z_i.get_value().unwrap()).collect();
+    }
+    z
+  }
+
+  #[test]
+  fn test_manifest() {
+    let manifest = Manifest::from_json(
+      r#"{"circuit":"claim","claim":{"claim":"at_least","threshold":18},"max_len":3}"#,
+    )
+    .unwrap();
+    assert_eq!(manifest, Manifest::Claim {
+      claim:   Claim::AtLeast { threshold: 18, scale: 0 },
+      max_len: 3,
+    });
+    let task = manifest.task::<F>(b"21").unwrap();
+    assert_eq!(run(&task), task.z0);
+    assert!(manifest.task::<F>(b"17").is_err());
+
+    // the parameters are sized by the manifest, not by the input
+    let pp = manifest.public_params::<E>(&task).unwrap();
+    let other = manifest.public_params::<E>(&manifest.task(b"100").unwrap()).unwrap();
+    assert_eq!(pp.digest(), other.digest());
+
+    // invalid parameters, missing sizes and unknown circuits
+    let invalid = [
+      r#"{"circuit":"claim","claim":{"claim":"at_least","threshold":18},"max_len":0}"#,
+      r#"{"circuit":"regex","pattern":"a(","chunk_len":8}"#,
+      r#"{"circuit":"jwt","claim":"email","max_value_len":20}"#,
+      r#"{"circuit":"sql","query":"select"}"#,
+    ];
+    for json in invalid {
+      assert!(Manifest::from_json(json).is_err(), "{json}");
+    }
+
+    // the key of the issuer of JWTs, of the length of its algorithm
+    let jwt = |point: &str| {
+      let sizes = r#""max_value_len":20,"header_len":36,"payload_len":82"#;
+      let key = format!(r#"{{"alg":"ES256","point":"{point}"}}"#);
+      Manifest::from_json(&format!(r#"{{"circuit":"jwt","claim":"email",{sizes},"key":{key}}}"#))
+    };
+    assert!(jwt(&"00".repeat(64)).is_ok());
+    assert!(jwt("00").is_err());
+  }
+
+  #[test]
+  fn test_regex_manifest() {
+    let pattern = r#"\{(?:"\w+":"[^"]*",)*"sub":"([^"]*)".*"#;
+    let json = format!(r#"{{"circuit":"regex","pattern":{pattern:?},"chunk_len":16}}"#);
+    let manifest = Manifest::from_json(&json).unwrap();
+    let input = br#"{"iss":"example.com","sub":"alice"}"#;
+    let task = manifest.task::<F>(input).unwrap();
+    assert_eq!(task.steps.len(), 3);
+
+    let z = run(&task);
+    let dfa = Dfa::compile(pattern).unwrap();
+    let tables: [&dyn DecomposableTable; 1] = [&dfa];
+    assert_eq!(task.lookups.unwrap().verify(&tables, &task.z0, &z), Ok(()));
+    assert!(regex::verify_io(&dfa, &z, &dfa.reveal(input).unwrap(), 16).is_ok());
+  }
+}
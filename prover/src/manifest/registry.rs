@@ -0,0 +1,164 @@
+//! A registry of the approved manifests of a verifier.
+//!
+//! A manifest is approved with the public parameters of its circuit, and
+//! identified by its [`Manifest::digest`], which the prover sends along with
+//! its proof. The registry only verifies a proof against the parameters of
+//! the manifest it claims, so that a verifier accepts the proofs of the tasks
+//! it approved, and no others, even if they are of circuits it knows of.
+use std::sync::Arc;
+
+use super::Manifest;
+use crate::{
+  supernova::{error::SuperNovaError, PublicParams, RecursiveSNARK},
+  traits::{CurveCycleEquipped, Dual, Engine},
+};
+
+/// An approved manifest
+#[derive(Debug)]
+struct ApprovedManifest<E1: CurveCycleEquipped> {
+  manifest:        Manifest,
+  manifest_digest: E1::Scalar,
+  circuits_digest: E1::Scalar,
+  pp:              Arc<PublicParams<E1>>,
+}
+
+/// The approved manifests, keyed by manifest digest
+#[derive(Debug)]
+pub struct ManifestRegistry<E1: CurveCycleEquipped> {
+  manifests: Vec<ApprovedManifest<E1>>,
+}
+
+impl<E1: CurveCycleEquipped> Default for ManifestRegistry<E1> {
+  fn default() -> Self { Self { manifests: Vec::new() } }
+}
+
+impl<E1: CurveCycleEquipped> ManifestRegistry<E1> {
+  /// Creates an empty registry
+  pub fn new() -> Self { Self::default() }
+
+  /// Approves `manifest`, whose circuit has the parameters `pp`, and returns
+  /// its digest.
+  ///
+  /// Fails if the manifest is already approved.
+  pub fn approve(
+    &mut self,
+    manifest: Manifest,
+    pp: Arc<PublicParams<E1>>,
+  ) -> Result<E1::Scalar, SuperNovaError> {
+    let manifest_digest = manifest.digest();
+    if self.manifests.iter().any(|m| m.manifest_digest == manifest_digest) {
+      return Err(SuperNovaError::ManifestExists);
+    }
+    let circuits_digest = pp.circuits_digest();
+    self.manifests.push(ApprovedManifest { manifest, manifest_digest, circuits_digest, pp });
+    Ok(manifest_digest)
+  }
+
+  /// Revokes the manifest with the given digest, and returns it with its
+  /// parameters
+  pub fn revoke(
+    &mut self,
+    manifest_digest: &E1::Scalar,
+  ) -> Option<(Manifest, Arc<PublicParams<E1>>)> {
+    let index = self.manifests.iter().position(|m| m.manifest_digest == *manifest_digest)?;
+    let approved = self.manifests.remove(index);
+    Some((approved.manifest, approved.pp))
+  }
+
+  /// The manifest with the given digest, and its parameters
+  pub fn get(&self, manifest_digest: &E1::Scalar) -> Option<(&Manifest, &Arc<PublicParams<E1>>)> {
+    self.find(manifest_digest).map(|m| (&m.manifest, &m.pp))
+  }
+
+  /// The digests of the approved manifests with the digests of their
+  /// circuits, in the order of their approval
+  pub fn manifests(&self) -> impl Iterator<Item = (&E1::Scalar, &E1::Scalar)> {
+    self.manifests.iter().map(|m| (&m.manifest_digest, &m.circuits_digest))
+  }
+
+  /// Verifies `recursive_snark`, a proof for the manifest of digest
+  /// `manifest_digest`, against the parameters of that manifest, and returns
+  /// the manifest along with the outputs
+  #[allow(clippy::type_complexity)]
+  pub fn verify(
+    &self,
+    manifest_digest: &E1::Scalar,
+    recursive_snark: &RecursiveSNARK<E1>,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+  ) -> Result<(&Manifest, Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), SuperNovaError> {
+    let approved = self.find(manifest_digest).ok_or(SuperNovaError::UnapprovedManifest)?;
+    if recursive_snark.pp_digest() != approved.pp.digest() {
+      return Err(SuperNovaError::ParamsDigestMismatch);
+    }
+    let (zi_primary, zi_secondary) =
+      recursive_snark.verify(&approved.pp, z0_primary, z0_secondary)?;
+    Ok((&approved.manifest, zi_primary, zi_secondary))
+  }
+
+  fn find(&self, manifest_digest: &E1::Scalar) -> Option<&ApprovedManifest<E1>> {
+    self.manifests.iter().find(|m| m.manifest_digest == *manifest_digest)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+
+  use super::*;
+  use crate::{circuits::claim::Claim, provider::Bn256EngineIPA, supernova::TrivialCircuit};
+
+  type E = Bn256EngineIPA;
+  type F = <E as Engine>::Scalar;
+  type F2 = <Dual<E> as Engine>::Scalar;
+
+  fn prove(manifest: &Manifest, input: &[u8]) -> (Arc<PublicParams<E>>, RecursiveSNARK<E>, Vec<F>) {
+    let task = manifest.task::<F>(input).unwrap();
+    let pp = Arc::new(manifest.public_params::<E>(&task).unwrap());
+    let secondary = TrivialCircuit::default();
+    let step = &task.steps[0];
+    let mut snark =
+      RecursiveSNARK::new(&pp, step, step, &secondary, &task.z0, &[F2::ZERO]).unwrap();
+    snark.prove_step(&pp, step, &secondary).unwrap();
+    (pp, snark, task.z0)
+  }
+
+  #[test]
+  fn test_manifest_registry() {
+    let adult = Manifest::Claim { claim: Claim::AtLeast { threshold: 18, scale: 0 }, max_len: 3 };
+    let senior =
+      Manifest::Claim { claim: Claim::AtLeast { threshold: 65, scale: 0 }, max_len: 3 };
+    let (adult_pp, adult_snark, adult_z0) = prove(&adult, b"70");
+    let (_, senior_snark, senior_z0) = prove(&senior, b"70");
+
+    let mut registry = ManifestRegistry::new();
+    let adult_digest = registry.approve(adult.clone(), adult_pp.clone()).unwrap();
+    assert_eq!(adult_digest, adult.digest::<F>());
+    assert_eq!(registry.manifests().collect::<Vec<_>>(), [(
+      &adult_digest,
+      &adult_pp.circuits_digest()
+    )]);
+    assert_eq!(registry.approve(adult.clone(), adult_pp), Err(SuperNovaError::ManifestExists));
+
+    // a proof is accepted for its approved manifest only
+    let (manifest, ..) =
+      registry.verify(&adult_digest, &adult_snark, &adult_z0, &[F2::ZERO]).unwrap();
+    assert_eq!(manifest, &adult);
+    assert_eq!(
+      registry.verify(&senior.digest(), &senior_snark, &senior_z0, &[F2::ZERO]).map(|_| ()),
+      Err(SuperNovaError::UnapprovedManifest)
+    );
+    assert_eq!(
+      registry.verify(&adult_digest, &senior_snark, &senior_z0, &[F2::ZERO]).map(|_| ()),
+      Err(SuperNovaError::ParamsDigestMismatch)
+    );
+
+    // nor once it is revoked
+    assert_eq!(registry.revoke(&adult_digest).map(|(manifest, _)| manifest), Some(adult));
+    assert!(registry.get(&adult_digest).is_none());
+    assert_eq!(
+      registry.verify(&adult_digest, &adult_snark, &adult_z0, &[F2::ZERO]).map(|_| ()),
+      Err(SuperNovaError::UnapprovedManifest)
+    );
+  }
+}
@@ -0,0 +1,326 @@
+//! Step circuits accessing a memory, and the circuit that folds their
+//! accesses into the fingerprints carried in `z`.
+use bellpepper::gadgets::Assignment;
+use bellpepper_core::{
+  boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use crate::{
+  lookup::field_to_u64, provider::poseidon::PoseidonConstantsCircuit, supernova::StepCircuit,
+};
+
+/// The number of elements of `z` the memory accumulators take: the
+/// challenges `beta` and `gamma`, the fingerprints of the reads and of the
+/// writes, the time of the last access, and the running hash of the accesses
+pub const MEMORY_ARITY: usize = 6;
+
+/// The number of bits of the time between two accesses to a cell
+pub const TIME_BITS: usize = 32;
+
+/// An access to a cell: its address, the value and the time it was last
+/// written at, and the value written
+struct Access<F: PrimeField> {
+  addr:      AllocatedNum<F>,
+  old_value: AllocatedNum<F>,
+  old_time:  AllocatedNum<F>,
+  value:     AllocatedNum<F>,
+}
+
+/// Where the values and times of the cells read come from
+enum Hints<'a, F> {
+  /// the memory itself, which the native execution updates
+  State { cells: &'a mut [(F, u64)], time: u64, recorded: Vec<(F, u64)> },
+  /// the hints recorded by the native execution
+  Replay(core::slice::Iter<'a, (F, u64)>),
+}
+
+/// The accesses a step makes to the memory, in the order they are made
+pub struct Memory<'a, F: PrimeField> {
+  hints:    Hints<'a, F>,
+  accesses: Vec<Access<F>>,
+}
+
+impl<'a, F: PrimeField> Memory<'a, F> {
+  pub(crate) fn tracing(cells: &'a mut [(F, u64)], time: u64) -> Self {
+    Self { hints: Hints::State { cells, time, recorded: Vec::new() }, accesses: Vec::new() }
+  }
+
+  pub(crate) fn replaying(hints: &'a [(F, u64)]) -> Self {
+    Self { hints: Hints::Replay(hints.iter()), accesses: Vec::new() }
+  }
+
+  /// The address, the old value and time, and the value of the accesses,
+  /// if they are known
+  pub(crate) fn access_values(&self) -> Option<Vec<[F; 4]>> {
+    self
+      .accesses
+      .iter()
+      .map(|access| {
+        Some([
+          access.addr.get_value()?,
+          access.old_value.get_value()?,
+          access.old_time.get_value()?,
+          access.value.get_value()?,
+        ])
+      })
+      .collect()
+  }
+
+  /// The values and times of the cells the native execution read
+  pub(crate) fn into_recorded(self) -> Vec<(F, u64)> {
+    match self.hints {
+      Hints::State { recorded, .. } => recorded,
+      Hints::Replay(_) => Vec::new(),
+    }
+  }
+
+  /// The value and time of the cell at `addr` before the access, writing
+  /// `value` to it when the memory is at hand
+  fn hint(
+    &mut self,
+    addr: &AllocatedNum<F>,
+    value: Option<F>,
+  ) -> Result<Option<(F, u64)>, SynthesisError> {
+    match &mut self.hints {
+      Hints::State { cells, time, recorded } => {
+        let addr = field_to_u64(addr.get_value().get()?).ok_or(SynthesisError::Unsatisfiable)?;
+        let cell = cells.get_mut(addr as usize).ok_or(SynthesisError::Unsatisfiable)?;
+        let old = *cell;
+        *time += 1;
+        *cell = (*value.get()?, *time);
+        recorded.push(old);
+        Ok(Some(old))
+      },
+      Hints::Replay(hints) => Ok(hints.next().copied()),
+    }
+  }
+
+  /// Writes `value` to the cell at `addr`, returning the value it held
+  pub fn write<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    addr: &AllocatedNum<F>,
+    value: &AllocatedNum<F>,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    let old = self.hint(addr, value.get_value())?;
+    let old_value = AllocatedNum::alloc(cs.namespace(|| "old value"), || Ok(old.get()?.0))?;
+    let old_time = AllocatedNum::alloc(cs.namespace(|| "old time"), || Ok(F::from(old.get()?.1)))?;
+    self.accesses.push(Access {
+      addr: addr.clone(),
+      old_value: old_value.clone(),
+      old_time,
+      value: value.clone(),
+    });
+    Ok(old_value)
+  }
+
+  /// Reads the cell at `addr`
+  pub fn read<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    addr: &AllocatedNum<F>,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    // a read writes back the value it reads
+    let old = match &self.hints {
+      Hints::State { cells, .. } => addr
+        .get_value()
+        .and_then(|addr| field_to_u64(&addr))
+        .and_then(|addr| cells.get(addr as usize))
+        .map(|cell| cell.0),
+      Hints::Replay(hints) => hints.clone().next().map(|hint| hint.0),
+    };
+    let value = AllocatedNum::alloc(cs.namespace(|| "value"), || {
+      old.ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    let old_value = self.write(cs.namespace(|| "write back"), addr, &value)?;
+    cs.enforce(
+      || "the value read is written back",
+      |lc| lc + value.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + old_value.get_variable(),
+    );
+    Ok(value)
+  }
+
+  /// Folds the accesses into the fingerprints `reads` and `writes` for the
+  /// challenges `beta` and `gamma`, the accesses taking the times after
+  /// `time`, and into the running hash `hash`. Returns the next fingerprints,
+  /// time and hash.
+  fn accumulate<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    beta: &AllocatedNum<F>,
+    gamma: &AllocatedNum<F>,
+    reads: &AllocatedNum<F>,
+    writes: &AllocatedNum<F>,
+    time: &AllocatedNum<F>,
+    hash: &AllocatedNum<F>,
+  ) -> Result<[AllocatedNum<F>; 4], SynthesisError> {
+    let beta_sq = beta.square(cs.namespace(|| "beta^2"))?;
+    let (mut reads, mut writes) = (reads.clone(), writes.clone());
+    for (i, access) in self.accesses.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("access {i}"));
+      let t = F::from(i as u64 + 1);
+
+      // the cell was last written before the access
+      let gap = time
+        .get_value()
+        .zip(access.old_time.get_value())
+        .map(|(time, old)| time + t - old - F::ONE);
+      let gap_bits = gap.map(|gap| gap.to_repr());
+      let mut gap_lc = LinearCombination::zero();
+      for j in 0..TIME_BITS {
+        let bit = AllocatedBit::alloc(
+          cs.namespace(|| format!("gap bit {j}")),
+          gap_bits.as_ref().map(|repr| (repr.as_ref()[j / 8] >> (j % 8)) & 1 == 1),
+        )?;
+        gap_lc = gap_lc + (F::from(1 << j), bit.get_variable());
+      }
+      cs.enforce(
+        || "gap",
+        |_| gap_lc,
+        |lc| lc + CS::one(),
+        |lc| lc + time.get_variable() + (t, CS::one()) - access.old_time.get_variable() - CS::one(),
+      );
+
+      let addr = &access.addr;
+      let read = fingerprint(
+        cs.namespace(|| "read"),
+        [beta, &beta_sq, gamma, addr, &access.old_value],
+        LinearCombination::zero() + access.old_time.get_variable(),
+        access.old_time.get_value(),
+      )?;
+      let write = fingerprint(
+        cs.namespace(|| "write"),
+        [beta, &beta_sq, gamma, addr, &access.value],
+        LinearCombination::zero() + time.get_variable() + (t, CS::one()),
+        time.get_value().map(|time| time + t),
+      )?;
+      reads = reads.mul(cs.namespace(|| "reads"), &read)?;
+      writes = writes.mul(cs.namespace(|| "writes"), &write)?;
+    }
+
+    let num_accesses = F::from(self.accesses.len() as u64);
+    let time_next =
+      AllocatedNum::alloc(cs.namespace(|| "time"), || Ok(*time.get_value().get()? + num_accesses))?;
+    cs.enforce(
+      || "time",
+      |lc| lc + time.get_variable() + (num_accesses, CS::one()),
+      |lc| lc + CS::one(),
+      |lc| lc + time_next.get_variable(),
+    );
+
+    let hash_next = if self.accesses.is_empty() {
+      hash.clone()
+    } else {
+      let elements = core::iter::once(hash.clone())
+        .chain(self.accesses.iter().flat_map(|access| {
+          [&access.addr, &access.old_value, &access.old_time, &access.value].map(Clone::clone)
+        }))
+        .collect::<Vec<_>>();
+      PoseidonConstantsCircuit::<F>::default().hash_circuit(cs.namespace(|| "hash"), &elements)?
+    };
+    Ok([reads, writes, time_next, hash_next])
+  }
+}
+
+/// Allocates `gamma - (addr + beta value + beta^2 time)`, the fingerprint of
+/// an access of `value` to `addr` at `time`
+fn fingerprint<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  [beta, beta_sq, gamma, addr, value]: [&AllocatedNum<F>; 5],
+  time: LinearCombination<F>,
+  time_value: Option<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let beta_value = value.mul(cs.namespace(|| "beta value"), beta)?;
+  let f = AllocatedNum::alloc(cs.namespace(|| "fingerprint"), || {
+    Ok(
+      *gamma.get_value().get()?
+        - addr.get_value().get()?
+        - beta_value.get_value().get()?
+        - *beta_sq.get_value().get()? * time_value.get()?,
+    )
+  })?;
+  cs.enforce(
+    || "beta^2 time = gamma - addr - beta value - fingerprint",
+    |lc| lc + beta_sq.get_variable(),
+    |_| time,
+    |lc| {
+      lc + gamma.get_variable() - addr.get_variable() - beta_value.get_variable() - f.get_variable()
+    },
+  );
+  Ok(f)
+}
+
+/// A step of an incremental computation accessing a memory -- to be
+/// implemented by applications, and run as a `StepCircuit` by wrapping it
+/// in a [`MemoryStep`]
+pub trait MemoryCircuit<F: PrimeField>: Send + Sync + Clone {
+  /// The number of inputs or outputs of each step, besides the memory
+  /// accumulators
+  fn arity(&self) -> usize;
+
+  /// This circuit's assigned index, for use when enforcing the program
+  /// counter
+  fn circuit_index(&self) -> usize;
+
+  /// Synthesize the circuit for a computation step, accessing the memory
+  /// through `memory`, and return the output `pc_{i+1}` and `z_{i+1}`
+  #[allow(clippy::type_complexity)]
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+    memory: &mut Memory<'_, F>,
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError>;
+}
+
+/// The `StepCircuit` of a step of a [`MemoryCircuit`], whose `z` is the `z`
+/// of the circuit followed by the [`MEMORY_ARITY`] memory accumulators,
+/// along with the values and times of the cells the step reads, recorded by
+/// the native execution
+#[derive(Clone, Debug)]
+pub struct MemoryStep<F, C> {
+  circuit: C,
+  hints:   Vec<(F, u64)>,
+}
+
+impl<F: PrimeField, C: MemoryCircuit<F>> MemoryStep<F, C> {
+  /// Wraps a step of `circuit`, with the hints `MemoryTrace::step` recorded
+  /// for it, or none to synthesize the shape of the circuit
+  pub fn new(circuit: C, hints: Vec<(F, u64)>) -> Self { Self { circuit, hints } }
+
+  /// The wrapped circuit
+  pub fn circuit(&self) -> &C { &self.circuit }
+}
+
+impl<F: PrimeField, C: MemoryCircuit<F>> StepCircuit<F> for MemoryStep<F, C> {
+  fn arity(&self) -> usize { self.circuit.arity() + MEMORY_ARITY }
+
+  fn circuit_index(&self) -> usize { self.circuit.circuit_index() }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    if z.len() != self.arity() {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let (z, accumulators) = z.split_at(self.circuit.arity());
+    let [beta, gamma, reads, writes, time, hash] = accumulators else {
+      unreachable!("z has the arity of the step")
+    };
+
+    let mut memory = Memory::replaying(&self.hints);
+    let (pc_next, mut z_next) =
+      self.circuit.synthesize(&mut cs.namespace(|| "step"), pc, z, &mut memory)?;
+    let accumulators_next =
+      memory.accumulate(cs.namespace(|| "accumulate"), beta, gamma, reads, writes, time, hash)?;
+    z_next.extend([beta.clone(), gamma.clone()].into_iter().chain(accumulators_next));
+    Ok((pc_next, z_next))
+  }
+}
@@ -0,0 +1,267 @@
+//! An offline memory-checking argument, for step circuits to model a RAM
+//! across folding steps without hashing it at every step.
+//!
+//! Every access to a cell of a [`Memory`], a read or a write, reads the value
+//! and the time the cell was last written at, which the native execution
+//! provides as hints, and writes a value at the current time, later than
+//! the time read. Following Blum et al., the memory is consistent if the
+//! multiset of the reads, along with the final contents of the cells, equals
+//! the multiset of the writes, along with their initial contents. A
+//! [`MemoryStep`] folds fingerprints of these multisets into grand products
+//! in `z`,
+//!
+//! `prod (gamma - (addr + beta value + beta^2 time))`,
+//!
+//! along with a running hash of the accesses. As for lookups, a
+//! [`MemoryTrace`] runs the computation natively first, and derives `beta`
+//! and `gamma` from the hash of the accesses and the final contents of the
+//! cells, the [`MemoryClaim`] of the computation. The verifier of the
+//! computation checks its final `z` against this claim and the initial
+//! contents of the memory with [`MemoryClaim::verify`], in time linear in the
+//! size of the memory.
+use bellpepper::util_cs::witness_cs::WitnessCS;
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem};
+use ff::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::NovaError, provider::poseidon::PoseidonConstantsCircuit};
+
+mod circuit;
+
+pub use circuit::{Memory, MemoryCircuit, MemoryStep, MEMORY_ARITY, TIME_BITS};
+
+/// The native execution of a computation accessing a memory, which records
+/// the hints of its steps and derives the challenges of its accumulators
+pub struct MemoryTrace<F: PrimeField> {
+  cells:     Vec<(F, u64)>,
+  time:      u64,
+  hash:      F,
+  constants: PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> MemoryTrace<F> {
+  /// Starts the trace of a computation on a memory with contents `init`
+  pub fn new(init: &[F]) -> Self {
+    Self {
+      cells:     init.iter().map(|v| (*v, 0)).collect(),
+      time:      0,
+      hash:      F::ZERO,
+      constants: PoseidonConstantsCircuit::default(),
+    }
+  }
+
+  /// Executes a step of `circuit` on `pc` and `z`, without the memory
+  /// accumulators, and returns the next `pc` and `z`, along with the hints
+  /// to build the `MemoryStep` of the step with
+  #[allow(clippy::type_complexity)]
+  pub fn step<C: MemoryCircuit<F>>(
+    &mut self,
+    circuit: &C,
+    pc: Option<F>,
+    z: &[F],
+  ) -> Result<(Option<F>, Vec<F>, Vec<(F, u64)>), NovaError> {
+    if z.len() != circuit.arity() {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+    let mut cs = WitnessCS::<F>::new();
+    let pc = pc.map(|pc| AllocatedNum::alloc_infallible(cs.namespace(|| "pc"), || pc));
+    let z = z
+      .iter()
+      .enumerate()
+      .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+      .collect::<Vec<_>>();
+
+    let mut memory = Memory::tracing(&mut self.cells, self.time);
+    let (pc_next, z_next) = circuit
+      .synthesize(&mut cs, pc.as_ref(), &z, &mut memory)
+      .map_err(|e| NovaError::SynthesisError(e.to_string()))?;
+    if z_next.len() != circuit.arity() {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+
+    let accesses = memory.access_values().ok_or(NovaError::IncorrectWitness)?;
+    let hints = memory.into_recorded();
+    if !accesses.is_empty() {
+      let elements =
+        core::iter::once(self.hash).chain(accesses.iter().flatten().copied()).collect::<Vec<_>>();
+      self.hash = self.constants.hash(&elements);
+    }
+    self.time += accesses.len() as u64;
+
+    let pc_next =
+      pc_next.map(|pc| pc.get_value().ok_or(NovaError::IncorrectWitness)).transpose()?;
+    let z_next = z_next
+      .iter()
+      .map(|z_i| z_i.get_value().ok_or(NovaError::IncorrectWitness))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok((pc_next, z_next, hints))
+  }
+
+  /// Finishes the trace into the claim about the memory of the computation
+  pub fn finish(self) -> MemoryClaim<F> {
+    let (beta, gamma) = MemoryClaim::challenges(&self.constants, self.hash, &self.cells);
+    MemoryClaim { beta, gamma, cells: self.cells }
+  }
+}
+
+/// The final contents of the memory of a computation, with the times they
+/// were written at, and the challenges of its memory accumulators
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryClaim<F: PrimeField> {
+  beta:  F,
+  gamma: F,
+  cells: Vec<(F, u64)>,
+}
+
+impl<F: PrimeField> MemoryClaim<F> {
+  /// The challenges for the accesses of running hash `hash`, with the final
+  /// contents `cells`
+  fn challenges(constants: &PoseidonConstantsCircuit<F>, hash: F, cells: &[(F, u64)]) -> (F, F) {
+    let elements = core::iter::once(hash)
+      .chain(cells.iter().flat_map(|(v, t)| [*v, F::from(*t)]))
+      .collect::<Vec<_>>();
+    let beta = constants.hash(&elements);
+    (beta, constants.hash(&[beta]))
+  }
+
+  /// The final contents of the memory
+  pub fn cells(&self) -> impl Iterator<Item = &F> { self.cells.iter().map(|(v, _)| v) }
+
+  /// The initial memory accumulators, to append to the initial `z` of the
+  /// computation
+  pub fn initial_accumulators(&self) -> [F; MEMORY_ARITY] {
+    [self.beta, self.gamma, F::ONE, F::ONE, F::ZERO, F::ZERO]
+  }
+
+  /// Checks that the accesses of the computation from `z0` to `z_final`,
+  /// both ending with the memory accumulators, are consistent with a memory
+  /// of initial contents `init`
+  pub fn verify(&self, init: &[F], z0: &[F], z_final: &[F]) -> Result<(), NovaError> {
+    if z0.len() < MEMORY_ARITY || z_final.len() != z0.len() || init.len() != self.cells.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let [beta, gamma, reads, writes, _, hash] = z_final[z_final.len() - MEMORY_ARITY..] else {
+      unreachable!("z ends with the accumulators")
+    };
+    if z0[z0.len() - MEMORY_ARITY..] != self.initial_accumulators()
+      || (beta, gamma) != (self.beta, self.gamma)
+      || Self::challenges(&PoseidonConstantsCircuit::default(), hash, &self.cells)
+        != (self.beta, self.gamma)
+    {
+      return Err(NovaError::InvalidMemoryAccesses);
+    }
+
+    // the initial contents are written at time 0, and the final ones are read
+    let fingerprint = |addr: usize, value: F, time: u64| {
+      self.gamma - (F::from(addr as u64) + self.beta * value + self.beta.square() * F::from(time))
+    };
+    let init_writes = init.iter().enumerate().map(|(a, v)| fingerprint(a, *v, 0)).product::<F>();
+    let final_reads =
+      self.cells.iter().enumerate().map(|(a, (v, t))| fingerprint(a, *v, *t)).product::<F>();
+    if writes * init_writes != reads * final_reads {
+      return Err(NovaError::InvalidMemoryAccesses);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bellpepper_core::{test_cs::TestConstraintSystem, SynthesisError};
+
+  use super::*;
+  use crate::{provider::Bn256EngineIPA, supernova::StepCircuit, traits::Engine};
+
+  type F = <Bn256EngineIPA as Engine>::Scalar;
+
+  /// A step of a parser counting the occurrences of the bytes of its input
+  /// in memory: `z -> z + count(byte)`, incrementing `count(byte)`
+  #[derive(Clone)]
+  struct Histogram {
+    byte: u64,
+  }
+
+  impl MemoryCircuit<F> for Histogram {
+    fn arity(&self) -> usize { 1 }
+
+    fn circuit_index(&self) -> usize { 0 }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      pc: Option<&AllocatedNum<F>>,
+      z: &[AllocatedNum<F>],
+      memory: &mut Memory<'_, F>,
+    ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+      let addr = AllocatedNum::alloc(cs.namespace(|| "byte"), || Ok(F::from(self.byte)))?;
+      let count = memory.read(cs.namespace(|| "read"), &addr)?;
+      let next = AllocatedNum::alloc(cs.namespace(|| "next"), || {
+        count.get_value().map(|count| count + F::ONE).ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      cs.enforce(
+        || "next = count + 1",
+        |lc| lc + count.get_variable() + CS::one(),
+        |lc| lc + CS::one(),
+        |lc| lc + next.get_variable(),
+      );
+      memory.write(cs.namespace(|| "write"), &addr, &next)?;
+      let z_next = z[0].add(cs.namespace(|| "z + count"), &count)?;
+      Ok((pc.cloned(), vec![z_next]))
+    }
+  }
+
+  #[test]
+  fn test_memory_step() {
+    let steps = [3, 1, 3, 3, 0].map(|byte| Histogram { byte });
+    let init = vec![F::ZERO; 4];
+
+    let mut trace = MemoryTrace::new(&init);
+    let mut z = vec![F::ZERO];
+    let mut hints = Vec::new();
+    for step in &steps {
+      let (_, z_next, step_hints) = trace.step(step, None, &z).unwrap();
+      z = z_next;
+      hints.push(step_hints);
+    }
+    // 0 + 0 + 1 + 2 + 0
+    assert_eq!(z, vec![F::from(3)]);
+    let claim = trace.finish();
+    assert_eq!(claim.cells().copied().collect::<Vec<_>>(), [1, 1, 0, 3].map(F::from));
+
+    // the steps are satisfied with the accumulators appended to `z`
+    let z0 = core::iter::once(F::ZERO).chain(claim.initial_accumulators()).collect::<Vec<_>>();
+    let run = |hints: &[Vec<(F, u64)>]| {
+      let mut z = z0.clone();
+      for (step, hints) in steps.iter().zip(hints) {
+        let step = MemoryStep::new(step.clone(), hints.clone());
+        let mut cs = TestConstraintSystem::<F>::new();
+        let z_alloc = z
+          .iter()
+          .enumerate()
+          .map(|(i, z_i)| {
+            AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i)
+          })
+          .collect::<Vec<_>>();
+        let (_, z_next) = step.synthesize(&mut cs, None, &z_alloc).unwrap();
+        assert!(cs.is_satisfied());
+        z = z_next.iter().map(|z_i| z_i.get_value().unwrap()).collect();
+      }
+      z
+    };
+    let z = run(&hints);
+    assert_eq!(z[0], F::from(3));
+    assert_eq!(claim.verify(&init, &z0, &z), Ok(()));
+
+    // the memory must start with its initial contents
+    assert_eq!(
+      claim.verify(&[F::ONE, F::ZERO, F::ZERO, F::ZERO], &z0, &z),
+      Err(NovaError::InvalidMemoryAccesses)
+    );
+
+    // and reading a stale value breaks the fingerprints
+    let mut stale_hints = hints.clone();
+    stale_hints[3][0] = hints[2][0];
+    let z = run(&stale_hints);
+    assert_eq!(claim.verify(&init, &z0, &z), Err(NovaError::InvalidMemoryAccesses));
+  }
+}
@@ -0,0 +1,269 @@
+//! Metrics of a prover or verifier deployment, as Prometheus-style counters
+//! and histograms.
+//!
+//! With the `metrics` feature, the crate reports, to the [`Recorder`]
+//! installed with `set_recorder`:
+//! - [`PROOFS_TOTAL`], the compressed proofs produced,
+//! - [`PROOF_SIZE_BYTES`], their sizes serialized with `bincode`,
+//! - [`VERIFICATION_FAILURES_TOTAL`], the compressed proofs that failed to verify, by the kind of
+//!   their error, e.g. `ProofVerifyError`,
+//! - [`PHASE_DURATION_SECONDS`], the durations of the proving phases of [`crate::profiling`], e.g.
+//!   a folding step or a compression.
+//!
+//! The proofs are labeled by their `scheme`, `nova` or `supernova`. A
+//! deployment brings its own recorder, e.g. to forward the metrics to the
+//! client library it already runs, or installs a [`PrometheusRecorder`] and
+//! serves [`PrometheusRecorder::render`] to be scraped.
+//!
+//! Without the feature, or until a recorder is installed, nothing is
+//! recorded.
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  sync::{Mutex, PoisonError},
+};
+
+/// The counter of the compressed proofs produced
+pub const PROOFS_TOTAL: &str = "edge_prover_proofs_total";
+
+/// The histogram of the sizes of the compressed proofs produced, in bytes
+pub const PROOF_SIZE_BYTES: &str = "edge_prover_proof_size_bytes";
+
+/// The counter of the compressed proofs that failed to verify
+pub const VERIFICATION_FAILURES_TOTAL: &str = "edge_prover_verification_failures_total";
+
+/// The histogram of the durations of the proving phases, in seconds
+pub const PHASE_DURATION_SECONDS: &str = "edge_prover_phase_duration_seconds";
+
+/// The labels of a metric, as pairs of a name and a value
+pub type Labels<'a> = &'a [(&'static str, &'a str)];
+
+/// The sink of the metrics of the crate
+pub trait Recorder: Send + Sync {
+  /// Increments the counter `name` of `labels`
+  fn increment_counter(&self, name: &'static str, labels: Labels<'_>);
+
+  /// Records `value` in the histogram `name` of `labels`
+  fn record_histogram(&self, name: &'static str, labels: Labels<'_>, value: f64);
+}
+
+/// A series of a metric: its name and the labels of the series, rendered
+type Series = (&'static str, String);
+
+/// A recorder aggregating the metrics in memory, rendered in the text format
+/// of Prometheus
+///
+/// Histograms are rendered as summaries, of their count and their sum.
+#[derive(Debug, Default)]
+pub struct PrometheusRecorder {
+  counters:   Mutex<BTreeMap<Series, u64>>,
+  histograms: Mutex<BTreeMap<Series, (u64, f64)>>,
+}
+
+/// The labels `labels` in the syntax of Prometheus, e.g. `{scheme="nova"}`
+fn render_labels(labels: Labels<'_>) -> String {
+  if labels.is_empty() {
+    return String::new();
+  }
+  let labels = labels
+    .iter()
+    .map(|(name, value)| {
+      format!(
+        "{name}=\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+      )
+    })
+    .collect::<Vec<_>>();
+  format!("{{{}}}", labels.join(","))
+}
+
+impl PrometheusRecorder {
+  /// The value of the counter `name` of `labels`
+  pub fn counter(&self, name: &'static str, labels: Labels<'_>) -> u64 {
+    let counters = self.counters.lock().unwrap_or_else(PoisonError::into_inner);
+    counters.get(&(name, render_labels(labels))).copied().unwrap_or(0)
+  }
+
+  /// The number of values, and their sum, of the histogram `name` of
+  /// `labels`
+  pub fn histogram(&self, name: &'static str, labels: Labels<'_>) -> (u64, f64) {
+    let histograms = self.histograms.lock().unwrap_or_else(PoisonError::into_inner);
+    histograms.get(&(name, render_labels(labels))).copied().unwrap_or((0, 0.0))
+  }
+
+  /// The metrics recorded so far, in the text format of Prometheus
+  pub fn render(&self) -> String {
+    let mut out = String::new();
+    let counters = self.counters.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut last = "";
+    for ((name, labels), value) in counters.iter() {
+      if *name != last {
+        let _ = writeln!(out, "# TYPE {name} counter");
+        last = name;
+      }
+      let _ = writeln!(out, "{name}{labels} {value}");
+    }
+    let histograms = self.histograms.lock().unwrap_or_else(PoisonError::into_inner);
+    for ((name, labels), (count, sum)) in histograms.iter() {
+      if *name != last {
+        let _ = writeln!(out, "# TYPE {name} summary");
+        last = name;
+      }
+      let _ = writeln!(out, "{name}_count{labels} {count}");
+      let _ = writeln!(out, "{name}_sum{labels} {sum}");
+    }
+    out
+  }
+}
+
+impl Recorder for PrometheusRecorder {
+  fn increment_counter(&self, name: &'static str, labels: Labels<'_>) {
+    let mut counters = self.counters.lock().unwrap_or_else(PoisonError::into_inner);
+    *counters.entry((name, render_labels(labels))).or_default() += 1;
+  }
+
+  fn record_histogram(&self, name: &'static str, labels: Labels<'_>, value: f64) {
+    let mut histograms = self.histograms.lock().unwrap_or_else(PoisonError::into_inner);
+    let (count, sum) = histograms.entry((name, render_labels(labels))).or_default();
+    *count += 1;
+    *sum += value;
+  }
+}
+
+/// The kind of an error, from its `Debug` form: the innermost variant of
+/// nested errors, e.g. `InvalidPCS` for `NovaError(PCSError(InvalidPCS))`
+#[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+fn error_kind(debug: &str) -> &str {
+  let mut kind = "";
+  for segment in debug.split('(') {
+    let len = segment.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(segment.len());
+    if len == 0 {
+      break;
+    }
+    kind = &segment[..len];
+    if len < segment.len() {
+      break;
+    }
+  }
+  kind
+}
+
+#[cfg(feature = "metrics")]
+mod recording {
+  use std::{fmt::Debug, sync::Arc};
+
+  use once_cell::sync::OnceCell;
+  use serde::Serialize;
+
+  use super::*;
+  use crate::traits::snark::serialized_size;
+
+  static RECORDER: OnceCell<Arc<dyn Recorder>> = OnceCell::new();
+
+  /// Installs `recorder` as the recorder of the crate, which fails, and
+  /// returns it, if one is already installed
+  pub fn set_recorder(recorder: Arc<dyn Recorder>) -> Result<(), Arc<dyn Recorder>> {
+    RECORDER.set(recorder)
+  }
+
+  pub(crate) fn record_proof<T: Serialize>(scheme: &'static str, proof: &T) {
+    if let Some(recorder) = RECORDER.get() {
+      let labels = [("scheme", scheme)];
+      recorder.increment_counter(PROOFS_TOTAL, &labels);
+      recorder.record_histogram(PROOF_SIZE_BYTES, &labels, serialized_size(proof) as f64);
+    }
+  }
+
+  pub(crate) fn record_verification<T, E: Debug>(scheme: &'static str, result: &Result<T, E>) {
+    if let (Some(recorder), Err(err)) = (RECORDER.get(), result) {
+      let debug = format!("{err:?}");
+      recorder.increment_counter(VERIFICATION_FAILURES_TOTAL, &[
+        ("scheme", scheme),
+        ("error", error_kind(&debug)),
+      ]);
+    }
+  }
+
+  /// The timer of a proving phase, which records its duration when dropped
+  #[derive(Debug)]
+  pub(crate) struct PhaseTimer {
+    name:  &'static str,
+    start: Option<std::time::Instant>,
+  }
+
+  impl PhaseTimer {
+    pub(crate) fn start(name: &'static str) -> Self {
+      // `Instant` is not available on the web
+      let start =
+        (!cfg!(target_arch = "wasm32") && RECORDER.get().is_some()).then(std::time::Instant::now);
+      Self { name, start }
+    }
+  }
+
+  impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+      if let (Some(recorder), Some(start)) = (RECORDER.get(), self.start) {
+        let seconds = start.elapsed().as_secs_f64();
+        recorder.record_histogram(PHASE_DURATION_SECONDS, &[("phase", self.name)], seconds);
+      }
+    }
+  }
+}
+
+#[cfg(feature = "metrics")] pub use recording::set_recorder;
+#[cfg(feature = "metrics")]
+pub(crate) use recording::{record_proof, record_verification, PhaseTimer};
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_proof<T: serde::Serialize>(_scheme: &'static str, _proof: &T) {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_verification<T, E>(_scheme: &'static str, _result: &Result<T, E>) {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_prometheus_recorder() {
+    let recorder = PrometheusRecorder::default();
+    recorder.increment_counter(PROOFS_TOTAL, &[("scheme", "nova")]);
+    recorder.increment_counter(PROOFS_TOTAL, &[("scheme", "nova")]);
+    recorder.increment_counter(PROOFS_TOTAL, &[("scheme", "supernova")]);
+    recorder.record_histogram(PROOF_SIZE_BYTES, &[("scheme", "nova")], 1000.0);
+    recorder.record_histogram(PROOF_SIZE_BYTES, &[("scheme", "nova")], 500.0);
+    recorder.increment_counter(VERIFICATION_FAILURES_TOTAL, &[("error", "a \"quoted\" kind")]);
+
+    assert_eq!(recorder.counter(PROOFS_TOTAL, &[("scheme", "nova")]), 2);
+    assert_eq!(recorder.counter(PROOFS_TOTAL, &[("scheme", "plonk")]), 0);
+    assert_eq!(recorder.histogram(PROOF_SIZE_BYTES, &[("scheme", "nova")]), (2, 1500.0));
+    assert_eq!(
+      recorder.render(),
+      "# TYPE edge_prover_proofs_total counter\nedge_prover_proofs_total{scheme=\"nova\"} \
+       2\nedge_prover_proofs_total{scheme=\"supernova\"} 1\n# TYPE \
+       edge_prover_verification_failures_total \
+       counter\nedge_prover_verification_failures_total{error=\"a \\\"quoted\\\" kind\"} 1\n# \
+       TYPE edge_prover_proof_size_bytes \
+       summary\nedge_prover_proof_size_bytes_count{scheme=\"nova\"} \
+       2\nedge_prover_proof_size_bytes_sum{scheme=\"nova\"} 1500\n"
+    );
+  }
+
+  #[test]
+  fn test_error_kind() {
+    use crate::{
+      errors::{NovaError, PCSError},
+      supernova::error::SuperNovaError,
+    };
+
+    let kind = |err: &dyn std::fmt::Debug| error_kind(&format!("{err:?}")).to_string();
+    assert_eq!(kind(&NovaError::ProofVerifyError), "ProofVerifyError");
+    assert_eq!(kind(&NovaError::SerializationError("(bad)".into())), "SerializationError");
+    assert_eq!(
+      kind(&SuperNovaError::NovaError(NovaError::InvalidSumcheckProof)),
+      "InvalidSumcheckProof"
+    );
+    assert_eq!(kind(&NovaError::PCSError(PCSError::InvalidPCS)), "InvalidPCS");
+    assert_eq!(kind(&SuperNovaError::UnSatIndex("r_primary", 3)), "UnSatIndex");
+  }
+}
@@ -0,0 +1,154 @@
+//! Upgrading serialized proofs and keys of older releases to the current
+//! format.
+//!
+//! The format of the serialized objects of the crate is versioned:
+//! - version 0 encodes commitments in the compressed encoding of their curve library,
+//!   `GroupEncoding::to_bytes`,
+//! - version 1, [`FORMAT_VERSION`], encodes them in the SEC1 compressed form of
+//!   `DlogGroup::to_compressed_bytes`.
+//!
+//! [`upgrade_proof`] decodes the bytes of an object of a version and encodes
+//! it in the current one, re-encoding its points. An upgraded proof verifies
+//! if it did in its release, but for a proof of a transcript that is not
+//! versioned, which does not verify with the current transcript whatever its
+//! encoding, see `provider::legacy`.
+use std::cell::Cell;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::NovaError;
+
+/// The current format version
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The format versions of the releases of the crate, oldest first
+pub const RELEASED_VERSIONS: [u32; 2] = [0, 1];
+
+/// The first format version encoding commitments in SEC1 form
+pub(crate) const SEC1_COMMITMENTS: u32 = 1;
+
+thread_local! {
+  static DECODING_VERSION: Cell<u32> = const { Cell::new(FORMAT_VERSION) };
+}
+
+/// The format version of the bytes being decoded on this thread
+pub(crate) fn decoding_version() -> u32 { DECODING_VERSION.with(Cell::get) }
+
+/// Runs `f`, decoding bytes of the format version `version`
+pub(crate) fn with_decoding_version<T>(version: u32, f: impl FnOnce() -> T) -> T {
+  // restores the version when `f` returns or panics
+  struct Restore(u32);
+  impl Drop for Restore {
+    fn drop(&mut self) { DECODING_VERSION.with(|v| v.set(self.0)) }
+  }
+  let _restore = Restore(DECODING_VERSION.with(|v| v.replace(version)));
+  f()
+}
+
+/// Upgrades `bytes`, a proof or a key of type `T` serialized with `bincode`
+/// in the format version `from_version`, to the current format
+///
+/// Fails if `from_version` is not one of [`RELEASED_VERSIONS`], or if the
+/// bytes are not those of a `T` in that version.
+pub fn upgrade_proof<T: Serialize + DeserializeOwned>(
+  bytes: &[u8],
+  from_version: u32,
+) -> Result<Vec<u8>, NovaError> {
+  if !RELEASED_VERSIONS.contains(&from_version) {
+    return Err(NovaError::UnsupportedFormatVersion(from_version));
+  }
+  let value: T = with_decoding_version(from_version, || bincode::deserialize(bytes))
+    .map_err(|e| NovaError::SerializationError(e.to_string()))?;
+  bincode::serialize(&value).map_err(|e| NovaError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::PrimeField;
+  use group::{Group as _, GroupEncoding};
+  use serde::Deserialize;
+
+  use super::*;
+  use crate::{
+    provider::{traits::DlogGroup, Bn256EngineIPA},
+    traits::Engine,
+    CompressedCommitment,
+  };
+
+  type E = Bn256EngineIPA;
+  type G = <E as Engine>::GE;
+
+  /// A proof of a commitment and a scalar
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  #[serde(bound = "")]
+  struct Proof {
+    comms: Vec<CompressedCommitment<E>>,
+    eval:  <E as Engine>::Scalar,
+  }
+
+  fn proof(points: &[G]) -> Proof {
+    let comms = points
+      .iter()
+      .map(|p| CompressedCommitment::<E>::from_bytes(&p.to_compressed_bytes()).unwrap())
+      .collect();
+    Proof { comms, eval: <E as Engine>::Scalar::from(7) }
+  }
+
+  /// `proof(points)` in the format version 0
+  fn legacy_bytes(points: &[G]) -> Vec<u8> {
+    #[derive(Serialize)]
+    struct LegacyProof {
+      comms: Vec<<G as DlogGroup>::Compressed>,
+      eval:  <E as Engine>::Scalar,
+    }
+    let comms = points.iter().map(|p| p.to_bytes().into()).collect();
+    bincode::serialize(&LegacyProof { comms, eval: proof(points).eval }).unwrap()
+  }
+
+  #[test]
+  fn test_upgrade_fixtures() {
+    let points = [G::generator(), G::identity(), G::generator().double()];
+    let current = bincode::serialize(&proof(&points)).unwrap();
+
+    // the commitment to the generator (1, 2), in the current format
+    let generator = bincode::serialize(&proof(&points[..1])).unwrap();
+    let fixture = [
+      vec![1, 0, 0, 0, 0, 0, 0, 0],
+      vec![33, 0, 0, 0, 0, 0, 0, 0, 2],
+      vec![0; 31],
+      vec![1],
+      proof(&[]).eval.to_repr().to_vec(),
+    ]
+    .concat();
+    assert_eq!(generator, fixture);
+
+    for version in RELEASED_VERSIONS {
+      let bytes = if version < SEC1_COMMITMENTS { legacy_bytes(&points) } else { current.clone() };
+      assert_eq!(upgrade_proof::<Proof>(&bytes, version).unwrap(), current);
+    }
+    assert_eq!(
+      bincode::deserialize::<Proof>(&upgrade_proof::<Proof>(&legacy_bytes(&points), 0).unwrap())
+        .unwrap(),
+      proof(&points)
+    );
+    assert_eq!(decoding_version(), FORMAT_VERSION);
+  }
+
+  #[test]
+  fn test_upgrade_errors() {
+    let points = [G::generator()];
+    assert_eq!(
+      upgrade_proof::<Proof>(&legacy_bytes(&points), FORMAT_VERSION + 1),
+      Err(NovaError::UnsupportedFormatVersion(FORMAT_VERSION + 1))
+    );
+    // truncated bytes do not decode in any version
+    for version in RELEASED_VERSIONS {
+      let bytes = if version < SEC1_COMMITMENTS {
+        legacy_bytes(&points)
+      } else {
+        bincode::serialize(&proof(&points)).unwrap()
+      };
+      assert!(upgrade_proof::<Proof>(&bytes[..bytes.len() - 1], version).is_err());
+    }
+  }
+}
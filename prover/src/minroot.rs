@@ -0,0 +1,177 @@
+//! The MinRoot delay function, as a step circuit.
+//!
+//! MinRoot iterates `(x, y) -> ((x + y)^{1/5}, x)` over a field where fifth
+//! roots are unique. Computing an iteration takes an exponentiation by the
+//! inverse of 5, i.e. hundreds of sequential multiplications, whereas checking
+//! it takes three constraints, `x_next^5 = x + y`, so that a proof of the
+//! iterations shows they were run.
+//!
+//! A step of [`MinRootCircuit`] runs `num_iters_per_step` iterations, each a
+//! non-deterministic input of the step, so that its size scales linearly with
+//! that parameter, from a few constraints to the size of real-world
+//! circuits. It is the circuit of the folding benchmarks, and of the
+//! large-scale regression test of folding.
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+use num_bigint::BigInt;
+
+use crate::{gadgets::native::f_to_nat, supernova::StepCircuit};
+
+/// The exponent of fifth roots in `F`, the inverse of 5 modulo `p - 1`, if 5
+/// does not divide `p - 1`, i.e. if every element has a single fifth root
+fn fifth_root_exponent<F: PrimeField>() -> Option<Vec<u64>> {
+  let exponent = BigInt::from(5).modinv(&f_to_nat(&-F::ONE))?;
+  Some(exponent.to_u64_digits().1)
+}
+
+/// A circuit of arity 2 that runs `num_iters_per_step` iterations of the
+/// MinRoot delay function per step, from `(x, y)` to `(x_n, y_n)`
+#[derive(Clone, Debug)]
+pub struct MinRootCircuit<F> {
+  /// the fifth root `x_next` of each iteration of the step
+  roots: Vec<F>,
+}
+
+impl<F: PrimeField> MinRootCircuit<F> {
+  /// The inputs and the circuits of `num_steps` steps of
+  /// `num_iters_per_step` iterations from `(x_0, y_0)`, or `None` if the
+  /// fifth roots of `F` are not unique
+  pub fn new(
+    x_0: F,
+    y_0: F,
+    num_iters_per_step: usize,
+    num_steps: usize,
+  ) -> Option<(Vec<F>, Vec<Self>)> {
+    let exponent = fifth_root_exponent::<F>()?;
+    let (mut x, mut y) = (x_0, y_0);
+    let circuits = (0..num_steps)
+      .map(|_| {
+        let roots = (0..num_iters_per_step)
+          .map(|_| {
+            let x_next = (x + y).pow_vartime(&exponent);
+            (x, y) = (x_next, x);
+            x_next
+          })
+          .collect();
+        Self { roots }
+      })
+      .collect();
+    Some((vec![x_0, y_0], circuits))
+  }
+
+  /// The number of iterations of a step
+  pub fn num_iters_per_step(&self) -> usize { self.roots.len() }
+
+  /// The outputs of a step on `z`
+  pub fn output(&self, z: &[F]) -> Vec<F> {
+    self.roots.iter().fold(z.to_vec(), |z, x_next| vec![*x_next, z[0]])
+  }
+}
+
+impl<F: PrimeField> StepCircuit<F> for MinRootCircuit<F> {
+  fn arity(&self) -> usize { 2 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let (mut x, mut y) = (z[0].clone(), z[1].clone());
+    for (i, root) in self.roots.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("iteration {i}"));
+      let x_next = AllocatedNum::alloc(cs.namespace(|| "x_next"), || Ok(*root))?;
+
+      let x_next_sq = x_next.square(cs.namespace(|| "x_next_sq"))?;
+      let x_next_quad = x_next_sq.square(cs.namespace(|| "x_next_quad"))?;
+      cs.enforce(
+        || "x_next^5 = x + y",
+        |lc| lc + x_next_quad.get_variable(),
+        |lc| lc + x_next.get_variable(),
+        |lc| lc + x.get_variable() + y.get_variable(),
+      );
+
+      (x, y) = (x_next, x);
+    }
+    Ok((pc.cloned(), vec![x, y]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+
+  use super::*;
+  use crate::{
+    bellpepper::test_shape_cs::TestShapeCS,
+    provider::{ipa_pc, Bn256EngineIPA},
+    spartan::snark::RelaxedR1CSSNARK,
+    supernova::TrivialCircuit,
+    traits::{snark::RelaxedR1CSSNARKTrait, CurveCycleEquipped, Dual, Engine},
+    CompressedSNARK, PublicParams, RecursiveSNARK,
+  };
+
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  /// Folds and compresses `num_steps` steps of `num_iters_per_step`
+  /// iterations, and checks the outputs of the proof against the iterations
+  /// run outside of the circuit
+  fn test_minroot_with<E1: CurveCycleEquipped>(num_iters_per_step: usize, num_steps: usize) {
+    let (z0, circuits) =
+      MinRootCircuit::new(E1::Scalar::from(3), E1::Scalar::from(4), num_iters_per_step, num_steps)
+        .unwrap();
+
+    // the circuit takes three constraints and allocations per iteration
+    let mut cs: TestShapeCS<E1> = TestShapeCS::new();
+    let z = (0..2)
+      .map(|i| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(z0[i])).unwrap())
+      .collect::<Vec<_>>();
+    circuits[0].synthesize(&mut cs, None, &z).unwrap();
+    assert_eq!(cs.num_constraints(), 3 * num_iters_per_step);
+    assert_eq!(cs.num_aux(), 2 + 3 * num_iters_per_step);
+
+    // the iterations are fifth roots
+    let zn = circuits.iter().fold(z0.clone(), |z, circuit| {
+      assert_eq!(circuit.num_iters_per_step(), num_iters_per_step);
+      circuit.roots.iter().fold(z, |z, x_next| {
+        assert_eq!(x_next.pow_vartime([5]), z[0] + z[1]);
+        vec![*x_next, z[0]]
+      })
+    });
+    assert_eq!(zn, circuits.iter().fold(z0.clone(), |z, circuit| circuit.output(&z)));
+
+    let secondary = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuits[0],
+      &secondary,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuits[0], &secondary, &z0, &z0_secondary).unwrap();
+    for circuit in &circuits {
+      recursive_snark.prove_step(&pp, circuit, &secondary).unwrap();
+    }
+    let (zn_primary, _) = recursive_snark.verify(&pp, num_steps, &z0, &z0_secondary).unwrap();
+    assert_eq!(zn_primary, zn);
+
+    let (pk, vk) = CompressedSNARK::<_, S<E1>, S<Dual<E1>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    let (zn_primary, _) = snark.verify(&vk, num_steps, &z0, &z0_secondary).unwrap();
+    assert_eq!(zn_primary, zn);
+  }
+
+  #[test]
+  fn test_minroot() { test_minroot_with::<Bn256EngineIPA>(16, 5); }
+
+  // The large-scale regression test of folding, with steps the size of
+  // real-world circuits:
+  // cargo test --release test_minroot_large -- --ignored
+  #[test]
+  #[ignore]
+  fn test_minroot_large() { test_minroot_with::<Bn256EngineIPA>(1 << 14, 32); }
+}
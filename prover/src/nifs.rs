@@ -81,6 +81,9 @@ impl<E: Engine> NIFS<E> {
     // fold the witness using `r` and `T`
     let W = W1.fold(W2, &T, &r)?;
 
+    #[cfg(feature = "debug-fold")]
+    Self::check_fold(ck, S, U1, W1, U2, W2, &U, &W)?;
+
     // return the folded instance and witness
     Ok((Self { comm_T: comm_T.compress() }, (U, W), r))
   }
@@ -106,6 +109,36 @@ impl<E: Engine> NIFS<E> {
     ABC_Z_1: &mut R1CSResult<E>,
     ABC_Z_2: &mut R1CSResult<E>,
   ) -> Result<(Self, E::Scalar), NovaError> {
+    let (nifs, fold) =
+      Self::prove_instance_mut(ck, ro_consts, pp_digest, S, U1, W1, U2, W2, T, ABC_Z_1, ABC_Z_2)?;
+    let r = fold.challenge();
+    fold.fold_witness(ck, S, U1, W1, U2, W2, T)?;
+    Ok((nifs, r))
+  }
+
+  /// Same as [`NIFS::prove_mut`], but only updates `U1`: the fold of `W1`
+  /// is returned, to run with [`DeferredFold::fold_witness`] once the cross
+  /// term `T` is no longer written, so that the caller overlaps it with
+  /// work that only depends on the folded instance, e.g. the synthesis of the
+  /// other circuit of the step.
+  #[allow(clippy::too_many_arguments)]
+  #[tracing::instrument(skip_all, level = "trace", name = "NIFS::prove_instance_mut")]
+  pub fn prove_instance_mut(
+    ck: &CommitmentKey<E>,
+    ro_consts: &ROConstants<E>,
+    pp_digest: &E::Scalar,
+    S: &R1CSShape<E>,
+    U1: &mut RelaxedR1CSInstance<E>,
+    W1: &RelaxedR1CSWitness<E>,
+    U2: &R1CSInstance<E>,
+    W2: &R1CSWitness<E>,
+    T: &mut Vec<E::Scalar>,
+    ABC_Z_1: &mut R1CSResult<E>,
+    ABC_Z_2: &mut R1CSResult<E>,
+  ) -> Result<(Self, DeferredFold<E>), NovaError> {
+    #[cfg(feature = "debug-fold")]
+    let inputs = (U1.clone(), W1.clone());
+
     // initialize a new RO
     let mut ro = E::RO::new(ro_consts.clone(), NUM_FE_FOR_RO);
 
@@ -128,11 +161,44 @@ impl<E: Engine> NIFS<E> {
     // fold the instance using `r` and `comm_T`
     U1.fold_mut(U2, &comm_T, &r);
 
-    // fold the witness using `r` and `T`
-    W1.fold_mut(W2, T, &r)?;
+    let fold = DeferredFold {
+      r,
+      #[cfg(feature = "debug-fold")]
+      inputs,
+    };
+    Ok((Self { comm_T: comm_T.compress() }, fold))
+  }
+
+  /// Checks that folding the satisfying pairs `(U1, W1)` and `(U2, W2)`
+  /// produced a satisfying pair `(U, W)`, and returns
+  /// `NovaError::InvalidFold` with the failing constraint rows otherwise,
+  /// which points at a bad cross term. Unsatisfying inputs are left for the
+  /// usual verification to catch. This is slow: with the `debug-fold`
+  /// feature, [`NIFS::prove`], [`NIFS::prove_mut`] and
+  /// [`DeferredFold::fold_witness`] run it on every fold.
+  #[allow(clippy::too_many_arguments)]
+  pub fn check_fold(
+    ck: &CommitmentKey<E>,
+    S: &R1CSShape<E>,
+    U1: &RelaxedR1CSInstance<E>,
+    W1: &RelaxedR1CSWitness<E>,
+    U2: &R1CSInstance<E>,
+    W2: &R1CSWitness<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+  ) -> Result<(), NovaError> {
+    let U2 = RelaxedR1CSInstance::from_r1cs_instance(ck, S, U2.clone());
+    let W2 = RelaxedR1CSWitness::from_r1cs_witness(S, W2.clone());
+    if !U1.is_sat_debug(ck, S, W1)?.is_sat() || !U2.is_sat_debug(ck, S, &W2)?.is_sat() {
+      return Ok(());
+    }
 
-    // return the commitment
-    Ok((Self { comm_T: comm_T.compress() }, r))
+    let report = U.is_sat_debug(ck, S, W)?;
+    if report.is_sat() {
+      return Ok(());
+    }
+    tracing::error!(?report, "folding satisfying instances produced an unsatisfying one");
+    Err(NovaError::InvalidFold(report.unsat_rows.iter().map(|r| r.row).collect()))
   }
 
   /// Takes as input a relaxed R1CS instance `U1` and R1CS instance `U2`
@@ -172,6 +238,44 @@ impl<E: Engine> NIFS<E> {
   }
 }
 
+/// The fold of a witness, deferred by [`NIFS::prove_instance_mut`]
+#[derive(Debug)]
+#[must_use = "the witness is not folded until `fold_witness` runs"]
+pub struct DeferredFold<E: Engine> {
+  r:      E::Scalar,
+  #[cfg(feature = "debug-fold")]
+  inputs: (RelaxedR1CSInstance<E>, RelaxedR1CSWitness<E>),
+}
+
+impl<E: Engine> DeferredFold<E> {
+  /// The challenge of the fold
+  pub fn challenge(&self) -> E::Scalar { self.r }
+
+  /// Folds `W2` into `W1` with the cross term `T` of the fold, after which
+  /// `W1` satisfies `U1`, the folded instance. With the `debug-fold`
+  /// feature, checks the fold as [`NIFS::check_fold`] does.
+  #[allow(clippy::too_many_arguments)]
+  pub fn fold_witness(
+    self,
+    ck: &CommitmentKey<E>,
+    S: &R1CSShape<E>,
+    U1: &RelaxedR1CSInstance<E>,
+    W1: &mut RelaxedR1CSWitness<E>,
+    U2: &R1CSInstance<E>,
+    W2: &R1CSWitness<E>,
+    T: &[E::Scalar],
+  ) -> Result<(), NovaError> {
+    // fold the witness using `r` and `T`
+    W1.fold_mut(W2, T, &self.r)?;
+
+    #[cfg(feature = "debug-fold")]
+    NIFS::check_fold(ck, S, &self.inputs.0, &self.inputs.1, U2, W2, U1, W1)?;
+    #[cfg(not(feature = "debug-fold"))]
+    let _ = (ck, S, U1, U2);
+    Ok(())
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use ::bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
@@ -367,4 +471,31 @@ mod tests {
 
   #[test]
   fn test_tiny_r1cs() { test_tiny_r1cs_with::<Bn256EngineKZG>(); }
+
+  fn test_check_fold_with<E: Engine>() {
+    let S = crate::r1cs::tests::tiny_r1cs::<E>(3);
+    let ck = commitment_key(&S, &*default_ck_hint());
+    let ro_consts = ROConstants::<E>::default();
+    let pp_digest = E::Scalar::ZERO;
+
+    // x = 2, y = x^3 + x + 5
+    let W2 = R1CSWitness::new(&S, [4u64, 8, 10].map(E::Scalar::from).to_vec()).unwrap();
+    let X = [2u64, 15].map(E::Scalar::from).to_vec();
+    let U2 = R1CSInstance::new(&S, W2.commit(&ck), X).unwrap();
+    let (U1, W1) = (RelaxedR1CSInstance::default(&ck, &S), RelaxedR1CSWitness::default(&S));
+
+    let (_, (U, mut W), _) =
+      NIFS::prove(&ck, &ro_consts, &pp_digest, &S, &U1, &W1, &U2, &W2).unwrap();
+    NIFS::check_fold(&ck, &S, &U1, &W1, &U2, &W2, &U, &W).unwrap();
+
+    // a corrupted error vector, as a bad cross term would produce, is caught
+    W.E[2] += E::Scalar::ONE;
+    assert_eq!(
+      NIFS::check_fold(&ck, &S, &U1, &W1, &U2, &W2, &U, &W),
+      Err(NovaError::InvalidFold(vec![2]))
+    );
+  }
+
+  #[test]
+  fn test_check_fold() { test_check_fold_with::<Bn256EngineKZG>(); }
 }
@@ -0,0 +1,627 @@
+//! Parallelism shims.
+//!
+//! With the `parallel` feature (enabled by default) this module re-exports `rayon`, and every
+//! data-parallel loop in the crate runs on the rayon thread pool. With the feature disabled, the
+//! same names are provided by sequential adapters over [`Iterator`], so the crate builds without
+//! `rayon` for targets that cannot spawn workers (e.g. `wasm32` without threads).
+//!
+//! Call sites import from here rather than from `rayon` directly:
+//!
+//! ```ignore
+//! use crate::parallel::prelude::*;
+//!
+//! let (a, b) = crate::parallel::join(|| left(), || right());
+//! let c: Vec<_> = a.par_iter().zip_eq(b.par_iter()).map(|(x, y)| x * y).collect();
+//! ```
+//!
+//! Outside of modules that import the prelude, [`maybe_par_iter!`](crate::maybe_par_iter) and
+//! friends pick the right iterator without any imports.
+//!
+//! Loops doing a few field operations per element split into tasks of at least [`min_len`]
+//! elements, with `with_min_len`: on short vectors the cost of spawning a task outweighs its work,
+//! and such a loop runs slower in parallel than serially. The length is tuned per device, for the
+//! duration of a call, with [`set_min_len`].
+
+use std::{cell::Cell, marker::PhantomData};
+
+#[cfg(feature = "parallel")]
+pub use rayon::current_num_threads;
+#[cfg(not(feature = "parallel"))]
+pub use serial::{current_num_threads, join};
+
+/// The default of [`min_len`], about tens of microseconds of field multiplications per task
+pub const DEFAULT_MIN_LEN: usize = 1 << 10;
+
+thread_local! {
+  static MIN_LEN: Cell<usize> = const { Cell::new(DEFAULT_MIN_LEN) };
+}
+
+/// The fewest elements a task of a light parallel loop takes, e.g. of an inner product or of the
+/// folding of a vector: a loop over fewer elements runs on the current thread
+pub fn min_len() -> usize { MIN_LEN.with(Cell::get) }
+
+/// Sets [`min_len`] to `len`, at least 1, e.g. lower on a device of many slow cores, or higher
+/// where waking workers is expensive, until the returned guard is dropped.
+///
+/// The length is set for the current thread and for the closures it runs with [`join`], so that
+/// concurrent calls, e.g. proofs on two devices, each run with their own length.
+pub fn set_min_len(len: usize) -> MinLenGuard {
+  let previous = MIN_LEN.with(|min_len| min_len.replace(len.max(1)));
+  MinLenGuard { previous, _thread: PhantomData }
+}
+
+/// Restores the previous [`min_len`] of its thread when dropped, see [`set_min_len`]
+#[must_use = "the length is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct MinLenGuard {
+  previous: usize,
+  // the guard restores the length of the thread that set it
+  _thread:  PhantomData<*const ()>,
+}
+
+impl Drop for MinLenGuard {
+  fn drop(&mut self) { MIN_LEN.with(|min_len| min_len.set(self.previous)) }
+}
+
+/// Runs both closures, potentially in parallel, as `rayon::join` does, with the [`min_len`] of
+/// the current thread
+#[cfg(feature = "parallel")]
+pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
+where
+  A: FnOnce() -> RA + Send,
+  B: FnOnce() -> RB + Send,
+  RA: Send,
+  RB: Send, {
+  let len = min_len();
+  rayon::join(
+    move || {
+      let _guard = set_min_len(len);
+      oper_a()
+    },
+    move || {
+      let _guard = set_min_len(len);
+      oper_b()
+    },
+  )
+}
+
+/// The number of items queued between two stages of a [`pipeline`]: a stage runs at most this
+/// many items ahead of the next, which bounds the memory of the items in flight
+pub const PIPELINE_DEPTH: usize = 1;
+
+/// Runs `first`, then `second`, then `third` on each item in turn, the stages working on
+/// consecutive items at once: while `third` runs on an item, `second` runs on the next one and
+/// `first` on the one after, each stage on its own thread but `third`, which runs on the current
+/// one. The queues between the stages hold [`PIPELINE_DEPTH`] items. Stops at the first error,
+/// the earlier stages stopping once they find the next one gone.
+#[cfg(feature = "parallel")]
+pub fn pipeline<I, A, B, Err>(
+  items: I,
+  mut first: impl FnMut(I::Item) -> Result<A, Err> + Send,
+  mut second: impl FnMut(A) -> Result<B, Err> + Send,
+  mut third: impl FnMut(B) -> Result<(), Err>,
+) -> Result<(), Err>
+where
+  I: IntoIterator,
+  I::IntoIter: Send,
+  A: Send,
+  B: Send,
+  Err: Send,
+{
+  use std::sync::mpsc::sync_channel;
+
+  let items = items.into_iter();
+  std::thread::scope(|s| {
+    let (tx_a, rx_a) = sync_channel::<Result<A, Err>>(PIPELINE_DEPTH);
+    let (tx_b, rx_b) = sync_channel::<Result<B, Err>>(PIPELINE_DEPTH);
+    s.spawn(move || {
+      for item in items {
+        let a = first(item);
+        let failed = a.is_err();
+        if tx_a.send(a).is_err() || failed {
+          break;
+        }
+      }
+    });
+    s.spawn(move || {
+      for a in rx_a {
+        let b = a.and_then(&mut second);
+        let failed = b.is_err();
+        if tx_b.send(b).is_err() || failed {
+          break;
+        }
+      }
+    });
+    for b in rx_b {
+      third(b?)?;
+    }
+    Ok(())
+  })
+}
+
+/// Runs `first`, then `second`, then `third` on each item in turn, on the current thread
+#[cfg(not(feature = "parallel"))]
+pub fn pipeline<I, A, B, Err>(
+  items: I,
+  mut first: impl FnMut(I::Item) -> Result<A, Err> + Send,
+  mut second: impl FnMut(A) -> Result<B, Err> + Send,
+  mut third: impl FnMut(B) -> Result<(), Err>,
+) -> Result<(), Err>
+where
+  I: IntoIterator,
+  I::IntoIter: Send,
+  A: Send,
+  B: Send,
+  Err: Send,
+{
+  for item in items {
+    third(second(first(item)?)?)?;
+  }
+  Ok(())
+}
+
+/// The traits needed to call parallel iterator methods.
+pub mod prelude {
+  #[cfg(feature = "parallel")] pub use rayon::prelude::*;
+  #[cfg(feature = "parallel")]
+  pub use rayon_scan::ScanParallelIterator;
+
+  #[cfg(not(feature = "parallel"))]
+  pub use super::serial::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator, ParallelSlice, ParallelSliceMut,
+    ScanParallelIterator,
+  };
+}
+
+/// `$e.par_iter()`, with the trait in scope: a rayon iterator with the `parallel` feature, and a
+/// sequential one without it.
+#[macro_export]
+macro_rules! maybe_par_iter {
+  ($e:expr) => {{
+    use $crate::parallel::prelude::IntoParallelRefIterator as _;
+    $e.par_iter()
+  }};
+}
+
+/// `$e.par_iter_mut()`, see [`maybe_par_iter!`](crate::maybe_par_iter).
+#[macro_export]
+macro_rules! maybe_par_iter_mut {
+  ($e:expr) => {{
+    use $crate::parallel::prelude::IntoParallelRefMutIterator as _;
+    $e.par_iter_mut()
+  }};
+}
+
+/// `$e.into_par_iter()`, see [`maybe_par_iter!`](crate::maybe_par_iter).
+#[macro_export]
+macro_rules! maybe_into_par_iter {
+  ($e:expr) => {{
+    use $crate::parallel::prelude::IntoParallelIterator as _;
+    $e.into_par_iter()
+  }};
+}
+
+/// Sequential stand-ins for the parts of the `rayon` API used in this crate.
+///
+/// The adapters return [`Serial`], which only exposes the rayon-style methods below; this keeps
+/// method resolution unambiguous next to [`Iterator`] and `itertools`.
+#[cfg(not(feature = "parallel"))]
+mod serial {
+  use std::{
+    cmp::Ordering,
+    iter::{self, Product, Sum},
+    slice,
+  };
+
+  /// Runs both closures on the current thread, `oper_a` first.
+  pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
+  where
+    A: FnOnce() -> RA,
+    B: FnOnce() -> RB, {
+    let a = oper_a();
+    (a, oper_b())
+  }
+
+  /// There is exactly one worker: the caller.
+  pub fn current_num_threads() -> usize { 1 }
+
+  /// A sequential iterator dressed up as a parallel one.
+  #[derive(Clone, Debug)]
+  pub struct Serial<I>(I);
+
+  impl<I: Iterator> IntoIterator for Serial<I> {
+    type IntoIter = I;
+    type Item = I::Item;
+
+    fn into_iter(self) -> I { self.0 }
+  }
+
+  pub trait ParallelIterator: Sized {
+    type Item;
+    type Seq: Iterator<Item = Self::Item>;
+
+    /// Unwraps the underlying sequential iterator.
+    fn into_seq(self) -> Self::Seq;
+
+    fn map<R, F>(self, f: F) -> Serial<iter::Map<Self::Seq, F>>
+    where F: FnMut(Self::Item) -> R {
+      Serial(self.into_seq().map(f))
+    }
+
+    fn filter<P>(self, predicate: P) -> Serial<iter::Filter<Self::Seq, P>>
+    where P: FnMut(&Self::Item) -> bool {
+      Serial(self.into_seq().filter(predicate))
+    }
+
+    fn filter_map<R, F>(self, f: F) -> Serial<iter::FilterMap<Self::Seq, F>>
+    where F: FnMut(Self::Item) -> Option<R> {
+      Serial(self.into_seq().filter_map(f))
+    }
+
+    fn flat_map<U, F>(self, f: F) -> Serial<iter::FlatMap<Self::Seq, U, F>>
+    where
+      U: IntoIterator,
+      F: FnMut(Self::Item) -> U, {
+      Serial(self.into_seq().flat_map(f))
+    }
+
+    fn flatten(self) -> Serial<iter::Flatten<Self::Seq>>
+    where Self::Item: IntoIterator {
+      Serial(self.into_seq().flatten())
+    }
+
+    fn inspect<F>(self, f: F) -> Serial<iter::Inspect<Self::Seq, F>>
+    where F: FnMut(&Self::Item) {
+      Serial(self.into_seq().inspect(f))
+    }
+
+    fn cloned<'a, T>(self) -> Serial<iter::Cloned<Self::Seq>>
+    where
+      T: 'a + Clone,
+      Self::Seq: Iterator<Item = &'a T>, {
+      Serial(self.into_seq().cloned())
+    }
+
+    fn copied<'a, T>(self) -> Serial<iter::Copied<Self::Seq>>
+    where
+      T: 'a + Copy,
+      Self::Seq: Iterator<Item = &'a T>, {
+      Serial(self.into_seq().copied())
+    }
+
+    fn zip<Z>(self, other: Z) -> Serial<iter::Zip<Self::Seq, Z::IntoIter>>
+    where Z: IntoIterator {
+      Serial(self.into_seq().zip(other))
+    }
+
+    fn zip_eq<Z>(self, other: Z) -> Serial<itertools::structs::ZipEq<Self::Seq, Z::IntoIter>>
+    where Z: IntoIterator {
+      Serial(itertools::zip_eq(self.into_seq(), other))
+    }
+
+    fn enumerate(self) -> Serial<iter::Enumerate<Self::Seq>> { Serial(self.into_seq().enumerate()) }
+
+    fn rev(self) -> Serial<iter::Rev<Self::Seq>>
+    where Self::Seq: DoubleEndedIterator {
+      Serial(self.into_seq().rev())
+    }
+
+    fn skip(self, n: usize) -> Serial<iter::Skip<Self::Seq>> { Serial(self.into_seq().skip(n)) }
+
+    fn take(self, n: usize) -> Serial<iter::Take<Self::Seq>> { Serial(self.into_seq().take(n)) }
+
+    fn step_by(self, step: usize) -> Serial<iter::StepBy<Self::Seq>> {
+      Serial(self.into_seq().step_by(step))
+    }
+
+    fn with_min_len(self, _min: usize) -> Self { self }
+
+    fn with_max_len(self, _max: usize) -> Self { self }
+
+    fn for_each<F>(self, f: F)
+    where F: FnMut(Self::Item) {
+      self.into_seq().for_each(f)
+    }
+
+    fn try_for_each<F, E>(self, f: F) -> Result<(), E>
+    where F: FnMut(Self::Item) -> Result<(), E> {
+      self.into_seq().try_for_each(f)
+    }
+
+    fn collect<C>(self) -> C
+    where C: FromIterator<Self::Item> {
+      self.into_seq().collect()
+    }
+
+    fn collect_into_vec(self, target: &mut Vec<Self::Item>) {
+      target.clear();
+      target.extend(self.into_seq());
+    }
+
+    fn unzip<A, B, FromA, FromB>(self) -> (FromA, FromB)
+    where
+      Self::Seq: Iterator<Item = (A, B)>,
+      FromA: Default + Extend<A>,
+      FromB: Default + Extend<B>, {
+      self.into_seq().unzip()
+    }
+
+    fn sum<S>(self) -> S
+    where S: Sum<Self::Item> {
+      self.into_seq().sum()
+    }
+
+    fn product<P>(self) -> P
+    where P: Product<Self::Item> {
+      self.into_seq().product()
+    }
+
+    fn reduce<OP, ID>(self, identity: ID, op: OP) -> Self::Item
+    where
+      OP: FnMut(Self::Item, Self::Item) -> Self::Item,
+      ID: FnOnce() -> Self::Item, {
+      self.into_seq().fold(identity(), op)
+    }
+
+    fn reduce_with<OP>(self, op: OP) -> Option<Self::Item>
+    where OP: FnMut(Self::Item, Self::Item) -> Self::Item {
+      self.into_seq().reduce(op)
+    }
+
+    /// Like rayon, yields the folded value of each "split"; here there is exactly one.
+    fn fold<T, ID, F>(self, identity: ID, fold_op: F) -> Serial<iter::Once<T>>
+    where
+      ID: FnOnce() -> T,
+      F: FnMut(T, Self::Item) -> T, {
+      Serial(iter::once(self.into_seq().fold(identity(), fold_op)))
+    }
+
+    fn count(self) -> usize { self.into_seq().count() }
+
+    fn any<P>(self, predicate: P) -> bool
+    where P: FnMut(Self::Item) -> bool {
+      self.into_seq().any(predicate)
+    }
+
+    fn all<P>(self, predicate: P) -> bool
+    where P: FnMut(Self::Item) -> bool {
+      self.into_seq().all(predicate)
+    }
+
+    fn find_any<P>(self, predicate: P) -> Option<Self::Item>
+    where P: FnMut(&Self::Item) -> bool {
+      self.into_seq().find(predicate)
+    }
+
+    fn max(self) -> Option<Self::Item>
+    where Self::Item: Ord {
+      self.into_seq().max()
+    }
+
+    fn min(self) -> Option<Self::Item>
+    where Self::Item: Ord {
+      self.into_seq().min()
+    }
+
+    fn max_by<F>(self, compare: F) -> Option<Self::Item>
+    where F: FnMut(&Self::Item, &Self::Item) -> Ordering {
+      self.into_seq().max_by(compare)
+    }
+
+    fn min_by<F>(self, compare: F) -> Option<Self::Item>
+    where F: FnMut(&Self::Item, &Self::Item) -> Ordering {
+      self.into_seq().min_by(compare)
+    }
+  }
+
+  impl<I: Iterator> ParallelIterator for Serial<I> {
+    type Item = I::Item;
+    type Seq = I;
+
+    fn into_seq(self) -> I { self.0 }
+  }
+
+  pub trait IndexedParallelIterator: ParallelIterator {
+    fn len(&self) -> usize;
+  }
+
+  impl<I: ExactSizeIterator> IndexedParallelIterator for Serial<I> {
+    fn len(&self) -> usize { self.0.len() }
+  }
+
+  /// Inclusive prefix scan, as provided by `rayon_scan`.
+  pub trait ScanParallelIterator: ParallelIterator {
+    fn scan<F>(self, scan_op: F, identity: Self::Item) -> Serial<std::vec::IntoIter<Self::Item>>
+    where
+      Self::Item: Clone,
+      F: Fn(&Self::Item, &Self::Item) -> Self::Item, {
+      let mut acc = identity;
+      let out = self
+        .into_seq()
+        .map(|x| {
+          acc = scan_op(&acc, &x);
+          acc.clone()
+        })
+        .collect::<Vec<_>>();
+      Serial(out.into_iter())
+    }
+  }
+
+  impl<I: ParallelIterator> ScanParallelIterator for I {}
+
+  pub trait IntoParallelIterator {
+    type Item;
+    type Iter: ParallelIterator<Item = Self::Item>;
+
+    fn into_par_iter(self) -> Self::Iter;
+  }
+
+  impl<T: IntoIterator> IntoParallelIterator for T {
+    type Item = T::Item;
+    type Iter = Serial<T::IntoIter>;
+
+    fn into_par_iter(self) -> Self::Iter { Serial(self.into_iter()) }
+  }
+
+  pub trait IntoParallelRefIterator<'data> {
+    type Item;
+    type Iter: ParallelIterator<Item = Self::Item>;
+
+    fn par_iter(&'data self) -> Self::Iter;
+  }
+
+  impl<'data, T: 'data + ?Sized> IntoParallelRefIterator<'data> for T
+  where &'data T: IntoIterator
+  {
+    type Item = <&'data T as IntoIterator>::Item;
+    type Iter = Serial<<&'data T as IntoIterator>::IntoIter>;
+
+    fn par_iter(&'data self) -> Self::Iter { Serial(IntoIterator::into_iter(self)) }
+  }
+
+  pub trait IntoParallelRefMutIterator<'data> {
+    type Item;
+    type Iter: ParallelIterator<Item = Self::Item>;
+
+    fn par_iter_mut(&'data mut self) -> Self::Iter;
+  }
+
+  impl<'data, T: 'data + ?Sized> IntoParallelRefMutIterator<'data> for T
+  where &'data mut T: IntoIterator
+  {
+    type Item = <&'data mut T as IntoIterator>::Item;
+    type Iter = Serial<<&'data mut T as IntoIterator>::IntoIter>;
+
+    fn par_iter_mut(&'data mut self) -> Self::Iter { Serial(IntoIterator::into_iter(self)) }
+  }
+
+  pub trait ParallelSlice<T> {
+    fn par_windows(&self, window_size: usize) -> Serial<slice::Windows<'_, T>>;
+
+    fn par_chunks(&self, chunk_size: usize) -> Serial<slice::Chunks<'_, T>>;
+  }
+
+  impl<T> ParallelSlice<T> for [T] {
+    fn par_windows(&self, window_size: usize) -> Serial<slice::Windows<'_, T>> {
+      Serial(self.windows(window_size))
+    }
+
+    fn par_chunks(&self, chunk_size: usize) -> Serial<slice::Chunks<'_, T>> {
+      Serial(self.chunks(chunk_size))
+    }
+  }
+
+  pub trait ParallelSliceMut<T> {
+    fn par_chunks_mut(&mut self, chunk_size: usize) -> Serial<slice::ChunksMut<'_, T>>;
+  }
+
+  impl<T> ParallelSliceMut<T> for [T] {
+    fn par_chunks_mut(&mut self, chunk_size: usize) -> Serial<slice::ChunksMut<'_, T>> {
+      Serial(self.chunks_mut(chunk_size))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::prelude::*;
+
+  #[test]
+  fn test_shim_matches_sequential() {
+    let v = (1..=64u64).collect::<Vec<_>>();
+    let w = v.iter().rev().copied().collect::<Vec<_>>();
+
+    let dot: u64 = v.par_iter().zip_eq(w.par_iter()).map(|(a, b)| a * b).sum();
+    let expected: u64 = v.iter().zip(w.iter()).map(|(a, b)| a * b).sum();
+    assert_eq!(dot, expected);
+
+    let total = v.par_iter().map(|x| *x).reduce(|| 0, |a, b| a + b);
+    assert_eq!(total, v.iter().sum::<u64>());
+
+    let prefix: Vec<u64> = v.clone().into_par_iter().scan(|a, b| a + b, 0).collect();
+    assert_eq!(prefix.last(), Some(&total));
+    assert_eq!(prefix[..3], [1, 3, 6]);
+
+    let mut doubled = vec![];
+    v.par_iter().map(|x| 2 * x).collect_into_vec(&mut doubled);
+    assert_eq!(doubled.len(), v.len());
+
+    let mut u = v.clone();
+    crate::maybe_par_iter_mut!(u).for_each(|x| *x += 1);
+    assert_eq!(crate::maybe_par_iter!(u).zip_eq(&v).filter(|(a, b)| **a != **b + 1).count(), 0);
+
+    let (a, b) = crate::parallel::join(|| 1, || 2);
+    assert_eq!((a, b), (1, 2));
+  }
+
+  #[test]
+  fn test_min_len() {
+    let v = (1..=4096u64).collect::<Vec<_>>();
+    let expected: u64 = v.iter().map(|x| x * x).sum();
+    for len in [1, 7, super::DEFAULT_MIN_LEN, v.len() + 1] {
+      let sum: u64 = v.par_iter().map(|x| x * x).with_min_len(len).sum();
+      assert_eq!(sum, expected);
+    }
+
+    assert_eq!(super::min_len(), super::DEFAULT_MIN_LEN);
+    {
+      let _guard = super::set_min_len(0);
+      assert_eq!(super::min_len(), 1);
+    }
+    assert_eq!(super::min_len(), super::DEFAULT_MIN_LEN);
+  }
+
+  #[test]
+  fn test_min_len_splits_tasks() {
+    // the number of tasks a loop over `v` splits into, with the current length
+    let tasks =
+      |v: &[u64]| v.par_iter().with_min_len(super::min_len()).fold(|| (), |(), _| ()).count();
+    let v = (1..=4096u64).collect::<Vec<_>>();
+
+    let _guard = super::set_min_len(v.len());
+    assert_eq!(tasks(&v), 1);
+    {
+      let _guard = super::set_min_len(v.len() / 4);
+      assert!((1..=4).contains(&tasks(&v)));
+
+      // the length holds in the closures of `join`, but not on other threads
+      let (a, b) = crate::parallel::join(super::min_len, || tasks(&v));
+      assert_eq!(a, v.len() / 4);
+      assert!((1..=4).contains(&b));
+      let other = std::thread::spawn(super::min_len).join().unwrap();
+      assert_eq!(other, super::DEFAULT_MIN_LEN);
+    }
+    assert_eq!(super::min_len(), v.len());
+    assert_eq!(tasks(&v), 1);
+  }
+
+  #[test]
+  fn test_pipeline() {
+    let mut seen = vec![];
+    let result = super::pipeline(
+      1..=16u64,
+      |x| Ok::<_, u64>(x * x),
+      |x| Ok(x + 1),
+      |x| {
+        seen.push(x);
+        Ok(())
+      },
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!(seen, (1..=16u64).map(|x| x * x + 1).collect::<Vec<_>>());
+
+    let mut seen = vec![];
+    let result = super::pipeline(
+      1..=16u64,
+      |x| if x == 5 { Err(x) } else { Ok(x) },
+      Ok,
+      |x| {
+        seen.push(x);
+        Ok(())
+      },
+    );
+    assert_eq!(result, Err(5));
+    assert_eq!(seen, [1, 2, 3, 4]);
+  }
+}
@@ -0,0 +1,263 @@
+//! Memory high-water marks of the proving phases.
+//!
+//! With the `mem-profiling` feature, [`TrackingAllocator`] wraps the global
+//! allocator of a binary to count the bytes it holds:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: edge_prover::profiling::TrackingAllocator =
+//!   edge_prover::profiling::TrackingAllocator::system();
+//! ```
+//!
+//! The prover then records, for each phase it enters (setting up the public
+//! parameters, a folding step, a compression, ...), the peak number of bytes
+//! held above what was held when the phase started, and [`report`] returns
+//! them. Phases may nest, but the counters are global, so phases running
+//! concurrently on different threads see each other's allocations.
+//!
+//! Without the feature, or if the binary does not install the allocator,
+//! entering a phase does nothing and the report is empty.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The memory used by all the runs of a phase
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseMemory {
+  /// the name of the phase
+  pub phase:           String,
+  /// the number of times the phase ran
+  pub calls:           usize,
+  /// the largest number of bytes a run held above what it started with
+  pub peak_bytes:      usize,
+  /// the total number of bytes the runs allocated, including those freed
+  pub allocated_bytes: usize,
+}
+
+/// The memory used by the phases that ran since the last [`reset`], in the
+/// order they first ran
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryReport {
+  /// the memory used by each phase
+  pub phases: Vec<PhaseMemory>,
+}
+
+impl MemoryReport {
+  /// The memory used by the phase named `phase`, if it ran
+  pub fn phase(&self, phase: &str) -> Option<&PhaseMemory> {
+    self.phases.iter().find(|p| p.phase == phase)
+  }
+}
+
+impl fmt::Display for MemoryReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for p in &self.phases {
+      writeln!(
+        f,
+        "{}: {} call(s), peak {} KiB, allocated {} KiB",
+        p.phase,
+        p.calls,
+        p.peak_bytes / 1024,
+        p.allocated_bytes / 1024
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "mem-profiling")]
+mod tracking {
+  use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+      atomic::{AtomicUsize, Ordering},
+      Mutex, PoisonError,
+    },
+  };
+
+  use super::{MemoryReport, PhaseMemory};
+
+  /// bytes currently held
+  static CURRENT: AtomicUsize = AtomicUsize::new(0);
+  /// largest value of `CURRENT` since the innermost phase started
+  static PEAK: AtomicUsize = AtomicUsize::new(0);
+  /// bytes allocated so far, including those freed
+  static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+  static REPORT: Mutex<MemoryReport> = Mutex::new(MemoryReport { phases: Vec::new() });
+
+  fn record_alloc(size: usize) {
+    TOTAL.fetch_add(size, Ordering::Relaxed);
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+  }
+
+  fn record_dealloc(size: usize) { CURRENT.fetch_sub(size, Ordering::Relaxed); }
+
+  /// A global allocator counting the bytes held through the allocator it
+  /// wraps
+  #[derive(Debug, Default)]
+  pub struct TrackingAllocator<A = System> {
+    inner: A,
+  }
+
+  impl TrackingAllocator<System> {
+    /// Wraps the system allocator
+    pub const fn system() -> Self { Self { inner: System } }
+  }
+
+  impl<A> TrackingAllocator<A> {
+    /// Wraps `inner`
+    pub const fn new(inner: A) -> Self { Self { inner } }
+  }
+
+  unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+      let ptr = self.inner.alloc(layout);
+      if !ptr.is_null() {
+        record_alloc(layout.size());
+      }
+      ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+      let ptr = self.inner.alloc_zeroed(layout);
+      if !ptr.is_null() {
+        record_alloc(layout.size());
+      }
+      ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+      self.inner.dealloc(ptr, layout);
+      record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+      let new_ptr = self.inner.realloc(ptr, layout, new_size);
+      if !new_ptr.is_null() {
+        if new_size > layout.size() {
+          record_alloc(new_size - layout.size());
+        } else {
+          record_dealloc(layout.size() - new_size);
+        }
+      }
+      new_ptr
+    }
+  }
+
+  /// The memory used by the phases that ran since the last [`reset`]
+  pub fn report() -> MemoryReport { REPORT.lock().unwrap_or_else(PoisonError::into_inner).clone() }
+
+  /// Forgets the phases that ran so far
+  pub fn reset() { REPORT.lock().unwrap_or_else(PoisonError::into_inner).phases.clear() }
+
+  /// The counters when a phase started
+  #[derive(Debug)]
+  pub(super) struct PhaseStart {
+    name:       &'static str,
+    current:    usize,
+    total:      usize,
+    outer_peak: usize,
+  }
+
+  impl PhaseStart {
+    pub(super) fn new(name: &'static str) -> Self {
+      let current = CURRENT.load(Ordering::Relaxed);
+      let outer_peak = PEAK.swap(current, Ordering::Relaxed);
+      Self { name, current, total: TOTAL.load(Ordering::Relaxed), outer_peak }
+    }
+
+    pub(super) fn end(&self) {
+      let peak = PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+      let peak_bytes = peak.saturating_sub(self.current);
+      let allocated_bytes = TOTAL.load(Ordering::Relaxed) - self.total;
+
+      let mut report = REPORT.lock().unwrap_or_else(PoisonError::into_inner);
+      match report.phases.iter_mut().find(|p| p.phase == self.name) {
+        Some(p) => {
+          p.calls += 1;
+          p.peak_bytes = p.peak_bytes.max(peak_bytes);
+          p.allocated_bytes += allocated_bytes;
+        },
+        None => report.phases.push(PhaseMemory {
+          phase: self.name.to_string(),
+          calls: 1,
+          peak_bytes,
+          allocated_bytes,
+        }),
+      }
+    }
+  }
+}
+
+#[cfg(feature = "mem-profiling")]
+pub use tracking::{report, reset, TrackingAllocator};
+
+/// A running phase, which ends when it is dropped
+#[derive(Debug)]
+#[must_use = "the phase ends when it is dropped"]
+pub struct Phase {
+  #[cfg(feature = "mem-profiling")]
+  start:  tracking::PhaseStart,
+  // reports the duration of the phase, see `crate::metrics`
+  #[cfg(feature = "metrics")]
+  _timer: crate::metrics::PhaseTimer,
+}
+
+impl Phase {
+  /// Starts the phase named `name`
+  #[cfg_attr(not(any(feature = "mem-profiling", feature = "metrics")), allow(unused_variables))]
+  pub fn enter(name: &'static str) -> Self {
+    Self {
+      #[cfg(feature = "mem-profiling")]
+      start:                                   tracking::PhaseStart::new(name),
+      #[cfg(feature = "metrics")]
+      _timer:                                  crate::metrics::PhaseTimer::start(name),
+    }
+  }
+}
+
+#[cfg(feature = "mem-profiling")]
+impl Drop for Phase {
+  fn drop(&mut self) { self.start.end() }
+}
+
+#[cfg(not(feature = "mem-profiling"))]
+/// The memory used by the phases that ran since the last [`reset`]
+pub fn report() -> MemoryReport { MemoryReport::default() }
+
+#[cfg(not(feature = "mem-profiling"))]
+/// Forgets the phases that ran so far
+pub fn reset() {}
+
+#[cfg(all(test, feature = "mem-profiling"))]
+mod tests {
+  use super::*;
+
+  #[global_allocator]
+  static ALLOCATOR: TrackingAllocator = TrackingAllocator::system();
+
+  #[test]
+  fn test_phase_memory() {
+    {
+      let _outer = Phase::enter("test_phase_memory::outer");
+      let big = vec![1u8; 1 << 20];
+      {
+        let _inner = Phase::enter("test_phase_memory::inner");
+        let small = vec![1u8; 1 << 10];
+        drop(small);
+      }
+      drop(big);
+    }
+
+    // allocated bytes are not affected by the tests running concurrently
+    let report = report();
+    let outer = report.phase("test_phase_memory::outer").unwrap();
+    let inner = report.phase("test_phase_memory::inner").unwrap();
+    assert_eq!((outer.calls, inner.calls), (1, 1));
+    assert!(outer.allocated_bytes >= (1 << 20) + (1 << 10));
+    assert!(inner.allocated_bytes >= 1 << 10);
+    assert!(report.to_string().contains("test_phase_memory::outer: 1 call(s)"));
+  }
+}
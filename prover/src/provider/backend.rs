@@ -0,0 +1,356 @@
+//! Choosing the polynomial commitment backend of a deployment, IPA, KZG or
+//! hash-based, by the size of its circuits and the speed of its devices.
+//!
+//! Each backend wins in its own regime: IPA is transparent but its verifier
+//! is linear in the size of the circuit, KZG verifies in a few pairings but
+//! needs the SRS of a ceremony (see [`super::srs`]), and the hash-based
+//! [`super::small_field::ligero::LigeroPCS`] proves without MSMs, with
+//! proofs of the square root of the size of the circuit, over small fields
+//! that do not fold. [`recommend`] estimates the three for a [`Target`] on
+//! the [`DeviceProfile`]s of the prover and of the verifier, measured with
+//! [`DeviceProfile::calibrate`], and picks the fastest to prove within the
+//! budgets of the target.
+//!
+//! [`BackendDecision::setup_with`] runs the setup of the application over
+//! the engine of the backend, and [`BackendDecision::bind`] binds the
+//! parameters set up to the decision, which is then part of their digest, so
+//! that a verifier pinning the digest rejects parameters of another backend.
+//! The recommendation needs the `prove` feature; the decisions do not, so
+//! that verifiers check them.
+use std::time::Duration;
+
+use halo2curves::bn256::Bn256;
+use serde::{Deserialize, Serialize};
+
+use super::{hyperkzg, ipa_pc, Bn256EngineIPA, Bn256EngineKZG};
+#[cfg(feature = "prove")]
+use crate::supernova::estimate::DeviceProfile;
+use crate::{
+  errors::NovaError,
+  traits::{evaluation::EvaluationEngineTrait, CurveCycleEquipped, Dual},
+  PublicParams,
+};
+
+/// The time of the pairings of a KZG verification, in points of an MSM
+#[cfg(feature = "prove")]
+const PAIRING_POINTS: usize = 200;
+
+/// The time of a hash of a block of 64 bytes, in field multiplications
+#[cfg(feature = "prove")]
+const HASH_MULS: usize = 64;
+
+/// How many multiplications of a small field take the time of one of the
+/// scalar field of a curve
+#[cfg(feature = "prove")]
+const SMALL_FIELD_SPEEDUP: usize = 8;
+
+/// The columns the hash-based backend opens, for 100 bits of security, as
+/// `LigeroPCS` does
+#[cfg(feature = "prove")]
+const HASH_QUERIES: usize = (100 * 100usize).div_ceil(41);
+
+/// A polynomial commitment backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PcsBackend {
+  /// Inner-product arguments over Pedersen commitments, transparent
+  Ipa,
+  /// HyperKZG, of a trusted setup
+  Kzg,
+  /// Ligero over a small field, transparent and without folding
+  Hash,
+}
+
+/// The size and the constraints of a deployment
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+  /// the number of constraints of the circuit committed to
+  pub num_constraints: usize,
+  /// whether the proofs fold steps, which only the backends over curves do
+  pub folds:           bool,
+  /// whether the deployment trusts the SRS of a ceremony, which KZG needs
+  pub trusted_setup:   bool,
+  /// the largest proof the verifier takes, in bytes, e.g. on chain
+  pub max_proof_bytes: Option<usize>,
+  /// the longest verification the verifier takes
+  pub max_verify_time: Option<Duration>,
+}
+
+impl Target {
+  /// The target of a folding circuit of `num_constraints` constraints, without
+  /// a trusted setup or budgets
+  pub fn new(num_constraints: usize) -> Self {
+    Self {
+      num_constraints,
+      folds: true,
+      trusted_setup: false,
+      max_proof_bytes: None,
+      max_verify_time: None,
+    }
+  }
+}
+
+/// The estimated costs of a backend for a target
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendEstimate {
+  /// the backend
+  pub backend:     PcsBackend,
+  /// the time to commit to the circuit and prove an evaluation, on the prover
+  pub prove_time:  Duration,
+  /// the time to verify the evaluation, on the verifier
+  pub verify_time: Duration,
+  /// the size of the evaluation argument, in bytes
+  pub proof_bytes: usize,
+}
+
+#[cfg(feature = "prove")]
+impl BackendEstimate {
+  fn within(&self, target: &Target) -> bool {
+    target.max_proof_bytes.is_none_or(|max| self.proof_bytes <= max)
+      && target.max_verify_time.is_none_or(|max| self.verify_time <= max)
+  }
+}
+
+/// The estimate of `backend` for a circuit of `2^log_n` constraints
+#[cfg(feature = "prove")]
+fn estimate(
+  backend: PcsBackend,
+  log_n: usize,
+  prover: &DeviceProfile,
+  verifier: &DeviceProfile,
+) -> BackendEstimate {
+  let n = 1usize << log_n;
+  let (prove_time, verify_time, proof_bytes) = match backend {
+    // committing, then halving the vectors down to one
+    PcsBackend::Ipa => (prover.time(2 * n, 4 * n), verifier.time(n, 2 * n), (2 * log_n + 2) * 32),
+    // committing, then to each of the `log_n` folds, which a verifier checks
+    // at three points with a batched pairing
+    PcsBackend::Kzg => (
+      prover.time(2 * n, 4 * n),
+      verifier.time(log_n + PAIRING_POINTS, 6 * log_n),
+      (4 * log_n + 3) * 32,
+    ),
+    // encoding the rows, hashing the columns, then opening some columns
+    PcsBackend::Hash => {
+      let (rows, cols) = (1usize << (log_n / 2), 1usize << log_n.div_ceil(2));
+      let (code_len, log_code_len) = (2 * cols, log_n.div_ceil(2) + 1);
+      let queries = HASH_QUERIES.min(code_len);
+      let encoding = rows * code_len * log_code_len / SMALL_FIELD_SPEEDUP;
+      let hashing = rows * code_len / 8 * HASH_MULS;
+      let checks = (queries * rows + 2 * code_len * log_code_len) / SMALL_FIELD_SPEEDUP;
+      (
+        prover.time(0, encoding + hashing),
+        verifier.time(0, checks + queries * log_code_len * HASH_MULS),
+        2 * cols * 16 + queries * (rows * 8 + log_code_len * 32),
+      )
+    },
+  };
+  BackendEstimate { backend, prove_time, verify_time, proof_bytes }
+}
+
+/// The backend of a target, the size it was decided for, and the estimates
+/// of the backends it was picked from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recommendation {
+  /// the backend picked
+  pub decision:      BackendDecision,
+  /// whether the backend is within the budgets of the target; if none is, the
+  /// fastest to prove is picked anyway
+  pub within_budget: bool,
+  /// the estimates of the backends available to the target
+  pub estimates:     Vec<BackendEstimate>,
+}
+
+/// Picks the backend of `target`: of the backends available to it, within
+/// its budgets, the fastest on `prover`, then on `verifier`
+#[cfg(feature = "prove")]
+pub fn recommend(
+  target: &Target,
+  prover: &DeviceProfile,
+  verifier: &DeviceProfile,
+) -> Recommendation {
+  let log_n = target.num_constraints.max(1).next_power_of_two().trailing_zeros() as usize;
+  let available = [
+    Some(PcsBackend::Ipa),
+    target.trusted_setup.then_some(PcsBackend::Kzg),
+    (!target.folds).then_some(PcsBackend::Hash),
+  ];
+  let estimates = available
+    .into_iter()
+    .flatten()
+    .map(|backend| estimate(backend, log_n, prover, verifier))
+    .collect::<Vec<_>>();
+
+  let within_budget = estimates.iter().any(|e| e.within(target));
+  let best = estimates
+    .iter()
+    .filter(|e| !within_budget || e.within(target))
+    .min_by_key(|e| (e.prove_time, e.verify_time, e.proof_bytes))
+    .map_or(PcsBackend::Ipa, |e| e.backend);
+  Recommendation {
+    decision: BackendDecision { backend: best, log_num_constraints: log_n as u32 },
+    within_budget,
+    estimates,
+  }
+}
+
+/// The backend of a deployment, and the size of circuits it was decided for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendDecision {
+  /// the backend
+  pub backend:             PcsBackend,
+  /// the logarithm of the number of constraints it was decided for
+  pub log_num_constraints: u32,
+}
+
+impl BackendDecision {
+  /// Binds `pp` to this decision, which [`PublicParams::digest`], and the
+  /// keys set up from `pp`, then cover, for verifiers to pin; fails unless
+  /// `pp` is set up over the engine of the backend
+  pub fn bind<E1: PcsEngine>(&self, pp: PublicParams<E1>) -> Result<PublicParams<E1>, NovaError> {
+    if E1::BACKEND != self.backend {
+      return Err(NovaError::UnsupportedBackend);
+    }
+    Ok(pp.with_backend(*self))
+  }
+
+  /// Checks that `pp` is bound to this decision, and digests to the pinned
+  /// `digest`
+  pub fn check<E1: CurveCycleEquipped>(
+    &self,
+    pp: &PublicParams<E1>,
+    digest: &E1::Scalar,
+  ) -> Result<(), NovaError> {
+    if pp.backend() != Some(self) || pp.digest() != *digest {
+      return Err(NovaError::UnsupportedBackend);
+    }
+    Ok(())
+  }
+
+  /// Runs `setup` over the engine of the backend, and fails for the hash-based
+  /// backend, which has no curve cycle to fold over
+  pub fn setup_with<S: BackendSetup>(&self, setup: S) -> Result<S::Output, NovaError> {
+    match self.backend {
+      PcsBackend::Ipa => Ok(setup.setup::<Bn256EngineIPA>()),
+      PcsBackend::Kzg => Ok(setup.setup::<Bn256EngineKZG>()),
+      PcsBackend::Hash => Err(NovaError::UnsupportedBackend),
+    }
+  }
+}
+
+/// An engine of a curve cycle, with the evaluation engine of its backend
+pub trait PcsEngine: CurveCycleEquipped {
+  /// The backend of the engine
+  const BACKEND: PcsBackend;
+
+  /// The evaluation engine of the backend, for the compressing SNARK of the
+  /// primary circuits
+  type EE: EvaluationEngineTrait<Self>;
+
+  /// The evaluation engine of the compressing SNARK of the secondary
+  /// circuits, IPA on either backend
+  type SecondaryEE: EvaluationEngineTrait<Dual<Self>>;
+}
+
+impl PcsEngine for Bn256EngineIPA {
+  type EE = ipa_pc::EvaluationEngine<Self>;
+  type SecondaryEE = ipa_pc::EvaluationEngine<Dual<Self>>;
+
+  const BACKEND: PcsBackend = PcsBackend::Ipa;
+}
+
+impl PcsEngine for Bn256EngineKZG {
+  type EE = hyperkzg::EvaluationEngine<Bn256, Self>;
+  type SecondaryEE = ipa_pc::EvaluationEngine<Dual<Self>>;
+
+  const BACKEND: PcsBackend = PcsBackend::Kzg;
+}
+
+/// The setup of an application, generic over the engine of its backend, for
+/// [`BackendDecision::setup_with`]
+pub trait BackendSetup {
+  /// What the setup makes, e.g. the public parameters and the keys
+  type Output;
+
+  /// Sets up over `E1`, whose compressing SNARK commits with `E1::EE`
+  fn setup<E1: PcsEngine>(self) -> Self::Output;
+}
+
+#[cfg(all(test, feature = "prove"))]
+mod tests {
+  use super::*;
+  use crate::{supernova::circuit::TrivialCircuit, traits::snark::default_ck_hint};
+
+  /// A device of about 5µs per point of an MSM
+  const DEVICE: DeviceProfile = DeviceProfile { msm_ns_per_point: 5000.0, field_mul_ns: 30.0 };
+
+  fn backend(target: Target) -> PcsBackend { recommend(&target, &DEVICE, &DEVICE).decision.backend }
+
+  #[test]
+  fn test_recommend() {
+    // folding needs a curve, and KZG a trusted setup
+    assert_eq!(backend(Target::new(1 << 16)), PcsBackend::Ipa);
+    assert_eq!(backend(Target { trusted_setup: true, ..Target::new(1 << 16) }), PcsBackend::Kzg);
+
+    // without folding, hashing is the fastest, until its proofs are too large
+    let unfolded =
+      |n: usize| Target { folds: false, max_proof_bytes: Some(64 << 10), ..Target::new(n) };
+    assert_eq!(backend(Target { max_proof_bytes: None, ..unfolded(1 << 20) }), PcsBackend::Hash);
+    assert_eq!(backend(unfolded(1 << 8)), PcsBackend::Hash);
+    assert_eq!(backend(unfolded(1 << 20)), PcsBackend::Ipa);
+
+    // the linear verifier of IPA misses a tight verification budget
+    let target = Target {
+      trusted_setup: true,
+      max_verify_time: Some(Duration::from_millis(100)),
+      ..Target::new(1 << 20)
+    };
+    let recommendation = recommend(&target, &DEVICE, &DEVICE);
+    assert_eq!(recommendation.decision.backend, PcsBackend::Kzg);
+    assert!(recommendation.within_budget);
+    assert_eq!(recommendation.estimates.len(), 2);
+    let too_tight = Target { max_proof_bytes: Some(1), ..target };
+    assert!(!recommend(&too_tight, &DEVICE, &DEVICE).within_budget);
+  }
+
+  struct Backend;
+
+  impl BackendSetup for Backend {
+    type Output = PcsBackend;
+
+    fn setup<E1: PcsEngine>(self) -> PcsBackend { E1::BACKEND }
+  }
+
+  #[test]
+  fn test_backend_decision() {
+    let ipa = BackendDecision { backend: PcsBackend::Ipa, log_num_constraints: 16 };
+    let kzg = BackendDecision { backend: PcsBackend::Kzg, ..ipa };
+    let circuit = TrivialCircuit::default();
+    let pp = PublicParams::<Bn256EngineIPA>::setup(
+      &circuit,
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let unbound = pp.digest();
+
+    // the decision is part of the digest, once decoded too
+    assert!(kzg.bind(pp.clone()).is_err());
+    let pp = ipa.bind(pp).unwrap();
+    let bound = pp.digest();
+    assert_ne!(bound, unbound);
+    let decoded: PublicParams<Bn256EngineIPA> =
+      bincode::deserialize(&bincode::serialize(&pp).unwrap()).unwrap();
+    assert_eq!(decoded.backend(), Some(&ipa));
+    assert_eq!(decoded.digest(), bound);
+
+    ipa.check(&pp, &bound).unwrap();
+    assert_eq!(kzg.check(&pp, &bound), Err(NovaError::UnsupportedBackend));
+    assert_eq!(ipa.check(&pp, &unbound), Err(NovaError::UnsupportedBackend));
+
+    assert_eq!(ipa.setup_with(Backend), Ok(PcsBackend::Ipa));
+    assert_eq!(kzg.setup_with(Backend), Ok(PcsBackend::Kzg));
+    let hash = BackendDecision { backend: PcsBackend::Hash, ..ipa };
+    assert_eq!(hash.setup_with(Backend), Err(NovaError::UnsupportedBackend));
+  }
+}
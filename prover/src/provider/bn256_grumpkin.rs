@@ -11,11 +11,11 @@ use grumpkin_msm::{bn256 as bn256_msm, grumpkin as grumpkin_msm};
 use halo2curves::{bn256::G2Affine, CurveAffine, CurveExt};
 use num_bigint::BigInt;
 use num_traits::Num;
-use rayon::prelude::*;
 use sha3::Shake256;
 
 use crate::{
   impl_traits,
+  parallel::prelude::*,
   provider::{traits::DlogGroup, util::msm::cpu_best_msm},
   traits::{Group, PrimeFieldExt, TranscriptReprTrait},
 };
@@ -71,12 +71,15 @@ impl_traits!(
 #[cfg(test)]
 mod tests {
   use ff::Field;
+  use group::Group as _;
   use rand::thread_rng;
 
   use crate::provider::{
     bn256_grumpkin::{bn256, grumpkin},
+    tests::conformance::check_point_encodings,
     traits::DlogGroup,
     util::msm::cpu_best_msm,
+    Bn256EngineIPA, GrumpkinEngine,
   };
 
   #[test]
@@ -106,4 +109,17 @@ mod tests {
 
     assert_eq!(cpu_msm, gpu_msm);
   }
+
+  #[test]
+  fn test_point_encodings() {
+    check_point_encodings::<Bn256EngineIPA>();
+    check_point_encodings::<GrumpkinEngine>();
+
+    // the BN254 generator is (1, 2)
+    let g = bn256::Point::generator();
+    let expected_x = [vec![0; 31], vec![1]].concat();
+    assert_eq!(g.to_compressed_bytes(), [vec![2], expected_x.clone()].concat());
+    assert_eq!(g.to_affine_bytes(), [expected_x, vec![0; 31], vec![2]].concat());
+    assert_eq!(bn256::Point::identity().to_compressed_bytes(), vec![0]);
+  }
 }
@@ -0,0 +1,143 @@
+//! This module provides the maps from transcript outputs to challenges, i.e.
+//! implementations of `HashToField`.
+//!
+//! [`WideReduction`] is the historical map: the 64 squeezed bytes are read as a
+//! little-endian integer and reduced modulo the field order. [`ExpandMessageXmd`]
+//! follows RFC 9380 instead (`hash_to_field` with `count = 1` over
+//! `expand_message_xmd`), so that challenges can be re-derived by any
+//! implementation of the RFC.
+use core::marker::PhantomData;
+
+use digest::{core_api::BlockSizeUser, Digest};
+use ff::PrimeField;
+
+use crate::traits::{HashToField, PrimeFieldExt};
+
+/// Security parameter `k` of RFC 9380, in bits
+const SECURITY_BITS: usize = 128;
+
+/// Prefix used by RFC 9380 to shorten domain separation tags over 255 bytes
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// Reduces a 64-byte message modulo the field order, ignoring the domain separation tag
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WideReduction;
+
+impl<F: PrimeFieldExt> HashToField<F> for WideReduction {
+  fn hash_to_field(_dst: &[u8], msg: &[u8]) -> F { F::from_uniform(msg) }
+}
+
+/// RFC 9380 `hash_to_field` over `expand_message_xmd` with the hash function
+/// `H`, e.g. `sha2::Sha256` or `sha3::Keccak256`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExpandMessageXmd<H>(PhantomData<fn() -> H>);
+
+impl<F: PrimeField, H: Digest + BlockSizeUser> HashToField<F> for ExpandMessageXmd<H> {
+  fn hash_to_field(dst: &[u8], msg: &[u8]) -> F {
+    // L = ceil((ceil(log2(p)) + k) / 8)
+    let len = (F::NUM_BITS as usize + SECURITY_BITS).div_ceil(8);
+    let bytes = expand_message_xmd::<H>(msg, dst, len);
+
+    // OS2IP(bytes) mod p
+    let radix = F::from(256);
+    bytes.iter().fold(F::ZERO, |acc, b| acc * radix + F::from(u64::from(*b)))
+  }
+}
+
+/// RFC 9380 `expand_message_xmd`, producing `len_in_bytes` uniform bytes.
+///
+/// Panics if `len_in_bytes` exceeds 65535 bytes or 255 blocks of `H`.
+pub fn expand_message_xmd<H: Digest + BlockSizeUser>(
+  msg: &[u8],
+  dst: &[u8],
+  len_in_bytes: usize,
+) -> Vec<u8> {
+  let b_in_bytes = <H as Digest>::output_size();
+  let r_in_bytes = H::block_size();
+  let ell = len_in_bytes.div_ceil(b_in_bytes);
+  assert!(ell <= 255 && len_in_bytes <= 65535, "expand_message_xmd output too long");
+
+  let dst = if dst.len() > 255 {
+    H::new().chain_update(OVERSIZE_DST_PREFIX).chain_update(dst).finalize().to_vec()
+  } else {
+    dst.to_vec()
+  };
+  let dst_prime = [dst.as_slice(), &[dst.len() as u8]].concat();
+
+  let b_0 = H::new()
+    .chain_update(vec![0u8; r_in_bytes])
+    .chain_update(msg)
+    .chain_update((len_in_bytes as u16).to_be_bytes())
+    .chain_update([0u8])
+    .chain_update(&dst_prime)
+    .finalize();
+
+  let mut uniform_bytes = Vec::with_capacity(ell * b_in_bytes);
+  let mut b_i = H::new().chain_update(&b_0).chain_update([1u8]).chain_update(&dst_prime).finalize();
+  uniform_bytes.extend_from_slice(&b_i);
+  for i in 2..=ell {
+    let xored = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect::<Vec<_>>();
+    b_i = H::new().chain_update(xored).chain_update([i as u8]).chain_update(&dst_prime).finalize();
+    uniform_bytes.extend_from_slice(&b_i);
+  }
+  uniform_bytes.truncate(len_in_bytes);
+  uniform_bytes
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+  use sha2::Sha256;
+  use sha3::Keccak256;
+
+  use super::*;
+  use crate::provider::bn256_grumpkin::bn256;
+
+  // RFC 9380, appendix K.1
+  #[test]
+  fn test_expand_message_xmd_sha256() {
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+    assert_eq!(
+      hex::encode(expand_message_xmd::<Sha256>(b"", dst, 0x20)),
+      "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+    );
+  }
+
+  #[test]
+  fn test_expand_message_xmd_lengths() {
+    let long_dst = [7u8; 300];
+    for len in [1, 32, 48, 64, 100] {
+      assert_eq!(expand_message_xmd::<Keccak256>(b"msg", b"dst", len).len(), len);
+      assert_eq!(expand_message_xmd::<Keccak256>(b"msg", &long_dst, len).len(), len);
+    }
+    // the output is not a prefix of a longer one, since the length is hashed in
+    let short = expand_message_xmd::<Keccak256>(b"msg", b"dst", 32);
+    let long = expand_message_xmd::<Keccak256>(b"msg", b"dst", 64);
+    assert_ne!(short[..], long[..32]);
+  }
+
+  #[test]
+  fn test_hash_to_field() {
+    type F = bn256::Scalar;
+    let msg = [42u8; 64];
+
+    let wide: F = WideReduction::hash_to_field(b"c", &msg);
+    assert_eq!(wide, F::from_uniform(&msg));
+
+    let c1: F = ExpandMessageXmd::<Sha256>::hash_to_field(b"c1", &msg);
+    let c2: F = ExpandMessageXmd::<Sha256>::hash_to_field(b"c2", &msg);
+    assert_ne!(c1, c2);
+    assert_ne!(c1, wide);
+    assert_ne!(c1, F::ZERO);
+
+    // OS2IP is big-endian
+    let bytes = expand_message_xmd::<Sha256>(&msg, b"c1", 48);
+    let hi = F::from_uniform(
+      &[bytes[..16].iter().rev().copied().collect::<Vec<_>>(), vec![0; 48]].concat(),
+    );
+    let lo = F::from_uniform(
+      &[bytes[16..].iter().rev().copied().collect::<Vec<_>>(), vec![0; 32]].concat(),
+    );
+    assert_eq!(c1, hi * F::from(2).pow_vartime([256u64]) + lo);
+  }
+}
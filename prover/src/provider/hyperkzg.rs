@@ -19,17 +19,13 @@ use ff::{Field, PrimeFieldBits};
 use group::{prime::PrimeCurveAffine as _, Curve, Group as _};
 use itertools::Itertools as _;
 use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
-use rayon::{
-  iter::{
-    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
-  },
-  prelude::*,
-};
 use ref_cast::RefCast as _;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
   errors::NovaError,
+  integrations,
+  parallel::prelude::*,
   provider::{
     kzg_commitment::{KZGCommitmentEngine, KZGProverKey, KZGVerifierKey, UniversalKZGParam},
     pedersen::Commitment,
@@ -40,16 +36,20 @@ use crate::{
   traits::{
     commitment::{CommitmentEngineTrait, Len},
     evaluation::EvaluationEngineTrait,
-    Engine as NovaEngine, Group, TranscriptEngineTrait, TranscriptReprTrait,
+    validated, Engine as NovaEngine, Group, TranscriptEngineTrait, TranscriptReprTrait, Validate,
   },
 };
 
-/// Provides an implementation of a polynomial evaluation argument
+/// Provides an implementation of a polynomial evaluation argument, whose
+/// shape is checked when deserialized
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(bound(
-  serialize = "E::G1Affine: Serialize, E::Fr: Serialize",
-  deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
-))]
+#[serde(
+  remote = "Self",
+  bound(
+    serialize = "E::G1Affine: Serialize, E::Fr: Serialize",
+    deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
+  )
+)]
 pub struct EvaluationArgument<E: Engine> {
   comms: Vec<E::G1Affine>,
   evals: Vec<Vec<E::Fr>>,
@@ -58,6 +58,42 @@ pub struct EvaluationArgument<E: Engine> {
   C_H:   E::G1Affine,
 }
 
+impl<E: Engine> Validate for EvaluationArgument<E> {
+  fn validate(&self) -> Result<(), NovaError> {
+    // the evaluations at r, -r and r^2 of the polynomials P_0, ..., P_{\ell-1},
+    // of which P_1, ..., P_{\ell-1} are committed in `comms`
+    let ell = self.comms.len() + 1;
+    if ell >= usize::BITS as usize
+      || self.evals.len() != 3
+      || self.evals.iter().any(|evals_i| evals_i.len() != ell)
+      || self.R_x.len() != 3
+    {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(())
+  }
+}
+
+impl<E: Engine> Serialize for EvaluationArgument<E>
+where
+  E::G1Affine: Serialize,
+  E::Fr: Serialize,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for EvaluationArgument<E>
+where
+  E::G1Affine: Deserialize<'de>,
+  E::Fr: Deserialize<'de>,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    validated::<_, D>(Self::deserialize(deserializer)?)
+  }
+}
+
 /// Provides an implementation of a polynomial evaluation engine using KZG
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EvaluationEngine<E, NE> {
@@ -85,9 +121,9 @@ where
   fn compute_challenge(
     com: &[E::G1Affine],
     transcript: &mut impl TranscriptEngineTrait<NE>,
-  ) -> E::Fr {
+  ) -> Result<E::Fr, NovaError> {
     transcript.absorb(b"c", &com);
-    transcript.squeeze(b"c").unwrap()
+    transcript.squeeze(b"c")
   }
 
   // Compute challenge q = Hash(vk, C0, ..., C_{k-1}, u0, ...., u_{t-1},
@@ -96,15 +132,18 @@ where
   fn get_batch_challenge(
     v: &[Vec<E::Fr>],
     transcript: &mut impl TranscriptEngineTrait<NE>,
-  ) -> E::Fr {
+  ) -> Result<E::Fr, NovaError> {
     transcript.absorb(b"v", &v.iter().flatten().cloned().collect::<Vec<E::Fr>>().as_slice());
 
-    transcript.squeeze(b"r").unwrap()
+    transcript.squeeze(b"r")
   }
 
-  fn compute_a(c_q: &E::G1Affine, transcript: &mut impl TranscriptEngineTrait<NE>) -> E::Fr {
+  fn compute_a(
+    c_q: &E::G1Affine,
+    transcript: &mut impl TranscriptEngineTrait<NE>,
+  ) -> Result<E::Fr, NovaError> {
     transcript.absorb(b"C_Q", c_q);
-    transcript.squeeze(b"a").unwrap()
+    transcript.squeeze(b"a")
   }
 
   fn compute_pi_polynomials(hat_P: &[E::Fr], point: &[E::Fr]) -> Vec<Vec<E::Fr>> {
@@ -175,7 +214,7 @@ where
 
 impl<E, NE> EvaluationEngineTrait<NE> for EvaluationEngine<E, NE>
 where
-  E: MultiMillerLoop,
+  E: MultiMillerLoop + 'static,
   NE: NovaEngine<GE = E::G1, Scalar = E::Fr, CE = KZGCommitmentEngine<E>>,
   E::Fr: Serialize + DeserializeOwned,
   E::G1Affine: Serialize + DeserializeOwned,
@@ -189,6 +228,7 @@ where
   E::G1Affine: TranscriptReprTrait<E::G1>,
 {
   type EvaluationArgument = EvaluationArgument<E>;
+  type ProverCommitmentState = ();
   type ProverKey = KZGProverKey<E>;
   type VerifierKey = KZGVerifierKey<E>;
 
@@ -197,6 +237,8 @@ where
     UniversalKZGParam::trim(ck, len)
   }
 
+  fn commitment_state(_ck: &UniversalKZGParam<E>, _pk: &Self::ProverKey, _poly: &[E::Fr]) {}
+
   fn prove(
     ck: &UniversalKZGParam<E>,
     _pk: &Self::ProverKey,
@@ -209,7 +251,10 @@ where
     let x: Vec<E::Fr> = point.to_vec();
     let ell = x.len();
     let n = hat_P.len();
-    assert_eq!(n, 1 << ell); // Below we assume that n is a power of two
+    // Below we assume that n is a power of two
+    if ell == 0 || ell >= usize::BITS as usize || n != 1 << ell {
+      return Err(NovaError::InvalidInputLength);
+    }
 
     // Phase 1  -- create commitments com_1, ..., com_\ell
     // We do not compute final Pi (and its commitment as well since it is already
@@ -220,23 +265,23 @@ where
     let comms = Self::compute_commitments(ck, _C, &polys);
 
     // Phase 2
-    let r = Self::compute_challenge(&comms, transcript);
+    let r = Self::compute_challenge(&comms, transcript)?;
     let u = vec![r, -r, r * r];
     let evals = Self::compute_evals(&polys, &u);
 
     // Phase 3
     // Compute B(x) = f_0(x) + q * f_1(x) + ... + q^(k-1) * f_{k-1}(x)
-    let q = Self::get_batch_challenge(&evals, transcript);
+    let q = Self::get_batch_challenge(&evals, transcript)?;
     let batched_Pi: UniPoly<E::Fr> = polys.into_par_iter().map(UniPoly::new).rlc(&q);
 
     // Q(x), R(x) = P(x) / D(x), where D(x) = (x - r) * (x + r) * (x - r^2) = 1 *
     // x^3 - r^2 * x^2 - r^2 * x + r^4
     let D = UniPoly::new(vec![u[2] * u[2], -u[2], -u[2], E::Fr::from(1)]);
-    let (Q_x, R_x) = batched_Pi.divide_with_q_and_r(&D).unwrap();
+    let (Q_x, R_x) = batched_Pi.divide_with_q_and_r(&D).ok_or(NovaError::InternalError)?;
 
     let C_Q = <NE::CE as CommitmentEngineTrait<NE>>::commit(ck, &Q_x.coeffs).comm.to_affine();
 
-    let a = Self::compute_a(&C_Q, transcript);
+    let a = Self::compute_a(&C_Q, transcript)?;
 
     // K(x) = P(x) - Q(x) * D(a) - R(a), note that R(a) should be subtracted from a
     // free term of polynomial
@@ -258,12 +303,16 @@ where
     P_of_x: &E::Fr,
     pi: &Self::EvaluationArgument,
   ) -> Result<(), NovaError> {
-    let r = Self::compute_challenge(&pi.comms, transcript);
+    let r = Self::compute_challenge(&pi.comms, transcript)?;
     let u = [r, -r, r * r];
 
     if pi.evals.len() != u.len() {
       return Err(NovaError::ProofVerifyError);
     }
+    // each point is evaluated on the `point.len()` polynomials P_0, ..., P_{\ell-1}
+    if point.is_empty() || pi.evals.iter().any(|evals_i| evals_i.len() != point.len()) {
+      return Err(NovaError::ProofVerifyError);
+    }
     if pi.R_x.len() != u.len() {
       return Err(NovaError::ProofVerifyError);
     }
@@ -271,7 +320,7 @@ where
     let mut comms = pi.comms.to_vec();
     comms.insert(0, C.comm.to_affine());
 
-    let q = Self::get_batch_challenge(&pi.evals, transcript);
+    let q = Self::get_batch_challenge(&pi.evals, transcript)?;
     let R_x = UniPoly::new(pi.R_x.clone());
 
     let verification_failed = pi.evals.iter().zip_eq(u.iter()).any(|(evals_i, u_i)| {
@@ -328,18 +377,24 @@ where
     // D = (x - r) * (x + r) * (x - r^2) = 1 * x^3 - r^2 * x^2 - r^2 * x + r^4
     let D = UniPoly::new(vec![r_squared * r_squared, -r_squared, -r_squared, E::Fr::from(1)]);
 
-    let a = Self::compute_a(&C_Q, transcript);
+    let a = Self::compute_a(&C_Q, transcript)?;
 
     let C_K = C_P - (C_Q * D.evaluate(&a) + vk.g * R_x.evaluate(&a));
 
-    let pairing_inputs: Vec<(E::G1Affine, E::G2Prepared)> =
-      vec![(C_H, vk.beta_h.into()), ((C_H * (-a) - C_K).to_affine(), vk.h.into())];
+    let pairs = [(C_H, vk.beta_h), ((C_H * (-a) - C_K).to_affine(), vk.h)];
 
-    #[allow(clippy::map_identity)]
-    let pairing_input_refs = pairing_inputs.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+    // on a chain, the pairings are checked by its syscalls
+    let successful = integrations::syscalls::pairing_check::<E>(&pairs).unwrap_or_else(|| {
+      let pairing_inputs: Vec<(E::G1Affine, E::G2Prepared)> =
+        pairs.iter().map(|(p, q)| (*p, (*q).into())).collect();
 
-    let pairing_result = E::multi_miller_loop(pairing_input_refs.as_slice()).final_exponentiation();
-    let successful: bool = pairing_result.is_identity().into();
+      #[allow(clippy::map_identity)]
+      let pairing_input_refs = pairing_inputs.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+      let pairing_result =
+        E::multi_miller_loop(pairing_input_refs.as_slice()).final_exponentiation();
+      pairing_result.is_identity().into()
+    });
     if !successful {
       return Err(NovaError::ProofVerifyError);
     }
@@ -360,7 +415,7 @@ mod tests {
       keccak::Keccak256Transcript,
       util::{iterators::DoubleEndedIteratorExt as _, test_utils::prove_verify_from_num_vars},
     },
-    spartan::powers,
+    spartan::{polys::multilinear::MultilinearPolynomial, powers},
     traits::TranscriptEngineTrait,
     zip_with, CommitmentEngineTrait, CommitmentKey,
   };
@@ -572,6 +627,47 @@ mod tests {
       .unwrap();
   }
 
+  #[test]
+  fn test_hyperkzg_shplonk_malformed_proof() {
+    let n = 8;
+    let poly = (1..=n as u64).map(Fr::from).collect::<Vec<_>>();
+    let point = vec![Fr::from(4), Fr::from(3), Fr::from(8)];
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+
+    let ck: CommitmentKey<NE> =
+      <KZGCommitmentEngine<E> as CommitmentEngineTrait<NE>>::setup(b"test", n);
+    let ck = Arc::new(ck);
+    let (pk, vk) = EvaluationEngine::<E, NE>::setup(ck.clone());
+    let C: Commitment<NE> = KZGCommitmentEngine::commit(&ck, &poly);
+
+    let mut prover_transcript = Keccak256Transcript::new(b"TestEval");
+    let proof =
+      EvaluationEngine::<E, NE>::prove(&ck, &pk, &mut prover_transcript, &C, &poly, &point, &eval)
+        .unwrap();
+
+    // malformed arguments are rejected instead of panicking the verifier
+    let malformations: [fn(&mut EvaluationArgument<E>); 4] = [
+      |proof| proof.evals[0].truncate(2),
+      |proof| proof.evals[2].clear(),
+      |proof| proof.evals.iter_mut().for_each(Vec::clear),
+      |proof| proof.R_x.truncate(1),
+    ];
+    for malform in malformations {
+      let mut malformed = proof.clone();
+      malform(&mut malformed);
+      let mut verifier_transcript = Keccak256Transcript::<NE>::new(b"TestEval");
+      assert!(EvaluationEngine::<E, NE>::verify(
+        &vk,
+        &mut verifier_transcript,
+        &C,
+        &point,
+        &eval,
+        &malformed
+      )
+      .is_err());
+    }
+  }
+
   #[test]
   fn test_hyperkzg_shplonk_pcs_negative() {
     let n = 8;
@@ -3,19 +3,26 @@
 use core::iter;
 use std::{marker::PhantomData, sync::Arc};
 
-use ff::Field;
-use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
+use ff::{Field, PrimeField, PrimeFieldBits};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
+  constants::NUM_CHALLENGE_BITS,
   digest::SimpleDigestible,
   errors::{NovaError, PCSError},
-  provider::{pedersen::CommitmentKeyExtTrait, traits::DlogGroup, util::field::batch_invert},
+  parallel::{self, prelude::*},
+  provider::{
+    pedersen::CommitmentKeyExtTrait,
+    traits::DlogGroup,
+    util::{field::batch_invert_inline, inline::InlineVec},
+  },
+  secret::wipe,
   spartan::polys::eq::EqPolynomial,
   traits::{
-    commitment::{CommitmentEngineTrait, CommitmentTrait},
+    commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
     evaluation::EvaluationEngineTrait,
-    Engine, TranscriptEngineTrait, TranscriptReprTrait,
+    validated, Engine, ROConstants, ROTrait, TranscriptEngineTrait, TranscriptRepr,
+    TranscriptReprTrait, Validate,
   },
   zip_with, Commitment, CommitmentKey, CompressedCommitment, CE,
 };
@@ -50,6 +57,7 @@ where
   CommitmentKey<E>: CommitmentKeyExtTrait<E>,
 {
   type EvaluationArgument = InnerProductArgument<E>;
+  type ProverCommitmentState = ();
   type ProverKey = ProverKey<E>;
   type VerifierKey = VerifierKey<E>;
 
@@ -64,6 +72,8 @@ where
     (pk, vk)
   }
 
+  fn commitment_state(_ck: &CommitmentKey<E>, _pk: &Self::ProverKey, _poly: &[E::Scalar]) {}
+
   fn prove(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
@@ -73,10 +83,16 @@ where
     point: &[E::Scalar],
     eval: &E::Scalar,
   ) -> Result<Self::EvaluationArgument, NovaError> {
-    let u = InnerProductInstance::new(comm, &EqPolynomial::evals_from_points(point), eval);
+    let u = InnerProductInstance::new(comm, point, eval);
     let w = InnerProductWitness::new(poly);
 
-    InnerProductArgument::prove(ck.clone(), pk.ck_s.clone(), &u, &w, transcript)
+    InnerProductArgument::prove(
+      ck.clone(),
+      pk.ck_s.clone(),
+      &u,
+      &w,
+      &mut TranscriptChallenger(transcript),
+    )
   }
 
   /// A method to verify purported evaluations of a batch of polynomials
@@ -88,23 +104,93 @@ where
     eval: &E::Scalar,
     arg: &Self::EvaluationArgument,
   ) -> Result<(), NovaError> {
-    let u = InnerProductInstance::new(comm, &EqPolynomial::evals_from_points(point), eval);
+    if point.len() >= 32 {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let u = InnerProductInstance::new(comm, point, eval);
 
-    arg.verify(&vk.ck_v, vk.ck_s.clone(), 1 << point.len(), &u, transcript)?;
+    arg.verify(
+      &vk.ck_v,
+      vk.ck_s.clone(),
+      1 << point.len(),
+      &u,
+      &mut TranscriptChallenger(transcript),
+    )?;
 
     Ok(())
   }
 }
 
+impl<E> EvaluationEngine<E>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  CommitmentKey<E>: CommitmentKeyExtTrait<E>,
+{
+  /// The constant generators of the final check of the verifier for
+  /// polynomials with `num_vars` variables, i.e. the `2^num_vars` first
+  /// generators of the commitment key followed by the base for the inner
+  /// product
+  pub fn verifier_generators(
+    vk: &VerifierKey<E>,
+    num_vars: usize,
+  ) -> Vec<(E::Base, E::Base, bool)> {
+    let (ck, _) = (*vk.ck_v).clone().split_at(1 << num_vars);
+    ck.combine(&vk.ck_s).to_coordinates()
+  }
+
+  /// Proves the evaluation of `poly` at `point` with challenges squeezed from
+  /// the random oracle of `E` instead of a transcript, so that the argument
+  /// can be verified inside a circuit with [`crate::gadgets::check_ipa_msm`]
+  pub fn prove_for_circuit(
+    ck: &CommitmentKey<E>,
+    pk: &ProverKey<E>,
+    comm: &Commitment<E>,
+    poly: &[E::Scalar],
+    point: &[E::Scalar],
+    eval: &E::Scalar,
+  ) -> Result<InnerProductArgument<E>, NovaError> {
+    let u = InnerProductInstance::new(comm, point, eval);
+    let w = InnerProductWitness::new(poly);
+
+    InnerProductArgument::prove(ck.clone(), pk.ck_s.clone(), &u, &w, &mut OracleChallenger::new())
+  }
+
+  /// Replays the verification of `arg`, proven with
+  /// [`Self::prove_for_circuit`], up to its final check, which is returned as
+  /// a multi-scalar multiplication with the hints to check it inside a
+  /// circuit with [`crate::gadgets::check_ipa_msm`]
+  pub fn verifier_msm(
+    vk: &VerifierKey<E>,
+    comm: &Commitment<E>,
+    point: &[E::Scalar],
+    eval: &E::Scalar,
+    arg: &InnerProductArgument<E>,
+  ) -> Result<IPAVerifierMSM<E>, NovaError> {
+    if point.len() >= 32 {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let u = InnerProductInstance::new(comm, point, eval);
+
+    arg.verifier_msm(&vk.ck_v, vk.ck_s.clone(), 1 << point.len(), &u, &mut OracleChallenger::new())
+  }
+}
+
 fn inner_product<T: Field + Send + Sync>(a: &[T], b: &[T]) -> T {
-  zip_with!(par_iter, (a, b), |x, y| *x * y).sum()
+  zip_with!(par_iter, (a, b), |x, y| *x * y).with_min_len(parallel::min_len()).sum()
 }
 
-/// An inner product instance consists of a commitment to a vector `a` and
-/// another vector `b` and the claim that c = <a, b>.
+/// An inner product instance consists of a commitment to a vector `a`, a
+/// point `r`, and the claim that c = <a, b> for b = eq(r, .), the evaluations
+/// of the equality polynomial at `r`. The vector `b` is never materialized:
+/// the prover and the verifier use its tensor structure instead.
+#[derive(TranscriptRepr)]
+#[transcript(group = E::GE)]
 struct InnerProductInstance<E: Engine> {
   comm_a_vec: Commitment<E>,
-  b_vec:      Vec<E::Scalar>,
+  // the point is produced from the transcript, so it is not absorbed again
+  #[transcript(skip)]
+  point:      Vec<E::Scalar>,
   c:          E::Scalar,
 }
 
@@ -113,36 +199,176 @@ where
   E: Engine,
   E::GE: DlogGroup,
 {
-  fn new(comm_a_vec: &Commitment<E>, b_vec: &[E::Scalar], c: &E::Scalar) -> Self {
-    Self { comm_a_vec: *comm_a_vec, b_vec: b_vec.to_vec(), c: *c }
+  fn new(comm_a_vec: &Commitment<E>, point: &[E::Scalar], c: &E::Scalar) -> Self {
+    Self { comm_a_vec: *comm_a_vec, point: point.to_vec(), c: *c }
+  }
+
+  /// Absorbs the instance in `transcript`, unprefixed in legacy transcripts
+  fn absorb_in(&self, transcript: &mut E::TE) {
+    if <E::TE as TranscriptEngineTrait<E>>::LEGACY {
+      transcript.absorb(b"U", &LegacyInstance(self));
+    } else {
+      transcript.absorb(b"U", self);
+    }
   }
 }
 
-impl<E: Engine> TranscriptReprTrait<E::GE> for InnerProductInstance<E> {
+/// The encoding of an `InnerProductInstance` before length prefixes
+struct LegacyInstance<'a, E: Engine>(&'a InnerProductInstance<E>);
+
+impl<E: Engine> TranscriptReprTrait<E::GE> for LegacyInstance<'_, E> {
   fn to_transcript_bytes(&self) -> Vec<u8> {
-    // we do not need to include self.b_vec as in our context it is produced from
-    // the transcript
-    [self.comm_a_vec.to_transcript_bytes(), self.c.to_transcript_bytes()].concat()
+    [self.0.comm_a_vec.to_transcript_bytes(), self.0.c.to_transcript_bytes()].concat()
+  }
+}
+
+/// The source of the challenges of an `InnerProductArgument`
+trait Challenger<E: Engine> {
+  /// Absorbs the instance, and returns the challenge scaling the base for
+  /// the inner product
+  fn instance(&mut self, U: &InnerProductInstance<E>) -> Result<E::Scalar, NovaError>;
+
+  /// Absorbs the cross terms of a round, and returns its challenge
+  fn round(
+    &mut self,
+    L: &CompressedCommitment<E>,
+    R: &CompressedCommitment<E>,
+  ) -> Result<E::Scalar, NovaError>;
+}
+
+/// The challenges of the transcript of the verifier
+struct TranscriptChallenger<'a, E: Engine>(&'a mut E::TE);
+
+impl<E> Challenger<E> for TranscriptChallenger<'_, E>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  CommitmentKey<E>: CommitmentKeyExtTrait<E>,
+{
+  fn instance(&mut self, U: &InnerProductInstance<E>) -> Result<E::Scalar, NovaError> {
+    self.0.dom_sep(InnerProductArgument::<E>::protocol_name());
+    U.absorb_in(self.0);
+    self.0.squeeze(b"r")
+  }
+
+  fn round(
+    &mut self,
+    L: &CompressedCommitment<E>,
+    R: &CompressedCommitment<E>,
+  ) -> Result<E::Scalar, NovaError> {
+    self.0.absorb(b"L", L);
+    self.0.absorb(b"R", R);
+    self.0.squeeze(b"r")
+  }
+}
+
+/// The limbs of [`NUM_CHALLENGE_BITS`] bits of `scalar`, little-endian, in
+/// which the random oracle absorbs it
+fn scalar_limbs<E: Engine>(scalar: &E::Scalar) -> Vec<E::Base> {
+  let bits = scalar.to_le_bits().into_iter().take(E::Scalar::NUM_BITS as usize).collect::<Vec<_>>();
+  bits
+    .chunks(NUM_CHALLENGE_BITS)
+    .map(|limb| {
+      limb.iter().rev().fold(E::Base::ZERO, |acc, b| acc.double() + E::Base::from(u64::from(*b)))
+    })
+    .collect()
+}
+
+/// The coordinates of `comm` as the random oracle absorbs them
+fn coordinates<E: Engine>(comm: &Commitment<E>) -> [E::Base; 3] {
+  let (x, y, is_infinity) = comm.to_coordinates();
+  [x, y, E::Base::from(u64::from(is_infinity))]
+}
+
+/// The challenges of an argument checked inside a circuit (see
+/// [`crate::gadgets::check_ipa_msm`]), squeezed from the random oracle of
+/// `E`: the first from the commitment, the point and the evaluation of the
+/// instance, and each next one from the previous one and the cross terms of
+/// its round, by their coordinates
+struct OracleChallenger<E: Engine> {
+  constants: ROConstants<E>,
+  challenge: E::Scalar,
+}
+
+impl<E: Engine> OracleChallenger<E> {
+  fn new() -> Self { Self { constants: ROConstants::<E>::default(), challenge: E::Scalar::ZERO } }
+
+  /// Squeezes the next challenge from a random oracle absorbing `elements`
+  fn squeeze(&mut self, elements: &[E::Base]) -> E::Scalar {
+    let mut ro = E::RO::new(self.constants.clone(), elements.len());
+    elements.iter().for_each(|e| ro.absorb(*e));
+    self.challenge = ro.squeeze(NUM_CHALLENGE_BITS);
+    self.challenge
+  }
+}
+
+impl<E: Engine> Challenger<E> for OracleChallenger<E> {
+  fn instance(&mut self, U: &InnerProductInstance<E>) -> Result<E::Scalar, NovaError> {
+    let mut elements = coordinates::<E>(&U.comm_a_vec).to_vec();
+    for scalar in U.point.iter().chain(iter::once(&U.c)) {
+      elements.extend(scalar_limbs::<E>(scalar));
+    }
+    Ok(self.squeeze(&elements))
+  }
+
+  fn round(
+    &mut self,
+    L: &CompressedCommitment<E>,
+    R: &CompressedCommitment<E>,
+  ) -> Result<E::Scalar, NovaError> {
+    // the previous challenge holds in a single limb
+    let mut elements = scalar_limbs::<E>(&self.challenge)[..1].to_vec();
+    elements.extend(coordinates::<E>(&Commitment::<E>::decompress(L)?));
+    elements.extend(coordinates::<E>(&Commitment::<E>::decompress(R)?));
+    Ok(self.squeeze(&elements))
   }
 }
 
-struct InnerProductWitness<E: Engine> {
-  a_vec: Vec<E::Scalar>,
+/// The witness of an inner product argument, borrowing the vector `a` from
+/// its owner, which wipes it (see [`crate::secret`]); the folds of the vector
+/// are wiped by the argument once used
+struct InnerProductWitness<'a, E: Engine> {
+  a_vec: &'a [E::Scalar],
 }
 
-impl<E: Engine> InnerProductWitness<E> {
-  fn new(a_vec: &[E::Scalar]) -> Self { Self { a_vec: a_vec.to_vec() } }
+impl<'a, E: Engine> InnerProductWitness<'a, E> {
+  fn new(a_vec: &'a [E::Scalar]) -> Self { Self { a_vec } }
 }
 
-/// An inner product argument
+/// The bound on the number of rounds of an inner product argument
+const MAX_ROUNDS: usize = 32;
+
+/// An inner product argument, whose rounds are checked to be fewer than
+/// [`MAX_ROUNDS`] when deserialized
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(bound = "")]
+#[serde(remote = "Self", bound = "")]
 pub struct InnerProductArgument<E: Engine> {
   pub(in crate::provider) L_vec: Vec<CompressedCommitment<E>>,
   pub(in crate::provider) R_vec: Vec<CompressedCommitment<E>>,
   pub(in crate::provider) a_hat: E::Scalar,
 }
 
+impl<E: Engine> Validate for InnerProductArgument<E> {
+  fn validate(&self) -> Result<(), NovaError> {
+    if self.L_vec.len() >= MAX_ROUNDS || self.L_vec.len() != self.R_vec.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(())
+  }
+}
+
+impl<E: Engine> Serialize for InnerProductArgument<E> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for InnerProductArgument<E> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    validated::<_, D>(Self::deserialize(deserializer)?)
+  }
+}
+
 impl<E> InnerProductArgument<E>
 where
   E: Engine,
@@ -151,193 +377,373 @@ where
 {
   const fn protocol_name() -> &'static [u8] { b"IPA" }
 
-  fn prove(
+  fn prove<C: Challenger<E>>(
     ck: CommitmentKey<E>,
     mut ck_c: CommitmentKey<E>,
     U: &InnerProductInstance<E>,
-    W: &InnerProductWitness<E>,
-    transcript: &mut E::TE,
+    W: &InnerProductWitness<'_, E>,
+    challenger: &mut C,
   ) -> Result<Self, NovaError> {
-    transcript.dom_sep(Self::protocol_name());
-
-    let (ck, _) = ck.split_at(U.b_vec.len());
-
-    if U.b_vec.len() != W.a_vec.len() {
+    let n = W.a_vec.len();
+    if U.point.len() >= usize::BITS as usize || n != 1 << U.point.len() || n > ck.length() {
       return Err(NovaError::InvalidInputLength);
     }
 
-    // absorb the instance in the transcript
-    transcript.absorb(b"U", U);
+    let (ck, _) = ck.split_at(n);
 
-    // sample a random base for committing to the inner product
-    let r = transcript.squeeze(b"r")?;
+    // absorb the instance in the transcript, and sample a random base for
+    // committing to the inner product
+    let r = challenger.instance(U)?;
     ck_c.scale(&r);
 
-    // a closure that executes a step of the recursive inner product argument
+    // a closure that executes a step of the recursive inner product argument.
+    // The vector b is b_scale * eq((x, point), .), so its left half is
+    // b_scale * (1 - x) * eq(point, .) and its right half is
+    // b_scale * x * eq(point, .), and it folds into another such vector.
     let prove_inner = |a_vec: &[E::Scalar],
-                       b_vec: &[E::Scalar],
+                       b_scale: E::Scalar,
+                       x: E::Scalar,
+                       point: &[E::Scalar],
                        ck: CommitmentKey<E>,
-                       transcript: &mut E::TE|
+                       challenger: &mut C|
      -> Result<
       (
         CompressedCommitment<E>,
         CompressedCommitment<E>,
         Vec<E::Scalar>,
-        Vec<E::Scalar>,
+        E::Scalar,
         CommitmentKey<E>,
       ),
       NovaError,
     > {
       let n = a_vec.len();
       let (ck_L, ck_R) = ck.split_at(n / 2);
-
-      let c_L = inner_product(&a_vec[0..n / 2], &b_vec[n / 2..n]);
-      let c_R = inner_product(&a_vec[n / 2..n], &b_vec[0..n / 2]);
-
-      let L = CE::<E>::commit(
-        &ck_R.combine(&ck_c),
-        &a_vec[0..n / 2].iter().chain(iter::once(&c_L)).copied().collect::<Vec<E::Scalar>>(),
-      )
-      .compress();
-      let R = CE::<E>::commit(
-        &ck_L.combine(&ck_c),
-        &a_vec[n / 2..n].iter().chain(iter::once(&c_R)).copied().collect::<Vec<E::Scalar>>(),
-      )
-      .compress();
-
-      transcript.absorb(b"L", &L);
-      transcript.absorb(b"R", &R);
-
-      let r = transcript.squeeze(b"r")?;
-      let r_inverse = r.invert().unwrap();
-
-      // fold the left half and the right half
-      let a_vec_folded =
-        zip_with!((a_vec[0..n / 2].par_iter(), a_vec[n / 2..n].par_iter()), |a_L, a_R| *a_L * r
-          + r_inverse * *a_R)
-        .collect::<Vec<E::Scalar>>();
-
-      let b_vec_folded =
-        zip_with!((b_vec[0..n / 2].par_iter(), b_vec[n / 2..n].par_iter()), |b_L, b_R| *b_L
-          * r_inverse
-          + r * *b_R)
-        .collect::<Vec<E::Scalar>>();
-
-      let ck_folded = CommitmentKeyExtTrait::fold(&ck_L, &ck_R, &r_inverse, &r);
-
-      Ok((L, R, a_vec_folded, b_vec_folded, ck_folded))
+      let (a_L, a_R) = a_vec.split_at(n / 2);
+      let eq = EqPolynomial::evals_from_points(point);
+      let (b_L_scale, b_R_scale) = (b_scale * (E::Scalar::ONE - x), b_scale * x);
+
+      // L and R are independent multi-scalar multiplications
+      let commit_cross_term = |ck: &CommitmentKey<E>, a: &[E::Scalar], b_scale: E::Scalar| {
+        let c = inner_product(a, &eq) * b_scale;
+        let mut scalars = a.iter().chain(iter::once(&c)).copied().collect::<Vec<E::Scalar>>();
+        let comm = CE::<E>::commit(&ck.combine(&ck_c), &scalars).compress();
+        wipe(&mut scalars);
+        comm
+      };
+      let (L, R) = parallel::join(
+        || commit_cross_term(&ck_R, a_L, b_R_scale),
+        || commit_cross_term(&ck_L, a_R, b_L_scale),
+      );
+
+      let r = challenger.round(&L, &R)?;
+      let r_inverse = Option::<E::Scalar>::from(r.invert()).ok_or(NovaError::InternalError)?;
+
+      // fold the left half and the right half, folding the generators
+      // alongside the vector
+      let (a_vec_folded, ck_folded) = parallel::join(
+        || {
+          zip_with!((a_L.par_iter(), a_R.par_iter()), |a_L, a_R| *a_L * r + r_inverse * *a_R)
+            .with_min_len(parallel::min_len())
+            .collect::<Vec<E::Scalar>>()
+        },
+        || CommitmentKeyExtTrait::fold(&ck_L, &ck_R, &r_inverse, &r),
+      );
+      let b_scale_folded = b_L_scale * r_inverse + b_R_scale * r;
+
+      Ok((L, R, a_vec_folded, b_scale_folded, ck_folded))
     };
 
     // two vectors to hold the logarithmic number of group elements
     let mut L_vec: Vec<CompressedCommitment<E>> = Vec::new();
     let mut R_vec: Vec<CompressedCommitment<E>> = Vec::new();
 
-    // we create mutable copies of vectors and generators
-    let mut a_vec = W.a_vec.to_vec();
-    let mut b_vec = U.b_vec.to_vec();
+    // the folded vector a, which is W.a_vec before the first round
+    let mut a_vec: Option<Vec<E::Scalar>> = None;
+    let mut b_scale = E::Scalar::ONE;
     let mut ck = ck;
-    for _i in 0..usize::try_from(U.b_vec.len().ilog2()).unwrap() {
-      let (L, R, a_vec_folded, b_vec_folded, ck_folded) =
-        prove_inner(&a_vec, &b_vec, ck, transcript)?;
+    for (i, x) in U.point.iter().enumerate() {
+      let a = a_vec.as_deref().unwrap_or(W.a_vec);
+      let (L, R, a_vec_folded, b_scale_folded, ck_folded) =
+        prove_inner(a, b_scale, *x, &U.point[i + 1..], ck, challenger)?;
       L_vec.push(L);
       R_vec.push(R);
 
-      a_vec = a_vec_folded;
-      b_vec = b_vec_folded;
+      if let Some(mut a) = a_vec.replace(a_vec_folded) {
+        wipe(&mut a);
+      }
+      b_scale = b_scale_folded;
       ck = ck_folded;
     }
 
-    Ok(Self { L_vec, R_vec, a_hat: a_vec[0] })
+    let a_hat = a_vec.as_deref().unwrap_or(W.a_vec)[0];
+    if let Some(mut a) = a_vec {
+      wipe(&mut a);
+    }
+    Ok(Self { L_vec, R_vec, a_hat })
   }
 
-  fn verify(
+  /// Replays the transcript of the verifier, and computes the scalars of its
+  /// final check
+  fn reduce(
     &self,
-    ck: &CommitmentKey<E>,
     mut ck_c: CommitmentKey<E>,
     n: usize,
     U: &InnerProductInstance<E>,
-    transcript: &mut E::TE,
-  ) -> Result<(), NovaError> {
-    let (ck, _) = ck.clone().split_at(U.b_vec.len());
-
-    transcript.dom_sep(Self::protocol_name());
-    if U.b_vec.len() != n
-      || n != (1 << self.L_vec.len())
+    challenger: &mut impl Challenger<E>,
+  ) -> Result<Reduction<E>, NovaError> {
+    if self.L_vec.len() >= MAX_ROUNDS
+      || num_rounds(n) != Some(self.L_vec.len())
+      || U.point.len() != self.L_vec.len()
       || self.L_vec.len() != self.R_vec.len()
-      || self.L_vec.len() >= 32
     {
       return Err(NovaError::InvalidInputLength);
     }
 
-    // absorb the instance in the transcript
-    transcript.absorb(b"U", U);
-
-    // sample a random base for committing to the inner product
-    let r = transcript.squeeze(b"r")?;
-    ck_c.scale(&r);
+    // absorb the instance in the transcript, and sample a random base for
+    // committing to the inner product
+    let r_c = challenger.instance(U)?;
+    ck_c.scale(&r_c);
 
-    let P = U.comm_a_vec + CE::<E>::commit(&ck_c, &[U.c]);
-
-    // compute a vector of public coins using self.L_vec and self.R_vec
-    let r = (0..self.L_vec.len())
-      .map(|i| {
-        transcript.absorb(b"L", &self.L_vec[i]);
-        transcript.absorb(b"R", &self.R_vec[i]);
-        transcript.squeeze(b"r")
-      })
-      .collect::<Result<Vec<E::Scalar>, NovaError>>()?;
+    // compute a vector of public coins using self.L_vec and self.R_vec, the
+    // vectors of the rounds being stored inline
+    let mut r = InlineVec::<E::Scalar, MAX_ROUNDS>::new();
+    for (L, R) in self.L_vec.iter().zip(&self.R_vec) {
+      r.push(challenger.round(L, R)?)?;
+    }
 
     // precompute scalars necessary for verification
-    let r_square: Vec<E::Scalar> =
-      (0..self.L_vec.len()).into_par_iter().map(|i| r[i] * r[i]).collect();
-    let r_inverse = batch_invert(r.clone())?;
-    let r_inverse_square: Vec<E::Scalar> =
-      (0..self.L_vec.len()).into_par_iter().map(|i| r_inverse[i] * r_inverse[i]).collect();
+    let r_square = r.map(|r_i| r_i.square());
+    let r_inverse = batch_invert_inline(r)?;
+    let r_inverse_square = r_inverse.map(|r_inverse_i| r_inverse_i.square());
+
+    let b_hat = eq_tensor_inner_product(&U.point, &r, &r_inverse);
 
     // compute the vector with the tensor structure
-    let s = {
-      let mut s = vec![E::Scalar::ZERO; n];
-      s[0] = {
-        let mut v = E::Scalar::ONE;
-        for r_inverse_i in r_inverse {
-          v *= r_inverse_i;
-        }
-        v
-      };
-      for i in 1..n {
-        let pos_in_r = (31 - (i as u32).leading_zeros()) as usize;
-        s[i] = s[i - (1 << pos_in_r)] * r_square[(self.L_vec.len() - 1) - pos_in_r];
-      }
-      s
-    };
+    let s = challenge_tensor(&r_inverse, &r_square, n)?;
+
+    Ok(Reduction { r_c, ck_c, r_square, r_inverse_square, s, b_hat })
+  }
+
+  fn verify(
+    &self,
+    ck: &CommitmentKey<E>,
+    ck_c: CommitmentKey<E>,
+    n: usize,
+    U: &InnerProductInstance<E>,
+    challenger: &mut impl Challenger<E>,
+  ) -> Result<(), NovaError> {
+    let Reduction { ck_c, r_square, r_inverse_square, s, b_hat, .. } =
+      self.reduce(ck_c, n, U, challenger)?;
+
+    if ck.length() < n {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (ck, _) = ck.clone().split_at(n);
 
     let ck_hat = {
       let c = CE::<E>::commit(&ck, &s).compress();
       CommitmentKey::<E>::reinterpret_commitments_as_ck(&[c])?
     };
 
-    let b_hat = inner_product(&U.b_vec, &s);
+    // P_hat = sum_i r_i^2 * L_i + sum_i r_i^-2 * R_i + P, where P = comm + c * ck_c,
+    // must equal a_hat * ck_hat + a_hat * b_hat * ck_c: we check that their
+    // difference is the identity with a single multi-scalar multiplication, by
+    // negating the scalars of the right-hand side
+    let ck_folded = {
+      let ck_L = CommitmentKey::<E>::reinterpret_commitments_as_ck(&self.L_vec)?;
+      let ck_R = CommitmentKey::<E>::reinterpret_commitments_as_ck(&self.R_vec)?;
+      let ck_comm = CommitmentKey::<E>::reinterpret_commitments_as_ck(&[U.comm_a_vec.compress()])?;
+      ck_L.combine(&ck_R).combine(&ck_comm).combine(&ck_hat).combine(&ck_c)
+    };
+    let scalars = r_square
+      .iter()
+      .chain(r_inverse_square.iter())
+      .copied()
+      .chain([E::Scalar::ONE, -self.a_hat, U.c - self.a_hat * b_hat])
+      .collect::<Vec<E::Scalar>>();
+
+    if CE::<E>::commit(&ck_folded, &scalars) == Commitment::<E>::default() {
+      Ok(())
+    } else {
+      Err(NovaError::PCSError(PCSError::InvalidPCS))
+    }
+  }
 
-    let P_hat = {
-      let ck_folded = {
-        let ck_L = CommitmentKey::<E>::reinterpret_commitments_as_ck(&self.L_vec)?;
-        let ck_R = CommitmentKey::<E>::reinterpret_commitments_as_ck(&self.R_vec)?;
-        let ck_P = CommitmentKey::<E>::reinterpret_commitments_as_ck(&[P.compress()])?;
-        ck_L.combine(&ck_R).combine(&ck_P)
-      };
+  /// Replays the verification of `self` up to its final check, which is
+  /// returned as a multi-scalar multiplication
+  fn verifier_msm(
+    &self,
+    ck: &CommitmentKey<E>,
+    ck_c: CommitmentKey<E>,
+    n: usize,
+    U: &InnerProductInstance<E>,
+    challenger: &mut impl Challenger<E>,
+  ) -> Result<IPAVerifierMSM<E>, NovaError> {
+    let Reduction { r_c, ck_c, r_square, r_inverse_square, s, b_hat } =
+      self.reduce(ck_c, n, U, challenger)?;
+    if ck.length() < n {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (ck, _) = ck.clone().split_at(n);
+
+    // P_hat = a_hat * (<s, G> + b_hat * r_c * H), where P_hat = sum_i r_i^2 * L_i
+    // + sum_i r_i^-2 * R_i + comm + c * r_c * H, so we move everything to the left
+    let generator_scalars = s
+      .iter()
+      .map(|s_i| -(self.a_hat * s_i))
+      .chain(iter::once((U.c - self.a_hat * b_hat) * r_c))
+      .collect();
+    let to_coordinates = |c: &[CompressedCommitment<E>]| {
+      c.iter()
+        .map(|c| Commitment::<E>::decompress(c).map(|c| c.to_coordinates()))
+        .collect::<Result<Vec<_>, NovaError>>()
+    };
+
+    // the hints of the check inside a circuit
+    let R_hat = zip_with!(iter, (self.R_vec, r_inverse_square), |R, r_inverse_square_i| {
+      Commitment::<E>::decompress(R).map(|R| (R * *r_inverse_square_i).to_coordinates())
+    })
+    .collect::<Result<Vec<_>, NovaError>>()?;
+    let G_hat = CE::<E>::commit(&ck, &s).to_coordinates();
+    let H_hat = CE::<E>::commit(&ck_c, &[b_hat]).to_coordinates();
+
+    Ok(IPAVerifierMSM {
+      L_vec: to_coordinates(&self.L_vec)?,
+      R_vec: to_coordinates(&self.R_vec)?,
+      L_scalars: r_square.to_vec(),
+      R_scalars: r_inverse_square.to_vec(),
+      generator_scalars,
+      a_hat: self.a_hat,
+      R_hat,
+      G_hat,
+      H_hat,
+    })
+  }
+}
+
+/// The number of rounds of an inner product argument over `n` entries, if
+/// `n` is a power of two
+fn num_rounds(n: usize) -> Option<usize> {
+  n.is_power_of_two().then(|| n.trailing_zeros() as usize)
+}
 
-      CE::<E>::commit(
-        &ck_folded,
-        &r_square
-          .iter()
-          .chain(r_inverse_square.iter())
-          .chain(iter::once(&E::Scalar::ONE))
-          .copied()
-          .collect::<Vec<E::Scalar>>(),
-      )
+/// The inner product `<b, s>` of `b = eq(point, .)` and the tensor `s` of the
+/// challenges `r` (see [`challenge_tensor`]), in O(log n) time and memory:
+/// both are tensor products over the rounds, of `(1 - x_j, x_j)` and
+/// `(r_j^-1, r_j)` respectively, so their inner product is a product, and
+/// neither vector of `n` entries is materialized
+fn eq_tensor_inner_product<F: Field>(point: &[F], r: &[F], r_inverse: &[F]) -> F {
+  zip_with!(iter, (point, r, r_inverse), |x, r_j, r_inverse_j| {
+    (F::ONE - x) * r_inverse_j + *x * r_j
+  })
+  .product()
+}
+
+/// The tensor `s` of the challenges of the rounds, of `n` entries: the i-th
+/// entry is the product over the rounds of `r_j` if the bit of i for round j
+/// is set, and of `r_j^-1` otherwise, the first round being the most
+/// significant bit. It is built by doubling, from the product of the
+/// `r_j^-1`, so that no entry is indexed from the challenges; the numbers of
+/// challenges are checked against `n` and [`MAX_ROUNDS`] first.
+fn challenge_tensor<F: Field>(
+  r_inverse: &[F],
+  r_square: &[F],
+  n: usize,
+) -> Result<Vec<F>, NovaError> {
+  let rounds = r_square.len();
+  if rounds >= MAX_ROUNDS || r_inverse.len() != rounds || num_rounds(n) != Some(rounds) {
+    return Err(NovaError::InvalidInputLength);
+  }
+
+  let mut s = Vec::with_capacity(n);
+  s.push(r_inverse.iter().product());
+  // the last round is the least significant bit: s[i + 2^k] = s[i] * r_j^2
+  for r_square_j in r_square.iter().rev() {
+    let len = s.len();
+    s.extend_from_within(..);
+    s[len..].par_iter_mut().with_min_len(parallel::min_len()).for_each(|s_i| *s_i *= r_square_j);
+  }
+  Ok(s)
+}
+
+/// The verifier's state after replaying the transcript of an
+/// `InnerProductArgument`
+struct Reduction<E: Engine> {
+  r_c:              E::Scalar, // the challenge scaling the base for the inner product
+  ck_c:             CommitmentKey<E>, // the scaled base for the inner product
+  r_square:         InlineVec<E::Scalar, MAX_ROUNDS>,
+  r_inverse_square: InlineVec<E::Scalar, MAX_ROUNDS>,
+  s:                Vec<E::Scalar>, // the tensor of the challenges
+  b_hat:            E::Scalar,
+}
+
+/// The final check of the verifier of an `InnerProductArgument` for a
+/// commitment `comm`, as a multi-scalar multiplication:
+/// `comm + sum_i L_scalars[i] * L_vec[i] + sum_i R_scalars[i] * R_vec[i]
+/// + sum_i generator_scalars[i] * generators[i]` is the identity, where
+/// `generators` are given by `EvaluationEngine::verifier_generators`.
+///
+/// Points are given by their coordinates, so that the check can be done
+/// inside a circuit over `E::Base` with [`crate::gadgets::check_ipa_msm`],
+/// which recomputes the scalars from the challenges instead, and takes the
+/// final message `a_hat` of the argument with the hints `R_hat`, the
+/// `r_i^-2 * R_vec[i]`, `G_hat`, the `<s, G>` of the generators but the
+/// last, and `H_hat`, `b_hat * r_c * H` of the last one, `H`, which it checks
+/// against the challenges.
+#[derive(Clone, Debug)]
+pub struct IPAVerifierMSM<E: Engine> {
+  pub L_vec:             Vec<(E::Base, E::Base, bool)>,
+  pub R_vec:             Vec<(E::Base, E::Base, bool)>,
+  pub L_scalars:         Vec<E::Scalar>,
+  pub R_scalars:         Vec<E::Scalar>,
+  pub generator_scalars: Vec<E::Scalar>,
+  pub a_hat:             E::Scalar,
+  pub R_hat:             Vec<(E::Base, E::Base, bool)>,
+  pub G_hat:             (E::Base, E::Base, bool),
+  pub H_hat:             (E::Base, E::Base, bool),
+}
+
+impl<E> IPAVerifierMSM<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// Checks the multi-scalar multiplication natively
+  pub fn check(
+    &self,
+    generators: &[(E::Base, E::Base, bool)],
+    comm: (E::Base, E::Base, bool),
+  ) -> Result<(), NovaError> {
+    let to_point = |(x, y, is_infinity): &(E::Base, E::Base, bool)| {
+      if *is_infinity {
+        Ok(<E::GE as group::Group>::identity())
+      } else {
+        E::GE::from_coordinates(*x, *y).ok_or(NovaError::DecompressionError)
+      }
     };
+    if generators.len() != self.generator_scalars.len()
+      || self.L_vec.len() != self.L_scalars.len()
+      || self.R_vec.len() != self.R_scalars.len()
+    {
+      return Err(NovaError::InvalidInputLength);
+    }
 
-    if P_hat == CE::<E>::commit(&ck_hat.combine(&ck_c), &[self.a_hat, self.a_hat * b_hat]) {
+    let points = iter::once(&comm)
+      .chain(self.L_vec.iter())
+      .chain(self.R_vec.iter())
+      .chain(generators.iter())
+      .map(to_point)
+      .collect::<Result<Vec<_>, _>>()?;
+    let scalars = iter::once(&E::Scalar::ONE)
+      .chain(self.L_scalars.iter())
+      .chain(self.R_scalars.iter())
+      .chain(self.generator_scalars.iter());
+    let sum = points
+      .iter()
+      .zip(scalars)
+      .fold(<E::GE as group::Group>::identity(), |acc, (p, s)| acc + *p * s);
+
+    if bool::from(group::Group::is_identity(&sum)) {
       Ok(())
     } else {
       Err(NovaError::PCSError(PCSError::InvalidPCS))
@@ -347,10 +753,54 @@ where
 
 #[cfg(test)]
 mod test {
-  use crate::provider::{
-    ipa_pc::EvaluationEngine, util::test_utils::prove_verify_from_num_vars, GrumpkinEngine,
+  use ff::Field;
+  use halo2curves::bn256::Fr;
+
+  use super::{challenge_tensor, eq_tensor_inner_product, MAX_ROUNDS};
+  use crate::{
+    errors::NovaError,
+    provider::{
+      ipa_pc::EvaluationEngine, util::test_utils::prove_verify_from_num_vars, GrumpkinEngine,
+    },
+    spartan::polys::eq::EqPolynomial,
   };
 
+  #[test]
+  fn test_challenge_tensor() {
+    let r = [Fr::from(2), Fr::from(3), Fr::from(5)];
+    let r_inverse = r.map(|r_j| r_j.invert().unwrap());
+    let r_square = r.map(|r_j| r_j.square());
+    let s = challenge_tensor(&r_inverse, &r_square, 8).unwrap();
+    for (i, s_i) in s.iter().enumerate() {
+      let expected: Fr =
+        (0..3).map(|j| if (i >> (2 - j)) & 1 == 1 { r[j] } else { r_inverse[j] }).product();
+      assert_eq!(*s_i, expected);
+    }
+
+    // the lengths must match the number of rounds, whatever they are
+    assert_eq!(challenge_tensor(&r_inverse, &r_square, 4), Err(NovaError::InvalidInputLength));
+    assert_eq!(challenge_tensor(&r_inverse, &r_square, 9), Err(NovaError::InvalidInputLength));
+    assert_eq!(challenge_tensor(&r_inverse, &r_square, 0), Err(NovaError::InvalidInputLength));
+    assert_eq!(challenge_tensor(&r_inverse[1..], &r_square, 8), Err(NovaError::InvalidInputLength));
+    let many = vec![Fr::ONE; MAX_ROUNDS];
+    assert_eq!(challenge_tensor(&many, &many, usize::MAX), Err(NovaError::InvalidInputLength));
+    assert_eq!(challenge_tensor(&many, &many, 1 << MAX_ROUNDS), Err(NovaError::InvalidInputLength));
+  }
+
+  #[test]
+  fn test_eq_tensor_inner_product() {
+    let point = [Fr::from(7), Fr::from(11), Fr::from(13), Fr::from(17)];
+    let r = [Fr::from(2), Fr::from(3), Fr::from(5), Fr::from(19)];
+    let r_inverse = r.map(|r_j| r_j.invert().unwrap());
+    let r_square = r.map(|r_j| r_j.square());
+
+    // the product over the rounds is the inner product of the materialized vectors
+    let b = EqPolynomial::evals_from_points(&point);
+    let s = challenge_tensor(&r_inverse, &r_square, 16).unwrap();
+    let b_hat: Fr = b.iter().zip(s.iter()).map(|(b_i, s_i)| *b_i * s_i).sum();
+    assert_eq!(eq_tensor_inner_product(&point, &r, &r_inverse), b_hat);
+  }
+
   #[test]
   fn test_multiple_polynomial_size() {
     for num_vars in [4, 5, 6] {
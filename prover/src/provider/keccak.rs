@@ -1,30 +1,59 @@
 //! This module provides an implementation of `TranscriptEngineTrait` using
-//! keccak256
+//! keccak256, with a pluggable map from its output to challenges.
+//!
+//! From `TRANSCRIPT_VERSION` 1, the transcript absorbs its version under a
+//! domain separator, and every label and message prefixed by its length, with
+//! messages tagged as absorbed or as domain separators, so that no two
+//! sequences of messages absorb the same bytes: the labels of the messages
+//! are their type tags, and the bytes of `L_vec`/`R_vec` or of an instance can
+//! no longer run into the next label. `LEGACY_TRANSCRIPT_VERSION` concatenates
+//! labels and messages as before, for verifying proofs made before versioning,
+//! e.g. with the engines of `provider::legacy`.
 use core::marker::PhantomData;
 
 use sha3::{Digest, Keccak256};
 
 use crate::{
   errors::NovaError,
-  traits::{Engine, PrimeFieldExt, TranscriptEngineTrait, TranscriptReprTrait},
+  provider::hash_to_field::WideReduction,
+  traits::{Engine, HashToField, TranscriptEngineTrait, TranscriptReprTrait},
 };
 
-const PERSONA_TAG: &[u8] = b"NoTR";
-const DOM_SEP_TAG: &[u8] = b"NoDS";
-const KECCAK256_STATE_SIZE: usize = 64;
+pub(crate) const PERSONA_TAG: &[u8] = b"NoTR";
+pub(crate) const DOM_SEP_TAG: &[u8] = b"NoDS";
+pub(crate) const ABSORB_TAG: &[u8] = b"NoAB";
+pub(crate) const VERSION_TAG: &[u8] = b"NoTV";
+pub(crate) const KECCAK256_STATE_SIZE: usize = 64;
 const KECCAK256_PREFIX_CHALLENGE_LO: u8 = 0;
 const KECCAK256_PREFIX_CHALLENGE_HI: u8 = 1;
 
-/// Provides an implementation of `TranscriptEngine`
+/// The version of the encoding of the transcripts of this crate, with type
+/// tags and length prefixes
+pub const TRANSCRIPT_VERSION: u8 = 1;
+
+/// The version of the transcripts of proofs made before versioning, which
+/// concatenate labels and messages
+pub const LEGACY_TRANSCRIPT_VERSION: u8 = 0;
+
+/// Provides an implementation of `TranscriptEngine`, deriving challenges from
+/// its 64-byte state with `H`, under the squeeze label as domain separation
+/// tag, and encoding its messages as of `VERSION`, `TRANSCRIPT_VERSION` or
+/// `LEGACY_TRANSCRIPT_VERSION`
 #[derive(Debug)]
-pub struct Keccak256Transcript<E: Engine> {
+pub struct Keccak256Transcript<E: Engine, H = WideReduction, const VERSION: u8 = TRANSCRIPT_VERSION>
+{
   round:      u16,
   state:      [u8; KECCAK256_STATE_SIZE],
   transcript: Keccak256,
-  _p:         PhantomData<E>,
+  _p:         PhantomData<(E, fn() -> H)>,
 }
 
-fn compute_updated_state(keccak_instance: Keccak256, input: &[u8]) -> [u8; KECCAK256_STATE_SIZE] {
+/// Computes the state following `keccak_instance` updated with `input`,
+/// without allocating
+pub(crate) fn compute_updated_state(
+  keccak_instance: Keccak256,
+  input: &[u8],
+) -> [u8; KECCAK256_STATE_SIZE] {
   let mut updated_instance = keccak_instance;
   updated_instance.update(input);
 
@@ -37,17 +66,42 @@ fn compute_updated_state(keccak_instance: Keccak256, input: &[u8]) -> [u8; KECCA
   hasher_lo.update(input_lo);
   hasher_hi.update(input_hi);
 
-  let output_lo = hasher_lo.finalize();
-  let output_hi = hasher_hi.finalize();
+  let mut output = [0u8; KECCAK256_STATE_SIZE];
+  output[..KECCAK256_STATE_SIZE / 2].copy_from_slice(&hasher_lo.finalize());
+  output[KECCAK256_STATE_SIZE / 2..].copy_from_slice(&hasher_hi.finalize());
+  output
+}
+
+/// Updates `hasher` with `bytes`, prefixed by their length as a little-endian
+/// `u64`
+pub(crate) fn update_framed(hasher: &mut Keccak256, bytes: &[u8]) {
+  hasher.update((bytes.len() as u64).to_le_bytes());
+  hasher.update(bytes);
+}
 
-  [output_lo, output_hi].concat().as_slice().try_into().unwrap()
+/// The input the initial state of a transcript of `VERSION` labeled `label`
+/// is derived from
+pub(crate) fn initial_input<const VERSION: u8>(label: &[u8]) -> Keccak256 {
+  let mut hasher = Keccak256::new();
+  hasher.update(PERSONA_TAG);
+  if VERSION == LEGACY_TRANSCRIPT_VERSION {
+    hasher.update(label);
+  } else {
+    hasher.update(VERSION_TAG);
+    hasher.update([VERSION]);
+    update_framed(&mut hasher, label);
+  }
+  hasher
 }
 
-impl<E: Engine> TranscriptEngineTrait<E> for Keccak256Transcript<E> {
+impl<E: Engine, H: HashToField<E::Scalar>, const VERSION: u8> TranscriptEngineTrait<E>
+  for Keccak256Transcript<E, H, VERSION>
+{
+  const LEGACY: bool = VERSION == LEGACY_TRANSCRIPT_VERSION;
+
   fn new(label: &'static [u8]) -> Self {
     let keccak_instance = Keccak256::new();
-    let input = [PERSONA_TAG, label].concat();
-    let output = compute_updated_state(keccak_instance.clone(), &input);
+    let output = compute_updated_state(initial_input::<VERSION>(label), &[]);
 
     Self {
       round:      0u16,
@@ -60,9 +114,16 @@ impl<E: Engine> TranscriptEngineTrait<E> for Keccak256Transcript<E> {
   fn squeeze(&mut self, label: &'static [u8]) -> Result<E::Scalar, NovaError> {
     // we gather the full input from the round, preceded by the current state of the
     // transcript
-    let input =
-      [DOM_SEP_TAG, self.round.to_le_bytes().as_ref(), self.state.as_ref(), label].concat();
-    let output = compute_updated_state(self.transcript.clone(), &input);
+    let mut input = self.transcript.clone();
+    input.update(DOM_SEP_TAG);
+    input.update(self.round.to_le_bytes());
+    input.update(self.state);
+    if VERSION == LEGACY_TRANSCRIPT_VERSION {
+      input.update(label);
+    } else {
+      update_framed(&mut input, label);
+    }
+    let output = compute_updated_state(input, &[]);
 
     // update state
     self.round = self.round.checked_add(1).ok_or(NovaError::InternalTranscriptError)?;
@@ -70,17 +131,27 @@ impl<E: Engine> TranscriptEngineTrait<E> for Keccak256Transcript<E> {
     self.transcript = Keccak256::new();
 
     // squeeze out a challenge
-    Ok(E::Scalar::from_uniform(&output))
+    Ok(H::hash_to_field(label, &output))
   }
 
   fn absorb<T: TranscriptReprTrait<E::GE>>(&mut self, label: &'static [u8], o: &T) {
-    self.transcript.update(label);
-    self.transcript.update(o.to_transcript_bytes());
+    if VERSION == LEGACY_TRANSCRIPT_VERSION {
+      self.transcript.update(label);
+      self.transcript.update(o.to_transcript_bytes());
+    } else {
+      self.transcript.update(ABSORB_TAG);
+      update_framed(&mut self.transcript, label);
+      update_framed(&mut self.transcript, &o.to_transcript_bytes());
+    }
   }
 
   fn dom_sep(&mut self, bytes: &'static [u8]) {
     self.transcript.update(DOM_SEP_TAG);
-    self.transcript.update(bytes);
+    if VERSION == LEGACY_TRANSCRIPT_VERSION {
+      self.transcript.update(bytes);
+    } else {
+      update_framed(&mut self.transcript, bytes);
+    }
   }
 }
 
@@ -91,12 +162,18 @@ mod tests {
   use sha3::{Digest, Keccak256};
 
   use crate::{
-    provider::{keccak::Keccak256Transcript, Bn256EngineKZG, GrumpkinEngine},
-    traits::{Engine, PrimeFieldExt, TranscriptEngineTrait, TranscriptReprTrait},
+    provider::{
+      hash_to_field::{ExpandMessageXmd, WideReduction},
+      keccak::{Keccak256Transcript, LEGACY_TRANSCRIPT_VERSION, TRANSCRIPT_VERSION},
+      Bn256EngineKZG, GrumpkinEngine,
+    },
+    traits::{Engine, HashToField, PrimeFieldExt, TranscriptEngineTrait, TranscriptReprTrait},
   };
 
+  // the legacy transcripts squeeze the challenges they squeezed before versioning
   fn test_keccak_transcript_with<E: Engine>(expected_h1: &'static str, expected_h2: &'static str) {
-    let mut transcript: Keccak256Transcript<E> = Keccak256Transcript::new(b"test");
+    let mut transcript: Keccak256Transcript<E, WideReduction, LEGACY_TRANSCRIPT_VERSION> =
+      Keccak256Transcript::new(b"test");
 
     // two scalars
     let s1 = <E as Engine>::Scalar::from(2u64);
@@ -141,10 +218,21 @@ mod tests {
   }
 
   use super::{
-    DOM_SEP_TAG, KECCAK256_PREFIX_CHALLENGE_HI, KECCAK256_PREFIX_CHALLENGE_LO,
-    KECCAK256_STATE_SIZE, PERSONA_TAG,
+    ABSORB_TAG, DOM_SEP_TAG, KECCAK256_PREFIX_CHALLENGE_HI, KECCAK256_PREFIX_CHALLENGE_LO,
+    KECCAK256_STATE_SIZE, PERSONA_TAG, VERSION_TAG,
   };
 
+  fn framed(bytes: &[u8]) -> Vec<u8> { [&(bytes.len() as u64).to_le_bytes(), bytes].concat() }
+
+  fn initial_state_for_testing(label: &[u8]) -> [u8; KECCAK256_STATE_SIZE] {
+    let input = [PERSONA_TAG, VERSION_TAG, &[TRANSCRIPT_VERSION], &framed(label)].concat();
+    compute_updated_state_for_testing(&input)
+  }
+
+  fn absorbed_for_testing(label: &[u8], bytes: &[u8]) -> Vec<u8> {
+    [ABSORB_TAG, &framed(label), &framed(bytes)].concat()
+  }
+
   fn compute_updated_state_for_testing(input: &[u8]) -> [u8; KECCAK256_STATE_SIZE] {
     let input_lo = [input, &[KECCAK256_PREFIX_CHALLENGE_LO]].concat();
     let input_hi = [input, &[KECCAK256_PREFIX_CHALLENGE_HI]].concat();
@@ -168,7 +256,8 @@ mod tests {
     label: &'static [u8],
   ) -> [u8; 64] {
     let input =
-      [transcript, DOM_SEP_TAG, round.to_le_bytes().as_ref(), state.as_ref(), label].concat();
+      [transcript, DOM_SEP_TAG, round.to_le_bytes().as_ref(), state.as_ref(), &framed(label)]
+        .concat();
     compute_updated_state_for_testing(&input)
   }
 
@@ -191,13 +280,11 @@ mod tests {
 
     for i in 0..10 {
       transcript.absorb(&labels[i][..], &scalars[i]);
-      manual_transcript.extend(labels[i]);
-      manual_transcript.extend(scalars[i].to_transcript_bytes());
+      manual_transcript.extend(absorbed_for_testing(labels[i], &scalars[i].to_transcript_bytes()));
     }
 
     // compute the initial state
-    let input = [PERSONA_TAG, test_label].concat();
-    let initial_state = compute_updated_state_for_testing(&input);
+    let initial_state = initial_state_for_testing(test_label);
 
     // make a challenge
     let c1: <E as Engine>::Scalar = transcript.squeeze(b"c1").unwrap();
@@ -207,6 +294,50 @@ mod tests {
     assert_eq!(to_hex(c1), to_hex(E::Scalar::from_uniform(&c1_bytes)));
   }
 
+  fn test_keccak_transcript_rfc9380_with<E: Engine>() {
+    let mut transcript: Keccak256Transcript<E, ExpandMessageXmd<Keccak256>> =
+      Keccak256Transcript::new(b"test");
+    let s1 = <E as Engine>::Scalar::from(2u64);
+    transcript.absorb(b"s1", &s1);
+    let c1: <E as Engine>::Scalar = transcript.squeeze(b"c1").unwrap();
+
+    // an external verifier only needs the 64-byte state and RFC 9380
+    let initial_state = initial_state_for_testing(b"test");
+    let manual_transcript = absorbed_for_testing(b"s1", &s1.to_transcript_bytes());
+    let c1_bytes = squeeze_for_testing(&manual_transcript, 0u16, initial_state, b"c1");
+    assert_eq!(c1, ExpandMessageXmd::<Keccak256>::hash_to_field(b"c1", &c1_bytes));
+    assert_ne!(c1, <E as Engine>::Scalar::from_uniform(&c1_bytes));
+  }
+
+  #[test]
+  fn test_keccak_transcript_versions() {
+    type E = Bn256EngineKZG;
+    type Legacy = Keccak256Transcript<E, WideReduction, LEGACY_TRANSCRIPT_VERSION>;
+    type Current = Keccak256Transcript<E>;
+    fn squeeze<T: TranscriptEngineTrait<E>>(
+      dom_sep: &'static [u8],
+      label: &'static [u8],
+    ) -> Vec<u8> {
+      let mut transcript = T::new(b"test");
+      transcript.dom_sep(dom_sep);
+      transcript.absorb(label, &<E as Engine>::Scalar::from(2u64));
+      transcript.squeeze(b"c").unwrap().to_repr().as_ref().to_vec()
+    }
+
+    // the legacy encoding cannot tell where a label ends, the current one can
+    assert_eq!(squeeze::<Legacy>(b"IPA", b"U"), squeeze::<Legacy>(b"IP", b"AU"));
+    assert_ne!(squeeze::<Current>(b"IPA", b"U"), squeeze::<Current>(b"IP", b"AU"));
+
+    // and the versions are domain-separated
+    assert_ne!(squeeze::<Current>(b"IPA", b"U"), squeeze::<Legacy>(b"IPA", b"U"));
+  }
+
+  #[test]
+  fn test_keccak_transcript_rfc9380() {
+    test_keccak_transcript_rfc9380_with::<Bn256EngineKZG>();
+    test_keccak_transcript_rfc9380_with::<GrumpkinEngine>();
+  }
+
   #[test]
   fn test_keccak_transcript_incremental_vs_explicit() {
     // test_keccak_transcript_incremental_vs_explicit_with::<PallasEngine>();
@@ -14,6 +14,7 @@ use crate::{
   digest::SimpleDigestible,
   fast_serde,
   fast_serde::{FastSerde, SerdeByteError, SerdeByteTypes},
+  parallel,
   provider::{pedersen::Commitment, traits::DlogGroup, util::fb_msm},
   traits::{
     commitment::{CommitmentEngineTrait, Len},
@@ -205,7 +206,7 @@ where E::Fr: PrimeFieldBits
     let window_size = fb_msm::get_mul_window_size(max_degree);
     let scalar_bits = E::Fr::NUM_BITS as usize;
 
-    let (powers_of_g_projective, powers_of_h_projective) = rayon::join(
+    let (powers_of_g_projective, powers_of_h_projective) = parallel::join(
       || {
         let g_table = fb_msm::get_window_table(scalar_bits, window_size, g);
         fb_msm::multi_scalar_mul::<E::G1>(scalar_bits, window_size, &g_table, &nz_powers_of_beta)
@@ -219,7 +220,7 @@ where E::Fr: PrimeFieldBits
     let mut powers_of_g = vec![E::G1Affine::identity(); powers_of_g_projective.len()];
     let mut powers_of_h = vec![E::G2Affine::identity(); powers_of_h_projective.len()];
 
-    rayon::join(
+    parallel::join(
       || E::G1::batch_normalize(&powers_of_g_projective, &mut powers_of_g),
       || E::G2::batch_normalize(&powers_of_h_projective, &mut powers_of_h),
     );
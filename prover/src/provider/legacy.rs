@@ -0,0 +1,94 @@
+//! The engines of this crate with the transcripts of proofs made before
+//! transcripts were versioned, `LEGACY_TRANSCRIPT_VERSION`, to verify such
+//! proofs. They share their curves, commitments and keys with the engines of
+//! `provider`, so that a proof or a key serialized with `Bn256EngineKZG`
+//! deserializes as one of `legacy::Bn256EngineKZG`, whose verifier absorbs
+//! messages unprefixed.
+use halo2curves::bn256::Bn256;
+
+use crate::{
+  provider::{
+    bn256_grumpkin::{bn256, grumpkin},
+    hash_to_field::WideReduction,
+    keccak::{Keccak256Transcript, LEGACY_TRANSCRIPT_VERSION},
+    kzg_commitment::KZGCommitmentEngine,
+    pedersen::CommitmentEngine as PedersenCommitmentEngine,
+    poseidon::PoseidonRO,
+  },
+  traits::{CurveCycleEquipped, Engine},
+};
+
+/// The transcript of proofs made before versioning
+pub type LegacyTranscript<E> = Keccak256Transcript<E, WideReduction, LEGACY_TRANSCRIPT_VERSION>;
+
+/// `provider::GrumpkinEngine` with legacy transcripts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GrumpkinEngine;
+
+/// `provider::Bn256EngineIPA` with legacy transcripts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bn256EngineIPA;
+
+/// `provider::Bn256EngineZM` with legacy transcripts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bn256EngineZM;
+
+/// `provider::Bn256EngineKZG` with legacy transcripts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bn256EngineKZG;
+
+impl Engine for GrumpkinEngine {
+  type Base = grumpkin::Base;
+  type CE = PedersenCommitmentEngine<Self>;
+  type GE = grumpkin::Point;
+  type RO = PoseidonRO<Self::Base, Self::Scalar>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
+  type Scalar = grumpkin::Scalar;
+  type TE = LegacyTranscript<Self>;
+}
+
+impl Engine for Bn256EngineIPA {
+  type Base = bn256::Base;
+  type CE = PedersenCommitmentEngine<Self>;
+  type GE = bn256::Point;
+  type RO = PoseidonRO<Self::Base, Self::Scalar>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
+  type Scalar = bn256::Scalar;
+  type TE = LegacyTranscript<Self>;
+}
+
+impl Engine for Bn256EngineZM {
+  type Base = bn256::Base;
+  type CE = KZGCommitmentEngine<Bn256>;
+  type GE = bn256::Point;
+  type RO = PoseidonRO<Self::Base, Self::Scalar>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
+  type Scalar = bn256::Scalar;
+  type TE = LegacyTranscript<Self>;
+}
+
+impl Engine for Bn256EngineKZG {
+  type Base = bn256::Base;
+  type CE = KZGCommitmentEngine<Bn256>;
+  type GE = bn256::Point;
+  type RO = PoseidonRO<Self::Base, Self::Scalar>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
+  type Scalar = bn256::Scalar;
+  type TE = LegacyTranscript<Self>;
+}
+
+impl CurveCycleEquipped for Bn256EngineIPA {
+  type Secondary = GrumpkinEngine;
+}
+
+impl CurveCycleEquipped for Bn256EngineKZG {
+  type Secondary = GrumpkinEngine;
+}
+
+impl CurveCycleEquipped for Bn256EngineZM {
+  type Secondary = GrumpkinEngine;
+}
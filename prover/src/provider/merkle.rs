@@ -0,0 +1,347 @@
+//! This module provides a vector commitment engine over Merkle trees
+//!
+//! A vector of scalars is committed to by the root of a binary Merkle tree
+//! whose leaves are the hashes of its entries, padded with zeros to a power of
+//! two. Leaves and nodes are hashed by a [`MerkleHasher`]: [`Blake3Hasher`]
+//! hashes their bytes, and [`PoseidonHasher`] hashes them as field elements,
+//! which is cheaper to check inside a circuit.
+//!
+//! Unlike the commitments of the `CE` of an `Engine`, Merkle roots are not
+//! additively homomorphic, so they cannot be folded and do not implement
+//! `CommitmentTrait`. [`MerkleCommitmentEngine`] mirrors
+//! `CommitmentEngineTrait` instead, with openings of single entries on top.
+//! Its commitments can be absorbed in the transcript of the engine, and its
+//! trees are meant to be the `ProverCommitmentState` of hash-based evaluation
+//! engines.
+use core::{fmt::Debug, marker::PhantomData};
+
+use ff::{Field, PrimeField};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  parallel::prelude::*,
+  provider::poseidon::PoseidonConstantsCircuit,
+  scalar_bytes::ScalarBytesLe,
+  traits::{commitment::Len, Engine, Group, TranscriptReprTrait},
+};
+
+/// The hash of a leaf or of a node of a Merkle tree
+pub trait MerkleDigest:
+  Clone + Copy + Debug + Default + PartialEq + Eq + Send + Sync + Serialize + DeserializeOwned {
+  /// Returns the bytes of the hash
+  fn to_bytes(&self) -> Vec<u8>;
+}
+
+impl MerkleDigest for [u8; 32] {
+  fn to_bytes(&self) -> Vec<u8> { self.to_vec() }
+}
+
+/// A hash that is a field element
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldDigest<F>(pub F);
+
+impl<F: PrimeField + Serialize + DeserializeOwned> MerkleDigest for FieldDigest<F> {
+  fn to_bytes(&self) -> Vec<u8> { ScalarBytesLe::new(&self.0).as_bytes().to_vec() }
+}
+
+/// A hash function for the leaves and the nodes of a Merkle tree over `F`
+pub trait MerkleHasher<F: PrimeField>:
+  Clone + Debug + PartialEq + Send + Sync + Serialize + DeserializeOwned {
+  /// The type of the hashes
+  type Digest: MerkleDigest;
+
+  /// Creates a hasher, domain-separated by `label`
+  fn new(label: &[u8]) -> Self;
+
+  /// Hashes a leaf
+  fn hash_leaf(&self, leaf: &F) -> Self::Digest;
+
+  /// Hashes two sibling nodes into their parent
+  fn hash_node(&self, left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
+}
+
+/// Hashes leaves and nodes with Blake3, keyed by the hash of the label
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blake3Hasher {
+  key: [u8; 32],
+}
+
+impl Blake3Hasher {
+  /// The key of the hasher, e.g. to hash its nodes inside a circuit with
+  /// `gadgets::blake3_keyed`: a node is the hash of `1`, then of its children
+  pub fn key(&self) -> &[u8; 32] { &self.key }
+
+  /// Hashes several entries into a single leaf, e.g. a column of a matrix
+  pub(crate) fn hash_leaves<F: PrimeField>(&self, leaves: &[F]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_keyed(&self.key);
+    hasher.update(&[0]);
+    for leaf in leaves {
+      hasher.update(ScalarBytesLe::new(leaf).as_bytes());
+    }
+    hasher.finalize().into()
+  }
+}
+
+impl<F: PrimeField> MerkleHasher<F> for Blake3Hasher {
+  type Digest = [u8; 32];
+
+  fn new(label: &[u8]) -> Self { Self { key: *blake3::hash(label).as_bytes() } }
+
+  fn hash_leaf(&self, leaf: &F) -> [u8; 32] { self.hash_leaves(core::slice::from_ref(leaf)) }
+
+  fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_keyed(&self.key);
+    hasher.update(&[1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+  }
+}
+
+/// Hashes leaves and nodes with Poseidon over `F`, prefixed by a field
+/// element derived from the label
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoseidonHasher<F: PrimeField> {
+  constants: PoseidonConstantsCircuit<F>,
+  domain:    FieldDigest<F>,
+}
+
+impl<F> MerkleHasher<F> for PoseidonHasher<F>
+where F: PrimeField + Serialize + DeserializeOwned
+{
+  type Digest = FieldDigest<F>;
+
+  fn new(label: &[u8]) -> Self {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&blake3::hash(label).as_bytes()[..16]);
+    let domain = FieldDigest(F::from_u128(u128::from_le_bytes(bytes)));
+    Self { constants: PoseidonConstantsCircuit::default(), domain }
+  }
+
+  fn hash_leaf(&self, leaf: &F) -> FieldDigest<F> {
+    FieldDigest(self.constants.hash(&[self.domain.0, F::ZERO, *leaf]))
+  }
+
+  fn hash_node(&self, left: &FieldDigest<F>, right: &FieldDigest<F>) -> FieldDigest<F> {
+    FieldDigest(self.constants.hash(&[self.domain.0, F::ONE, left.0, right.0]))
+  }
+}
+
+/// A type that holds the parameters of Merkle commitments: the hasher, and
+/// the maximum length of the committed vectors
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MerkleCommitmentKey<F: PrimeField, H: MerkleHasher<F>> {
+  hasher:     H,
+  num_leaves: usize,
+  _p:         PhantomData<F>,
+}
+
+impl<F: PrimeField, H: MerkleHasher<F>> Len for MerkleCommitmentKey<F, H> {
+  fn length(&self) -> usize { self.num_leaves }
+}
+
+/// A type that holds a Merkle commitment: the root of the tree, and the
+/// length of the committed vector
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MerkleCommitment<D: MerkleDigest> {
+  root: D,
+  len:  usize,
+}
+
+impl<D: MerkleDigest> MerkleCommitment<D> {
+  /// The root of the tree
+  pub fn root(&self) -> &D { &self.root }
+
+  /// The length of the committed vector
+  pub fn len(&self) -> usize { self.len }
+
+  /// Whether the committed vector is empty
+  pub fn is_empty(&self) -> bool { self.len == 0 }
+}
+
+impl<G: Group, D: MerkleDigest> TranscriptReprTrait<G> for MerkleCommitment<D> {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    [self.root.to_bytes(), (self.len as u64).to_le_bytes().to_vec()].concat()
+  }
+}
+
+/// A Merkle tree, kept by the prover to open the commitment to its leaves
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MerkleTree<D: MerkleDigest> {
+  /// the layers of the tree, from the hashes of the leaves to the root
+  layers: Vec<Vec<D>>,
+  len:    usize,
+}
+
+impl<D: MerkleDigest> MerkleTree<D> {
+  /// Opens the commitment at `index`
+  pub fn open(&self, index: usize) -> Result<MerkleOpening<D>, NovaError> {
+    if index >= self.len {
+      return Err(NovaError::InvalidIndex);
+    }
+    let siblings = self.layers[..self.layers.len() - 1]
+      .iter()
+      .enumerate()
+      .map(|(depth, layer)| layer[(index >> depth) ^ 1])
+      .collect();
+    Ok(MerkleOpening { index, siblings })
+  }
+}
+
+/// An opening of a Merkle commitment at a single index
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MerkleOpening<D: MerkleDigest> {
+  index:    usize,
+  /// the siblings of the path from the leaf to the root, leaf first
+  siblings: Vec<D>,
+}
+
+impl<D: MerkleDigest> MerkleOpening<D> {
+  /// The index of the opened entry
+  pub fn index(&self) -> usize { self.index }
+}
+
+/// Provides a vector commitment engine over Merkle trees, hashed with `H`
+#[derive(Clone, Debug)]
+pub struct MerkleCommitmentEngine<E: Engine, H: MerkleHasher<E::Scalar>> {
+  _p: PhantomData<(E, H)>,
+}
+
+impl<E: Engine, H: MerkleHasher<E::Scalar>> MerkleCommitmentEngine<E, H> {
+  /// Creates the parameters for commitments to vectors of up to `n` entries,
+  /// with a hasher domain-separated by `label`. Like `setup` on a
+  /// `CommitmentEngineTrait`, but nothing needs to be sampled.
+  pub fn setup(label: &'static [u8], n: usize) -> MerkleCommitmentKey<E::Scalar, H> {
+    MerkleCommitmentKey { hasher: H::new(label), num_leaves: n, _p: PhantomData }
+  }
+
+  /// Commits to the provided vector
+  pub fn commit(
+    ck: &MerkleCommitmentKey<E::Scalar, H>,
+    v: &[E::Scalar],
+  ) -> Result<MerkleCommitment<H::Digest>, NovaError> {
+    Self::commit_with_tree(ck, v).map(|(comm, _)| comm)
+  }
+
+  /// Commits to the provided vector, and returns the tree, to open the
+  /// commitment later on
+  pub fn commit_with_tree(
+    ck: &MerkleCommitmentKey<E::Scalar, H>,
+    v: &[E::Scalar],
+  ) -> Result<(MerkleCommitment<H::Digest>, MerkleTree<H::Digest>), NovaError> {
+    if v.len() > ck.num_leaves {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+
+    let mut leaves = v.to_vec();
+    leaves.resize(v.len().max(1).next_power_of_two(), E::Scalar::ZERO);
+    let leaf_hashes = leaves.par_iter().map(|leaf| ck.hasher.hash_leaf(leaf)).collect();
+    Ok(build_tree(leaf_hashes, v.len(), |left, right| ck.hasher.hash_node(left, right)))
+  }
+
+  /// Verifies that `opening` opens `comm` to `value`
+  pub fn verify_opening(
+    ck: &MerkleCommitmentKey<E::Scalar, H>,
+    comm: &MerkleCommitment<H::Digest>,
+    value: &E::Scalar,
+    opening: &MerkleOpening<H::Digest>,
+  ) -> Result<(), NovaError> {
+    verify_path(comm, ck.hasher.hash_leaf(value), opening, |left, right| {
+      ck.hasher.hash_node(left, right)
+    })
+  }
+}
+
+/// Builds the tree over `leaf_hashes`, whose number is a power of two, of a
+/// vector of `len` entries
+pub(crate) fn build_tree<D: MerkleDigest>(
+  leaf_hashes: Vec<D>,
+  len: usize,
+  hash_node: impl Fn(&D, &D) -> D + Sync,
+) -> (MerkleCommitment<D>, MerkleTree<D>) {
+  let mut layers = vec![leaf_hashes];
+  while let Some(layer) = layers.last().filter(|layer| layer.len() > 1) {
+    let parents = layer.par_chunks(2).map(|pair| hash_node(&pair[0], &pair[1])).collect();
+    layers.push(parents);
+  }
+
+  let root = layers[layers.len() - 1][0];
+  (MerkleCommitment { root, len }, MerkleTree { layers, len })
+}
+
+/// Verifies that `opening` opens `comm` to the leaf hashed to `leaf_hash`
+pub(crate) fn verify_path<D: MerkleDigest>(
+  comm: &MerkleCommitment<D>,
+  leaf_hash: D,
+  opening: &MerkleOpening<D>,
+  hash_node: impl Fn(&D, &D) -> D,
+) -> Result<(), NovaError> {
+  // the depth of the tree is fixed by the length of the vector, so that a
+  // path cannot stop at an inner node
+  let depth = comm.len.max(1).next_power_of_two().trailing_zeros() as usize;
+  if opening.index >= comm.len || opening.siblings.len() != depth {
+    return Err(NovaError::ProofVerifyError);
+  }
+
+  let root = opening.siblings.iter().enumerate().fold(leaf_hash, |node, (depth, sibling)| {
+    if (opening.index >> depth) & 1 == 0 {
+      hash_node(&node, sibling)
+    } else {
+      hash_node(sibling, &node)
+    }
+  });
+  if root != comm.root {
+    return Err(NovaError::ProofVerifyError);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::Bn256EngineKZG;
+
+  type E = Bn256EngineKZG;
+
+  fn test_merkle_commitment_with<H: MerkleHasher<<E as Engine>::Scalar>>() {
+    let ck = MerkleCommitmentEngine::<E, H>::setup(b"test", 8);
+    let v = (0..5).map(|_| <E as Engine>::Scalar::random(&mut OsRng)).collect::<Vec<_>>();
+    let (comm, tree) = MerkleCommitmentEngine::<E, H>::commit_with_tree(&ck, &v).unwrap();
+    assert_eq!(comm, MerkleCommitmentEngine::<E, H>::commit(&ck, &v).unwrap());
+
+    for (i, value) in v.iter().enumerate() {
+      let opening = tree.open(i).unwrap();
+      MerkleCommitmentEngine::<E, H>::verify_opening(&ck, &comm, value, &opening).unwrap();
+
+      // a wrong value does not verify
+      let wrong_value = *value + <E as Engine>::Scalar::ONE;
+      assert!(
+        MerkleCommitmentEngine::<E, H>::verify_opening(&ck, &comm, &wrong_value, &opening).is_err()
+      );
+    }
+
+    // the padding cannot be opened
+    assert!(tree.open(v.len()).is_err());
+
+    // the same vector commits differently under another label
+    let other_ck = MerkleCommitmentEngine::<E, H>::setup(b"other", 8);
+    assert_ne!(comm, MerkleCommitmentEngine::<E, H>::commit(&other_ck, &v).unwrap());
+
+    // vectors longer than the key are rejected
+    let v = vec![<E as Engine>::Scalar::ZERO; 9];
+    assert!(MerkleCommitmentEngine::<E, H>::commit(&ck, &v).is_err());
+  }
+
+  #[test]
+  fn test_merkle_commitment() {
+    test_merkle_commitment_with::<Blake3Hasher>();
+    test_merkle_commitment_with::<PoseidonHasher<<E as Engine>::Scalar>>();
+  }
+}
@@ -5,6 +5,37 @@
 pub mod hyperkzg;
 pub mod ipa_pc;
 
+// public modules to choose the commitment backend of a deployment by its size
+pub mod backend;
+
+// public modules for vector commitments that are not additively homomorphic
+pub mod merkle;
+
+// public modules to audit the transparent setup of commitment keys
+pub mod setup_log;
+
+// public modules to import the SRS of KZG commitments from ceremonies
+pub mod srs;
+
+// public modules for commitments interoperable with the dalek ecosystem
+#[cfg(feature = "ristretto")] pub mod ristretto;
+
+// public modules for sigma protocols over the groups of Pedersen commitments
+pub mod sigma;
+
+// public modules for engines over small prime fields
+pub mod small_field;
+
+// public modules to hash application data the same inside and outside circuits
+pub mod sponge;
+
+// public modules to configure how transcripts derive challenges
+pub mod hash_to_field;
+pub mod keccak;
+
+// public modules to verify proofs made before transcripts were versioned
+pub mod legacy;
+
 // crate-public modules, made crate-public mostly for tests
 pub(crate) mod bn256_grumpkin;
 mod pedersen;
@@ -15,10 +46,12 @@ mod kzg_commitment;
 pub(crate) mod util;
 
 // crate-private modules
-mod keccak;
 mod tests;
 
 use halo2curves::bn256::Bn256;
+/// Splits, combines and folds Pedersen commitment keys, so that a commitment
+/// splits additively along the split of its key
+pub use pedersen::CommitmentKeyExtTrait;
 
 use self::kzg_commitment::KZGCommitmentEngine;
 use crate::{
@@ -26,7 +59,7 @@ use crate::{
     bn256_grumpkin::{bn256, grumpkin},
     keccak::Keccak256Transcript,
     pedersen::CommitmentEngine as PedersenCommitmentEngine,
-    poseidon::{PoseidonRO, PoseidonROCircuit},
+    poseidon::PoseidonRO,
   },
   traits::{CurveCycleEquipped, Engine},
 };
@@ -46,7 +79,8 @@ impl Engine for Bn256EngineIPA {
   type CE = PedersenCommitmentEngine<Self>;
   type GE = bn256::Point;
   type RO = PoseidonRO<Self::Base, Self::Scalar>;
-  type ROCircuit = PoseidonROCircuit<Self::Base>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
   type Scalar = bn256::Scalar;
   type TE = Keccak256Transcript<Self>;
 }
@@ -56,7 +90,8 @@ impl Engine for GrumpkinEngine {
   type CE = PedersenCommitmentEngine<Self>;
   type GE = grumpkin::Point;
   type RO = PoseidonRO<Self::Base, Self::Scalar>;
-  type ROCircuit = PoseidonROCircuit<Self::Base>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
   type Scalar = grumpkin::Scalar;
   type TE = Keccak256Transcript<Self>;
 }
@@ -71,7 +106,8 @@ impl Engine for Bn256EngineZM {
   type CE = KZGCommitmentEngine<Bn256>;
   type GE = bn256::Point;
   type RO = PoseidonRO<Self::Base, Self::Scalar>;
-  type ROCircuit = PoseidonROCircuit<Self::Base>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
   type Scalar = bn256::Scalar;
   type TE = Keccak256Transcript<Self>;
 }
@@ -84,7 +120,8 @@ impl Engine for Bn256EngineKZG {
   type CE = KZGCommitmentEngine<Bn256>;
   type GE = bn256::Point;
   type RO = PoseidonRO<Self::Base, Self::Scalar>;
-  type ROCircuit = PoseidonROCircuit<Self::Base>;
+  #[cfg(feature = "prove")]
+  type ROCircuit = crate::provider::poseidon::PoseidonROCircuit<Self::Base>;
   type Scalar = bn256::Scalar;
   type TE = Keccak256Transcript<Self>;
 }
@@ -112,10 +149,19 @@ mod test {
   use rand_core::OsRng;
   use sha3::Shake256;
 
-  use crate::provider::{
-    bn256_grumpkin::{bn256, grumpkin},
-    traits::DlogGroup,
-    util::msm::cpu_best_msm,
+  use crate::{
+    provider::{
+      bn256_grumpkin::{bn256, grumpkin},
+      pedersen::CommitmentKeyExtTrait,
+      traits::DlogGroup,
+      util::msm::cpu_best_msm,
+      Bn256EngineIPA,
+    },
+    traits::{
+      commitment::{CommitmentEngineTrait, CommitmentTrait},
+      Engine,
+    },
+    Commitment, CommitmentKey,
   };
 
   macro_rules! impl_cycle_pair_test {
@@ -141,7 +187,21 @@ mod test {
         assert_eq!(ck_par.len(), n);
         assert_eq!(ck_ser.len(), n);
         assert_eq!(ck_par, ck_ser);
+        for chunk_size in [1, 3, 16] {
+          let ck_chunked =
+            <$curve::Point as DlogGroup>::from_label_chunked(label, n, chunk_size, &mut |_| true);
+          assert_eq!(ck_chunked.as_ref(), Some(&ck_ser));
+        }
       }
+
+      // the computation stops as soon as asked to
+      let mut calls = 0;
+      let stopped = <$curve::Point as DlogGroup>::from_label_chunked(label, 10, 3, &mut |done| {
+        calls += 1;
+        done < 6
+      });
+      assert!(stopped.is_none());
+      assert_eq!(calls, 2);
     };
   }
 
@@ -167,4 +227,72 @@ mod test {
   fn test_bn256_from_label() {
     impl_cycle_pair_test!(bn256);
   }
+
+  #[test]
+  fn test_commitment_key_fold_and_scale() {
+    type E = Bn256EngineIPA;
+    type F = <E as Engine>::Scalar;
+
+    let (L, R) = CommitmentKey::<E>::from_label(b"fold", 8).split_at(4);
+    let naive = |w1: F, w2: F| {
+      let ck = L.ck.iter().zip(R.ck.iter()).map(|(l, r)| (*l * w1 + *r * w2).to_affine());
+      ck.collect::<Vec<_>>()
+    };
+
+    // negations and trivial weights take shortcuts, with the same results
+    let w = F::random(OsRng);
+    for (w1, w2) in [(w, -w), (F::ONE, -F::ONE), (-F::ONE, w), (F::ZERO, w), (w, F::ONE)] {
+      assert_eq!(CommitmentKey::<E>::fold(&L, &R, &w1, &w2).ck, naive(w1, w2));
+    }
+
+    for r in [F::ONE, -F::ONE, w] {
+      let mut scaled = L.clone();
+      scaled.scale(&r);
+      assert_eq!(scaled.ck, L.ck.iter().map(|g| (*g * r).to_affine()).collect::<Vec<_>>());
+    }
+  }
+
+  #[test]
+  fn test_commitment_key_from_label() {
+    type E = Bn256EngineIPA;
+
+    // seeds need not be static
+    let seed = format!("seed {}", 42);
+    let ck = CommitmentKey::<E>::from_label(seed.as_bytes(), 5);
+    assert_eq!(ck.ck.len(), 5);
+    assert_eq!(ck, CommitmentKey::<E>::from_label(seed.as_bytes(), 5));
+    assert_eq!(ck.ck[..], CommitmentKey::<E>::from_label(seed.as_bytes(), 9).ck[..5]);
+    assert_ne!(ck, CommitmentKey::<E>::from_label(b"seed 43", 5));
+
+    // the setup is the derivation from its label, up to the next power of two
+    assert_eq!(
+      <E as Engine>::CE::setup(b"seed 42", 5),
+      CommitmentKey::<E>::from_label(b"seed 42", 8)
+    );
+  }
+
+  #[test]
+  fn test_commitment_homomorphism() {
+    type E = Bn256EngineIPA;
+    type F = <E as Engine>::Scalar;
+    type CE = <E as Engine>::CE;
+
+    let ck = CE::setup(b"homomorphism", 8);
+    let (a, b) = ((0..8).map(|_| F::random(OsRng)).collect::<Vec<_>>(), vec![F::ONE; 8]);
+    let (comm_a, comm_b) = (CE::commit(&ck, &a), CE::commit(&ck, &b));
+    let r = F::random(OsRng);
+
+    // commitments add and scale along their vectors
+    let folded = a.iter().zip(&b).map(|(a, b)| *a + r * b).collect::<Vec<_>>();
+    assert_eq!(CE::commit(&ck, &folded), comm_a + comm_b.scale(&r));
+    assert_eq!(
+      Commitment::<E>::linear_combination(&[comm_a, comm_b], &[F::ONE, r]),
+      Ok(comm_a + comm_b * r)
+    );
+
+    // and split additively along the split of their key
+    let (ck_lo, ck_hi) = ck.clone().split_at(3);
+    assert_eq!(CE::commit(&ck_lo, &a[..3]) + CE::commit(&ck_hi, &a[3..]), comm_a);
+    assert_eq!(CE::commit(&ck_lo.combine(&ck_hi), &a), comm_a);
+  }
 }
@@ -9,24 +9,28 @@ use std::io::Cursor;
 use ff::Field;
 use group::{
   prime::{PrimeCurve, PrimeCurveAffine},
-  Curve, Group, GroupEncoding,
+  Curve, Group,
 };
 use halo2curves::serde::SerdeObject;
-use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
   errors::NovaError,
   fast_serde,
   fast_serde::{FastSerde, SerdeByteError, SerdeByteTypes},
+  migrate,
+  parallel::prelude::*,
   provider::traits::DlogGroup,
   traits::{
     commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
-    AbsorbInROTrait, Engine, ROTrait, TranscriptReprTrait,
+    validated, AbsorbInROTrait, Engine, ROTrait, SetupProgress, TranscriptReprTrait, Validate,
   },
   zip_with,
 };
 
+/// The number of generators computed between two reports of progress
+const SETUP_CHUNK_SIZE: usize = 1 << 16;
+
 /// A type that holds commitment generators
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CommitmentKey<E>
@@ -36,6 +40,19 @@ where
   pub ck: Vec<<E::GE as PrimeCurve>::Affine>,
 }
 
+impl<E> CommitmentKey<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// Derives `n` generators from the public string `seed`, so that
+  /// independent parties can reproduce the key instead of shipping it. See
+  /// `DlogGroup::from_label` for the derivation: the key for `n` is a prefix
+  /// of the key for any larger `n`, and `setup(label, n)` derives
+  /// `n.next_power_of_two()` generators from `label`.
+  pub fn from_label(seed: &[u8], n: usize) -> Self { Self { ck: E::GE::from_label(seed, n) } }
+}
+
 impl<E> Len for CommitmentKey<E>
 where
   E: Engine,
@@ -98,14 +115,86 @@ pub struct Commitment<E: Engine> {
   pub(crate) comm: E::GE,
 }
 
-/// A type that holds a compressed commitment
+impl<E> Commitment<E>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+{
+  /// Encodes the commitment as big-endian affine coordinates x || y, see
+  /// [`DlogGroup::to_affine_bytes`]
+  pub fn to_affine_bytes(&self) -> Vec<u8> { self.comm.to_affine_bytes() }
+
+  /// Decodes a commitment encoded with [`Commitment::to_affine_bytes`]
+  pub fn from_affine_bytes(bytes: &[u8]) -> Result<Self, NovaError> {
+    E::GE::from_affine_bytes(bytes).map(|comm| Self { comm }).ok_or(NovaError::DecompressionError)
+  }
+}
+
+/// A type that holds a compressed commitment, in the SEC1 compressed encoding
+/// of [`DlogGroup::to_compressed_bytes`]. It is checked to decode to a point
+/// when deserialized.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(bound = "")]
+#[serde(remote = "Self", bound = "")]
 pub struct CompressedCommitment<E>
 where
   E: Engine,
   E::GE: DlogGroup<ScalarExt = E::Scalar>, {
-  pub(crate) comm: <E::GE as DlogGroup>::Compressed,
+  pub(crate) comm: Vec<u8>,
+  #[serde(skip)]
+  _p:              PhantomData<E>,
+}
+
+impl<E> CompressedCommitment<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// Returns the SEC1 encoding of the commitment
+  pub fn as_bytes(&self) -> &[u8] { &self.comm }
+
+  /// Wraps a SEC1 encoded commitment, checking that it decodes to a point
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, NovaError> {
+    E::GE::from_compressed_bytes(bytes).ok_or(NovaError::DecompressionError)?;
+    Ok(Self { comm: bytes.to_vec(), _p: PhantomData })
+  }
+}
+
+impl<E> Validate for CompressedCommitment<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  fn validate(&self) -> Result<(), NovaError> {
+    E::GE::from_compressed_bytes(&self.comm).ok_or(NovaError::DecompressionError)?;
+    Ok(())
+  }
+}
+
+impl<E> Serialize for CompressedCommitment<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de, E> Deserialize<'de> for CompressedCommitment<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    if migrate::decoding_version() < migrate::SEC1_COMMITMENTS {
+      // the compressed encoding of the curve library, see `crate::migrate`
+      let legacy = <E::GE as DlogGroup>::Compressed::deserialize(deserializer)?;
+      let comm = Option::from(<E::GE as group::GroupEncoding>::from_bytes(&legacy.into()))
+        .ok_or_else(|| D::Error::custom(NovaError::DecompressionError))?;
+      return Ok(Commitment::<E> { comm }.compress());
+    }
+    validated::<_, D>(Self::deserialize(deserializer)?)
+  }
 }
 
 impl<E> CommitmentTrait<E> for Commitment<E>
@@ -116,16 +205,13 @@ where
   type CompressedCommitment = CompressedCommitment<E>;
 
   fn compress(&self) -> Self::CompressedCommitment {
-    CompressedCommitment { comm: <E::GE as GroupEncoding>::to_bytes(&self.comm).into() }
+    CompressedCommitment { comm: self.comm.to_compressed_bytes(), _p: PhantomData }
   }
 
   fn to_coordinates(&self) -> (E::Base, E::Base, bool) { self.comm.to_coordinates() }
 
   fn decompress(c: &Self::CompressedCommitment) -> Result<Self, NovaError> {
-    let opt_comm = <<E as Engine>::GE as GroupEncoding>::from_bytes(&c.comm.clone().into());
-    let Some(comm) = Option::from(opt_comm) else {
-      return Err(NovaError::DecompressionError);
-    };
+    let comm = E::GE::from_compressed_bytes(&c.comm).ok_or(NovaError::DecompressionError)?;
     Ok(Self { comm })
   }
 }
@@ -168,7 +254,7 @@ where
   E: Engine,
   E::GE: DlogGroup<ScalarExt = E::Scalar>,
 {
-  fn to_transcript_bytes(&self) -> Vec<u8> { self.comm.to_transcript_bytes() }
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.comm.clone() }
 }
 
 impl<E> MulAssign<E::Scalar> for Commitment<E>
@@ -227,10 +313,49 @@ where
     Self::CommitmentKey { ck: E::GE::from_label(label, n.next_power_of_two()) }
   }
 
+  fn setup_with_progress(
+    label: &'static [u8],
+    n: usize,
+    stage: &str,
+    progress: &dyn SetupProgress,
+  ) -> Result<Self::CommitmentKey, NovaError> {
+    let n = n.next_power_of_two();
+    if !progress.report(stage, 0, n) {
+      return Err(NovaError::Interrupted);
+    }
+    let ck = E::GE::from_label_chunked(label, n, SETUP_CHUNK_SIZE, &mut |done| {
+      progress.report(stage, done, n)
+    })
+    .ok_or(NovaError::Interrupted)?;
+    Ok(Self::CommitmentKey { ck })
+  }
+
   fn commit(ck: &Self::CommitmentKey, v: &[E::Scalar]) -> Self::Commitment {
     assert!(ck.ck.len() >= v.len());
     Commitment { comm: E::GE::vartime_multiscalar_mul(v, &ck.ck[..v.len()]) }
   }
+
+  fn commit_at(ck: &Self::CommitmentKey, offset: usize, v: &[E::Scalar]) -> Self::Commitment {
+    assert!(ck.ck.len() >= offset + v.len());
+    Commitment { comm: E::GE::vartime_multiscalar_mul(v, &ck.ck[offset..offset + v.len()]) }
+  }
+}
+
+/// Computes `w * g`, with a negation or nothing in place of a scalar
+/// multiplication when `w` is `0`, `1` or `-1`
+fn mul_signed<E>(g: &<E::GE as PrimeCurve>::Affine, w: &E::Scalar) -> E::GE
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  if bool::from(w.is_zero()) {
+    E::GE::identity()
+  } else if *w == E::Scalar::ONE {
+    g.to_curve()
+  } else if *w == -E::Scalar::ONE {
+    -g.to_curve()
+  } else {
+    *g * w
+  }
 }
 
 /// A trait listing properties of a commitment key that can be managed in a
@@ -260,6 +385,9 @@ where
   ) -> Result<Self, NovaError>
   where
     Self: Sized;
+
+  /// Returns the coordinates of the generators of the commitment key
+  fn to_coordinates(&self) -> Vec<(E::Base, E::Base, bool)>;
 }
 
 impl<E> CommitmentKeyExtTrait<E> for CommitmentKey<E>
@@ -281,10 +409,17 @@ where
   // weights
   fn fold(L: &Self, R: &Self, w1: &E::Scalar, w2: &E::Scalar) -> Self {
     debug_assert!(L.ck.len() == R.ck.len());
-    let ck_curve: Vec<E::GE> = zip_with!(par_iter, (L.ck, R.ck), |l, r| {
-      E::GE::vartime_multiscalar_mul(&[*w1, *w2], &[*l, *r])
-    })
-    .collect();
+    let is_trivial =
+      |w: &E::Scalar| bool::from(w.is_zero()) || *w == E::Scalar::ONE || *w == -E::Scalar::ONE;
+    let ck_curve: Vec<E::GE> = if is_trivial(w1) || is_trivial(w2) {
+      zip_with!(par_iter, (L.ck, R.ck), |l, r| mul_signed::<E>(l, w1) + mul_signed::<E>(r, w2))
+        .collect()
+    } else {
+      zip_with!(par_iter, (L.ck, R.ck), |l, r| {
+        E::GE::vartime_multiscalar_mul(&[*w1, *w2], &[*l, *r])
+      })
+      .collect()
+    };
     let mut ck_affine = vec![<E::GE as PrimeCurve>::Affine::identity(); L.ck.len()];
     E::GE::batch_normalize(&ck_curve, &mut ck_affine);
 
@@ -293,6 +428,13 @@ where
 
   /// Scales each element in `self` by `r`
   fn scale(&mut self, r: &E::Scalar) {
+    if *r == E::Scalar::ONE {
+      return;
+    }
+    if *r == -E::Scalar::ONE {
+      self.ck.par_iter_mut().for_each(|g| *g = -*g);
+      return;
+    }
     let ck_scaled: Vec<E::GE> = self.ck.par_iter().map(|g| *g * r).collect();
     E::GE::batch_normalize(&ck_scaled, &mut self.ck);
   }
@@ -307,4 +449,8 @@ where
     E::GE::batch_normalize(&d, &mut ck);
     Ok(Self { ck })
   }
+
+  fn to_coordinates(&self) -> Vec<(E::Base, E::Base, bool)> {
+    self.ck.par_iter().map(|g| g.to_curve().to_coordinates()).collect()
+  }
 }
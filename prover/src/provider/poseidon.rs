@@ -1,34 +1,253 @@
 //! Poseidon Constants and Poseidon-based RO used in Nova
-use core::marker::PhantomData;
+//!
+//! The constants are not hard-coded for a given field: they are generated by
+//! `neptune` for any `PrimeField`, with a number of rounds derived from the
+//! size of the field for the standard security level, so that new fields
+//! (e.g. secp or BLS scalars) get sound parameters. Since generating them is
+//! expensive, they are generated once per field and cached for the lifetime
+//! of the process, e.g. across calls to `PublicParams::setup`.
+//!
+//! Nova hashes with a sponge of arity 24. The adaptive hash of
+//! [`adaptive_hash`] instead picks, per number of inputs and per field, the
+//! arity among [`ARITIES`] of the fewest S-boxes, e.g. a width of 3 for two
+//! inputs, where the width of 25 spends most of its constraints on padding.
+use core::{
+  any::{Any, TypeId},
+  marker::PhantomData,
+};
+use std::{
+  collections::HashMap,
+  sync::{Arc, PoisonError, RwLock},
+};
 
+#[cfg(feature = "prove")]
 use bellpepper_core::{
   boolean::{AllocatedBit, Boolean},
   num::AllocatedNum,
   ConstraintSystem, SynthesisError,
 };
 use ff::{PrimeField, PrimeFieldBits};
-use generic_array::typenum::U24;
+use generic_array::typenum::{U11, U16, U2, U24, U4, U8};
+#[cfg(feature = "prove")]
+use neptune::{circuit2::Elt, sponge::circuit::SpongeCircuit};
 use neptune::{
-  circuit2::Elt,
   poseidon::PoseidonConstants,
   sponge::{
     api::{IOPattern, SpongeAPI, SpongeOp},
-    circuit::SpongeCircuit,
     vanilla::{Mode::Simplex, Sponge, SpongeTrait},
   },
-  Strength,
+  Arity, Strength,
 };
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::traits::{ROCircuitTrait, ROTrait};
+#[cfg(feature = "prove")] use crate::traits::ROCircuitTrait;
+use crate::traits::ROTrait;
 
 /// All Poseidon Constants that are used in Nova
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PoseidonConstantsCircuit<Scalar: PrimeField>(PoseidonConstants<Scalar, U24>);
 
+/// The Poseidon constants generated so far, keyed by their `TypeId`, of
+/// their field and arity
+static CONSTANTS_CACHE: Lazy<RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>> =
+  Lazy::new(Default::default);
+
+/// The constants of type `T`, from the cache, or from `generate` on first use
+// an entry is inserted under the `TypeId` of its own type
+#[allow(clippy::expect_used)]
+fn cached<T: Clone + Send + Sync + 'static>(generate: impl FnOnce() -> T) -> T {
+  let key = TypeId::of::<T>();
+  if let Some(constants) = CONSTANTS_CACHE.read().unwrap_or_else(PoisonError::into_inner).get(&key)
+  {
+    return constants.downcast_ref::<T>().expect("cache entries match their key").clone();
+  }
+
+  let constants = generate();
+  CONSTANTS_CACHE
+    .write()
+    .unwrap_or_else(PoisonError::into_inner)
+    .entry(key)
+    .or_insert_with(|| Box::new(constants.clone()));
+  constants
+}
+
+/// Hashes `elements` with a sponge over `constants`
+// the sponge is used exactly as its IO pattern says
+#[allow(clippy::expect_used)]
+fn sponge_hash<F: PrimeField, A: Arity<F>>(
+  constants: &PoseidonConstants<F, A>,
+  elements: &[F],
+) -> F {
+  let mut sponge = Sponge::new_with_constants(constants, Simplex);
+  let acc = &mut ();
+  let parameter = IOPattern(vec![SpongeOp::Absorb(elements.len() as u32), SpongeOp::Squeeze(1u32)]);
+
+  sponge.start(parameter, None, acc);
+  SpongeAPI::absorb(&mut sponge, elements.len() as u32, elements, acc);
+  let hash = SpongeAPI::squeeze(&mut sponge, 1, acc);
+  sponge.finish(acc).expect("the sponge follows its IO pattern");
+  hash[0]
+}
+
+/// Hashes `elements` inside a circuit, into the same element as `sponge_hash`
+#[cfg(feature = "prove")]
+// the sponge is used exactly as its IO pattern says
+#[allow(clippy::expect_used)]
+fn sponge_hash_circuit<F: PrimeField, A: Arity<F>, CS: ConstraintSystem<F>>(
+  constants: &PoseidonConstants<F, A>,
+  mut cs: CS,
+  elements: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let parameter = IOPattern(vec![SpongeOp::Absorb(elements.len() as u32), SpongeOp::Squeeze(1u32)]);
+  let mut ns = cs.namespace(|| "ns");
+
+  let hash = {
+    let mut sponge = SpongeCircuit::new_with_constants(constants, Simplex);
+    let acc = &mut ns;
+
+    sponge.start(parameter, None, acc);
+    SpongeAPI::absorb(
+      &mut sponge,
+      elements.len() as u32,
+      &elements.iter().cloned().map(Elt::Allocated).collect::<Vec<Elt<F>>>(),
+      acc,
+    );
+
+    let output = SpongeAPI::squeeze(&mut sponge, 1, acc);
+    sponge.finish(acc).expect("the sponge follows its IO pattern");
+    output
+  };
+
+  Elt::ensure_allocated(&hash[0], &mut ns.namespace(|| "ensure allocated"), true)
+}
+
+impl<Scalar: PrimeField> PoseidonConstantsCircuit<Scalar> {
+  /// Generate Poseidon constants for `Scalar`, bypassing the cache
+  fn generate() -> Self { Self(Sponge::<Scalar, U24>::api_constants(Strength::Standard)) }
+
+  /// Hashes `elements` into a single field element with a sponge over these
+  /// constants
+  pub(crate) fn hash(&self, elements: &[Scalar]) -> Scalar { sponge_hash(&self.0, elements) }
+
+  /// Hashes `elements` inside a circuit, into the same element as `hash`
+  #[cfg(feature = "prove")]
+  pub(crate) fn hash_circuit<CS: ConstraintSystem<Scalar>>(
+    &self,
+    cs: CS,
+    elements: &[AllocatedNum<Scalar>],
+  ) -> Result<AllocatedNum<Scalar>, SynthesisError> {
+    sponge_hash_circuit(&self.0, cs, elements)
+  }
+}
+
 impl<Scalar: PrimeField> Default for PoseidonConstantsCircuit<Scalar> {
-  /// Generate Poseidon constants
-  fn default() -> Self { Self(Sponge::<Scalar, U24>::api_constants(Strength::Standard)) }
+  /// Returns the Poseidon constants for `Scalar`, generating them on first use
+  fn default() -> Self { cached(Self::generate) }
+}
+
+/// The arities of the adaptive hash, the numbers of elements a permutation
+/// absorbs, of widths one more
+pub(crate) const ARITIES: [usize; 6] = [2, 4, 8, 11, 16, 24];
+
+/// The Poseidon constants of `F` and the arity `A`, generating them on first
+/// use
+fn arity_constants<F: PrimeField, A: Arity<F>>() -> Arc<PoseidonConstants<F, A>> {
+  cached(|| Arc::new(Sponge::<F, A>::api_constants(Strength::Standard)))
+}
+
+/// Runs `$body` with `$A` the arity type of the arity `$arity`, one of
+/// [`ARITIES`], or `$otherwise` for another arity
+macro_rules! with_arity {
+  ($arity:expr, $A:ident => $body:expr,_ => $otherwise:expr) => {
+    match $arity {
+      2 => {
+        type $A = U2;
+        $body
+      },
+      4 => {
+        type $A = U4;
+        $body
+      },
+      8 => {
+        type $A = U8;
+        $body
+      },
+      11 => {
+        type $A = U11;
+        $body
+      },
+      16 => {
+        type $A = U16;
+        $body
+      },
+      24 => {
+        type $A = U24;
+        $body
+      },
+      _ => $otherwise,
+    }
+  };
+}
+
+/// The number of S-boxes of hashing `len` elements with a sponge of `arity`
+/// over `F`: a permutation per `arity` elements, of a full round an S-box per
+/// element of the state and of a partial round a single S-box
+fn sbox_count<F: PrimeField>(arity: usize, len: usize) -> Option<usize> {
+  let (full_rounds, partial_rounds) = with_arity!(arity, A => {
+    let constants = arity_constants::<F, A>();
+    (constants.full_rounds, constants.partial_rounds)
+  }, _ => return None);
+  Some(len.div_ceil(arity).max(1) * (full_rounds * (arity + 1) + partial_rounds))
+}
+
+/// The arity of the adaptive hash of `len` elements over `F`: of the fewest
+/// S-boxes, the smaller on a tie
+pub(crate) fn adaptive_arity<F: PrimeField>(len: usize) -> usize {
+  ARITIES
+    .into_iter()
+    .filter_map(|arity| Some((sbox_count::<F>(arity, len)?, arity)))
+    .min()
+    .map_or(24, |(_, arity)| arity)
+}
+
+/// Hashes `elements` with a sponge of `arity`, `None` if it is not one of
+/// [`ARITIES`]; of arity 24, this is the hash of [`PoseidonConstantsCircuit`]
+pub(crate) fn hash_with_arity<F: PrimeField>(elements: &[F], arity: usize) -> Option<F> {
+  with_arity!(arity, A => Some(sponge_hash(&*arity_constants::<F, A>(), elements)), _ => None)
+}
+
+/// Hashes `elements` inside a circuit, into the same element as
+/// `hash_with_arity`; unsatisfiable if `arity` is not one of [`ARITIES`]
+#[cfg(feature = "prove")]
+pub(crate) fn hash_circuit_with_arity<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  elements: &[AllocatedNum<F>],
+  arity: usize,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  with_arity!(
+    arity,
+    A => sponge_hash_circuit(&*arity_constants::<F, A>(), cs, elements),
+    _ => Err(SynthesisError::Unsatisfiable)
+  )
+}
+
+/// Hashes `elements` with the sponge of [`adaptive_arity`]
+// `adaptive_arity` only returns supported arities
+#[allow(clippy::expect_used)]
+pub(crate) fn adaptive_hash<F: PrimeField>(elements: &[F]) -> F {
+  let arity = adaptive_arity::<F>(elements.len());
+  hash_with_arity(elements, arity).expect("adaptive arities are supported")
+}
+
+/// Hashes `elements` inside a circuit, into the same element as
+/// [`adaptive_hash`]
+#[cfg(feature = "prove")]
+pub(crate) fn adaptive_hash_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  elements: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  hash_circuit_with_arity(cs, elements, adaptive_arity::<F>(elements.len()))
 }
 
 /// A Poseidon-based RO to use outside circuits
@@ -49,6 +268,7 @@ where
   Base: PrimeField + PrimeFieldBits + Serialize + for<'de> Deserialize<'de>,
   Scalar: PrimeField,
 {
+  #[cfg(feature = "prove")]
   type CircuitRO = PoseidonROCircuit<Base>;
   type Constants = PoseidonConstantsCircuit<Base>;
 
@@ -68,19 +288,11 @@ where
     assert!(!self.squeezed, "Cannot squeeze again after squeezing");
     self.squeezed = true;
 
-    let mut sponge = Sponge::new_with_constants(&self.constants.0, Simplex);
-    let acc = &mut ();
-    let parameter =
-      IOPattern(vec![SpongeOp::Absorb(self.num_absorbs as u32), SpongeOp::Squeeze(1u32)]);
-
-    sponge.start(parameter, None, acc);
     assert_eq!(self.num_absorbs, self.state.len());
-    SpongeAPI::absorb(&mut sponge, self.num_absorbs as u32, &self.state, acc);
-    let hash = SpongeAPI::squeeze(&mut sponge, 1, acc);
-    sponge.finish(acc).unwrap();
+    let hash = self.constants.hash(&self.state);
 
     // Only return `num_bits`
-    let bits = hash[0].to_le_bits();
+    let bits = hash.to_le_bits();
     let mut res = Scalar::ZERO;
     let mut coeff = Scalar::ONE;
     for bit in bits[..num_bits].into_iter() {
@@ -94,6 +306,7 @@ where
 }
 
 /// A Poseidon-based RO gadget to use inside the verifier circuit.
+#[cfg(feature = "prove")]
 #[derive(Debug)]
 pub struct PoseidonROCircuit<Scalar: PrimeField> {
   // Internal state
@@ -103,6 +316,7 @@ pub struct PoseidonROCircuit<Scalar: PrimeField> {
   squeezed:    bool,
 }
 
+#[cfg(feature = "prove")]
 impl<Scalar> ROCircuitTrait<Scalar> for PoseidonROCircuit<Scalar>
 where Scalar: PrimeField + PrimeFieldBits + Serialize + for<'de> Deserialize<'de>
 {
@@ -121,6 +335,8 @@ where Scalar: PrimeField + PrimeFieldBits + Serialize + for<'de> Deserialize<'de
   }
 
   /// Compute a challenge by hashing the current state
+  // the sponge is used exactly as its IO pattern says
+  #[allow(clippy::expect_used)]
   fn squeeze<CS: ConstraintSystem<Scalar>>(
     &mut self,
     mut cs: CS,
@@ -149,7 +365,7 @@ where Scalar: PrimeField + PrimeFieldBits + Serialize + for<'de> Deserialize<'de
       );
 
       let output = SpongeAPI::squeeze(&mut sponge, 1, acc);
-      sponge.finish(acc).unwrap();
+      sponge.finish(acc).expect("the sponge follows its IO pattern");
       output
     };
 
@@ -214,6 +430,19 @@ mod tests {
     assert_eq!(num.to_repr(), num2.get_value().unwrap().to_repr());
   }
 
+  fn test_poseidon_constants_with<F: PrimeField>() {
+    // cached constants are the generated ones, for every field
+    let fresh = PoseidonConstantsCircuit::<F>::generate();
+    assert_eq!(PoseidonConstantsCircuit::<F>::default(), fresh);
+    assert_eq!(PoseidonConstantsCircuit::<F>::default(), fresh);
+  }
+
+  #[test]
+  fn test_poseidon_constants() {
+    test_poseidon_constants_with::<<Bn256EngineKZG as Engine>::Scalar>();
+    test_poseidon_constants_with::<<GrumpkinEngine as Engine>::Scalar>();
+  }
+
   #[test]
   fn test_poseidon_ro() {
     test_poseidon_ro_with::<Bn256EngineKZG>();
@@ -0,0 +1,198 @@
+//! This module provides a Pedersen commitment engine over ristretto255
+//!
+//! The group is the prime-order group of RFC 9496, as implemented by
+//! `curve25519-dalek`, so that commitments interoperate with the dalek
+//! ecosystem: the generators of a key are derived from its label as the
+//! `GeneratorsChain` of Bulletproofs derives them, the blinding generator is
+//! that of its `PedersenGens`, and [`RistrettoCommitmentKey::pedersen_gens`] is
+//! the key of the commitments to the values of its range proofs. Commitments
+//! encode to the 32 bytes of their compressed point, and can be checked against
+//! the test vectors of those libraries.
+//!
+//! ristretto255 is not a Weierstrass curve of a cycle, so it cannot be the
+//! group of an `Engine`, nor be folded. Like
+//! [`MerkleCommitmentEngine`](super::merkle::MerkleCommitmentEngine),
+//! [`RistrettoCommitmentEngine`] mirrors `CommitmentEngineTrait` instead, with
+//! blinded commitments on top.
+use core::ops::{Add, AddAssign};
+
+use curve25519_dalek::{
+  constants::{RISTRETTO_BASEPOINT_COMPRESSED, RISTRETTO_BASEPOINT_POINT},
+  ristretto::{CompressedRistretto, RistrettoPoint},
+  scalar::Scalar,
+  traits::{Identity, VartimeMultiscalarMul},
+};
+use digest::{ExtendableOutput, Update, XofReader};
+use serde::{Deserialize, Serialize};
+use sha3::{Sha3_512, Shake256};
+
+use crate::{
+  errors::NovaError,
+  traits::{commitment::Len, Group, TranscriptReprTrait},
+};
+
+/// The first `n` generators of the chain of `label`, as Bulletproofs'
+/// `GeneratorsChain` derives them: the stream of SHAKE256 on
+/// `"GeneratorsChain" || label` is split in 64-byte blocks, each mapped to the
+/// group with `RistrettoPoint::from_uniform_bytes`
+fn generators_chain(label: &[u8], n: usize) -> Vec<RistrettoPoint> {
+  let mut shake = Shake256::default();
+  shake.update(b"GeneratorsChain");
+  shake.update(label);
+  let mut reader = shake.finalize_xof();
+  (0..n)
+    .map(|_| {
+      let mut uniform_bytes = [0u8; 64];
+      reader.read(&mut uniform_bytes);
+      RistrettoPoint::from_uniform_bytes(&uniform_bytes)
+    })
+    .collect()
+}
+
+/// The blinding generator of Bulletproofs' `PedersenGens`: the hash to the
+/// group of the compressed base point, with SHA3-512
+fn blinding_generator() -> RistrettoPoint {
+  RistrettoPoint::hash_from_bytes::<Sha3_512>(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes())
+}
+
+/// A type that holds the generators of Pedersen commitments over ristretto255
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RistrettoCommitmentKey {
+  ck: Vec<RistrettoPoint>,
+  h:  RistrettoPoint,
+}
+
+impl RistrettoCommitmentKey {
+  /// The key of Bulletproofs' `PedersenGens`, committing to a single value
+  /// with the base point of ristretto255
+  pub fn pedersen_gens() -> Self {
+    Self { ck: vec![RISTRETTO_BASEPOINT_POINT], h: blinding_generator() }
+  }
+
+  /// The generators of the committed entries
+  pub fn generators(&self) -> &[RistrettoPoint] { &self.ck }
+
+  /// The generator of the blinding factor
+  pub fn blinding_generator(&self) -> &RistrettoPoint { &self.h }
+}
+
+impl Len for RistrettoCommitmentKey {
+  fn length(&self) -> usize { self.ck.len() }
+}
+
+/// A type that holds a Pedersen commitment over ristretto255
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RistrettoCommitment(RistrettoPoint);
+
+impl RistrettoCommitment {
+  /// The committed point
+  pub fn point(&self) -> &RistrettoPoint { &self.0 }
+
+  /// The canonical encoding of the commitment, as in RFC 9496
+  pub fn to_bytes(&self) -> [u8; 32] { self.0.compress().to_bytes() }
+
+  /// The commitment of the encoding `bytes`, which must be canonical
+  pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, NovaError> {
+    CompressedRistretto(*bytes).decompress().map(Self).ok_or(NovaError::DecompressionError)
+  }
+}
+
+impl Default for RistrettoCommitment {
+  fn default() -> Self { Self(RistrettoPoint::identity()) }
+}
+
+impl Add for RistrettoCommitment {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self { Self(self.0 + other.0) }
+}
+
+impl AddAssign for RistrettoCommitment {
+  fn add_assign(&mut self, other: Self) { self.0 += other.0 }
+}
+
+impl<G: Group> TranscriptReprTrait<G> for RistrettoCommitment {
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.to_bytes().to_vec() }
+}
+
+/// Provides a Pedersen commitment engine over ristretto255
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RistrettoCommitmentEngine;
+
+impl RistrettoCommitmentEngine {
+  /// Creates the key for commitments to vectors of up to `n` entries, from
+  /// the generators chain of `label`. Like `setup` on a
+  /// `CommitmentEngineTrait`, but the key for `n` is exactly `n` generators,
+  /// the first `n` of Bulletproofs' chain of `label`.
+  pub fn setup(label: &'static [u8], n: usize) -> RistrettoCommitmentKey {
+    RistrettoCommitmentKey { ck: generators_chain(label, n), h: blinding_generator() }
+  }
+
+  /// Commits to the provided vector
+  pub fn commit(
+    ck: &RistrettoCommitmentKey,
+    v: &[Scalar],
+  ) -> Result<RistrettoCommitment, NovaError> {
+    Self::commit_blinded(ck, v, &Scalar::ZERO)
+  }
+
+  /// Commits to the provided vector, blinded by `r`
+  pub fn commit_blinded(
+    ck: &RistrettoCommitmentKey,
+    v: &[Scalar],
+    r: &Scalar,
+  ) -> Result<RistrettoCommitment, NovaError> {
+    if v.len() > ck.ck.len() {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    let comm = RistrettoPoint::vartime_multiscalar_mul(
+      v.iter().chain([r]),
+      ck.ck[..v.len()].iter().chain([&ck.h]),
+    );
+    Ok(RistrettoCommitment(comm))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+
+  type CE = RistrettoCommitmentEngine;
+
+  #[test]
+  fn test_ristretto_commitment() {
+    // the encodings of the first multiples of the base point, from RFC 9496
+    let ck = RistrettoCommitmentKey::pedersen_gens();
+    for (v, encoding) in [
+      (1u64, "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76"),
+      (2, "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919"),
+      (3, "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259"),
+    ] {
+      let comm = CE::commit(&ck, &[Scalar::from(v)]).unwrap();
+      assert_eq!(hex::encode(comm.to_bytes()), encoding);
+      assert_eq!(RistrettoCommitment::from_bytes(&comm.to_bytes()), Ok(comm));
+    }
+
+    // commitments add up, blinding factors included
+    let ck = CE::setup(b"test", 8);
+    let v: Vec<Scalar> = (0..5).map(|_| Scalar::random(&mut OsRng)).collect();
+    let w: Vec<Scalar> = (0..5).map(|_| Scalar::random(&mut OsRng)).collect();
+    let (r, s) = (Scalar::random(&mut OsRng), Scalar::random(&mut OsRng));
+    let sum: Vec<Scalar> = v.iter().zip(w.iter()).map(|(a, b)| a + b).collect();
+    assert_eq!(
+      CE::commit_blinded(&ck, &v, &r).unwrap() + CE::commit_blinded(&ck, &w, &s).unwrap(),
+      CE::commit_blinded(&ck, &sum, &(r + s)).unwrap()
+    );
+    assert_ne!(CE::commit(&ck, &v).unwrap(), CE::commit_blinded(&ck, &v, &r).unwrap());
+
+    // the key for `n` is a prefix of the key for a larger `n`
+    assert_eq!(CE::setup(b"test", 3).generators(), &ck.generators()[..3]);
+    assert_eq!(CE::setup(b"test", 3).blinding_generator(), ck.blinding_generator());
+    assert_eq!(CE::commit(&ck, &[Scalar::ONE; 9]), Err(NovaError::InvalidCommitmentKeyLength));
+
+    // a non-canonical encoding does not decode
+    assert_eq!(RistrettoCommitment::from_bytes(&[0xff; 32]), Err(NovaError::DecompressionError));
+  }
+}
@@ -0,0 +1,205 @@
+//! An auditable log of the transparent derivation of Pedersen commitment keys.
+//!
+//! Pedersen keys need no ceremony: `CommitmentEngineTrait::setup` derives the
+//! generators from a public label (see `DlogGroup::from_label`), so anyone can
+//! recompute them. A [`SetupLog`] records that derivation: the label, the
+//! number of generators, and digests of the generators, chunk by chunk. It is
+//! meant to ship alongside a serialized key, so that an integrator can check
+//! cheaply that the key is the one the log describes ([`SetupLog::verify_key`]),
+//! and, once, that the log describes the transparent derivation from its label
+//! ([`SetupLog::verify_derivation`]), which pinpoints the first chunk that does
+//! not match.
+use group::prime::PrimeCurveAffine;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+  errors::NovaError,
+  parallel::prelude::*,
+  provider::{pedersen::CommitmentKey, traits::DlogGroup},
+  traits::Engine,
+};
+
+/// The number of generators hashed into each digest of the log
+const LOG_CHUNK_SIZE: usize = 1 << 12;
+
+/// The derivation the log describes, hashed into its digest
+const DERIVATION: &str = "SHAKE256(label) in 32-byte blocks, hashed to the curve under the domain \
+                          prefix \"from_uniform_bytes\"";
+
+/// A log of the transparent derivation of a Pedersen commitment key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupLog {
+  /// the label the generators are derived from
+  pub label:          Vec<u8>,
+  /// the number of generators of the key
+  pub num_generators: usize,
+  /// the number of generators hashed into each of `chunk_digests`
+  pub chunk_size:     usize,
+  /// the SHA3-256 digests of the compressed encodings of the generators, by
+  /// chunks of `chunk_size`
+  pub chunk_digests:  Vec<[u8; 32]>,
+  /// the digest of the derivation, the parameters and the chunk digests, to
+  /// publish alongside the key
+  pub digest:         [u8; 32],
+}
+
+impl SetupLog {
+  /// Derives the commitment key `setup(label, n)` would derive, along with
+  /// its log
+  pub fn derive<E>(label: &[u8], n: usize) -> (CommitmentKey<E>, Self)
+  where
+    E: Engine,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+    let ck = CommitmentKey::<E>::from_label(label, n.next_power_of_two());
+    let log = Self::new::<E>(label, &ck);
+    (ck, log)
+  }
+
+  /// Logs `ck` as derived from `label`, without checking that it is
+  fn new<E>(label: &[u8], ck: &CommitmentKey<E>) -> Self
+  where
+    E: Engine,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+    let chunk_digests = chunk_digests::<E>(ck, LOG_CHUNK_SIZE);
+    let digest = log_digest(label, ck.ck.len(), LOG_CHUNK_SIZE, &chunk_digests);
+    Self {
+      label: label.to_vec(),
+      num_generators: ck.ck.len(),
+      chunk_size: LOG_CHUNK_SIZE,
+      chunk_digests,
+      digest,
+    }
+  }
+
+  /// Checks that the log is consistent with its digest
+  fn verify_digest(&self) -> Result<(), NovaError> {
+    let expected_chunks = self.num_generators.div_ceil(self.chunk_size.max(1));
+    if self.chunk_size == 0
+      || self.chunk_digests.len() != expected_chunks
+      || log_digest(&self.label, self.num_generators, self.chunk_size, &self.chunk_digests)
+        != self.digest
+    {
+      return Err(NovaError::InvalidSetupLog(None));
+    }
+    Ok(())
+  }
+
+  /// Checks that `ck` is the key the log describes. This only hashes the
+  /// generators of `ck`.
+  pub fn verify_key<E>(&self, ck: &CommitmentKey<E>) -> Result<(), NovaError>
+  where
+    E: Engine,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+    self.verify_digest()?;
+    if ck.ck.len() != self.num_generators {
+      return Err(NovaError::InvalidSetupLog(None));
+    }
+    self.verify_chunks(&chunk_digests::<E>(ck, self.chunk_size))
+  }
+
+  /// Checks that the log describes the derivation of its generators from its
+  /// label, by deriving them again. This is as expensive as the setup.
+  pub fn verify_derivation<E>(&self) -> Result<(), NovaError>
+  where
+    E: Engine,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+    self.verify_digest()?;
+    let ck = CommitmentKey::<E>::from_label(&self.label, self.num_generators);
+    self.verify_chunks(&chunk_digests::<E>(&ck, self.chunk_size))
+  }
+
+  /// Compares the chunk digests of the log with `chunk_digests`, reporting
+  /// the first chunk that differs
+  fn verify_chunks(&self, chunk_digests: &[[u8; 32]]) -> Result<(), NovaError> {
+    match self.chunk_digests.iter().zip(chunk_digests).position(|(logged, actual)| logged != actual)
+    {
+      Some(chunk) => Err(NovaError::InvalidSetupLog(Some(chunk))),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Hashes the compressed encodings of the generators of `ck`, by chunks of
+/// `chunk_size`
+fn chunk_digests<E>(ck: &CommitmentKey<E>, chunk_size: usize) -> Vec<[u8; 32]>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  ck.ck
+    .par_chunks(chunk_size)
+    .map(|chunk| {
+      let mut hasher = Sha3_256::new();
+      for g in chunk {
+        hasher.update(g.to_curve().to_compressed_bytes());
+      }
+      hasher.finalize().into()
+    })
+    .collect()
+}
+
+/// Hashes the derivation, its parameters and the chunk digests
+fn log_digest(
+  label: &[u8],
+  num_generators: usize,
+  chunk_size: usize,
+  chunk_digests: &[[u8; 32]],
+) -> [u8; 32] {
+  let mut hasher = Sha3_256::new();
+  hasher.update(DERIVATION.as_bytes());
+  hasher.update((label.len() as u64).to_le_bytes());
+  hasher.update(label);
+  hasher.update((num_generators as u64).to_le_bytes());
+  hasher.update((chunk_size as u64).to_le_bytes());
+  for chunk_digest in chunk_digests {
+    hasher.update(chunk_digest);
+  }
+  hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{Bn256EngineIPA, GrumpkinEngine},
+    traits::commitment::CommitmentEngineTrait,
+  };
+
+  fn test_setup_log_with<E>()
+  where
+    E: Engine,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>,
+    E::CE: CommitmentEngineTrait<E, CommitmentKey = CommitmentKey<E>>, {
+    let n = 3 * LOG_CHUNK_SIZE + 1;
+    let (ck, log) = SetupLog::derive::<E>(b"test", n);
+    assert_eq!(ck, E::CE::setup(b"test", n));
+    assert_eq!(log.num_generators, n.next_power_of_two());
+
+    log.verify_key(&ck).unwrap();
+    log.verify_derivation::<E>().unwrap();
+
+    // the log survives a round trip
+    let log: SetupLog = bincode::deserialize(&bincode::serialize(&log).unwrap()).unwrap();
+    log.verify_key(&ck).unwrap();
+
+    // a key with a replaced generator is caught at its chunk
+    let mut bad_ck = ck.clone();
+    bad_ck.ck[2 * LOG_CHUNK_SIZE + 5] = bad_ck.ck[0];
+    assert!(matches!(log.verify_key(&bad_ck), Err(NovaError::InvalidSetupLog(Some(2)))));
+
+    // a key derived from another label does not match
+    let (other_ck, _) = SetupLog::derive::<E>(b"other", n);
+    assert!(log.verify_key(&other_ck).is_err());
+
+    // a log whose label was changed is caught by its digest
+    let mut bad_log = log.clone();
+    bad_log.label = b"other".to_vec();
+    assert!(matches!(bad_log.verify_derivation::<E>(), Err(NovaError::InvalidSetupLog(None))));
+  }
+
+  #[test]
+  fn test_setup_log() {
+    test_setup_log_with::<Bn256EngineIPA>();
+    test_setup_log_with::<GrumpkinEngine>();
+  }
+}
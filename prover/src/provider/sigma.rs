@@ -0,0 +1,272 @@
+//! Sigma protocols over a [`DlogGroup`], made non-interactive with the
+//! transcript of an engine, e.g. to bind the identity key of a client to a
+//! proof without a SNARK:
+//! - an [`OpeningProof`] proves the knowledge of an opening `w` of `P = <w, bases>`: with a single
+//!   base, the knowledge of the secret key of `P`, as a Schnorr proof, and with the generators of a
+//!   commitment key, the knowledge of the opening of a Pedersen commitment,
+//! - a [`DlogEqualityProof`] proves that `X = x * G` and `Y = x * H` share their discrete
+//!   logarithm, as a Chaum-Pedersen proof.
+//!
+//! The proofs absorb their statement before their first message, so that a
+//! proof in a transcript that already holds e.g. the instance of a SNARK is
+//! bound to that instance.
+use ff::Field;
+use group::prime::{PrimeCurve, PrimeCurveAffine};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::{
+  pedersen::{Commitment, CommitmentEngine, CommitmentKey},
+  traits::DlogGroup,
+};
+use crate::{
+  errors::NovaError,
+  secret::wipe,
+  traits::{Engine, TranscriptEngineTrait},
+};
+
+type Affine<E> = <<E as Engine>::GE as PrimeCurve>::Affine;
+
+/// Absorbs `points` in `transcript`, as the commitments they are
+fn absorb_points<E>(transcript: &mut E::TE, label: &'static [u8], points: &[E::GE])
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  let comms = points.iter().map(|comm| Commitment::<E> { comm: *comm }).collect::<Vec<_>>();
+  transcript.absorb(label, &comms);
+}
+
+/// A proof of the knowledge of an opening `w` of `P = <w, bases>`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OpeningProof<E: Engine> {
+  comm_r: Commitment<E>,
+  s:      Vec<E::Scalar>,
+}
+
+impl<E> OpeningProof<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// The challenge of the proof, from a transcript of its statement and of
+  /// its first message
+  fn challenge(
+    transcript: &mut E::TE,
+    bases: &[Affine<E>],
+    point: &E::GE,
+    comm_r: &Commitment<E>,
+  ) -> Result<E::Scalar, NovaError> {
+    transcript.dom_sep(b"opening");
+    absorb_points::<E>(
+      transcript,
+      b"bases",
+      &bases.iter().map(|b| b.to_curve()).collect::<Vec<_>>(),
+    );
+    absorb_points::<E>(transcript, b"P", &[*point]);
+    transcript.absorb(b"R", comm_r);
+    transcript.squeeze(b"c")
+  }
+
+  /// Proves the knowledge of `witness`, the opening of `point` on `bases`
+  pub fn prove(
+    bases: &[Affine<E>],
+    point: &E::GE,
+    witness: &[E::Scalar],
+    transcript: &mut E::TE,
+    mut rng: impl RngCore + CryptoRng,
+  ) -> Result<Self, NovaError> {
+    if witness.len() != bases.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut r = (0..bases.len()).map(|_| E::Scalar::random(&mut rng)).collect::<Vec<_>>();
+    let comm_r = Commitment { comm: E::GE::vartime_multiscalar_mul(&r, bases) };
+
+    let c = Self::challenge(transcript, bases, point, &comm_r)?;
+    let s = r.iter().zip(witness).map(|(r, w)| *r + c * w).collect();
+    wipe(&mut r);
+    Ok(Self { comm_r, s })
+  }
+
+  /// Verifies the knowledge of an opening of `point` on `bases`
+  pub fn verify(
+    &self,
+    bases: &[Affine<E>],
+    point: &E::GE,
+    transcript: &mut E::TE,
+  ) -> Result<(), NovaError> {
+    if self.s.len() != bases.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let c = Self::challenge(transcript, bases, point, &self.comm_r)?;
+    if E::GE::vartime_multiscalar_mul(&self.s, bases) != self.comm_r.comm + *point * c {
+      return Err(NovaError::ProofVerifyError);
+    }
+    Ok(())
+  }
+}
+
+impl<E> OpeningProof<E>
+where
+  E: Engine<CE = CommitmentEngine<E>>,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// Proves the knowledge of `v`, the opening of the Pedersen commitment
+  /// `comm` under the first generators of `ck`
+  pub fn prove_commitment(
+    ck: &CommitmentKey<E>,
+    comm: &Commitment<E>,
+    v: &[E::Scalar],
+    transcript: &mut E::TE,
+    rng: impl RngCore + CryptoRng,
+  ) -> Result<Self, NovaError> {
+    if v.len() > ck.ck.len() {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    Self::prove(&ck.ck[..v.len()], &comm.comm, v, transcript, rng)
+  }
+
+  /// Verifies the knowledge of an opening of the Pedersen commitment `comm`
+  /// under the first generators of `ck`
+  pub fn verify_commitment(
+    &self,
+    ck: &CommitmentKey<E>,
+    comm: &Commitment<E>,
+    transcript: &mut E::TE,
+  ) -> Result<(), NovaError> {
+    if self.s.len() > ck.ck.len() {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    self.verify(&ck.ck[..self.s.len()], &comm.comm, transcript)
+  }
+}
+
+/// A proof that `X = x * G` and `Y = x * H` for the same `x`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct DlogEqualityProof<E: Engine> {
+  comm_r_g: Commitment<E>,
+  comm_r_h: Commitment<E>,
+  s:        E::Scalar,
+}
+
+impl<E> DlogEqualityProof<E>
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>,
+{
+  /// The challenge of the proof, from a transcript of its statement and of
+  /// its first message
+  fn challenge(
+    transcript: &mut E::TE,
+    (g, h): (&E::GE, &E::GE),
+    (x, y): (&E::GE, &E::GE),
+    (comm_r_g, comm_r_h): (&Commitment<E>, &Commitment<E>),
+  ) -> Result<E::Scalar, NovaError> {
+    transcript.dom_sep(b"dlog equality");
+    absorb_points::<E>(transcript, b"statement", &[*g, *h, *x, *y]);
+    absorb_points::<E>(transcript, b"R", &[comm_r_g.comm, comm_r_h.comm]);
+    transcript.squeeze(b"c")
+  }
+
+  /// Proves that `x_point = x * g` and `y_point = x * h`
+  pub fn prove(
+    (g, h): (&E::GE, &E::GE),
+    (x_point, y_point): (&E::GE, &E::GE),
+    x: &E::Scalar,
+    transcript: &mut E::TE,
+    mut rng: impl RngCore + CryptoRng,
+  ) -> Result<Self, NovaError> {
+    let mut r = vec![E::Scalar::random(&mut rng)];
+    let comm_r_g = Commitment { comm: *g * r[0] };
+    let comm_r_h = Commitment { comm: *h * r[0] };
+
+    let c = Self::challenge(transcript, (g, h), (x_point, y_point), (&comm_r_g, &comm_r_h))?;
+    let s = r[0] + c * x;
+    wipe(&mut r);
+    Ok(Self { comm_r_g, comm_r_h, s })
+  }
+
+  /// Verifies that `x_point` and `y_point` share their discrete logarithm in
+  /// the bases `g` and `h`
+  pub fn verify(
+    &self,
+    (g, h): (&E::GE, &E::GE),
+    (x_point, y_point): (&E::GE, &E::GE),
+    transcript: &mut E::TE,
+  ) -> Result<(), NovaError> {
+    let c =
+      Self::challenge(transcript, (g, h), (x_point, y_point), (&self.comm_r_g, &self.comm_r_h))?;
+    if *g * self.s != self.comm_r_g.comm + *x_point * c
+      || *h * self.s != self.comm_r_h.comm + *y_point * c
+    {
+      return Err(NovaError::ProofVerifyError);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use group::{Curve, Group as _};
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    provider::{Bn256EngineIPA, GrumpkinEngine},
+    traits::commitment::CommitmentEngineTrait,
+  };
+
+  fn test_sigma_with<E>()
+  where
+    E: Engine<CE = CommitmentEngine<E>>,
+    E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+    let transcript = || E::TE::new(b"test");
+
+    // a Schnorr proof of the knowledge of a secret key
+    let g = E::GE::generator();
+    let sk = E::Scalar::random(&mut OsRng);
+    let pk = g * sk;
+    let bases = [g.to_affine()];
+    let proof = OpeningProof::<E>::prove(&bases, &pk, &[sk], &mut transcript(), OsRng).unwrap();
+    assert_eq!(proof.verify(&bases, &pk, &mut transcript()), Ok(()));
+    assert_eq!(
+      proof.verify(&bases, &(pk + g), &mut transcript()),
+      Err(NovaError::ProofVerifyError)
+    );
+    let mut other_transcript = E::TE::new(b"other");
+    assert!(proof.verify(&bases, &pk, &mut other_transcript).is_err());
+
+    // the knowledge of the opening of a Pedersen commitment
+    let ck = E::CE::setup(b"test", 4);
+    let v = (0..3).map(|_| E::Scalar::random(&mut OsRng)).collect::<Vec<_>>();
+    let comm = E::CE::commit(&ck, &v);
+    let proof = OpeningProof::prove_commitment(&ck, &comm, &v, &mut transcript(), OsRng).unwrap();
+    assert_eq!(proof.verify_commitment(&ck, &comm, &mut transcript()), Ok(()));
+    let other = E::CE::commit(&ck, &v[..2]);
+    assert!(proof.verify_commitment(&ck, &other, &mut transcript()).is_err());
+    assert_eq!(
+      OpeningProof::prove_commitment(&ck, &comm, &[v[0]; 5], &mut transcript(), OsRng),
+      Err(NovaError::InvalidCommitmentKeyLength)
+    );
+
+    // the equality of two discrete logarithms
+    let h = E::GE::from_label(b"test h", 1)[0].to_curve();
+    let (x_point, y_point) = (g * sk, h * sk);
+    let proof =
+      DlogEqualityProof::<E>::prove((&g, &h), (&x_point, &y_point), &sk, &mut transcript(), OsRng)
+        .unwrap();
+    assert_eq!(proof.verify((&g, &h), (&x_point, &y_point), &mut transcript()), Ok(()));
+    let y_other = h * (sk + E::Scalar::ONE);
+    assert_eq!(
+      proof.verify((&g, &h), (&x_point, &y_other), &mut transcript()),
+      Err(NovaError::ProofVerifyError)
+    );
+  }
+
+  #[test]
+  fn test_sigma() {
+    test_sigma_with::<Bn256EngineIPA>();
+    test_sigma_with::<GrumpkinEngine>();
+  }
+}
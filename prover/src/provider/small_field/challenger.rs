@@ -0,0 +1,115 @@
+//! A Fiat-Shamir transcript over keccak256 for small-field protocols, which
+//! samples its challenges in an extension of the field.
+//!
+//! `Keccak256Transcript` is bound to an `Engine`, which a small field cannot
+//! be: it has no curve. [`Challenger`] absorbs bytes and elements of the
+//! field, and squeezes elements of the field, of its extensions, and indices.
+use ff::PrimeField;
+use sha3::{Digest, Keccak256};
+
+use super::extension::ExtensionField;
+use crate::{errors::NovaError, scalar_bytes::ScalarBytesLe};
+
+const PERSONA_TAG: &[u8] = b"NoSF";
+const SQUEEZE_TAG: &[u8] = b"NoSQ";
+
+/// A Fiat-Shamir transcript for protocols over a small field
+#[derive(Clone, Debug)]
+pub struct Challenger {
+  state: Keccak256,
+}
+
+impl Challenger {
+  /// Creates a transcript, domain-separated by `label`
+  pub fn new(label: &'static [u8]) -> Self {
+    let mut state = Keccak256::new();
+    state.update(PERSONA_TAG);
+    state.update(label);
+    Self { state }
+  }
+
+  /// Absorbs `bytes` under `label`
+  pub fn absorb_bytes(&mut self, label: &'static [u8], bytes: &[u8]) {
+    self.state.update(label);
+    self.state.update((bytes.len() as u64).to_le_bytes());
+    self.state.update(bytes);
+  }
+
+  /// Absorbs elements of an extension of `F`, e.g. `F` itself, under `label`
+  pub fn absorb<F: PrimeField, EF: ExtensionField<F>>(&mut self, label: &'static [u8], v: &[EF]) {
+    let bytes = v
+      .iter()
+      .flat_map(|e| e.to_base_coeffs())
+      .flat_map(|c| ScalarBytesLe::new(&c).as_bytes().to_vec())
+      .collect::<Vec<_>>();
+    self.absorb_bytes(label, &bytes);
+  }
+
+  /// Squeezes 32 bytes under `label`, which are absorbed in turn
+  fn squeeze_bytes(&mut self, label: &'static [u8]) -> [u8; 32] {
+    let mut hasher = self.state.clone();
+    hasher.update(SQUEEZE_TAG);
+    hasher.update(label);
+    let output: [u8; 32] = hasher.finalize().into();
+    self.state.update(output);
+    output
+  }
+
+  /// Squeezes an element of `F`, which fails unless `F` has at most 64 bits,
+  /// so that the reduction of 128 bits is close to uniform
+  pub fn squeeze_base<F: PrimeField>(&mut self, label: &'static [u8]) -> Result<F, NovaError> {
+    if F::NUM_BITS > 64 {
+      return Err(NovaError::UnsupportedField(F::NUM_BITS));
+    }
+    let output = self.squeeze_bytes(label);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&output[..16]);
+    Ok(F::from_u128(u128::from_le_bytes(bytes)))
+  }
+
+  /// Squeezes an element of an extension of `F`
+  pub fn squeeze<F: PrimeField, EF: ExtensionField<F>>(
+    &mut self,
+    label: &'static [u8],
+  ) -> Result<EF, NovaError> {
+    let coeffs =
+      (0..EF::DEGREE).map(|_| self.squeeze_base::<F>(label)).collect::<Result<Vec<_>, _>>()?;
+    EF::from_base_coeffs(&coeffs).ok_or(NovaError::InternalError)
+  }
+
+  /// Squeezes an index below `n`, which fails unless `n` is a power of two
+  pub fn squeeze_index(&mut self, label: &'static [u8], n: usize) -> Result<usize, NovaError> {
+    if !n.is_power_of_two() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let output = self.squeeze_bytes(label);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&output[..8]);
+    Ok((u64::from_le_bytes(bytes) as usize) & (n - 1))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::small_field::fields::BabyBear;
+
+  #[test]
+  fn test_squeeze_preconditions() {
+    let mut challenger = Challenger::new(b"test");
+    assert!(challenger.squeeze_index(b"i", 16).is_ok_and(|i| i < 16));
+    assert_eq!(challenger.squeeze_index(b"i", 12), Err(NovaError::InvalidInputLength));
+    assert_eq!(challenger.squeeze_index(b"i", 0), Err(NovaError::InvalidInputLength));
+    assert!(challenger.squeeze::<BabyBear, BabyBear>(b"c").is_ok());
+  }
+
+  #[test]
+  fn test_squeeze_large_field() {
+    let mut challenger = Challenger::new(b"test");
+    assert!(challenger.squeeze_base::<BabyBear>(b"c").is_ok());
+    assert_eq!(
+      challenger.squeeze_base::<halo2curves::bn256::Fr>(b"c"),
+      Err(NovaError::UnsupportedField(254))
+    );
+  }
+}
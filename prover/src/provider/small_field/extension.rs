@@ -0,0 +1,301 @@
+//! Extension fields of small prime fields, to sample challenges from.
+//!
+//! A challenge in a 64-bit field makes a protocol sound up to `2^-64` at
+//! best, so the challenges of a small-field engine live in an extension of
+//! degree `D` of its field, where the polynomials committed to still live.
+//! [`BinomialExtension`] is the extension `F[X] / (X^D - W)`, for a `W` that
+//! makes `X^D - W` irreducible.
+use core::{
+  fmt::Debug,
+  iter::{Product, Sum},
+  ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use ff::{Field, PrimeField};
+use rand_core::RngCore;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::fields::{BabyBear, Goldilocks};
+use crate::scalar_bytes::ScalarBytesLe;
+
+/// An extension of the field `F`, in which the challenges of a protocol over
+/// `F` are sampled. Every field is an extension of degree 1 of itself.
+///
+/// Its `Default` is zero, and `Self::from(F::ONE)` is one.
+pub trait ExtensionField<F: Field>:
+  Copy
+  + Clone
+  + Debug
+  + Default
+  + Eq
+  + Send
+  + Sync
+  + 'static
+  + From<F>
+  + Add<Output = Self>
+  + Sub<Output = Self>
+  + Mul<Output = Self>
+  + Mul<F, Output = Self>
+  + Neg<Output = Self>
+  + AddAssign
+  + SubAssign
+  + MulAssign
+  + Sum
+  + Product {
+  /// The degree of the extension
+  const DEGREE: usize;
+
+  /// The element whose coordinates over `F` are `coeffs`, or `None` if there
+  /// are not `DEGREE` of them
+  fn from_base_coeffs(coeffs: &[F]) -> Option<Self>;
+
+  /// The coordinates of the element over `F`
+  fn to_base_coeffs(&self) -> Vec<F>;
+
+  /// The inverse of the element, or `None` if it is zero
+  fn inverse(&self) -> Option<Self>;
+}
+
+impl<F: Field> ExtensionField<F> for F {
+  const DEGREE: usize = 1;
+
+  fn from_base_coeffs(coeffs: &[F]) -> Option<Self> {
+    match coeffs {
+      [c] => Some(*c),
+      _ => None,
+    }
+  }
+
+  fn to_base_coeffs(&self) -> Vec<F> { vec![*self] }
+
+  fn inverse(&self) -> Option<Self> { Option::from(self.invert()) }
+}
+
+/// A prime field with a non-residue `W` such that `X^D - W` is irreducible,
+/// and whose order is 1 modulo `D`
+pub trait BinomialNonResidue<const D: usize>: PrimeField {
+  /// The non-residue `W`
+  fn non_residue() -> Self;
+}
+
+impl BinomialNonResidue<2> for Goldilocks {
+  fn non_residue() -> Self { Self::from(7) }
+}
+
+impl BinomialNonResidue<3> for Goldilocks {
+  fn non_residue() -> Self { Self::from(2) }
+}
+
+impl BinomialNonResidue<3> for BabyBear {
+  fn non_residue() -> Self { Self::from(2) }
+}
+
+impl BinomialNonResidue<4> for BabyBear {
+  fn non_residue() -> Self { Self::from(11) }
+}
+
+/// The extension `F[X] / (X^D - W)` of `F`, whose elements are represented
+/// by their coefficients, lowest degree first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BinomialExtension<F, const D: usize>([F; D]);
+
+/// The quadratic extension of Goldilocks, of about 128 bits
+pub type GoldilocksExt2 = BinomialExtension<Goldilocks, 2>;
+
+/// The cubic extension of Goldilocks, of about 192 bits
+pub type GoldilocksExt3 = BinomialExtension<Goldilocks, 3>;
+
+/// The cubic extension of BabyBear, of about 93 bits
+pub type BabyBearExt3 = BinomialExtension<BabyBear, 3>;
+
+/// The quartic extension of BabyBear, of about 124 bits
+pub type BabyBearExt4 = BinomialExtension<BabyBear, 4>;
+
+impl<F: BinomialNonResidue<D>, const D: usize> BinomialExtension<F, D> {
+  /// A uniformly random element
+  pub fn random(mut rng: impl RngCore) -> Self {
+    Self(core::array::from_fn(|_| F::random(&mut rng)))
+  }
+
+  /// `W^((p - 1) / D)`, so that the Frobenius map sends `X^i` to
+  /// `X^i * W^(i (p - 1) / D)`
+  fn frobenius_coeff() -> F {
+    // p - 1, as little-endian limbs, divided by D
+    let mut exp = ScalarBytesLe::new(&-F::ONE)
+      .as_bytes()
+      .chunks(8)
+      .map(|chunk| {
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(bytes)
+      })
+      .collect::<Vec<_>>();
+    let mut rem = 0u128;
+    for limb in exp.iter_mut().rev() {
+      let cur = (rem << 64) | u128::from(*limb);
+      *limb = (cur / D as u128) as u64;
+      rem = cur % D as u128;
+    }
+    debug_assert_eq!(rem, 0, "the order of the field is not 1 modulo D");
+    F::non_residue().pow_vartime(&exp)
+  }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Default for BinomialExtension<F, D> {
+  fn default() -> Self { Self([F::ZERO; D]) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> From<F> for BinomialExtension<F, D> {
+  fn from(c: F) -> Self {
+    let mut coeffs = [F::ZERO; D];
+    coeffs[0] = c;
+    Self(coeffs)
+  }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Add for BinomialExtension<F, D> {
+  type Output = Self;
+
+  fn add(self, rhs: Self) -> Self { Self(core::array::from_fn(|i| self.0[i] + rhs.0[i])) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Sub for BinomialExtension<F, D> {
+  type Output = Self;
+
+  fn sub(self, rhs: Self) -> Self { Self(core::array::from_fn(|i| self.0[i] - rhs.0[i])) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Neg for BinomialExtension<F, D> {
+  type Output = Self;
+
+  fn neg(self) -> Self { Self(self.0.map(|c| -c)) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Mul for BinomialExtension<F, D> {
+  type Output = Self;
+
+  fn mul(self, rhs: Self) -> Self {
+    let w = F::non_residue();
+    let mut coeffs = [F::ZERO; D];
+    for (i, a) in self.0.iter().enumerate() {
+      for (j, b) in rhs.0.iter().enumerate() {
+        // X^(i + j) = W X^(i + j - D) past the degree
+        if i + j < D {
+          coeffs[i + j] += *a * b;
+        } else {
+          coeffs[i + j - D] += w * a * b;
+        }
+      }
+    }
+    Self(coeffs)
+  }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Mul<F> for BinomialExtension<F, D> {
+  type Output = Self;
+
+  fn mul(self, rhs: F) -> Self { Self(self.0.map(|c| c * rhs)) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> AddAssign for BinomialExtension<F, D> {
+  fn add_assign(&mut self, rhs: Self) { *self = *self + rhs; }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> SubAssign for BinomialExtension<F, D> {
+  fn sub_assign(&mut self, rhs: Self) { *self = *self - rhs; }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> MulAssign for BinomialExtension<F, D> {
+  fn mul_assign(&mut self, rhs: Self) { *self = *self * rhs; }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Sum for BinomialExtension<F, D> {
+  fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::default(), Add::add) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> Product for BinomialExtension<F, D> {
+  fn product<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::from(F::ONE), Mul::mul) }
+}
+
+impl<F: BinomialNonResidue<D>, const D: usize> ExtensionField<F> for BinomialExtension<F, D> {
+  const DEGREE: usize = D;
+
+  fn from_base_coeffs(coeffs: &[F]) -> Option<Self> { coeffs.try_into().ok().map(Self) }
+
+  fn to_base_coeffs(&self) -> Vec<F> { self.0.to_vec() }
+
+  fn inverse(&self) -> Option<Self> {
+    // the product of the conjugates of `self` other than itself, by which
+    // multiplying `self` gives its norm, in `F`
+    let z = Self::frobenius_coeff();
+    let conjugates = (1..D)
+      .map(|k| {
+        let z_k = z.pow_vartime([k as u64]);
+        let mut z_ik = F::ONE;
+        Self(self.0.map(|c| {
+          let c = c * z_ik;
+          z_ik *= z_k;
+          c
+        }))
+      })
+      .product::<Self>();
+    let norm = (*self * conjugates).0[0];
+    Option::<F>::from(norm.invert()).map(|norm_inv| conjugates * norm_inv)
+  }
+}
+
+impl<F: BinomialNonResidue<D> + Serialize, const D: usize> Serialize for BinomialExtension<F, D> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.0.as_slice().serialize(serializer)
+  }
+}
+
+impl<'de, F, const D: usize> Deserialize<'de> for BinomialExtension<F, D>
+where F: BinomialNonResidue<D> + Deserialize<'de>
+{
+  fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+    let coeffs = Vec::<F>::deserialize(deserializer)?;
+    Self::from_base_coeffs(&coeffs)
+      .ok_or_else(|| De::Error::invalid_length(coeffs.len(), &"the degree of the extension"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+
+  fn test_extension_field_with<F: BinomialNonResidue<D>, const D: usize>() {
+    let one = BinomialExtension::<F, D>::from(F::ONE);
+    let (a, b, c) = (
+      BinomialExtension::<F, D>::random(OsRng),
+      BinomialExtension::<F, D>::random(OsRng),
+      BinomialExtension::<F, D>::random(OsRng),
+    );
+    assert_eq!(a * (b + c), a * b + a * c);
+    assert_eq!((a * b) * c, a * (b * c));
+    assert_eq!(a * one, a);
+
+    // X^D = W
+    let x = BinomialExtension::<F, D>::from_base_coeffs(
+      &(0..D).map(|i| if i == 1 { F::ONE } else { F::ZERO }).collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let x_d = (0..D).map(|_| x).product::<BinomialExtension<F, D>>();
+    assert_eq!(x_d, BinomialExtension::from(F::non_residue()));
+
+    // every non-zero element is invertible
+    assert_eq!(a * a.inverse().unwrap(), one);
+    assert_eq!(x * x.inverse().unwrap(), one);
+    assert!(BinomialExtension::<F, D>::default().inverse().is_none());
+  }
+
+  #[test]
+  fn test_extension_field() {
+    test_extension_field_with::<Goldilocks, 2>();
+    test_extension_field_with::<Goldilocks, 3>();
+    test_extension_field_with::<BabyBear, 3>();
+    test_extension_field_with::<BabyBear, 4>();
+  }
+}
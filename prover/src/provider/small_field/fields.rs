@@ -0,0 +1,44 @@
+//! The Goldilocks and BabyBear prime fields.
+//!
+//! Both have large power-of-two subgroups, for the Reed-Solomon encodings of
+//! the hash-based commitments, and elements of at most 64 bits, whose
+//! arithmetic is much cheaper than that of 254-bit fields.
+use ff::PrimeField;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::scalar_bytes::ScalarBytesLe;
+
+/// The Goldilocks field, of order `2^64 - 2^32 + 1`
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "18446744069414584321"]
+#[PrimeFieldGenerator = "7"]
+#[PrimeFieldReprEndianness = "little"]
+pub struct Goldilocks([u64; 2]);
+
+/// The BabyBear field, of order `15 * 2^27 + 1`
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "2013265921"]
+#[PrimeFieldGenerator = "31"]
+#[PrimeFieldReprEndianness = "little"]
+pub struct BabyBear([u64; 1]);
+
+/// Serializes a field element as its canonical representation, like the
+/// fields of `halo2curves`
+macro_rules! impl_serde {
+  ($field:ident) => {
+    impl Serialize for $field {
+      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ScalarBytesLe::new(self).serialize(serializer)
+      }
+    }
+
+    impl<'de> Deserialize<'de> for $field {
+      fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ScalarBytesLe::<Self>::deserialize(deserializer)?.to_scalar().map_err(D::Error::custom)
+      }
+    }
+  };
+}
+
+impl_serde!(Goldilocks);
+impl_serde!(BabyBear);
@@ -0,0 +1,369 @@
+//! A hash-based polynomial commitment scheme for multilinear polynomials over
+//! small fields, after Ligero and Brakedown.
+//!
+//! The `2^n` evaluations of a polynomial are laid out as a matrix of
+//! `2^(n/2)` rows, which are encoded with a Reed-Solomon code of rate `1/2`,
+//! and the commitment is the root of a Merkle tree over the columns of the
+//! encoded matrix. To prove an evaluation at a point `(r_row, r_col)`, the
+//! prover sends two combinations of the rows, by random coefficients, for
+//! proximity, and by `eq(r_row, .)`, for the evaluation, which the verifier
+//! checks against a number of opened columns, set by the security level of
+//! the engine. The point and the coefficients live in the extension field of
+//! the engine.
+use core::marker::PhantomData;
+
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use super::{challenger::Challenger, extension::ExtensionField, SmallFieldEngine};
+use crate::{
+  errors::NovaError,
+  parallel::prelude::*,
+  provider::merkle::{
+    build_tree, verify_path, Blake3Hasher, MerkleCommitment, MerkleHasher, MerkleOpening,
+    MerkleTree,
+  },
+};
+
+/// The domain separation label of the Merkle hasher
+const MERKLE_LABEL: &[u8] = b"ligero";
+
+/// The logarithm of the inverse of the rate of the code
+const LOG_BLOWUP: usize = 1;
+
+/// A commitment to a multilinear polynomial
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LigeroCommitment {
+  root:     MerkleCommitment<[u8; 32]>,
+  num_vars: usize,
+}
+
+impl LigeroCommitment {
+  /// The number of variables of the committed polynomial
+  pub fn num_vars(&self) -> usize { self.num_vars }
+}
+
+/// What the prover keeps of a commitment to open it: the encoded rows and the
+/// tree over its columns
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LigeroProverData<SE: SmallFieldEngine> {
+  encoded_rows: Vec<Vec<SE::Base>>,
+  tree:         MerkleTree<[u8; 32]>,
+}
+
+/// A proof of an evaluation of a committed polynomial
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LigeroProof<SE: SmallFieldEngine> {
+  /// the combination of the rows by random coefficients
+  u_proximity: Vec<SE::Ext>,
+  /// the combination of the rows by `eq(r_row, .)`
+  u_eval:      Vec<SE::Ext>,
+  /// the opened columns of the encoded matrix
+  columns:     Vec<Vec<SE::Base>>,
+  openings:    Vec<MerkleOpening<[u8; 32]>>,
+}
+
+/// Provides a hash-based polynomial commitment scheme over the field of `SE`
+#[derive(Clone, Debug)]
+pub struct LigeroPCS<SE: SmallFieldEngine> {
+  _p: PhantomData<SE>,
+}
+
+/// The number of rows and of columns of the matrix of a polynomial in
+/// `num_vars` variables
+fn dimensions(num_vars: usize) -> (usize, usize) {
+  (1 << (num_vars / 2), 1 << (num_vars - num_vars / 2))
+}
+
+/// The evaluations of `eq(r, .)` over the hypercube, with the first variable
+/// as the most significant bit of the index, as `EqPolynomial`
+fn eq_evals<F: PrimeField, EF: ExtensionField<F>>(r: &[EF]) -> Vec<EF> {
+  let mut evals = vec![EF::from(F::ONE)];
+  for r_i in r {
+    evals = evals
+      .iter()
+      .flat_map(|e| {
+        let hi = *e * *r_i;
+        [*e - hi, hi]
+      })
+      .collect();
+  }
+  evals
+}
+
+/// Encodes `coeffs` with a Reed-Solomon code of rate `2^-LOG_BLOWUP`, by
+/// evaluating the polynomial of coefficients `coeffs` over the subgroup of
+/// order `coeffs.len() << LOG_BLOWUP`, with a radix-2 NTT
+fn encode<F: PrimeField, EF: ExtensionField<F>>(coeffs: &[EF]) -> Vec<EF> {
+  let n = coeffs.len() << LOG_BLOWUP;
+  let log_n = n.trailing_zeros();
+  let mut a = coeffs.to_vec();
+  a.resize(n, EF::default());
+  if n == 1 {
+    return a;
+  }
+
+  for i in 0..n {
+    let j = i.reverse_bits() >> (usize::BITS - log_n);
+    if i < j {
+      a.swap(i, j);
+    }
+  }
+
+  let mut omega = F::ROOT_OF_UNITY;
+  for _ in log_n..F::S {
+    omega = omega.square();
+  }
+  let mut len = 2;
+  while len <= n {
+    let omega_len = omega.pow_vartime([(n / len) as u64]);
+    for chunk in a.chunks_mut(len) {
+      let (lo, hi) = chunk.split_at_mut(len / 2);
+      let mut w = F::ONE;
+      for (u, v) in lo.iter_mut().zip(hi.iter_mut()) {
+        let t = *v * w;
+        *v = *u - t;
+        *u += t;
+        w *= omega_len;
+      }
+    }
+    len <<= 1;
+  }
+  a
+}
+
+/// Combines `rows` by `coeffs`
+fn combine<F: PrimeField, EF: ExtensionField<F>>(rows: &[&[F]], coeffs: &[EF]) -> Vec<EF> {
+  let num_cols = rows.first().map_or(0, |row| row.len());
+  (0..num_cols)
+    .into_par_iter()
+    .map(|j| rows.iter().zip(coeffs).map(|(row, c)| *c * row[j]).sum())
+    .collect()
+}
+
+/// Combines the entries of `column` by `coeffs`
+fn combine_column<F: PrimeField, EF: ExtensionField<F>>(column: &[F], coeffs: &[EF]) -> EF {
+  column.iter().zip(coeffs).map(|(v, c)| *c * *v).sum()
+}
+
+impl<SE: SmallFieldEngine> LigeroPCS<SE> {
+  fn hasher() -> Blake3Hasher { <Blake3Hasher as MerkleHasher<SE::Base>>::new(MERKLE_LABEL) }
+
+  /// The number of columns opened for the security level of `SE`: each
+  /// query catches a codeword which is `1/4`-far from the code with
+  /// probability `1/4`, i.e. brings `log2(4/3) > 0.41` bits
+  fn num_queries(code_len: usize) -> usize {
+    (SE::SECURITY_LEVEL.bits() * 100).div_ceil(41).min(code_len)
+  }
+
+  /// Commits to the multilinear polynomial of evaluations `poly`, whose
+  /// length is a power of two
+  pub fn commit(poly: &[SE::Base]) -> Result<(LigeroCommitment, LigeroProverData<SE>), NovaError> {
+    if !poly.len().is_power_of_two() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let num_vars = poly.len().trailing_zeros() as usize;
+    let (_, num_cols) = dimensions(num_vars);
+    if num_cols.trailing_zeros() as usize + LOG_BLOWUP > SE::Base::S as usize {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    let encoded_rows =
+      poly.par_chunks(num_cols).map(encode::<SE::Base, SE::Base>).collect::<Vec<_>>();
+    let code_len = num_cols << LOG_BLOWUP;
+    let hasher = Self::hasher();
+    let leaf_hashes = (0..code_len)
+      .into_par_iter()
+      .map(|j| hasher.hash_leaves(&encoded_rows.iter().map(|row| row[j]).collect::<Vec<_>>()))
+      .collect();
+    let (root, tree) = build_tree(leaf_hashes, code_len, |left, right| {
+      <Blake3Hasher as MerkleHasher<SE::Base>>::hash_node(&hasher, left, right)
+    });
+
+    Ok((LigeroCommitment { root, num_vars }, LigeroProverData { encoded_rows, tree }))
+  }
+
+  /// The challenges of an evaluation proof: the coefficients of the
+  /// proximity combination, after absorbing the commitment, the point and
+  /// the evaluation
+  fn proximity_coeffs(
+    challenger: &mut Challenger,
+    comm: &LigeroCommitment,
+    point: &[SE::Ext],
+    eval: &SE::Ext,
+  ) -> Result<Vec<SE::Ext>, NovaError> {
+    let (num_rows, _) = dimensions(comm.num_vars);
+    challenger.absorb_bytes(b"comm", comm.root.root());
+    challenger.absorb_bytes(b"num_vars", &(comm.num_vars as u64).to_le_bytes());
+    challenger.absorb::<SE::Base, SE::Ext>(b"point", point);
+    challenger.absorb::<SE::Base, SE::Ext>(b"eval", &[*eval]);
+    (0..num_rows).map(|_| challenger.squeeze::<SE::Base, SE::Ext>(b"gamma")).collect()
+  }
+
+  /// The columns to open, after absorbing the combinations of the rows
+  fn query_indices(
+    challenger: &mut Challenger,
+    u_proximity: &[SE::Ext],
+    u_eval: &[SE::Ext],
+    code_len: usize,
+  ) -> Result<Vec<usize>, NovaError> {
+    challenger.absorb::<SE::Base, SE::Ext>(b"u_proximity", u_proximity);
+    challenger.absorb::<SE::Base, SE::Ext>(b"u_eval", u_eval);
+    let num_queries = Self::num_queries(code_len);
+    if num_queries == code_len {
+      return Ok((0..code_len).collect());
+    }
+    (0..num_queries).map(|_| challenger.squeeze_index(b"query", code_len)).collect()
+  }
+
+  /// Evaluates `poly` at `point`, and proves the evaluation
+  pub fn prove(
+    data: &LigeroProverData<SE>,
+    challenger: &mut Challenger,
+    comm: &LigeroCommitment,
+    poly: &[SE::Base],
+    point: &[SE::Ext],
+  ) -> Result<(SE::Ext, LigeroProof<SE>), NovaError> {
+    if poly.len() != 1 << comm.num_vars || point.len() != comm.num_vars {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (num_rows, num_cols) = dimensions(comm.num_vars);
+    let (r_row, r_col) = point.split_at(comm.num_vars / 2);
+    let rows = poly.chunks(num_cols).collect::<Vec<_>>();
+
+    let u_eval = combine(&rows, &eq_evals::<SE::Base, SE::Ext>(r_row));
+    let eval = u_eval.iter().zip(eq_evals::<SE::Base, SE::Ext>(r_col)).map(|(u, e)| *u * e).sum();
+
+    let gamma = Self::proximity_coeffs(challenger, comm, point, &eval)?;
+    debug_assert_eq!(gamma.len(), num_rows);
+    let u_proximity = combine(&rows, &gamma);
+
+    let code_len = num_cols << LOG_BLOWUP;
+    let indices = Self::query_indices(challenger, &u_proximity, &u_eval, code_len)?;
+    let columns =
+      indices.iter().map(|j| data.encoded_rows.iter().map(|row| row[*j]).collect()).collect();
+    let openings = indices.iter().map(|j| data.tree.open(*j)).collect::<Result<_, _>>()?;
+
+    Ok((eval, LigeroProof { u_proximity, u_eval, columns, openings }))
+  }
+
+  /// Verifies that the polynomial committed to by `comm` evaluates to `eval`
+  /// at `point`
+  pub fn verify(
+    challenger: &mut Challenger,
+    comm: &LigeroCommitment,
+    point: &[SE::Ext],
+    eval: &SE::Ext,
+    proof: &LigeroProof<SE>,
+  ) -> Result<(), NovaError> {
+    if point.len() != comm.num_vars {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (num_rows, num_cols) = dimensions(comm.num_vars);
+    let code_len = num_cols << LOG_BLOWUP;
+    if proof.u_proximity.len() != num_cols
+      || proof.u_eval.len() != num_cols
+      || comm.root.len() != code_len
+    {
+      return Err(NovaError::ProofVerifyError);
+    }
+    let (r_row, r_col) = point.split_at(comm.num_vars / 2);
+
+    // the combination by `eq(r_row, .)` evaluates to `eval` at `r_col`
+    let claimed: SE::Ext =
+      proof.u_eval.iter().zip(eq_evals::<SE::Base, SE::Ext>(r_col)).map(|(u, e)| *u * e).sum();
+    if claimed != *eval {
+      return Err(NovaError::ProofVerifyError);
+    }
+
+    let gamma = Self::proximity_coeffs(challenger, comm, point, eval)?;
+    let indices = Self::query_indices(challenger, &proof.u_proximity, &proof.u_eval, code_len)?;
+    if proof.columns.len() != indices.len() || proof.openings.len() != indices.len() {
+      return Err(NovaError::ProofVerifyError);
+    }
+
+    // the opened columns are consistent with the encodings of both
+    // combinations
+    let encoded_proximity = encode::<SE::Base, SE::Ext>(&proof.u_proximity);
+    let encoded_eval = encode::<SE::Base, SE::Ext>(&proof.u_eval);
+    let eq_row = eq_evals::<SE::Base, SE::Ext>(r_row);
+    let hasher = Self::hasher();
+    for ((j, column), opening) in indices.iter().zip(&proof.columns).zip(&proof.openings) {
+      if column.len() != num_rows
+        || opening.index() != *j
+        || combine_column(column, &gamma) != encoded_proximity[*j]
+        || combine_column(column, &eq_row) != encoded_eval[*j]
+      {
+        return Err(NovaError::ProofVerifyError);
+      }
+      verify_path(&comm.root, hasher.hash_leaves(column), opening, |left, right| {
+        <Blake3Hasher as MerkleHasher<SE::Base>>::hash_node(&hasher, left, right)
+      })?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::small_field::{BabyBearEngine, GoldilocksEngine};
+
+  fn test_ligero_with<SE: SmallFieldEngine>(num_vars: usize) {
+    let poly = (0..1 << num_vars).map(|_| SE::Base::random(OsRng)).collect::<Vec<_>>();
+    let point = (0..num_vars)
+      .map(|_| {
+        let coeffs =
+          (0..<SE::Ext as ExtensionField<SE::Base>>::DEGREE).map(|_| SE::Base::random(OsRng));
+        SE::Ext::from_base_coeffs(&coeffs.collect::<Vec<_>>()).unwrap()
+      })
+      .collect::<Vec<_>>();
+
+    let (comm, data) = LigeroPCS::<SE>::commit(&poly).unwrap();
+    let (eval, proof) =
+      LigeroPCS::<SE>::prove(&data, &mut Challenger::new(b"test"), &comm, &poly, &point).unwrap();
+
+    // the evaluation is that of the multilinear extension
+    let expected: SE::Ext =
+      poly.iter().zip(eq_evals::<SE::Base, SE::Ext>(&point)).map(|(v, e)| e * *v).sum();
+    assert_eq!(eval, expected);
+
+    LigeroPCS::<SE>::verify(&mut Challenger::new(b"test"), &comm, &point, &eval, &proof).unwrap();
+
+    // a wrong evaluation is rejected
+    let wrong_eval = eval + SE::Ext::from(SE::Base::ONE);
+    assert!(LigeroPCS::<SE>::verify(
+      &mut Challenger::new(b"test"),
+      &comm,
+      &point,
+      &wrong_eval,
+      &proof
+    )
+    .is_err());
+
+    // so is a proof whose proximity combination does not match the columns
+    let mut bad_proof = proof.clone();
+    bad_proof.u_proximity[0] += SE::Ext::from(SE::Base::ONE);
+    assert!(LigeroPCS::<SE>::verify(
+      &mut Challenger::new(b"test"),
+      &comm,
+      &point,
+      &eval,
+      &bad_proof
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_ligero() {
+    for num_vars in [0, 1, 5, 10] {
+      test_ligero_with::<GoldilocksEngine>(num_vars);
+      test_ligero_with::<BabyBearEngine>(num_vars);
+    }
+  }
+}
@@ -0,0 +1,60 @@
+//! Engines over small prime fields, with challenges in extension fields.
+//!
+//! A [`SmallFieldEngine`] pairs a small prime field, Goldilocks or BabyBear,
+//! in which the polynomials of a prover live, with an extension of it, in
+//! which the challenges of the [`Challenger`] are sampled so that the
+//! protocols reach the security level of the engine. It has no curve, so it
+//! cannot implement `Engine`: its polynomials are committed to with the
+//! hash-based [`ligero::LigeroPCS`], which is much cheaper than an MSM for the
+//! hash-heavy circuits of client-side proving.
+use core::fmt::Debug;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::constants::SecurityLevel;
+
+mod challenger;
+pub mod extension;
+pub mod fields;
+pub mod ligero;
+
+pub use challenger::Challenger;
+use extension::{BabyBearExt4, ExtensionField, GoldilocksExt2};
+use fields::{BabyBear, Goldilocks};
+
+/// A collection of the types of a prover over a small prime field
+pub trait SmallFieldEngine: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
+  /// The prime field the committed polynomials live in
+  type Base: ff::PrimeField + Serialize + DeserializeOwned;
+
+  /// The extension of `Self::Base` the challenges live in
+  type Ext: ExtensionField<Self::Base> + Serialize + DeserializeOwned;
+
+  /// The security level the challenges and the queries of the commitments
+  /// reach; the extension must be large enough for it
+  const SECURITY_LEVEL: SecurityLevel;
+}
+
+/// An engine over Goldilocks, with challenges in its quadratic extension
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GoldilocksEngine;
+
+/// An engine over BabyBear, with challenges in its quartic extension
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BabyBearEngine;
+
+impl SmallFieldEngine for GoldilocksEngine {
+  type Base = Goldilocks;
+  type Ext = GoldilocksExt2;
+
+  // a 128-bit extension leaves a margin below 128 bits for the soundness
+  // errors of multi-round protocols
+  const SECURITY_LEVEL: SecurityLevel = SecurityLevel::Bits100;
+}
+
+impl SmallFieldEngine for BabyBearEngine {
+  type Base = BabyBear;
+  type Ext = BabyBearExt4;
+
+  const SECURITY_LEVEL: SecurityLevel = SecurityLevel::Bits100;
+}
@@ -0,0 +1,310 @@
+//! A Poseidon duplex sponge for applications, hashing the same inside and
+//! outside circuits.
+//!
+//! A [`PoseidonDuplex`] is started under a domain tag, naming the use of the
+//! hash, then absorbs field elements or byte strings and squeezes field
+//! elements, in any interleaving. Each squeeze hashes the state, the tag at
+//! first or the last squeezed element after, with the elements absorbed
+//! since, by the Poseidon sponge of the crate, whose IO pattern commits to
+//! their number: the outputs of different tags, or of different sequences of
+//! absorbs, differ. Byte strings are packed as `gadgets::pack_bytes` packs
+//! them, then their length, so that strings of different lengths differ.
+//!
+//! A [`PoseidonDuplexCircuit`] makes the same calls inside a circuit, to the
+//! same outputs, so that an application hashes its data once natively, e.g.
+//! to commit to it, and proves the hash in its step circuit without
+//! reimplementing the padding rules.
+//!
+//! [`hash`] and [`hash_circuit`] hash a sequence of elements at once, with the
+//! Poseidon width of the fewest constraints for its length and field (see
+//! [`hash_arity`]), rather than the width of 25 of the sponge of the crate:
+//! a hash of a few elements then costs a fraction of the constraints, and of
+//! many elements a few permutations less.
+#[cfg(feature = "prove")]
+use bellpepper_core::{boolean::Boolean, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use super::poseidon::{adaptive_arity, adaptive_hash, hash_with_arity, PoseidonConstantsCircuit};
+#[cfg(feature = "prove")]
+use super::poseidon::{adaptive_hash_circuit, hash_circuit_with_arity};
+#[cfg(feature = "prove")] use crate::gadgets::alloc_packed;
+
+/// The arities [`hash`] picks from, the numbers of elements a permutation
+/// absorbs
+pub const POSEIDON_ARITIES: [usize; 6] = super::poseidon::ARITIES;
+
+/// The arity [`hash`] picks to hash `len` elements of `F`, of the fewest
+/// S-boxes; its constants are generated on first use and cached, as those of
+/// the sponge of the crate
+pub fn hash_arity<F: PrimeField>(len: usize) -> usize { adaptive_arity::<F>(len) }
+
+/// Hashes `elements` into an element, with the sponge of [`hash_arity`]
+pub fn hash<F: PrimeField>(elements: &[F]) -> F { adaptive_hash(elements) }
+
+/// Hashes `elements` inside a circuit, into the same element as [`hash`]
+#[cfg(feature = "prove")]
+pub fn hash_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  elements: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  adaptive_hash_circuit(cs, elements)
+}
+
+/// Hashes `elements` with the sponge of `arity`, `None` unless it is one of
+/// [`POSEIDON_ARITIES`], e.g. to pin the arity of a hash across versions
+pub fn hash_with<F: PrimeField>(elements: &[F], arity: usize) -> Option<F> {
+  hash_with_arity(elements, arity)
+}
+
+/// Hashes `elements` inside a circuit, into the same element as
+/// [`hash_with`]; unsatisfiable unless `arity` is one of
+/// [`POSEIDON_ARITIES`]
+#[cfg(feature = "prove")]
+pub fn hash_circuit_with<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  elements: &[AllocatedNum<F>],
+  arity: usize,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  hash_circuit_with_arity(cs, elements, arity)
+}
+
+/// The number of bytes packed into an element, as `gadgets::pack_bytes` does
+const BYTES_PER_ELEMENT: usize = 16;
+
+/// The elements of `bytes`: their packing, then their length
+fn byte_elements<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+  let packed = bytes
+    .chunks(BYTES_PER_ELEMENT)
+    .map(|chunk| F::from_u128(chunk.iter().fold(0u128, |acc, b| (acc << 8) | u128::from(*b))));
+  packed.chain([F::from(bytes.len() as u64)]).collect()
+}
+
+/// The initial state of a duplex of the domain tag `domain`
+fn initial_state<F: PrimeField>(constants: &PoseidonConstantsCircuit<F>, domain: &[u8]) -> F {
+  constants.hash(&byte_elements(domain))
+}
+
+/// A duplex sponge, outside circuits
+#[derive(Clone, Debug)]
+pub struct PoseidonDuplex<F: PrimeField> {
+  constants: PoseidonConstantsCircuit<F>,
+  state:     F,
+  absorbed:  Vec<F>,
+}
+
+impl<F: PrimeField> PoseidonDuplex<F> {
+  /// Starts a duplex under the domain tag `domain`, e.g.
+  /// `b"my-app/commitment/v1"`
+  pub fn new(domain: &[u8]) -> Self {
+    let constants = PoseidonConstantsCircuit::default();
+    let state = initial_state(&constants, domain);
+    Self { constants, state, absorbed: Vec::new() }
+  }
+
+  /// Absorbs `elements`
+  pub fn absorb(&mut self, elements: &[F]) { self.absorbed.extend_from_slice(elements) }
+
+  /// Absorbs the byte string `bytes`
+  pub fn absorb_bytes(&mut self, bytes: &[u8]) { self.absorbed.extend(byte_elements::<F>(bytes)) }
+
+  /// Squeezes an element, the hash of the state and of the elements absorbed
+  /// since the last squeeze
+  pub fn squeeze(&mut self) -> F {
+    let input = [vec![self.state], std::mem::take(&mut self.absorbed)].concat();
+    self.state = self.constants.hash(&input);
+    self.state
+  }
+
+  /// Squeezes `n` elements
+  pub fn squeeze_many(&mut self, n: usize) -> Vec<F> { (0..n).map(|_| self.squeeze()).collect() }
+}
+
+/// A duplex sponge inside a circuit, of the same outputs as a
+/// [`PoseidonDuplex`] making the same calls
+#[cfg(feature = "prove")]
+#[derive(Clone, Debug)]
+pub struct PoseidonDuplexCircuit<F: PrimeField> {
+  constants: PoseidonConstantsCircuit<F>,
+  state:     AllocatedNum<F>,
+  absorbed:  Vec<AllocatedNum<F>>,
+}
+
+/// Allocates the constant `value`
+#[cfg(feature = "prove")]
+fn alloc_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: F,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let num = AllocatedNum::alloc(cs.namespace(|| "alloc"), || Ok(value))?;
+  cs.enforce(
+    || "constant",
+    |lc| lc + num.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + (value, CS::one()),
+  );
+  Ok(num)
+}
+
+#[cfg(feature = "prove")]
+impl<F: PrimeField> PoseidonDuplexCircuit<F> {
+  /// Starts a duplex under the domain tag `domain`, a constant of the
+  /// circuit
+  pub fn new<CS: ConstraintSystem<F>>(cs: CS, domain: &[u8]) -> Result<Self, SynthesisError> {
+    let constants = PoseidonConstantsCircuit::default();
+    let state = alloc_constant(cs, initial_state(&constants, domain))?;
+    Ok(Self { constants, state, absorbed: Vec::new() })
+  }
+
+  /// Absorbs `elements`
+  pub fn absorb(&mut self, elements: &[AllocatedNum<F>]) {
+    self.absorbed.extend_from_slice(elements)
+  }
+
+  /// Absorbs the byte string of the bits `bits`, most significant bit first
+  /// in each byte, as `gadgets::alloc_bytes` allocates them
+  pub fn absorb_bytes<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    bits: &[Boolean],
+  ) -> Result<(), SynthesisError> {
+    if bits.len() % 8 != 0 {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let packed = alloc_packed(cs.namespace(|| "pack"), bits)?;
+    let len = alloc_constant(cs.namespace(|| "len"), F::from((bits.len() / 8) as u64))?;
+    self.absorbed.extend(packed);
+    self.absorbed.push(len);
+    Ok(())
+  }
+
+  /// Squeezes an element, as [`PoseidonDuplex::squeeze`]
+  pub fn squeeze<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    let input = [vec![self.state.clone()], std::mem::take(&mut self.absorbed)].concat();
+    self.state = self.constants.hash_circuit(cs.namespace(|| "hash"), &input)?;
+    Ok(self.state.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type F = halo2curves::bn256::Fr;
+
+  #[test]
+  fn test_duplex_separation() {
+    let squeeze = |domain: &[u8], calls: &dyn Fn(&mut PoseidonDuplex<F>)| {
+      let mut duplex = PoseidonDuplex::<F>::new(domain);
+      calls(&mut duplex);
+      duplex.squeeze()
+    };
+    let base = squeeze(b"app", &|d| d.absorb_bytes(b"ab"));
+    assert_eq!(base, squeeze(b"app", &|d| d.absorb_bytes(b"ab")));
+    assert_ne!(base, squeeze(b"other app", &|d| d.absorb_bytes(b"ab")));
+    // the lengths of byte strings are absorbed
+    assert_ne!(base, squeeze(b"app", &|d| d.absorb_bytes(b"ab\0")));
+    assert_ne!(base, squeeze(b"app", &|d| d.absorb_bytes(b"")));
+    // absorbs are streamed, squeezes are not
+    let ab = squeeze(b"app", &|d| d.absorb(&[F::from(1), F::from(2)]));
+    assert_eq!(
+      ab,
+      squeeze(b"app", &|d| {
+        d.absorb(&[F::from(1)]);
+        d.absorb(&[F::from(2)]);
+      })
+    );
+    assert_ne!(
+      ab,
+      squeeze(b"app", &|d| {
+        d.absorb(&[F::from(1)]);
+        d.squeeze();
+        d.absorb(&[F::from(2)]);
+      })
+    );
+
+    let mut duplex = PoseidonDuplex::<F>::new(b"app");
+    let outputs = duplex.squeeze_many(2);
+    assert_ne!(outputs[0], outputs[1]);
+  }
+
+  #[cfg(feature = "prove")]
+  #[test]
+  fn test_duplex_circuit() {
+    use bellpepper_core::test_cs::TestConstraintSystem;
+
+    use crate::gadgets::alloc_bytes;
+
+    let mut native = PoseidonDuplex::<F>::new(b"app/v1");
+    native.absorb_bytes(b"hello, world");
+    native.absorb(&[F::from(7)]);
+    let first = native.squeeze();
+    let second = native.squeeze();
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let mut duplex = PoseidonDuplexCircuit::new(cs.namespace(|| "duplex"), b"app/v1").unwrap();
+    let bytes = alloc_bytes(cs.namespace(|| "bytes"), Some(&b"hello, world"[..]), 12).unwrap();
+    duplex.absorb_bytes(cs.namespace(|| "absorb bytes"), &bytes).unwrap();
+    let seven = AllocatedNum::alloc(cs.namespace(|| "seven"), || Ok(F::from(7))).unwrap();
+    duplex.absorb(&[seven]);
+    assert_eq!(duplex.squeeze(cs.namespace(|| "first")).unwrap().get_value(), Some(first));
+    assert_eq!(duplex.squeeze(cs.namespace(|| "second")).unwrap().get_value(), Some(second));
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_hash_arity() {
+    // short inputs take narrow widths, long inputs wide ones
+    assert_eq!(hash_arity::<F>(1), 2);
+    assert_eq!(hash_arity::<F>(2), 2);
+    assert!(hash_arity::<F>(100) >= 8);
+    for len in [1, 3, 10, 24, 50] {
+      assert!(POSEIDON_ARITIES.contains(&hash_arity::<F>(len)));
+    }
+
+    let elements = (0..5).map(F::from).collect::<Vec<_>>();
+    assert_eq!(hash(&elements), hash_with(&elements, hash_arity::<F>(5)).unwrap());
+    assert_ne!(hash_with(&elements, 2), hash_with(&elements, 4));
+    assert_eq!(hash_with(&elements, 3), None);
+    // of arity 24, the hash is that of the sponge of the crate
+    assert_eq!(
+      hash_with(&elements, 24).unwrap(),
+      PoseidonConstantsCircuit::<F>::default().hash(&elements)
+    );
+  }
+
+  #[cfg(feature = "prove")]
+  #[test]
+  fn test_hash_circuit() {
+    use bellpepper_core::test_cs::TestConstraintSystem;
+
+    let num_constraints = |len: usize, arity: Option<usize>| {
+      let elements = (0..len as u64).map(F::from).collect::<Vec<_>>();
+      let mut cs = TestConstraintSystem::<F>::new();
+      let allocated = elements
+        .iter()
+        .enumerate()
+        .map(|(i, e)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("e{i}")), || *e))
+        .collect::<Vec<_>>();
+      let out = match arity {
+        Some(arity) => hash_circuit_with(cs.namespace(|| "hash"), &allocated, arity).unwrap(),
+        None => hash_circuit(cs.namespace(|| "hash"), &allocated).unwrap(),
+      };
+      assert!(cs.is_satisfied());
+      let expected = arity.map_or_else(|| hash(&elements), |a| hash_with(&elements, a).unwrap());
+      assert_eq!(out.get_value(), Some(expected));
+      cs.num_constraints()
+    };
+
+    for len in [1, 2, 5, 16, 50, 100] {
+      let adaptive = num_constraints(len, None);
+      assert!(adaptive <= num_constraints(len, Some(24)), "{len} elements");
+    }
+    assert!(2 * num_constraints(2, None) < num_constraints(2, Some(24)));
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    assert!(hash_circuit_with(cs.namespace(|| "hash"), &[], 3).is_err());
+  }
+}
@@ -0,0 +1,675 @@
+//! This module imports KZG structured reference strings from the powers of
+//! tau of public ceremonies.
+//!
+//! The SRS sampled by `KZGCommitmentEngine::setup` is only fit for testing:
+//! its trapdoor derives from the label. The loaders below read the output of a
+//! ceremony into a [`UniversalKZGParam`] instead:
+//! - [`from_ptau`] reads the `.ptau` files of snarkjs (e.g. the Hermez and the Perpetual Powers of
+//!   Tau ceremonies), over BN254,
+//! - [`from_ignition`] reads the transcripts of the Aztec Ignition ceremony, over BN254,
+//! - [`from_ethereum_ceremony`] reads the `transcript.json` of the Ethereum KZG ceremony, over
+//!   BLS12-381, or over any curve whose `GroupEncoding` is the compressed encoding used by the
+//!   transcript.
+//!
+//! Each loader checks its output with [`check_srs`]: every point is in the
+//! prime-order subgroup of its group, and pairings check that the powers in
+//! G1 and in G2 are the powers of a single tau. The random combinations of the
+//! pairings are drawn from the `rng` of the caller, e.g. `OsRng`, a seeded RNG
+//! in tests, or the RNG of an HSM.
+use std::io::{self, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use ff::{Field, PrimeFieldBits};
+use group::{prime::PrimeCurveAffine, Curve, Group, GroupEncoding};
+use halo2curves::{
+  bn256::{Bn256, Fq, Fq2, G1Affine, G2Affine},
+  CurveAffine,
+};
+use pairing::{MillerLoopResult, MultiMillerLoop};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+  parallel::prelude::*,
+  provider::{kzg_commitment::UniversalKZGParam, traits::DlogGroup},
+  scalar_bytes::ScalarBytesLe,
+};
+
+/// Errors returned when importing an SRS
+#[derive(Debug, Error)]
+pub enum SrsError {
+  /// returned when the file cannot be read
+  #[error(transparent)]
+  Io(#[from] io::Error),
+  /// returned when the file is not in the expected format
+  #[error("InvalidFormat: {0}")]
+  InvalidFormat(&'static str),
+  /// returned when the ceremony has fewer powers than requested
+  #[error("NotEnoughPowers: {available} available, {requested} requested")]
+  NotEnoughPowers {
+    /// the number of powers of the ceremony
+    available: usize,
+    /// the number of powers requested
+    requested: usize,
+  },
+  /// returned when a point is not on the curve, or not in the prime-order
+  /// subgroup, or is the identity
+  #[error("InvalidPoint: {group} power {index}")]
+  InvalidPoint {
+    /// the group of the point, `"G1"` or `"G2"`
+    group: &'static str,
+    /// the index of the point among the powers
+    index: usize,
+  },
+  /// returned when the powers are not the powers of a single tau
+  #[error("InconsistentPowers")]
+  InconsistentPowers,
+}
+
+/// Checks that `srs` is made of points of the prime-order subgroups, other
+/// than the identity, and that its powers in G1 and in G2 are the powers of a
+/// single tau.
+///
+/// The consistency of the powers is checked with two pairing equations, on
+/// random linear combinations of the powers, drawn from `rng`, while each
+/// point is checked for subgroup membership by a scalar multiplication, which
+/// dominates the cost.
+pub fn check_srs<E>(
+  srs: &UniversalKZGParam<E>,
+  mut rng: impl RngCore + CryptoRng,
+) -> Result<(), SrsError>
+where
+  E: MultiMillerLoop,
+  E::Fr: PrimeFieldBits,
+  E::G1: DlogGroup<ScalarExt = E::Fr, AffineExt = E::G1Affine>,
+{
+  let (g, h) = (&srs.powers_of_g, &srs.powers_of_h);
+  if g.len() < 2 || h.len() < 2 {
+    return Err(SrsError::NotEnoughPowers { available: g.len().min(h.len()), requested: 2 });
+  }
+
+  if let Some((index, _)) = g.par_iter().enumerate().find_any(|(_, p)| !is_valid_point(**p)) {
+    return Err(SrsError::InvalidPoint { group: "G1", index });
+  }
+  if let Some((index, _)) = h.par_iter().enumerate().find_any(|(_, p)| !is_valid_point(**p)) {
+    return Err(SrsError::InvalidPoint { group: "G2", index });
+  }
+
+  // e(sum_i r_i tau^{i+1} G, H) = e(sum_i r_i tau^i G, tau H)
+  let r = (0..g.len() - 1).map(|_| E::Fr::random(&mut rng)).collect::<Vec<_>>();
+  let g_shifted = E::G1::vartime_multiscalar_mul(&r, &g[1..]).to_affine();
+  let g_combined = E::G1::vartime_multiscalar_mul(&r, &g[..g.len() - 1]).to_affine();
+  if !pairings_match::<E>((&g_shifted, &h[0]), (&g_combined, &h[1])) {
+    return Err(SrsError::InconsistentPowers);
+  }
+
+  // e(tau G, sum_j r_j tau^j H) = e(G, sum_j r_j tau^{j+1} H)
+  let r = (0..h.len() - 1).map(|_| E::Fr::random(&mut rng)).collect::<Vec<_>>();
+  let combine_h = |h: &[E::G2Affine]| {
+    h.par_iter().zip(r.par_iter()).map(|(p, r)| *p * r).reduce(E::G2::identity, |a, b| a + b)
+  };
+  let h_combined = combine_h(&h[..h.len() - 1]).to_affine();
+  let h_shifted = combine_h(&h[1..]).to_affine();
+  if !pairings_match::<E>((&g[1], &h_combined), (&g[0], &h_shifted)) {
+    return Err(SrsError::InconsistentPowers);
+  }
+
+  Ok(())
+}
+
+/// Whether `p` is in the prime-order subgroup of its group, and is not the
+/// identity
+fn is_valid_point<C>(p: C) -> bool
+where
+  C: PrimeCurveAffine,
+  C::Scalar: PrimeFieldBits, {
+  if bool::from(p.is_identity()) {
+    return false;
+  }
+  // multiply by the order of the subgroup, which the scalar multiplication
+  // of the group would reduce to zero
+  let p = p.to_curve();
+  let mut acc = C::Curve::identity();
+  for bit in C::Scalar::char_le_bits().iter().rev() {
+    acc = acc.double();
+    if *bit {
+      acc += p;
+    }
+  }
+  bool::from(acc.is_identity())
+}
+
+/// Whether e(a.0, a.1) = e(b.0, b.1)
+fn pairings_match<E: MultiMillerLoop>(
+  a: (&E::G1Affine, &E::G2Affine),
+  b: (&E::G1Affine, &E::G2Affine),
+) -> bool {
+  let b_0 = (-b.0.to_curve()).to_affine();
+  let pairing_inputs: Vec<(E::G1Affine, E::G2Prepared)> =
+    vec![(*a.0, (*a.1).into()), (b_0, (*b.1).into())];
+
+  #[allow(clippy::map_identity)]
+  let pairing_input_refs = pairing_inputs.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+  E::multi_miller_loop(pairing_input_refs.as_slice()).final_exponentiation().is_identity().into()
+}
+
+/// Reads an element of the base field of BN254 encoded as 32 little-endian
+/// bytes, in Montgomery form if `montgomery` is set
+fn fq_from_le_bytes(bytes: &[u8], montgomery: bool) -> Option<Fq> {
+  let x = ScalarBytesLe::<Fq>::from_slice(bytes).ok()?.to_scalar().ok()?;
+  if !montgomery {
+    return Some(x);
+  }
+  // x = a * R for R = 2^256
+  let r_inv = Option::<Fq>::from(Fq::from(2).pow_vartime([256]).invert())?;
+  Some(x * r_inv)
+}
+
+/// Reads a point of G1 of BN254 as the coordinates x || y, each in the
+/// encoding of `read_fq`
+fn g1_from_coordinates(bytes: &[u8], read_fq: impl Fn(&[u8]) -> Option<Fq>) -> Option<G1Affine> {
+  let (x, y) = (read_fq(&bytes[..32])?, read_fq(&bytes[32..64])?);
+  Option::from(G1Affine::from_xy(x, y))
+}
+
+/// Reads a point of G2 of BN254 as the coordinates x.c0 || x.c1 || y.c0 ||
+/// y.c1, each in the encoding of `read_fq`
+fn g2_from_coordinates(bytes: &[u8], read_fq: impl Fn(&[u8]) -> Option<Fq>) -> Option<G2Affine> {
+  let fq = |i: usize| read_fq(&bytes[32 * i..32 * (i + 1)]);
+  let x = Fq2 { c0: fq(0)?, c1: fq(1)? };
+  let y = Fq2 { c0: fq(2)?, c1: fq(3)? };
+  Option::from(G2Affine::from_xy(x, y))
+}
+
+/// Decodes `points`, each encoded on `size` bytes, with `decode`, reporting
+/// the index of the first point that does not decode
+fn decode_points<C: Send>(
+  bytes: &[u8],
+  size: usize,
+  group: &'static str,
+  decode: impl Fn(&[u8]) -> Option<C> + Sync,
+) -> Result<Vec<C>, SrsError> {
+  let points = bytes.par_chunks(size).map(&decode).collect::<Vec<_>>();
+  points
+    .into_iter()
+    .enumerate()
+    .map(|(index, p)| p.ok_or(SrsError::InvalidPoint { group, index }))
+    .collect()
+}
+
+/// The type of the header section of a `.ptau` file
+const PTAU_HEADER: u32 = 1;
+/// The type of the section of the powers of tau in G1 of a `.ptau` file
+const PTAU_TAU_G1: u32 = 2;
+/// The type of the section of the powers of tau in G2 of a `.ptau` file
+const PTAU_TAU_G2: u32 = 3;
+
+/// Reads the first `num_g1` powers of tau in G1, and the first two in G2, of
+/// a snarkjs `.ptau` file over BN254, and checks them with [`check_srs`] and `rng`.
+///
+/// A `.ptau` file starts with the magic `"ptau"`, a version and a number of
+/// sections, each made of a type, a size and its data, all little-endian.
+/// The header section holds the size of the field elements, the modulus and
+/// the power of the ceremony, and the sections of the powers hold points as
+/// their coordinates x || y, in little-endian Montgomery form.
+pub fn from_ptau<R: Read + Seek>(
+  mut reader: R,
+  num_g1: usize,
+  rng: impl RngCore + CryptoRng,
+) -> Result<UniversalKZGParam<Bn256>, SrsError> {
+  let mut magic = [0u8; 4];
+  reader.read_exact(&mut magic)?;
+  if &magic != b"ptau" {
+    return Err(SrsError::InvalidFormat("not a ptau file"));
+  }
+  let _version = reader.read_u32::<LittleEndian>()?;
+  let num_sections = reader.read_u32::<LittleEndian>()?;
+
+  // the offsets of the sections we read
+  let (mut header, mut tau_g1, mut tau_g2) = (None, None, None);
+  for _ in 0..num_sections {
+    let section_type = reader.read_u32::<LittleEndian>()?;
+    let size = reader.read_u64::<LittleEndian>()?;
+    let offset = reader.stream_position()?;
+    match section_type {
+      PTAU_HEADER => header = Some(offset),
+      PTAU_TAU_G1 => tau_g1 = Some((offset, size)),
+      PTAU_TAU_G2 => tau_g2 = Some((offset, size)),
+      _ => {},
+    }
+    reader.seek(SeekFrom::Current(
+      i64::try_from(size).map_err(|_| SrsError::InvalidFormat("section too large"))?,
+    ))?;
+  }
+  let header = header.ok_or(SrsError::InvalidFormat("missing header section"))?;
+  let (tau_g1, tau_g1_size) = tau_g1.ok_or(SrsError::InvalidFormat("missing G1 section"))?;
+  let (tau_g2, tau_g2_size) = tau_g2.ok_or(SrsError::InvalidFormat("missing G2 section"))?;
+
+  reader.seek(SeekFrom::Start(header))?;
+  if reader.read_u32::<LittleEndian>()? != 32 {
+    return Err(SrsError::InvalidFormat("not over BN254"));
+  }
+  // the modulus is odd, so q - 1 only differs from it in its lowest byte
+  let mut q_minus_one = [0u8; 32];
+  reader.read_exact(&mut q_minus_one)?;
+  q_minus_one[0] = q_minus_one[0].wrapping_sub(1);
+  if fq_from_le_bytes(&q_minus_one, false) != Some(-Fq::ONE) {
+    return Err(SrsError::InvalidFormat("not over BN254"));
+  }
+  let power = reader.read_u32::<LittleEndian>()?;
+  if power >= 32 {
+    return Err(SrsError::InvalidFormat("power too large"));
+  }
+  let available = ((1usize << power) * 2 - 1).min(tau_g1_size as usize / 64);
+  if num_g1 > available {
+    return Err(SrsError::NotEnoughPowers { available, requested: num_g1 });
+  }
+  if tau_g2_size < 2 * 128 {
+    return Err(SrsError::NotEnoughPowers { available: tau_g2_size as usize / 128, requested: 2 });
+  }
+
+  let read_fq = |bytes: &[u8]| fq_from_le_bytes(bytes, true);
+  let mut bytes = vec![0u8; num_g1 * 64];
+  reader.seek(SeekFrom::Start(tau_g1))?;
+  reader.read_exact(&mut bytes)?;
+  let powers_of_g = decode_points(&bytes, 64, "G1", |b| g1_from_coordinates(b, read_fq))?;
+
+  let mut bytes = vec![0u8; 2 * 128];
+  reader.seek(SeekFrom::Start(tau_g2))?;
+  reader.read_exact(&mut bytes)?;
+  let powers_of_h = decode_points(&bytes, 128, "G2", |b| g2_from_coordinates(b, read_fq))?;
+
+  let srs = UniversalKZGParam { powers_of_g, powers_of_h };
+  check_srs(&srs, rng)?;
+  Ok(srs)
+}
+
+/// Reads an element of the base field of BN254 as the Aztec Ignition
+/// transcripts encode it: four 64-bit limbs, least significant first, each
+/// big-endian
+fn fq_from_ignition_bytes(bytes: &[u8]) -> Option<Fq> {
+  let mut le_bytes = [0u8; 32];
+  for (limb, le_limb) in bytes.chunks(8).zip(le_bytes.chunks_mut(8)) {
+    le_limb.copy_from_slice(limb);
+    le_limb.reverse();
+  }
+  fq_from_le_bytes(&le_bytes, false)
+}
+
+/// Reads the first `num_g1` powers of tau in G1, and the first two in G2, of
+/// the Aztec Ignition ceremony over BN254 from its transcripts, given in
+/// order, and checks them with [`check_srs`] and `rng`.
+///
+/// The transcripts hold the powers of tau from tau^1 on, so the generators of
+/// G1 and G2 are prepended. Each transcript starts with a manifest of seven
+/// big-endian 32-bit integers (its number, the number of transcripts, the
+/// total numbers of points in G1 and in G2, its numbers of points in G1 and in
+/// G2, and the index of its first point), followed by its points in G1 and
+/// its points in G2. The checksum that ends each transcript is not checked:
+/// the pairings of [`check_srs`] check the points.
+pub fn from_ignition<R: Read>(
+  transcripts: impl IntoIterator<Item = R>,
+  num_g1: usize,
+  rng: impl RngCore + CryptoRng,
+) -> Result<UniversalKZGParam<Bn256>, SrsError> {
+  let mut powers_of_g = vec![G1Affine::generator()];
+  let mut powers_of_h = vec![G2Affine::generator()];
+
+  for (i, mut reader) in transcripts.into_iter().enumerate() {
+    if powers_of_g.len() >= num_g1 {
+      break;
+    }
+    let mut manifest = [0u32; 7];
+    reader.read_u32_into::<BigEndian>(&mut manifest)?;
+    let [transcript_number, _, _, _, num_g1_points, num_g2_points, start_from] = manifest;
+    if transcript_number as usize != i || start_from as usize != powers_of_g.len() - 1 {
+      return Err(SrsError::InvalidFormat("transcripts out of order"));
+    }
+
+    let num_points = (num_g1_points as usize).min(num_g1 - powers_of_g.len());
+    let mut bytes = vec![0u8; num_points * 64];
+    reader.read_exact(&mut bytes)?;
+    powers_of_g
+      .extend(decode_points(&bytes, 64, "G1", |b| g1_from_coordinates(b, fq_from_ignition_bytes))?);
+
+    if i == 0 {
+      if num_g2_points == 0 {
+        return Err(SrsError::NotEnoughPowers { available: 1, requested: 2 });
+      }
+      io::copy(
+        &mut reader.by_ref().take((num_g1_points as usize - num_points) as u64 * 64),
+        &mut io::sink(),
+      )?;
+      let mut bytes = [0u8; 128];
+      reader.read_exact(&mut bytes)?;
+      powers_of_h.extend(decode_points(&bytes, 128, "G2", |b| {
+        g2_from_coordinates(b, fq_from_ignition_bytes)
+      })?);
+    }
+  }
+
+  if powers_of_g.len() < num_g1 {
+    return Err(SrsError::NotEnoughPowers { available: powers_of_g.len(), requested: num_g1 });
+  }
+  if powers_of_h.len() < 2 {
+    return Err(SrsError::NotEnoughPowers { available: powers_of_h.len(), requested: 2 });
+  }
+
+  let srs = UniversalKZGParam { powers_of_g, powers_of_h };
+  check_srs(&srs, rng)?;
+  Ok(srs)
+}
+
+/// The `transcript.json` of the Ethereum KZG ceremony, e.g. as read by
+/// `serde_json::from_reader`. Only the powers are read.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EthereumCeremony {
+  /// the transcripts of the ceremony, one per size of SRS
+  pub transcripts: Vec<EthereumTranscript>,
+}
+
+/// A transcript of the Ethereum KZG ceremony
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthereumTranscript {
+  /// the number of powers in G1
+  pub num_g1_powers: usize,
+  /// the number of powers in G2
+  pub num_g2_powers: usize,
+  /// the powers of tau
+  pub powers_of_tau: EthereumPowersOfTau,
+}
+
+/// The powers of tau of a transcript of the Ethereum KZG ceremony, from tau^0,
+/// as hexadecimal compressed points
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EthereumPowersOfTau {
+  /// the powers of tau in G1
+  pub g1_powers: Vec<String>,
+  /// the powers of tau in G2
+  pub g2_powers: Vec<String>,
+}
+
+/// Decodes `0x`-prefixed hexadecimal compressed points, reporting the index of
+/// the first point that does not decode
+fn decode_hex_points<C: GroupEncoding + Send>(
+  powers: &[String],
+  group: &'static str,
+) -> Result<Vec<C>, SrsError> {
+  let points = powers.par_iter().map(|hex| point_from_hex(hex)).collect::<Vec<_>>();
+  points
+    .into_iter()
+    .enumerate()
+    .map(|(index, p)| p.ok_or(SrsError::InvalidPoint { group, index }))
+    .collect()
+}
+
+/// Decodes a `0x`-prefixed hexadecimal compressed point
+fn point_from_hex<C: GroupEncoding>(hex: &str) -> Option<C> {
+  let hex = hex.strip_prefix("0x")?.as_bytes();
+  let mut repr = C::Repr::default();
+  if hex.len() != 2 * repr.as_ref().len() {
+    return None;
+  }
+  for (byte, digits) in repr.as_mut().iter_mut().zip(hex.chunks(2)) {
+    let digits = std::str::from_utf8(digits).ok()?;
+    *byte = u8::from_str_radix(digits, 16).ok()?;
+  }
+  Option::from(C::from_bytes(&repr))
+}
+
+/// Reads the first `num_g1` powers of tau in G1, and all the powers in G2, of
+/// the smallest transcript of the Ethereum KZG ceremony that has enough of
+/// them, and checks them with [`check_srs`] and `rng`.
+pub fn from_ethereum_ceremony<E>(
+  ceremony: &EthereumCeremony,
+  num_g1: usize,
+  rng: impl RngCore + CryptoRng,
+) -> Result<UniversalKZGParam<E>, SrsError>
+where
+  E: MultiMillerLoop,
+  E::Fr: PrimeFieldBits,
+  E::G1: DlogGroup<ScalarExt = E::Fr, AffineExt = E::G1Affine>,
+  E::G1Affine: GroupEncoding,
+  E::G2Affine: GroupEncoding,
+{
+  let transcript = ceremony
+    .transcripts
+    .iter()
+    .filter(|t| t.powers_of_tau.g1_powers.len() >= num_g1)
+    .min_by_key(|t| t.powers_of_tau.g1_powers.len())
+    .ok_or_else(|| SrsError::NotEnoughPowers {
+      available: ceremony
+        .transcripts
+        .iter()
+        .map(|t| t.powers_of_tau.g1_powers.len())
+        .max()
+        .unwrap_or(0),
+      requested: num_g1,
+    })?;
+  if transcript.powers_of_tau.g1_powers.len() != transcript.num_g1_powers
+    || transcript.powers_of_tau.g2_powers.len() != transcript.num_g2_powers
+  {
+    return Err(SrsError::InvalidFormat("inconsistent number of powers"));
+  }
+
+  let powers_of_g = decode_hex_points(&transcript.powers_of_tau.g1_powers[..num_g1], "G1")?;
+  let powers_of_h = decode_hex_points(&transcript.powers_of_tau.g2_powers, "G2")?;
+
+  let srs = UniversalKZGParam { powers_of_g, powers_of_h };
+  check_srs(&srs, rng)?;
+  Ok(srs)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use byteorder::WriteBytesExt;
+  use halo2curves::bn256::Fr;
+  use rand::rngs::StdRng;
+  use rand_core::{OsRng, SeedableRng};
+
+  use super::*;
+
+  /// The powers tau^0, ..., tau^{n-1} of the generators of BN254
+  fn srs_from_tau(tau: Fr, n: usize) -> UniversalKZGParam<Bn256> {
+    let powers = (0..n).scan(Fr::ONE, |acc, _| {
+      let power = *acc;
+      *acc *= tau;
+      Some(power)
+    });
+    let (powers_of_g, powers_of_h) = powers
+      .map(|p| ((G1Affine::generator() * p).to_affine(), (G2Affine::generator() * p).to_affine()))
+      .unzip();
+    UniversalKZGParam { powers_of_g, powers_of_h }
+  }
+
+  fn fq_to_le_bytes(x: &Fq, montgomery: bool) -> Vec<u8> {
+    let r = Fq::from(2).pow_vartime([256]);
+    let x = if montgomery { *x * r } else { *x };
+    ScalarBytesLe::new(&x).as_bytes().to_vec()
+  }
+
+  fn g1_to_bytes(p: &G1Affine, fq_to_bytes: impl Fn(&Fq) -> Vec<u8>) -> Vec<u8> {
+    let coordinates = p.coordinates().unwrap();
+    [fq_to_bytes(coordinates.x()), fq_to_bytes(coordinates.y())].concat()
+  }
+
+  fn g2_to_bytes(p: &G2Affine, fq_to_bytes: impl Fn(&Fq) -> Vec<u8>) -> Vec<u8> {
+    let coordinates = p.coordinates().unwrap();
+    let (x, y) = (coordinates.x(), coordinates.y());
+    [fq_to_bytes(&x.c0), fq_to_bytes(&x.c1), fq_to_bytes(&y.c0), fq_to_bytes(&y.c1)].concat()
+  }
+
+  /// Writes the powers of `srs` in a `.ptau` file of the given power
+  fn to_ptau(srs: &UniversalKZGParam<Bn256>, power: u32) -> Vec<u8> {
+    let fq_to_bytes = |x: &Fq| fq_to_le_bytes(x, true);
+    let mut modulus = fq_to_le_bytes(&-Fq::ONE, false);
+    modulus[0] += 1;
+    let mut header = vec![];
+    header.write_u32::<LittleEndian>(32).unwrap();
+    header.extend(modulus);
+    header.write_u32::<LittleEndian>(power).unwrap();
+    header.write_u32::<LittleEndian>(power).unwrap();
+    let num_g1 = (1 << power) * 2 - 1;
+    let tau_g1 =
+      srs.powers_of_g[..num_g1].iter().flat_map(|p| g1_to_bytes(p, fq_to_bytes)).collect();
+    let tau_g2 =
+      srs.powers_of_h[..1 << power].iter().flat_map(|p| g2_to_bytes(p, fq_to_bytes)).collect();
+
+    let mut bytes = b"ptau".to_vec();
+    bytes.write_u32::<LittleEndian>(1).unwrap();
+    bytes.write_u32::<LittleEndian>(4).unwrap();
+    // the sections are not required to be in order, and unknown ones are skipped
+    for (section_type, data) in [(2, tau_g1), (1, header), (7, vec![0u8; 5]), (3, tau_g2)] {
+      bytes.write_u32::<LittleEndian>(section_type).unwrap();
+      bytes.write_u64::<LittleEndian>(data.len() as u64).unwrap();
+      bytes.extend(data);
+    }
+    bytes
+  }
+
+  #[test]
+  fn test_from_ptau() {
+    let tau = Fr::random(StdRng::seed_from_u64(0));
+    let srs = srs_from_tau(tau, 8);
+    let ptau = to_ptau(&srs, 2);
+
+    let loaded = from_ptau(Cursor::new(&ptau), 7, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g[..7]);
+    assert_eq!(loaded.powers_of_h, srs.powers_of_h[..2]);
+    let loaded = from_ptau(Cursor::new(&ptau), 3, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g[..3]);
+
+    assert!(matches!(
+      from_ptau(Cursor::new(&ptau), 8, OsRng),
+      Err(SrsError::NotEnoughPowers { available: 7, requested: 8 })
+    ));
+    assert!(matches!(
+      from_ptau(Cursor::new(&ptau[1..]), 7, OsRng),
+      Err(SrsError::InvalidFormat(_))
+    ));
+
+    // powers of two different taus are caught by the pairings
+    let mut bad_srs = srs.clone();
+    bad_srs.powers_of_g[3] = srs_from_tau(tau + Fr::ONE, 8).powers_of_g[3];
+    assert!(matches!(
+      from_ptau(Cursor::new(&to_ptau(&bad_srs, 2)), 7, OsRng),
+      Err(SrsError::InconsistentPowers)
+    ));
+    let mut bad_srs = srs.clone();
+    bad_srs.powers_of_h[1] = srs_from_tau(tau + Fr::ONE, 8).powers_of_h[1];
+    assert!(matches!(
+      from_ptau(Cursor::new(&to_ptau(&bad_srs, 2)), 7, OsRng),
+      Err(SrsError::InconsistentPowers)
+    ));
+  }
+
+  #[test]
+  fn test_from_ignition() {
+    let srs = srs_from_tau(Fr::random(StdRng::seed_from_u64(1)), 7);
+    let fq_to_bytes = |x: &Fq| {
+      let le_bytes = fq_to_le_bytes(x, false);
+      le_bytes.chunks(8).flat_map(|limb| limb.iter().rev().copied()).collect::<Vec<_>>()
+    };
+
+    // tau^1..tau^3 and tau^1 H, tau^2 H in the first transcript, tau^4..tau^6 in the second
+    let transcript = |number: u32, g1: &[G1Affine], g2: &[G2Affine], start_from: u32| {
+      let mut bytes = vec![];
+      for x in [number, 2, 6, 2, g1.len() as u32, g2.len() as u32, start_from] {
+        bytes.write_u32::<BigEndian>(x).unwrap();
+      }
+      bytes.extend(g1.iter().flat_map(|p| g1_to_bytes(p, fq_to_bytes)));
+      bytes.extend(g2.iter().flat_map(|p| g2_to_bytes(p, fq_to_bytes)));
+      bytes.extend([0u8; 64]);
+      bytes
+    };
+    let transcripts = [
+      transcript(0, &srs.powers_of_g[1..4], &srs.powers_of_h[1..3], 0),
+      transcript(1, &srs.powers_of_g[4..7], &[], 3),
+    ];
+
+    let loaded = from_ignition(transcripts.iter().map(Cursor::new), 7, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g);
+    assert_eq!(loaded.powers_of_h, srs.powers_of_h[..2]);
+    let loaded = from_ignition(transcripts.iter().map(Cursor::new), 3, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g[..3]);
+
+    assert!(matches!(
+      from_ignition(transcripts.iter().map(Cursor::new), 8, OsRng),
+      Err(SrsError::NotEnoughPowers { available: 7, requested: 8 })
+    ));
+    assert!(matches!(
+      from_ignition(transcripts.iter().rev().map(Cursor::new), 7, OsRng),
+      Err(SrsError::InvalidFormat(_))
+    ));
+  }
+
+  #[test]
+  fn test_from_ethereum_ceremony() {
+    let srs = srs_from_tau(Fr::random(StdRng::seed_from_u64(2)), 8);
+    let to_hex =
+      |bytes: &[u8]| format!("0x{}", bytes.iter().map(|b| format!("{b:02x}")).collect::<String>());
+    let transcript = |n: usize| {
+      serde_json::json!({
+        "numG1Powers": n,
+        "numG2Powers": 3,
+        "powersOfTau": {
+          "G1Powers": srs.powers_of_g[..n].iter().map(|p| to_hex(p.to_bytes().as_ref())).collect::<Vec<_>>(),
+          "G2Powers": srs.powers_of_h[..3].iter().map(|p| to_hex(p.to_bytes().as_ref())).collect::<Vec<_>>(),
+        },
+        "witness": {},
+      })
+    };
+    let ceremony: EthereumCeremony = serde_json::from_value(serde_json::json!({
+      "transcripts": [transcript(8), transcript(4)],
+      "participantIds": [],
+    }))
+    .unwrap();
+
+    // the smallest transcript with enough powers is read
+    let loaded = from_ethereum_ceremony::<Bn256>(&ceremony, 3, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g[..3]);
+    assert_eq!(loaded.powers_of_h, srs.powers_of_h[..3]);
+    let loaded = from_ethereum_ceremony::<Bn256>(&ceremony, 6, OsRng).unwrap();
+    assert_eq!(loaded.powers_of_g, srs.powers_of_g[..6]);
+
+    assert!(matches!(
+      from_ethereum_ceremony::<Bn256>(&ceremony, 9, OsRng),
+      Err(SrsError::NotEnoughPowers { available: 8, requested: 9 })
+    ));
+
+    let mut bad_ceremony = ceremony.clone();
+    bad_ceremony.transcripts[1].powers_of_tau.g1_powers[2] = "0x00".to_string();
+    assert!(matches!(
+      from_ethereum_ceremony::<Bn256>(&bad_ceremony, 3, OsRng),
+      Err(SrsError::InvalidPoint { group: "G1", index: 2 })
+    ));
+  }
+
+  #[test]
+  fn test_check_srs() {
+    // a seeded RNG makes the check deterministic
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut srs = srs_from_tau(Fr::random(&mut rng), 4);
+    check_srs(&srs, &mut rng).unwrap();
+
+    srs.powers_of_h[1] = G2Affine::identity();
+    assert!(matches!(
+      check_srs(&srs, &mut rng),
+      Err(SrsError::InvalidPoint { group: "G2", index: 1 })
+    ));
+    srs.powers_of_g[2] = G1Affine::identity();
+    assert!(matches!(
+      check_srs(&srs, &mut rng),
+      Err(SrsError::InvalidPoint { group: "G1", index: 2 })
+    ));
+
+    srs.powers_of_h.truncate(1);
+    assert!(matches!(check_srs(&srs, &mut rng), Err(SrsError::NotEnoughPowers { .. })));
+  }
+}
@@ -0,0 +1,275 @@
+//! A conformance suite for curve and engine implementations.
+//!
+//! Any new [`Engine`] should pass [`check_engine`] and, if its group is a new
+//! [`DlogGroup`], [`check_group`]: add a test calling them to the list at the
+//! bottom of this module. The checks cover the group laws, the MSM against a
+//! naive sum, the serialization round-trips and the transcript
+//! representations, which the rest of the crate relies on without checking.
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, Group as _, GroupEncoding};
+use rand_core::OsRng;
+
+use crate::{
+  errors::NovaError,
+  provider::traits::DlogGroup,
+  traits::{
+    commitment::{CommitmentEngineTrait, CommitmentTrait},
+    Engine, Group, TranscriptEngineTrait, TranscriptReprTrait,
+  },
+  Commitment,
+};
+
+/// Round-trips `value` through bincode
+fn bincode_round_trip<T>(value: &T) -> T
+where T: serde::Serialize + for<'de> serde::Deserialize<'de> {
+  bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap()
+}
+
+/// Runs every check on the group of `E`
+pub(crate) fn check_group<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  check_group_laws::<E>();
+  check_msm::<E>();
+  check_point_encodings::<E>();
+  check_group_serialization::<E>();
+  check_group_transcript_reprs::<E>();
+}
+
+/// Runs every check on the commitment engine and transcript of `E`
+pub(crate) fn check_engine<E: Engine>() {
+  check_commitments::<E>();
+  check_engine_serialization::<E>();
+  check_transcript::<E>();
+}
+
+pub(crate) fn check_group_laws<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  let (g, o) = (E::GE::generator(), E::GE::identity());
+  let (p, q, r) = (E::GE::random(OsRng), E::GE::random(OsRng), E::GE::random(OsRng));
+  let (a, b) = (E::Scalar::random(OsRng), E::Scalar::random(OsRng));
+
+  assert_eq!(p + o, p);
+  assert_eq!(p + q, q + p);
+  assert_eq!((p + q) + r, p + (q + r));
+  assert_eq!(p - p, o);
+  assert_eq!(p + (-p), o);
+  assert_eq!(p.double(), p + p);
+  assert_eq!(p * (a + b), p * a + p * b);
+  assert_eq!((p + q) * a, p * a + q * a);
+  assert_eq!(p * (a * b), (p * a) * b);
+  assert_eq!(p * E::Scalar::ONE, p);
+  assert_eq!(p * E::Scalar::ZERO, o);
+  assert_eq!(p * -E::Scalar::ONE, -p);
+  assert!(!bool::from(g.is_identity()));
+  assert!(bool::from(o.is_identity()));
+
+  // the coordinates are on the curve described by the group parameters
+  let (A, B, ..) = E::GE::group_params();
+  for p in [g, p, q] {
+    let (x, y, is_infinity) = p.to_coordinates();
+    assert!(!is_infinity);
+    assert_eq!(y.square(), x.square() * x + A * x + B);
+    assert_eq!(E::GE::from_coordinates(x, y), Some(p));
+    assert_eq!(E::GE::from_coordinates(x, y + E::Base::ONE), None);
+  }
+  assert!(o.to_coordinates().2);
+
+  // affine and projective forms agree
+  assert_eq!(p.to_affine().to_curve(), p);
+  assert_eq!(p.to_affine() * a, p * a);
+  assert!(bool::from(<E::GE as DlogGroup>::AffineExt::identity().is_identity()));
+}
+
+pub(crate) fn check_msm<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  // sizes on either side of the threshold for accelerated MSMs
+  for n in [1, 2, 7, 64, 127, 128, 130] {
+    let bases = E::GE::from_label(b"conformance", n);
+    assert_eq!(bases.len(), n);
+    let scalars = (0..n).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+    let naive =
+      scalars.iter().zip(bases.iter()).fold(E::GE::identity(), |acc, (s, b)| acc + *b * *s);
+    assert_eq!(E::GE::vartime_multiscalar_mul(&scalars, &bases), naive);
+  }
+
+  // generators are deterministic, prefix-stable and non-trivial
+  let bases = E::GE::from_label(b"conformance", 16);
+  assert_eq!(bases, E::GE::from_label(b"conformance", 16));
+  assert_ne!(bases, E::GE::from_label(b"other label", 16));
+  assert_eq!(bases[..8], E::GE::from_label(b"conformance", 8)[..]);
+  assert!(bases.iter().all(|b| !bool::from(b.is_identity())));
+}
+
+pub(crate) fn check_point_encodings<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  for p in [E::GE::identity(), E::GE::generator(), E::GE::random(OsRng), E::GE::random(OsRng)] {
+    let compressed = p.to_compressed_bytes();
+    assert_eq!(E::GE::from_compressed_bytes(&compressed), Some(p));
+    let affine = p.to_affine_bytes();
+    assert_eq!(E::GE::from_affine_bytes(&affine), Some(p));
+    let repr = p.to_bytes();
+    assert_eq!(Option::<E::GE>::from(E::GE::from_bytes(&repr)), Some(p));
+  }
+
+  // malformed encodings are rejected
+  let len = (E::Base::NUM_BITS as usize).div_ceil(8);
+  let mut compressed = E::GE::generator().to_compressed_bytes();
+  assert_eq!(compressed.len(), len + 1);
+  compressed[0] = 4;
+  assert_eq!(E::GE::from_compressed_bytes(&compressed), None);
+  assert_eq!(E::GE::from_compressed_bytes(&[0, 0]), None);
+  assert_eq!(E::GE::from_compressed_bytes(&[]), None);
+  assert_eq!(E::GE::from_affine_bytes(&vec![0xff; 2 * len]), None);
+  let mut affine = E::GE::generator().to_affine_bytes();
+  assert_eq!(affine.len(), 2 * len);
+  affine[2 * len - 1] ^= 1;
+  assert_eq!(E::GE::from_affine_bytes(&affine), None);
+}
+
+pub(crate) fn check_group_serialization<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  let p = E::GE::random(OsRng);
+  assert_eq!(bincode_round_trip(&p), p);
+  assert_eq!(bincode_round_trip(&E::GE::identity()), E::GE::identity());
+  assert_eq!(bincode_round_trip(&p.to_affine()), p.to_affine());
+
+  let compressed = <E::GE as DlogGroup>::Compressed::from(p.to_bytes());
+  assert_eq!(bincode_round_trip(&compressed), compressed);
+
+  let s = E::Scalar::random(OsRng);
+  assert_eq!(bincode_round_trip(&s), s);
+  let x = p.to_coordinates().0;
+  assert_eq!(bincode_round_trip(&x), x);
+}
+
+pub(crate) fn check_group_transcript_reprs<E>()
+where
+  E: Engine,
+  E::GE: DlogGroup<ScalarExt = E::Scalar>, {
+  let (a, b) = (E::Scalar::random(OsRng), E::Scalar::random(OsRng));
+
+  // deterministic, injective and of a fixed length
+  let scalar_repr = |s: &E::Scalar| TranscriptReprTrait::<E::GE>::to_transcript_bytes(s);
+  assert_eq!(scalar_repr(&a), scalar_repr(&a));
+  assert_ne!(scalar_repr(&a), scalar_repr(&b));
+  assert_eq!(scalar_repr(&a).len(), scalar_repr(&E::Scalar::ZERO).len());
+
+  let compressed_repr =
+    |p: E::GE| <E::GE as DlogGroup>::Compressed::from(p.to_bytes()).to_transcript_bytes();
+  let (p, q) = (E::GE::random(OsRng), E::GE::random(OsRng));
+  assert_eq!(compressed_repr(p), compressed_repr(p));
+  assert_ne!(compressed_repr(p), compressed_repr(q));
+  assert_ne!(compressed_repr(p), compressed_repr(E::GE::identity()));
+}
+
+pub(crate) fn check_commitments<E: Engine>() {
+  let n = 16;
+  let ck = E::CE::setup(b"conformance", n);
+  let v = (0..n).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+  let w = (0..n).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+  let s = E::Scalar::random(OsRng);
+
+  // commitments are additively homomorphic
+  let sum = v.iter().zip(w.iter()).map(|(a, b)| *a + b).collect::<Vec<_>>();
+  let scaled = v.iter().map(|a| *a * s).collect::<Vec<_>>();
+  let (cv, cw) = (E::CE::commit(&ck, &v), E::CE::commit(&ck, &w));
+  assert_eq!(cv + cw, E::CE::commit(&ck, &sum));
+  assert_eq!(cv * s, E::CE::commit(&ck, &scaled));
+  assert_eq!(E::CE::commit(&ck, &vec![E::Scalar::ZERO; n]), Commitment::<E>::default());
+  assert_ne!(cv, cw);
+
+  // committing to a prefix pads with zeros
+  let padded = [&v[..n / 2], &vec![E::Scalar::ZERO; n / 2]].concat();
+  assert_eq!(E::CE::commit(&ck, &v[..n / 2]), E::CE::commit(&ck, &padded));
+
+  // compression round-trips, including for the identity
+  for c in [cv, cw, Commitment::<E>::default()] {
+    assert_eq!(Commitment::<E>::decompress(&c.compress()).unwrap(), c);
+  }
+  assert_ne!(cv.compress(), cw.compress());
+
+  // the setup with progress produces the same key, and can be interrupted
+  let reports = std::sync::Mutex::new(Vec::new());
+  let record = |stage: &str, done: usize, total: usize| {
+    assert_eq!(stage, "ck");
+    reports.lock().unwrap().push((done, total));
+    true
+  };
+  assert_eq!(E::CE::setup_with_progress(b"conformance", n, "ck", &record).unwrap(), ck);
+  let reports = reports.into_inner().unwrap();
+  assert_eq!(reports.first().map(|r| r.0), Some(0));
+  assert!(reports.last().is_some_and(|(done, total)| done == total && *total >= n));
+  let interrupt = |_: &str, done: usize, _: usize| done == 0;
+  assert_eq!(
+    E::CE::setup_with_progress(b"conformance", n, "ck", &interrupt),
+    Err(NovaError::Interrupted)
+  );
+}
+
+pub(crate) fn check_engine_serialization<E: Engine>() {
+  let ck = E::CE::setup(b"conformance", 4);
+  assert_eq!(bincode_round_trip(&ck), ck);
+
+  let c = E::CE::commit(&ck, &(0..4).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>());
+  assert_eq!(bincode_round_trip(&c), c);
+  assert_eq!(bincode_round_trip(&c.compress()), c.compress());
+  let identity = Commitment::<E>::default();
+  assert_eq!(bincode_round_trip(&identity), identity);
+}
+
+pub(crate) fn check_transcript<E: Engine>() {
+  let ck = E::CE::setup(b"conformance", 4);
+  let c = E::CE::commit(&ck, &(0..4).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>());
+  let s = E::Scalar::random(OsRng);
+
+  let run = |label: &'static [u8], c: &Commitment<E>| {
+    let mut transcript = E::TE::new(b"conformance");
+    transcript.absorb(b"c", c);
+    transcript.absorb(b"s", &s);
+    transcript.absorb(b"v", &[s, s].as_slice());
+    (transcript.squeeze(label).unwrap(), transcript.squeeze(label).unwrap())
+  };
+
+  // the transcript is deterministic, and successive challenges differ
+  let (r1, r2) = run(b"r", &c);
+  assert_eq!(run(b"r", &c), (r1, r2));
+  assert_ne!(r1, r2);
+  // challenges depend on their label and on everything absorbed
+  assert_ne!(run(b"q", &c).0, r1);
+  assert_ne!(run(b"r", &(c + c)).0, r1);
+
+  // commitment reprs are deterministic and injective
+  let repr = |c: &Commitment<E>| c.to_transcript_bytes();
+  assert_eq!(repr(&c), repr(&c));
+  assert_ne!(repr(&c), repr(&(c + c)));
+  assert_ne!(repr(&c), repr(&Commitment::<E>::default()));
+  assert_ne!(c.compress().to_transcript_bytes(), (c + c).compress().to_transcript_bytes());
+}
+
+#[test]
+fn test_bn256_conformance() {
+  use crate::provider::{Bn256EngineIPA, Bn256EngineKZG, Bn256EngineZM};
+
+  check_group::<Bn256EngineIPA>();
+  check_engine::<Bn256EngineIPA>();
+  check_engine::<Bn256EngineKZG>();
+  check_engine::<Bn256EngineZM>();
+}
+
+#[test]
+fn test_grumpkin_conformance() {
+  use crate::provider::GrumpkinEngine;
+
+  check_group::<GrumpkinEngine>();
+  check_engine::<GrumpkinEngine>();
+}
@@ -1,3 +1,4 @@
+#[cfg(test)] pub(crate) mod conformance;
 mod ipa_pc;
 
 #[cfg(test)]
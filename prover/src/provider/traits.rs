@@ -1,5 +1,6 @@
 use std::{fmt::Debug, ops::Mul};
 
+use ff::{Field, PrimeField, PrimeFieldBits};
 use group::{
   prime::{PrimeCurve, PrimeCurveAffine},
   GroupEncoding,
@@ -39,11 +40,98 @@ pub trait DlogGroup:
   /// A method to compute a multiexponentation
   fn vartime_multiscalar_mul(scalars: &[Self::ScalarExt], bases: &[Self::AffineExt]) -> Self;
 
-  /// Produce a vector of group elements using a static label
-  fn from_label(label: &'static [u8], n: usize) -> Vec<Self::Affine>;
+  /// Produce a vector of group elements using a label: the stream of
+  /// SHAKE256 on `label` is split in 32-byte blocks, and the i-th element is
+  /// the hash to the curve of the i-th block, with the `hash_to_curve` of the
+  /// curve and the domain prefix `"from_uniform_bytes"`. The elements for `n`
+  /// are thus a prefix of those for any larger `n`.
+  fn from_label(label: &[u8], n: usize) -> Vec<Self::Affine>;
+
+  /// Produces the same group elements as `from_label`, computing them in
+  /// chunks of `chunk_size` elements. After each chunk, `on_chunk` is called
+  /// with the number of elements computed so far, and can return `false` to
+  /// stop, in which case `None` is returned. The elements do not depend on
+  /// `chunk_size` nor on the number of threads.
+  fn from_label_chunked(
+    label: &[u8],
+    n: usize,
+    chunk_size: usize,
+    on_chunk: &mut dyn FnMut(usize) -> bool,
+  ) -> Option<Vec<Self::Affine>>;
 
   /// Returns the affine coordinates (x, y, infinity) for the point
   fn to_coordinates(&self) -> (<Self as Group>::Base, <Self as Group>::Base, bool);
+
+  /// Returns the point with affine coordinates (x, y), if it is on the curve
+  fn from_coordinates(x: <Self as Group>::Base, y: <Self as Group>::Base) -> Option<Self>;
+
+  /// Encodes the point in SEC1 compressed form: `0x02` or `0x03` according to
+  /// the parity of y, followed by x in big-endian; the identity is `0x00`
+  fn to_compressed_bytes(&self) -> Vec<u8> {
+    let (x, y, is_infinity) = self.to_coordinates();
+    if is_infinity {
+      return vec![0];
+    }
+    let tag = if bool::from(y.is_odd()) { 3 } else { 2 };
+    [vec![tag], field_to_be_bytes(&x)].concat()
+  }
+
+  /// Decodes a point from the encoding of
+  /// [`DlogGroup::to_compressed_bytes`], rejecting non-canonical encodings
+  fn from_compressed_bytes(bytes: &[u8]) -> Option<Self> {
+    match bytes.split_first()? {
+      (0, []) => Some(<Self as group::Group>::identity()),
+      (tag @ (2 | 3), x) => {
+        let x = field_from_be_bytes::<<Self as Group>::Base>(x)?;
+        let (a, b, ..) = Self::group_params();
+        let y = Option::<<Self as Group>::Base>::from((x.square() * x + a * x + b).sqrt())?;
+        let y = if bool::from(y.is_odd()) == (*tag == 3) { y } else { -y };
+        Self::from_coordinates(x, y)
+      },
+      _ => None,
+    }
+  }
+
+  /// Encodes the point as its affine coordinates x || y in big-endian, with
+  /// the identity as all zeros, as e.g. the EVM precompiles expect
+  fn to_affine_bytes(&self) -> Vec<u8> {
+    let (x, y, _) = self.to_coordinates();
+    [field_to_be_bytes(&x), field_to_be_bytes(&y)].concat()
+  }
+
+  /// Decodes a point from the encoding of [`DlogGroup::to_affine_bytes`],
+  /// rejecting non-canonical encodings
+  fn from_affine_bytes(bytes: &[u8]) -> Option<Self> {
+    if bytes.len() % 2 != 0 {
+      return None;
+    }
+    if bytes.iter().all(|b| *b == 0) {
+      return Some(<Self as group::Group>::identity());
+    }
+    let (x, y) = bytes.split_at(bytes.len() / 2);
+    Self::from_coordinates(field_from_be_bytes(x)?, field_from_be_bytes(y)?)
+  }
+}
+
+/// Big-endian encoding of the canonical representative of `x`, over the
+/// fewest bytes that fit any field element
+pub(crate) fn field_to_be_bytes<F: PrimeFieldBits>(x: &F) -> Vec<u8> {
+  let len = (F::NUM_BITS as usize).div_ceil(8);
+  let mut out = vec![0u8; len];
+  for (i, bit) in x.to_le_bits().iter().by_vals().take(8 * len).enumerate() {
+    if bit {
+      out[len - 1 - i / 8] |= 1 << (i % 8);
+    }
+  }
+  out
+}
+
+/// Inverse of [`field_to_be_bytes`]
+pub(crate) fn field_from_be_bytes<F: PrimeFieldBits>(bytes: &[u8]) -> Option<F> {
+  let radix = F::from(256);
+  let x = bytes.iter().fold(F::ZERO, |acc, b| acc * radix + F::from(u64::from(*b)));
+  // reject values that are not reduced, or of the wrong length
+  (field_to_be_bytes(&x) == bytes).then_some(x)
 }
 
 /// This implementation behaves in ways specific to the halo2curves suite of
@@ -66,11 +154,13 @@ macro_rules! impl_traits {
       type Base = $name::Base;
       type Scalar = $name::Scalar;
 
+      // the orders are hex literals of the macro invocations
+      #[allow(clippy::expect_used)]
       fn group_params() -> (Self::Base, Self::Base, BigInt, BigInt) {
         let A = $name::Point::a();
         let B = $name::Point::b();
-        let order = BigInt::from_str_radix($order_str, 16).unwrap();
-        let base = BigInt::from_str_radix($base_str, 16).unwrap();
+        let order = BigInt::from_str_radix($order_str, 16).expect("the order is in hex");
+        let base = BigInt::from_str_radix($base_str, 16).expect("the base is in hex");
 
         (A, B, order, base)
       }
@@ -95,50 +185,74 @@ macro_rules! impl_traits {
         cpu_best_msm(bases, scalars)
       }
 
-      fn from_label(label: &'static [u8], n: usize) -> Vec<Self::Affine> {
+      // the callback never stops the computation
+      #[allow(clippy::expect_used)]
+      fn from_label(label: &[u8], n: usize) -> Vec<Self::Affine> {
+        Self::from_label_chunked(label, n, n, &mut |_| true)
+          .expect("the computation is not stopped")
+      }
+
+      // a XOF stream does not end
+      #[allow(clippy::expect_used)]
+      fn from_label_chunked(
+        label: &[u8],
+        n: usize,
+        chunk_size: usize,
+        on_chunk: &mut dyn FnMut(usize) -> bool,
+      ) -> Option<Vec<Self::Affine>> {
         let mut shake = Shake256::default();
         shake.update(label);
         let mut reader = shake.finalize_xof();
-        let mut uniform_bytes_vec = Vec::new();
-        for _ in 0..n {
-          let mut uniform_bytes = [0u8; 32];
-          reader.read_exact(&mut uniform_bytes).unwrap();
-          uniform_bytes_vec.push(uniform_bytes);
-        }
-        let gens_proj: Vec<$name::Point> = (0..n)
-          .into_par_iter()
-          .map(|i| {
-            let hash = $name::Point::hash_to_curve("from_uniform_bytes");
-            hash(&uniform_bytes_vec[i])
-          })
-          .collect();
-
-        let num_threads = rayon::current_num_threads();
-        if gens_proj.len() > num_threads {
-          let chunk = (gens_proj.len() as f64 / num_threads as f64).ceil() as usize;
-          (0..num_threads)
-            .into_par_iter()
-            .flat_map(|i| {
-              let start = i * chunk;
-              let end = if i == num_threads - 1 {
-                gens_proj.len()
-              } else {
-                core::cmp::min((i + 1) * chunk, gens_proj.len())
-              };
-              if end > start {
-                let mut gens = vec![$name::Affine::identity(); end - start];
-                <Self as Curve>::batch_normalize(&gens_proj[start..end], &mut gens);
-                gens
-              } else {
-                vec![]
-              }
+
+        // the i-th element is hashed from the i-th 32 bytes of the stream, whatever the chunks
+        let mut gens = Vec::with_capacity(n);
+        while gens.len() < n {
+          let len = chunk_size.max(1).min(n - gens.len());
+          let mut uniform_bytes_vec = vec![[0u8; 32]; len];
+          for uniform_bytes in uniform_bytes_vec.iter_mut() {
+            reader.read_exact(uniform_bytes).expect("the XOF stream is unbounded");
+          }
+          let gens_proj: Vec<$name::Point> = uniform_bytes_vec
+            .par_iter()
+            .map(|uniform_bytes| {
+              let hash = $name::Point::hash_to_curve("from_uniform_bytes");
+              hash(uniform_bytes)
             })
-            .collect()
-        } else {
-          let mut gens = vec![$name::Affine::identity(); n];
-          <Self as Curve>::batch_normalize(&gens_proj, &mut gens);
-          gens
+            .collect();
+
+          let num_threads = $crate::parallel::current_num_threads();
+          if gens_proj.len() > num_threads {
+            let chunk = (gens_proj.len() as f64 / num_threads as f64).ceil() as usize;
+            let normalized: Vec<$name::Affine> = (0..num_threads)
+              .into_par_iter()
+              .flat_map(|i| {
+                let start = i * chunk;
+                let end = if i == num_threads - 1 {
+                  gens_proj.len()
+                } else {
+                  core::cmp::min((i + 1) * chunk, gens_proj.len())
+                };
+                if end > start {
+                  let mut gens = vec![$name::Affine::identity(); end - start];
+                  <Self as Curve>::batch_normalize(&gens_proj[start..end], &mut gens);
+                  gens
+                } else {
+                  vec![]
+                }
+              })
+              .collect();
+            gens.extend(normalized);
+          } else {
+            let mut normalized = vec![$name::Affine::identity(); len];
+            <Self as Curve>::batch_normalize(&gens_proj, &mut normalized);
+            gens.extend(normalized);
+          }
+
+          if !on_chunk(gens.len()) {
+            return None;
+          }
         }
+        Some(gens)
       }
 
       fn to_coordinates(&self) -> (Self::Base, Self::Base, bool) {
@@ -149,11 +263,18 @@ macro_rules! impl_traits {
           (Self::Base::zero(), Self::Base::zero(), true)
         }
       }
+
+      fn from_coordinates(x: Self::Base, y: Self::Base) -> Option<Self> {
+        Option::from(<$name::Affine as halo2curves::CurveAffine>::from_xy(x, y))
+          .map($name::Point::from)
+      }
     }
 
     impl PrimeFieldExt for $name::Scalar {
+      // the hashers of the callers output 64 bytes
+      #[allow(clippy::expect_used)]
       fn from_uniform(bytes: &[u8]) -> Self {
-        let bytes_arr: [u8; 64] = bytes.try_into().unwrap();
+        let bytes_arr: [u8; 64] = bytes.try_into().expect("64 uniform bytes");
         $name::Scalar::from_uniform_bytes(&bytes_arr)
       }
     }
@@ -163,7 +284,9 @@ macro_rules! impl_traits {
     }
 
     impl<G: Group> TranscriptReprTrait<G> for $name::Scalar {
-      fn to_transcript_bytes(&self) -> Vec<u8> { self.to_repr().to_vec() }
+      fn to_transcript_bytes(&self) -> Vec<u8> {
+        $crate::scalar_bytes::ScalarBytesLe::new(self).as_bytes().to_vec()
+      }
     }
 
     impl<G: DlogGroup> TranscriptReprTrait<G> for $name::Affine {
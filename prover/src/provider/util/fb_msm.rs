@@ -8,7 +8,8 @@
 /// results are efficiently combined.
 use ff::{PrimeField, PrimeFieldBits};
 use group::{prime::PrimeCurve, Curve};
-use rayon::prelude::*;
+
+use crate::parallel::prelude::*;
 
 /// Determines the window size for scalar multiplication based on the number of
 /// scalars.
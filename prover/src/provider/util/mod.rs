@@ -10,9 +10,79 @@ pub mod msm {
   }
 }
 
+pub mod inline {
+  use core::ops::{Deref, DerefMut};
+
+  use crate::errors::NovaError;
+
+  /// A vector of at most `N` elements, stored inline, for the vectors of a
+  /// small static bound, e.g. the challenges of the rounds of an inner
+  /// product argument, which then do not allocate
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  pub struct InlineVec<T, const N: usize> {
+    items: [T; N],
+    len:   usize,
+  }
+
+  impl<T: Copy + Default, const N: usize> InlineVec<T, N> {
+    /// An empty vector
+    pub fn new() -> Self { Self { items: [T::default(); N], len: 0 } }
+
+    /// Pushes `item`, which fails if the vector holds `N` elements
+    pub fn push(&mut self, item: T) -> Result<(), NovaError> {
+      *self.items.get_mut(self.len).ok_or(NovaError::InvalidInputLength)? = item;
+      self.len += 1;
+      Ok(())
+    }
+
+    /// The vector of the images of the elements by `f`
+    pub fn map(&self, mut f: impl FnMut(&T) -> T) -> Self {
+      let mut image = Self::new();
+      for (item, image) in self.iter().zip(image.items.iter_mut()) {
+        *image = f(item);
+      }
+      image.len = self.len;
+      image
+    }
+  }
+
+  impl<T: Copy + Default, const N: usize> Default for InlineVec<T, N> {
+    fn default() -> Self { Self::new() }
+  }
+
+  impl<T, const N: usize> Deref for InlineVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] { &self.items[..self.len] }
+  }
+
+  impl<T, const N: usize> DerefMut for InlineVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] { &mut self.items[..self.len] }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_vec() {
+      let mut v = InlineVec::<u64, 3>::new();
+      for i in 1..=3 {
+        v.push(i).unwrap();
+      }
+      assert_eq!(v.push(4), Err(NovaError::InvalidInputLength));
+      assert_eq!(&*v, &[1, 2, 3]);
+      assert_eq!(&*v.map(|i| i * i), &[1, 4, 9]);
+      v[0] = 0;
+      assert_eq!(v.iter().sum::<u64>(), 5);
+    }
+  }
+}
+
 pub mod field {
   use ff::{BatchInverter, Field};
 
+  use super::inline::InlineVec;
   use crate::errors::NovaError;
 
   #[inline]
@@ -25,6 +95,18 @@ pub mod field {
     let _ = BatchInverter::invert_with_external_scratch(&mut v, &mut scratch_space[..]);
     Ok(v)
   }
+
+  /// Same as [`batch_invert`], for a vector stored inline, without allocating
+  pub fn batch_invert_inline<F: Field, const N: usize>(
+    mut v: InlineVec<F, N>,
+  ) -> Result<InlineVec<F, N>, NovaError> {
+    if v.iter().any(|x| x.is_zero_vartime()) {
+      return Err(NovaError::InternalError);
+    }
+    let mut scratch_space = v;
+    let _ = BatchInverter::invert_with_external_scratch(&mut v, &mut scratch_space);
+    Ok(v)
+  }
 }
 
 pub mod iterators {
@@ -35,8 +117,10 @@ pub mod iterators {
   };
 
   use ff::Field;
-  use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
-  use rayon_scan::ScanParallelIterator;
+
+  use crate::parallel::prelude::{
+    IndexedParallelIterator, IntoParallelIterator, ParallelIterator, ScanParallelIterator,
+  };
 
   pub trait DoubleEndedIteratorExt: DoubleEndedIterator {
     /// This function employs Horner's scheme and core traits to create a
@@ -88,7 +172,7 @@ pub mod iterators {
           a += &b;
           a
         })
-        .unwrap()
+        .expect("the iterator is not empty")
     }
   }
 
@@ -190,6 +274,23 @@ pub mod test_utils {
     // state.
     assert_eq!(pcp, pcv);
 
+    // Proving with the auxiliary data of the commitment yields a valid proof too.
+    let state = EE::commitment_state(&ock, &prover_key, poly.evaluations());
+    let proof_with_state = EE::prove_with_state(
+      &*ock,
+      &prover_key,
+      &state,
+      &mut E::TE::new(b"TestEval"),
+      commitment,
+      poly.evaluations(),
+      point,
+      eval,
+    )
+    .unwrap();
+    let mut verifier_transcript = E::TE::new(b"TestEval");
+    EE::verify(&verifier_key, &mut verifier_transcript, commitment, point, eval, &proof_with_state)
+      .unwrap();
+
     if evaluate_bad_proof {
       // Generate another point to verify proof. Also produce eval.
       let altered_verifier_point =
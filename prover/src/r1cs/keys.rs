@@ -0,0 +1,216 @@
+//! The commitment keys of the primary and secondary engines of a cycle, made
+//! by one pipeline.
+//!
+//! [`CommitmentKeys::setup`] makes both keys with [`cached_commitment_key`],
+//! which is generic over the engine: a key is loaded from a
+//! [`CommitmentKeyCache`] if the cache holds it and it matches the digest it is
+//! stored with, and generated and stored otherwise. The digest of the pair,
+//! [`CommitmentKeys::digest`], binds the two keys together, so that the keys of
+//! public parameters are checked against it at once, e.g. when the parameters
+//! are loaded with keys from elsewhere.
+use std::{
+  any::type_name,
+  fs,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use bincode::Options;
+use serde::Serialize;
+
+use super::{commitment_key_digest, CommitmentKeySpec};
+use crate::{
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  traits::{commitment::CommitmentEngineTrait, CurveCycleEquipped, Dual, Engine, SetupProgress},
+  CommitmentKey,
+};
+
+/// A store of commitment keys across setups, shared by the engines of a cycle
+pub trait CommitmentKeyCache: Send + Sync {
+  /// The bytes stored under `name`, if any
+  fn load(&self, name: &str) -> Option<Vec<u8>>;
+
+  /// Stores `bytes` under `name`; a failure only loses the entry
+  fn store(&self, name: &str, bytes: &[u8]);
+}
+
+/// No cache: every key is generated
+impl CommitmentKeyCache for () {
+  fn load(&self, _name: &str) -> Option<Vec<u8>> { None }
+
+  fn store(&self, _name: &str, _bytes: &[u8]) {}
+}
+
+/// A cache of commitment keys in a directory, a file per key
+#[derive(Clone, Debug)]
+pub struct DirCommitmentKeyCache {
+  dir: PathBuf,
+}
+
+impl DirCommitmentKeyCache {
+  /// The cache in `dir`, which is created on the first store
+  pub fn new(dir: impl Into<PathBuf>) -> Self { Self { dir: dir.into() } }
+
+  /// The directory of the cache
+  pub fn dir(&self) -> &Path { &self.dir }
+}
+
+impl CommitmentKeyCache for DirCommitmentKeyCache {
+  fn load(&self, name: &str) -> Option<Vec<u8>> { fs::read(self.dir.join(name)).ok() }
+
+  fn store(&self, name: &str, bytes: &[u8]) {
+    // written aside then renamed, so that a reader never loads a partial key
+    let path = self.dir.join(name);
+    let partial = self.dir.join(format!("{name}.partial"));
+    let _ = fs::create_dir_all(&self.dir)
+      .and_then(|()| fs::write(&partial, bytes))
+      .and_then(|()| fs::rename(&partial, &path));
+  }
+}
+
+/// The encoding of cached keys, as for digests
+fn encoding() -> impl Options {
+  bincode::DefaultOptions::new().with_little_endian().with_fixint_encoding()
+}
+
+/// The name a key of `size` generators of `E` is cached under
+fn cache_name<E: Engine>(size: usize) -> String {
+  let engine: String =
+    type_name::<E>().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+  format!("ck-{engine}-{size}")
+}
+
+/// The commitment key of `size` generators of `E`, loaded from `cache` if it
+/// holds it, and generated as
+/// [`commitment_key_with_progress`](super::commitment_key_with_progress) does
+/// and stored otherwise
+pub fn cached_commitment_key<E: Engine>(
+  size: usize,
+  stage: &str,
+  progress: &dyn SetupProgress,
+  cache: &dyn CommitmentKeyCache,
+) -> Result<CommitmentKey<E>, NovaError> {
+  let name = cache_name::<E>(size);
+  let cached = cache.load(&name).and_then(|bytes| {
+    let (spec, ck): (CommitmentKeySpec<E>, CommitmentKey<E>) =
+      encoding().reject_trailing_bytes().deserialize(&bytes).ok()?;
+    (commitment_key_digest::<E>(&ck).ok()? == spec.digest()).then_some(ck)
+  });
+  if let Some(ck) = cached {
+    return Ok(ck);
+  }
+
+  let ck = E::CE::setup_with_progress(b"ck", size, stage, progress)?;
+  if let Ok(bytes) = encoding().serialize(&(CommitmentKeySpec::<E>::new(&ck), &ck)) {
+    cache.store(&name, &bytes);
+  }
+  Ok(ck)
+}
+
+/// The commitment keys of the primary and secondary engines of a cycle
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitmentKeys<E1: CurveCycleEquipped> {
+  /// The key of the primary engine
+  pub primary:   Arc<CommitmentKey<E1>>,
+  /// The key of the secondary engine
+  pub secondary: Arc<CommitmentKey<Dual<E1>>>,
+}
+
+/// The descriptions of a pair of keys, digested together
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct CommitmentKeySpecs<E1: CurveCycleEquipped> {
+  primary:   CommitmentKeySpec<E1>,
+  secondary: CommitmentKeySpec<Dual<E1>>,
+}
+
+impl<E1: CurveCycleEquipped> SimpleDigestible for CommitmentKeySpecs<E1> {}
+
+impl<E1: CurveCycleEquipped> CommitmentKeys<E1> {
+  /// The keys of `size_primary` and `size_secondary` generators, made by
+  /// [`cached_commitment_key`] under the stages `"primary commitment key"`
+  /// and `"secondary commitment key"`
+  pub fn setup(
+    size_primary: usize,
+    size_secondary: usize,
+    progress: &dyn SetupProgress,
+    cache: &dyn CommitmentKeyCache,
+  ) -> Result<Self, NovaError> {
+    let primary =
+      cached_commitment_key::<E1>(size_primary, "primary commitment key", progress, cache)?;
+    let secondary = cached_commitment_key::<Dual<E1>>(
+      size_secondary,
+      "secondary commitment key",
+      progress,
+      cache,
+    )?;
+    Ok(Self { primary: Arc::new(primary), secondary: Arc::new(secondary) })
+  }
+
+  /// The digest binding the two keys together
+  pub fn digest(&self) -> Result<E1::Scalar, NovaError> {
+    let specs = CommitmentKeySpecs::<E1> {
+      primary:   CommitmentKeySpec::new(&self.primary)?,
+      secondary: CommitmentKeySpec::new(&self.secondary)?,
+    };
+    DigestComputer::<E1::Scalar, _>::new(&specs).digest().map_err(|_| NovaError::DigestError)
+  }
+
+  /// Checks the keys against the digest of a pair, e.g. a published one
+  pub fn check(&self, digest: &E1::Scalar) -> Result<(), NovaError> {
+    if self.digest()? != *digest {
+      return Err(NovaError::DigestError);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Mutex;
+
+  use super::*;
+  use crate::provider::Bn256EngineIPA;
+
+  type E = Bn256EngineIPA;
+
+  /// A cache in memory, recording its stores
+  #[derive(Default)]
+  struct MemoryCache(Mutex<Vec<(String, Vec<u8>)>>);
+
+  impl CommitmentKeyCache for MemoryCache {
+    fn load(&self, name: &str) -> Option<Vec<u8>> {
+      let entries = self.0.lock().unwrap();
+      entries.iter().rev().find(|(n, _)| n == name).map(|(_, bytes)| bytes.clone())
+    }
+
+    fn store(&self, name: &str, bytes: &[u8]) {
+      self.0.lock().unwrap().push((name.to_string(), bytes.to_vec()));
+    }
+  }
+
+  #[test]
+  fn test_commitment_keys() {
+    let progress = |_: &str, _: usize, _: usize| true;
+    let cache = MemoryCache::default();
+    let keys = CommitmentKeys::<E>::setup(16, 8, &progress, &cache).unwrap();
+    assert_eq!(keys, CommitmentKeys::<E>::setup(16, 8, &progress, &()).unwrap());
+    assert_eq!(cache.0.lock().unwrap().len(), 2);
+
+    // both keys are then loaded from the cache
+    assert_eq!(CommitmentKeys::<E>::setup(16, 8, &progress, &cache).unwrap(), keys);
+    assert_eq!(cache.0.lock().unwrap().len(), 2);
+    keys.check(&keys.digest().unwrap()).unwrap();
+
+    // a corrupted entry is generated again, and the pair is bound together
+    let name = cache_name::<E>(16);
+    let mut bytes = cache.load(&name).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    cache.store(&name, &bytes);
+    let other = CommitmentKeys::<E>::setup(16, 16, &progress, &cache).unwrap();
+    assert_eq!(other.primary, keys.primary);
+    assert_eq!(other.check(&keys.digest().unwrap()), Err(NovaError::DigestError));
+  }
+}
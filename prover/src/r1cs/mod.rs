@@ -1,13 +1,15 @@
 //! This module defines R1CS related types and a folding scheme for Relaxed R1CS
+pub mod keys;
+pub mod segments;
 mod sparse;
 pub(crate) mod util;
 
 use core::cmp::max;
 
+use bincode::Options;
 use ff::Field;
 use once_cell::sync::OnceCell;
 use rand_core::{CryptoRng, RngCore};
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 pub(crate) use sparse::SparseMatrix;
 
@@ -16,8 +18,11 @@ use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
   gadgets::{f_to_nat, nat_to_limbs, scalar_as_base},
+  parallel::{self, prelude::*},
+  secret::{wipe, Zeroize},
   traits::{
-    commitment::CommitmentEngineTrait, AbsorbInROTrait, Engine, ROTrait, TranscriptReprTrait,
+    commitment::{CommitmentEngineTrait, Len},
+    AbsorbInROTrait, Engine, ROTrait, SetupProgress, TranscriptReprTrait,
   },
   zip_with, Commitment, CommitmentKey, CE,
 };
@@ -59,14 +64,23 @@ pub struct R1CSInstance<E: Engine> {
   pub(crate) X:      Vec<E::Scalar>,
 }
 
-/// A type that holds a witness for a given Relaxed R1CS instance
+/// A type that holds a witness for a given Relaxed R1CS instance.
+///
+/// Its serde layout is stable, to persist folding state: the fields `W` and
+/// `E`, in this order. [`RelaxedR1CSWitness::to_bytes`] encodes it as digests
+/// are encoded (see [`crate::digest`]).
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RelaxedR1CSWitness<E: Engine> {
   pub(crate) W: Vec<E::Scalar>,
   pub(crate) E: Vec<E::Scalar>,
 }
 
-/// A type that holds a Relaxed R1CS instance
+/// A type that holds a Relaxed R1CS instance.
+///
+/// Its serde layout is stable, to persist folding state: the fields `comm_W`,
+/// `comm_E`, `X` and `u`, in this order, the commitments as the commitment
+/// engine serializes them. [`RelaxedR1CSInstance::to_bytes`] encodes it as
+/// digests are encoded (see [`crate::digest`]).
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct RelaxedR1CSInstance<E: Engine> {
@@ -76,6 +90,34 @@ pub struct RelaxedR1CSInstance<E: Engine> {
   pub(crate) u:      E::Scalar,
 }
 
+/// A constraint that a relaxed R1CS witness does not satisfy, as reported by
+/// [`RelaxedR1CSInstance::is_sat_debug`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatRow<E: Engine> {
+  /// the index of the constraint
+  pub row:      usize,
+  /// the error `(Az ∘ Bz - u·Cz)` the constraint has at this row
+  pub expected: E::Scalar,
+  /// the entry of the error vector `E` at this row
+  pub actual:   E::Scalar,
+}
+
+/// The outcome of [`RelaxedR1CSInstance::is_sat_debug`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SatReport<E: Engine> {
+  /// the failing constraints, in increasing order of rows
+  pub unsat_rows: Vec<UnsatRow<E>>,
+  /// whether `comm_W` is a commitment to `W`
+  pub comm_W_ok:  bool,
+  /// whether `comm_E` is a commitment to `E`
+  pub comm_E_ok:  bool,
+}
+
+impl<E: Engine> SatReport<E> {
+  /// Whether the witness satisfies the instance
+  pub fn is_sat(&self) -> bool { self.unsat_rows.is_empty() && self.comm_W_ok && self.comm_E_ok }
+}
+
 /// A type for functions that hints commitment key sizing by returning the floor
 /// of the number of required generators.
 pub type CommitmentKeyHint<E> = dyn Fn(&R1CSShape<E>) -> usize;
@@ -98,6 +140,68 @@ pub fn commitment_key<E: Engine>(
   E::CE::setup(b"ck", size)
 }
 
+/// Produces the same key as [`commitment_key`], reporting its progress to
+/// `progress` under the stage `stage`
+pub fn commitment_key_with_progress<E: Engine>(
+  S: &R1CSShape<E>,
+  ck_floor: &CommitmentKeyHint<E>,
+  stage: &str,
+  progress: &dyn SetupProgress,
+) -> Result<CommitmentKey<E>, NovaError> {
+  let size = commitment_key_size(S, ck_floor);
+  E::CE::setup_with_progress(b"ck", size, stage, progress)
+}
+
+/// A compact description of a commitment key produced by [`commitment_key`].
+///
+/// For commitment engines whose keys are derived transparently from a label
+/// (e.g. Pedersen), the key can be re-derived deterministically from its size,
+/// and the digest pins the derived key. This lets a client obtain the
+/// secondary-curve key locally instead of downloading it alongside the
+/// primary setup.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CommitmentKeySpec<E: Engine> {
+  size:   usize,
+  digest: E::Scalar,
+}
+
+impl<E: Engine> CommitmentKeySpec<E> {
+  /// Describe the provided commitment key
+  pub fn new(ck: &CommitmentKey<E>) -> Result<Self, NovaError> {
+    Ok(Self { size: ck.length(), digest: commitment_key_digest::<E>(ck)? })
+  }
+
+  /// The number of generators of the described key
+  pub fn size(&self) -> usize { self.size }
+
+  /// The digest of the described key
+  pub fn digest(&self) -> E::Scalar { self.digest }
+
+  /// Re-derives the described commitment key, checking it against the digest
+  pub fn derive(&self) -> Result<CommitmentKey<E>, NovaError> {
+    let ck = E::CE::setup(b"ck", self.size);
+    if ck.length() != self.size || commitment_key_digest::<E>(&ck)? != self.digest {
+      return Err(NovaError::DigestError);
+    }
+    Ok(ck)
+  }
+}
+
+/// A borrowed commitment key, so that it can be digested
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct CommitmentKeyRef<'a, E: Engine>(&'a CommitmentKey<E>);
+
+impl<E: Engine> SimpleDigestible for CommitmentKeyRef<'_, E> {}
+
+/// Computes the digest of a commitment key
+pub fn commitment_key_digest<E: Engine>(ck: &CommitmentKey<E>) -> Result<E::Scalar, NovaError> {
+  DigestComputer::<E::Scalar, _>::new(&CommitmentKeyRef::<E>(ck))
+    .digest()
+    .map_err(|_| NovaError::DigestError)
+}
+
 /// Computes the number of generators required for the commitment key
 /// corresponding to shape `S`.
 pub fn commitment_key_size<E: Engine>(S: &R1CSShape<E>, ck_floor: &CommitmentKeyHint<E>) -> usize {
@@ -188,10 +292,14 @@ impl<E: Engine> R1CSShape<E> {
     let u = E::Scalar::random(&mut rng);
     let X = (0..self.num_io).map(|_| E::Scalar::random(&mut rng)).collect::<Vec<E::Scalar>>();
 
-    let E = self.compute_E(&W, &u, &X).unwrap();
+    // the random vectors are sampled to the sizes of the shape
+    #[allow(clippy::expect_used)]
+    let E = self.compute_E(&W, &u, &X).expect("the random vectors match the shape");
 
-    let (comm_W, comm_E) =
-      rayon::join(|| CE::<E>::commit(commitment_key, &W), || CE::<E>::commit(commitment_key, &E));
+    let (comm_W, comm_E) = parallel::join(
+      || CE::<E>::commit(commitment_key, &W),
+      || CE::<E>::commit(commitment_key, &E),
+    );
 
     let witness = RelaxedR1CSWitness { W, E };
     let instance = RelaxedR1CSInstance { comm_W, comm_E, u, X };
@@ -200,6 +308,8 @@ impl<E: Engine> R1CSShape<E> {
   }
 
   /// returned the digest of the `R1CSShape`
+  // serializing the shape into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E::Scalar {
     self
       .digest
@@ -227,9 +337,9 @@ impl<E: Engine> R1CSShape<E> {
       return Err(NovaError::InvalidWitnessLength);
     }
 
-    let (Az, (Bz, Cz)) = rayon::join(
+    let (Az, (Bz, Cz)) = parallel::join(
       || self.A.multiply_vec(z),
-      || rayon::join(|| self.B.multiply_vec(z), || self.C.multiply_vec(z)),
+      || parallel::join(|| self.B.multiply_vec(z), || self.C.multiply_vec(z)),
     );
 
     Ok((Az, Bz, Cz))
@@ -245,9 +355,9 @@ impl<E: Engine> R1CSShape<E> {
       return Err(NovaError::InvalidWitnessLength);
     }
 
-    let (Az, (Bz, Cz)) = rayon::join(
+    let (Az, (Bz, Cz)) = parallel::join(
       || self.A.multiply_witness(W, u, X),
-      || rayon::join(|| self.B.multiply_witness(W, u, X), || self.C.multiply_witness(W, u, X)),
+      || parallel::join(|| self.B.multiply_witness(W, u, X), || self.C.multiply_witness(W, u, X)),
     );
 
     Ok((Az, Bz, Cz))
@@ -266,10 +376,10 @@ impl<E: Engine> R1CSShape<E> {
 
     let R1CSResult { AZ, BZ, CZ } = ABC_Z;
 
-    rayon::join(
+    parallel::join(
       || self.A.multiply_witness_into(W, u, X, AZ),
       || {
-        rayon::join(
+        parallel::join(
           || self.B.multiply_witness_into(W, u, X, BZ),
           || self.C.multiply_witness_into(W, u, X, CZ),
         )
@@ -290,9 +400,9 @@ impl<E: Engine> R1CSShape<E> {
       return Err(NovaError::InvalidWitnessLength);
     }
 
-    let (Az, (Bz, Cz)) = rayon::join(
+    let (Az, (Bz, Cz)) = parallel::join(
       || self.A.multiply_witness(W, u, X),
-      || rayon::join(|| self.B.multiply_witness(W, u, X), || self.C.multiply_witness(W, u, X)),
+      || parallel::join(|| self.B.multiply_witness(W, u, X), || self.C.multiply_witness(W, u, X)),
     );
 
     let E = zip_with!((Az.into_par_iter(), Bz.into_par_iter(), Cz.into_par_iter()), |a, b, c| a
@@ -329,7 +439,7 @@ impl<E: Engine> R1CSShape<E> {
     // verify if comm_E and comm_W are commitments to E and W
     let res_comm = {
       let (comm_W, comm_E) =
-        rayon::join(|| CE::<E>::commit(ck, &W.W), || CE::<E>::commit(ck, &W.E));
+        parallel::join(|| CE::<E>::commit(ck, &W.W), || CE::<E>::commit(ck, &W.E));
       U.comm_W == comm_W && U.comm_E == comm_E
     };
 
@@ -493,7 +603,9 @@ impl<E: Engine> R1CSShape<E> {
       M.cols += num_vars_padded - self.num_vars;
 
       let ex = {
-        let nnz = M.indptr.last().unwrap();
+        // `indptr` always holds the leading zero
+        #[allow(clippy::expect_used)]
+        let nnz = M.indptr.last().expect("indptr has an entry per row and one more");
         vec![*nnz; num_cons_padded - self.num_cons]
       };
       M.indptr.extend(ex);
@@ -541,6 +653,18 @@ impl<E: Engine> R1CSWitness<E> {
   pub fn commit(&self, ck: &CommitmentKey<E>) -> Commitment<E> { CE::<E>::commit(ck, &self.W) }
 }
 
+impl<E: Engine> Zeroize for R1CSWitness<E> {
+  fn zeroize(&mut self) { wipe(&mut self.W) }
+}
+
+impl<E: Engine> Zeroize for R1CSResult<E> {
+  fn zeroize(&mut self) {
+    wipe(&mut self.AZ);
+    wipe(&mut self.BZ);
+    wipe(&mut self.CZ);
+  }
+}
+
 impl<E: Engine> R1CSInstance<E> {
   /// A method to create an instance object using constituent elements
   pub fn new(
@@ -566,6 +690,35 @@ impl<E: Engine> AbsorbInROTrait<E> for R1CSInstance<E> {
 }
 
 impl<E: Engine> RelaxedR1CSWitness<E> {
+  /// A witness of `S` made of its constituent vectors
+  pub fn new(S: &R1CSShape<E>, W: Vec<E::Scalar>, E: Vec<E::Scalar>) -> Result<Self, NovaError> {
+    if S.num_vars != W.len() || S.num_cons != E.len() {
+      Err(NovaError::InvalidWitnessLength)
+    } else {
+      Ok(Self { W, E })
+    }
+  }
+
+  /// The witness vector
+  pub fn W(&self) -> &[E::Scalar] { &self.W }
+
+  /// The error vector
+  pub fn E(&self) -> &[E::Scalar] { &self.E }
+
+  /// The encoding of the witness, in its serde layout
+  pub fn to_bytes(&self) -> Result<Vec<u8>, NovaError> {
+    encoding().serialize(self).map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+
+  /// Decodes a witness of `S` encoded by [`RelaxedR1CSWitness::to_bytes`]
+  pub fn from_bytes(S: &R1CSShape<E>, bytes: &[u8]) -> Result<Self, NovaError> {
+    let witness: Self = encoding()
+      .reject_trailing_bytes()
+      .deserialize(bytes)
+      .map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    Self::new(S, witness.W, witness.E)
+  }
+
   /// Produces a default `RelaxedR1CSWitness` given an `R1CSShape`
   pub fn default(S: &R1CSShape<E>) -> Self {
     Self { W: vec![E::Scalar::ZERO; S.num_vars], E: vec![E::Scalar::ZERO; S.num_cons] }
@@ -629,7 +782,55 @@ impl<E: Engine> RelaxedR1CSWitness<E> {
   }
 }
 
+impl<E: Engine> Zeroize for RelaxedR1CSWitness<E> {
+  fn zeroize(&mut self) {
+    wipe(&mut self.W);
+    wipe(&mut self.E);
+  }
+}
+
 impl<E: Engine> RelaxedR1CSInstance<E> {
+  /// An instance of `S` made of its constituent elements
+  pub fn new(
+    S: &R1CSShape<E>,
+    comm_W: Commitment<E>,
+    comm_E: Commitment<E>,
+    X: Vec<E::Scalar>,
+    u: E::Scalar,
+  ) -> Result<Self, NovaError> {
+    if S.num_io != X.len() {
+      Err(NovaError::InvalidInputLength)
+    } else {
+      Ok(Self { comm_W, comm_E, X, u })
+    }
+  }
+
+  /// The commitment to the witness vector
+  pub fn comm_W(&self) -> &Commitment<E> { &self.comm_W }
+
+  /// The commitment to the error vector
+  pub fn comm_E(&self) -> &Commitment<E> { &self.comm_E }
+
+  /// The public IO
+  pub fn X(&self) -> &[E::Scalar] { &self.X }
+
+  /// The relaxation scalar
+  pub fn u(&self) -> &E::Scalar { &self.u }
+
+  /// The encoding of the instance, in its serde layout
+  pub fn to_bytes(&self) -> Result<Vec<u8>, NovaError> {
+    encoding().serialize(self).map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+
+  /// Decodes an instance of `S` encoded by [`RelaxedR1CSInstance::to_bytes`]
+  pub fn from_bytes(S: &R1CSShape<E>, bytes: &[u8]) -> Result<Self, NovaError> {
+    let instance: Self = encoding()
+      .reject_trailing_bytes()
+      .deserialize(bytes)
+      .map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    Self::new(S, instance.comm_W, instance.comm_E, instance.X, instance.u)
+  }
+
   /// Produces a default `RelaxedR1CSInstance` given `R1CSGens` and
   /// `R1CSShape`
   pub fn default(_ck: &CommitmentKey<E>, S: &R1CSShape<E>) -> Self {
@@ -637,6 +838,34 @@ impl<E: Engine> RelaxedR1CSInstance<E> {
     Self { comm_W, comm_E, u: E::Scalar::ZERO, X: vec![E::Scalar::ZERO; S.num_io] }
   }
 
+  /// Checks if the instance is satisfiable given a witness and its shape,
+  /// like [`R1CSShape::is_sat_relaxed`], but reports every failing
+  /// constraint and commitment instead of stopping at the first one. This
+  /// is meant for debugging, e.g. a bad cross-term computation.
+  pub fn is_sat_debug(
+    &self,
+    ck: &CommitmentKey<E>,
+    S: &R1CSShape<E>,
+    W: &RelaxedR1CSWitness<E>,
+  ) -> Result<SatReport<E>, NovaError> {
+    if W.E.len() != S.num_cons {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+
+    let E = S.compute_E(&W.W, &self.u, &self.X)?;
+    let unsat_rows = W
+      .E
+      .iter()
+      .zip(E)
+      .enumerate()
+      .filter(|(_, (actual, expected))| *actual != expected)
+      .map(|(row, (actual, expected))| UnsatRow { row, expected, actual: *actual })
+      .collect();
+
+    let (comm_W, comm_E) = W.commit(ck);
+    Ok(SatReport { unsat_rows, comm_W_ok: self.comm_W == comm_W, comm_E_ok: self.comm_E == comm_E })
+  }
+
   /// Initializes a new `RelaxedR1CSInstance` from an `R1CSInstance`
   pub fn from_r1cs_instance(
     _ck: &CommitmentKey<E>,
@@ -692,6 +921,11 @@ impl<E: Engine> RelaxedR1CSInstance<E> {
   }
 }
 
+/// The encoding of folding state, as for digests
+fn encoding() -> impl Options {
+  bincode::DefaultOptions::new().with_little_endian().with_fixint_encoding()
+}
+
 impl<E: Engine> TranscriptReprTrait<E::GE> for RelaxedR1CSInstance<E> {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     [
@@ -712,7 +946,10 @@ impl<E: Engine> AbsorbInROTrait<E> for RelaxedR1CSInstance<E> {
 
     // absorb each element of self.X in bignum format
     for x in &self.X {
-      let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
+      // a field element is narrower than its limbs
+      #[allow(clippy::expect_used)]
+      let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS)
+        .expect("a field element fits in the limbs");
       for limb in limbs {
         ro.absorb(scalar_as_base::<E>(limb));
       }
@@ -731,9 +968,9 @@ pub(crate) mod tests {
 
   use super::*;
   use crate::{
-    provider::{Bn256EngineIPA, Bn256EngineKZG},
+    provider::{Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine},
     r1cs::sparse::SparseMatrix,
-    traits::Engine,
+    traits::{snark::default_ck_hint, Engine},
   };
 
   pub(crate) fn tiny_r1cs<E: Engine>(num_vars: usize) -> R1CSShape<E> {
@@ -830,4 +1067,97 @@ pub(crate) mod tests {
 
   #[test]
   fn test_random_r1cs() { test_random_r1cs_with::<Bn256EngineIPA>(); }
+
+  fn test_commitment_key_spec_with<E: Engine>() {
+    let S = tiny_r1cs::<E>(4);
+    let ck = commitment_key(&S, &*default_ck_hint());
+
+    let spec = CommitmentKeySpec::<E>::new(&ck).unwrap();
+    assert_eq!(spec.size(), ck.length());
+    assert_eq!(spec.derive().unwrap(), ck);
+
+    // a spec carrying the wrong digest is rejected
+    let bad_spec =
+      CommitmentKeySpec::<E> { size: spec.size(), digest: spec.digest() + E::Scalar::ONE };
+    assert_eq!(bad_spec.derive(), Err(NovaError::DigestError));
+  }
+
+  #[test]
+  fn test_commitment_key_spec() { test_commitment_key_spec_with::<GrumpkinEngine>(); }
+
+  fn test_is_sat_debug_with<E: Engine>() {
+    let S = tiny_r1cs::<E>(3);
+    let ck = commitment_key(&S, &*default_ck_hint());
+
+    // x = 2, y = x^3 + x + 5
+    let W = R1CSWitness::new(&S, [4u64, 8, 10].map(E::Scalar::from).to_vec()).unwrap();
+    let X = [2u64, 15].map(E::Scalar::from).to_vec();
+    let U = R1CSInstance::new(&S, W.commit(&ck), X).unwrap();
+    let mut W = RelaxedR1CSWitness::from_r1cs_witness(&S, W);
+    let U = RelaxedR1CSInstance::from_r1cs_instance(&ck, &S, U);
+
+    let report = U.is_sat_debug(&ck, &S, &W).unwrap();
+    assert!(report.is_sat());
+    S.is_sat_relaxed(&ck, &U, &W).unwrap();
+
+    // every offending entry of the error vector is reported
+    W.E[1] = E::Scalar::ONE;
+    W.E[3] = E::Scalar::from(2);
+    let report = U.is_sat_debug(&ck, &S, &W).unwrap();
+    assert!(!report.is_sat());
+    assert_eq!(report.unsat_rows, vec![
+      UnsatRow { row: 1, expected: E::Scalar::ZERO, actual: E::Scalar::ONE },
+      UnsatRow { row: 3, expected: E::Scalar::ZERO, actual: E::Scalar::from(2) },
+    ]);
+    assert!(report.comm_W_ok);
+    assert!(!report.comm_E_ok);
+
+    W.E.pop();
+    assert_eq!(U.is_sat_debug(&ck, &S, &W), Err(NovaError::InvalidWitnessLength));
+  }
+
+  #[test]
+  fn test_is_sat_debug() { test_is_sat_debug_with::<Bn256EngineKZG>(); }
+
+  fn test_relaxed_r1cs_bytes_with<E: Engine>() {
+    let S = tiny_r1cs::<E>(3);
+    let ck = commitment_key(&S, &*default_ck_hint());
+
+    // a folding state of the cubic, `x = 2` folded into itself with `r = 3`
+    let w = R1CSWitness::new(&S, [4u64, 8, 10].map(E::Scalar::from).to_vec()).unwrap();
+    let X = [2u64, 15].map(E::Scalar::from).to_vec();
+    let u = R1CSInstance::new(&S, w.commit(&ck), X).unwrap();
+    let W = RelaxedR1CSWitness::from_r1cs_witness(&S, w.clone());
+    let U = RelaxedR1CSInstance::from_r1cs_instance(&ck, &S, u.clone());
+    let (T, comm_T) = S.commit_T(&ck, &U, &W, &u, &w).unwrap();
+    let r = E::Scalar::from(3);
+    let (W, U) = (W.fold(&w, &T, &r).unwrap(), U.fold(&u, &comm_T, &r));
+    S.is_sat_relaxed(&ck, &U, &W).unwrap();
+
+    // the state is rebuilt from its bytes, or from its parts
+    let (W_bytes, U_bytes) = (W.to_bytes().unwrap(), U.to_bytes().unwrap());
+    assert_eq!(RelaxedR1CSWitness::from_bytes(&S, &W_bytes), Ok(W.clone()));
+    assert_eq!(RelaxedR1CSInstance::from_bytes(&S, &U_bytes), Ok(U.clone()));
+    assert_eq!(RelaxedR1CSWitness::new(&S, W.W().to_vec(), W.E().to_vec()), Ok(W.clone()));
+    assert_eq!(
+      RelaxedR1CSInstance::new(&S, *U.comm_W(), *U.comm_E(), U.X().to_vec(), *U.u()),
+      Ok(U.clone())
+    );
+
+    // but not with trailing bytes, nor for another shape
+    assert!(RelaxedR1CSWitness::from_bytes(&S, &[W_bytes.as_slice(), &[0]].concat()).is_err());
+    assert!(RelaxedR1CSInstance::from_bytes(&S, &[U_bytes.as_slice(), &[0]].concat()).is_err());
+    let S_other = tiny_r1cs::<E>(4);
+    assert_eq!(
+      RelaxedR1CSWitness::from_bytes(&S_other, &W_bytes),
+      Err(NovaError::InvalidWitnessLength)
+    );
+    assert_eq!(
+      RelaxedR1CSInstance::new(&S, *U.comm_W(), *U.comm_E(), vec![], *U.u()),
+      Err(NovaError::InvalidInputLength)
+    );
+  }
+
+  #[test]
+  fn test_relaxed_r1cs_bytes() { test_relaxed_r1cs_bytes_with::<Bn256EngineKZG>(); }
 }
@@ -0,0 +1,280 @@
+//! Witnesses split into named segments, each with its own commitment.
+//!
+//! A [`WitnessLayout`] splits the witness of a step into consecutive named
+//! segments, e.g. a public table, the data of the step and randomness. A
+//! [`SegmentedCommitment`] commits to each segment under the commitment key of
+//! the whole witness, with the other segments zeroed, so that the commitments
+//! to the segments add up to the commitment `comm_W` of the witness: they are
+//! checked against an instance with [`SegmentedCommitment::check`], and fold
+//! jointly with it under the challenge `comm_W` folds under.
+//!
+//! A segment whose values are the same across steps commits to the same
+//! commitment, which [`SegmentedCommitment::commit_reusing`] reuses instead of
+//! committing to the segment again, and a segment is disclosed on its own with
+//! a [`SegmentOpening`], without disclosing the other segments. Commitments
+//! are binding but not hiding: a segment that must stay hidden should include
+//! randomness.
+use core::ops::Range;
+
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  r1cs::{R1CSInstance, R1CSWitness, RelaxedR1CSInstance},
+  traits::{commitment::CommitmentEngineTrait, Engine},
+  Commitment, CommitmentKey, CE,
+};
+
+/// A named segment of a witness
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+  /// the name of the segment
+  pub name: String,
+  /// the number of values in the segment
+  pub len:  usize,
+}
+
+/// The split of a witness into consecutive named segments
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessLayout {
+  segments: Vec<Segment>,
+}
+
+impl WitnessLayout {
+  /// The layout of `segments`, in order, whose names must be distinct
+  pub fn new(segments: Vec<Segment>) -> Result<Self, NovaError> {
+    for (i, segment) in segments.iter().enumerate() {
+      if segments[..i].iter().any(|s| s.name == segment.name) {
+        return Err(NovaError::InvalidSegment(segment.name.clone()));
+      }
+    }
+    Ok(Self { segments })
+  }
+
+  /// The segments of the layout
+  pub fn segments(&self) -> &[Segment] { &self.segments }
+
+  /// The length of the witnesses of the layout
+  pub fn len(&self) -> usize { self.segments.iter().map(|s| s.len).sum() }
+
+  /// Whether the layout has no values
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// The index of the segment `name`
+  pub fn index(&self, name: &str) -> Result<usize, NovaError> {
+    self.segments.iter().position(|s| s.name == name).ok_or(NovaError::InvalidSegment(name.into()))
+  }
+
+  /// The range of the witness the segment `name` spans
+  pub fn range(&self, name: &str) -> Result<Range<usize>, NovaError> {
+    Ok(self.range_at(self.index(name)?))
+  }
+
+  fn range_at(&self, index: usize) -> Range<usize> {
+    let start = self.segments[..index].iter().map(|s| s.len).sum::<usize>();
+    start..start + self.segments[index].len
+  }
+}
+
+/// The commitment to the segment at `range` of a witness with `values`,
+/// under the key of the whole witness
+fn commit_segment<E: Engine>(
+  ck: &CommitmentKey<E>,
+  range: Range<usize>,
+  values: &[E::Scalar],
+) -> Commitment<E> {
+  // the values after the segment are zero, and need not be committed to
+  let mut padded = vec![E::Scalar::ZERO; range.start];
+  padded.extend_from_slice(values);
+  CE::<E>::commit(ck, &padded)
+}
+
+/// The commitments to the segments of a witness, in the order of its layout
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SegmentedCommitment<E: Engine> {
+  comms: Vec<Commitment<E>>,
+}
+
+impl<E: Engine> SegmentedCommitment<E> {
+  /// Commits to the segments of the witness `W` of layout `layout`
+  pub fn commit(
+    ck: &CommitmentKey<E>,
+    layout: &WitnessLayout,
+    W: &[E::Scalar],
+  ) -> Result<Self, NovaError> {
+    Self::commit_reusing(ck, layout, W, &[])
+  }
+
+  /// Commits to the segments of the witness `W` of layout `layout`, but for
+  /// the segments of `reused`, whose commitments from a previous witness with
+  /// the same values are reused
+  pub fn commit_reusing(
+    ck: &CommitmentKey<E>,
+    layout: &WitnessLayout,
+    W: &[E::Scalar],
+    reused: &[(&str, Commitment<E>)],
+  ) -> Result<Self, NovaError> {
+    if W.len() != layout.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    let mut comms = vec![None; layout.segments.len()];
+    for (name, comm) in reused {
+      comms[layout.index(name)?] = Some(*comm);
+    }
+    let comms = comms
+      .into_iter()
+      .enumerate()
+      .map(|(i, comm)| {
+        let range = layout.range_at(i);
+        comm.unwrap_or_else(|| commit_segment::<E>(ck, range.clone(), &W[range]))
+      })
+      .collect();
+    Ok(Self { comms })
+  }
+
+  /// Commits to the segments of `witness`
+  pub fn commit_witness(
+    ck: &CommitmentKey<E>,
+    layout: &WitnessLayout,
+    witness: &R1CSWitness<E>,
+  ) -> Result<Self, NovaError> {
+    Self::commit(ck, layout, &witness.W)
+  }
+
+  /// The commitment to the segment `name`
+  pub fn get(&self, layout: &WitnessLayout, name: &str) -> Result<Commitment<E>, NovaError> {
+    self.comms.get(layout.index(name)?).copied().ok_or(NovaError::InvalidSegment(name.into()))
+  }
+
+  /// The commitment to the whole witness, the sum of the commitments to its
+  /// segments
+  pub fn joint(&self) -> Commitment<E> {
+    self.comms.iter().fold(Commitment::<E>::default(), |acc, comm| acc + *comm)
+  }
+
+  /// Checks that the segments add up to `comm_W`
+  pub fn check_commitment(&self, comm_W: &Commitment<E>) -> Result<(), NovaError> {
+    if self.joint() != *comm_W {
+      return Err(NovaError::InvalidSegment("joint".into()));
+    }
+    Ok(())
+  }
+
+  /// Checks that the segments add up to the witness commitment of `U`
+  pub fn check(&self, U: &R1CSInstance<E>) -> Result<(), NovaError> {
+    self.check_commitment(&U.comm_W)
+  }
+
+  /// Checks that the segments add up to the witness commitment of the
+  /// relaxed instance `U`
+  pub fn check_relaxed(&self, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+    self.check_commitment(&U.comm_W)
+  }
+
+  /// Folds `other` into the segments, under the challenge `r` the witness
+  /// commitments fold under, segment by segment
+  pub fn fold(&self, other: &Self, r: &E::Scalar) -> Result<Self, NovaError> {
+    if self.comms.len() != other.comms.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let comms = self.comms.iter().zip(&other.comms).map(|(c1, c2)| *c1 + *c2 * *r).collect();
+    Ok(Self { comms })
+  }
+
+  /// Opens the segment `name` of the witness `W`, disclosing its values
+  pub fn open(
+    layout: &WitnessLayout,
+    W: &[E::Scalar],
+    name: &str,
+  ) -> Result<SegmentOpening<E>, NovaError> {
+    if W.len() != layout.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    let values = W[layout.range(name)?].to_vec();
+    Ok(SegmentOpening { name: name.into(), values })
+  }
+
+  /// Verifies that `opening` opens its segment
+  pub fn verify_opening(
+    &self,
+    ck: &CommitmentKey<E>,
+    layout: &WitnessLayout,
+    opening: &SegmentOpening<E>,
+  ) -> Result<(), NovaError> {
+    let range = layout.range(&opening.name)?;
+    if opening.values.len() != range.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    if commit_segment::<E>(ck, range, &opening.values) != self.get(layout, &opening.name)? {
+      return Err(NovaError::InvalidSegment(opening.name.clone()));
+    }
+    Ok(())
+  }
+}
+
+/// The values of a segment, disclosed on their own
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SegmentOpening<E: Engine> {
+  /// the name of the segment
+  pub name:   String,
+  /// the values of the segment
+  pub values: Vec<E::Scalar>,
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::{Bn256EngineIPA, Bn256EngineKZG};
+
+  fn test_segments_with<E: Engine>() {
+    let segment = |name: &str, len| Segment { name: name.into(), len };
+    let layout =
+      WitnessLayout::new(vec![segment("table", 4), segment("step", 3), segment("randomness", 1)])
+        .unwrap();
+    assert_eq!(layout.range("step"), Ok(4..7));
+    let ck = CE::<E>::setup(b"segments", layout.len());
+
+    let random = || (0..layout.len()).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+    let (W1, mut W2) = (random(), random());
+    W2[..4].copy_from_slice(&W1[..4]);
+
+    // the segments add up to the commitment to the witness
+    let comms1 = SegmentedCommitment::<E>::commit(&ck, &layout, &W1).unwrap();
+    let comm_W1 = CE::<E>::commit(&ck, &W1);
+    assert_eq!(comms1.check_commitment(&comm_W1), Ok(()));
+
+    // a segment with the same values is reused
+    let table = comms1.get(&layout, "table").unwrap();
+    let comms2 =
+      SegmentedCommitment::<E>::commit_reusing(&ck, &layout, &W2, &[("table", table)]).unwrap();
+    assert_eq!(comms2, SegmentedCommitment::commit(&ck, &layout, &W2).unwrap());
+
+    // segments fold along the witnesses
+    let r = E::Scalar::random(OsRng);
+    let folded = W1.iter().zip(&W2).map(|(w1, w2)| *w1 + r * w2).collect::<Vec<_>>();
+    let comms = comms1.fold(&comms2, &r).unwrap();
+    assert_eq!(comms.check_commitment(&CE::<E>::commit(&ck, &folded)), Ok(()));
+
+    // and open on their own
+    let opening = SegmentedCommitment::<E>::open(&layout, &folded, "step").unwrap();
+    assert_eq!(opening.values, folded[4..7]);
+    assert_eq!(comms.verify_opening(&ck, &layout, &opening), Ok(()));
+    let mut wrong = opening.clone();
+    wrong.values[0] += E::Scalar::ONE;
+    assert!(comms.verify_opening(&ck, &layout, &wrong).is_err());
+
+    assert!(WitnessLayout::new(vec![segment("a", 1), segment("a", 2)]).is_err());
+    assert!(comms.get(&layout, "unknown").is_err());
+  }
+
+  #[test]
+  fn test_segments() {
+    test_segments_with::<Bn256EngineIPA>();
+    test_segments_with::<Bn256EngineKZG>();
+  }
+}
@@ -9,10 +9,11 @@ use std::{cmp::Ordering, collections::BTreeSet};
 use ff::PrimeField;
 use itertools::Itertools as _;
 use rand_core::{CryptoRng, RngCore};
-use rayon::prelude::*;
 use ref_cast::RefCast;
 use serde::{Deserialize, Serialize};
 
+use crate::parallel::prelude::*;
+
 /// CSR format sparse matrix, We follow the names used by scipy.
 /// Detailed explanation here: <https://stackoverflow.com/questions/52299420/scipy-csr-matrix-understand-indptr>
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -100,13 +101,20 @@ impl<F: PrimeField> SparseMatrix<F> {
   }
 
   /// Returns an iterator into the rows
+  // `windows(2)` yields slices of two
+  #[allow(clippy::expect_used)]
   pub fn iter_rows(&self) -> impl Iterator<Item = &RowData> {
-    self.indptr.windows(2).map(|ptrs| RowData::ref_cast(ptrs.try_into().unwrap()))
+    self.indptr.windows(2).map(|ptrs| RowData::ref_cast(ptrs.try_into().expect("windows of two")))
   }
 
   /// Returns a parallel iterator into the rows
+  // `par_windows(2)` yields slices of two
+  #[allow(clippy::expect_used)]
   pub fn par_iter_rows(&self) -> impl IndexedParallelIterator<Item = &RowData> {
-    self.indptr.par_windows(2).map(|ptrs| RowData::ref_cast(ptrs.try_into().unwrap()))
+    self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| RowData::ref_cast(ptrs.try_into().expect("windows of two")))
   }
 
   /// Retrieves the data for row slice [i..j] from `row`.
@@ -139,13 +147,15 @@ impl<F: PrimeField> SparseMatrix<F> {
     sink
   }
 
+  // `par_windows(2)` yields slices of two
+  #[allow(clippy::expect_used)]
   fn multiply_vec_into_unchecked(&self, vector: &[F], sink: &mut Vec<F>) {
     self
       .indptr
       .par_windows(2)
       .map(|ptrs| {
         self
-          .get_row_unchecked(ptrs.try_into().unwrap())
+          .get_row_unchecked(ptrs.try_into().expect("windows of two"))
           .map(|(val, col_idx)| *val * vector[*col_idx])
           .sum()
       })
@@ -182,37 +192,46 @@ impl<F: PrimeField> SparseMatrix<F> {
   /// Multiply by a witness representing a dense vector; uses rayon to
   /// parallelize. This does not check that the shape of the matrix/vector
   /// are compatible.
+  // `par_windows(2)` yields slices of two
+  #[allow(clippy::expect_used)]
   fn multiply_witness_into_unchecked(&self, W: &[F], u: &F, X: &[F], sink: &mut Vec<F>) {
     let num_vars = W.len();
     self
       .indptr
       .par_windows(2)
       .map(|ptrs| {
-        self.get_row_unchecked(ptrs.try_into().unwrap()).fold(F::ZERO, |acc, (val, col_idx)| {
-          let val = match col_idx.cmp(&num_vars) {
-            Ordering::Less => *val * W[*col_idx],
-            Ordering::Equal => *val * *u,
-            Ordering::Greater => *val * X[*col_idx - num_vars - 1],
-          };
-          acc + val
-        })
+        self.get_row_unchecked(ptrs.try_into().expect("windows of two")).fold(
+          F::ZERO,
+          |acc, (val, col_idx)| {
+            let val = match col_idx.cmp(&num_vars) {
+              Ordering::Less => *val * W[*col_idx],
+              Ordering::Equal => *val * *u,
+              Ordering::Greater => *val * X[*col_idx - num_vars - 1],
+            };
+            acc + val
+          },
+        )
       })
       .collect_into_vec(sink);
   }
 
   /// number of non-zero entries
-  pub fn len(&self) -> usize { *self.indptr.last().unwrap() }
+  // `indptr` always holds the leading zero
+  #[allow(clippy::expect_used)]
+  pub fn len(&self) -> usize { *self.indptr.last().expect("indptr is never empty") }
 
   /// empty matrix
   pub fn is_empty(&self) -> bool { self.len() == 0 }
 
   /// returns a custom iterator
+  // `indptr` always holds the leading zero
+  #[allow(clippy::expect_used)]
   pub fn iter(&self) -> Iter<'_, F> {
     let mut row = 0;
     while self.indptr[row + 1] == 0 {
       row += 1;
     }
-    Iter { matrix: self, row, i: 0, nnz: *self.indptr.last().unwrap() }
+    Iter { matrix: self, row, i: 0, nnz: *self.indptr.last().expect("indptr is never empty") }
   }
 
   pub fn num_rows(&self) -> usize { self.indptr.len() - 1 }
@@ -0,0 +1,199 @@
+//! The bytes of scalars, in an explicit byte order.
+//!
+//! [`PrimeField::to_repr`] leaves the byte order of a representation to its
+//! field: the fields of this crate are little-endian, whereas e.g. EVM
+//! verifiers read big-endian words. A [`ScalarBytes`] fixes the order in its
+//! type, [`ScalarBytesLe`] or [`ScalarBytesBe`], whatever the order of the
+//! field, and only converts back to a scalar from its canonical form, the
+//! bytes of an integer below the modulus.
+//!
+//! The scalars of this crate are serialized and absorbed in transcripts as
+//! their [`ScalarBytesLe`], so that a verifier re-implemented elsewhere reads
+//! the bytes of a scalar in that order.
+use core::{fmt, hash, marker::PhantomData};
+
+use ff::PrimeField;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+  errors::NovaError,
+  traits::{Group, TranscriptReprTrait},
+};
+
+/// The byte order of a [`ScalarBytes`]
+pub trait ByteOrder: Send + Sync + 'static {
+  /// Whether the most significant byte is first
+  const BIG_ENDIAN: bool;
+}
+
+/// The least significant byte first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LittleEndian;
+
+impl ByteOrder for LittleEndian {
+  const BIG_ENDIAN: bool = false;
+}
+
+/// The most significant byte first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigEndian;
+
+impl ByteOrder for BigEndian {
+  const BIG_ENDIAN: bool = true;
+}
+
+/// The bytes of a scalar of `F` in the order `O`, of the length of a
+/// representation of `F`
+pub struct ScalarBytes<F: PrimeField, O: ByteOrder> {
+  repr: F::Repr,
+  _p:   PhantomData<O>,
+}
+
+/// The little-endian bytes of a scalar
+pub type ScalarBytesLe<F> = ScalarBytes<F, LittleEndian>;
+
+/// The big-endian bytes of a scalar
+pub type ScalarBytesBe<F> = ScalarBytes<F, BigEndian>;
+
+/// Whether the representations of `F` are big-endian
+fn repr_is_big_endian<F: PrimeField>() -> bool { F::ONE.to_repr().as_ref()[0] != 1 }
+
+impl<F: PrimeField, O: ByteOrder> ScalarBytes<F, O> {
+  /// The bytes of `scalar`
+  pub fn new(scalar: &F) -> Self {
+    let mut repr = scalar.to_repr();
+    if O::BIG_ENDIAN != repr_is_big_endian::<F>() {
+      repr.as_mut().reverse();
+    }
+    Self { repr, _p: PhantomData }
+  }
+
+  /// The bytes `bytes`, canonical or not, which must be of the length of a
+  /// representation of `F`
+  pub fn from_slice(bytes: &[u8]) -> Result<Self, NovaError> {
+    let mut repr = F::Repr::default();
+    if bytes.len() != repr.as_ref().len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    repr.as_mut().copy_from_slice(bytes);
+    Ok(Self { repr, _p: PhantomData })
+  }
+
+  /// The scalar of the bytes, if they are canonical
+  pub fn to_scalar(&self) -> Result<F, NovaError> {
+    let mut repr = self.repr;
+    if O::BIG_ENDIAN != repr_is_big_endian::<F>() {
+      repr.as_mut().reverse();
+    }
+    Option::from(F::from_repr(repr)).ok_or(NovaError::NonCanonicalScalar)
+  }
+
+  /// Whether the bytes are those of an integer below the modulus
+  pub fn is_canonical(&self) -> bool { self.to_scalar().is_ok() }
+
+  /// The bytes
+  pub fn as_bytes(&self) -> &[u8] { self.repr.as_ref() }
+
+  /// The same bytes in the order `P`
+  pub fn into_order<P: ByteOrder>(self) -> ScalarBytes<F, P> {
+    let mut repr = self.repr;
+    if O::BIG_ENDIAN != P::BIG_ENDIAN {
+      repr.as_mut().reverse();
+    }
+    ScalarBytes { repr, _p: PhantomData }
+  }
+}
+
+impl<F: PrimeField, O: ByteOrder> AsRef<[u8]> for ScalarBytes<F, O> {
+  fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<F: PrimeField, O: ByteOrder> Clone for ScalarBytes<F, O> {
+  fn clone(&self) -> Self { *self }
+}
+
+impl<F: PrimeField, O: ByteOrder> Copy for ScalarBytes<F, O> {}
+
+impl<F: PrimeField, O: ByteOrder> PartialEq for ScalarBytes<F, O> {
+  fn eq(&self, other: &Self) -> bool { self.as_bytes() == other.as_bytes() }
+}
+
+impl<F: PrimeField, O: ByteOrder> Eq for ScalarBytes<F, O> {}
+
+impl<F: PrimeField, O: ByteOrder> hash::Hash for ScalarBytes<F, O> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) { self.as_bytes().hash(state) }
+}
+
+impl<F: PrimeField, O: ByteOrder> fmt::Debug for ScalarBytes<F, O> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let order = if O::BIG_ENDIAN { "be" } else { "le" };
+    write!(f, "ScalarBytes({order}, 0x")?;
+    self.as_bytes().iter().try_for_each(|b| write!(f, "{b:02x}"))?;
+    write!(f, ")")
+  }
+}
+
+impl<F: PrimeField, O: ByteOrder> From<&F> for ScalarBytes<F, O> {
+  fn from(scalar: &F) -> Self { Self::new(scalar) }
+}
+
+/// The bytes, as a sequence of bytes
+impl<F: PrimeField, O: ByteOrder> Serialize for ScalarBytes<F, O> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.as_bytes().serialize(serializer)
+  }
+}
+
+/// Only canonical bytes deserialize
+impl<'de, F: PrimeField, O: ByteOrder> Deserialize<'de> for ScalarBytes<F, O> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let bytes = Vec::<u8>::deserialize(deserializer)?;
+    let scalar_bytes = Self::from_slice(&bytes)
+      .map_err(|_| D::Error::invalid_length(bytes.len(), &"the bytes of a scalar"))?;
+    if !scalar_bytes.is_canonical() {
+      return Err(D::Error::custom("non-canonical scalar"));
+    }
+    Ok(scalar_bytes)
+  }
+}
+
+impl<G: Group, F: PrimeField, O: ByteOrder> TranscriptReprTrait<G> for ScalarBytes<F, O> {
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.as_bytes().to_vec() }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+  use halo2curves::bn256::Fr;
+
+  use super::*;
+  use crate::provider::small_field::fields::BabyBear;
+
+  #[test]
+  fn test_scalar_bytes() {
+    let x = Fr::from(0x0102);
+    let le = ScalarBytesLe::new(&x);
+    let be = ScalarBytesBe::new(&x);
+    assert_eq!(&le.as_bytes()[..3], &[2, 1, 0]);
+    assert_eq!(&be.as_bytes()[29..], &[0, 1, 2]);
+    assert_eq!(le.into_order::<BigEndian>(), be);
+    assert_eq!(be.to_scalar(), Ok(x));
+    assert_eq!(ScalarBytesBe::<Fr>::from_slice(be.as_bytes()), Ok(be));
+    assert!(ScalarBytesBe::<Fr>::from_slice(&[0; 31]).is_err());
+
+    // the bytes of the modulus are not canonical, nor do they deserialize
+    let modulus = ScalarBytesLe::<Fr>::new(&-Fr::ONE);
+    let mut bytes = modulus.as_bytes().to_vec();
+    bytes[0] += 1;
+    let modulus = ScalarBytesLe::<Fr>::from_slice(&bytes).unwrap();
+    assert_eq!(modulus.to_scalar(), Err(NovaError::NonCanonicalScalar));
+    let json = serde_json::to_string(&modulus).unwrap();
+    assert!(serde_json::from_str::<ScalarBytesLe<Fr>>(&json).is_err());
+    let json = serde_json::to_string(&le).unwrap();
+    assert_eq!(serde_json::from_str::<ScalarBytesLe<Fr>>(&json).unwrap(), le);
+
+    // the order is that of the type, whatever the length of the field
+    let y = BabyBear::from(0x0102);
+    assert_eq!(ScalarBytesBe::new(&y).as_bytes(), &[0, 0, 0, 0, 0, 0, 1, 2]);
+  }
+}
@@ -0,0 +1,56 @@
+//! Wiping the secrets a prover handles once they are used.
+//!
+//! The witnesses of a client-side prover hold its secrets, e.g. the session
+//! keys and the plaintext responses of a TLS transcript, and so do the advice
+//! of its steps and its scratch values. The types holding them implement
+//! [`Zeroize`], which overwrites their scalars with zeros by writes the
+//! compiler does not elide, keeping their lengths, and the provers wipe their
+//! own scratch values once used. A `RecursiveSNARK` is wiped by its owner,
+//! with [`Zeroize::zeroize`], once it is compressed.
+//!
+//! The scalars of `ff` fields do not implement [`Zeroize`], so [`wipe`]
+//! overwrites vectors of them, along with their spare capacity, which holds
+//! what was left when they were truncated, e.g. by the rounds of a sum-check.
+use core::{
+  ptr,
+  sync::atomic::{compiler_fence, Ordering},
+};
+
+use ff::Field;
+pub use zeroize::Zeroize;
+
+/// Overwrites `scalars` and its spare capacity with zeros, keeping its
+/// length, by volatile writes that are not elided even if `scalars` is freed
+/// right after
+pub fn wipe<F: Field>(scalars: &mut Vec<F>) {
+  for scalar in scalars.iter_mut() {
+    // SAFETY: `scalar` is a valid and aligned reference, and `F: Copy` has no
+    // drop glue to skip
+    unsafe { ptr::write_volatile(scalar, F::ZERO) };
+  }
+  for slot in scalars.spare_capacity_mut() {
+    // SAFETY: as above, the slot being allocated but not initialized
+    unsafe { ptr::write_volatile(slot.as_mut_ptr(), F::ZERO) };
+  }
+  compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+  use halo2curves::bn256::Fr;
+
+  use super::*;
+
+  #[test]
+  fn test_wipe() {
+    let mut scalars = vec![Fr::ONE, Fr::from(2), -Fr::ONE];
+    scalars.truncate(2);
+    wipe(&mut scalars);
+    assert_eq!(scalars, vec![Fr::ZERO; 2]);
+
+    // the truncated scalar is wiped along
+    // SAFETY: the spare slot was initialized before the truncation, and wiped
+    let spare = unsafe { scalars.spare_capacity_mut()[0].assume_init() };
+    assert_eq!(spare, Fr::ZERO);
+  }
+}
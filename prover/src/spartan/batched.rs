@@ -11,7 +11,6 @@ use std::{iter, sync::Arc};
 use ff::Field;
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -26,6 +25,8 @@ use super::{
 use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
+  inspect::VerifyTrace,
+  parallel::{self, prelude::*},
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness, SparseMatrix},
   spartan::{
     polys::{multilinear::SparsePolynomial, power::PowPolynomial},
@@ -33,7 +34,10 @@ use crate::{
   },
   traits::{
     evaluation::EvaluationEngineTrait,
-    snark::{BatchedRelaxedR1CSSNARKTrait, DigestHelperTrait, RelaxedR1CSSNARKTrait},
+    snark::{
+      serialized_size, AppContext, BatchedRelaxedR1CSSNARKTrait, DigestHelperTrait, ProofSize,
+      RelaxedR1CSSNARKTrait,
+    },
     Engine, TranscriptEngineTrait,
   },
   zip_with, CommitmentKey,
@@ -87,7 +91,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> SimpleDigestible for VerifierKey<E
 
 impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierKey<E, EE> {
   /// Returns the digest of the verifier's key.
-  fn digest(&self) -> E::Scalar {
+  fn digest(&self) -> Result<E::Scalar, NovaError> {
     self
       .digest
       .get_or_try_init(|| {
@@ -95,7 +99,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
         dc.digest()
       })
       .cloned()
-      .expect("Failure to retrieve digest!")
+      .map_err(|_| NovaError::DigestError)
   }
 }
 
@@ -127,17 +131,18 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
 
     let vk = VerifierKey::new(S, vk_ee);
 
-    let pk = ProverKey { pk_ee, vk_digest: vk.digest() };
+    let pk = ProverKey { pk_ee, vk_digest: vk.digest()? };
 
     Ok((pk, vk))
   }
 
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: Vec<&R1CSShape<E>>,
     U: &[RelaxedR1CSInstance<E>],
     W: &[RelaxedR1CSWitness<E>],
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     let num_instances = U.len();
     // Pad shapes and ensure their sizes are correct
@@ -147,6 +152,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     let W = zip_with!(iter, (W, S), |w, s| w.pad(s)).collect::<Vec<RelaxedR1CSWitness<E>>>();
 
     let mut transcript = E::TE::new(b"BatchedRelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
     transcript.absorb(b"vk", &pk.vk_digest);
     if num_instances > 1 {
@@ -164,8 +170,8 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
 
     let (num_rounds_x, num_rounds_y): (Vec<_>, Vec<_>) =
       S.iter().map(|s| (s.num_cons.log_2(), s.num_vars.log_2() + 1)).unzip();
-    let num_rounds_x_max = *num_rounds_x.iter().max().unwrap();
-    let num_rounds_y_max = *num_rounds_y.iter().max().unwrap();
+    let num_rounds_x_max = *num_rounds_x.iter().max().ok_or(NovaError::InvalidInputLength)?;
+    let num_rounds_y_max = *num_rounds_y.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
     // Generate tau polynomial corresponding to eq(τ, τ², τ⁴ , …)
     // for a random challenge τ
@@ -227,7 +233,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
       par_iter,
       (claims_outer[1], claims_outer[2], polys_Cz, polys_E, r_x),
       |eval_Az, eval_Bz, poly_Cz, poly_E, r_x| {
-        let (eval_Cz, eval_E) = rayon::join(
+        let (eval_Cz, eval_E) = parallel::join(
           || MultilinearPolynomial::evaluate_with(poly_Cz, r_x),
           || MultilinearPolynomial::evaluate_with(poly_E, r_x),
         );
@@ -349,11 +355,17 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     })
   }
 
-  fn verify(&self, vk: &Self::VerifierKey, U: &[RelaxedR1CSInstance<E>]) -> Result<(), NovaError> {
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &[RelaxedR1CSInstance<E>],
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
     let num_instances = U.len();
     let mut transcript = E::TE::new(b"BatchedRelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
-    transcript.absorb(b"vk", &vk.digest());
+    transcript.absorb(b"vk", &vk.digest()?);
     if num_instances > 1 {
       let num_instances_field = E::Scalar::from(num_instances as u64);
       transcript.absorb(b"n", &num_instances_field);
@@ -361,11 +373,18 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     transcript.absorb(b"U", &U);
 
     let num_instances = U.len();
+    if vk.S.len() != num_instances
+      || self.claims_outer.len() != num_instances
+      || self.evals_E.len() != num_instances
+      || self.evals_W.len() != num_instances
+    {
+      return Err(NovaError::InvalidInputLength);
+    }
 
     let (num_rounds_x, num_rounds_y): (Vec<_>, Vec<_>) =
       vk.S.iter().map(|s| (s.num_cons.log_2(), s.num_vars.log_2() + 1)).unzip();
-    let num_rounds_x_max = *num_rounds_x.iter().max().unwrap();
-    let num_rounds_y_max = *num_rounds_y.iter().max().unwrap();
+    let num_rounds_x_max = *num_rounds_x.iter().max().ok_or(NovaError::InvalidInputLength)?;
+    let num_rounds_y_max = *num_rounds_y.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
     // Define τ polynomials of the appropriate size for each instance
     let tau = transcript.squeeze(b"t")?;
@@ -520,8 +539,13 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
       u_vec
     };
 
-    let batched_u =
-      batch_eval_verify(u_vec, &mut transcript, &self.sc_proof_batch, &self.evals_batch)?;
+    let batched_u = batch_eval_verify(
+      u_vec,
+      &mut transcript,
+      &self.sc_proof_batch,
+      &self.evals_batch,
+      &mut VerifyTrace::new(),
+    )?;
 
     // verify
     EE::verify(
@@ -561,20 +585,40 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E>
     <Self as BatchedRelaxedR1CSSNARKTrait<E>>::setup(ck, vec![S])
   }
 
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     let slice_U = slice::from_ref(U);
     let slice_W = slice::from_ref(W);
-    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::prove(ck, pk, vec![S], slice_U, slice_W)
+    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::prove_with_context(
+      ck,
+      pk,
+      vec![S],
+      slice_U,
+      slice_W,
+      context,
+    )
+  }
+
+  fn size_breakdown(&self) -> ProofSize {
+    let sumcheck = serialized_size(&self.sc_proof_outer)
+      + serialized_size(&self.sc_proof_inner)
+      + serialized_size(&self.sc_proof_batch);
+    ProofSize::of_snark(serialized_size(self), sumcheck, serialized_size(&self.eval_arg))
   }
 
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
     let slice = slice::from_ref(U);
-    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::verify(self, vk, slice)
+    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::verify_with_context(self, vk, slice, context)
   }
 }
@@ -6,12 +6,12 @@ use std::sync::Arc;
 use ff::Field;
 use itertools::{chain, Itertools as _};
 use once_cell::sync::*;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
+  parallel::{self, prelude::*},
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
   spartan::{
     math::Math,
@@ -37,7 +37,10 @@ use crate::{
   traits::{
     commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
     evaluation::EvaluationEngineTrait,
-    snark::{BatchedRelaxedR1CSSNARKTrait, DigestHelperTrait, RelaxedR1CSSNARKTrait},
+    snark::{
+      serialized_size, AppContext, BatchedRelaxedR1CSSNARKTrait, DigestHelperTrait, ProofSize,
+      RelaxedR1CSSNARKTrait,
+    },
     Engine, TranscriptEngineTrait,
   },
   zip_with, zip_with_for_each, Commitment, CommitmentKey, CompressedCommitment,
@@ -77,7 +80,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> SimpleDigestible for VerifierKey<E
 
 impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierKey<E, EE> {
   /// Returns the digest of the verifier's key
-  fn digest(&self) -> E::Scalar {
+  fn digest(&self) -> Result<E::Scalar, NovaError> {
     self
       .digest
       .get_or_try_init(|| {
@@ -85,7 +88,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
         dc.digest()
       })
       .cloned()
-      .expect("Failure to retrieve digest!")
+      .map_err(|_| NovaError::DigestError)
   }
 }
 
@@ -162,16 +165,17 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     let S_comm = S_repr.iter().map(|s_repr| s_repr.commit(&*ck)).collect::<Vec<_>>();
     let num_vars = S.iter().map(|s| s.num_vars).collect::<Vec<_>>();
     let vk = VerifierKey::new(num_vars, S_comm.clone(), vk_ee);
-    let pk = ProverKey { pk_ee, S_repr, S_comm, vk_digest: vk.digest() };
+    let pk = ProverKey { pk_ee, S_repr, S_comm, vk_digest: vk.digest()? };
     Ok((pk, vk))
   }
 
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: Vec<&R1CSShape<E>>,
     U: &[RelaxedR1CSInstance<E>],
     W: &[RelaxedR1CSWitness<E>],
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     // Pad shapes so that num_vars = num_cons = Nᵢ and check the sizes are correct
     let S = S.par_iter().map(|s| s.pad()).collect::<Vec<_>>();
@@ -179,7 +183,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     // N[i] = max(|Aᵢ|+|Bᵢ|+|Cᵢ|, 2*num_varsᵢ, num_consᵢ)
     let Nis = pk.S_repr.iter().map(|s| s.N).collect::<Vec<_>>();
     assert!(Nis.iter().all(|&Ni| Ni.is_power_of_two()));
-    let N_max = *Nis.iter().max().unwrap();
+    let N_max = *Nis.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
     let num_instances = U.len();
 
@@ -191,6 +195,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
 
     // Initialize transcript with vk || [Uᵢ]
     let mut transcript = E::TE::new(b"BatchedRelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
     transcript.absorb(b"vk", &pk.vk_digest);
     if num_instances > 1 {
       let num_instances_field = E::Scalar::from(num_instances as u64);
@@ -222,9 +227,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     let comms_Az_Bz_Cz = polys_Az_Bz_Cz
       .par_iter()
       .map(|[Az, Bz, Cz]| {
-        let (comm_Az, (comm_Bz, comm_Cz)) = rayon::join(
+        let (comm_Az, (comm_Bz, comm_Cz)) = parallel::join(
           || E::CE::commit(ck, Az),
-          || rayon::join(|| E::CE::commit(ck, Bz), || E::CE::commit(ck, Cz)),
+          || parallel::join(|| E::CE::commit(ck, Bz), || E::CE::commit(ck, Cz)),
         );
         [comm_Az, comm_Bz, comm_Cz]
       })
@@ -255,10 +260,10 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     let evals_Az_Bz_Cz_at_tau =
       zip_with!(par_iter, (polys_Az_Bz_Cz, coords_tau), |ABCs, tau_coords| {
         let [Az, Bz, Cz] = ABCs;
-        let (eval_Az, (eval_Bz, eval_Cz)) = rayon::join(
+        let (eval_Az, (eval_Bz, eval_Cz)) = parallel::join(
           || MultilinearPolynomial::evaluate_with(Az, tau_coords),
           || {
-            rayon::join(
+            parallel::join(
               || MultilinearPolynomial::evaluate_with(Bz, tau_coords),
               || MultilinearPolynomial::evaluate_with(Cz, tau_coords),
             )
@@ -332,7 +337,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
       .par_iter()
       .map(|[L_row, L_col]| {
         let (comm_L_row, comm_L_col) =
-          rayon::join(|| E::CE::commit(ck, L_row), || E::CE::commit(ck, L_col));
+          parallel::join(|| E::CE::commit(ck, L_row), || E::CE::commit(ck, L_col));
         [comm_L_row, comm_L_col]
       })
       .collect::<Vec<_>>();
@@ -665,17 +670,36 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARKTrait<E>
     })
   }
 
-  fn verify(&self, vk: &Self::VerifierKey, U: &[RelaxedR1CSInstance<E>]) -> Result<(), NovaError> {
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &[RelaxedR1CSInstance<E>],
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
     let num_instances = U.len();
     let num_claims_per_instance = 10;
 
+    if vk.S_comm.len() != num_instances
+      || self.comms_Az_Bz_Cz.len() != num_instances
+      || self.comms_L_row_col.len() != num_instances
+      || self.comms_mem_oracles.len() != num_instances
+      || self.evals_Az_Bz_Cz_at_tau.len() != num_instances
+      || self.evals_Az_Bz_Cz_W_E.len() != num_instances
+      || self.evals_L_row_col.len() != num_instances
+      || self.evals_mem_oracle.len() != num_instances
+      || self.evals_mem_preprocessed.len() != num_instances
+    {
+      return Err(NovaError::InvalidInputLength);
+    }
+
     // number of rounds of sum-check
     let num_rounds = vk.S_comm.iter().map(|s| s.N.log_2()).collect::<Vec<_>>();
-    let num_rounds_max = *num_rounds.iter().max().unwrap();
+    let num_rounds_max = *num_rounds.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
     let mut transcript = E::TE::new(b"BatchedRelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
-    transcript.absorb(b"vk", &vk.digest());
+    transcript.absorb(b"vk", &vk.digest()?);
     if num_instances > 1 {
       let num_instances_field = E::Scalar::from(num_instances as u64);
       transcript.absorb(b"n", &num_instances_field);
@@ -1088,15 +1112,15 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARK<E, EE> {
       // equal to the initial claim σ_j scaled by 2^{n-m-i-1}.
       let evals =
         zip_with!(par_iter, (mem, outer, inner, witness), |mem, outer, inner, witness| {
-          let ((evals_mem, evals_outer), (evals_inner, evals_witness)) = rayon::join(
+          let ((evals_mem, evals_outer), (evals_inner, evals_witness)) = parallel::join(
             || {
-              rayon::join(
+              parallel::join(
                 || Self::get_evals(mem, remaining_variables),
                 || Self::get_evals(outer, remaining_variables),
               )
             },
             || {
-              rayon::join(
+              parallel::join(
                 || Self::get_evals(inner, remaining_variables),
                 || Self::get_evals(witness, remaining_variables),
               )
@@ -1118,12 +1142,8 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARK<E, EE> {
       let evals_combined_2 = (0..evals.len()).map(|i| evals[i][1] * coeffs[i]).sum();
       let evals_combined_3 = (0..evals.len()).map(|i| evals[i][2] * coeffs[i]).sum();
 
-      let evals = vec![
-        evals_combined_0,
-        running_claim - evals_combined_0,
-        evals_combined_2,
-        evals_combined_3,
-      ];
+      let evals =
+        [evals_combined_0, running_claim - evals_combined_0, evals_combined_2, evals_combined_3];
       // Coefficient representation of S(X_i)
       let poly = UniPoly::from_evals(&evals);
 
@@ -1141,15 +1161,15 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARK<E, EE> {
         par_iter_mut,
         (mem, outer, inner, witness),
         |mem, outer, inner, witness| {
-          rayon::join(
+          parallel::join(
             || {
-              rayon::join(
+              parallel::join(
                 || Self::bind(mem, remaining_variables, &r_i),
                 || Self::bind(outer, remaining_variables, &r_i),
               )
             },
             || {
-              rayon::join(
+              parallel::join(
                 || Self::bind(inner, remaining_variables, &r_i),
                 || Self::bind(witness, remaining_variables, &r_i),
               )
@@ -1180,15 +1200,13 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedRelaxedR1CSSNARK<E, EE> {
   /// n-i. If m < n-i, then the polynomials in the instance are not
   /// defined over X_i, so the univariate polynomial is constant and equal
   /// to 2^{n-m-i-1}*σ, where σ is the initial claim.
-  fn get_evals<T: SumcheckEngine<E>>(inst: &T, remaining_variables: usize) -> Vec<Vec<E::Scalar>> {
+  fn get_evals<T: SumcheckEngine<E>>(inst: &T, remaining_variables: usize) -> Vec<[E::Scalar; 3]> {
     let num_instance_variables = inst.size().log_2(); // m
     if num_instance_variables < remaining_variables {
-      let deg = inst.degree();
-
       // The evaluations at X_i = 0, 2, 3 are all equal to the scaled claim
       Self::scaled_claims(inst, remaining_variables - 1)
         .into_iter()
-        .map(|scaled_claim| vec![scaled_claim; deg])
+        .map(|scaled_claim| [scaled_claim; 3])
         .collect()
     } else {
       inst.evaluation_points()
@@ -1241,21 +1259,42 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E>
     <Self as BatchedRelaxedR1CSSNARKTrait<E>>::setup(ck, vec![S])
   }
 
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     let slice_U = slice::from_ref(U);
     let slice_W = slice::from_ref(W);
 
-    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::prove(ck, pk, vec![S], slice_U, slice_W)
+    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::prove_with_context(
+      ck,
+      pk,
+      vec![S],
+      slice_U,
+      slice_W,
+      context,
+    )
   }
 
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+  fn size_breakdown(&self) -> ProofSize {
+    ProofSize::of_snark(
+      serialized_size(self),
+      serialized_size(&self.sc),
+      serialized_size(&self.eval_arg),
+    )
+  }
+
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
     let slice = slice::from_ref(U);
-    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::verify(self, vk, slice)
+    <Self as BatchedRelaxedR1CSSNARKTrait<E>>::verify_with_context(self, vk, slice, context)
   }
 }
@@ -0,0 +1,205 @@
+//! Deferred openings: evaluation claims made along the steps of a
+//! computation, discharged at once when the computation is compressed.
+//!
+//! A step that needs the evaluation of a committed polynomial, e.g. of a
+//! table committed to once and looked up by every step, defers it to a
+//! [`DeferredOpenings`] accumulator instead of proving it on the spot: the
+//! step uses the evaluation, and the accumulator keeps the claim and its
+//! polynomial. [`DeferredOpenings::prove`] then reduces all the claims, of
+//! polynomials of any sizes and at any points, to one claim at a single point
+//! with the batched sum-check of the compressing SNARKs, over a random linear
+//! combination of the claims, and proves that claim with one evaluation
+//! argument of `EE`. The steps do no work of the evaluation engine, and the
+//! [`BatchedOpening`] is verified once, against the claims.
+//!
+//! The claims are public, and absorbed before the combination is drawn: the
+//! application binds them to its proof, e.g. by carrying the commitments and
+//! the evaluations in the outputs of its steps. The compressed prover
+//! discharges them with
+//! [`CompressedSNARK::prove_with_deferred`](crate::CompressedSNARK::prove_with_deferred),
+//! in a transcript bound to the compressed proof, and the verifier checks them
+//! with
+//! [`CompressedSNARK::verify_with_deferred`](crate::CompressedSNARK::verify_with_deferred).
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  errors::NovaError,
+  inspect::VerifyTrace,
+  spartan::{
+    polys::multilinear::MultilinearPolynomial,
+    snark::{batch_eval_reduce, batch_eval_verify},
+    sumcheck::SumcheckProof,
+    PolyEvalInstance, PolyEvalWitness,
+  },
+  traits::{evaluation::EvaluationEngineTrait, Engine, TranscriptEngineTrait},
+  Commitment, CommitmentKey,
+};
+
+/// A claim that the polynomial committed to in `comm` evaluates to `eval` at
+/// `point`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OpeningClaim<E: Engine> {
+  /// the commitment to the polynomial
+  pub comm:  Commitment<E>,
+  /// the point of the evaluation
+  pub point: Vec<E::Scalar>,
+  /// the evaluation
+  pub eval:  E::Scalar,
+}
+
+/// The evaluation claims deferred by the steps of a computation, with their
+/// polynomials, kept by the prover
+#[derive(Clone, Debug)]
+pub struct DeferredOpenings<E: Engine> {
+  claims: Vec<OpeningClaim<E>>,
+  polys:  Vec<Vec<E::Scalar>>,
+}
+
+impl<E: Engine> Default for DeferredOpenings<E> {
+  fn default() -> Self { Self { claims: Vec::new(), polys: Vec::new() } }
+}
+
+/// The claims absorbed into `transcript`, and their instances
+fn absorb_claims<E: Engine>(
+  claims: &[OpeningClaim<E>],
+  transcript: &mut E::TE,
+) -> Vec<PolyEvalInstance<E>> {
+  claims
+    .iter()
+    .map(|claim| {
+      transcript.absorb(b"c", &claim.comm);
+      transcript.absorb(b"x", &claim.point.as_slice());
+      transcript.absorb(b"e", &claim.eval);
+      PolyEvalInstance { c: claim.comm, x: claim.point.clone(), e: claim.eval }
+    })
+    .collect()
+}
+
+impl<E: Engine> DeferredOpenings<E> {
+  /// An accumulator without claims
+  pub fn new() -> Self { Self::default() }
+
+  /// Defers the evaluation at `point` of `poly`, of `2^point.len()`
+  /// coefficients, committed to in `comm`, and returns the evaluation
+  pub fn defer(
+    &mut self,
+    comm: Commitment<E>,
+    poly: Vec<E::Scalar>,
+    point: Vec<E::Scalar>,
+  ) -> Result<E::Scalar, NovaError> {
+    if point.len() >= usize::BITS as usize || poly.len() != 1 << point.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+    self.claims.push(OpeningClaim { comm, point, eval });
+    self.polys.push(poly);
+    Ok(eval)
+  }
+
+  /// The claims deferred so far, for the verifier
+  pub fn claims(&self) -> &[OpeningClaim<E>] { &self.claims }
+
+  /// The number of claims deferred so far
+  pub fn len(&self) -> usize { self.claims.len() }
+
+  /// Whether no claim was deferred
+  pub fn is_empty(&self) -> bool { self.claims.is_empty() }
+
+  /// Proves all the claims with one evaluation argument, under the keys of
+  /// `EE`; fails without claims
+  pub fn prove<EE: EvaluationEngineTrait<E>>(
+    self,
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    transcript: &mut E::TE,
+  ) -> Result<BatchedOpening<E, EE>, NovaError> {
+    if self.is_empty() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let u_vec = absorb_claims(&self.claims, transcript);
+    let w_vec = self.polys.into_iter().map(|p| PolyEvalWitness { p }).collect::<Vec<_>>();
+    let (u, w, sc_proof, evals) = batch_eval_reduce(u_vec, &w_vec, transcript)?;
+    let eval_arg = EE::prove(ck, pk, transcript, &u.c, &w.p, &u.x, &u.e)?;
+    Ok(BatchedOpening { sc_proof, evals, eval_arg })
+  }
+}
+
+/// The proof of a batch of deferred claims: the sum-check reducing them to a
+/// single claim, the evaluations it ends with, and the evaluation argument of
+/// that claim
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchedOpening<E: Engine, EE: EvaluationEngineTrait<E>> {
+  sc_proof: SumcheckProof<E>,
+  evals:    Vec<E::Scalar>,
+  eval_arg: EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedOpening<E, EE> {
+  /// Verifies the proof of `claims`, in the order they were deferred
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    transcript: &mut E::TE,
+    claims: &[OpeningClaim<E>],
+  ) -> Result<(), NovaError> {
+    if claims.is_empty() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let u_vec = absorb_claims(claims, transcript);
+    let u =
+      batch_eval_verify(u_vec, transcript, &self.sc_proof, &self.evals, &mut VerifyTrace::new())?;
+    EE::verify(vk, transcript, &u.c, &u.x, &u.e, &self.eval_arg)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, Bn256EngineIPA},
+    traits::commitment::CommitmentEngineTrait,
+  };
+
+  type E = Bn256EngineIPA;
+  type EE = ipa_pc::EvaluationEngine<E>;
+  type F = <E as Engine>::Scalar;
+
+  fn random(n: usize) -> Vec<F> { (0..n).map(|_| F::random(&mut OsRng)).collect() }
+
+  #[test]
+  fn test_deferred_openings() {
+    let ck = Arc::new(<E as Engine>::CE::setup(b"test", 1 << 6));
+    let (pk, vk) = EE::setup(ck.clone());
+
+    // steps of polynomials of different sizes defer their evaluations
+    let mut deferred = DeferredOpenings::<E>::new();
+    for num_vars in [2, 6, 4, 6] {
+      let poly = random(1 << num_vars);
+      let comm = <E as Engine>::CE::commit(&ck, &poly);
+      deferred.defer(comm, poly, random(num_vars)).unwrap();
+    }
+    assert!(deferred.defer(Default::default(), random(3), random(2)).is_err());
+    assert_eq!(deferred.len(), 4);
+
+    let claims = deferred.claims().to_vec();
+    let proof = deferred.prove::<EE>(&ck, &pk, &mut <E as Engine>::TE::new(b"deferred")).unwrap();
+    proof.verify(&vk, &mut <E as Engine>::TE::new(b"deferred"), &claims).unwrap();
+
+    // a wrong evaluation, or a missing claim, does not verify
+    let mut wrong = claims.clone();
+    wrong[1].eval += F::ONE;
+    assert!(proof.verify(&vk, &mut <E as Engine>::TE::new(b"deferred"), &wrong).is_err());
+    assert!(proof.verify(&vk, &mut <E as Engine>::TE::new(b"deferred"), &claims[..3]).is_err());
+
+    assert!(DeferredOpenings::<E>::new()
+      .prove::<EE>(&ck, &pk, &mut <E as Engine>::TE::new(b"deferred"))
+      .is_err());
+  }
+}
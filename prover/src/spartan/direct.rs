@@ -0,0 +1,251 @@
+//! This module provides interfaces to directly prove a step circuit by using a
+//! Spartan SNARK, without any folding. In particular, it supports any SNARK
+//! that implements the `RelaxedR1CSSNARKTrait` trait (e.g., the SNARKs
+//! implemented in snark.rs or ppsnark.rs).
+//!
+//! The proved statement is `z_out = F(z_in)` for the step circuit `F`: both
+//! `z_in` and `z_out` are public, and the verifier checks the proof against
+//! them.
+use core::marker::PhantomData;
+use std::sync::Arc;
+
+use bellpepper_core::{num::AllocatedNum, Circuit, ConstraintSystem, SynthesisError};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  bellpepper::{
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+  },
+  errors::NovaError,
+  r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
+  supernova::StepCircuit,
+  traits::{snark::RelaxedR1CSSNARKTrait, Engine},
+  Commitment, CommitmentKey,
+};
+
+/// Wraps a step circuit to expose its inputs and outputs as public IO
+struct DirectCircuit<'a, E: Engine, SC: StepCircuit<E::Scalar>> {
+  z_i: Option<&'a [E::Scalar]>, // inputs to the circuit
+  sc:  &'a SC,                  // step circuit to be executed
+}
+
+impl<E: Engine, SC: StepCircuit<E::Scalar>> Circuit<E::Scalar> for DirectCircuit<'_, E, SC> {
+  fn synthesize<CS: ConstraintSystem<E::Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+    // obtain the arity information
+    let arity = self.sc.arity();
+
+    // Allocate zi. If inputs.zi is not provided, allocate default value 0
+    let zero = vec![E::Scalar::ZERO; arity];
+    let z_i = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("zi_{i}")), || Ok(self.z_i.unwrap_or(&zero)[i]))
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Scalar>>, _>>()?;
+
+    // there is no program counter to enforce outside of NIVC
+    let (_pc, z_i_plus_one) = self.sc.synthesize(&mut cs.namespace(|| "F"), None, &z_i)?;
+    if z_i_plus_one.len() != arity {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    // inputize both z_i and z_i_plus_one
+    for (j, input) in z_i.iter().enumerate() {
+      input.inputize(cs.namespace(|| format!("input {j}")))?;
+    }
+    for (j, output) in z_i_plus_one.iter().enumerate() {
+      output.inputize(cs.namespace(|| format!("output {j}")))?;
+    }
+
+    Ok(())
+  }
+}
+
+/// A type that holds the prover key for `DirectSNARK`
+pub struct ProverKey<E, S>
+where
+  E: Engine,
+  S: RelaxedR1CSSNARKTrait<E>, {
+  S:  R1CSShape<E>,
+  ck: Arc<CommitmentKey<E>>,
+  pk: S::ProverKey,
+}
+
+/// A type that holds the verifier key for `DirectSNARK`
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub struct VerifierKey<E, S>
+where
+  E: Engine,
+  S: RelaxedR1CSSNARKTrait<E>, {
+  arity: usize,
+  vk:    S::VerifierKey,
+}
+
+/// A SNARK proving a single step of a step circuit, without folding
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct DirectSNARK<E, S, C>
+where
+  E: Engine,
+  S: RelaxedR1CSSNARKTrait<E>,
+  C: StepCircuit<E::Scalar>, {
+  comm_W: Commitment<E>, // commitment to the witness
+  snark:  S,             // snark proving the witness is satisfying
+  _p:     PhantomData<C>,
+}
+
+impl<E, S, C> DirectSNARK<E, S, C>
+where
+  E: Engine,
+  S: RelaxedR1CSSNARKTrait<E>,
+  C: StepCircuit<E::Scalar>,
+{
+  /// Produces prover and verifier keys for the direct SNARK
+  pub fn setup(sc: &C) -> Result<(ProverKey<E, S>, VerifierKey<E, S>), NovaError> {
+    // construct a circuit that can be synthesized
+    let circuit: DirectCircuit<'_, E, C> = DirectCircuit { z_i: None, sc };
+
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    circuit.synthesize(&mut cs)?;
+
+    let (shape, ck) = cs.r1cs_shape_and_key(&*S::ck_floor());
+    let ck = Arc::new(ck);
+
+    let (pk, vk) = S::setup(ck.clone(), &shape)?;
+
+    let pk = ProverKey { S: shape, ck, pk };
+    let vk = VerifierKey { arity: sc.arity(), vk };
+
+    Ok((pk, vk))
+  }
+
+  /// Produces a proof that `sc` maps `z_i` to the returned outputs
+  pub fn prove(
+    pk: &ProverKey<E, S>,
+    sc: &C,
+    z_i: &[E::Scalar],
+  ) -> Result<(Self, Vec<E::Scalar>), NovaError> {
+    if z_i.len() != sc.arity() {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+
+    let mut cs = SatisfyingAssignment::<E>::new();
+    let circuit: DirectCircuit<'_, E, C> = DirectCircuit { z_i: Some(z_i), sc };
+    circuit.synthesize(&mut cs)?;
+
+    let (u, w) = cs.r1cs_instance_and_witness(&pk.S, &pk.ck)?;
+    let z_i_plus_one = u.X[sc.arity()..].to_vec();
+
+    // convert the instance and witness to relaxed form
+    let (u_relaxed, w_relaxed) = (
+      RelaxedR1CSInstance::from_r1cs_instance_unchecked(&u.comm_W, &u.X),
+      RelaxedR1CSWitness::from_r1cs_witness(&pk.S, w),
+    );
+
+    // prove the instance using Spartan
+    let snark = S::prove(&pk.ck, &pk.pk, &pk.S, &u_relaxed, &w_relaxed)?;
+
+    Ok((Self { comm_W: u.comm_W, snark, _p: PhantomData }, z_i_plus_one))
+  }
+
+  /// Verifies a proof that the step circuit maps `z_i` to `z_i_plus_one`
+  pub fn verify(
+    &self,
+    vk: &VerifierKey<E, S>,
+    z_i: &[E::Scalar],
+    z_i_plus_one: &[E::Scalar],
+  ) -> Result<(), NovaError> {
+    if z_i.len() != vk.arity || z_i_plus_one.len() != vk.arity {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    // construct an instance using the provided commitment to the witness and IO
+    let io = [z_i, z_i_plus_one].concat();
+    let u_relaxed = RelaxedR1CSInstance::from_r1cs_instance_unchecked(&self.comm_W, &io);
+
+    // verify the snark using the constructed instance
+    self.snark.verify(&vk.vk, &u_relaxed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use halo2curves::bn256::Bn256;
+
+  use super::*;
+  use crate::{
+    provider::{hyperkzg, ipa_pc, Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine},
+    spartan::{ppsnark, snark},
+    traits::evaluation::EvaluationEngineTrait,
+  };
+
+  #[derive(Clone, Debug, Default)]
+  struct CubicCircuit<F> {
+    _p: PhantomData<F>,
+  }
+
+  impl<F: ff::PrimeField> StepCircuit<F> for CubicCircuit<F> {
+    fn arity(&self) -> usize { 1 }
+
+    fn circuit_index(&self) -> usize { 0 }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      pc: Option<&AllocatedNum<F>>,
+      z: &[AllocatedNum<F>],
+    ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+      // Consider a cubic equation: `x^3 + x + 5 = y`, where `x` and `y` are
+      // respectively the input and output.
+      let x = &z[0];
+      let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+      let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+        Ok(x_cu.get_value().unwrap() + x.get_value().unwrap() + F::from(5u64))
+      })?;
+
+      cs.enforce(
+        || "y = x^3 + x + 5",
+        |lc| lc + x_cu.get_variable() + x.get_variable() + (F::from(5u64), CS::one()),
+        |lc| lc + CS::one(),
+        |lc| lc + y.get_variable(),
+      );
+
+      Ok((pc.cloned(), vec![y]))
+    }
+  }
+
+  fn test_direct_snark_with<E: Engine, S: RelaxedR1CSSNARKTrait<E>>() {
+    let circuit = CubicCircuit::default();
+    let (pk, vk) = DirectSNARK::<E, S, CubicCircuit<E::Scalar>>::setup(&circuit).unwrap();
+
+    let z_i = [E::Scalar::from(2u64)];
+    let (proof, z_i_plus_one) = DirectSNARK::prove(&pk, &circuit, &z_i).unwrap();
+    assert_eq!(z_i_plus_one, vec![E::Scalar::from(15u64)]);
+    proof.verify(&vk, &z_i, &z_i_plus_one).unwrap();
+
+    // a wrong claimed output, or IO of the wrong length, is rejected
+    assert!(proof.verify(&vk, &z_i, &[E::Scalar::from(16u64)]).is_err());
+    assert_eq!(proof.verify(&vk, &z_i, &[]), Err(NovaError::InvalidInputLength));
+    assert_eq!(
+      DirectSNARK::<E, S, _>::prove(&pk, &circuit, &[]).map(|_| ()),
+      Err(NovaError::InvalidInitialInputLength)
+    );
+  }
+
+  fn test_direct_snark_with_ee<E: Engine, EE: EvaluationEngineTrait<E>>() {
+    test_direct_snark_with::<E, snark::RelaxedR1CSSNARK<E, EE>>();
+    test_direct_snark_with::<E, ppsnark::RelaxedR1CSSNARK<E, EE>>();
+  }
+
+  #[test]
+  fn test_direct_snark() {
+    test_direct_snark_with_ee::<Bn256EngineIPA, ipa_pc::EvaluationEngine<Bn256EngineIPA>>();
+    test_direct_snark_with_ee::<GrumpkinEngine, ipa_pc::EvaluationEngine<GrumpkinEngine>>();
+    test_direct_snark_with_ee::<Bn256EngineKZG, hyperkzg::EvaluationEngine<Bn256, Bn256EngineKZG>>(
+    );
+  }
+}
@@ -0,0 +1,267 @@
+//! A verification path for embedded verifiers, e.g. in smart-contract VMs
+//! compiled from Rust, whose memory is bounded at compile time.
+//!
+//! A [`FixedSumcheckProof`] holds at most `MAX_ROUNDS` rounds of degree
+//! `DEGREE` in arrays, with a fixed-size encoding of
+//! [`FixedSumcheckProof::ENCODED_LEN`] bytes, and verifies without
+//! allocating. [`FixedTranscript`] is a keccak256 transcript that does not
+//! allocate either, and squeezes the challenges of a `Keccak256Transcript`
+//! with the default map to challenges, so that a proof made with the latter
+//! verifies on the stack. The group operations of commitment openings are
+//! left to the host, e.g. to the alt_bn128 syscalls of the VM.
+use core::{marker::PhantomData, mem::size_of};
+
+use ff::PrimeField;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+  constants::SecurityLevel,
+  errors::NovaError,
+  provider::keccak::{
+    compute_updated_state, initial_input, update_framed, ABSORB_TAG, DOM_SEP_TAG,
+    KECCAK256_STATE_SIZE, TRANSCRIPT_VERSION,
+  },
+  scalar_bytes::ScalarBytesLe,
+  spartan::{ExtSumcheckProof, SumcheckTranscript},
+  traits::{Engine, PrimeFieldExt},
+};
+
+/// A keccak256 transcript that does not allocate, squeezing the challenges
+/// of a `Keccak256Transcript` that absorbs the same scalars
+#[derive(Clone, Debug)]
+pub struct FixedTranscript<E: Engine> {
+  round:      u16,
+  state:      [u8; KECCAK256_STATE_SIZE],
+  transcript: Keccak256,
+  _p:         PhantomData<E>,
+}
+
+impl<E: Engine> FixedTranscript<E> {
+  /// Creates a transcript with label `label`
+  pub fn new(label: &'static [u8]) -> Self {
+    Self {
+      round:      0u16,
+      state:      compute_updated_state(initial_input::<TRANSCRIPT_VERSION>(label), &[]),
+      transcript: Keccak256::new(),
+      _p:         PhantomData,
+    }
+  }
+
+  /// Absorbs `scalars` under `label`
+  pub fn absorb(&mut self, label: &'static [u8], scalars: &[E::Scalar]) {
+    let len = scalars.len() * size_of::<<E::Scalar as PrimeField>::Repr>();
+    self.transcript.update(ABSORB_TAG);
+    update_framed(&mut self.transcript, label);
+    self.transcript.update((len as u64).to_le_bytes());
+    for scalar in scalars {
+      self.transcript.update(ScalarBytesLe::new(scalar));
+    }
+  }
+
+  /// Squeezes a challenge under `label`
+  pub fn squeeze(&mut self, label: &'static [u8]) -> Result<E::Scalar, NovaError> {
+    let mut input = self
+      .transcript
+      .clone()
+      .chain_update(DOM_SEP_TAG)
+      .chain_update(self.round.to_le_bytes())
+      .chain_update(self.state);
+    update_framed(&mut input, label);
+    let output = compute_updated_state(input, &[]);
+
+    self.round = self.round.checked_add(1).ok_or(NovaError::InternalTranscriptError)?;
+    self.state = output;
+    self.transcript = Keccak256::new();
+    Ok(E::Scalar::from_uniform(&output))
+  }
+}
+
+impl<E: Engine> SumcheckTranscript<E::Scalar, E::Scalar> for FixedTranscript<E> {
+  fn absorb_round(&mut self, evals: &[E::Scalar]) { self.absorb(b"p", evals) }
+
+  fn squeeze_challenge(&mut self) -> Result<E::Scalar, NovaError> { self.squeeze(b"c") }
+}
+
+/// A sum-check proof of at most `MAX_ROUNDS` rounds of degree `DEGREE`,
+/// laid out as an `ExtSumcheckProof` over `F`, in arrays
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedSumcheckProof<F: PrimeField, const MAX_ROUNDS: usize, const DEGREE: usize> {
+  num_rounds:  usize,
+  round_evals: [[F; DEGREE]; MAX_ROUNDS],
+}
+
+/// Evaluates at `r` the polynomial of a round of claim `claim`, of
+/// evaluations `evals` at `0, 2, 3, ..., DEGREE`
+// the interpolation points are distinct
+#[allow(clippy::expect_used)]
+fn interpolate<F: PrimeField, const DEGREE: usize>(evals: &[F; DEGREE], claim: F, r: F) -> F {
+  let y = |i: usize| match i {
+    0 => evals[0],
+    1 => claim - evals[0],
+    i => evals[i - 1],
+  };
+  (0..=DEGREE)
+    .map(|i| {
+      let x_i = F::from(i as u64);
+      let (num, den) = (0..=DEGREE).filter(|j| *j != i).fold((y(i), F::ONE), |(num, den), j| {
+        let x_j = F::from(j as u64);
+        (num * (r - x_j), den * (x_i - x_j))
+      });
+      num * Option::<F>::from(den.invert()).expect("the interpolation points are distinct")
+    })
+    .sum()
+}
+
+impl<F: PrimeField, const MAX_ROUNDS: usize, const DEGREE: usize>
+  FixedSumcheckProof<F, MAX_ROUNDS, DEGREE>
+{
+  /// The length of the encoding of a proof: the number of rounds as 4
+  /// little-endian bytes, followed by the representations of the
+  /// evaluations of all `MAX_ROUNDS` rounds, the unused ones being zero
+  pub const ENCODED_LEN: usize = 4 + MAX_ROUNDS * DEGREE * size_of::<F::Repr>();
+
+  /// Lays out `proof` in arrays, which fails if it has more than
+  /// `MAX_ROUNDS` rounds or rounds of another degree than `DEGREE`
+  pub fn from_proof(proof: &ExtSumcheckProof<F>) -> Result<Self, NovaError> {
+    let evals = proof.round_evals();
+    if evals.len() > MAX_ROUNDS || evals.iter().any(|evals| evals.len() != DEGREE) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut round_evals = [[F::ZERO; DEGREE]; MAX_ROUNDS];
+    for (fixed, evals) in round_evals.iter_mut().zip(evals) {
+      fixed.copy_from_slice(evals);
+    }
+    Ok(Self { num_rounds: evals.len(), round_evals })
+  }
+
+  /// Writes the encoding of the proof into `out`, of length `ENCODED_LEN`
+  pub fn encode(&self, out: &mut [u8]) -> Result<(), NovaError> {
+    if out.len() != Self::ENCODED_LEN {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (num_rounds, out) = out.split_at_mut(4);
+    num_rounds.copy_from_slice(&(self.num_rounds as u32).to_le_bytes());
+    for (chunk, eval) in
+      out.chunks_exact_mut(size_of::<F::Repr>()).zip(self.round_evals.iter().flatten())
+    {
+      chunk.copy_from_slice(ScalarBytesLe::new(eval).as_bytes());
+    }
+    Ok(())
+  }
+
+  /// Reads a proof from its encoding, which must be canonical: the
+  /// evaluations must be reduced, and those of the unused rounds zero
+  pub fn decode(bytes: &[u8]) -> Result<Self, NovaError> {
+    if bytes.len() != Self::ENCODED_LEN {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let (num_rounds, bytes) = bytes.split_at(4);
+    let num_rounds = num_rounds.try_into().map_err(|_| NovaError::InvalidInputLength)?;
+    let num_rounds = u32::from_le_bytes(num_rounds) as usize;
+    if num_rounds > MAX_ROUNDS {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+
+    let mut round_evals = [[F::ZERO; DEGREE]; MAX_ROUNDS];
+    for (eval, chunk) in
+      round_evals.iter_mut().flatten().zip(bytes.chunks_exact(size_of::<F::Repr>()))
+    {
+      *eval = ScalarBytesLe::<F>::from_slice(chunk)
+        .and_then(|bytes| bytes.to_scalar())
+        .map_err(|_| NovaError::InvalidSumcheckProof)?;
+    }
+    if round_evals[num_rounds..].iter().flatten().any(|eval| !bool::from(eval.is_zero())) {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+    Ok(Self { num_rounds, round_evals })
+  }
+
+  /// Verifies the proof of `claim` over `num_rounds` rounds, which must
+  /// reach `level`, without allocating. Returns the claimed evaluation of the
+  /// combination at the challenges, and the challenges, followed by zeros up
+  /// to `MAX_ROUNDS`.
+  pub fn verify<T: SumcheckTranscript<F, F>>(
+    &self,
+    claim: F,
+    num_rounds: usize,
+    level: SecurityLevel,
+    transcript: &mut T,
+  ) -> Result<(F, [F; MAX_ROUNDS]), NovaError> {
+    level.check_sumcheck::<F>(num_rounds, DEGREE)?;
+    if DEGREE == 0 || self.num_rounds != num_rounds {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+
+    let mut e = claim;
+    let mut r = [F::ZERO; MAX_ROUNDS];
+    for (evals, r_i) in self.round_evals[..num_rounds].iter().zip(r.iter_mut()) {
+      transcript.absorb_round(evals);
+      *r_i = transcript.squeeze_challenge()?;
+      e = interpolate(evals, e, *r_i);
+    }
+    Ok((e, r))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::{provider::Bn256EngineIPA, traits::TranscriptEngineTrait};
+
+  type E = Bn256EngineIPA;
+  type F = <E as Engine>::Scalar;
+
+  #[test]
+  fn test_fixed_sumcheck() {
+    let num_vars = 5;
+    let a = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let b = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let claim = a.iter().zip(&b).map(|(a, b)| *a * b).sum::<F>();
+    let comb = |v: &[F]| v[0] * v[1];
+    let level = <E as Engine>::SECURITY_LEVEL;
+
+    let (proof, r, final_evals) = ExtSumcheckProof::prove(
+      claim,
+      &[a.as_slice(), b.as_slice()],
+      2,
+      comb,
+      &mut <E as Engine>::TE::new(b"test"),
+    )
+    .unwrap();
+
+    // the proof round-trips through its fixed-size encoding
+    let fixed = FixedSumcheckProof::<F, 8, 2>::from_proof(&proof).unwrap();
+    let mut bytes = [0u8; FixedSumcheckProof::<F, 8, 2>::ENCODED_LEN];
+    fixed.encode(&mut bytes).unwrap();
+    let decoded = FixedSumcheckProof::<F, 8, 2>::decode(&bytes).unwrap();
+    assert_eq!(decoded, fixed);
+
+    // and verifies with the challenges of the keccak256 transcript
+    let (e, r_fixed) =
+      decoded.verify(claim, num_vars, level, &mut FixedTranscript::<E>::new(b"test")).unwrap();
+    assert_eq!(r_fixed[..num_vars], r[..]);
+    assert!(r_fixed[num_vars..].iter().all(|r_i| r_i.is_zero_vartime()));
+    assert_eq!(e, comb(&final_evals));
+
+    // the encoding must be canonical
+    let mut padded = bytes;
+    padded[FixedSumcheckProof::<F, 8, 2>::ENCODED_LEN - 1] = 1;
+    assert_eq!(
+      FixedSumcheckProof::<F, 8, 2>::decode(&padded),
+      Err(NovaError::InvalidSumcheckProof)
+    );
+
+    // and the proof must fit
+    assert_eq!(
+      FixedSumcheckProof::<F, 4, 2>::from_proof(&proof),
+      Err(NovaError::InvalidInputLength)
+    );
+    assert_eq!(
+      decoded.verify(claim, num_vars - 1, level, &mut FixedTranscript::<E>::new(b"test")),
+      Err(NovaError::InvalidSumcheckProof)
+    );
+  }
+}
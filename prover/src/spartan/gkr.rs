@@ -0,0 +1,486 @@
+//! A GKR prover for data-parallel layered circuits, e.g. many copies of a
+//! SHA-256 compression, whose intermediate wires are never committed to.
+//!
+//! A [`LayeredCircuit`] is a stack of layers of addition and multiplication
+//! gates, each reading two wires of the layer below, run on `N` copies of its
+//! inputs. Starting from a claim on the multilinear extension of the outputs
+//! of all copies, a [`GkrProof`] reduces a claim on a layer to a claim on the
+//! layer below with two sum-checks: one over the gates, reducing to claims on
+//! the wires each gate reads, and one over the wires of the layer below. The
+//! last claim is on the multilinear extension of the inputs, which is the only
+//! vector the prover commits to: a [`GkrArgument`] commits to it with the
+//! commitment engine of the R1CS layer and opens it at that claim with its
+//! evaluation engine, so that a Spartan proof can bind the same commitment.
+//!
+//! The values of copy `c` on wire `g` of a layer of width `w` sit at index
+//! `c w + g` of its table, both padded to powers of two. The verifier works in
+//! time linear in the size of a copy, and logarithmic in the number of copies.
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  constants::SecurityLevel,
+  errors::NovaError,
+  spartan::{
+    polys::{eq::EqPolynomial, multilinear::MultilinearPolynomial},
+    sumcheck::extension::{ExtSumcheckProof, SumcheckTranscript},
+  },
+  traits::{
+    commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine,
+    TranscriptEngineTrait,
+  },
+  Commitment, CommitmentKey,
+};
+
+/// A gate of a layer, on two wires of the layer below
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gate {
+  /// The sum of two wires
+  Add(usize, usize),
+  /// The product of two wires
+  Mul(usize, usize),
+}
+
+impl Gate {
+  fn wires(&self) -> (usize, usize) {
+    match *self {
+      Self::Add(l, r) | Self::Mul(l, r) => (l, r),
+    }
+  }
+}
+
+/// A layered circuit, run on many copies of its inputs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayeredCircuit {
+  num_inputs: usize,
+  layers:     Vec<Vec<Gate>>,
+}
+
+/// A claim that the multilinear extension of the inputs of all the copies of
+/// a circuit, padded, evaluates to `eval` at `point`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GkrInputClaim<F> {
+  /// The point of the claim
+  pub point: Vec<F>,
+  /// The claimed evaluation
+  pub eval:  F,
+}
+
+impl LayeredCircuit {
+  /// Creates a circuit on `num_inputs` inputs from its `layers`, the first
+  /// one reading the inputs and the last one being the outputs
+  pub fn new(num_inputs: usize, layers: Vec<Vec<Gate>>) -> Result<Self, NovaError> {
+    if num_inputs == 0 || layers.is_empty() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut width = num_inputs;
+    for layer in &layers {
+      let in_range = |wire: usize| wire < width;
+      if layer.is_empty()
+        || !layer.iter().all(|gate| in_range(gate.wires().0) && in_range(gate.wires().1))
+      {
+        return Err(NovaError::InvalidIndex);
+      }
+      width = layer.len();
+    }
+    Ok(Self { num_inputs, layers })
+  }
+
+  /// The padded widths of the inputs and of the layers
+  fn widths(&self) -> Vec<usize> {
+    core::iter::once(self.num_inputs)
+      .chain(self.layers.iter().map(Vec::len))
+      .map(usize::next_power_of_two)
+      .collect()
+  }
+
+  /// The number of outputs of a copy
+  pub fn num_outputs(&self) -> usize { self.layers.last().map_or(0, Vec::len) }
+
+  /// Flattens the vectors of `width` values of the copies into a table,
+  /// padding the copies and their number
+  fn flatten<F: PrimeField>(
+    copies: &[Vec<F>],
+    len: usize,
+    width: usize,
+  ) -> Result<Vec<F>, NovaError> {
+    if copies.is_empty() || copies.iter().any(|copy| copy.len() != len) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let mut table = vec![F::ZERO; copies.len().next_power_of_two() * width];
+    for (c, copy) in copies.iter().enumerate() {
+      table[c * width..c * width + len].copy_from_slice(copy);
+    }
+    Ok(table)
+  }
+
+  /// The table of the inputs of the copies, whose multilinear extension an
+  /// input claim is on
+  pub fn input_table<F: PrimeField>(&self, inputs: &[Vec<F>]) -> Result<Vec<F>, NovaError> {
+    Self::flatten(inputs, self.num_inputs, self.widths()[0])
+  }
+
+  /// The tables of the inputs and of every layer, on the copies of `inputs`
+  fn evaluate<F: PrimeField>(&self, inputs: &[Vec<F>]) -> Result<Vec<Vec<F>>, NovaError> {
+    let widths = self.widths();
+    let num_copies = inputs.len().next_power_of_two();
+    let mut tables = vec![self.input_table(inputs)?];
+    for (layer, (w_in, w_out)) in self.layers.iter().zip(widths.iter().zip(&widths[1..])) {
+      // the tables start with the inputs
+      #[allow(clippy::expect_used)]
+      let below = tables.last().expect("the inputs are a table");
+      let mut table = vec![F::ZERO; num_copies * w_out];
+      for c in 0..num_copies {
+        for (g, gate) in layer.iter().enumerate() {
+          let (l, r) = gate.wires();
+          let (l, r) = (below[c * w_in + l], below[c * w_in + r]);
+          table[c * w_out + g] = match gate {
+            Gate::Add(..) => l + r,
+            Gate::Mul(..) => l * r,
+          };
+        }
+      }
+      tables.push(table);
+    }
+    Ok(tables)
+  }
+
+  /// The evaluations, at the point of `eq_g`, of the selectors of the
+  /// additions and the multiplications of `layer`
+  fn selectors<F: PrimeField>(layer: &[Gate], eq_g: &[F]) -> (F, F) {
+    layer.iter().zip(eq_g).fold((F::ZERO, F::ZERO), |(add, mul), (gate, eq)| match gate {
+      Gate::Add(..) => (add + eq, mul),
+      Gate::Mul(..) => (add, mul + eq),
+    })
+  }
+
+  /// The evaluations of `rho wiring_left + wiring_right` of `layer` at the
+  /// point of `eq_g` for the gates, over the wires below
+  fn wiring<F: PrimeField>(layer: &[Gate], eq_g: &[F], rho: F, w_in: usize) -> Vec<F> {
+    let mut wiring = vec![F::ZERO; w_in];
+    for (gate, eq) in layer.iter().zip(eq_g) {
+      let (l, r) = gate.wires();
+      wiring[l] += rho * eq;
+      wiring[r] += eq;
+    }
+    wiring
+  }
+}
+
+/// The proof of a layer: the sum-check over its gates, the claims on the
+/// wires they read, and the sum-check over the wires of the layer below
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct GkrLayerProof<F> {
+  gates:      ExtSumcheckProof<F>,
+  eval_left:  F,
+  eval_right: F,
+  wiring:     ExtSumcheckProof<F>,
+  eval_below: F,
+}
+
+/// A proof that the copies of a layered circuit compute their outputs,
+/// reduced to a claim on their inputs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GkrProof<F> {
+  layers: Vec<GkrLayerProof<F>>,
+}
+
+/// The combination of the sum-check over the gates, of `eq`, `add`, `mul`,
+/// `left` and `right`
+fn gates_comb<F: PrimeField>(v: &[F]) -> F { v[0] * (v[1] * (v[3] + v[4]) + v[2] * v[3] * v[4]) }
+
+impl<F: PrimeField> GkrProof<F> {
+  /// Runs `circuit` on the copies of `inputs`, and proves the outputs it
+  /// returns, along with the claim on the inputs the proof reduces to
+  pub fn prove<T: SumcheckTranscript<F, F>>(
+    circuit: &LayeredCircuit,
+    inputs: &[Vec<F>],
+    transcript: &mut T,
+  ) -> Result<(Self, Vec<Vec<F>>, GkrInputClaim<F>), NovaError> {
+    let widths = circuit.widths();
+    let tables = circuit.evaluate(inputs)?;
+    let num_copies = inputs.len().next_power_of_two();
+    let copy_vars = num_copies.trailing_zeros() as usize;
+
+    // the tables start with the inputs
+    #[allow(clippy::expect_used)]
+    let top = tables.last().expect("the circuit has layers");
+    let w_top = widths[widths.len() - 1];
+    let outputs = (0..inputs.len())
+      .map(|c| top[c * w_top..c * w_top + circuit.num_outputs()].to_vec())
+      .collect();
+    transcript.absorb_round(top);
+    let mut point = (0..top.len().trailing_zeros())
+      .map(|_| transcript.squeeze_challenge())
+      .collect::<Result<Vec<_>, _>>()?;
+    let mut claim = MultilinearPolynomial::evaluate_with(top, &point);
+
+    let mut layers = Vec::with_capacity(circuit.layers.len());
+    for (i, layer) in circuit.layers.iter().enumerate().rev() {
+      let (w_in, w_out, below) = (widths[i], widths[i + 1], &tables[i]);
+
+      // the sum-check over the gates, in copy-major order
+      let eq = EqPolynomial::evals_from_points(&point);
+      let (mut add, mut mul, mut left, mut right) =
+        (vec![F::ZERO; eq.len()], vec![F::ZERO; eq.len()], vec![F::ZERO; eq.len()], vec![
+          F::ZERO;
+          eq.len()
+        ]);
+      for c in 0..num_copies {
+        for (g, gate) in layer.iter().enumerate() {
+          let (l, r) = gate.wires();
+          let j = c * w_out + g;
+          match gate {
+            Gate::Add(..) => add[j] = F::ONE,
+            Gate::Mul(..) => mul[j] = F::ONE,
+          }
+          (left[j], right[j]) = (below[c * w_in + l], below[c * w_in + r]);
+        }
+      }
+      let (gates, r_gates, evals) = ExtSumcheckProof::prove(
+        claim,
+        &[eq.as_slice(), &add, &mul, &left, &right],
+        3,
+        gates_comb,
+        transcript,
+      )?;
+      let (eval_left, eval_right) = (evals[3], evals[4]);
+      transcript.absorb_round(&[eval_left, eval_right]);
+      let rho = transcript.squeeze_challenge()?;
+
+      // the sum-check over the wires below of `rho left + right`
+      let (r_c, r_g) = r_gates.split_at(copy_vars);
+      let eq_c = EqPolynomial::evals_from_points(r_c);
+      let wiring = LayeredCircuit::wiring(layer, &EqPolynomial::evals_from_points(r_g), rho, w_in);
+      let weights =
+        eq_c.iter().flat_map(|eq| wiring.iter().map(move |w| *eq * w)).collect::<Vec<_>>();
+      let (wiring, r_wires, evals) = ExtSumcheckProof::prove(
+        rho * eval_left + eval_right,
+        &[weights.as_slice(), below],
+        2,
+        |v: &[F]| v[0] * v[1],
+        transcript,
+      )?;
+      let eval_below = evals[1];
+      transcript.absorb_round(&[eval_below]);
+
+      layers.push(GkrLayerProof { gates, eval_left, eval_right, wiring, eval_below });
+      (point, claim) = (r_wires, eval_below);
+    }
+    Ok((Self { layers }, outputs, GkrInputClaim { point, eval: claim }))
+  }
+
+  /// Verifies that the copies of `circuit` compute `outputs`, the sum-checks
+  /// reaching `level`, and returns the claim on their inputs to check
+  pub fn verify<T: SumcheckTranscript<F, F>>(
+    &self,
+    circuit: &LayeredCircuit,
+    outputs: &[Vec<F>],
+    level: SecurityLevel,
+    transcript: &mut T,
+  ) -> Result<GkrInputClaim<F>, NovaError> {
+    if self.layers.len() != circuit.layers.len() {
+      return Err(NovaError::ProofVerifyError);
+    }
+    let widths = circuit.widths();
+    let copy_vars = outputs.len().next_power_of_two().trailing_zeros() as usize;
+
+    let top = LayeredCircuit::flatten(outputs, circuit.num_outputs(), widths[widths.len() - 1])?;
+    transcript.absorb_round(&top);
+    let mut point = (0..top.len().trailing_zeros())
+      .map(|_| transcript.squeeze_challenge())
+      .collect::<Result<Vec<_>, _>>()?;
+    let mut claim = MultilinearPolynomial::evaluate_with(&top, &point);
+
+    for (proof, (i, layer)) in self.layers.iter().zip(circuit.layers.iter().enumerate().rev()) {
+      let (w_in, w_out) = (widths[i], widths[i + 1]);
+
+      let (e, r_gates) = proof.gates.verify::<F, T>(
+        claim,
+        copy_vars + w_out.trailing_zeros() as usize,
+        3,
+        level,
+        transcript,
+      )?;
+      let (r_c, r_g) = r_gates.split_at(copy_vars);
+      let eq_g = EqPolynomial::evals_from_points(r_g);
+      let (add, mul) = LayeredCircuit::selectors(layer, &eq_g);
+      let eq = EqPolynomial::new(point).evaluate(&r_gates);
+      if e != gates_comb(&[eq, add, mul, proof.eval_left, proof.eval_right]) {
+        return Err(NovaError::ProofVerifyError);
+      }
+      transcript.absorb_round(&[proof.eval_left, proof.eval_right]);
+      let rho = transcript.squeeze_challenge()?;
+
+      let (e, r_wires) = proof.wiring.verify::<F, T>(
+        rho * proof.eval_left + proof.eval_right,
+        copy_vars + w_in.trailing_zeros() as usize,
+        2,
+        level,
+        transcript,
+      )?;
+      let (r_wires_c, r_wires_x) = r_wires.split_at(copy_vars);
+      let eq_x = EqPolynomial::evals_from_points(r_wires_x);
+      let wiring = LayeredCircuit::wiring(layer, &eq_g, rho, w_in)
+        .iter()
+        .zip(&eq_x)
+        .map(|(w, eq)| *w * eq)
+        .sum::<F>();
+      let eq_c = EqPolynomial::new(r_c.to_vec()).evaluate(r_wires_c);
+      if e != eq_c * wiring * proof.eval_below {
+        return Err(NovaError::ProofVerifyError);
+      }
+      transcript.absorb_round(&[proof.eval_below]);
+
+      (point, claim) = (r_wires, proof.eval_below);
+    }
+    Ok(GkrInputClaim { point, eval: claim })
+  }
+}
+
+/// The transcript of an engine, as the transcript of its sum-checks
+struct EngineTranscript<'a, E: Engine>(&'a mut E::TE);
+
+impl<E: Engine> SumcheckTranscript<E::Scalar, E::Scalar> for EngineTranscript<'_, E> {
+  fn absorb_round(&mut self, evals: &[E::Scalar]) { self.0.absorb(b"p", &evals) }
+
+  fn squeeze_challenge(&mut self) -> Result<E::Scalar, NovaError> { self.0.squeeze(b"c") }
+}
+
+/// A [`GkrProof`] over the scalar field of `E`, along with the commitment to
+/// the inputs of the copies and the opening of that commitment at the claim
+/// the proof reduces to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct GkrArgument<E: Engine, EE: EvaluationEngineTrait<E>> {
+  comm_inputs: Commitment<E>,
+  proof:       GkrProof<E::Scalar>,
+  eval_arg:    EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> GkrArgument<E, EE> {
+  /// Runs `circuit` on the copies of `inputs`, and proves the outputs it
+  /// returns, committing only to the inputs with `ck`
+  pub fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    circuit: &LayeredCircuit,
+    inputs: &[Vec<E::Scalar>],
+    transcript: &mut E::TE,
+  ) -> Result<(Self, Vec<Vec<E::Scalar>>), NovaError> {
+    let table = circuit.input_table(inputs)?;
+    let comm_inputs = E::CE::commit(ck, &table);
+    transcript.absorb(b"C", &comm_inputs);
+
+    let (proof, outputs, claim) =
+      GkrProof::prove(circuit, inputs, &mut EngineTranscript::<E>(transcript))?;
+    let eval_arg = EE::prove(ck, pk, transcript, &comm_inputs, &table, &claim.point, &claim.eval)?;
+    Ok((Self { comm_inputs, proof, eval_arg }, outputs))
+  }
+
+  /// Verifies that the copies of `circuit` on the committed inputs compute
+  /// `outputs`
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    circuit: &LayeredCircuit,
+    outputs: &[Vec<E::Scalar>],
+    transcript: &mut E::TE,
+  ) -> Result<(), NovaError> {
+    transcript.absorb(b"C", &self.comm_inputs);
+    let claim = self.proof.verify(
+      circuit,
+      outputs,
+      E::SECURITY_LEVEL,
+      &mut EngineTranscript::<E>(transcript),
+    )?;
+    EE::verify(vk, transcript, &self.comm_inputs, &claim.point, &claim.eval, &self.eval_arg)
+  }
+
+  /// The commitment to the inputs of the copies, for the R1CS layer to bind
+  /// to its own witness
+  pub fn comm_inputs(&self) -> &Commitment<E> { &self.comm_inputs }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use ff::Field;
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::{ipa_pc, Bn256EngineIPA};
+
+  type E = Bn256EngineIPA;
+  type F = <E as Engine>::Scalar;
+  type EE = ipa_pc::EvaluationEngine<E>;
+
+  /// A toy compression of 4 words into 3, in three layers
+  fn compression() -> LayeredCircuit {
+    use Gate::{Add, Mul};
+    LayeredCircuit::new(4, vec![
+      vec![Mul(0, 1), Add(2, 3), Add(0, 2), Mul(1, 3), Mul(3, 3)],
+      vec![Mul(0, 1), Add(2, 4), Mul(3, 4)],
+      vec![Add(0, 1), Mul(1, 2), Add(2, 0)],
+    ])
+    .unwrap()
+  }
+
+  fn compress(x: &[F]) -> Vec<F> {
+    let l1 = [x[0] * x[1], x[2] + x[3], x[0] + x[2], x[1] * x[3], x[3] * x[3]];
+    let l2 = [l1[0] * l1[1], l1[2] + l1[4], l1[3] * l1[4]];
+    vec![l2[0] + l2[1], l2[1] * l2[2], l2[2] + l2[0]]
+  }
+
+  #[test]
+  fn test_gkr_proof() {
+    let circuit = compression();
+    let inputs =
+      (0..6).map(|_| (0..4).map(|_| F::random(OsRng)).collect::<Vec<_>>()).collect::<Vec<_>>();
+    let transcript = || <E as Engine>::TE::new(b"test");
+    let level = <E as Engine>::SECURITY_LEVEL;
+
+    let (proof, outputs, claim) = GkrProof::prove(&circuit, &inputs, &mut transcript()).unwrap();
+    assert_eq!(outputs, inputs.iter().map(|x| compress(x)).collect::<Vec<_>>());
+    let table = circuit.input_table(&inputs).unwrap();
+    assert_eq!(MultilinearPolynomial::evaluate_with(&table, &claim.point), claim.eval);
+    assert_eq!(proof.verify(&circuit, &outputs, level, &mut transcript()), Ok(claim));
+
+    // other outputs do not reduce to the claim of the prover
+    let mut wrong_outputs = outputs.clone();
+    wrong_outputs[5][1] += F::ONE;
+    assert!(proof
+      .verify(&circuit, &wrong_outputs, level, &mut transcript())
+      .map_or(true, |claim| MultilinearPolynomial::evaluate_with(&table, &claim.point)
+        != claim.eval));
+  }
+
+  #[test]
+  fn test_gkr_argument() {
+    let circuit = compression();
+    let inputs =
+      (0..8).map(|_| (0..4).map(|_| F::random(OsRng)).collect::<Vec<_>>()).collect::<Vec<_>>();
+    let ck = Arc::new(<E as Engine>::CE::setup(b"test", 32));
+    let (pk, vk) = EE::setup(ck.clone());
+
+    let (argument, outputs) = GkrArgument::<E, EE>::prove(
+      &ck,
+      &pk,
+      &circuit,
+      &inputs,
+      &mut <E as Engine>::TE::new(b"test"),
+    )
+    .unwrap();
+    assert_eq!(
+      argument.verify(&vk, &circuit, &outputs, &mut <E as Engine>::TE::new(b"test")),
+      Ok(())
+    );
+
+    let mut wrong_outputs = outputs.clone();
+    wrong_outputs[0][0] += F::ONE;
+    assert!(argument
+      .verify(&vk, &circuit, &wrong_outputs, &mut <E as Engine>::TE::new(b"test"))
+      .is_err());
+  }
+}
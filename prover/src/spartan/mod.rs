@@ -5,12 +5,22 @@
 //! the verifier's state small if the PCS provides a succinct verifier)
 //! We also provide direct.rs that allows proving a step circuit directly with
 //! either of the two SNARKs.
+//! In gkr.rs we provide a GKR prover for many copies of a layered circuit,
+//! whose inputs are committed to and opened like the witness of a SNARK.
+//! In embedded.rs we provide fixed-size sum-check proofs and transcripts, to
+//! verify without allocating.
+//! In deferred.rs we accumulate the evaluation claims of the steps of a
+//! computation, and prove them at once when it is compressed.
 //!
 //! In polynomial.rs we also provide foundational types and functions for
 //! manipulating multilinear polynomials.
 
 pub mod batched;
 pub mod batched_ppsnark;
+pub mod deferred;
+#[cfg(feature = "prove")] pub mod direct;
+pub mod embedded;
+pub mod gkr;
 #[macro_use]
 mod macros;
 pub(crate) mod math;
@@ -21,12 +31,13 @@ mod sumcheck;
 
 use ff::Field;
 use itertools::Itertools as _;
-use rayon::{iter::IntoParallelRefIterator, prelude::*};
-use rayon_scan::ScanParallelIterator as _;
 use ref_cast::RefCast;
+pub use sumcheck::extension::{ExtSumcheckProof, SumcheckTranscript};
 
 use crate::{
+  parallel::{self, prelude::*},
   r1cs::{R1CSShape, SparseMatrix},
+  secret::{wipe, Zeroize},
   traits::Engine,
   Commitment,
 };
@@ -47,6 +58,10 @@ struct PolyEvalWitness<E: Engine> {
   p: Vec<E::Scalar>, // polynomial
 }
 
+impl<E: Engine> Zeroize for PolyEvalWitness<E> {
+  fn zeroize(&mut self) { wipe(&mut self.p) }
+}
+
 impl<E: Engine> PolyEvalWitness<E> {
   /// Given [Pᵢ] and s, compute P = ∑ᵢ sⁱ⋅Pᵢ
   ///
@@ -58,7 +73,9 @@ impl<E: Engine> PolyEvalWitness<E> {
   fn batch_diff_size(W: &[&Self], s: E::Scalar) -> Self {
     let powers = powers(&s, W.len());
 
-    let size_max = W.iter().map(|w| w.p.len()).max().unwrap();
+    // the batch is never empty
+    #[allow(clippy::expect_used)]
+    let size_max = W.iter().map(|w| w.p.len()).max().expect("at least one polynomial");
     let p_vec = W.par_iter().map(|w| &w.p);
     // Scale the input polynomials by the power of s
     let p = zip_with!((p_vec, powers.par_iter()), |v, weight| {
@@ -165,14 +182,14 @@ fn compute_eval_table_sparse<E: Engine>(
     }
   };
 
-  let (A_evals, (B_evals, C_evals)) = rayon::join(
+  let (A_evals, (B_evals, C_evals)) = parallel::join(
     || {
       let mut A_evals: Vec<E::Scalar> = vec![E::Scalar::ZERO; 2 * S.num_vars];
       inner(&S.A, &mut A_evals);
       A_evals
     },
     || {
-      rayon::join(
+      parallel::join(
         || {
           let mut B_evals: Vec<E::Scalar> = vec![E::Scalar::ZERO; 2 * S.num_vars];
           inner(&S.B, &mut B_evals);
@@ -2,7 +2,10 @@
 //! evaluated based on binary input values.
 
 use ff::PrimeField;
-use rayon::prelude::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+
+use crate::parallel::prelude::{
+  IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
+};
 
 /// Represents the multilinear extension polynomial (MLE) of the equality
 /// polynomial $eq(x,e)$, denoted as $\tilde{eq}(x, e)$.
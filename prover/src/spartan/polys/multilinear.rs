@@ -9,12 +9,15 @@ use std::ops::{Add, Index};
 use ff::PrimeField;
 use itertools::Itertools as _;
 use rand_core::{CryptoRng, RngCore};
-use rayon::prelude::{
-  IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
-};
 use serde::{Deserialize, Serialize};
 
-use crate::spartan::{math::Math, polys::eq::EqPolynomial};
+use crate::{
+  parallel::prelude::{
+    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
+  },
+  secret::{wipe, Zeroize},
+  spartan::{math::Math, polys::eq::EqPolynomial},
+};
 
 /// A multilinear extension of a polynomial $Z(\cdot)$, denote it as
 /// $\tilde{Z}(x_1, ..., x_m)$ where the degree of each variable is at most one.
@@ -108,6 +111,10 @@ impl<Scalar: PrimeField> MultilinearPolynomial<Scalar> {
   }
 }
 
+impl<Scalar: PrimeField> Zeroize for MultilinearPolynomial<Scalar> {
+  fn zeroize(&mut self) { wipe(&mut self.Z) }
+}
+
 impl<Scalar: PrimeField> Index<usize> for MultilinearPolynomial<Scalar> {
   type Output = Scalar;
 
@@ -8,12 +8,13 @@ use std::{
 };
 
 use ff::PrimeField;
-use rayon::prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 use ref_cast::RefCast;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+  parallel::prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator},
   provider::util::iterators::DoubleEndedIteratorExt as _,
+  scalar_bytes::ScalarBytesLe,
   traits::{Group, TranscriptReprTrait},
 };
 
@@ -54,10 +55,10 @@ impl<Scalar: PrimeField> UniPoly<Scalar> {
       // Now we know that self.degree() >= divisor.degree();
       let mut quotient = vec![Scalar::ZERO; self.degree() - divisor.degree() + 1];
       let mut remainder: Self = self.clone();
-      // Can unwrap here because we know self is not zero.
-      let divisor_leading_inv = divisor.leading_coefficient().unwrap().invert().unwrap();
+      // fails if the divisor has leading zeros
+      let divisor_leading_inv = Option::<Scalar>::from(divisor.leading_coefficient()?.invert())?;
       while !remainder.is_zero() && remainder.degree() >= divisor.degree() {
-        let cur_q_coeff = *remainder.leading_coefficient().unwrap() * divisor_leading_inv;
+        let cur_q_coeff = *remainder.leading_coefficient()? * divisor_leading_inv;
         let cur_q_degree = remainder.degree() - divisor.degree();
         quotient[cur_q_degree] = cur_q_coeff;
 
@@ -118,7 +119,7 @@ impl<Scalar: PrimeField> UniPoly<Scalar> {
   pub fn from_evals(evals: &[Scalar]) -> Self {
     // we only support degree-2 or degree-3 univariate polynomials
     assert!(evals.len() == 3 || evals.len() == 4);
-    let two_inv = Scalar::from(2).invert().unwrap();
+    let two_inv = Scalar::TWO_INV;
     let coeffs = if evals.len() == 3 {
       // ax^2 + bx + c
       let c = evals[0];
@@ -127,7 +128,9 @@ impl<Scalar: PrimeField> UniPoly<Scalar> {
       vec![c, b, a]
     } else {
       // ax^3 + bx^2 + cx + d
-      let six_inv = Scalar::from(6).invert().unwrap();
+      // 6 is invertible in the large prime fields we support
+      #[allow(clippy::expect_used)]
+      let six_inv = Scalar::from(6).invert().expect("6 is invertible in a prime field");
 
       let d = evals[0];
       let a = six_inv
@@ -170,6 +173,9 @@ impl<Scalar: PrimeField> UniPoly<Scalar> {
 }
 
 impl<Scalar: PrimeField> CompressedUniPoly<Scalar> {
+  /// The number of coefficients of the polynomial, but its linear term
+  pub fn num_coeffs(&self) -> usize { self.coeffs_except_linear_term.len() }
+
   // we require eval(0) + eval(1) = hint, so we can solve for the linear term as:
   // linear_term = hint - 2 * constant_term - deg2 term - deg3 term
   pub fn decompress(&self, hint: &Scalar) -> UniPoly<Scalar> {
@@ -191,7 +197,7 @@ impl<Scalar: PrimeField> CompressedUniPoly<Scalar> {
 impl<G: Group> TranscriptReprTrait<G> for UniPoly<G::Scalar> {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     let coeffs = self.compress().coeffs_except_linear_term;
-    coeffs.iter().flat_map(|&t| t.to_repr().as_ref().to_vec()).collect::<Vec<u8>>()
+    coeffs.iter().flat_map(|t| ScalarBytesLe::new(t).as_bytes().to_vec()).collect::<Vec<u8>>()
   }
 }
 
@@ -5,19 +5,26 @@
 //! matrices. This is beneficial when using a polynomial commitment scheme in
 //! which the verifier's costs is succinct. This code includes experimental
 //! optimizations to reduce runtimes and proof sizes.
+//!
+//! It is a drop-in alternative to `snark::RelaxedR1CSSNARK` wherever a
+//! `RelaxedR1CSSNARKTrait` is expected, e.g. as the secondary SNARK of
+//! `supernova::snark::CompressedSNARK` or with `direct::DirectSNARK`, at the
+//! cost of a commitment key large enough to commit to the R1CS matrices (see
+//! `ck_floor`).
 use core::cmp::max;
 use std::sync::Arc;
 
 use ff::Field;
 use itertools::Itertools as _;
 use once_cell::sync::OnceCell;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::polys::{masked_eq::MaskedEqPolynomial, multilinear::SparsePolynomial};
 use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
+  parallel::{self, prelude::*},
+  profiling::Phase,
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
   spartan::{
     math::Math,
@@ -41,7 +48,7 @@ use crate::{
   traits::{
     commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
     evaluation::EvaluationEngineTrait,
-    snark::{DigestHelperTrait, RelaxedR1CSSNARKTrait},
+    snark::{serialized_size, AppContext, DigestHelperTrait, ProofSize, RelaxedR1CSSNARKTrait},
     Engine, TranscriptEngineTrait, TranscriptReprTrait,
   },
   zip_with, Commitment, CommitmentKey, CompressedCommitment,
@@ -160,7 +167,7 @@ impl<E: Engine> R1CSShapeSparkRepr<E> {
 
     // timestamp polynomials for row
     let (ts_row, ts_col) =
-      rayon::join(|| timestamp_calc(N, N, &row), || timestamp_calc(N, N, &col));
+      parallel::join(|| timestamp_calc(N, N, &row), || timestamp_calc(N, N, &col));
 
     // a routine to turn a vector of usize into a vector scalars
     let to_vec_scalar = |v: &[usize]| -> Vec<E::Scalar> {
@@ -364,24 +371,24 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARK<E, EE> {
     let mut cubic_polys: Vec<CompressedUniPoly<E::Scalar>> = Vec::new();
     let num_rounds = mem.size().log_2();
     for _ in 0..num_rounds {
-      let ((evals_mem, evals_outer), (evals_inner, evals_witness)) = rayon::join(
-        || rayon::join(|| mem.evaluation_points(), || outer.evaluation_points()),
-        || rayon::join(|| inner.evaluation_points(), || witness.evaluation_points()),
+      let ((evals_mem, evals_outer), (evals_inner, evals_witness)) = parallel::join(
+        || parallel::join(|| mem.evaluation_points(), || outer.evaluation_points()),
+        || parallel::join(|| inner.evaluation_points(), || witness.evaluation_points()),
       );
 
-      let evals: Vec<Vec<E::Scalar>> = evals_mem
+      let evals: Vec<[E::Scalar; 3]> = evals_mem
         .into_iter()
         .chain(evals_outer.into_iter())
         .chain(evals_inner.into_iter())
         .chain(evals_witness.into_iter())
-        .collect::<Vec<Vec<E::Scalar>>>();
+        .collect::<Vec<[E::Scalar; 3]>>();
       assert_eq!(evals.len(), claims.len());
 
       let evals_combined_0 = (0..evals.len()).map(|i| evals[i][0] * coeffs[i]).sum();
       let evals_combined_2 = (0..evals.len()).map(|i| evals[i][1] * coeffs[i]).sum();
       let evals_combined_3 = (0..evals.len()).map(|i| evals[i][2] * coeffs[i]).sum();
 
-      let evals = vec![evals_combined_0, e - evals_combined_0, evals_combined_2, evals_combined_3];
+      let evals = [evals_combined_0, e - evals_combined_0, evals_combined_2, evals_combined_3];
       let poly = UniPoly::from_evals(&evals);
 
       // append the prover's message to the transcript
@@ -391,9 +398,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARK<E, EE> {
       let r_i = transcript.squeeze(b"c")?;
       r.push(r_i);
 
-      let _ = rayon::join(
-        || rayon::join(|| mem.bound(&r_i), || outer.bound(&r_i)),
-        || rayon::join(|| inner.bound(&r_i), || witness.bound(&r_i)),
+      let _ = parallel::join(
+        || parallel::join(|| mem.bound(&r_i), || outer.bound(&r_i)),
+        || parallel::join(|| inner.bound(&r_i), || witness.bound(&r_i)),
       );
 
       e = poly.evaluate(&r_i);
@@ -421,7 +428,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> VerifierKey<E, EE> {
 }
 impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierKey<E, EE> {
   /// Returns the digest of the verifier's key
-  fn digest(&self) -> E::Scalar {
+  fn digest(&self) -> Result<E::Scalar, NovaError> {
     self
       .digest
       .get_or_try_init(|| {
@@ -429,7 +436,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
         dc.digest()
       })
       .cloned()
-      .expect("Failure to retrieve digest!")
+      .map_err(|_| NovaError::DigestError)
   }
 }
 
@@ -469,27 +476,30 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 
     let vk = VerifierKey::new(S.num_cons, S.num_vars, S_comm.clone(), vk_ee);
 
-    let pk = ProverKey { pk_ee, S_repr, S_comm, vk_digest: vk.digest() };
+    let pk = ProverKey { pk_ee, S_repr, S_comm, vk_digest: vk.digest()? };
 
     Ok((pk, vk))
   }
 
   /// produces a succinct proof of satisfiability of a `RelaxedR1CS` instance
   #[tracing::instrument(skip_all, name = "PPSNARK::prove")]
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     // pad the R1CSShape
+    let _phase = Phase::enter("PPSNARK::prove");
     let S = S.pad();
     // sanity check that R1CSShape has all required size characteristics
     assert!(S.is_regular_shape());
 
     let W = W.pad(&S); // pad the witness
     let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
     // append the verifier key (which includes commitment to R1CS matrices) and the
     // RelaxedR1CSInstance to the transcript
@@ -503,9 +513,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let (mut Az, mut Bz, mut Cz) = S.multiply_vec(&z)?;
 
     // commit to Az, Bz, Cz
-    let (comm_Az, (comm_Bz, comm_Cz)) = rayon::join(
+    let (comm_Az, (comm_Bz, comm_Cz)) = parallel::join(
       || E::CE::commit(ck, &Az),
-      || rayon::join(|| E::CE::commit(ck, &Bz), || E::CE::commit(ck, &Cz)),
+      || parallel::join(|| E::CE::commit(ck, &Bz), || E::CE::commit(ck, &Cz)),
     );
 
     transcript.absorb(b"c", &[comm_Az, comm_Bz, comm_Cz].as_slice());
@@ -539,7 +549,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     // L_col(i) = z(col(i)) for all i
     let (mem_row, mem_col, L_row, L_col) = pk.S_repr.evaluation_oracles(&S, &tau, &z);
     let (comm_L_row, comm_L_col) =
-      rayon::join(|| E::CE::commit(ck, &L_row), || E::CE::commit(ck, &L_col));
+      parallel::join(|| E::CE::commit(ck, &L_row), || E::CE::commit(ck, &L_col));
 
     // since all the three polynomials are opened at tau,
     // we can combine them into a single polynomial opened at tau
@@ -567,7 +577,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let gamma = transcript.squeeze(b"g")?;
     let r = transcript.squeeze(b"r")?;
 
-    let ((mut outer_sc_inst, mut inner_sc_inst), mem_res) = rayon::join(
+    let ((mut outer_sc_inst, mut inner_sc_inst), mem_res) = parallel::join(
       || {
         // a sum-check instance to prove the first claim
         let outer_sc_inst = OuterSumcheckInstance::new(
@@ -796,12 +806,26 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
   }
 
   /// verifies a proof of satisfiability of a `RelaxedR1CS` instance
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+  fn size_breakdown(&self) -> ProofSize {
+    ProofSize::of_snark(
+      serialized_size(self),
+      serialized_size(&self.sc),
+      serialized_size(&self.eval_arg),
+    )
+  }
+
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
     let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
     // append the verifier key (including commitment to R1CS matrices) and the
     // RelaxedR1CSInstance to the transcript
-    transcript.absorb(b"vk", &vk.digest());
+    transcript.absorb(b"vk", &vk.digest()?);
     transcript.absorb(b"U", U);
 
     let comm_Az = Commitment::<E>::decompress(&self.comm_Az)?;
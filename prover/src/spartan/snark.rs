@@ -9,13 +9,16 @@ use std::sync::Arc;
 use ff::Field;
 use itertools::Itertools as _;
 use once_cell::sync::OnceCell;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
+  inspect::{Check, VerifyTrace},
+  parallel::{self, prelude::*},
+  profiling::Phase,
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness, SparseMatrix},
+  secret::Zeroize,
   spartan::{
     compute_eval_table_sparse,
     polys::{
@@ -29,7 +32,7 @@ use crate::{
   },
   traits::{
     evaluation::EvaluationEngineTrait,
-    snark::{DigestHelperTrait, RelaxedR1CSSNARKTrait},
+    snark::{serialized_size, AppContext, DigestHelperTrait, ProofSize, RelaxedR1CSSNARKTrait},
     Engine, TranscriptEngineTrait,
   },
   CommitmentKey,
@@ -62,7 +65,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> VerifierKey<E, EE> {
 
 impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierKey<E, EE> {
   /// Returns the digest of the verifier's key.
-  fn digest(&self) -> E::Scalar {
+  fn digest(&self) -> Result<E::Scalar, NovaError> {
     self
       .digest
       .get_or_try_init(|| {
@@ -70,7 +73,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
         dc.digest()
       })
       .cloned()
-      .expect("Failure to retrieve digest!")
+      .map_err(|_| NovaError::DigestError)
   }
 }
 
@@ -111,27 +114,30 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 
     let vk: VerifierKey<E, EE> = VerifierKey::new(S, vk_ee);
 
-    let pk = ProverKey { pk_ee, vk_digest: vk.digest() };
+    let pk = ProverKey { pk_ee, vk_digest: vk.digest()? };
 
     Ok((pk, vk))
   }
 
   /// produces a succinct proof of satisfiability of a `RelaxedR1CS` instance
   #[tracing::instrument(skip_all, name = "SNARK::prove")]
-  fn prove(
+  fn prove_with_context(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError> {
     // pad the R1CSShape
+    let _phase = Phase::enter("SNARK::prove");
     let S = S.pad();
     // sanity check that R1CSShape has all required size characteristics
     assert!(S.is_regular_shape());
 
     let W = W.pad(&S); // pad the witness
     let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
     // append the digest of vk (which includes R1CS matrices) and the
     // RelaxedR1CSInstance to the transcript
@@ -142,8 +148,8 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let mut z = [W.W.clone(), vec![U.u], U.X.clone()].concat();
 
     let (num_rounds_x, num_rounds_y) = (
-      usize::try_from(S.num_cons.ilog2()).unwrap(),
-      (usize::try_from(S.num_vars.ilog2()).unwrap() + 1),
+      S.num_cons.checked_ilog2().ok_or(NovaError::InvalidInputLength)? as usize,
+      S.num_vars.checked_ilog2().ok_or(NovaError::InvalidInputLength)? as usize + 1,
     );
 
     // outer sum-check
@@ -187,6 +193,10 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 
     let claim_Cz = MultilinearPolynomial::evaluate_with_chis(poly_Cz.evaluations(), &chis_r_x);
     let eval_E = MultilinearPolynomial::evaluate_with_chis(&W.E, &chis_r_x);
+    // the products of the witness are wiped once used (see `crate::secret`)
+    for mut poly in [poly_Az, poly_Bz, poly_Cz, poly_uCz_E] {
+      poly.zeroize();
+    }
     transcript.absorb(b"claims_outer", &[claim_Az, claim_Bz, claim_Cz, eval_E].as_slice());
 
     // inner sum-check
@@ -207,9 +217,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
         .collect::<Vec<E::Scalar>>()
     };
 
-    let poly_z = {
+    let mut poly_z = {
       z.resize(S.num_vars * 2, E::Scalar::ZERO);
-      z
+      MultilinearPolynomial::new(z)
     };
 
     let comb_func = |poly_A_comp: &E::Scalar, poly_B_comp: &E::Scalar| -> E::Scalar {
@@ -219,10 +229,11 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       &claim_inner_joint,
       num_rounds_y,
       &mut MultilinearPolynomial::new(poly_ABC),
-      &mut MultilinearPolynomial::new(poly_z),
+      &mut poly_z,
       comb_func,
       &mut transcript,
     )?;
+    poly_z.zeroize();
 
     // Add additional claims about W and E polynomials to the list from CC
     // We will reduce a vector of claims of evaluations at different points into
@@ -234,7 +245,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     // the verifier can compute a commitment to the batched polynomial.
     let eval_W = MultilinearPolynomial::evaluate_with(&W.W, &r_y[1..]);
 
-    let w_vec = vec![PolyEvalWitness { p: W.W }, PolyEvalWitness { p: W.E }];
+    let mut w_vec = vec![PolyEvalWitness { p: W.W }, PolyEvalWitness { p: W.E }];
     let u_vec =
       vec![PolyEvalInstance { c: U.comm_W, x: r_y[1..].to_vec(), e: eval_W }, PolyEvalInstance {
         c: U.comm_E,
@@ -242,7 +253,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
         e: eval_E,
       }];
 
-    let (batched_u, batched_w, sc_proof_batch, claims_batch_left) =
+    let (batched_u, mut batched_w, sc_proof_batch, claims_batch_left) =
       batch_eval_reduce(u_vec, &w_vec, &mut transcript)?;
 
     let eval_arg = EE::prove(
@@ -254,6 +265,8 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       &batched_u.x,
       &batched_u.e,
     )?;
+    w_vec.iter_mut().for_each(Zeroize::zeroize);
+    batched_w.zeroize();
 
     Ok(Self {
       sc_proof_outer,
@@ -268,33 +281,64 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
   }
 
   /// verifies a proof of satisfiability of a `RelaxedR1CS` instance
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+  fn size_breakdown(&self) -> ProofSize {
+    let sumcheck = serialized_size(&self.sc_proof_outer)
+      + serialized_size(&self.sc_proof_inner)
+      + serialized_size(&self.sc_proof_batch);
+    ProofSize::of_snark(serialized_size(self), sumcheck, serialized_size(&self.eval_arg))
+  }
+
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError> {
+    self.verify_with_trace(vk, U, context, &mut VerifyTrace::new())
+  }
+
+  fn verify_with_trace(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+    trace: &mut VerifyTrace,
+  ) -> Result<(), NovaError> {
     let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    context.absorb_in::<E>(&mut transcript);
 
     // append the digest of R1CS matrices and the RelaxedR1CSInstance to the
     // transcript
-    transcript.absorb(b"vk", &vk.digest());
+    transcript.absorb(b"vk", &vk.digest()?);
     transcript.absorb(b"U", U);
 
     let (num_rounds_x, num_rounds_y) = (
-      usize::try_from(vk.S.num_cons.ilog2()).unwrap(),
-      (usize::try_from(vk.S.num_vars.ilog2()).unwrap() + 1),
+      vk.S.num_cons.checked_ilog2().ok_or(NovaError::InvalidInputLength)? as usize,
+      vk.S.num_vars.checked_ilog2().ok_or(NovaError::InvalidInputLength)? as usize + 1,
     );
 
     // outer sum-check
     let tau: EqPolynomial<_> = PowPolynomial::new(&transcript.squeeze(b"t")?, num_rounds_x).into();
 
-    let (claim_outer_final, r_x) =
-      self.sc_proof_outer.verify(E::Scalar::ZERO, num_rounds_x, 3, &mut transcript)?;
+    let (claim_outer_final, r_x) = self.sc_proof_outer.verify_with_trace(
+      "outer sum-check",
+      E::Scalar::ZERO,
+      num_rounds_x,
+      3,
+      &mut transcript,
+      trace,
+    )?;
 
     // verify claim_outer_final
     let (claim_Az, claim_Bz, claim_Cz) = self.claims_outer;
     let taus_bound_rx = tau.evaluate(&r_x);
     let claim_outer_final_expected =
       taus_bound_rx * (claim_Az * claim_Bz - U.u * claim_Cz - self.eval_E);
-    if claim_outer_final != claim_outer_final_expected {
-      return Err(NovaError::InvalidSumcheckProof);
-    }
+    trace.check(
+      Check::SumcheckFinalClaim { sumcheck: "outer sum-check".to_string() },
+      claim_outer_final == claim_outer_final_expected,
+      NovaError::InvalidSumcheckProof,
+    )?;
 
     transcript.absorb(
       b"claims_outer",
@@ -306,16 +350,21 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let claim_inner_joint =
       self.claims_outer.0 + r * self.claims_outer.1 + r * r * self.claims_outer.2;
 
-    let (claim_inner_final, r_y) =
-      self.sc_proof_inner.verify(claim_inner_joint, num_rounds_y, 2, &mut transcript)?;
+    let (claim_inner_final, r_y) = self.sc_proof_inner.verify_with_trace(
+      "inner sum-check",
+      claim_inner_joint,
+      num_rounds_y,
+      2,
+      &mut transcript,
+      trace,
+    )?;
 
     // verify claim_inner_final
     let eval_Z = {
       let eval_X = {
         // public IO is (u, X)
         let X = vec![U.u].into_iter().chain(U.X.iter().cloned()).collect::<Vec<E::Scalar>>();
-        SparsePolynomial::new(usize::try_from(vk.S.num_vars.ilog2()).unwrap(), X)
-          .evaluate(&r_y[1..])
+        SparsePolynomial::new(num_rounds_y - 1, X).evaluate(&r_y[1..])
       };
       (E::Scalar::ONE - r_y[0]) * self.eval_W + r_y[0] * eval_X
     };
@@ -337,7 +386,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
             .sum()
         };
 
-      let (T_x, T_y) = rayon::join(
+      let (T_x, T_y) = parallel::join(
         || EqPolynomial::evals_from_points(r_x),
         || EqPolynomial::evals_from_points(r_y),
       );
@@ -348,9 +397,11 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let evals = multi_evaluate(&[&vk.S.A, &vk.S.B, &vk.S.C], &r_x, &r_y);
 
     let claim_inner_final_expected = (evals[0] + r * evals[1] + r * r * evals[2]) * eval_Z;
-    if claim_inner_final != claim_inner_final_expected {
-      return Err(NovaError::InvalidSumcheckProof);
-    }
+    trace.check(
+      Check::SumcheckFinalClaim { sumcheck: "inner sum-check".to_string() },
+      claim_inner_final == claim_inner_final_expected,
+      NovaError::InvalidSumcheckProof,
+    )?;
 
     // add claims about W and E polynomials
     let u_vec: Vec<PolyEvalInstance<E>> = vec![
@@ -359,16 +410,19 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     ];
 
     let batched_u =
-      batch_eval_verify(u_vec, &mut transcript, &self.sc_proof_batch, &self.evals_batch)?;
+      batch_eval_verify(u_vec, &mut transcript, &self.sc_proof_batch, &self.evals_batch, trace)?;
 
     // verify
-    EE::verify(
-      &vk.vk_ee,
-      &mut transcript,
-      &batched_u.c,
-      &batched_u.x,
-      &batched_u.e,
-      &self.eval_arg,
+    trace.check_result(
+      Check::Opening { opening: "batched W and E evaluations".to_string() },
+      EE::verify(
+        &vk.vk_ee,
+        &mut transcript,
+        &batched_u.c,
+        &batched_u.x,
+        &batched_u.e,
+        &self.eval_arg,
+      ),
     )?;
 
     Ok(())
@@ -456,9 +510,14 @@ pub(in crate::spartan) fn batch_eval_verify<E: Engine>(
   transcript: &mut E::TE,
   sc_proof_batch: &SumcheckProof<E>,
   evals_batch: &[E::Scalar],
+  trace: &mut VerifyTrace,
 ) -> Result<PolyEvalInstance<E>, NovaError> {
   let num_claims = u_vec.len();
-  assert_eq!(evals_batch.len(), num_claims);
+  trace.check(
+    Check::equality("number of batched evaluations"),
+    evals_batch.len() == num_claims,
+    NovaError::InvalidInputLength,
+  )?;
 
   // generate a challenge
   let rho = transcript.squeeze(b"r")?;
@@ -466,12 +525,19 @@ pub(in crate::spartan) fn batch_eval_verify<E: Engine>(
 
   // Compute nᵢ and n = maxᵢ{nᵢ}
   let num_rounds = u_vec.iter().map(|u| u.x.len()).collect::<Vec<_>>();
-  let num_rounds_max = *num_rounds.iter().max().unwrap();
+  let num_rounds_max = *num_rounds.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
   let claims = u_vec.iter().map(|u| u.e).collect::<Vec<_>>();
 
-  let (claim_batch_final, r) =
-    sc_proof_batch.verify_batch(&claims, &num_rounds, &powers_of_rho, 2, transcript)?;
+  let (claim_batch_final, r) = sc_proof_batch.verify_batch_with_trace(
+    "batch evaluation sum-check",
+    &claims,
+    &num_rounds,
+    &powers_of_rho,
+    2,
+    transcript,
+    trace,
+  )?;
 
   let claim_batch_final_expected = {
     let evals_r = u_vec.iter().map(|u| {
@@ -485,9 +551,11 @@ pub(in crate::spartan) fn batch_eval_verify<E: Engine>(
     .sum()
   };
 
-  if claim_batch_final != claim_batch_final_expected {
-    return Err(NovaError::InvalidSumcheckProof);
-  }
+  trace.check(
+    Check::SumcheckFinalClaim { sumcheck: "batch evaluation sum-check".to_string() },
+    claim_batch_final == claim_batch_final_expected,
+    NovaError::InvalidSumcheckProof,
+  )?;
 
   transcript.absorb(b"l", &evals_batch);
 
@@ -1,7 +1,7 @@
 use ff::Field;
-use rayon::prelude::*;
 
 use crate::{
+  parallel::{self, prelude::*},
   provider::util::field::batch_invert,
   spartan::{
     math::Math,
@@ -26,9 +26,9 @@ pub trait SumcheckEngine<E: Engine>: Send + Sync {
   /// the size of the polynomials
   fn size(&self) -> usize;
 
-  /// returns evaluation points at 0, 2, d-1 (where d is the degree of the
-  /// sum-check polynomial)
-  fn evaluation_points(&self) -> Vec<Vec<E::Scalar>>;
+  /// returns evaluation points at 0, 2, 3 of the cubic sum-check polynomial,
+  /// in arrays that do not allocate per instance and round
+  fn evaluation_points(&self) -> Vec<[E::Scalar; 3]>;
 
   /// bounds a variable in the constituent polynomials
   fn bound(&mut self, r: &E::Scalar);
@@ -85,7 +85,7 @@ impl<E: Engine> SumcheckEngine<E> for WitnessBoundSumcheck<E> {
     self.poly_W.len()
   }
 
-  fn evaluation_points(&self) -> Vec<Vec<E::Scalar>> {
+  fn evaluation_points(&self) -> Vec<[E::Scalar; 3]> {
     let comb_func = |poly_A_comp: &E::Scalar,
                      poly_B_comp: &E::Scalar,
                      _: &E::Scalar|
@@ -98,7 +98,7 @@ impl<E: Engine> SumcheckEngine<E> for WitnessBoundSumcheck<E> {
       &comb_func,
     );
 
-    vec![vec![eval_point_0, eval_point_2, eval_point_3]]
+    vec![[eval_point_0, eval_point_2, eval_point_3]]
   }
 
   fn bound(&mut self, r: &E::Scalar) {
@@ -176,7 +176,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
      -> (Vec<E::Scalar>, Vec<E::Scalar>) {
       let hash_func = |addr: &E::Scalar, val: &E::Scalar| -> E::Scalar { *val * gamma + *addr };
       assert_eq!(addr.len(), lookups.len());
-      rayon::join(
+      parallel::join(
         || {
           (0..mem.len())
             .map(|i| hash_func(&E::Scalar::from(i as u64), &mem[i]))
@@ -186,7 +186,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
       )
     };
 
-    let ((T_row, W_row), (T_col, W_col)) = rayon::join(
+    let ((T_row, W_row), (T_col, W_col)) = parallel::join(
       || hash_func_vec(mem_row, addr_row, L_row),
       || hash_func_vec(mem_col, addr_col, L_col),
     );
@@ -200,9 +200,9 @@ impl<E: Engine> MemorySumcheckInstance<E> {
       (Result<Vec<E::Scalar>, NovaError>, Result<Vec<E::Scalar>, NovaError>),
       (Vec<E::Scalar>, Vec<E::Scalar>),
     ) {
-      rayon::join(
+      parallel::join(
         || {
-          rayon::join(
+          parallel::join(
             || {
               let inv = batch_invert(T.par_iter().map(|e| *e + *r).collect::<Vec<_>>())?;
 
@@ -216,7 +216,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
           )
         },
         || {
-          rayon::join(
+          parallel::join(
             || T.par_iter().map(|e| *e + *r).collect(),
             || W.par_iter().map(|e| *e + *r).collect(),
           )
@@ -227,7 +227,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
     let (
       ((t_plus_r_inv_row, w_plus_r_inv_row), (t_plus_r_row, w_plus_r_row)),
       ((t_plus_r_inv_col, w_plus_r_inv_col), (t_plus_r_col, w_plus_r_col)),
-    ) = rayon::join(|| helper(&T_row, &W_row, ts_row, r), || helper(&T_col, &W_col, ts_col, r));
+    ) = parallel::join(|| helper(&T_row, &W_row, ts_row, r), || helper(&T_col, &W_col, ts_col, r));
 
     let t_plus_r_inv_row = t_plus_r_inv_row?;
     let w_plus_r_inv_row = w_plus_r_inv_row?;
@@ -237,15 +237,15 @@ impl<E: Engine> MemorySumcheckInstance<E> {
     let (
       (comm_t_plus_r_inv_row, comm_w_plus_r_inv_row),
       (comm_t_plus_r_inv_col, comm_w_plus_r_inv_col),
-    ) = rayon::join(
+    ) = parallel::join(
       || {
-        rayon::join(
+        parallel::join(
           || E::CE::commit(ck, &t_plus_r_inv_row),
           || E::CE::commit(ck, &w_plus_r_inv_row),
         )
       },
       || {
-        rayon::join(
+        parallel::join(
           || E::CE::commit(ck, &t_plus_r_inv_col),
           || E::CE::commit(ck, &w_plus_r_inv_col),
         )
@@ -307,7 +307,7 @@ impl<E: Engine> SumcheckEngine<E> for MemorySumcheckInstance<E> {
     self.w_plus_r_row.len()
   }
 
-  fn evaluation_points(&self) -> Vec<Vec<E::Scalar>> {
+  fn evaluation_points(&self) -> Vec<[E::Scalar; 3]> {
     let comb_func = |poly_A_comp: &E::Scalar,
                      poly_B_comp: &E::Scalar,
                      _poly_C_comp: &E::Scalar|
@@ -384,12 +384,12 @@ impl<E: Engine> SumcheckEngine<E> for MemorySumcheckInstance<E> {
       );
 
     vec![
-      vec![eval_inv_0_row, eval_inv_2_row, eval_inv_3_row],
-      vec![eval_inv_0_col, eval_inv_2_col, eval_inv_3_col],
-      vec![eval_T_0_row, eval_T_2_row, eval_T_3_row],
-      vec![eval_W_0_row, eval_W_2_row, eval_W_3_row],
-      vec![eval_T_0_col, eval_T_2_col, eval_T_3_col],
-      vec![eval_W_0_col, eval_W_2_col, eval_W_3_col],
+      [eval_inv_0_row, eval_inv_2_row, eval_inv_3_row],
+      [eval_inv_0_col, eval_inv_2_col, eval_inv_3_col],
+      [eval_T_0_row, eval_T_2_row, eval_T_3_row],
+      [eval_W_0_row, eval_W_2_row, eval_W_3_row],
+      [eval_T_0_col, eval_T_2_col, eval_T_3_col],
+      [eval_W_0_col, eval_W_2_col, eval_W_3_col],
     ]
   }
 
@@ -467,7 +467,7 @@ impl<E: Engine> SumcheckEngine<E> for OuterSumcheckInstance<E> {
     self.poly_tau.len()
   }
 
-  fn evaluation_points(&self) -> Vec<Vec<E::Scalar>> {
+  fn evaluation_points(&self) -> Vec<[E::Scalar; 3]> {
     let comb_func =
       |poly_A_comp: &E::Scalar,
        poly_B_comp: &E::Scalar,
@@ -497,7 +497,7 @@ impl<E: Engine> SumcheckEngine<E> for OuterSumcheckInstance<E> {
         &comb_func2,
       );
 
-    vec![vec![eval_point_h_0, eval_point_h_2, eval_point_h_3], vec![
+    vec![[eval_point_h_0, eval_point_h_2, eval_point_h_3], [
       eval_point_e_0,
       eval_point_e_2,
       eval_point_e_3,
@@ -546,7 +546,7 @@ impl<E: Engine> SumcheckEngine<E> for InnerSumcheckInstance<E> {
     self.poly_L_row.len()
   }
 
-  fn evaluation_points(&self) -> Vec<Vec<E::Scalar>> {
+  fn evaluation_points(&self) -> Vec<[E::Scalar; 3]> {
     let (poly_A, poly_B, poly_C) = (&self.poly_L_row, &self.poly_L_col, &self.poly_val);
     let comb_func = |poly_A_comp: &E::Scalar,
                      poly_B_comp: &E::Scalar,
@@ -556,7 +556,7 @@ impl<E: Engine> SumcheckEngine<E> for InnerSumcheckInstance<E> {
     let (eval_point_0, eval_point_2, eval_point_3) =
       SumcheckProof::<E>::compute_eval_points_cubic(poly_A, poly_B, poly_C, &comb_func);
 
-    vec![vec![eval_point_0, eval_point_2, eval_point_3]]
+    vec![[eval_point_0, eval_point_2, eval_point_3]]
   }
 
   fn bound(&mut self, r: &E::Scalar) {
@@ -0,0 +1,302 @@
+//! A sum-check whose evaluation tables live in a base field `F`, and whose
+//! challenges and claims live in an extension `EF` of it.
+//!
+//! The first round only reads the tables in `F`; binding them to the first
+//! challenge halves them into tables in `EF`, so the prover never holds a
+//! full-size table in the extension, which is `EF::DEGREE` times larger. With
+//! `EF = F` this is the usual sum-check, over the fields of curve engines.
+use core::ops::{Add, Mul, Sub};
+
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  constants::SecurityLevel,
+  errors::NovaError,
+  parallel::prelude::*,
+  provider::{
+    keccak::Keccak256Transcript,
+    small_field::{extension::ExtensionField, Challenger},
+  },
+  traits::{Engine, HashToField, TranscriptEngineTrait},
+};
+
+/// The transcript of a sum-check with challenges in `EF`
+pub trait SumcheckTranscript<F: PrimeField, EF: ExtensionField<F>> {
+  /// absorbs the message of the prover for a round
+  fn absorb_round(&mut self, evals: &[EF]);
+
+  /// returns the challenge of the verifier for a round
+  fn squeeze_challenge(&mut self) -> Result<EF, NovaError>;
+}
+
+impl<F: PrimeField, EF: ExtensionField<F>> SumcheckTranscript<F, EF> for Challenger {
+  fn absorb_round(&mut self, evals: &[EF]) { self.absorb::<F, EF>(b"p", evals) }
+
+  fn squeeze_challenge(&mut self) -> Result<EF, NovaError> { self.squeeze::<F, EF>(b"c") }
+}
+
+impl<E: Engine, H: HashToField<E::Scalar>, const VERSION: u8>
+  SumcheckTranscript<E::Scalar, E::Scalar> for Keccak256Transcript<E, H, VERSION>
+{
+  fn absorb_round(&mut self, evals: &[E::Scalar]) { self.absorb(b"p", &evals) }
+
+  fn squeeze_challenge(&mut self) -> Result<E::Scalar, NovaError> { self.squeeze(b"c") }
+}
+
+/// A sum-check proof with claims in `EF`: for each round, the evaluations of
+/// the polynomial of the round at `0, 2, 3, ..., degree`, its evaluation at 1
+/// following from the claim of the round
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtSumcheckProof<EF> {
+  round_evals: Vec<Vec<EF>>,
+}
+
+/// The points, in `F`, the polynomial of a round is sent at
+fn round_points<F: PrimeField>(degree: usize) -> Vec<F> {
+  core::iter::once(0).chain(2..=degree as u64).map(F::from).collect()
+}
+
+/// Evaluates at `r` the polynomial of evaluations `evals` at `0, 1, ...`
+// the interpolation points are distinct
+#[allow(clippy::expect_used)]
+fn interpolate<F: PrimeField, EF: ExtensionField<F>>(evals: &[EF], r: EF) -> EF {
+  let points = (0..evals.len() as u64).map(F::from).collect::<Vec<_>>();
+  evals
+    .iter()
+    .enumerate()
+    .map(|(i, y)| {
+      let (num, den) = points
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != i)
+        .fold((*y, F::ONE), |(num, den), (_, x_j)| {
+          (num * (r - EF::from(*x_j)), den * (points[i] - x_j))
+        });
+      num * Option::<F>::from(den.invert()).expect("the interpolation points are distinct")
+    })
+    .sum()
+}
+
+/// The evaluations at `points` of the polynomial of a round, over `tables`
+/// in `X`, which is `F` in the first round and `EF` in the others
+fn round_evals<F, EF, X, C>(tables: &[&[X]], points: &[F], comb: &C) -> Vec<EF>
+where
+  F: PrimeField,
+  EF: ExtensionField<F> + From<X>,
+  X: Copy + Send + Sync + Add<Output = X> + Sub<Output = X> + Mul<F, Output = X>,
+  C: Fn(&[EF]) -> EF + Sync, {
+  let half = tables[0].len() / 2;
+  (0..half)
+    .into_par_iter()
+    .map(|i| {
+      let mut values = vec![EF::default(); tables.len()];
+      points
+        .iter()
+        .map(|t| {
+          // the tables are computed in `X` at the point, and only then lifted
+          for (value, table) in values.iter_mut().zip(tables) {
+            let (lo, hi) = (table[i], table[half + i]);
+            *value = EF::from(lo + (hi - lo) * *t);
+          }
+          comb(&values)
+        })
+        .collect::<Vec<_>>()
+    })
+    .reduce(
+      || vec![EF::default(); points.len()],
+      |a, b| a.into_iter().zip(b).map(|(a, b)| a + b).collect(),
+    )
+}
+
+/// Binds the top variable of `table`, in `X`, to `r`
+fn bind<F, EF, X>(table: &[X], r: EF) -> Vec<EF>
+where
+  F: PrimeField,
+  EF: ExtensionField<F> + From<X>,
+  X: Copy + Send + Sync + Sub<Output = X>, {
+  let half = table.len() / 2;
+  (0..half)
+    .into_par_iter()
+    .map(|i| EF::from(table[i]) + r * EF::from(table[half + i] - table[i]))
+    .collect()
+}
+
+impl<EF> ExtSumcheckProof<EF> {
+  /// The evaluations of the polynomials of the rounds, at `0, 2, ...,
+  /// degree`
+  pub(crate) fn round_evals(&self) -> &[Vec<EF>] { &self.round_evals }
+
+  /// Proves that `claim` is the sum over the hypercube of `comb` applied to
+  /// `tables`, which all have the same power-of-two length, `comb` being of
+  /// degree `degree`. Returns the proof, the challenges, and the evaluations
+  /// of the tables at the challenges.
+  pub fn prove<F, C, T>(
+    claim: EF,
+    tables: &[&[F]],
+    degree: usize,
+    comb: C,
+    transcript: &mut T,
+  ) -> Result<(Self, Vec<EF>, Vec<EF>), NovaError>
+  where
+    F: PrimeField,
+    EF: ExtensionField<F>,
+    C: Fn(&[EF]) -> EF + Sync,
+    T: SumcheckTranscript<F, EF>,
+  {
+    let len = tables.first().map_or(0, |table| table.len());
+    if degree == 0 || !len.is_power_of_two() || tables.iter().any(|table| table.len() != len) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let num_rounds = len.trailing_zeros() as usize;
+    let points = round_points::<F>(degree);
+
+    let mut r = Vec::with_capacity(num_rounds);
+    let mut round_evals_vec = Vec::with_capacity(num_rounds);
+    let mut bound: Vec<Vec<EF>> = Vec::new();
+    let mut claim_per_round = claim;
+    for round in 0..num_rounds {
+      let evals = if round == 0 {
+        round_evals::<F, EF, F, C>(tables, &points, &comb)
+      } else {
+        let bound = bound.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        round_evals::<F, EF, EF, C>(&bound, &points, &comb)
+      };
+
+      transcript.absorb_round(&evals);
+      let r_i = transcript.squeeze_challenge()?;
+
+      let mut all_evals = vec![evals[0], claim_per_round - evals[0]];
+      all_evals.extend_from_slice(&evals[1..]);
+      claim_per_round = interpolate::<F, EF>(&all_evals, r_i);
+
+      bound = if round == 0 {
+        tables.par_iter().map(|table| bind::<F, EF, F>(table, r_i)).collect()
+      } else {
+        bound.par_iter().map(|table| bind::<F, EF, EF>(table, r_i)).collect()
+      };
+      r.push(r_i);
+      round_evals_vec.push(evals);
+    }
+
+    let final_evals = if num_rounds == 0 {
+      tables.iter().map(|table| EF::from(table[0])).collect()
+    } else {
+      bound.iter().map(|table| table[0]).collect()
+    };
+    Ok((Self { round_evals: round_evals_vec }, r, final_evals))
+  }
+
+  /// Verifies the proof of `claim` over `num_rounds` rounds of degree
+  /// `degree`, which must reach `level` over `EF`. Returns the claimed
+  /// evaluation of the combination at the challenges, and the challenges.
+  pub fn verify<F, T>(
+    &self,
+    claim: EF,
+    num_rounds: usize,
+    degree: usize,
+    level: SecurityLevel,
+    transcript: &mut T,
+  ) -> Result<(EF, Vec<EF>), NovaError>
+  where
+    F: PrimeField,
+    EF: ExtensionField<F>,
+    T: SumcheckTranscript<F, EF>,
+  {
+    level.check_sumcheck_over(EF::DEGREE * F::CAPACITY as usize, num_rounds, degree)?;
+    if degree == 0
+      || self.round_evals.len() != num_rounds
+      || self.round_evals.iter().any(|evals| evals.len() != degree)
+    {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+
+    let mut e = claim;
+    let mut r = Vec::with_capacity(num_rounds);
+    for evals in &self.round_evals {
+      transcript.absorb_round(evals);
+      let r_i = transcript.squeeze_challenge()?;
+
+      let mut all_evals = vec![evals[0], e - evals[0]];
+      all_evals.extend_from_slice(&evals[1..]);
+      e = interpolate::<F, EF>(&all_evals, r_i);
+      r.push(r_i);
+    }
+    Ok((e, r))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand_core::OsRng;
+
+  use super::*;
+  use crate::provider::{
+    small_field::{BabyBearEngine, GoldilocksEngine, SmallFieldEngine},
+    Bn256EngineIPA,
+  };
+
+  /// The multilinear extension of `table` at `r`
+  fn evaluate<F: PrimeField, EF: ExtensionField<F>>(table: &[F], r: &[EF]) -> EF {
+    let mut eq = vec![EF::from(F::ONE)];
+    for r_i in r {
+      eq = eq.iter().flat_map(|e| [*e - *e * *r_i, *e * *r_i]).collect();
+    }
+    table.iter().zip(eq).map(|(v, e)| e * *v).sum()
+  }
+
+  fn test_ext_sumcheck_with<F, EF, T>(level: SecurityLevel, transcript: impl Fn() -> T)
+  where
+    F: PrimeField,
+    EF: ExtensionField<F>,
+    T: SumcheckTranscript<F, EF>, {
+    let num_vars = 6;
+    let a = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let b = (0..1 << num_vars).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+    let claim = EF::from(a.iter().zip(&b).map(|(a, b)| *a * b).sum::<F>());
+    let comb = |v: &[EF]| v[0] * v[1];
+
+    let (proof, r, final_evals) =
+      ExtSumcheckProof::prove(claim, &[a.as_slice(), b.as_slice()], 2, comb, &mut transcript())
+        .unwrap();
+    assert_eq!(final_evals, vec![evaluate(&a, &r), evaluate(&b, &r)]);
+
+    let (e, r_verifier) =
+      proof.verify::<F, T>(claim, num_vars, 2, level, &mut transcript()).unwrap();
+    assert_eq!(r_verifier, r);
+    assert_eq!(e, comb(&final_evals));
+
+    // a proof of another claim does not reduce to the evaluations
+    let wrong_claim = claim + EF::from(F::ONE);
+    let (e, _) = proof.verify::<F, T>(wrong_claim, num_vars, 2, level, &mut transcript()).unwrap();
+    assert_ne!(e, comb(&final_evals));
+
+    // and the proof must have the expected shape
+    assert_eq!(
+      proof.verify::<F, T>(claim, num_vars + 1, 2, level, &mut transcript()),
+      Err(NovaError::InvalidSumcheckProof)
+    );
+  }
+
+  #[test]
+  fn test_ext_sumcheck() {
+    type G = GoldilocksEngine;
+    type B = BabyBearEngine;
+    test_ext_sumcheck_with::<<G as SmallFieldEngine>::Base, <G as SmallFieldEngine>::Ext, _>(
+      G::SECURITY_LEVEL,
+      || Challenger::new(b"test"),
+    );
+    test_ext_sumcheck_with::<<B as SmallFieldEngine>::Base, <B as SmallFieldEngine>::Ext, _>(
+      B::SECURITY_LEVEL,
+      || Challenger::new(b"test"),
+    );
+
+    // the sum-check over the field of a curve engine is the special case
+    // `EF = F`
+    type E = Bn256EngineIPA;
+    test_ext_sumcheck_with::<<E as Engine>::Scalar, <E as Engine>::Scalar, _>(
+      E::SECURITY_LEVEL,
+      || <E as Engine>::TE::new(b"test"),
+    );
+  }
+}
@@ -1,25 +1,54 @@
 use ff::Field;
 use itertools::Itertools as _;
-use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
   errors::NovaError,
+  inspect::{Check, VerifyTrace},
+  parallel::{self, prelude::*},
   spartan::polys::{
     multilinear::MultilinearPolynomial,
     univariate::{CompressedUniPoly, UniPoly},
   },
-  traits::{Engine, TranscriptEngineTrait},
+  traits::{validated, Engine, TranscriptEngineTrait, Validate},
 };
 
 pub(in crate::spartan) mod engine;
+pub(crate) mod extension;
 
+/// A sum-check proof, whose rounds are checked when deserialized to be fewer
+/// than the bits of a `usize`, and to be polynomials of the same degree
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(bound = "")]
+#[serde(remote = "Self", bound = "")]
 pub(crate) struct SumcheckProof<E: Engine> {
   compressed_polys: Vec<CompressedUniPoly<E::Scalar>>,
 }
 
+impl<E: Engine> Validate for SumcheckProof<E> {
+  fn validate(&self) -> Result<(), NovaError> {
+    let num_coeffs = self.compressed_polys.first().map_or(1, CompressedUniPoly::num_coeffs);
+    if self.compressed_polys.len() >= usize::BITS as usize
+      || num_coeffs == 0
+      || self.compressed_polys.iter().any(|poly| poly.num_coeffs() != num_coeffs)
+    {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+    Ok(())
+  }
+}
+
+impl<E: Engine> Serialize for SumcheckProof<E> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for SumcheckProof<E> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    validated::<_, D>(Self::deserialize(deserializer)?)
+  }
+}
+
 impl<E: Engine> SumcheckProof<E> {
   pub fn new(compressed_polys: Vec<CompressedUniPoly<E::Scalar>>) -> Self {
     Self { compressed_polys }
@@ -32,21 +61,46 @@ impl<E: Engine> SumcheckProof<E> {
     degree_bound: usize,
     transcript: &mut E::TE,
   ) -> Result<(E::Scalar, Vec<E::Scalar>), NovaError> {
+    let trace = &mut VerifyTrace::new();
+    self.verify_with_trace("sum-check", claim, num_rounds, degree_bound, transcript, trace)
+  }
+
+  /// Same as [`SumcheckProof::verify`], recording its checks, of the
+  /// sum-check named `name`, in `trace`
+  pub fn verify_with_trace(
+    &self,
+    name: &str,
+    claim: E::Scalar,
+    num_rounds: usize,
+    degree_bound: usize,
+    transcript: &mut E::TE,
+    trace: &mut VerifyTrace,
+  ) -> Result<(E::Scalar, Vec<E::Scalar>), NovaError> {
+    // a sum-check over a small field does not reach the security level alone
+    trace.check_result(
+      Check::step(&format!("{name} security level")),
+      E::SECURITY_LEVEL.check_sumcheck::<E::Scalar>(num_rounds, degree_bound),
+    )?;
+
     let mut e = claim;
     let mut r: Vec<E::Scalar> = Vec::new();
 
     // verify that there is a univariate polynomial for each round
-    if self.compressed_polys.len() != num_rounds {
-      return Err(NovaError::InvalidSumcheckProof);
-    }
+    trace.check(
+      Check::SumcheckRounds { sumcheck: name.to_string() },
+      self.compressed_polys.len() == num_rounds,
+      NovaError::InvalidSumcheckProof,
+    )?;
 
     for i in 0..self.compressed_polys.len() {
       let poly = self.compressed_polys[i].decompress(&e);
 
       // verify degree bound
-      if poly.degree() != degree_bound {
-        return Err(NovaError::InvalidSumcheckProof);
-      }
+      trace.check(
+        Check::SumcheckDegree { sumcheck: name.to_string(), round: i },
+        poly.degree() == degree_bound,
+        NovaError::InvalidSumcheckProof,
+      )?;
 
       // we do not need to check if poly(0) + poly(1) = e, as
       // decompress() call above already ensures that holds
@@ -74,13 +128,34 @@ impl<E: Engine> SumcheckProof<E> {
     coeffs: &[E::Scalar],
     degree_bound: usize,
     transcript: &mut E::TE,
+  ) -> Result<(E::Scalar, Vec<E::Scalar>), NovaError> {
+    let trace = &mut VerifyTrace::new();
+    let name = "batched sum-check";
+    self.verify_batch_with_trace(name, claims, num_rounds, coeffs, degree_bound, transcript, trace)
+  }
+
+  /// Same as [`SumcheckProof::verify_batch`], recording its checks, of the
+  /// sum-check named `name`, in `trace`
+  pub fn verify_batch_with_trace(
+    &self,
+    name: &str,
+    claims: &[E::Scalar],
+    num_rounds: &[usize],
+    coeffs: &[E::Scalar],
+    degree_bound: usize,
+    transcript: &mut E::TE,
+    trace: &mut VerifyTrace,
   ) -> Result<(E::Scalar, Vec<E::Scalar>), NovaError> {
     let num_instances = claims.len();
-    assert_eq!(num_rounds.len(), num_instances);
-    assert_eq!(coeffs.len(), num_instances);
+    if num_rounds.len() != num_instances || coeffs.len() != num_instances {
+      return Err(NovaError::InvalidInputLength);
+    }
 
     // n = maxᵢ{nᵢ}
-    let num_rounds_max = *num_rounds.iter().max().unwrap();
+    let num_rounds_max = *num_rounds.iter().max().ok_or(NovaError::InvalidInputLength)?;
+    if num_rounds.iter().any(|n| num_rounds_max - n >= 64) {
+      return Err(NovaError::InvalidInputLength);
+    }
 
     // Random linear combination of claims,
     // where each claim is scaled by 2^{n-nᵢ} to account for the padding.
@@ -98,7 +173,7 @@ impl<E: Engine> SumcheckProof<E> {
     )
     .sum();
 
-    self.verify(claim, num_rounds_max, degree_bound, transcript)
+    self.verify_with_trace(name, claim, num_rounds_max, degree_bound, transcript, trace)
   }
 
   #[inline]
@@ -145,7 +220,7 @@ impl<E: Engine> SumcheckProof<E> {
         let (eval_point_0, eval_point_2) =
           Self::compute_eval_points_quad(poly_A, poly_B, &comb_func);
 
-        let evals = vec![eval_point_0, claim_per_round - eval_point_0, eval_point_2];
+        let evals = [eval_point_0, claim_per_round - eval_point_0, eval_point_2];
         UniPoly::from_evals(&evals)
       };
 
@@ -161,7 +236,7 @@ impl<E: Engine> SumcheckProof<E> {
       claim_per_round = poly.evaluate(&r_i);
 
       // bind all tables to the verifier's challenge
-      rayon::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i));
+      parallel::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i));
     }
 
     Ok((Self { compressed_polys: polys }, r, vec![poly_A[0], poly_B[0]]))
@@ -198,7 +273,7 @@ impl<E: Engine> SumcheckProof<E> {
       }
     }
 
-    let num_rounds_max = *num_rounds.iter().max().unwrap();
+    let num_rounds_max = *num_rounds.iter().max().ok_or(NovaError::InvalidInputLength)?;
     let mut e = zip_with!(iter, (claims, num_rounds, coeffs), |claim, num_rounds, coeff| {
       let scaled_claim = E::Scalar::from((1 << (num_rounds_max - num_rounds)) as u64) * claim;
       scaled_claim * coeff
@@ -227,7 +302,7 @@ impl<E: Engine> SumcheckProof<E> {
       let evals_combined_0 = (0..evals.len()).map(|i| evals[i].0 * coeffs[i]).sum();
       let evals_combined_2 = (0..evals.len()).map(|i| evals[i].1 * coeffs[i]).sum();
 
-      let evals = vec![evals_combined_0, e - evals_combined_0, evals_combined_2];
+      let evals = [evals_combined_0, e - evals_combined_0, evals_combined_2];
       let poly = UniPoly::from_evals(&evals);
 
       // append the prover's message to the transcript
@@ -243,7 +318,7 @@ impl<E: Engine> SumcheckProof<E> {
         |num_rounds, poly_A, poly_B| {
           if remaining_rounds <= *num_rounds {
             let _ =
-              rayon::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i));
+              parallel::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i));
           }
         }
       );
@@ -390,7 +465,7 @@ impl<E: Engine> SumcheckProof<E> {
             poly_A, poly_B, poly_C, poly_D, &comb_func,
           );
 
-        let evals = vec![eval_point_0, claim_per_round - eval_point_0, eval_point_2, eval_point_3];
+        let evals = [eval_point_0, claim_per_round - eval_point_0, eval_point_2, eval_point_3];
         UniPoly::from_evals(&evals)
       };
 
@@ -406,9 +481,9 @@ impl<E: Engine> SumcheckProof<E> {
       claim_per_round = poly.evaluate(&r_i);
 
       // bound all tables to the verifier's challenge
-      rayon::join(
-        || rayon::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i)),
-        || rayon::join(|| poly_C.bind_poly_var_top(&r_i), || poly_D.bind_poly_var_top(&r_i)),
+      parallel::join(
+        || parallel::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i)),
+        || parallel::join(|| poly_C.bind_poly_var_top(&r_i), || poly_D.bind_poly_var_top(&r_i)),
       );
     }
 
@@ -454,7 +529,7 @@ impl<E: Engine> SumcheckProof<E> {
       }
     }
 
-    let num_rounds_max = *num_rounds.iter().max().unwrap();
+    let num_rounds_max = *num_rounds.iter().max().ok_or(NovaError::InvalidInputLength)?;
 
     let mut r: Vec<E::Scalar> = Vec::new();
     let mut polys: Vec<CompressedUniPoly<E::Scalar>> = Vec::new();
@@ -488,12 +563,8 @@ impl<E: Engine> SumcheckProof<E> {
       let evals_combined_2 = (0..num_instances).map(|i| evals[i].1 * coeffs[i]).sum();
       let evals_combined_3 = (0..num_instances).map(|i| evals[i].2 * coeffs[i]).sum();
 
-      let evals = vec![
-        evals_combined_0,
-        claim_per_round - evals_combined_0,
-        evals_combined_2,
-        evals_combined_3,
-      ];
+      let evals =
+        [evals_combined_0, claim_per_round - evals_combined_0, evals_combined_2, evals_combined_3];
       let poly = UniPoly::from_evals(&evals);
 
       // append the prover's message to the transcript
@@ -520,9 +591,13 @@ impl<E: Engine> SumcheckProof<E> {
         ),
         |num_rounds, poly_A, poly_B, poly_C, poly_D| {
           if remaining_rounds <= *num_rounds {
-            let _ = rayon::join(
-              || rayon::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i)),
-              || rayon::join(|| poly_C.bind_poly_var_top(&r_i), || poly_D.bind_poly_var_top(&r_i)),
+            let _ = parallel::join(
+              || {
+                parallel::join(|| poly_A.bind_poly_var_top(&r_i), || poly_B.bind_poly_var_top(&r_i))
+              },
+              || {
+                parallel::join(|| poly_C.bind_poly_var_top(&r_i), || poly_D.bind_poly_var_top(&r_i))
+              },
             );
           }
         }
@@ -0,0 +1,146 @@
+//! The non-deterministic inputs of step circuits, e.g. the plaintexts of
+//! ciphertexts or hints to their parsers, supplied to each step explicitly.
+//!
+//! An [`AdvisedCircuit`] synthesizes a step from `pc`, `z` and a
+//! [`StepAdvice`], and an [`AdvisedStep`] pairs the circuit with the advice
+//! of a step, as a [`StepCircuit`]. The advice is bounded by
+//! [`AdvisedCircuit::max_advice_size`], which fixes the shape of the step:
+//! the advice is checked against it when the step is made, and the default
+//! advice synthesizes the shape of the circuit, e.g. for its public
+//! parameters. The advice is serializable, so that it is computed apart from
+//! the prover, and it is the witness digest of its step (see
+//! [`StepCircuit::witness_digest`]).
+use core::marker::PhantomData;
+
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use bincode::Options;
+use ff::PrimeField;
+use serde::{de::DeserializeOwned, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use super::StepCircuit;
+use crate::{errors::NovaError, secret::Zeroize};
+
+/// The non-deterministic inputs of a step
+pub trait StepAdvice: Clone + Default + Send + Sync + Serialize + DeserializeOwned {
+  /// The size of the advice, bounded by the circuit it is for
+  fn size(&self) -> usize;
+
+  /// The digest of the advice, the witness digest of its step: the SHA3-256
+  /// of its encoding
+  fn digest(&self) -> Option<[u8; 32]> {
+    let mut bytes = encoding().serialize(self).ok()?;
+    let digest = Sha3_256::digest(&bytes).into();
+    bytes.zeroize();
+    Some(digest)
+  }
+}
+
+/// The encoding of advice, as for digests (see [`crate::digest`])
+fn encoding() -> impl Options {
+  bincode::DefaultOptions::new().with_little_endian().with_fixint_encoding()
+}
+
+/// Bytes, e.g. a plaintext, of size their length
+impl StepAdvice for Vec<u8> {
+  fn size(&self) -> usize { self.len() }
+}
+
+/// A step circuit whose non-deterministic inputs are an advice
+pub trait AdvisedCircuit<F: PrimeField>: Send + Sync + Clone {
+  /// The advice of a step
+  type Advice: StepAdvice;
+
+  /// The arity of the step, as for [`StepCircuit::arity`]
+  fn arity(&self) -> usize;
+
+  /// The index of the step, as for [`StepCircuit::circuit_index`]
+  fn circuit_index(&self) -> usize;
+
+  /// The maximum size of the advice of a step, which the shape of the step
+  /// depends on
+  fn max_advice_size(&self) -> usize;
+
+  /// Synthesizes the step on `pc`, `z` and its advice `advice`, as for
+  /// [`StepCircuit::synthesize`]
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+    advice: &Self::Advice,
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError>;
+}
+
+/// The `StepCircuit` of an [`AdvisedCircuit`] and the advice of a step
+#[derive(Clone, Debug)]
+pub struct AdvisedStep<F: PrimeField, C: AdvisedCircuit<F>> {
+  circuit: C,
+  advice:  C::Advice,
+  _p:      PhantomData<F>,
+}
+
+impl<F: PrimeField, C: AdvisedCircuit<F>> AdvisedStep<F, C> {
+  /// The step of `circuit` with the advice `advice`, which must be within
+  /// the bound of the circuit
+  pub fn new(circuit: C, advice: C::Advice) -> Result<Self, NovaError> {
+    if advice.size() > circuit.max_advice_size() {
+      return Err(NovaError::InvalidAdvice(format!(
+        "the advice is of size {}, above {}",
+        advice.size(),
+        circuit.max_advice_size()
+      )));
+    }
+    Ok(Self { circuit, advice, _p: PhantomData })
+  }
+
+  /// The step of `circuit` with the default advice, whose shape is the shape
+  /// of every step of the circuit
+  pub fn shape(circuit: C) -> Result<Self, NovaError> { Self::new(circuit, C::Advice::default()) }
+
+  /// The step of `circuit` with the advice encoded as `bytes`, e.g. by
+  /// [`AdvisedStep::encoded_advice`]
+  pub fn from_encoded(circuit: C, bytes: &[u8]) -> Result<Self, NovaError> {
+    // `deserialize` alone accepts trailing bytes
+    let advice = encoding()
+      .reject_trailing_bytes()
+      .deserialize(bytes)
+      .map_err(|e| NovaError::InvalidAdvice(e.to_string()))?;
+    Self::new(circuit, advice)
+  }
+
+  /// The circuit of the step
+  pub fn circuit(&self) -> &C { &self.circuit }
+
+  /// The advice of the step
+  pub fn advice(&self) -> &C::Advice { &self.advice }
+
+  /// The encoding of the advice of the step
+  pub fn encoded_advice(&self) -> Result<Vec<u8>, NovaError> {
+    encoding().serialize(&self.advice).map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+}
+
+/// Wipes the advice, e.g. once the step is proved
+impl<F: PrimeField, C: AdvisedCircuit<F>> Zeroize for AdvisedStep<F, C>
+where C::Advice: Zeroize
+{
+  fn zeroize(&mut self) { self.advice.zeroize() }
+}
+
+impl<F: PrimeField, C: AdvisedCircuit<F>> StepCircuit<F> for AdvisedStep<F, C> {
+  fn arity(&self) -> usize { self.circuit.arity() }
+
+  fn circuit_index(&self) -> usize { self.circuit.circuit_index() }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    self.circuit.synthesize(cs, pc, z, &self.advice)
+  }
+
+  fn witness_digest(&self) -> Option<[u8; 32]> { self.advice.digest() }
+}
@@ -0,0 +1,131 @@
+//! Steps folding a variable number of units of application work, e.g. TLS
+//! records, up to a maximum batch size.
+//!
+//! A [`BatchUnit`] maps the `z` of the application, and a [`BatchStep`]
+//! synthesizes `max_batch` units per step, the units of the step followed by
+//! a padding unit in every unused slot, following the convention of
+//! [`crate::gadgets::select_if`]: a disabled slot passes `z` through
+//! unchanged, whatever its padding computes, and the number of enabled slots
+//! is added to a counter after the `z` of the application, so that the
+//! verifier reads the number of units the computation folded from its
+//! output. The padding unit must be satisfiable on every `z`.
+//!
+//! The shape of a step only depends on `max_batch`, which sets the time of
+//! every step: [`DeviceProfile::batch_size`](super::estimate::DeviceProfile::batch_size)
+//! picks it for the speed of the device, before the public parameters are
+//! made, and the steps then take from 1 to `max_batch` units each, e.g. as
+//! they arrive.
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use super::StepCircuit;
+use crate::{
+  errors::NovaError,
+  gadgets::{prefix_mask, select_if},
+};
+
+/// A unit of application work, of which a [`BatchStep`] folds a batch
+pub trait BatchUnit<F: PrimeField>: Send + Sync + Clone {
+  /// The number of elements of the `z` of the application
+  fn arity(&self) -> usize;
+
+  /// Synthesizes the unit on `z`, and returns the next `z`
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError>;
+}
+
+/// The step folding a batch of at most `max_batch` units, whose `z` is the
+/// `z` of the units followed by the number of units folded
+#[derive(Clone, Debug)]
+pub struct BatchStep<U> {
+  units:     Vec<U>,
+  padding:   U,
+  max_batch: usize,
+}
+
+impl<U> BatchStep<U> {
+  /// The step folding `units`, at most `max_batch` of them, the unused
+  /// slots padded with `padding`
+  pub fn new(units: Vec<U>, padding: U, max_batch: usize) -> Result<Self, NovaError> {
+    if max_batch == 0 || units.len() > max_batch {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(Self { units, padding, max_batch })
+  }
+
+  /// The steps folding `units` in order, `batch_size` per step and the rest
+  /// in the last step, of steps of at most `max_batch` units
+  pub fn steps(
+    units: Vec<U>,
+    padding: U,
+    batch_size: usize,
+    max_batch: usize,
+  ) -> Result<Vec<Self>, NovaError>
+  where
+    U: Clone,
+  {
+    if batch_size == 0 || batch_size > max_batch {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if units.is_empty() {
+      return Ok(vec![Self::new(units, padding, max_batch)?]);
+    }
+    units
+      .chunks(batch_size)
+      .map(|batch| Self::new(batch.to_vec(), padding.clone(), max_batch))
+      .collect()
+  }
+
+  /// The number of units of the step
+  pub fn len(&self) -> usize { self.units.len() }
+
+  /// Whether the step only pads
+  pub fn is_empty(&self) -> bool { self.units.is_empty() }
+}
+
+impl<F: PrimeField, U: BatchUnit<F>> StepCircuit<F> for BatchStep<U> {
+  fn arity(&self) -> usize { self.padding.arity() + 1 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let arity = self.padding.arity();
+    if z.len() != arity + 1 || self.units.len() > self.max_batch {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    let (mut z_units, count) = (z[..arity].to_vec(), &z[arity]);
+
+    let len =
+      AllocatedNum::alloc_infallible(cs.namespace(|| "len"), || F::from(self.units.len() as u64));
+    let mask = prefix_mask(cs.namespace(|| "mask"), &len, self.max_batch)?;
+    for (i, enabled) in mask.iter().enumerate() {
+      let mut cs = cs.namespace(|| format!("slot {i}"));
+      let unit = self.units.get(i).unwrap_or(&self.padding);
+      let z_next = unit.synthesize(&mut cs.namespace(|| "unit"), &z_units)?;
+      z_units = select_if(cs.namespace(|| "select"), enabled, &z_next, &z_units)?;
+    }
+
+    let count_next = AllocatedNum::alloc(cs.namespace(|| "count"), || {
+      Ok(
+        count.get_value().ok_or(SynthesisError::AssignmentMissing)?
+          + F::from(self.units.len() as u64),
+      )
+    })?;
+    cs.enforce(
+      || "count",
+      |lc| lc + count.get_variable() + len.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + count_next.get_variable(),
+    );
+    z_units.push(count_next);
+    Ok((pc.cloned(), z_units))
+  }
+}
@@ -0,0 +1,122 @@
+//! A bounded cache of the primary witnesses synthesized by
+//! [`RecursiveSNARK::prove_step`](super::RecursiveSNARK::prove_step).
+//!
+//! Retrying a step from the same state, e.g. from a clone of the
+//! `RecursiveSNARK` taken before a step that failed further down the line,
+//! synthesizes the same primary witness again. When the step circuit
+//! describes its non-deterministic inputs with
+//! [`StepCircuit::witness_digest`](super::StepCircuit::witness_digest), the
+//! witness and its commitment are cached under a hash of the state of the
+//! `RecursiveSNARK` and of that digest, and the retry reuses them instead of
+//! synthesizing and committing again.
+use std::{
+  collections::VecDeque,
+  sync::{Mutex, PoisonError},
+};
+
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+  digest::{Digestible, SimpleDigestible},
+  r1cs::{R1CSInstance, R1CSWitness, RelaxedR1CSInstance},
+  secret::{wipe, Zeroize},
+  traits::{CurveCycleEquipped, Dual},
+};
+
+/// Everything a primary witness depends on, besides the public parameters
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub(crate) struct StepKey<'a, E1: CurveCycleEquipped> {
+  pub(crate) pp_digest:       &'a E1::Scalar,
+  pub(crate) i:               usize,
+  pub(crate) circuit_index:   usize,
+  pub(crate) program_counter: &'a E1::Scalar,
+  pub(crate) z0_primary:      &'a [E1::Scalar],
+  pub(crate) zi_primary:      &'a [E1::Scalar],
+  pub(crate) r_U_secondary:   &'a RelaxedR1CSInstance<Dual<E1>>,
+  pub(crate) l_u_secondary:   &'a R1CSInstance<Dual<E1>>,
+  pub(crate) witness_digest:  [u8; 32],
+}
+
+impl<E1: CurveCycleEquipped> SimpleDigestible for StepKey<'_, E1> {}
+
+impl<E1: CurveCycleEquipped> StepKey<'_, E1> {
+  /// The hash the step is cached under
+  pub(crate) fn hash(&self) -> Option<[u8; 32]> {
+    let mut hasher = Sha3_256::new();
+    self.write_bytes(&mut hasher).ok()?;
+    Some(hasher.finalize().into())
+  }
+}
+
+/// A primary witness synthesized for a step, along with its instance and the
+/// outputs of the step
+#[derive(Clone, Debug)]
+pub(crate) struct CachedStep<E1: CurveCycleEquipped> {
+  pub(crate) l_u_primary:        R1CSInstance<E1>,
+  pub(crate) l_w_primary:        R1CSWitness<E1>,
+  pub(crate) zi_primary:         Vec<E1::Scalar>,
+  pub(crate) zi_primary_pc_next: E1::Scalar,
+}
+
+impl<E1: CurveCycleEquipped> Zeroize for CachedStep<E1> {
+  fn zeroize(&mut self) {
+    self.l_w_primary.zeroize();
+    wipe(&mut self.zi_primary);
+  }
+}
+
+/// A cache of up to `capacity` primary witnesses, evicting the oldest first.
+///
+/// It is shared, behind an `Arc`, by the `RecursiveSNARK`s it is set on (see
+/// `RecursiveSNARK::set_witness_cache`) and by their clones.
+#[derive(Debug)]
+pub struct WitnessCache<E1: CurveCycleEquipped> {
+  capacity: usize,
+  entries:  Mutex<VecDeque<([u8; 32], CachedStep<E1>)>>,
+}
+
+impl<E1: CurveCycleEquipped> WitnessCache<E1> {
+  /// Creates an empty cache of up to `capacity` witnesses
+  pub fn new(capacity: usize) -> Self {
+    Self { capacity, entries: Mutex::new(VecDeque::with_capacity(capacity)) }
+  }
+
+  /// The maximum number of witnesses in the cache
+  pub fn capacity(&self) -> usize { self.capacity }
+
+  /// The number of witnesses in the cache
+  pub fn len(&self) -> usize { self.entries.lock().unwrap_or_else(PoisonError::into_inner).len() }
+
+  /// Whether the cache is empty
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Wipes the witnesses (see [`crate::secret`]) and empties the cache
+  pub fn clear(&self) {
+    let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+    entries.iter_mut().for_each(|(_, step)| step.zeroize());
+    entries.clear();
+  }
+
+  pub(crate) fn get(&self, key: &[u8; 32]) -> Option<CachedStep<E1>> {
+    let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+    entries.iter().find(|(k, _)| k == key).map(|(_, step)| step.clone())
+  }
+
+  pub(crate) fn insert(&self, key: [u8; 32], step: CachedStep<E1>) {
+    if self.capacity == 0 {
+      return;
+    }
+    let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+    if entries.iter().any(|(k, _)| *k == key) {
+      return;
+    }
+    if entries.len() == self.capacity {
+      if let Some((_, mut evicted)) = entries.pop_front() {
+        evicted.zeroize();
+      }
+    }
+    entries.push_back((key, step));
+  }
+}
@@ -14,18 +14,21 @@
 //!       circuit is legal or not.
 //!    3. F circuit produce `program_counter_{i+1}` and sent to next round to optionally constraint
 //!       the next F' argumented circuit.
-use std::marker::PhantomData;
+#[cfg(feature = "prove")] use std::marker::PhantomData;
 
+#[cfg(feature = "prove")]
 use bellpepper::gadgets::{boolean_utils::conditionally_select_slice, Assignment};
+#[cfg(feature = "prove")]
 use bellpepper_core::{
   boolean::{AllocatedBit, Boolean},
   num::AllocatedNum,
   ConstraintSystem, SynthesisError,
 };
-use ff::{Field, PrimeField};
-use itertools::Itertools as _;
+#[cfg(feature = "prove")] use ff::{Field, PrimeField};
+#[cfg(feature = "prove")] use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "prove")]
 use crate::{
   constants::{NIO_NOVA_FOLD, NUM_HASH_BITS},
   gadgets::{
@@ -50,6 +53,7 @@ use crate::{
 // want that when we are using NIVC. Program counter should be able to be put to
 // `None` otherwise, or we could handle that slightly differently too
 
+#[cfg(feature = "prove")]
 /// A helper trait for a step of the incremental computation for `SuperNova`
 /// (i.e., circuit for F) -- to be implemented by applications.
 pub trait StepCircuit<F: PrimeField>: Send + Sync + Clone {
@@ -73,11 +77,22 @@ pub trait StepCircuit<F: PrimeField>: Send + Sync + Clone {
     pc: Option<&AllocatedNum<F>>,
     z: &[AllocatedNum<F>],
   ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError>;
+
+  /// Return a digest of the non-deterministic inputs of this step, i.e.
+  /// everything `synthesize` depends on besides `pc` and `z`, if the circuit
+  /// can provide one.
+  ///
+  /// Two steps with the same digest must synthesize the same witness for
+  /// the same `pc` and `z`, so that a retried step can reuse the witness
+  /// synthesized for it (see `WitnessCache`). Defaults to `None`, which
+  /// disables the reuse.
+  fn witness_digest(&self) -> Option<[u8; 32]> { None }
 }
 
 // NOTES: This seems to just enforce that when we call a circuit at a given
 // step, it matches the set program counter.
 
+#[cfg(feature = "prove")]
 /// A helper trait for a step of the incremental computation for `SuperNova`
 /// (i.e., circuit for F) -- automatically implemented for `StepCircuit` and
 /// used internally to enforce that the circuit selected by the program counter
@@ -108,8 +123,10 @@ pub trait EnforcingStepCircuit<F: PrimeField>: Send + Sync + Clone + StepCircuit
   }
 }
 
+#[cfg(feature = "prove")]
 impl<F: PrimeField, S: StepCircuit<F>> EnforcingStepCircuit<F> for S {}
 
+#[cfg(feature = "prove")]
 /// A trivial step circuit that simply returns the input
 /// NOTE: Should only be used as secondary circuit!!!
 #[derive(Clone, Debug, Default)]
@@ -117,6 +134,7 @@ pub struct TrivialCircuit<F> {
   _p: PhantomData<F>,
 }
 
+#[cfg(feature = "prove")]
 impl<F> StepCircuit<F> for TrivialCircuit<F>
 where F: PrimeField
 {
@@ -151,6 +169,7 @@ impl SuperNovaAugmentedCircuitParams {
   pub fn get_n_limbs(&self) -> usize { self.n_limbs }
 }
 
+#[cfg(feature = "prove")]
 #[derive(Debug)]
 pub struct SuperNovaAugmentedCircuitInputs<'a, E: Engine> {
   pp_digest: E::Scalar,
@@ -173,6 +192,7 @@ pub struct SuperNovaAugmentedCircuitInputs<'a, E: Engine> {
   last_augmented_circuit_index: E::Base,
 }
 
+#[cfg(feature = "prove")]
 impl<'a, E: Engine> SuperNovaAugmentedCircuitInputs<'a, E> {
   /// Create new inputs/witness for the verification circuit
   #[allow(clippy::too_many_arguments)]
@@ -191,6 +211,7 @@ impl<'a, E: Engine> SuperNovaAugmentedCircuitInputs<'a, E> {
   }
 }
 
+#[cfg(feature = "prove")]
 /// The augmented circuit F' in `SuperNova` that includes a step circuit F
 /// and the circuit for the verifier in `SuperNova`'s non-interactive folding
 /// scheme, `SuperNova` NIFS will fold strictly r1cs instance u with respective
@@ -203,6 +224,7 @@ pub struct SuperNovaAugmentedCircuit<'a, E: Engine, SC: EnforcingStepCircuit<E::
   num_augmented_circuits: usize,  // number of overall augmented circuits
 }
 
+#[cfg(feature = "prove")]
 impl<'a, E: Engine, SC: EnforcingStepCircuit<E::Base>> SuperNovaAugmentedCircuit<'a, E, SC> {
   /// Create a new verification circuit for the input relaxed r1cs instances
   pub const fn new(
@@ -254,7 +276,7 @@ impl<'a, E: Engine, SC: EnforcingStepCircuit<E::Base>> SuperNovaAugmentedCircuit
     // Allocate program_counter only on primary circuit
     let program_counter = if self.params.is_primary_circuit {
       Some(AllocatedNum::alloc(cs.namespace(|| "program_counter"), || {
-        Ok(self.inputs.get()?.program_counter.expect("program_counter missing"))
+        self.inputs.get()?.program_counter.ok_or(SynthesisError::AssignmentMissing)
       })?)
     } else {
       None
@@ -602,7 +624,7 @@ impl<'a, E: Engine, SC: EnforcingStepCircuit<E::Base>> SuperNovaAugmentedCircuit
     ro.absorb(&i_next);
     // optionally absorb program counter if exist
     if program_counter.is_some() {
-      ro.absorb(program_counter_new.as_ref().expect("new program counter missing"))
+      ro.absorb(program_counter_new.as_ref().ok_or(SynthesisError::AssignmentMissing)?)
     }
     for e in &z_0 {
       ro.absorb(e);
@@ -0,0 +1,170 @@
+//! Proving within a time budget, e.g. the time a mobile OS grants a task in
+//! the background.
+//!
+//! A [`Deadline`] is the time by which the prover hands back control.
+//! [`RecursiveSNARK::prove_steps_by`] proves steps while one more step fits
+//! before the deadline, a step taking as long as the longest step so far or
+//! its estimate, and [`CompressedSNARK::prove_by`] compresses if its estimate
+//! fits. Otherwise they return a [`Checkpoint`] rather than finishing late:
+//! the steps proved so far, which encode to bytes, e.g. to storage, and resume
+//! against the same public parameters with [`Checkpoint::resume`].
+//!
+//! A step or a compression is never interrupted once started, so the deadline
+//! holds as far as the estimates do.
+use std::time::{Duration, Instant};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use super::{
+  error::SuperNovaError,
+  estimate::TimeEstimate,
+  snark::{CompressedSNARK, ProverKey},
+  PublicParams, RecursiveSNARK, StepCircuit,
+};
+use crate::{
+  errors::NovaError,
+  traits::{
+    snark::{BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
+    CurveCycleEquipped, Dual, Engine,
+  },
+};
+
+/// The time by which proving hands back control
+pub struct Deadline {
+  clock: Box<dyn Fn() -> Duration + Send + Sync>,
+  end:   Duration,
+}
+
+impl Deadline {
+  /// The deadline `budget` from now, on [`std::time::Instant`]
+  pub fn after(budget: Duration) -> Self {
+    let origin = Instant::now();
+    Self::with_clock(move || origin.elapsed(), budget)
+  }
+
+  /// The deadline at `end` on `clock`, e.g. on targets without
+  /// [`std::time::Instant`], with `performance.now()`
+  pub fn with_clock(clock: impl Fn() -> Duration + Send + Sync + 'static, end: Duration) -> Self {
+    Self { clock: Box::new(clock), end }
+  }
+
+  /// The time left before the deadline, zero once it passed
+  pub fn remaining(&self) -> Duration { self.end.saturating_sub((self.clock)()) }
+}
+
+/// The result of proving by a deadline: `T` if it finished in time, the
+/// checkpoint to resume from otherwise
+#[derive(Debug)]
+pub enum Proving<T, E1: CurveCycleEquipped> {
+  /// Proving finished before the deadline
+  Done(T),
+  /// Proving stopped before the deadline, at the checkpoint
+  Paused(Checkpoint<E1>),
+}
+
+/// The steps proved of a computation, from which its proving resumes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Checkpoint<E1: CurveCycleEquipped> {
+  recursive_snark: RecursiveSNARK<E1>,
+}
+
+/// The encoding of checkpoints, as for digests (see [`crate::digest`])
+pub(super) fn encoding() -> impl Options {
+  bincode::DefaultOptions::new().with_little_endian().with_fixint_encoding()
+}
+
+impl<E1: CurveCycleEquipped> Checkpoint<E1> {
+  /// The checkpoint of the steps proved by `recursive_snark`
+  pub fn new(recursive_snark: RecursiveSNARK<E1>) -> Self { Self { recursive_snark } }
+
+  /// The number of steps proved, counting the first: proving resumes with
+  /// the step of that index in the computation
+  pub fn num_steps(&self) -> usize { self.recursive_snark.i }
+
+  /// The digest of the public parameters the checkpoint was made against
+  pub fn pp_digest(&self) -> E1::Scalar { self.recursive_snark.pp_digest }
+
+  /// The encoding of the checkpoint, which the witness cache of the
+  /// `RecursiveSNARK` is not part of
+  pub fn to_bytes(&self) -> Result<Vec<u8>, NovaError> {
+    encoding().serialize(self).map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+
+  /// The checkpoint of the encoding `bytes` of
+  /// [`Checkpoint::to_bytes`]
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, NovaError> {
+    // `deserialize` alone accepts trailing bytes
+    encoding()
+      .reject_trailing_bytes()
+      .deserialize(bytes)
+      .map_err(|e| NovaError::SerializationError(e.to_string()))
+  }
+
+  /// The `RecursiveSNARK` to resume proving with, if the checkpoint was made
+  /// against `pp`
+  pub fn resume(self, pp: &PublicParams<E1>) -> Result<RecursiveSNARK<E1>, SuperNovaError> {
+    if self.recursive_snark.pp_digest != pp.digest() {
+      return Err(SuperNovaError::ParamsDigestMismatch);
+    }
+    Ok(self.recursive_snark)
+  }
+}
+
+impl<E1: CurveCycleEquipped> RecursiveSNARK<E1> {
+  /// Proves the steps of `steps` in order, as [`RecursiveSNARK::prove_step`]
+  /// does, while one more step fits before `deadline`. A step is estimated to
+  /// take `estimate.per_step` (see [`PublicParams::estimate_prove_time`]), or
+  /// the time of the longest step proved so far if longer.
+  ///
+  /// Returns the `RecursiveSNARK` if every step was proved, and the
+  /// checkpoint of the steps proved otherwise.
+  pub fn prove_steps_by<
+    C1: StepCircuit<E1::Scalar>,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar>,
+  >(
+    mut self,
+    pp: &PublicParams<E1>,
+    steps: &[C1],
+    c_secondary: &C2,
+    estimate: &TimeEstimate,
+    deadline: &Deadline,
+  ) -> Result<Proving<Self, E1>, SuperNovaError> {
+    let mut step_time = estimate.per_step;
+    let mut start = (deadline.clock)();
+    for step in steps {
+      if deadline.end.saturating_sub(start) < step_time {
+        return Ok(Proving::Paused(Checkpoint::new(self)));
+      }
+      self.prove_step(pp, step, c_secondary)?;
+      let now = (deadline.clock)();
+      step_time = step_time.max(now.saturating_sub(start));
+      start = now;
+    }
+    Ok(Proving::Done(self))
+  }
+}
+
+impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  /// Same as [`CompressedSNARK::prove`], if the compression, estimated to
+  /// take `estimate.compression`, fits before `deadline`; returns the
+  /// checkpoint of `recursive_snark` otherwise
+  pub fn prove_by(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1>,
+    estimate: &TimeEstimate,
+    deadline: &Deadline,
+  ) -> Result<Proving<Self, E1>, SuperNovaError> {
+    if deadline.remaining() < estimate.compression {
+      return Ok(Proving::Paused(Checkpoint::new(recursive_snark.clone())));
+    }
+    Self::prove(pp, pk, recursive_snark).map(Proving::Done)
+  }
+}
@@ -20,4 +20,17 @@ pub enum SuperNovaError {
   /// Extended error for supernova
   #[error("UnSatIndex")]
   UnSatIndex(&'static str, usize),
+  /// returned when data produced against other public parameters is supplied
+  #[error("ParamsDigestMismatch")]
+  ParamsDigestMismatch,
+  /// returned when registering circuits, or a name, that are already
+  /// registered
+  #[error("CircuitVersionExists")]
+  CircuitVersionExists,
+  /// returned when approving a manifest that is already approved
+  #[error("ManifestExists")]
+  ManifestExists,
+  /// returned when verifying a proof for a manifest that is not approved
+  #[error("UnapprovedManifest")]
+  UnapprovedManifest,
 }
@@ -0,0 +1,313 @@
+//! Rough resource estimates for SuperNova proving.
+//!
+//! The memory estimates are derived from the R1CS shape sizes alone, so they can be computed right
+//! after setup (or after loading [`PublicParams`]) and before any proving starts. They model the
+//! large vectors the prover allocates (witnesses, error vectors, matrix-vector products, Spartan's
+//! polynomials) and ignore everything of size `O(1)` or `O(arity)`; treat them as a lower bound on
+//! the peak resident set size, accurate to within a small constant factor.
+//!
+//! The time estimates count MSM points and field multiplications in the same way and price them
+//! with a [`DeviceProfile`] measured on the device itself.
+
+use std::{mem::size_of, time::Duration};
+
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+use super::{snark::CompressedSNARK, BatchUnit, PublicParams, StepCircuit};
+use crate::{
+  bellpepper::shape_cs::ShapeCS,
+  errors::NovaError,
+  r1cs::{R1CSShape, SparseMatrix},
+  traits::{
+    commitment::{CommitmentEngineTrait, Len},
+    snark::{BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
+    CurveCycleEquipped, Dual, Engine,
+  },
+  CommitmentKey,
+};
+
+/// Number of scalar vectors of length `num_cons` alive at once while folding: `Az`, `Bz`, `Cz` of
+/// both the running and the new instance, the four cross terms, and `T`.
+const FOLD_VECTORS: usize = 11;
+
+/// Number of scalar vectors of the padded size alive at once while proving a Spartan instance:
+/// the padded witness and error, `Az`, `Bz`, `Cz`, the evaluation tables of `A`, `B`, `C` (twice
+/// the size each), the equality polynomial, and the sum-check's bound copies.
+const SPARTAN_VECTORS: usize = 16;
+
+/// An estimate of the memory used by a prover, in bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryEstimate {
+  /// memory held for the whole proving session: parameters, keys and running witnesses
+  pub resident:  usize,
+  /// memory additionally allocated, and released, while proving
+  pub transient: usize,
+}
+
+impl MemoryEstimate {
+  /// The estimated peak memory usage
+  pub fn peak(&self) -> usize { self.resident + self.transient }
+}
+
+fn matrix_bytes<E: Engine>(M: &SparseMatrix<E::Scalar>) -> usize {
+  M.data.len() * size_of::<E::Scalar>()
+    + M.indices.len() * size_of::<usize>()
+    + M.indptr.len() * size_of::<usize>()
+}
+
+fn shape_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  matrix_bytes::<E>(&S.A) + matrix_bytes::<E>(&S.B) + matrix_bytes::<E>(&S.C)
+}
+
+/// Commitment keys hold affine points, i.e. two base field elements each.
+fn ck_bytes<E: Engine>(ck: &CommitmentKey<E>) -> usize { ck.length() * 2 * size_of::<E::Base>() }
+
+/// `W` and `E` of a relaxed witness
+fn relaxed_witness_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  (S.num_vars + S.num_cons) * size_of::<E::Scalar>()
+}
+
+/// Witness synthesis plus folding a fresh instance into a running one
+fn step_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  (S.num_vars + S.num_io + 1 + FOLD_VECTORS * S.num_cons) * size_of::<E::Scalar>()
+}
+
+/// Proving a single relaxed instance with Spartan
+fn spartan_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  let n = S.num_cons.max(S.num_vars).next_power_of_two();
+  SPARTAN_VECTORS * n * size_of::<E::Scalar>()
+}
+
+impl<E1> PublicParams<E1>
+where E1: CurveCycleEquipped
+{
+  /// The shape of the primary circuit `circuit_index`, which fails unless it
+  /// is a circuit of these parameters
+  fn primary_shape(&self, circuit_index: usize) -> Result<&R1CSShape<E1>, NovaError> {
+    let shape = self.circuit_shapes.get(circuit_index).ok_or(NovaError::InvalidIndex)?;
+    Ok(&shape.r1cs_shape)
+  }
+
+  /// Memory held by these parameters
+  fn params_bytes(&self) -> usize {
+    self.circuit_shapes.iter().map(|c| shape_bytes(&c.r1cs_shape)).sum::<usize>()
+      + shape_bytes(&self.circuit_shape_secondary.r1cs_shape)
+      + ck_bytes(&self.ck_primary)
+      + ck_bytes(&self.ck_secondary)
+  }
+
+  /// Memory held by a [`super::RecursiveSNARK`] once every circuit has been
+  /// run at least once
+  pub(super) fn running_bytes(&self) -> usize {
+    let secondary = &self.circuit_shape_secondary.r1cs_shape;
+    self.circuit_shapes.iter().map(|c| relaxed_witness_bytes(&c.r1cs_shape)).sum::<usize>()
+      + relaxed_witness_bytes(secondary)
+      + secondary.num_vars * size_of::<<Dual<E1> as Engine>::Scalar>()
+  }
+
+  /// Estimates the memory needed to prove a step of `circuit` with a
+  /// [`super::RecursiveSNARK`] over these parameters, which fails unless
+  /// `circuit.circuit_index()` is a circuit of these parameters.
+  pub fn estimate_memory<C: StepCircuit<E1::Scalar>>(
+    &self,
+    circuit: &C,
+  ) -> Result<MemoryEstimate, NovaError> {
+    let primary = step_bytes(self.primary_shape(circuit.circuit_index())?);
+    let secondary = step_bytes(&self.circuit_shape_secondary.r1cs_shape);
+    Ok(MemoryEstimate {
+      resident:  self.params_bytes() + self.running_bytes(),
+      // the primary and secondary circuits are folded one after the other
+      transient: primary.max(secondary),
+    })
+  }
+}
+
+impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  /// Estimates the memory needed to compress a [`super::RecursiveSNARK`]
+  /// over `pp` with [`CompressedSNARK::prove`]. The prover key, whose size
+  /// depends on `S1` and `S2`, comes on top of this.
+  pub fn estimate_memory(pp: &PublicParams<E1>) -> MemoryEstimate {
+    // the batched primary SNARK works on all circuits at once, then the secondary SNARK runs
+    let primary = pp.circuit_shapes.iter().map(|c| spartan_bytes(&c.r1cs_shape)).sum::<usize>();
+    let secondary = spartan_bytes(&pp.circuit_shape_secondary.r1cs_shape);
+    MemoryEstimate {
+      resident:  pp.params_bytes() + pp.running_bytes(),
+      transient: primary.max(secondary),
+    }
+  }
+}
+
+/// Number of points in the calibration MSM
+const CALIBRATION_MSM_SIZE: usize = 1 << 10;
+
+/// Number of multiplications in the calibration field-mul loop
+const CALIBRATION_MULS: usize = 1 << 16;
+
+/// Field multiplications Spartan performs per entry of a padded vector (sum-checks, evaluation
+/// tables, polynomial binding).
+const SPARTAN_MULS_PER_ENTRY: usize = 40;
+
+/// MSM points the polynomial commitment opening costs per entry of a padded vector.
+const PCS_POINTS_PER_ENTRY: usize = 4;
+
+/// The speed of the current device, measured with
+/// [`DeviceProfile::calibrate`] and used by
+/// [`PublicParams::estimate_prove_time`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+  /// nanoseconds per point of a multi-scalar multiplication
+  pub msm_ns_per_point: f64,
+  /// nanoseconds per field multiplication
+  pub field_mul_ns:     f64,
+}
+
+impl DeviceProfile {
+  /// Runs one small MSM and one field-mul loop over `E`, timing them with `clock`,
+  /// which returns the time elapsed since an arbitrary fixed origin. Use this
+  /// on targets without [`std::time::Instant`], e.g. with `performance.now()`
+  /// in the browser.
+  pub fn calibrate_with<E: Engine>(mut clock: impl FnMut() -> Duration) -> Self {
+    let ck = E::CE::setup(b"calibration", CALIBRATION_MSM_SIZE);
+
+    let start = clock();
+    let mut acc = E::Scalar::from(7);
+    let scalars = (0..CALIBRATION_MSM_SIZE)
+      .map(|_| {
+        acc = acc.square() + E::Scalar::ONE;
+        acc
+      })
+      .collect::<Vec<_>>();
+    let mut x = acc;
+    for _ in 0..CALIBRATION_MULS {
+      x *= acc;
+    }
+    let field_time = clock() - start;
+
+    let start = clock();
+    let comm = E::CE::commit(&ck, &scalars);
+    let msm_time = clock() - start;
+
+    // keep the optimizer from discarding the work
+    std::hint::black_box((x, comm));
+
+    Self {
+      msm_ns_per_point: msm_time.as_nanos() as f64 / CALIBRATION_MSM_SIZE as f64,
+      field_mul_ns:     field_time.as_nanos() as f64
+        / (CALIBRATION_MULS + CALIBRATION_MSM_SIZE) as f64,
+    }
+  }
+
+  /// Like [`DeviceProfile::calibrate_with`], using [`std::time::Instant`]
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn calibrate<E: Engine>() -> Self {
+    let origin = std::time::Instant::now();
+    Self::calibrate_with::<E>(|| origin.elapsed())
+  }
+
+  pub(crate) fn time(&self, msm_points: usize, field_muls: usize) -> Duration {
+    let ns = self.msm_ns_per_point * msm_points as f64 + self.field_mul_ns * field_muls as f64;
+    Duration::from_nanos(ns as u64)
+  }
+
+  /// Committing to a witness of `num_vars` variables and to the cross term
+  /// of `num_cons` constraints of `nnz` entries, and computing `Az`, `Bz`,
+  /// `Cz` for both instances being folded
+  fn fold_time(&self, num_vars: usize, num_cons: usize, nnz: usize) -> Duration {
+    self.time(num_vars + num_cons, 2 * nnz + FOLD_VECTORS * num_cons)
+  }
+
+  /// The number of units of `unit`, from 1 to `max_batch`, whose slots in a
+  /// [`BatchStep`](super::BatchStep) fold in at most `target` on this device, to make batch
+  /// steps of: the time of the rest of a step, of the augmented circuits,
+  /// is left out of `target`
+  pub fn batch_size<E: Engine, U: BatchUnit<E::Scalar>>(
+    &self,
+    unit: &U,
+    target: Duration,
+    max_batch: usize,
+  ) -> usize {
+    let mut cs = ShapeCS::<E>::new();
+    let z = (0..unit.arity())
+      .map(|i| AllocatedNum::alloc(cs.namespace(|| format!("z {i}")), || Ok(E::Scalar::ZERO)))
+      .collect::<Result<Vec<_>, _>>();
+    if z.and_then(|z| unit.synthesize(&mut cs.namespace(|| "unit"), &z)).is_err() {
+      return 1;
+    }
+    // the enabled bit, and the selection of each output
+    let num_cons = cs.num_constraints() + unit.arity() + 2;
+    let num_vars = cs.num_aux() + unit.arity() + 1;
+    let nnz =
+      cs.constraints.iter().map(|(a, b, c)| a.iter().count() + b.iter().count() + c.iter().count());
+    let nnz = nnz.sum::<usize>() + 4 * unit.arity() + 2;
+
+    let per_unit = self.fold_time(num_vars, num_cons, nnz).as_nanos().max(1);
+    usize::try_from(target.as_nanos() / per_unit).unwrap_or(usize::MAX).clamp(1, max_batch.max(1))
+  }
+}
+
+/// An estimate of proving times
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeEstimate {
+  /// time of one [`super::RecursiveSNARK::prove_step`]
+  pub per_step:    Duration,
+  /// time of [`CompressedSNARK::prove`]
+  pub compression: Duration,
+}
+
+impl TimeEstimate {
+  /// The estimated time to run `num_steps` steps and compress the result,
+  /// saturating at `u64::MAX` nanoseconds
+  pub fn total(&self, num_steps: usize) -> Duration {
+    let ns = self
+      .per_step
+      .as_nanos()
+      .saturating_mul(num_steps as u128)
+      .saturating_add(self.compression.as_nanos());
+    Duration::from_nanos(u64::try_from(ns).unwrap_or(u64::MAX))
+  }
+}
+
+/// Committing to the witness and the cross term, and computing `Az`, `Bz`, `Cz` for both
+/// instances being folded
+fn step_time<E: Engine>(S: &R1CSShape<E>, profile: &DeviceProfile) -> Duration {
+  profile.fold_time(S.num_vars, S.num_cons, S.A.len() + S.B.len() + S.C.len())
+}
+
+fn spartan_time<E: Engine>(S: &R1CSShape<E>, profile: &DeviceProfile) -> Duration {
+  let n = S.num_cons.max(S.num_vars).next_power_of_two();
+  let nnz = S.A.len() + S.B.len() + S.C.len();
+  profile.time(PCS_POINTS_PER_ENTRY * n, SPARTAN_MULS_PER_ENTRY * n + 3 * nnz)
+}
+
+impl<E1> PublicParams<E1>
+where E1: CurveCycleEquipped
+{
+  /// Estimates the time to prove a step of `circuit` and to compress the
+  /// result on the device described by `profile`. The same profile is used
+  /// for both curves of the cycle, whose arithmetic costs about the same.
+  /// Fails unless `circuit.circuit_index()` is a circuit of these parameters.
+  pub fn estimate_prove_time<C: StepCircuit<E1::Scalar>>(
+    &self,
+    circuit: &C,
+    profile: &DeviceProfile,
+  ) -> Result<TimeEstimate, NovaError> {
+    let secondary = &self.circuit_shape_secondary.r1cs_shape;
+    let primary = self.primary_shape(circuit.circuit_index())?;
+    Ok(TimeEstimate {
+      per_step:    step_time(primary, profile) + step_time(secondary, profile),
+      compression: self
+        .circuit_shapes
+        .iter()
+        .map(|c| spartan_time(&c.r1cs_shape, profile))
+        .sum::<Duration>()
+        + spartan_time(secondary, profile),
+    })
+  }
+}
@@ -1,30 +1,38 @@
 #![doc = include_str!("./Readme.md")]
 
+#[cfg(feature = "prove")] use std::collections::HashMap;
 use std::{ops::Index, sync::Arc};
 
-use bellpepper_core::{ConstraintSystem, SynthesisError};
-use ff::Field;
+#[cfg(feature = "prove")]
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+#[cfg(feature = "prove")] use ff::Field;
 use itertools::Itertools as _;
 use once_cell::sync::OnceCell;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
+#[cfg(feature = "prove")]
 use crate::{
   bellpepper::{
     r1cs::{NovaShape, NovaWitness},
     shape_cs::ShapeCS,
     solver::SatisfyingAssignment,
   },
+  nifs::NIFS,
+  profiling::Phase,
+  r1cs::{commitment_key_size, keys::CommitmentKeyCache, CommitmentKeyHint},
+};
+use crate::{
   constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_HASH_BITS},
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
-  nifs::NIFS,
+  parallel::{self, prelude::*},
   r1cs::{
-    self, commitment_key_size, CommitmentKeyHint, R1CSInstance, R1CSResult, R1CSShape, R1CSWitness,
-    RelaxedR1CSInstance, RelaxedR1CSWitness,
+    self, keys::CommitmentKeys, CommitmentKeySpec, R1CSInstance, R1CSResult, R1CSShape,
+    R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness,
   },
   scalar_as_base,
+  secret::{wipe, Zeroize},
   traits::{
     commitment::{CommitmentEngineTrait, CommitmentTrait},
     AbsorbInROTrait, CurveCycleEquipped, Dual, Engine, ROConstants, ROConstantsCircuit, ROTrait,
@@ -33,9 +41,24 @@ use crate::{
 };
 
 mod circuit; // declare the module first
-pub use circuit::{StepCircuit, SuperNovaAugmentedCircuitParams, TrivialCircuit};
+pub use circuit::SuperNovaAugmentedCircuitParams;
+#[cfg(feature = "prove")]
+pub use circuit::{StepCircuit, TrivialCircuit};
+#[cfg(feature = "prove")] mod advice;
+#[cfg(feature = "prove")]
+pub use advice::{AdvisedCircuit, AdvisedStep, StepAdvice};
+#[cfg(feature = "prove")] mod batch;
+#[cfg(feature = "prove")]
+pub use batch::{BatchStep, BatchUnit};
+#[cfg(feature = "prove")] mod cache;
+#[cfg(feature = "prove")] pub use cache::WitnessCache;
+#[cfg(feature = "prove")] use cache::{CachedStep, StepKey};
+#[cfg(feature = "prove")] pub(crate) mod pipeline;
+#[cfg(feature = "prove")]
 use circuit::{SuperNovaAugmentedCircuit, SuperNovaAugmentedCircuitInputs};
 use error::SuperNovaError;
+#[cfg(feature = "prove")]
+use pipeline::{CommittedStep, Probe};
 
 /// A struct that manages all the digests of the primary circuits of a SuperNova
 /// instance
@@ -57,6 +80,8 @@ impl<E: Engine> CircuitDigests<E> {
   pub fn new(digests: Vec<E::Scalar>) -> Self { Self { digests } }
 
   /// Return the [`CircuitDigests`]' digest.
+  // serializing the digests into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E::Scalar {
     let dc: DigestComputer<'_, <E as Engine>::Scalar, Self> = DigestComputer::new(self);
     dc.digest().expect("Failure in computing digest")
@@ -115,6 +140,27 @@ use crate::{
   fast_serde::{FastSerde, SerdeByteError, SerdeByteTypes},
 };
 
+impl<E1> AuxParams<E1>
+where E1: CurveCycleEquipped
+{
+  /// Returns a compact description of the secondary commitment key
+  pub fn secondary_ck_spec(&self) -> Result<CommitmentKeySpec<Dual<E1>>, NovaError> {
+    CommitmentKeySpec::new(&self.ck_secondary)
+  }
+
+  /// Replaces the secondary commitment key with the one derived from `spec`.
+  ///
+  /// This lets a client download only the primary commitment key and derive
+  /// the much smaller secondary one locally.
+  pub fn with_derived_secondary_ck(
+    mut self,
+    spec: &CommitmentKeySpec<Dual<E1>>,
+  ) -> Result<Self, NovaError> {
+    self.ck_secondary = Arc::new(spec.derive()?);
+    Ok(self)
+  }
+}
+
 impl<E1> FastSerde for AuxParams<E1>
 where
   E1: CurveCycleEquipped,
@@ -138,6 +184,8 @@ where
   ///   6: ck_secondary (FastSerde)
   ///   7: circuit_shape_secondary (json)
   ///   8: digest (bincode)
+  // serializing into memory does not fail
+  #[allow(clippy::expect_used)]
   fn to_bytes(&self) -> Vec<u8> {
     let mut out = Vec::new();
 
@@ -147,26 +195,41 @@ where
     out.push(8); // num_sections
 
     // Write sections
-    Self::write_section_bytes(&mut out, 1, &bincode::serialize(&self.ro_consts_primary).unwrap());
+    Self::write_section_bytes(
+      &mut out,
+      1,
+      &bincode::serialize(&self.ro_consts_primary).expect("serializing to memory does not fail"),
+    );
     Self::write_section_bytes(
       &mut out,
       2,
-      &bincode::serialize(&self.ro_consts_circuit_primary).unwrap(),
+      &bincode::serialize(&self.ro_consts_circuit_primary)
+        .expect("serializing to memory does not fail"),
     );
     Self::write_section_bytes(&mut out, 3, &self.ck_primary.to_bytes());
-    Self::write_section_bytes(&mut out, 4, &bincode::serialize(&self.ro_consts_secondary).unwrap());
+    Self::write_section_bytes(
+      &mut out,
+      4,
+      &bincode::serialize(&self.ro_consts_secondary).expect("serializing to memory does not fail"),
+    );
     Self::write_section_bytes(
       &mut out,
       5,
-      &bincode::serialize(&self.ro_consts_circuit_secondary).unwrap(),
+      &bincode::serialize(&self.ro_consts_circuit_secondary)
+        .expect("serializing to memory does not fail"),
     );
     Self::write_section_bytes(&mut out, 6, &self.ck_secondary.to_bytes());
     Self::write_section_bytes(
       &mut out,
       7,
-      &bincode::serialize(&self.circuit_shape_secondary).unwrap(),
+      &bincode::serialize(&self.circuit_shape_secondary)
+        .expect("serializing to memory does not fail"),
+    );
+    Self::write_section_bytes(
+      &mut out,
+      8,
+      &bincode::serialize(&self.digest).expect("serializing to memory does not fail"),
     );
-    Self::write_section_bytes(&mut out, 8, &bincode::serialize(&self.digest).unwrap());
 
     out
   }
@@ -255,11 +318,26 @@ where E1: CurveCycleEquipped
   ///   number of generators required in the commitment scheme for the primary circuit.
   /// * `ck_hint2`: A `CommitmentKeyHint` for `E2`, similar to `ck_hint1`, but for the secondary
   ///   circuit.
+  #[cfg(feature = "prove")]
   pub fn setup<NC: NonUniformCircuit<E1>>(
     non_uniform_circuit: &NC,
     ck_hint1: &CommitmentKeyHint<E1>,
     ck_hint2: &CommitmentKeyHint<Dual<E1>>,
   ) -> Self {
+    Self::setup_with_cache(non_uniform_circuit, ck_hint1, ck_hint2, &())
+  }
+
+  #[cfg(feature = "prove")]
+  /// Same as [`PublicParams::setup`], loading the commitment keys from
+  /// `cache` if it holds them, and storing them in it otherwise (see
+  /// [`CommitmentKeys::setup`])
+  pub fn setup_with_cache<NC: NonUniformCircuit<E1>>(
+    non_uniform_circuit: &NC,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<Dual<E1>>,
+    cache: &dyn CommitmentKeyCache,
+  ) -> Self {
+    let _phase = Phase::enter("supernova::PublicParams::setup");
     let num_circuits = non_uniform_circuit.num_circuits();
 
     let augmented_circuit_params_primary =
@@ -272,9 +350,6 @@ where E1: CurveCycleEquipped
 
     let circuit_shapes = get_circuit_shapes(non_uniform_circuit);
 
-    let ck_primary = Self::compute_primary_ck(&circuit_shapes, ck_hint1);
-    let ck_primary = Arc::new(ck_primary);
-
     let augmented_circuit_params_secondary =
       SuperNovaAugmentedCircuitParams::new(BN_LIMB_WIDTH, BN_N_LIMBS, false);
     let ro_consts_secondary = ROConstants::<Dual<E1>>::default();
@@ -291,9 +366,19 @@ where E1: CurveCycleEquipped
         num_circuits,
       );
     let mut cs: ShapeCS<Dual<E1>> = ShapeCS::new();
+    // a circuit whose shape cannot be synthesized is a bug of the circuit
+    #[allow(clippy::expect_used)]
     circuit_secondary.synthesize(&mut cs).expect("circuit synthesis failed");
-    let (r1cs_shape_secondary, ck_secondary) = cs.r1cs_shape_and_key(ck_hint2);
-    let ck_secondary = Arc::new(ck_secondary);
+    let r1cs_shape_secondary = cs.r1cs_shape();
+    // the progress callback never interrupts the setup
+    #[allow(clippy::expect_used)]
+    let CommitmentKeys { primary: ck_primary, secondary: ck_secondary } = CommitmentKeys::setup(
+      Self::primary_ck_size(&circuit_shapes, ck_hint1),
+      commitment_key_size(&r1cs_shape_secondary, ck_hint2),
+      &|_: &str, _: usize, _: usize| true,
+      cache,
+    )
+    .expect("the setup is not interrupted");
     let circuit_shape_secondary = R1CSWithArity::new(r1cs_shape_secondary, F_arity_secondary);
 
     let pp = Self {
@@ -414,22 +499,36 @@ where E1: CurveCycleEquipped
     }
   }
 
-  /// Compute primary and secondary commitment keys sized to handle the
-  /// largest of the circuits in the provided `R1CSWithArity`.
-  fn compute_primary_ck(
+  #[cfg(feature = "prove")]
+  /// The size of the primary commitment key, to handle the largest of the
+  /// circuits in the provided `R1CSWithArity`.
+  // a setup has at least one circuit
+  #[allow(clippy::expect_used)]
+  fn primary_ck_size(
     circuit_params: &[R1CSWithArity<E1>],
     ck_hint1: &CommitmentKeyHint<E1>,
-  ) -> CommitmentKey<E1> {
-    let size_primary = circuit_params
+  ) -> usize {
+    circuit_params
       .iter()
       .map(|circuit| commitment_key_size(&circuit.r1cs_shape, ck_hint1))
       .max()
-      .unwrap();
+      .expect("there is at least one circuit")
+  }
+
+  /// The commitment keys of the primary and secondary circuits
+  pub fn commitment_keys(&self) -> CommitmentKeys<E1> {
+    CommitmentKeys { primary: self.ck_primary.clone(), secondary: self.ck_secondary.clone() }
+  }
 
-    E1::CE::setup(b"ck", size_primary)
+  /// Checks the commitment keys against the digest binding them together
+  /// (see [`CommitmentKeys::digest`]), e.g. a published one
+  pub fn check_commitment_keys(&self, digest: &E1::Scalar) -> Result<(), NovaError> {
+    self.commitment_keys().check(digest)
   }
 
   /// Return the [`PublicParams`]' digest.
+  // serializing the parameters into the hasher does not fail
+  #[allow(clippy::expect_used)]
   pub fn digest(&self) -> E1::Scalar {
     self
       .digest
@@ -455,18 +554,29 @@ where E1: CurveCycleEquipped
     )
   }
 
+  /// Returns a compact description of the secondary commitment key, from
+  /// which it can be re-derived without shipping the key itself
+  pub fn secondary_ck_spec(&self) -> Result<CommitmentKeySpec<Dual<E1>>, NovaError> {
+    CommitmentKeySpec::new(&self.ck_secondary)
+  }
+
   /// All of the primary circuit digests of this [`PublicParams`]
   pub fn circuit_param_digests(&self) -> CircuitDigests<E1> {
     let digests = self.circuit_shapes.iter().map(|cp| cp.digest()).collect::<Vec<_>>();
     CircuitDigests { digests }
   }
 
+  /// The digest of all of the primary circuits of this [`PublicParams`],
+  /// which identifies their version in a [`CircuitRegistry`]
+  pub fn circuits_digest(&self) -> E1::Scalar { self.circuit_param_digests().digest() }
+
   /// Returns all the primary R1CS Shapes
   fn primary_r1cs_shapes(&self) -> Vec<&R1CSShape<E1>> {
     self.circuit_shapes.iter().map(|cs| &cs.r1cs_shape).collect::<Vec<_>>()
   }
 }
 
+#[cfg(feature = "prove")]
 pub fn get_circuit_shapes<E1: CurveCycleEquipped, NC: NonUniformCircuit<E1>>(
   non_uniform_circuit: &NC,
 ) -> Vec<R1CSWithArity<E1>> {
@@ -493,6 +603,8 @@ pub fn get_circuit_shapes<E1: CurveCycleEquipped, NC: NonUniformCircuit<E1>>(
           num_circuits,
         );
       let mut cs: ShapeCS<E1> = ShapeCS::new();
+      // a circuit whose shape cannot be synthesized is a bug of the circuit
+      #[allow(clippy::expect_used)]
       circuit_primary.synthesize(&mut cs).expect("circuit synthesis failed");
 
       // We use the largest commitment_key for all instances
@@ -558,12 +670,40 @@ where E1: CurveCycleEquipped {
   // Proof for the secondary circuit to be accumulated into r_secondary in the next iteration
   l_w_secondary: R1CSWitness<Dual<E1>>,
   l_u_secondary: R1CSInstance<Dual<E1>>,
+
+  /// Cache of the primary witnesses, shared with the clones of this
+  #[cfg(feature = "prove")]
+  #[serde(skip)]
+  witness_cache: Option<Arc<WitnessCache<E1>>>,
+}
+
+/// Wipes the witnesses, the buffers and the inputs and outputs of the
+/// computation, e.g. once it is compressed, and unsets its witness cache,
+/// which is shared and wiped by [`WitnessCache::clear`]; the `RecursiveSNARK`
+/// no longer proves steps afterwards
+impl<E1: CurveCycleEquipped> Zeroize for RecursiveSNARK<E1> {
+  fn zeroize(&mut self) {
+    wipe(&mut self.z0_primary);
+    wipe(&mut self.zi_primary);
+    self.buffer_primary.zeroize();
+    self.buffer_secondary.zeroize();
+    self.r_W_primary.iter_mut().flatten().for_each(Zeroize::zeroize);
+    wipe(&mut self.z0_secondary);
+    wipe(&mut self.zi_secondary);
+    self.r_W_secondary.zeroize();
+    self.l_w_secondary.zeroize();
+    #[cfg(feature = "prove")]
+    {
+      self.witness_cache = None;
+    }
+  }
 }
 
 impl<E1> RecursiveSNARK<E1>
 where E1: CurveCycleEquipped
 {
   /// iterate base step to get new instance of recursive SNARK
+  #[cfg(feature = "prove")]
   #[allow(clippy::too_many_arguments)]
   pub fn new<C0: NonUniformCircuit<E1>>(
     pp: &PublicParams<E1>,
@@ -689,11 +829,9 @@ where E1: CurveCycleEquipped
       .iter()
       .map(|v| v.get_value().ok_or(NovaError::from(SynthesisError::AssignmentMissing).into()))
       .collect::<Result<Vec<<E1 as Engine>::Scalar>, SuperNovaError>>()?;
-    let zi_primary_pc_next =
-      zi_primary_pc_next
-        .expect("zi_primary_pc_next missing")
-        .get_value()
-        .ok_or::<SuperNovaError>(NovaError::from(SynthesisError::AssignmentMissing).into())?;
+    let zi_primary_pc_next = zi_primary_pc_next
+      .and_then(|pc| pc.get_value())
+      .ok_or::<SuperNovaError>(NovaError::from(SynthesisError::AssignmentMissing).into())?;
     let zi_secondary = zi_secondary
       .iter()
       .map(|v| v.get_value().ok_or(NovaError::from(SynthesisError::AssignmentMissing).into()))
@@ -709,8 +847,14 @@ where E1: CurveCycleEquipped
       .collect::<Vec<Option<RelaxedR1CSInstance<E1>>>>();
 
     // find the largest length r1cs shape for the buffer size
-    let max_num_cons =
-      pp.circuit_shapes.iter().map(|circuit| circuit.r1cs_shape.num_cons).max().unwrap();
+    // a setup has at least one circuit
+    #[allow(clippy::expect_used)]
+    let max_num_cons = pp
+      .circuit_shapes
+      .iter()
+      .map(|circuit| circuit.r1cs_shape.num_cons)
+      .max()
+      .expect("there is at least one circuit");
 
     let buffer_primary = ResourceBuffer {
       l_w:     None,
@@ -749,9 +893,20 @@ where E1: CurveCycleEquipped
       r_U_secondary,
       l_w_secondary,
       l_u_secondary,
+      witness_cache: None,
     })
   }
 
+  #[cfg(feature = "prove")]
+  /// Sets the cache in which `prove_step` looks for the primary witness of a
+  /// step before synthesizing it, and stores the witnesses it synthesizes
+  pub fn set_witness_cache(&mut self, witness_cache: Option<Arc<WitnessCache<E1>>>) {
+    self.witness_cache = witness_cache;
+  }
+
+  /// The digest of the [`PublicParams`] this was made against
+  pub fn pp_digest(&self) -> E1::Scalar { self.pp_digest }
+
   /// Inputs of the primary circuits
   pub fn z0_primary(&self) -> &Vec<E1::Scalar> { &self.z0_primary }
 
@@ -768,6 +923,7 @@ where E1: CurveCycleEquipped
   pub fn program_counter(&self) -> E1::Scalar { self.program_counter }
 
   /// executing a step of the incremental computation
+  #[cfg(feature = "prove")]
   #[allow(clippy::too_many_arguments)]
   #[tracing::instrument(skip_all, name = "supernova::RecursiveSNARK::prove_step")]
   pub fn prove_step<C1: StepCircuit<E1::Scalar>, C2: StepCircuit<<Dual<E1> as Engine>::Scalar>>(
@@ -776,12 +932,96 @@ where E1: CurveCycleEquipped
     c_primary: &C1,
     c_secondary: &C2,
   ) -> Result<(), SuperNovaError> {
+    let _phase = Phase::enter("supernova::RecursiveSNARK::prove_step");
     // First step was already done in the constructor
     if self.i == 0 {
       self.i = 1;
       return Ok(());
     }
+    self.fold_step(pp, c_primary, c_secondary, None)
+  }
+
+  /// Executes the steps of `steps` in turn, as many calls to
+  /// [`RecursiveSNARK::prove_step`] would, but pipelined across steps (see
+  /// [`parallel::pipeline`]): the primary step circuit of a step runs while
+  /// the witness of the previous step is committed to, and that of the step
+  /// before is folded. The steps bypass the witness cache.
+  #[cfg(feature = "prove")]
+  #[tracing::instrument(skip_all, name = "supernova::RecursiveSNARK::prove_steps")]
+  pub fn prove_steps<'a, C1, C2, I>(
+    &mut self,
+    pp: &PublicParams<E1>,
+    steps: I,
+  ) -> Result<(), SuperNovaError>
+  where
+    C1: StepCircuit<E1::Scalar> + 'a,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar> + 'a,
+    I: IntoIterator<Item = (&'a C1, &'a C2)>,
+    I::IntoIter: Send,
+  {
+    let _phase = Phase::enter("supernova::RecursiveSNARK::prove_steps");
+    let mut steps = steps.into_iter();
+    if self.i == 0 {
+      match steps.next() {
+        Some((c_primary, c_secondary)) => self.prove_step(pp, c_primary, c_secondary)?,
+        None => return Ok(()),
+      }
+    }
 
+    let num_augmented_circuits = self.num_augmented_circuits;
+    let mut program_counter = self.program_counter;
+    let mut zi_primary = self.zi_primary.clone();
+    // where the witness of each step circuit starts in that of its augmented circuit
+    let mut offsets = HashMap::new();
+    parallel::pipeline(
+      steps,
+      |(c_primary, c_secondary)| -> Result<_, SuperNovaError> {
+        let (witness, pc_next, zi_next) =
+          pipeline::record(c_primary, Some(program_counter), &zi_primary)
+            .map_err(NovaError::from)?;
+        program_counter = pc_next
+          .ok_or::<SuperNovaError>(NovaError::from(SynthesisError::AssignmentMissing).into())?;
+        zi_primary = zi_next;
+        Ok((c_primary, c_secondary, witness))
+      },
+      |(c_primary, c_secondary, witness)| -> Result<_, SuperNovaError> {
+        let circuit_index = c_primary.circuit_index();
+        let offset = match offsets.get(&circuit_index) {
+          Some(offset) => *offset,
+          None => {
+            let probe = Probe::new(c_primary.arity(), circuit_index);
+            let circuit_probe: SuperNovaAugmentedCircuit<'_, Dual<E1>, Probe> =
+              SuperNovaAugmentedCircuit::new(
+                &pp.augmented_circuit_params_primary,
+                None,
+                &probe,
+                pp.ro_consts_circuit_primary.clone(),
+                num_augmented_circuits,
+              );
+            let _ = circuit_probe.synthesize(&mut ShapeCS::<E1>::new());
+            *offsets.entry(circuit_index).or_insert(probe.offset()?)
+          },
+        };
+        Ok((c_primary, c_secondary, CommittedStep::new(&pp.ck_primary, witness, offset)))
+      },
+      |(c_primary, c_secondary, mut committed)| {
+        let folded = self.fold_step(pp, c_primary, c_secondary, Some(&committed));
+        committed.zeroize();
+        folded
+      },
+    )
+  }
+
+  #[cfg(feature = "prove")]
+  /// Folds the next step, its primary step circuit replaying the witness of
+  /// `committed` if any
+  fn fold_step<C1: StepCircuit<E1::Scalar>, C2: StepCircuit<<Dual<E1> as Engine>::Scalar>>(
+    &mut self,
+    pp: &PublicParams<E1>,
+    c_primary: &C1,
+    c_secondary: &C2,
+    committed: Option<&CommittedStep<E1>>,
+  ) -> Result<(), SuperNovaError> {
     // save the inputs before proceeding to the `i+1`th step
     let r_U_primary_i = self.r_U_primary.clone();
     // Create single-entry accumulator list for the secondary circuit to hand to
@@ -792,14 +1032,41 @@ where E1: CurveCycleEquipped
     let circuit_index = c_primary.circuit_index();
     assert_eq!(self.program_counter, E1::Scalar::from(circuit_index as u64));
 
+    // look for the primary witness of this step in the cache, before the
+    // secondary instance is folded, unless it was synthesized ahead
+    let cache_key = self.witness_cache.as_ref().filter(|_| committed.is_none()).and_then(|_| {
+      let witness_digest = c_primary.witness_digest()?;
+      StepKey::<E1> {
+        pp_digest: &self.pp_digest,
+        i: self.i,
+        circuit_index,
+        program_counter: &self.program_counter,
+        z0_primary: &self.z0_primary,
+        zi_primary: &self.zi_primary,
+        r_U_secondary: &self.r_U_secondary,
+        l_u_secondary: &self.l_u_secondary,
+        witness_digest,
+      }
+      .hash()
+    });
+    let cached_step =
+      self.witness_cache.as_ref().zip(cache_key.as_ref()).and_then(|(c, k)| c.get(k));
+
+    // The step runs in two stages, each of which folds the witness of the
+    // previous stage while it synthesizes and commits: the folded witnesses
+    // are only read by the next fold of their circuit, whereas the circuits
+    // only read the folded instances. Steps themselves overlap in
+    // `prove_steps` only, which runs and commits to the primary step circuits
+    // of the next steps ahead of this.
+
     // fold the secondary circuit's instance
-    let (nifs_secondary, _) = NIFS::prove_mut(
+    let (nifs_secondary, fold_secondary) = NIFS::prove_instance_mut(
       &*pp.ck_secondary,
       &pp.ro_consts_secondary,
       &scalar_as_base::<E1>(self.pp_digest),
       &pp.circuit_shape_secondary.r1cs_shape,
       &mut self.r_U_secondary,
-      &mut self.r_W_secondary,
+      &self.r_W_secondary,
       &self.l_u_secondary,
       &self.l_w_secondary,
       &mut self.buffer_secondary.T,
@@ -808,60 +1075,57 @@ where E1: CurveCycleEquipped
     )
     .map_err(SuperNovaError::NovaError)?;
 
-    let mut cs_primary = SatisfyingAssignment::<E1>::with_capacity(
-      pp[circuit_index].r1cs_shape.num_io + 1,
-      pp[circuit_index].r1cs_shape.num_vars,
-    );
     let T = Commitment::<Dual<E1>>::decompress(&nifs_secondary.comm_T)
       .map_err(SuperNovaError::NovaError)?;
-    let inputs_primary: SuperNovaAugmentedCircuitInputs<'_, Dual<E1>> =
-      SuperNovaAugmentedCircuitInputs::new(
-        scalar_as_base::<E1>(self.pp_digest),
-        E1::Scalar::from(self.i as u64),
-        &self.z0_primary,
-        Some(&self.zi_primary),
-        Some(&r_U_secondary_i),
-        Some(&l_u_secondary_i),
-        Some(&T),
-        Some(self.program_counter),
-        E1::Scalar::ZERO,
-      );
-
-    let circuit_primary: SuperNovaAugmentedCircuit<'_, Dual<E1>, C1> =
-      SuperNovaAugmentedCircuit::new(
-        &pp.augmented_circuit_params_primary,
-        Some(inputs_primary),
-        c_primary,
-        pp.ro_consts_circuit_primary.clone(),
-        self.num_augmented_circuits,
-      );
-
-    let (zi_primary_pc_next, zi_primary) =
-      circuit_primary.synthesize(&mut cs_primary).map_err(NovaError::from)?;
-    if zi_primary.len() != pp[circuit_index].F_arity {
-      return Err(SuperNovaError::NovaError(NovaError::InvalidInitialInputLength));
-    }
-
-    let (l_u_primary, l_w_primary) = cs_primary
-      .r1cs_instance_and_witness(&pp[circuit_index].r1cs_shape, &pp.ck_primary)
-      .map_err(SuperNovaError::NovaError)?;
-
-    let (r_U_primary, r_W_primary) = if let (Some(Some(r_U_primary)), Some(Some(r_W_primary))) =
-      (self.r_U_primary.get_mut(circuit_index), self.r_W_primary.get_mut(circuit_index))
-    {
-      (r_U_primary, r_W_primary)
-    } else {
-      self.r_U_primary[circuit_index] =
-        Some(RelaxedR1CSInstance::default(&*pp.ck_primary, &pp[circuit_index].r1cs_shape));
-      self.r_W_primary[circuit_index] =
-        Some(RelaxedR1CSWitness::default(&pp[circuit_index].r1cs_shape));
-      (
-        self.r_U_primary[circuit_index].as_mut().unwrap(),
-        self.r_W_primary[circuit_index].as_mut().unwrap(),
-      )
-    };
 
-    let (nifs_primary, _) = NIFS::prove_mut(
+    // synthesize the primary circuit, unless cached, while folding the
+    // secondary witness, taken out of `self` which the synthesis reads
+    let mut r_W_secondary =
+      std::mem::replace(&mut self.r_W_secondary, RelaxedR1CSWitness { W: vec![], E: vec![] });
+    let (folded_secondary, step) = parallel::join(
+      || {
+        fold_secondary.fold_witness(
+          &pp.ck_secondary,
+          &pp.circuit_shape_secondary.r1cs_shape,
+          &self.r_U_secondary,
+          &mut r_W_secondary,
+          &self.l_u_secondary,
+          &self.l_w_secondary,
+          &self.buffer_secondary.T,
+        )
+      },
+      || -> Result<_, SuperNovaError> {
+        match cached_step {
+          Some(cached_step) => Ok(cached_step),
+          None => {
+            let cached_step = self.synthesize_primary(
+              pp,
+              c_primary,
+              &r_U_secondary_i,
+              &l_u_secondary_i,
+              &T,
+              committed,
+            )?;
+            if let (Some(cache), Some(key)) = (&self.witness_cache, cache_key) {
+              cache.insert(key, cached_step.clone());
+            }
+            Ok(cached_step)
+          },
+        }
+      },
+    );
+    self.r_W_secondary = r_W_secondary;
+    folded_secondary.map_err(SuperNovaError::NovaError)?;
+    let CachedStep { l_u_primary, l_w_primary, zi_primary, zi_primary_pc_next } = step?;
+
+    // the running instance of a circuit is the default one until it is first folded
+    let r_U_primary = self.r_U_primary[circuit_index].get_or_insert_with(|| {
+      RelaxedR1CSInstance::default(&*pp.ck_primary, &pp[circuit_index].r1cs_shape)
+    });
+    let r_W_primary = self.r_W_primary[circuit_index]
+      .get_or_insert_with(|| RelaxedR1CSWitness::default(&pp[circuit_index].r1cs_shape));
+
+    let (nifs_primary, fold_primary) = NIFS::prove_instance_mut(
       &*pp.ck_primary,
       &pp.ro_consts_primary,
       &self.pp_digest,
@@ -875,52 +1139,63 @@ where E1: CurveCycleEquipped
       &mut self.buffer_primary.ABC_Z_2,
     )
     .map_err(SuperNovaError::NovaError)?;
-
-    let mut cs_secondary = SatisfyingAssignment::<Dual<E1>>::with_capacity(
-      pp.circuit_shape_secondary.r1cs_shape.num_io + 1,
-      pp.circuit_shape_secondary.r1cs_shape.num_vars,
-    );
     let binding =
       Commitment::<E1>::decompress(&nifs_primary.comm_T).map_err(SuperNovaError::NovaError)?;
-    let inputs_secondary: SuperNovaAugmentedCircuitInputs<'_, E1> =
-      SuperNovaAugmentedCircuitInputs::new(
-        self.pp_digest,
-        <Dual<E1> as Engine>::Scalar::from(self.i as u64),
-        &self.z0_secondary,
-        Some(&self.zi_secondary),
-        Some(&r_U_primary_i),
-        Some(&l_u_primary),
-        Some(&binding),
-        None, // pc is always None for secondary circuit
-        <Dual<E1> as Engine>::Scalar::from(circuit_index as u64),
-      );
 
-    let circuit_secondary: SuperNovaAugmentedCircuit<'_, E1, C2> = SuperNovaAugmentedCircuit::new(
-      &pp.augmented_circuit_params_secondary,
-      Some(inputs_secondary),
-      c_secondary,
-      pp.ro_consts_circuit_secondary.clone(),
-      self.num_augmented_circuits,
-    );
-    let (_, zi_secondary) =
-      circuit_secondary.synthesize(&mut cs_secondary).map_err(NovaError::from)?;
-    if zi_secondary.len() != pp.circuit_shape_secondary.F_arity {
-      return Err(SuperNovaError::NovaError(NovaError::InvalidInitialInputLength));
-    }
+    // synthesize the secondary circuit, while folding the primary witness
+    let (folded_primary, secondary) = parallel::join(
+      || {
+        fold_primary.fold_witness(
+          &pp.ck_primary,
+          &pp[circuit_index].r1cs_shape,
+          r_U_primary,
+          r_W_primary,
+          &l_u_primary,
+          &l_w_primary,
+          &self.buffer_primary.T,
+        )
+      },
+      || -> Result<_, SuperNovaError> {
+        let mut cs_secondary = SatisfyingAssignment::<Dual<E1>>::with_capacity(
+          pp.circuit_shape_secondary.r1cs_shape.num_io + 1,
+          pp.circuit_shape_secondary.r1cs_shape.num_vars,
+        );
+        let inputs_secondary: SuperNovaAugmentedCircuitInputs<'_, E1> =
+          SuperNovaAugmentedCircuitInputs::new(
+            self.pp_digest,
+            <Dual<E1> as Engine>::Scalar::from(self.i as u64),
+            &self.z0_secondary,
+            Some(&self.zi_secondary),
+            Some(&r_U_primary_i),
+            Some(&l_u_primary),
+            Some(&binding),
+            None, // pc is always None for secondary circuit
+            <Dual<E1> as Engine>::Scalar::from(circuit_index as u64),
+          );
+
+        let circuit_secondary: SuperNovaAugmentedCircuit<'_, E1, C2> =
+          SuperNovaAugmentedCircuit::new(
+            &pp.augmented_circuit_params_secondary,
+            Some(inputs_secondary),
+            c_secondary,
+            pp.ro_consts_circuit_secondary.clone(),
+            self.num_augmented_circuits,
+          );
+        let (_, zi_secondary) =
+          circuit_secondary.synthesize(&mut cs_secondary).map_err(NovaError::from)?;
+        if zi_secondary.len() != pp.circuit_shape_secondary.F_arity {
+          return Err(SuperNovaError::NovaError(NovaError::InvalidInitialInputLength));
+        }
 
-    let (l_u_secondary_next, l_w_secondary_next) = cs_secondary
-      .r1cs_instance_and_witness(&pp.circuit_shape_secondary.r1cs_shape, &pp.ck_secondary)?;
+        let (l_u_secondary_next, l_w_secondary_next) = cs_secondary
+          .r1cs_instance_and_witness(&pp.circuit_shape_secondary.r1cs_shape, &pp.ck_secondary)?;
+        Ok((zi_secondary, l_u_secondary_next, l_w_secondary_next))
+      },
+    );
+    folded_primary.map_err(SuperNovaError::NovaError)?;
+    let (zi_secondary, l_u_secondary_next, l_w_secondary_next) = secondary?;
 
     // update the running instances and witnesses
-    let zi_primary = zi_primary
-      .iter()
-      .map(|v| v.get_value().ok_or(NovaError::from(SynthesisError::AssignmentMissing).into()))
-      .collect::<Result<Vec<<E1 as Engine>::Scalar>, SuperNovaError>>()?;
-    let zi_primary_pc_next =
-      zi_primary_pc_next
-        .expect("zi_primary_pc_next missing")
-        .get_value()
-        .ok_or::<SuperNovaError>(NovaError::from(SynthesisError::AssignmentMissing).into())?;
     let zi_secondary = zi_secondary
       .iter()
       .map(|v| v.get_value().ok_or(NovaError::from(SynthesisError::AssignmentMissing).into()))
@@ -942,6 +1217,105 @@ where E1: CurveCycleEquipped
     Ok(())
   }
 
+  #[cfg(feature = "prove")]
+  /// Synthesizes the primary circuit of the next step, replaying the witness
+  /// of `committed` if any, and returns its instance and witness, along with
+  /// the outputs of the step
+  fn synthesize_primary<C1: StepCircuit<E1::Scalar>>(
+    &self,
+    pp: &PublicParams<E1>,
+    c_primary: &C1,
+    r_U_secondary_i: &[Option<RelaxedR1CSInstance<Dual<E1>>>],
+    l_u_secondary_i: &R1CSInstance<Dual<E1>>,
+    T: &Commitment<Dual<E1>>,
+    committed: Option<&CommittedStep<E1>>,
+  ) -> Result<CachedStep<E1>, SuperNovaError> {
+    let circuit_index = c_primary.circuit_index();
+    let shape = &pp[circuit_index].r1cs_shape;
+    let (zi_primary_pc_next, zi_primary, (l_u_primary, l_w_primary)) = match committed {
+      None => {
+        let (pc_next, zi_primary, cs_primary) =
+          self.synthesize_augmented(pp, c_primary, r_U_secondary_i, l_u_secondary_i, T)?;
+        (pc_next, zi_primary, cs_primary.r1cs_instance_and_witness(shape, &pp.ck_primary)?)
+      },
+      Some(committed) => {
+        let (pc_next, zi_primary, cs_primary) = self.synthesize_augmented(
+          pp,
+          &committed.replay(c_primary),
+          r_U_secondary_i,
+          l_u_secondary_i,
+          T,
+        )?;
+        (
+          pc_next,
+          zi_primary,
+          committed.r1cs_instance_and_witness(cs_primary, shape, &pp.ck_primary)?,
+        )
+      },
+    };
+
+    let zi_primary = zi_primary
+      .iter()
+      .map(|v| v.get_value().ok_or(NovaError::from(SynthesisError::AssignmentMissing).into()))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, SuperNovaError>>()?;
+    let zi_primary_pc_next = zi_primary_pc_next
+      .and_then(|pc| pc.get_value())
+      .ok_or::<SuperNovaError>(NovaError::from(SynthesisError::AssignmentMissing).into())?;
+
+    Ok(CachedStep { l_u_primary, l_w_primary, zi_primary, zi_primary_pc_next })
+  }
+
+  #[cfg(feature = "prove")]
+  /// Synthesizes the primary augmented circuit of the next step, with the
+  /// step circuit `c_primary`, and returns the outputs of the step and the
+  /// assignment
+  #[allow(clippy::type_complexity)]
+  fn synthesize_augmented<C1: StepCircuit<E1::Scalar>>(
+    &self,
+    pp: &PublicParams<E1>,
+    c_primary: &C1,
+    r_U_secondary_i: &[Option<RelaxedR1CSInstance<Dual<E1>>>],
+    l_u_secondary_i: &R1CSInstance<Dual<E1>>,
+    T: &Commitment<Dual<E1>>,
+  ) -> Result<
+    (Option<AllocatedNum<E1::Scalar>>, Vec<AllocatedNum<E1::Scalar>>, SatisfyingAssignment<E1>),
+    SuperNovaError,
+  > {
+    let circuit_index = c_primary.circuit_index();
+    let mut cs_primary = SatisfyingAssignment::<E1>::with_capacity(
+      pp[circuit_index].r1cs_shape.num_io + 1,
+      pp[circuit_index].r1cs_shape.num_vars,
+    );
+    let inputs_primary: SuperNovaAugmentedCircuitInputs<'_, Dual<E1>> =
+      SuperNovaAugmentedCircuitInputs::new(
+        scalar_as_base::<E1>(self.pp_digest),
+        E1::Scalar::from(self.i as u64),
+        &self.z0_primary,
+        Some(&self.zi_primary),
+        Some(r_U_secondary_i),
+        Some(l_u_secondary_i),
+        Some(T),
+        Some(self.program_counter),
+        E1::Scalar::ZERO,
+      );
+
+    let circuit_primary: SuperNovaAugmentedCircuit<'_, Dual<E1>, C1> =
+      SuperNovaAugmentedCircuit::new(
+        &pp.augmented_circuit_params_primary,
+        Some(inputs_primary),
+        c_primary,
+        pp.ro_consts_circuit_primary.clone(),
+        self.num_augmented_circuits,
+      );
+
+    let (zi_primary_pc_next, zi_primary) =
+      circuit_primary.synthesize(&mut cs_primary).map_err(NovaError::from)?;
+    if zi_primary.len() != pp[circuit_index].F_arity {
+      return Err(SuperNovaError::NovaError(NovaError::InvalidInitialInputLength));
+    }
+    Ok((zi_primary_pc_next, zi_primary, cs_primary))
+  }
+
   /// verify recursive snark
   #[allow(clippy::type_complexity)]
   pub fn verify(
@@ -1071,7 +1445,7 @@ where E1: CurveCycleEquipped
     }
 
     // check the satisfiability of all instance/witness pairs
-    let (res_r_primary, (res_r_secondary, res_l_secondary)) = rayon::join(
+    let (res_r_primary, (res_r_secondary, res_l_secondary)) = parallel::join(
       || {
         self.r_U_primary.par_iter().zip_eq(self.r_W_primary.par_iter()).enumerate().try_for_each(
           |(i, (u, w))| {
@@ -1083,7 +1457,7 @@ where E1: CurveCycleEquipped
         )
       },
       || {
-        rayon::join(
+        parallel::join(
           || {
             pp.circuit_shape_secondary.r1cs_shape.is_sat_relaxed(
               &pp.ck_secondary,
@@ -1119,6 +1493,7 @@ where E1: CurveCycleEquipped
   }
 }
 
+#[cfg(feature = "prove")]
 /// SuperNova helper trait, for implementors that provide sets of sub-circuits
 /// to be proved via NIVC. `C1` must be a type (likely an `Enum`) for which a
 /// potentially-distinct instance can be supplied for each `index` below
@@ -1143,6 +1518,7 @@ where E1: CurveCycleEquipped {
   fn secondary_circuit(&self) -> Self::C2;
 }
 
+#[cfg(feature = "prove")]
 /// Compute the circuit digest of a supernova [`StepCircuit`].
 ///
 /// Note for callers: This function should be called with its performance
@@ -1190,8 +1566,19 @@ fn num_ro_inputs(num_circuits: usize, num_limbs: usize, arity: usize, is_primary
       + num_circuits * instance_size
 }
 
+#[cfg(feature = "prove")] mod deadline;
+#[cfg(feature = "prove")]
+pub use deadline::{Checkpoint, Deadline, Proving};
 pub mod error;
+#[cfg(feature = "prove")] pub mod estimate;
+#[cfg(feature = "prove")] pub mod policy;
+mod registry;
+pub use registry::CircuitRegistry;
+#[cfg(feature = "prove")] pub mod remote;
 pub mod snark;
-mod utils;
+#[cfg(feature = "prove")] mod stream;
+#[cfg(feature = "prove")]
+pub use stream::{step_queue, StepReceiver, StepSender};
+#[cfg(feature = "prove")] mod utils;
 
 #[cfg(test)] mod test;
@@ -0,0 +1,223 @@
+//! The stages of the pipelined proving of steps, by
+//! [`RecursiveSNARK::prove_steps`](super::RecursiveSNARK::prove_steps) and
+//! by its Nova counterpart (see [`crate::parallel::pipeline`]).
+//!
+//! Folding a step is sequential, as the augmented circuit of a step reads the
+//! instances folded by the previous step, but its step circuit only reads
+//! `pc` and `z`, which are known as soon as the previous step circuit ran.
+//! So the first stage runs the step circuit of a step natively with
+//! [`record`], the second commits to the witness it recorded at its place in
+//! the witness of the augmented circuit, found once with a [`Probe`], and the
+//! third folds the step, its augmented circuit replaying the recorded witness
+//! and committing only to the rest of its witness. Commitments being linear,
+//! the step is proved exactly as by `prove_step`.
+use std::sync::Arc;
+
+use bellpepper::util_cs::witness_cs::WitnessCS;
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, Index, SynthesisError};
+use ff::PrimeField;
+use once_cell::sync::OnceCell;
+
+use super::StepCircuit;
+use crate::{
+  bellpepper::solver::SatisfyingAssignment,
+  errors::NovaError,
+  r1cs::{R1CSInstance, R1CSShape, R1CSWitness},
+  secret::{wipe, Zeroize},
+  traits::{commitment::CommitmentEngineTrait, Engine},
+  Commitment, CommitmentKey,
+};
+
+/// Where an output of a step circuit is: one of its inputs, `pc` then `z`,
+/// or one of the variables it allocates
+#[derive(Clone, Copy, Debug)]
+enum Output {
+  Input(usize),
+  Allocated(usize),
+}
+
+/// The witness a step circuit synthesizes on `pc` and `z`, i.e. the values
+/// of the variables it allocates in order, and where its outputs are
+#[derive(Debug)]
+pub(crate) struct StepWitness<F: PrimeField> {
+  values:  Vec<F>,
+  pc_next: Option<Output>,
+  z_next:  Vec<Output>,
+}
+
+impl<F: PrimeField> Zeroize for StepWitness<F> {
+  fn zeroize(&mut self) { wipe(&mut self.values) }
+}
+
+/// Runs `circuit` natively on `pc` and `z`, and returns the witness it
+/// synthesizes, along with the next `pc` and `z`
+#[allow(clippy::type_complexity)]
+pub(crate) fn record<F: PrimeField, C: StepCircuit<F>>(
+  circuit: &C,
+  pc: Option<F>,
+  z: &[F],
+) -> Result<(StepWitness<F>, Option<F>, Vec<F>), SynthesisError> {
+  let mut cs = WitnessCS::<F>::new();
+  let pc = pc.map(|pc| AllocatedNum::alloc_infallible(cs.namespace(|| "pc"), || pc));
+  let z = z
+    .iter()
+    .enumerate()
+    .map(|(i, z_i)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *z_i))
+    .collect::<Vec<_>>();
+  let num_inputs = usize::from(pc.is_some()) + z.len();
+  let (pc_next, z_next) = circuit.synthesize(&mut cs, pc.as_ref(), &z)?;
+
+  let output = |v: &AllocatedNum<F>| match v.get_variable().get_unchecked() {
+    Index::Aux(i) if i < num_inputs => Ok(Output::Input(i)),
+    Index::Aux(i) => Ok(Output::Allocated(i - num_inputs)),
+    // the augmented circuit has no public inputs of the step circuit
+    Index::Input(_) => Err(SynthesisError::Unsatisfiable),
+  };
+  let witness = StepWitness {
+    values:  vec![],
+    pc_next: pc_next.as_ref().map(output).transpose()?,
+    z_next:  z_next.iter().map(output).collect::<Result<Vec<_>, _>>()?,
+  };
+  let pc_next =
+    pc_next.map(|pc| pc.get_value().ok_or(SynthesisError::AssignmentMissing)).transpose()?;
+  let z_next = z_next
+    .iter()
+    .map(|z_i| z_i.get_value().ok_or(SynthesisError::AssignmentMissing))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let (inputs, mut values) = cs.to_assignments();
+  if inputs.len() != 1 {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  values.drain(..num_inputs);
+  Ok((StepWitness { values, ..witness }, pc_next, z_next))
+}
+
+/// A step circuit of the arity and index of another, which finds where the
+/// witness of the other starts in the witness of its augmented circuit, when
+/// the augmented circuit is synthesized on it
+#[derive(Clone, Debug)]
+pub(crate) struct Probe {
+  arity:         usize,
+  circuit_index: usize,
+  offset:        Arc<OnceCell<usize>>,
+}
+
+impl Probe {
+  /// The probe of a step circuit of arity `arity` and index `circuit_index`
+  pub(crate) fn new(arity: usize, circuit_index: usize) -> Self {
+    Self { arity, circuit_index, offset: Arc::new(OnceCell::new()) }
+  }
+
+  /// The offset of the witness of the step circuit, once found
+  pub(crate) fn offset(&self) -> Result<usize, NovaError> {
+    self.offset.get().copied().ok_or(NovaError::SynthesisError("the probe did not run".into()))
+  }
+}
+
+impl<F: PrimeField> StepCircuit<F> for Probe {
+  fn arity(&self) -> usize { self.arity }
+
+  fn circuit_index(&self) -> usize { self.circuit_index }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let probe = cs.alloc(|| "probe", || Ok(F::ZERO))?;
+    if let Index::Aux(i) = probe.get_unchecked() {
+      let _ = self.offset.set(i);
+    }
+    Ok((pc.cloned(), z.to_vec()))
+  }
+}
+
+/// A step circuit replaying the witness recorded for it, rather than
+/// synthesizing it again
+#[derive(Clone)]
+pub(crate) struct Replay<'a, C, F: PrimeField> {
+  circuit: &'a C,
+  witness: &'a StepWitness<F>,
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> StepCircuit<F> for Replay<'_, C, F> {
+  fn arity(&self) -> usize { self.circuit.arity() }
+
+  fn circuit_index(&self) -> usize { self.circuit.circuit_index() }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let inputs = pc.into_iter().chain(z).collect::<Vec<_>>();
+    let values = self
+      .witness
+      .values
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc(cs.namespace(|| format!("value {i}")), || Ok(*v)))
+      .collect::<Result<Vec<_>, _>>()?;
+    let output = |output: &Output| {
+      match *output {
+        Output::Input(i) => inputs.get(i).map(|v| (*v).clone()),
+        Output::Allocated(i) => values.get(i).cloned(),
+      }
+      .ok_or(SynthesisError::Unsatisfiable)
+    };
+    let pc_next = self.witness.pc_next.as_ref().map(output).transpose()?;
+    let z_next = self.witness.z_next.iter().map(output).collect::<Result<Vec<_>, _>>()?;
+    Ok((pc_next, z_next))
+  }
+
+  fn witness_digest(&self) -> Option<[u8; 32]> { self.circuit.witness_digest() }
+}
+
+/// A recorded witness, committed to with the generators at its place in the
+/// witness of the augmented circuit
+pub(crate) struct CommittedStep<E: Engine> {
+  witness: StepWitness<E::Scalar>,
+  offset:  usize,
+  comm:    Commitment<E>,
+}
+
+impl<E: Engine> CommittedStep<E> {
+  /// Commits to `witness`, which starts at `offset` in the witness of the
+  /// augmented circuit
+  pub(crate) fn new(ck: &CommitmentKey<E>, witness: StepWitness<E::Scalar>, offset: usize) -> Self {
+    let comm = E::CE::commit_at(ck, offset, &witness.values);
+    Self { witness, offset, comm }
+  }
+
+  /// `circuit`, replaying the recorded witness
+  pub(crate) fn replay<'a, C>(&'a self, circuit: &'a C) -> Replay<'a, C, E::Scalar> {
+    Replay { circuit, witness: &self.witness }
+  }
+
+  /// The instance and witness of the augmented circuit synthesized in `cs` on
+  /// [`CommittedStep::replay`], committing to the rest of the witness only
+  pub(crate) fn r1cs_instance_and_witness(
+    &self,
+    cs: SatisfyingAssignment<E>,
+    shape: &R1CSShape<E>,
+    ck: &CommitmentKey<E>,
+  ) -> Result<(R1CSInstance<E>, R1CSWitness<E>), NovaError> {
+    let (inputs, aux) = cs.to_assignments();
+    let end = self.offset + self.witness.values.len();
+    if aux.get(self.offset..end) != Some(&self.witness.values[..]) {
+      return Err(NovaError::SynthesisError("the recorded witness is not at its offset".into()));
+    }
+    let comm_W =
+      E::CE::commit(ck, &aux[..self.offset]) + self.comm + E::CE::commit_at(ck, end, &aux[end..]);
+    let X = inputs[1..].to_owned();
+    let W = R1CSWitness::new(shape, aux)?;
+    Ok((R1CSInstance::new(shape, comm_W, X)?, W))
+  }
+}
+
+impl<E: Engine> Zeroize for CommittedStep<E> {
+  fn zeroize(&mut self) { self.witness.zeroize() }
+}
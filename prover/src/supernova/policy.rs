@@ -0,0 +1,259 @@
+//! Deciding which phases of proving run on the device, and which are handed
+//! off to a remote prover (see [`super::remote`]).
+//!
+//! A [`ProvingPolicy`] weighs the [`Workload`] of a proof, its estimated time
+//! and memory on the device (see [`super::estimate`]) and the bytes handed
+//! off, against the [`DeviceHints`] of the host app: battery, network and
+//! free memory. Folding and compression are decided one after the other, and
+//! a phase runs locally unless it does not fit the device and can be handed
+//! off: offline, or on a metered network with a large upload, it runs locally
+//! anyway. Compression follows folding to the remote prover, which holds the
+//! steps by then.
+//!
+//! The host app overrides a decision with a hook, e.g. to keep a proof of
+//! sensitive inputs on the device whatever its cost.
+use std::{sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+  estimate::{DeviceProfile, MemoryEstimate, TimeEstimate},
+  snark::CompressedSNARK,
+  PublicParams, StepCircuit,
+};
+use crate::{
+  errors::NovaError,
+  traits::{
+    snark::{BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
+    CurveCycleEquipped, Dual,
+  },
+};
+
+/// A phase of proving
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvingPhase {
+  /// Proving the steps of the computation, with a `RecursiveSNARK`
+  Folding,
+  /// Compressing the `RecursiveSNARK` into a `CompressedSNARK`
+  Compression,
+}
+
+/// Where a phase runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Placement {
+  /// On the device
+  Local,
+  /// On a remote prover
+  Remote,
+}
+
+/// The network of the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Network {
+  /// No network
+  Offline,
+  /// A network billed by the byte, e.g. cellular
+  Metered,
+  /// A network without a cost per byte, e.g. Wi-Fi
+  #[default]
+  Unmetered,
+}
+
+/// What the host app knows of the state of the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceHints {
+  /// the charge of the battery, from 0 to 1, `None` without a battery
+  pub battery:          Option<f64>,
+  /// whether the device is charging
+  pub charging:         bool,
+  /// the network of the device
+  pub network:          Network,
+  /// the memory available to the prover, in bytes, if known
+  pub available_memory: Option<usize>,
+}
+
+/// The cost of a proof on the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workload {
+  /// the number of steps to prove
+  pub num_steps:          usize,
+  /// the time of a step and of the compression
+  pub time:               TimeEstimate,
+  /// the memory of folding a step
+  pub folding_memory:     MemoryEstimate,
+  /// the memory of the compression
+  pub compression_memory: MemoryEstimate,
+  /// the bytes of the running instances and witnesses, which are uploaded to
+  /// hand off a phase
+  pub handoff_bytes:      usize,
+}
+
+impl Workload {
+  /// The workload of `num_steps` steps of `circuit` over `pp`, compressed
+  /// with `S1` and `S2`, on the device described by `profile`, which fails
+  /// unless `circuit.circuit_index()` is a circuit of these parameters
+  pub fn estimate<E1, S1, S2, C>(
+    pp: &PublicParams<E1>,
+    circuit: &C,
+    profile: &DeviceProfile,
+    num_steps: usize,
+  ) -> Result<Self, NovaError>
+  where
+    E1: CurveCycleEquipped,
+    S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+    C: StepCircuit<E1::Scalar>,
+  {
+    Ok(Self {
+      num_steps,
+      time: pp.estimate_prove_time(circuit, profile)?,
+      folding_memory: pp.estimate_memory(circuit)?,
+      compression_memory: CompressedSNARK::<E1, S1, S2>::estimate_memory(pp),
+      handoff_bytes: pp.running_bytes(),
+    })
+  }
+}
+
+/// Why a phase runs where it does
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reason {
+  /// The phase fits the device
+  Fits,
+  /// The phase takes longer than the policy allows on the device
+  TooSlow,
+  /// The phase takes more memory than the device has
+  TooLarge,
+  /// The battery is too low to prove on the device
+  LowBattery,
+  /// The device has no network to hand off the phase with
+  Offline,
+  /// The upload of the phase is too large for a metered network
+  MeteredUpload,
+  /// Compression runs where folding ran
+  FollowsFolding,
+  /// The host app decided
+  Overridden,
+}
+
+/// Where a phase runs, and why
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decision {
+  /// where the phase runs
+  pub placement: Placement,
+  /// why it runs there
+  pub reason:    Reason,
+}
+
+impl Decision {
+  /// The decision of the host app to run a phase at `placement`
+  pub fn overridden(placement: Placement) -> Self { Self { placement, reason: Reason::Overridden } }
+}
+
+/// Where each phase of a proof runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+  /// where the steps are proved
+  pub folding:     Decision,
+  /// where the proof is compressed
+  pub compression: Decision,
+}
+
+/// A hook of the host app, which sees each decision of the policy and returns
+/// the decision to keep
+pub type OverrideHook =
+  Arc<dyn Fn(ProvingPhase, &Workload, &DeviceHints, Decision) -> Decision + Send + Sync>;
+
+/// The policy deciding where the phases of proofs run
+#[derive(Clone)]
+pub struct ProvingPolicy {
+  /// the longest time of a phase on the device
+  pub max_local_time:     Duration,
+  /// the most memory the prover takes on the device, or the
+  /// [`DeviceHints::available_memory`] if less
+  pub max_local_memory:   Option<usize>,
+  /// the lowest charge of the battery to prove on, when not charging
+  pub min_battery:        f64,
+  /// the most bytes to upload on a metered network
+  pub max_metered_upload: usize,
+  hook:                   Option<OverrideHook>,
+}
+
+impl Default for ProvingPolicy {
+  fn default() -> Self {
+    Self {
+      max_local_time:     Duration::from_secs(30),
+      max_local_memory:   None,
+      min_battery:        0.2,
+      max_metered_upload: 16 << 20,
+      hook:               None,
+    }
+  }
+}
+
+impl ProvingPolicy {
+  /// The policy passing each of its decisions to `hook`, which has the last
+  /// word
+  pub fn with_override<F>(self, hook: F) -> Self
+  where F: Fn(ProvingPhase, &Workload, &DeviceHints, Decision) -> Decision + Send + Sync + 'static
+  {
+    Self { hook: Some(Arc::new(hook)), ..self }
+  }
+
+  /// Where the phases of `workload` run, on a device in the state `hints`
+  pub fn plan(&self, workload: &Workload, hints: &DeviceHints) -> Plan {
+    let num_steps = u32::try_from(workload.num_steps).unwrap_or(u32::MAX);
+    let folding_time = workload.time.per_step.checked_mul(num_steps).unwrap_or(Duration::MAX);
+    let folding = self.decide(folding_time, workload.folding_memory.peak(), workload, hints);
+    let folding = self.hooked(ProvingPhase::Folding, workload, hints, folding);
+
+    let compression = match folding.placement {
+      Placement::Remote =>
+        Decision { placement: Placement::Remote, reason: Reason::FollowsFolding },
+      Placement::Local =>
+        self.decide(workload.time.compression, workload.compression_memory.peak(), workload, hints),
+    };
+    let compression = self.hooked(ProvingPhase::Compression, workload, hints, compression);
+    Plan { folding, compression }
+  }
+
+  /// Where a phase of `time` and `memory` on the device runs
+  fn decide(
+    &self,
+    time: Duration,
+    memory: usize,
+    workload: &Workload,
+    hints: &DeviceHints,
+  ) -> Decision {
+    let limit = match (self.max_local_memory, hints.available_memory) {
+      (Some(max), Some(available)) => Some(max.min(available)),
+      (max, available) => max.or(available),
+    };
+    let low_battery = !hints.charging && hints.battery.is_some_and(|b| b < self.min_battery);
+    let reason = if low_battery {
+      Reason::LowBattery
+    } else if limit.is_some_and(|limit| memory > limit) {
+      Reason::TooLarge
+    } else if time > self.max_local_time {
+      Reason::TooSlow
+    } else {
+      return Decision { placement: Placement::Local, reason: Reason::Fits };
+    };
+
+    match hints.network {
+      Network::Offline => Decision { placement: Placement::Local, reason: Reason::Offline },
+      Network::Metered if workload.handoff_bytes > self.max_metered_upload =>
+        Decision { placement: Placement::Local, reason: Reason::MeteredUpload },
+      _ => Decision { placement: Placement::Remote, reason },
+    }
+  }
+
+  fn hooked(
+    &self,
+    phase: ProvingPhase,
+    workload: &Workload,
+    hints: &DeviceHints,
+    decision: Decision,
+  ) -> Decision {
+    self.hook.as_ref().map_or(decision, |hook| hook(phase, workload, hints, decision))
+  }
+}
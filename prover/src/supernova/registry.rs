@@ -0,0 +1,99 @@
+//! A registry of the versions of the step circuits of a SuperNova instance.
+//!
+//! A version is identified by the digest of its primary circuits (see
+//! [`PublicParams::circuits_digest`]), and proofs made against it are bound to
+//! the digest of its [`PublicParams`]. Registering several versions lets a
+//! verifier accept proofs from all of them while a circuit update rolls out,
+//! and tell which version each proof used.
+use std::sync::Arc;
+
+use super::{error::SuperNovaError, PublicParams, RecursiveSNARK};
+use crate::traits::{CurveCycleEquipped, Dual, Engine};
+
+/// A registered version of the step circuits
+#[derive(Debug)]
+struct CircuitVersion<E1: CurveCycleEquipped> {
+  name:            String,
+  circuits_digest: E1::Scalar,
+  pp:              Arc<PublicParams<E1>>,
+}
+
+/// The registered versions of the step circuits, keyed by circuit digest
+#[derive(Debug)]
+pub struct CircuitRegistry<E1: CurveCycleEquipped> {
+  versions: Vec<CircuitVersion<E1>>,
+}
+
+impl<E1: CurveCycleEquipped> Default for CircuitRegistry<E1> {
+  fn default() -> Self { Self { versions: Vec::new() } }
+}
+
+impl<E1: CurveCycleEquipped> CircuitRegistry<E1> {
+  /// Creates an empty registry
+  pub fn new() -> Self { Self::default() }
+
+  /// Registers the circuits of `pp` under `name`, and returns their digest.
+  ///
+  /// Fails if these circuits, or another version named `name`, are already
+  /// registered.
+  pub fn register(
+    &mut self,
+    name: impl Into<String>,
+    pp: Arc<PublicParams<E1>>,
+  ) -> Result<E1::Scalar, SuperNovaError> {
+    let name = name.into();
+    let circuits_digest = pp.circuits_digest();
+    if self.versions.iter().any(|v| v.circuits_digest == circuits_digest || v.name == name) {
+      return Err(SuperNovaError::CircuitVersionExists);
+    }
+    self.versions.push(CircuitVersion { name, circuits_digest, pp });
+    Ok(circuits_digest)
+  }
+
+  /// Removes the version with the given circuit digest, e.g. once its
+  /// proofs are no longer accepted, and returns its parameters
+  pub fn unregister(&mut self, circuits_digest: &E1::Scalar) -> Option<Arc<PublicParams<E1>>> {
+    let index = self.versions.iter().position(|v| v.circuits_digest == *circuits_digest)?;
+    Some(self.versions.remove(index).pp)
+  }
+
+  /// The parameters of the version with the given circuit digest
+  pub fn get(&self, circuits_digest: &E1::Scalar) -> Option<&Arc<PublicParams<E1>>> {
+    self.versions.iter().find(|v| v.circuits_digest == *circuits_digest).map(|v| &v.pp)
+  }
+
+  /// The names and circuit digests of the registered versions, in the order
+  /// of their registration
+  pub fn versions(&self) -> impl Iterator<Item = (&str, &E1::Scalar)> {
+    self.versions.iter().map(|v| (v.name.as_str(), &v.circuits_digest))
+  }
+
+  /// The version `recursive_snark` claims to have been made against, if it
+  /// is registered. The claim is only checked by [`CircuitRegistry::verify`].
+  pub fn version_of(&self, recursive_snark: &RecursiveSNARK<E1>) -> Option<(&str, &E1::Scalar)> {
+    self
+      .find_by_pp_digest(&recursive_snark.pp_digest())
+      .map(|v| (v.name.as_str(), &v.circuits_digest))
+  }
+
+  /// Verifies `recursive_snark` against the version it was made against, and
+  /// returns the name of that version along with the outputs
+  #[allow(clippy::type_complexity)]
+  pub fn verify(
+    &self,
+    recursive_snark: &RecursiveSNARK<E1>,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+  ) -> Result<(&str, Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), SuperNovaError> {
+    let version = self
+      .find_by_pp_digest(&recursive_snark.pp_digest())
+      .ok_or(SuperNovaError::ParamsDigestMismatch)?;
+    let (zi_primary, zi_secondary) =
+      recursive_snark.verify(&version.pp, z0_primary, z0_secondary)?;
+    Ok((version.name.as_str(), zi_primary, zi_secondary))
+  }
+
+  fn find_by_pp_digest(&self, pp_digest: &E1::Scalar) -> Option<&CircuitVersion<E1>> {
+    self.versions.iter().find(|v| v.pp.digest() == *pp_digest)
+  }
+}
@@ -0,0 +1,289 @@
+//! The messages of remote proving, and a reference client, so that provers
+//! and the services submitting to them interoperate on one schema.
+//!
+//! A client submits a [`JobRequest`] to a prover: a [`JobKind::Compress`] job
+//! carries a [`CompressionRequest`] (see deferred compression in
+//! [`super::snark`]) and is answered with the `CompressedSNARK` of its claim,
+//! a [`JobKind::Fold`] job carries a [`Checkpoint`] and is answered with the
+//! checkpoint of the steps the prover folded from it. The prover answers the
+//! submission with a [`JobSubmitted`], and the client polls the
+//! [`JobStatus`] of the job until it is done.
+//!
+//! The messages are JSON over HTTP, on the routes [`JOBS_PATH`] and
+//! [`job_path`], with bytes in hexadecimal; their gRPC equivalent is
+//! `proto/remote.proto`, of the same fields. A payload is encoded as
+//! checkpoints are (see [`Checkpoint::to_bytes`]), and names the digest of
+//! the public parameters it was produced against, so that a prover picks its
+//! parameters before it decodes the payload.
+//!
+//! [`RemoteProver`] is the reference client, on a [`Transport`] that sends the
+//! requests, e.g. an HTTP client.
+use bincode::Options;
+use ff::PrimeField;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+  deadline::{encoding, Checkpoint},
+  error::SuperNovaError,
+  snark::{CompressedSNARK, CompressionRequest},
+};
+use crate::{
+  errors::NovaError,
+  traits::{
+    snark::{BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
+    CurveCycleEquipped, Dual,
+  },
+};
+
+/// The version of the protocol, of the messages and the routes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The route jobs are submitted to
+pub const JOBS_PATH: &str = "/v1/jobs";
+
+/// The route of the status of the job `job_id`
+pub fn job_path(job_id: &str) -> String { format!("{JOBS_PATH}/{job_id}") }
+
+/// The hexadecimal digest of public parameters, as messages name them
+pub fn digest_hex<F: PrimeField>(digest: &F) -> String { hex::encode(digest.to_repr()) }
+
+/// Bytes as hexadecimal strings, in the JSON of the messages and of
+/// manifests
+pub(crate) mod hex_bytes {
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    hex::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+  }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, NovaError> {
+  encoding().serialize(value).map_err(|e| NovaError::SerializationError(e.to_string()))
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, NovaError> {
+  encoding()
+    .reject_trailing_bytes()
+    .deserialize(bytes)
+    .map_err(|e| NovaError::SerializationError(e.to_string()))
+}
+
+/// The kind of a job, and of its payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+  /// Compresses a [`CompressionRequest`] into a `CompressedSNARK`
+  Compress,
+  /// Folds steps from a [`Checkpoint`], into another checkpoint
+  Fold,
+}
+
+/// A job submitted to a prover, to [`JOBS_PATH`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRequest {
+  /// The version of the protocol, [`PROTOCOL_VERSION`]
+  pub version:   u32,
+  /// The kind of the job
+  pub kind:      JobKind,
+  /// The hexadecimal digest of the public parameters of the payload, see
+  /// [`digest_hex`]
+  pub pp_digest: String,
+  /// The payload of the job, encoded
+  #[serde(with = "hex_bytes")]
+  pub payload:   Vec<u8>,
+}
+
+impl JobRequest {
+  /// The job compressing `request`
+  pub fn compress<E1: CurveCycleEquipped>(
+    request: &CompressionRequest<E1>,
+  ) -> Result<Self, NovaError> {
+    Ok(Self {
+      version:   PROTOCOL_VERSION,
+      kind:      JobKind::Compress,
+      pp_digest: digest_hex(&request.pp_digest()),
+      payload:   encode(request)?,
+    })
+  }
+
+  /// The job folding steps from `checkpoint`
+  pub fn fold<E1: CurveCycleEquipped>(checkpoint: &Checkpoint<E1>) -> Result<Self, NovaError> {
+    Ok(Self {
+      version:   PROTOCOL_VERSION,
+      kind:      JobKind::Fold,
+      pp_digest: digest_hex(&checkpoint.pp_digest()),
+      payload:   checkpoint.to_bytes()?,
+    })
+  }
+
+  /// Fails unless the job is of `kind`, in the version of this protocol
+  fn check(&self, kind: JobKind) -> Result<(), NovaError> {
+    if self.version != PROTOCOL_VERSION {
+      return Err(NovaError::RemoteProverError(format!("unsupported version {}", self.version)));
+    }
+    if self.kind != kind {
+      return Err(NovaError::RemoteProverError(format!("not a {kind:?} job")));
+    }
+    Ok(())
+  }
+
+  /// The compression request of a [`JobKind::Compress`] job, for the prover
+  pub fn compression_request<E1: CurveCycleEquipped>(
+    &self,
+  ) -> Result<CompressionRequest<E1>, NovaError> {
+    self.check(JobKind::Compress)?;
+    decode(&self.payload)
+  }
+
+  /// The checkpoint of a [`JobKind::Fold`] job, for the prover
+  pub fn checkpoint<E1: CurveCycleEquipped>(&self) -> Result<Checkpoint<E1>, NovaError> {
+    self.check(JobKind::Fold)?;
+    Checkpoint::from_bytes(&self.payload)
+  }
+}
+
+/// The answer of a prover to a submission
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSubmitted {
+  /// The id of the job, to poll its status at [`job_path`]
+  pub job_id: String,
+}
+
+/// The status of a job, at [`job_path`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+  /// The job waits for a prover
+  Queued,
+  /// The job is being proved
+  Running,
+  /// The job is done, with its result encoded
+  Done {
+    /// The `CompressedSNARK` of a [`JobKind::Compress`] job, the
+    /// [`Checkpoint`] of a [`JobKind::Fold`] job
+    #[serde(with = "hex_bytes")]
+    result: Vec<u8>,
+  },
+  /// The job failed
+  Failed {
+    /// The reason of the failure
+    message: String,
+  },
+}
+
+impl JobStatus {
+  /// The status of a job done with `snark`, for the prover
+  pub fn compressed<E1, S1, S2>(snark: &CompressedSNARK<E1, S1, S2>) -> Result<Self, NovaError>
+  where
+    E1: CurveCycleEquipped,
+    S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>>, {
+    Ok(Self::Done { result: encode(snark)? })
+  }
+
+  /// The status of a job done with `checkpoint`, for the prover
+  pub fn folded<E1: CurveCycleEquipped>(checkpoint: &Checkpoint<E1>) -> Result<Self, NovaError> {
+    Ok(Self::Done { result: checkpoint.to_bytes()? })
+  }
+
+  /// The result of the job, `None` while it is not done
+  fn result(self) -> Result<Option<Vec<u8>>, NovaError> {
+    match self {
+      Self::Queued | Self::Running => Ok(None),
+      Self::Done { result } => Ok(Some(result)),
+      Self::Failed { message } => Err(NovaError::RemoteProverError(message)),
+    }
+  }
+}
+
+/// The requests of a client to a prover, e.g. on an HTTP client
+pub trait Transport {
+  /// Sends `body` to `path`, an HTTP `POST`, and returns the body of the
+  /// answer
+  fn post(&mut self, path: &str, body: Vec<u8>) -> Result<Vec<u8>, NovaError>;
+
+  /// Reads `path`, an HTTP `GET`, and returns the body of the answer
+  fn get(&mut self, path: &str) -> Result<Vec<u8>, NovaError>;
+}
+
+/// A client of a remote prover
+#[derive(Debug)]
+pub struct RemoteProver<T> {
+  transport: T,
+}
+
+impl<T: Transport> RemoteProver<T> {
+  /// The client of the prover `transport` sends to
+  pub fn new(transport: T) -> Self { Self { transport } }
+
+  /// Submits `job`, and returns its id
+  pub fn submit(&mut self, job: &JobRequest) -> Result<String, NovaError> {
+    let body = serde_json::to_vec(job).map_err(|e| NovaError::SerializationError(e.to_string()))?;
+    let answer = self.transport.post(JOBS_PATH, body)?;
+    let submitted: JobSubmitted =
+      serde_json::from_slice(&answer).map_err(|e| NovaError::RemoteProverError(e.to_string()))?;
+    Ok(submitted.job_id)
+  }
+
+  /// The status of the job `job_id`
+  pub fn status(&mut self, job_id: &str) -> Result<JobStatus, NovaError> {
+    let answer = self.transport.get(&job_path(job_id))?;
+    serde_json::from_slice(&answer).map_err(|e| NovaError::RemoteProverError(e.to_string()))
+  }
+
+  /// Submits the compression of `request`, and returns the id of the job
+  pub fn submit_compression<E1: CurveCycleEquipped>(
+    &mut self,
+    request: &CompressionRequest<E1>,
+  ) -> Result<String, NovaError> {
+    self.submit(&JobRequest::compress(request)?)
+  }
+
+  /// Submits the folding of steps from `checkpoint`, and returns the id of
+  /// the job
+  pub fn submit_checkpoint<E1: CurveCycleEquipped>(
+    &mut self,
+    checkpoint: &Checkpoint<E1>,
+  ) -> Result<String, NovaError> {
+    self.submit(&JobRequest::fold(checkpoint)?)
+  }
+
+  /// The `CompressedSNARK` of the job `job_id`, compressing `request`, or
+  /// `None` while it is not done. Fails unless the proof is about the claim
+  /// of `request` (see [`CompressionRequest::check_response`]); the proof is
+  /// still to be verified.
+  pub fn compressed_snark<E1, S1, S2>(
+    &mut self,
+    job_id: &str,
+    request: &CompressionRequest<E1>,
+  ) -> Result<Option<CompressedSNARK<E1, S1, S2>>, SuperNovaError>
+  where
+    E1: CurveCycleEquipped,
+    S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+  {
+    let Some(result) = self.status(job_id)?.result()? else { return Ok(None) };
+    let snark = decode(&result)?;
+    request.check_response(&snark)?;
+    Ok(Some(snark))
+  }
+
+  /// The checkpoint of the job `job_id`, folding from a checkpoint of
+  /// `pp_digest`, or `None` while it is not done
+  pub fn checkpoint<E1: CurveCycleEquipped>(
+    &mut self,
+    job_id: &str,
+    pp_digest: E1::Scalar,
+  ) -> Result<Option<Checkpoint<E1>>, SuperNovaError> {
+    let Some(result) = self.status(job_id)?.result()? else { return Ok(None) };
+    let checkpoint = Checkpoint::<E1>::from_bytes(&result)?;
+    if checkpoint.pp_digest() != pp_digest {
+      return Err(SuperNovaError::ParamsDigestMismatch);
+    }
+    Ok(Some(checkpoint))
+  }
+}
@@ -1,15 +1,18 @@
 //! This module defines a final compressing SNARK for supernova proofs
 
+use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 
 use super::{error::SuperNovaError, PublicParams, RecursiveSNARK};
 use crate::{
   constants::NUM_HASH_BITS,
   errors::NovaError,
-  r1cs::{R1CSInstance, RelaxedR1CSWitness},
+  metrics,
+  profiling::Phase,
+  r1cs::{R1CSInstance, R1CSWitness, RelaxedR1CSWitness},
   scalar_as_base,
   traits::{
-    snark::{BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
+    snark::{AppContext, BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
     AbsorbInROTrait, CurveCycleEquipped, Dual, Engine, ROTrait,
   },
   RelaxedR1CSInstance, NIFS,
@@ -60,6 +63,98 @@ where
   zn_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
 }
 
+/// The data a client hands off to a server so the server can run the
+/// expensive [`CompressedSNARK::prove`] on its behalf.
+///
+/// The client folds locally with a [`RecursiveSNARK`] and ships the running
+/// instances together with the witnesses Spartan needs. The server cannot
+/// forge the folded claim: the instances are bound to `z0`, `zn`, the number
+/// of steps and the program counter through the hashes in the public IO of
+/// `l_u_secondary`, which [`CompressedSNARK::verify`] recomputes from the
+/// client's own `z0`. [`CompressionRequest::check_response`] additionally lets
+/// the client confirm that the returned proof is about the claim it sent.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompressionRequest<E1>
+where E1: CurveCycleEquipped {
+  pp_digest:       E1::Scalar,
+  num_steps:       usize,
+  program_counter: E1::Scalar,
+
+  r_U_primary: Vec<Option<RelaxedR1CSInstance<E1>>>,
+  r_W_primary: Vec<Option<RelaxedR1CSWitness<E1>>>,
+
+  r_U_secondary: RelaxedR1CSInstance<Dual<E1>>,
+  r_W_secondary: RelaxedR1CSWitness<Dual<E1>>,
+  l_u_secondary: R1CSInstance<Dual<E1>>,
+  l_w_secondary: R1CSWitness<Dual<E1>>,
+
+  zn_primary:   Vec<E1::Scalar>,
+  zn_secondary: Vec<<Dual<E1> as Engine>::Scalar>,
+}
+
+impl<E1> CompressionRequest<E1>
+where E1: CurveCycleEquipped
+{
+  /// Bundle the running instances and witnesses of a `RecursiveSNARK` for
+  /// compression on another machine
+  pub fn new(recursive_snark: &RecursiveSNARK<E1>) -> Self {
+    Self {
+      pp_digest:       recursive_snark.pp_digest,
+      num_steps:       recursive_snark.i,
+      program_counter: recursive_snark.program_counter,
+
+      r_U_primary: recursive_snark.r_U_primary.clone(),
+      r_W_primary: recursive_snark.r_W_primary.clone(),
+
+      r_U_secondary: recursive_snark.r_U_secondary.clone(),
+      r_W_secondary: recursive_snark.r_W_secondary.clone(),
+      l_u_secondary: recursive_snark.l_u_secondary.clone(),
+      l_w_secondary: recursive_snark.l_w_secondary.clone(),
+
+      zn_primary:   recursive_snark.zi_primary.clone(),
+      zn_secondary: recursive_snark.zi_secondary.clone(),
+    }
+  }
+
+  /// The digest of the public parameters this request was produced with
+  pub fn pp_digest(&self) -> E1::Scalar { self.pp_digest }
+
+  /// Checks that a `CompressedSNARK` returned by a server proves the claim
+  /// carried by this request.
+  ///
+  /// This only compares the public parts of the proof; the proof itself still
+  /// has to be checked with [`CompressedSNARK::verify`].
+  pub fn check_response<S1, S2>(
+    &self,
+    snark: &CompressedSNARK<E1, S1, S2>,
+  ) -> Result<(), SuperNovaError>
+  where
+    S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+  {
+    let is_primary_match = snark.r_U_primary.len() == self.r_U_primary.len()
+      && snark
+        .r_U_primary
+        .iter()
+        .zip_eq(self.r_U_primary.iter())
+        .all(|(U, req_U)| req_U.as_ref().is_none_or(|req_U| U == req_U));
+
+    if !is_primary_match
+      || snark.r_U_secondary != self.r_U_secondary
+      || snark.l_u_secondary != self.l_u_secondary
+      || snark.num_steps != self.num_steps
+      || snark.program_counter != self.program_counter
+      || snark.zn_primary != self.zn_primary
+      || snark.zn_secondary != self.zn_secondary
+    {
+      return Err(NovaError::ProofVerifyError.into());
+    }
+
+    Ok(())
+  }
+}
+
 impl<E1, S1, S2> CompressedSNARK<E1, S1, S2>
 where
   E1: CurveCycleEquipped,
@@ -100,24 +195,104 @@ where
     pk: &ProverKey<E1, S1, S2>,
     recursive_snark: &RecursiveSNARK<E1>,
   ) -> Result<Self, SuperNovaError> {
+    Self::prove_with_context(pp, pk, recursive_snark, AppContext::default())
+  }
+
+  /// Same as [`CompressedSNARK::prove`], binding the proof to the application
+  /// context `context`, in which only it verifies, with
+  /// [`CompressedSNARK::verify_with_context`]
+  pub fn prove_with_context(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1>,
+    context: AppContext<'_>,
+  ) -> Result<Self, SuperNovaError> {
+    Self::prove_inner(
+      pp,
+      pk,
+      &recursive_snark.r_U_primary,
+      &recursive_snark.r_W_primary,
+      &recursive_snark.r_U_secondary,
+      &recursive_snark.r_W_secondary,
+      &recursive_snark.l_u_secondary,
+      &recursive_snark.l_w_secondary,
+      recursive_snark.i,
+      recursive_snark.program_counter,
+      &recursive_snark.zi_primary,
+      &recursive_snark.zi_secondary,
+      context,
+    )
+  }
+
+  /// Create a new `CompressedSNARK` from a [`CompressionRequest`] handed off
+  /// by a client.
+  ///
+  /// This is the server-side entry point of deferred compression. The request
+  /// must have been produced against the same [`PublicParams`] as `pp`.
+  pub fn prove_request(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    request: &CompressionRequest<E1>,
+  ) -> Result<Self, SuperNovaError> {
+    if request.pp_digest != pp.digest() {
+      return Err(SuperNovaError::ParamsDigestMismatch);
+    }
+
+    Self::prove_inner(
+      pp,
+      pk,
+      &request.r_U_primary,
+      &request.r_W_primary,
+      &request.r_U_secondary,
+      &request.r_W_secondary,
+      &request.l_u_secondary,
+      &request.l_w_secondary,
+      request.num_steps,
+      request.program_counter,
+      &request.zn_primary,
+      &request.zn_secondary,
+      AppContext::default(),
+    )
+  }
+
+  fn prove_inner(
+    pp: &PublicParams<E1>,
+    pk: &ProverKey<E1, S1, S2>,
+    r_U_primary: &[Option<RelaxedR1CSInstance<E1>>],
+    r_W_primary: &[Option<RelaxedR1CSWitness<E1>>],
+    r_U_secondary: &RelaxedR1CSInstance<Dual<E1>>,
+    r_W_secondary: &RelaxedR1CSWitness<Dual<E1>>,
+    l_u_secondary: &R1CSInstance<Dual<E1>>,
+    l_w_secondary: &R1CSWitness<Dual<E1>>,
+    num_steps: usize,
+    program_counter: E1::Scalar,
+    zn_primary: &[E1::Scalar],
+    zn_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> Result<Self, SuperNovaError> {
+    let _phase = Phase::enter("supernova::CompressedSNARK::prove");
+    if r_U_primary.len() != pp.circuit_shapes.len() || r_W_primary.len() != pp.circuit_shapes.len()
+    {
+      return Err(NovaError::InvalidInputLength.into());
+    }
+
     // fold the secondary circuit's instance
     let res_secondary = NIFS::prove(
       &*pp.ck_secondary,
       &pp.ro_consts_secondary,
       &scalar_as_base::<E1>(pp.digest()),
       &pp.circuit_shape_secondary.r1cs_shape,
-      &recursive_snark.r_U_secondary,
-      &recursive_snark.r_W_secondary,
-      &recursive_snark.l_u_secondary,
-      &recursive_snark.l_w_secondary,
+      r_U_secondary,
+      r_W_secondary,
+      l_u_secondary,
+      l_w_secondary,
     );
 
     let (nifs_secondary, (f_U_secondary, f_W_secondary), _) = res_secondary?;
 
     // Prepare the list of primary Relaxed R1CS instances (a default instance is
     // provided for uninitialized circuits)
-    let r_U_primary = recursive_snark
-      .r_U_primary
+    let r_U_primary = r_U_primary
       .iter()
       .enumerate()
       .map(|(idx, r_U)| {
@@ -129,8 +304,7 @@ where
 
     // Prepare the list of primary relaxed R1CS witnesses (a default witness is
     // provided for uninitialized circuits)
-    let r_W_primary: Vec<RelaxedR1CSWitness<E1>> = recursive_snark
-      .r_W_primary
+    let r_W_primary: Vec<RelaxedR1CSWitness<E1>> = r_W_primary
       .iter()
       .enumerate()
       .map(|(idx, r_W)| {
@@ -139,39 +313,42 @@ where
       .collect::<Vec<_>>();
 
     // Generate a primary SNARK proof for the list of primary circuits
-    let r_W_snark_primary = S1::prove(
+    let r_W_snark_primary = S1::prove_with_context(
       &pp.ck_primary,
       &pk.pk_primary,
       pp.primary_r1cs_shapes(),
       &r_U_primary,
       &r_W_primary,
+      context,
     )?;
 
     // Generate a secondary SNARK proof for the secondary circuit
-    let f_W_snark_secondary = S2::prove(
+    let f_W_snark_secondary = S2::prove_with_context(
       &pp.ck_secondary,
       &pk.pk_secondary,
       &pp.circuit_shape_secondary.r1cs_shape,
       &f_U_secondary,
       &f_W_secondary,
+      context,
     )?;
 
     let compressed_snark = Self {
       r_U_primary,
       r_W_snark_primary,
 
-      r_U_secondary: recursive_snark.r_U_secondary.clone(),
-      l_u_secondary: recursive_snark.l_u_secondary.clone(),
+      r_U_secondary: r_U_secondary.clone(),
+      l_u_secondary: l_u_secondary.clone(),
       nifs_secondary,
       f_W_snark_secondary,
 
-      num_steps: recursive_snark.i,
-      program_counter: recursive_snark.program_counter,
+      num_steps,
+      program_counter,
 
-      zn_primary: recursive_snark.zi_primary.clone(),
-      zn_secondary: recursive_snark.zi_secondary.clone(),
+      zn_primary: zn_primary.to_vec(),
+      zn_secondary: zn_secondary.to_vec(),
     };
 
+    metrics::record_proof("supernova", &compressed_snark);
     Ok(compressed_snark)
   }
 
@@ -183,6 +360,40 @@ where
     vk: &VerifierKey<E1, S1, S2>,
     z0_primary: &[E1::Scalar],
     z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), SuperNovaError> {
+    self.verify_with_context(pp, vk, z0_primary, z0_secondary, AppContext::default())
+  }
+
+  /// Same as [`CompressedSNARK::verify`], for a proof made in the application
+  /// context `context` with [`CompressedSNARK::prove_with_context`]
+  #[allow(clippy::type_complexity)]
+  pub fn verify_with_context(
+    &self,
+    pp: &PublicParams<E1>,
+    vk: &VerifierKey<E1, S1, S2>,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
+  ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), SuperNovaError> {
+    let result = self.verify_inner(pp, vk, z0_primary, z0_secondary, context);
+    metrics::record_verification("supernova", &result);
+    #[cfg(feature = "log-failures")]
+    if let Err(e) = &result {
+      crate::inspect::FailureReport::new("supernova", self, e)
+        .with_key_digest("pp", &pp.digest())
+        .log();
+    }
+    result
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn verify_inner(
+    &self,
+    pp: &PublicParams<E1>,
+    vk: &VerifierKey<E1, S1, S2>,
+    z0_primary: &[E1::Scalar],
+    z0_secondary: &[<Dual<E1> as Engine>::Scalar],
+    context: AppContext<'_>,
   ) -> Result<(Vec<E1::Scalar>, Vec<<Dual<E1> as Engine>::Scalar>), SuperNovaError> {
     // Assumes that each circuit has the same arity, so we just use the first one'
     let num_field_primary_ro = 3 // params_next, i_new, program_counter_new
@@ -248,7 +459,8 @@ where
     }
 
     // Verify the primary SNARK
-    let res_primary = self.r_W_snark_primary.verify(&vk.vk_primary, &self.r_U_primary);
+    let res_primary =
+      self.r_W_snark_primary.verify_with_context(&vk.vk_primary, &self.r_U_primary, context);
 
     // Fold the secondary circuit's instance
     let f_U_secondary = self.nifs_secondary.verify(
@@ -259,7 +471,8 @@ where
     )?;
 
     // Verify the secondary SNARK
-    let res_secondary = self.f_W_snark_secondary.verify(&vk.vk_secondary, &f_U_secondary);
+    let res_secondary =
+      self.f_W_snark_secondary.verify_with_context(&vk.vk_secondary, &f_U_secondary, context);
 
     res_primary?;
 
@@ -279,7 +492,7 @@ mod test {
   use super::*;
   use crate::{
     provider::{ipa_pc, Bn256EngineIPA},
-    spartan::{batched, batched_ppsnark, snark::RelaxedR1CSSNARK},
+    spartan::{batched, batched_ppsnark, ppsnark, snark::RelaxedR1CSSNARK},
     supernova::{circuit::TrivialCircuit, NonUniformCircuit, StepCircuit},
   };
 
@@ -287,6 +500,7 @@ mod test {
   type S1<E> = batched::BatchedRelaxedR1CSSNARK<E, EE<E>>;
   type S1PP<E> = batched_ppsnark::BatchedRelaxedR1CSSNARK<E, EE<E>>;
   type S2<E> = RelaxedR1CSSNARK<E, EE<E>>;
+  type S2PP<E> = ppsnark::RelaxedR1CSSNARK<E, EE<E>>;
 
   #[derive(Clone)]
   struct SquareCircuit<E> {
@@ -549,11 +763,62 @@ mod test {
     compressed_snark.verify(&pp, &verifier_key, &z0_primary, &z0_secondary).unwrap();
   }
 
+  fn test_deferred_compression_with<E1, S1, S2>(num_steps: usize)
+  where
+    E1: CurveCycleEquipped,
+    S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<Dual<E1>>, {
+    let secondary_circuit = TrivialCircuit::default();
+    let test_circuits = TestCircuit::<E1>::new(num_steps);
+
+    let pp = PublicParams::setup(&test_circuits[0], &*S1::ck_floor(), &*S2::ck_floor());
+
+    let z0_primary = vec![E1::Scalar::from(17u64)];
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+
+    let mut recursive_snark = RecursiveSNARK::new(
+      &pp,
+      &test_circuits[0],
+      &test_circuits[0],
+      &secondary_circuit,
+      &z0_primary,
+      &z0_secondary,
+    )
+    .unwrap();
+
+    for circuit in test_circuits.iter().take(num_steps) {
+      recursive_snark.prove_step(&pp, circuit, &secondary_circuit).unwrap();
+    }
+
+    // the client ships the request, the server proves it
+    let request = CompressionRequest::new(&recursive_snark);
+    let request_bytes = bincode::serialize(&request).unwrap();
+    let request: CompressionRequest<E1> = bincode::deserialize(&request_bytes).unwrap();
+
+    let (prover_key, verifier_key) = CompressedSNARK::<_, S1, S2>::setup(&pp).unwrap();
+    let compressed_snark = CompressedSNARK::prove_request(&pp, &prover_key, &request).unwrap();
+
+    request.check_response(&compressed_snark).unwrap();
+    compressed_snark.verify(&pp, &verifier_key, &z0_primary, &z0_secondary).unwrap();
+
+    // a response about a different claim is rejected
+    let mut other_snark = recursive_snark.clone();
+    other_snark.prove_step(&pp, &test_circuits[num_steps % 2], &secondary_circuit).unwrap();
+    let other_compressed_snark = CompressedSNARK::prove(&pp, &prover_key, &other_snark).unwrap();
+    assert!(request.check_response(&other_compressed_snark).is_err());
+  }
+
+  #[test]
+  fn test_deferred_compression() {
+    test_deferred_compression_with::<Bn256EngineIPA, S1<_>, S2<_>>(4);
+  }
+
   #[test]
   fn test_nivc_trivial_with_compression() {
     const NUM_STEPS: usize = 6;
     test_compression_with::<Bn256EngineIPA, S1PP<_>, S2<_>, _, _>(NUM_STEPS, TestCircuit::new);
     test_compression_with::<Bn256EngineIPA, S1<_>, S2<_>, _, _>(NUM_STEPS, TestCircuit::new);
+    test_compression_with::<Bn256EngineIPA, S1PP<_>, S2PP<_>, _, _>(NUM_STEPS, TestCircuit::new);
   }
 
   #[test]
@@ -561,5 +826,6 @@ mod test {
     const NUM_STEPS: usize = 4;
     test_compression_with::<Bn256EngineIPA, S1PP<_>, S2<_>, _, _>(NUM_STEPS, BigTestCircuit::new);
     test_compression_with::<Bn256EngineIPA, S1<_>, S2<_>, _, _>(NUM_STEPS, BigTestCircuit::new);
+    test_compression_with::<Bn256EngineIPA, S1PP<_>, S2PP<_>, _, _>(NUM_STEPS, BigTestCircuit::new);
   }
 }
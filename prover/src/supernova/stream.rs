@@ -0,0 +1,73 @@
+//! Proving steps as their inputs arrive, e.g. the records of a TLS session
+//! or the chunks of a body as they are downloaded.
+//!
+//! [`step_queue`] makes a bounded queue of step circuits. The thread that
+//! receives the inputs pushes the step of each input with
+//! [`StepSender::push`], and [`RecursiveSNARK::prove_queued`], on another
+//! thread, proves the steps in the order they were pushed, so that step `i`
+//! is proved while the input of step `i + 1` is still being received. The
+//! bound limits the steps waiting to be proved, and so the memory of the
+//! inputs received ahead of the prover: a push waits while the queue is
+//! full. Once the sender is dropped, the prover proves the steps left and
+//! returns; if a step fails, the prover returns its error and the following
+//! pushes fail, handing their steps back.
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+use super::{error::SuperNovaError, PublicParams, RecursiveSNARK, StepCircuit};
+use crate::traits::{CurveCycleEquipped, Dual, Engine};
+
+/// The sending end of a [`step_queue`]
+#[derive(Debug)]
+pub struct StepSender<C> {
+  sender: SyncSender<C>,
+}
+
+/// The receiving end of a [`step_queue`], for
+/// [`RecursiveSNARK::prove_queued`]
+#[derive(Debug)]
+pub struct StepReceiver<C> {
+  receiver: Receiver<C>,
+}
+
+/// A queue of at most `bound` steps waiting to be proved
+pub fn step_queue<C>(bound: usize) -> (StepSender<C>, StepReceiver<C>) {
+  let (sender, receiver) = mpsc::sync_channel(bound);
+  (StepSender { sender }, StepReceiver { receiver })
+}
+
+impl<C> StepSender<C> {
+  /// Pushes `step`, waiting while the queue is full; returns `step` back if
+  /// the prover stopped
+  pub fn push(&self, step: C) -> Result<(), C> { self.sender.send(step).map_err(|e| e.0) }
+
+  /// Pushes `step` if the queue is not full; returns `step` back otherwise,
+  /// or if the prover stopped
+  pub fn try_push(&self, step: C) -> Result<(), C> {
+    self.sender.try_send(step).map_err(|e| match e {
+      TrySendError::Full(step) | TrySendError::Disconnected(step) => step,
+    })
+  }
+}
+
+impl<E1: CurveCycleEquipped> RecursiveSNARK<E1> {
+  /// Proves the steps of `steps`, as [`RecursiveSNARK::prove_step`] does,
+  /// as they are pushed and until the sender is dropped, and returns the
+  /// number of steps proved
+  pub fn prove_queued<
+    C1: StepCircuit<E1::Scalar>,
+    C2: StepCircuit<<Dual<E1> as Engine>::Scalar>,
+  >(
+    &mut self,
+    pp: &PublicParams<E1>,
+    steps: StepReceiver<C1>,
+    c_secondary: &C2,
+  ) -> Result<usize, SuperNovaError> {
+    let mut proved = 0;
+    // dropping `steps` on an error makes the following pushes fail
+    for step in steps.receiver.iter() {
+      self.prove_step(pp, &step, c_secondary)?;
+      proved += 1;
+    }
+    Ok(proved)
+  }
+}
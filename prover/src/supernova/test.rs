@@ -1,5 +1,5 @@
 use core::marker::PhantomData;
-use std::fmt::Write;
+use std::{fmt::Write, time::Duration};
 
 use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
 use expect_test::{expect, Expect};
@@ -12,7 +12,7 @@ use crate::{
   gadgets::{alloc_one, alloc_zero},
   provider::{poseidon::PoseidonConstantsCircuit, Bn256EngineIPA, GrumpkinEngine},
   supernova::circuit::{StepCircuit, TrivialCircuit},
-  traits::snark::default_ck_hint,
+  traits::snark::{default_ck_hint, BatchedRelaxedR1CSSNARKTrait, RelaxedR1CSSNARKTrait},
 };
 
 #[derive(Clone, Debug, Default)]
@@ -397,6 +397,690 @@ where E1: CurveCycleEquipped {
   assert_eq!(*program_counter, -<E1 as Engine>::Scalar::ONE);
 }
 
+fn test_memory_estimates_with<E1>()
+where E1: CurveCycleEquipped {
+  type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type S1<E> = crate::spartan::batched::BatchedRelaxedR1CSSNARK<E, EE<E>>;
+  type S2<E> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE<E>>;
+
+  let test_rom = TestROM::<E1>::new(vec![OPCODE_0, OPCODE_1]);
+  let pp = PublicParams::setup(&test_rom, &*default_ck_hint(), &*default_ck_hint());
+
+  let cubic = pp.estimate_memory(&test_rom.primary_circuit(OPCODE_0)).unwrap();
+  let square = pp.estimate_memory(&test_rom.primary_circuit(OPCODE_1)).unwrap();
+  assert_eq!(cubic.resident, square.resident);
+  assert!(cubic.transient > 0 && square.transient > 0);
+
+  // the parameters alone already hold the primary commitment key
+  let ck_len = crate::traits::commitment::Len::length(&*pp.ck_primary);
+  assert!(cubic.resident > ck_len * core::mem::size_of::<<E1 as Engine>::Base>());
+
+  let compression = snark::CompressedSNARK::<E1, S1<E1>, S2<Dual<E1>>>::estimate_memory(&pp);
+  assert_eq!(compression.resident, cubic.resident);
+  assert!(compression.peak() > cubic.peak().max(square.peak()));
+}
+
+#[test]
+fn test_memory_estimates() { test_memory_estimates_with::<Bn256EngineIPA>(); }
+
+fn test_prove_time_estimates_with<E1>()
+where E1: CurveCycleEquipped {
+  let profile = estimate::DeviceProfile::calibrate::<E1>();
+  assert!(profile.msm_ns_per_point > 0.0 && profile.field_mul_ns > 0.0);
+
+  let test_rom = TestROM::<E1>::new(vec![OPCODE_0, OPCODE_1]);
+  let pp = PublicParams::setup(&test_rom, &*default_ck_hint(), &*default_ck_hint());
+
+  let profile = estimate::DeviceProfile { msm_ns_per_point: 1000.0, field_mul_ns: 10.0 };
+  let cubic = pp.estimate_prove_time(&test_rom.primary_circuit(OPCODE_0), &profile).unwrap();
+  let square = pp.estimate_prove_time(&test_rom.primary_circuit(OPCODE_1), &profile).unwrap();
+  assert_eq!(cubic.compression, square.compression);
+  assert!(cubic.per_step > core::time::Duration::ZERO);
+  assert_eq!(cubic.total(2), 2 * cubic.per_step + cubic.compression);
+  // more steps than fit in a `u32` neither wrap nor overflow
+  assert!(cubic.total(1 << 33) > cubic.total(1 << 32));
+  assert_eq!(cubic.total(usize::MAX), Duration::from_nanos(u64::MAX));
+
+  // a slower device takes longer
+  let slower = estimate::DeviceProfile { msm_ns_per_point: 2000.0, field_mul_ns: 20.0 };
+  let slower_cubic = pp.estimate_prove_time(&test_rom.primary_circuit(OPCODE_0), &slower).unwrap();
+  assert!(slower_cubic.total(1) > cubic.total(1));
+
+  // a circuit that is not of the parameters is an error
+  let missing = TestROMCircuit::Cubic(CubicCircuit::new(2, test_rom.rom.len()));
+  use crate::errors::NovaError::InvalidIndex;
+  assert_eq!(pp.estimate_prove_time(&missing, &profile), Err(InvalidIndex));
+  assert_eq!(pp.estimate_memory(&missing), Err(InvalidIndex));
+}
+
+#[test]
+fn test_prove_time_estimates() { test_prove_time_estimates_with::<Bn256EngineIPA>(); }
+
+fn test_circuit_registry_with<E1>()
+where E1: CurveCycleEquipped {
+  // two versions of the circuits, for ROMs of different sizes
+  let roms = [vec![OPCODE_0, OPCODE_1], vec![OPCODE_0, OPCODE_1, OPCODE_1]];
+  let mut registry = CircuitRegistry::new();
+  let mut snarks = vec![];
+  for (version, rom) in roms.into_iter().enumerate() {
+    let test_rom = TestROM::<E1>::new(rom);
+    let pp = Arc::new(PublicParams::setup(&test_rom, &*default_ck_hint(), &*default_ck_hint()));
+    let circuits_digest = registry.register(format!("v{version}"), pp.clone()).unwrap();
+    assert_eq!(circuits_digest, pp.circuits_digest());
+
+    let mut z0_primary = vec![<E1 as Engine>::Scalar::ONE, <E1 as Engine>::Scalar::ZERO];
+    z0_primary.extend(test_rom.rom.iter().map(|op| <E1 as Engine>::Scalar::from(*op as u64)));
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+    let circuit_primary = test_rom.primary_circuit(test_rom.rom[0]);
+    let circuit_secondary = test_rom.secondary_circuit();
+    let mut recursive_snark = RecursiveSNARK::new(
+      &pp,
+      &test_rom,
+      &circuit_primary,
+      &circuit_secondary,
+      &z0_primary,
+      &z0_secondary,
+    )
+    .unwrap();
+    recursive_snark.prove_step(&pp, &circuit_primary, &circuit_secondary).unwrap();
+    snarks.push((recursive_snark, z0_primary, z0_secondary));
+  }
+  assert_eq!(registry.versions().map(|(name, _)| name).collect::<Vec<_>>(), ["v0", "v1"]);
+
+  // each proof is verified against the version it was made against
+  for (version, (snark, z0_primary, z0_secondary)) in snarks.iter().enumerate() {
+    let name = format!("v{version}");
+    assert_eq!(registry.version_of(snark).map(|(name, _)| name), Some(name.as_str()));
+    let (verified, ..) = registry.verify(snark, z0_primary, z0_secondary).unwrap();
+    assert_eq!(verified, name);
+  }
+
+  // a version cannot be registered twice
+  let (v0_digest, v0_pp) =
+    registry.versions().next().map(|(_, d)| (*d, registry.get(d).unwrap().clone())).unwrap();
+  assert_eq!(registry.register("v2", v0_pp), Err(SuperNovaError::CircuitVersionExists));
+
+  // proofs of an unregistered version are rejected
+  registry.unregister(&v0_digest).unwrap();
+  let (snark, z0_primary, z0_secondary) = &snarks[0];
+  assert!(registry.version_of(snark).is_none());
+  assert_eq!(
+    registry.verify(snark, z0_primary, z0_secondary).map(|_| ()),
+    Err(SuperNovaError::ParamsDigestMismatch)
+  );
+}
+
+#[test]
+fn test_circuit_registry() { test_circuit_registry_with::<Bn256EngineIPA>(); }
+
+/// A step circuit without non-deterministic inputs, which counts its
+/// syntheses
+#[derive(Clone, Debug, Default)]
+struct CountingCircuit<F> {
+  syntheses: Arc<std::sync::atomic::AtomicUsize>,
+  _p:        PhantomData<F>,
+}
+
+impl<F: PrimeField> StepCircuit<F> for CountingCircuit<F> {
+  fn arity(&self) -> usize { 1 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    self.syntheses.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    Ok((pc.cloned(), vec![z[0].square(cs.namespace(|| "x_sq"))?]))
+  }
+
+  fn witness_digest(&self) -> Option<[u8; 32]> { Some([0; 32]) }
+}
+
+impl<E1: CurveCycleEquipped> NonUniformCircuit<E1> for CountingCircuit<E1::Scalar> {
+  type C1 = Self;
+  type C2 = TrivialCircuit<<Dual<E1> as Engine>::Scalar>;
+
+  fn num_circuits(&self) -> usize { 1 }
+
+  fn primary_circuit(&self, _circuit_index: usize) -> Self { self.clone() }
+
+  fn secondary_circuit(&self) -> Self::C2 { Default::default() }
+}
+
+fn test_witness_cache_with<E1>()
+where E1: CurveCycleEquipped {
+  let circuit = CountingCircuit::<E1::Scalar>::default();
+  let secondary_circuit = TrivialCircuit::default();
+  let pp = PublicParams::<E1>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint());
+
+  let z0_primary = vec![<E1 as Engine>::Scalar::from(2u64)];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let mut recursive_snark =
+    RecursiveSNARK::new(&pp, &circuit, &circuit, &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap();
+  let cache = Arc::new(WitnessCache::new(1));
+  recursive_snark.set_witness_cache(Some(cache.clone()));
+  recursive_snark.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+
+  // the step is synthesized once, and its retry reuses the cached witness
+  let before = recursive_snark.clone();
+  let syntheses = circuit.syntheses.load(std::sync::atomic::Ordering::SeqCst);
+  recursive_snark.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+  assert_eq!(circuit.syntheses.load(std::sync::atomic::Ordering::SeqCst), syntheses + 1);
+  assert_eq!(cache.len(), 1);
+
+  let mut retry = before.clone();
+  retry.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+  assert_eq!(circuit.syntheses.load(std::sync::atomic::Ordering::SeqCst), syntheses + 1);
+  assert_eq!(retry.zi_primary(), recursive_snark.zi_primary());
+  retry.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+
+  // the next step is not in the cache, and evicts the previous one
+  retry.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+  assert_eq!(circuit.syntheses.load(std::sync::atomic::Ordering::SeqCst), syntheses + 2);
+  assert_eq!(cache.len(), 1);
+  retry.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+
+  let mut retry = before;
+  retry.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+  assert_eq!(circuit.syntheses.load(std::sync::atomic::Ordering::SeqCst), syntheses + 3);
+}
+
+#[test]
+fn test_witness_cache() { test_witness_cache_with::<Bn256EngineIPA>(); }
+
+/// A step circuit adding the bytes of its advice, of at most `max_len`
+/// bytes, to its input
+#[derive(Clone, Debug)]
+struct SumCircuit {
+  max_len: usize,
+}
+
+impl<F: PrimeField> AdvisedCircuit<F> for SumCircuit {
+  type Advice = Vec<u8>;
+
+  fn arity(&self) -> usize { 1 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn max_advice_size(&self) -> usize { self.max_len }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+    advice: &Vec<u8>,
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let mut sum = z[0].clone();
+    for i in 0..self.max_len {
+      let byte = F::from(u64::from(advice.get(i).copied().unwrap_or(0)));
+      let next = AllocatedNum::alloc(cs.namespace(|| format!("sum {i}")), || {
+        sum.get_value().map(|sum| sum + byte).ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      let byte = AllocatedNum::alloc(cs.namespace(|| format!("byte {i}")), || Ok(byte))?;
+      cs.enforce(
+        || format!("add {i}"),
+        |lc| lc + sum.get_variable() + byte.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + next.get_variable(),
+      );
+      sum = next;
+    }
+    Ok((pc.cloned(), vec![sum]))
+  }
+}
+
+impl<E1: CurveCycleEquipped> NonUniformCircuit<E1> for AdvisedStep<E1::Scalar, SumCircuit> {
+  type C1 = Self;
+  type C2 = TrivialCircuit<<Dual<E1> as Engine>::Scalar>;
+
+  fn num_circuits(&self) -> usize { 1 }
+
+  fn primary_circuit(&self, _circuit_index: usize) -> Self { self.clone() }
+
+  fn secondary_circuit(&self) -> Self::C2 { Default::default() }
+}
+
+fn test_step_advice_with<E1>()
+where E1: CurveCycleEquipped {
+  let circuit = SumCircuit { max_len: 4 };
+  let shape = AdvisedStep::<E1::Scalar, _>::shape(circuit.clone()).unwrap();
+  let pp = PublicParams::<E1>::setup(&shape, &*default_ck_hint(), &*default_ck_hint());
+
+  // the advice is bounded by the circuit
+  assert!(matches!(
+    AdvisedStep::<E1::Scalar, _>::new(circuit.clone(), vec![1; 5]),
+    Err(NovaError::InvalidAdvice(_))
+  ));
+
+  // the advice round-trips through its encoding, and is the witness digest
+  let steps = [vec![1, 2], vec![3, 4, 5, 6]]
+    .map(|advice| AdvisedStep::<E1::Scalar, _>::new(circuit.clone(), advice).unwrap());
+  let encoded = steps[0].encoded_advice().unwrap();
+  let decoded = AdvisedStep::<E1::Scalar, _>::from_encoded(circuit.clone(), &encoded).unwrap();
+  assert_eq!(decoded.advice(), steps[0].advice());
+  assert_eq!(decoded.witness_digest(), steps[0].witness_digest());
+  assert_ne!(steps[0].witness_digest(), steps[1].witness_digest());
+  assert!(
+    AdvisedStep::<E1::Scalar, _>::from_encoded(circuit, &[encoded, vec![0]].concat()).is_err()
+  );
+
+  // the parameters of the shape prove the steps of every advice
+  let z0_primary = vec![<E1 as Engine>::Scalar::ONE];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let secondary_circuit = TrivialCircuit::default();
+  let mut recursive_snark =
+    RecursiveSNARK::new(&pp, &shape, &steps[0], &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap();
+  for step in &steps {
+    recursive_snark.prove_step(&pp, step, &secondary_circuit).unwrap();
+  }
+  let (zi_primary, _) = recursive_snark.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+  assert_eq!(zi_primary, vec![<E1 as Engine>::Scalar::from(22)]);
+}
+
+#[test]
+fn test_step_advice() { test_step_advice_with::<Bn256EngineIPA>(); }
+
+fn test_prove_queued_with<E1>()
+where E1: CurveCycleEquipped {
+  let circuit = CountingCircuit::<E1::Scalar>::default();
+  let secondary_circuit = TrivialCircuit::default();
+  let pp = PublicParams::<E1>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint());
+  let z0_primary = vec![<E1 as Engine>::Scalar::from(2u64)];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let new_snark = || {
+    RecursiveSNARK::new(&pp, &circuit, &circuit, &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap()
+  };
+
+  let mut expected = new_snark();
+  for _ in 0..4 {
+    expected.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+  }
+
+  // the steps are proved as they are pushed, through a queue of one step
+  let mut recursive_snark = new_snark();
+  let (sender, receiver) = step_queue(1);
+  let proved = std::thread::scope(|s| {
+    let prover = s.spawn(|| recursive_snark.prove_queued(&pp, receiver, &secondary_circuit));
+    for _ in 0..4 {
+      sender.push(circuit.clone()).unwrap();
+    }
+    drop(sender);
+    prover.join().unwrap()
+  });
+  assert_eq!(proved, Ok(4));
+  assert_eq!(recursive_snark.zi_primary(), expected.zi_primary());
+  recursive_snark.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+
+  // pushes fail once the prover stopped
+  let (sender, receiver) = step_queue::<CountingCircuit<E1::Scalar>>(1);
+  drop(receiver);
+  assert!(sender.push(circuit.clone()).is_err());
+  assert!(sender.try_push(circuit).is_err());
+}
+
+#[test]
+fn test_prove_queued() { test_prove_queued_with::<Bn256EngineIPA>(); }
+
+fn test_prove_by_deadline_with<E1>()
+where E1: CurveCycleEquipped {
+  type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type S1<E> = crate::spartan::batched::BatchedRelaxedR1CSSNARK<E, EE<E>>;
+  type S2<E> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE<E>>;
+
+  let circuit = CountingCircuit::<E1::Scalar>::default();
+  let secondary_circuit = TrivialCircuit::default();
+  let pp =
+    PublicParams::<E1>::setup(&circuit, &*S1::<E1>::ck_floor(), &*S2::<Dual<E1>>::ck_floor());
+  let z0_primary = vec![<E1 as Engine>::Scalar::from(2u64)];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let new_snark = || {
+    RecursiveSNARK::new(&pp, &circuit, &circuit, &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap()
+  };
+  let steps = vec![circuit.clone(); 4];
+
+  let mut expected = new_snark();
+  for step in &steps {
+    expected.prove_step(&pp, step, &secondary_circuit).unwrap();
+  }
+
+  // on a clock ticking a second per reading, a step takes a second, and two
+  // fit in three seconds
+  let ticks = Arc::new(std::sync::atomic::AtomicU64::new(0));
+  let clock = {
+    let ticks = ticks.clone();
+    move || Duration::from_secs(ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+  };
+  let deadline = Deadline::with_clock(clock, Duration::from_secs(3));
+  let estimate = estimate::TimeEstimate::default();
+  let proving =
+    new_snark().prove_steps_by(&pp, &steps, &secondary_circuit, &estimate, &deadline).unwrap();
+  let Proving::Paused(checkpoint) = proving else { panic!("the steps do not fit") };
+  assert_eq!(checkpoint.num_steps(), 2);
+
+  // the checkpoint resumes from its encoding
+  let bytes = checkpoint.to_bytes().unwrap();
+  assert!(Checkpoint::<E1>::from_bytes(&[bytes.clone(), vec![0]].concat()).is_err());
+  let recursive_snark = Checkpoint::<E1>::from_bytes(&bytes).unwrap().resume(&pp).unwrap();
+  let deadline = Deadline::after(Duration::from_secs(3600));
+  let proving = recursive_snark
+    .prove_steps_by(&pp, &steps[2..], &secondary_circuit, &estimate, &deadline)
+    .unwrap();
+  let Proving::Done(recursive_snark) = proving else { panic!("the steps fit") };
+  assert_eq!(recursive_snark.zi_primary(), expected.zi_primary());
+  recursive_snark.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+
+  // the compression only starts if it fits
+  let (pk, vk) = snark::CompressedSNARK::<_, S1<E1>, S2<Dual<E1>>>::setup(&pp).unwrap();
+  let estimate = estimate::TimeEstimate { compression: Duration::from_secs(1), ..estimate };
+  let expired = Deadline::after(Duration::ZERO);
+  let proving =
+    snark::CompressedSNARK::prove_by(&pp, &pk, &recursive_snark, &estimate, &expired).unwrap();
+  assert!(matches!(proving, Proving::Paused(checkpoint) if checkpoint.num_steps() == 4));
+  let proving =
+    snark::CompressedSNARK::prove_by(&pp, &pk, &recursive_snark, &estimate, &deadline).unwrap();
+  let Proving::Done(compressed_snark) = proving else { panic!("the compression fits") };
+  compressed_snark.verify(&pp, &vk, &z0_primary, &z0_secondary).unwrap();
+}
+
+#[test]
+fn test_prove_by_deadline() { test_prove_by_deadline_with::<Bn256EngineIPA>(); }
+
+/// A remote prover that proves its jobs as they are submitted, over no
+/// network, folding one step of [`CountingCircuit`] per fold job
+struct LoopbackProver<'a, E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>, {
+  pp:   &'a PublicParams<E1>,
+  pk:   &'a snark::ProverKey<E1, S1, S2>,
+  jobs: Vec<remote::JobStatus>,
+}
+
+impl<E1, S1, S2> remote::Transport for LoopbackProver<'_, E1, S1, S2>
+where
+  E1: CurveCycleEquipped,
+  S1: BatchedRelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<Dual<E1>>,
+{
+  fn post(&mut self, path: &str, body: Vec<u8>) -> Result<Vec<u8>, NovaError> {
+    assert_eq!(path, remote::JOBS_PATH);
+    let job: remote::JobRequest = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job.pp_digest, remote::digest_hex(&self.pp.digest()));
+    let status = match job.kind {
+      remote::JobKind::Compress => {
+        let request = job.compression_request::<E1>()?;
+        let snark =
+          snark::CompressedSNARK::<E1, S1, S2>::prove_request(self.pp, self.pk, &request).unwrap();
+        remote::JobStatus::compressed(&snark)?
+      },
+      remote::JobKind::Fold => {
+        let mut recursive_snark = job.checkpoint::<E1>()?.resume(self.pp).unwrap();
+        let (circuit, secondary_circuit) = (CountingCircuit::default(), TrivialCircuit::default());
+        recursive_snark.prove_step(self.pp, &circuit, &secondary_circuit).unwrap();
+        remote::JobStatus::folded(&Checkpoint::new(recursive_snark))?
+      },
+    };
+    self.jobs.push(status);
+    let job_id = (self.jobs.len() - 1).to_string();
+    Ok(serde_json::to_vec(&remote::JobSubmitted { job_id }).unwrap())
+  }
+
+  fn get(&mut self, path: &str) -> Result<Vec<u8>, NovaError> {
+    let job_id = path.strip_prefix(&format!("{}/", remote::JOBS_PATH)).unwrap();
+    Ok(serde_json::to_vec(&self.jobs[job_id.parse::<usize>().unwrap()]).unwrap())
+  }
+}
+
+fn test_remote_proving_with<E1>()
+where E1: CurveCycleEquipped {
+  type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type S1<E> = crate::spartan::batched::BatchedRelaxedR1CSSNARK<E, EE<E>>;
+  type S2<E> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE<E>>;
+
+  let circuit = CountingCircuit::<E1::Scalar>::default();
+  let secondary_circuit = TrivialCircuit::default();
+  let pp =
+    PublicParams::<E1>::setup(&circuit, &*S1::<E1>::ck_floor(), &*S2::<Dual<E1>>::ck_floor());
+  let (pk, vk) = snark::CompressedSNARK::<_, S1<E1>, S2<Dual<E1>>>::setup(&pp).unwrap();
+  let z0_primary = vec![<E1 as Engine>::Scalar::from(2u64)];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let mut recursive_snark =
+    RecursiveSNARK::new(&pp, &circuit, &circuit, &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap();
+  recursive_snark.prove_step(&pp, &circuit, &secondary_circuit).unwrap();
+
+  let mut prover = remote::RemoteProver::new(LoopbackProver { pp: &pp, pk: &pk, jobs: vec![] });
+
+  // the prover folds a step from the checkpoint of the client
+  let job_id = prover.submit_checkpoint(&Checkpoint::new(recursive_snark)).unwrap();
+  let checkpoint = prover.checkpoint::<E1>(&job_id, pp.digest()).unwrap().unwrap();
+  assert_eq!(checkpoint.num_steps(), 2);
+  assert_eq!(
+    prover.checkpoint::<E1>(&job_id, E1::Scalar::ZERO).unwrap_err(),
+    SuperNovaError::ParamsDigestMismatch
+  );
+  let recursive_snark = checkpoint.resume(&pp).unwrap();
+  recursive_snark.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+
+  // then compresses the steps
+  let request = snark::CompressionRequest::new(&recursive_snark);
+  let job_id = prover.submit_compression(&request).unwrap();
+  let compressed_snark =
+    prover.compressed_snark::<E1, S1<E1>, S2<Dual<E1>>>(&job_id, &request).unwrap().unwrap();
+  compressed_snark.verify(&pp, &vk, &z0_primary, &z0_secondary).unwrap();
+
+  // jobs decode only as their kind, in the version of the protocol
+  let job = remote::JobRequest::compress(&request).unwrap();
+  assert!(job.checkpoint::<E1>().is_err());
+  let job = remote::JobRequest { version: remote::PROTOCOL_VERSION + 1, ..job };
+  assert!(matches!(job.compression_request::<E1>(), Err(NovaError::RemoteProverError(_))));
+
+  // the statuses are tagged, with bytes in hexadecimal
+  assert_eq!(serde_json::to_string(&remote::JobStatus::Queued).unwrap(), r#"{"status":"queued"}"#);
+  let done = remote::JobStatus::Done { result: vec![0xab, 0x01] };
+  assert_eq!(serde_json::to_string(&done).unwrap(), r#"{"status":"done","result":"ab01"}"#);
+}
+
+#[test]
+fn test_remote_proving() { test_remote_proving_with::<Bn256EngineIPA>(); }
+
+fn test_proving_policy_with<E1>()
+where E1: CurveCycleEquipped {
+  use policy::{DeviceHints, Network, Placement, ProvingPolicy, Reason, Workload};
+  type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type S1<E> = crate::spartan::batched::BatchedRelaxedR1CSSNARK<E, EE<E>>;
+  type S2<E> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE<E>>;
+
+  let test_rom = TestROM::<E1>::new(vec![OPCODE_0, OPCODE_1]);
+  let pp = PublicParams::setup(&test_rom, &*default_ck_hint(), &*default_ck_hint());
+  let profile = estimate::DeviceProfile { msm_ns_per_point: 1000.0, field_mul_ns: 10.0 };
+  let circuit = test_rom.primary_circuit(OPCODE_0);
+  let workload =
+    Workload::estimate::<E1, S1<E1>, S2<Dual<E1>>, _>(&pp, &circuit, &profile, 10).unwrap();
+  assert_eq!(Ok(workload.time), pp.estimate_prove_time(&circuit, &profile));
+  assert!(workload.handoff_bytes > 0);
+
+  // a small proof runs on the device
+  let policy = ProvingPolicy::default();
+  let hints = DeviceHints::default();
+  let plan = policy.plan(&workload, &hints);
+  assert_eq!((plan.folding.placement, plan.folding.reason), (Placement::Local, Reason::Fits));
+  assert_eq!(plan.compression.placement, Placement::Local);
+
+  // a slow one is handed off, and compressed where it is folded
+  let slow = Workload { num_steps: 1 << 30, ..workload };
+  let plan = policy.plan(&slow, &hints);
+  assert_eq!((plan.folding.placement, plan.folding.reason), (Placement::Remote, Reason::TooSlow));
+  assert_eq!(plan.compression.reason, Reason::FollowsFolding);
+  let offline = DeviceHints { network: Network::Offline, ..hints };
+  assert_eq!(policy.plan(&slow, &offline).folding.reason, Reason::Offline);
+
+  // the compression alone is handed off if only it does not fit
+  let small_memory =
+    DeviceHints { available_memory: Some(workload.folding_memory.peak()), ..hints };
+  let plan = policy.plan(&workload, &small_memory);
+  assert_eq!(plan.folding.placement, Placement::Local);
+  assert_eq!(
+    (plan.compression.placement, plan.compression.reason),
+    (Placement::Remote, Reason::TooLarge)
+  );
+  let metered = DeviceHints { network: Network::Metered, ..small_memory };
+  let policy_metered = ProvingPolicy { max_metered_upload: 0, ..ProvingPolicy::default() };
+  assert_eq!(policy_metered.plan(&workload, &metered).compression.reason, Reason::MeteredUpload);
+
+  // a low battery hands off, unless charging
+  let low_battery = DeviceHints { battery: Some(0.1), ..hints };
+  assert_eq!(policy.plan(&workload, &low_battery).folding.reason, Reason::LowBattery);
+  let charging = DeviceHints { charging: true, ..low_battery };
+  assert_eq!(policy.plan(&workload, &charging).folding.reason, Reason::Fits);
+
+  // the host app has the last word
+  let policy = ProvingPolicy::default().with_override(|phase, _, _, decision| match phase {
+    policy::ProvingPhase::Folding => policy::Decision::overridden(Placement::Local),
+    policy::ProvingPhase::Compression => decision,
+  });
+  let plan = policy.plan(&slow, &hints);
+  assert_eq!(plan.folding, policy::Decision::overridden(Placement::Local));
+  assert_eq!(
+    (plan.compression.placement, plan.compression.reason),
+    (Placement::Local, Reason::Fits)
+  );
+}
+
+#[test]
+fn test_proving_policy() { test_proving_policy_with::<Bn256EngineIPA>(); }
+
+/// A unit adding `x` to `z[0]`, of padding the unit adding 0
+#[derive(Clone, Debug, Default)]
+struct AddUnit {
+  x: u64,
+}
+
+impl<F: PrimeField> BatchUnit<F> for AddUnit {
+  fn arity(&self) -> usize { 1 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let x = F::from(self.x);
+    let sum = AllocatedNum::alloc(cs.namespace(|| "sum"), || {
+      z[0].get_value().map(|z| z + x).ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "add",
+      |lc| lc + z[0].get_variable() + (x, CS::one()),
+      |lc| lc + CS::one(),
+      |lc| lc + sum.get_variable(),
+    );
+    Ok(vec![sum])
+  }
+}
+
+impl<E1: CurveCycleEquipped> NonUniformCircuit<E1> for BatchStep<AddUnit> {
+  type C1 = Self;
+  type C2 = TrivialCircuit<<Dual<E1> as Engine>::Scalar>;
+
+  fn num_circuits(&self) -> usize { 1 }
+
+  fn primary_circuit(&self, _circuit_index: usize) -> Self { self.clone() }
+
+  fn secondary_circuit(&self) -> Self::C2 { Default::default() }
+}
+
+fn test_batch_steps_with<E1>()
+where E1: CurveCycleEquipped {
+  // slow and fast devices batch fewer and more units, within the maximum
+  let slow = estimate::DeviceProfile { msm_ns_per_point: 1_000_000.0, field_mul_ns: 10_000.0 };
+  let fast = estimate::DeviceProfile { msm_ns_per_point: 1.0, field_mul_ns: 0.01 };
+  let target = core::time::Duration::from_millis(10);
+  assert_eq!(slow.batch_size::<E1, _>(&AddUnit::default(), target, 8), 1);
+  assert_eq!(fast.batch_size::<E1, _>(&AddUnit::default(), target, 8), 8);
+
+  let max_batch = 4;
+  let units = (1..=6).map(|x| AddUnit { x }).collect::<Vec<_>>();
+  assert!(BatchStep::steps(units.clone(), AddUnit::default(), 5, max_batch).is_err());
+  assert!(BatchStep::new(units.clone(), AddUnit::default(), max_batch).is_err());
+  let steps = BatchStep::steps(units, AddUnit::default(), max_batch, max_batch).unwrap();
+  assert_eq!(steps.iter().map(BatchStep::len).collect::<Vec<_>>(), [4, 2]);
+
+  // the padded step has the outputs of the step unpadded
+  let outputs = |step: &BatchStep<AddUnit>| {
+    let mut cs = bellpepper_core::test_cs::TestConstraintSystem::<E1::Scalar>::new();
+    let z = [1, 0].map(|v| {
+      AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {v}")), || E1::Scalar::from(v))
+    });
+    let (_, z_next) = step.synthesize(&mut cs, None, &z).unwrap();
+    assert!(cs.is_satisfied());
+    z_next.iter().map(|z| z.get_value()).collect::<Vec<_>>()
+  };
+  let unpadded = BatchStep::new(vec![AddUnit { x: 5 }, AddUnit { x: 6 }], AddUnit::default(), 2);
+  assert_eq!(outputs(&steps[1]), outputs(&unpadded.unwrap()));
+
+  // full and padded steps are of the same shape
+  let shape = BatchStep::new(vec![], AddUnit::default(), max_batch).unwrap();
+  let pp = PublicParams::<E1>::setup(&shape, &*default_ck_hint(), &*default_ck_hint());
+  let z0_primary = vec![<E1 as Engine>::Scalar::ONE, <E1 as Engine>::Scalar::ZERO];
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let secondary_circuit = TrivialCircuit::default();
+  let mut recursive_snark =
+    RecursiveSNARK::new(&pp, &shape, &steps[0], &secondary_circuit, &z0_primary, &z0_secondary)
+      .unwrap();
+  for step in &steps {
+    recursive_snark.prove_step(&pp, step, &secondary_circuit).unwrap();
+  }
+
+  // the outputs are those of the units in sequence, and their number
+  let (zi_primary, _) = recursive_snark.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+  assert_eq!(zi_primary, vec![<E1 as Engine>::Scalar::from(22), <E1 as Engine>::Scalar::from(6)]);
+}
+
+#[test]
+fn test_batch_steps() { test_batch_steps_with::<Bn256EngineIPA>(); }
+
+fn test_prove_steps_with<E1>()
+where E1: CurveCycleEquipped {
+  let rom = vec![OPCODE_1, OPCODE_1, OPCODE_0, OPCODE_0, OPCODE_1, OPCODE_0];
+  let test_rom = TestROM::<E1>::new(rom);
+  let pp = PublicParams::setup(&test_rom, &*default_ck_hint(), &*default_ck_hint());
+
+  let mut z0_primary = vec![<E1 as Engine>::Scalar::ONE, <E1 as Engine>::Scalar::ZERO];
+  z0_primary.extend(test_rom.rom.iter().map(|opcode| <E1 as Engine>::Scalar::from(*opcode as u64)));
+  let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ONE];
+  let circuits = test_rom.rom.iter().map(|op| test_rom.primary_circuit(*op)).collect::<Vec<_>>();
+  let secondary = test_rom.secondary_circuit();
+  let new = || {
+    RecursiveSNARK::new(&pp, &test_rom, &circuits[0], &secondary, &z0_primary, &z0_secondary)
+      .unwrap()
+  };
+
+  let mut sequential = new();
+  for circuit in &circuits {
+    sequential.prove_step(&pp, circuit, &secondary).unwrap();
+  }
+
+  // the pipelined steps prove exactly what the steps one after another do,
+  // the program counter of each step running ahead of the folds
+  let mut pipelined = new();
+  pipelined.prove_steps(&pp, circuits.iter().map(|circuit| (circuit, &secondary))).unwrap();
+  assert_eq!(bincode::serialize(&pipelined).unwrap(), bincode::serialize(&sequential).unwrap());
+  pipelined.verify(&pp, &z0_primary, &z0_secondary).unwrap();
+  assert_eq!(pipelined.program_counter(), -<E1 as Engine>::Scalar::ONE);
+}
+
+#[test]
+fn test_prove_steps() { test_prove_steps_with::<Bn256EngineIPA>(); }
+
 #[test]
 #[tracing_test::traced_test]
 fn test_trivial_nivc() {
@@ -0,0 +1,207 @@
+//! Small standard step circuits, for benchmarks, examples, and integration
+//! tests.
+//!
+//! The circuits are generic over the scalar field of their engine, so that
+//! the same circuit runs on every `Engine` of this crate, as the primary
+//! circuit of a `RecursiveSNARK` or a circuit of a SuperNova program. Each
+//! one computes its outputs outside of the circuit too, with `output`, so
+//! that a test checks the outputs of a proof against known-good values:
+//! - [`CounterCircuit`] increments its input,
+//! - [`FibonacciCircuit`] steps a Fibonacci sequence a number of times,
+//! - [`HashChainCircuit`] hashes links into a chain with Poseidon,
+//! - [`MinRootCircuit`] runs iterations of the MinRoot delay function (see [`crate::minroot`]).
+use core::marker::PhantomData;
+
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+pub use crate::minroot::MinRootCircuit;
+use crate::{provider::poseidon::PoseidonConstantsCircuit, supernova::StepCircuit};
+
+/// A circuit of arity 1 that increments its input: `z_{i+1} = z_i + 1`
+#[derive(Clone, Debug, Default)]
+pub struct CounterCircuit<F> {
+  _p: PhantomData<F>,
+}
+
+impl<F: PrimeField> CounterCircuit<F> {
+  /// The outputs of a step on `z`
+  pub fn output(&self, z: &[F]) -> Vec<F> { vec![z[0] + F::ONE] }
+}
+
+impl<F: PrimeField> StepCircuit<F> for CounterCircuit<F> {
+  fn arity(&self) -> usize { 1 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let z_next = AllocatedNum::alloc(cs.namespace(|| "z_next"), || {
+      z[0].get_value().map(|z| z + F::ONE).ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "z_next = z + 1",
+      |lc| lc + z[0].get_variable() + CS::one(),
+      |lc| lc + CS::one(),
+      |lc| lc + z_next.get_variable(),
+    );
+    Ok((pc.cloned(), vec![z_next]))
+  }
+}
+
+/// A circuit of arity 2 that steps a Fibonacci sequence `num_iters` times
+/// per step: `(a, b) -> (b, a + b)`
+#[derive(Clone, Debug)]
+pub struct FibonacciCircuit<F> {
+  num_iters: usize,
+  _p:        PhantomData<F>,
+}
+
+impl<F: PrimeField> FibonacciCircuit<F> {
+  /// The circuit stepping the sequence `num_iters` times per step
+  pub fn new(num_iters: usize) -> Self { Self { num_iters, _p: PhantomData } }
+
+  /// The outputs of a step on `z`
+  pub fn output(&self, z: &[F]) -> Vec<F> {
+    let (mut a, mut b) = (z[0], z[1]);
+    for _ in 0..self.num_iters {
+      (a, b) = (b, a + b);
+    }
+    vec![a, b]
+  }
+}
+
+impl<F: PrimeField> StepCircuit<F> for FibonacciCircuit<F> {
+  fn arity(&self) -> usize { 2 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let (mut a, mut b) = (z[0].clone(), z[1].clone());
+    for i in 0..self.num_iters {
+      let c = a.add(cs.namespace(|| format!("a + b {i}")), &b)?;
+      (a, b) = (b, c);
+    }
+    Ok((pc.cloned(), vec![a, b]))
+  }
+}
+
+/// A circuit of arity 1 that hashes its links into a chain, one at a time:
+/// `z <- H(z, link)`, with the Poseidon hash of the folding circuits
+///
+/// The links are the non-deterministic inputs of a step, so the circuits of
+/// all the steps must have as many links.
+#[derive(Clone, Debug)]
+pub struct HashChainCircuit<F: PrimeField> {
+  links:     Vec<F>,
+  constants: PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> HashChainCircuit<F> {
+  /// The circuit hashing `links` into the chain
+  pub fn new(links: Vec<F>) -> Self {
+    Self { links, constants: PoseidonConstantsCircuit::default() }
+  }
+
+  /// The outputs of a step on `z`
+  pub fn output(&self, z: &[F]) -> Vec<F> {
+    vec![self.links.iter().fold(z[0], |z, link| self.constants.hash(&[z, *link]))]
+  }
+}
+
+impl<F: PrimeField> StepCircuit<F> for HashChainCircuit<F> {
+  fn arity(&self) -> usize { 1 }
+
+  fn circuit_index(&self) -> usize { 0 }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: Option<&AllocatedNum<F>>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(Option<AllocatedNum<F>>, Vec<AllocatedNum<F>>), SynthesisError> {
+    let mut chain = z[0].clone();
+    for (i, link) in self.links.iter().enumerate() {
+      let link = AllocatedNum::alloc(cs.namespace(|| format!("link {i}")), || Ok(*link))?;
+      chain =
+        self.constants.hash_circuit(cs.namespace(|| format!("hash link {i}")), &[chain, link])?;
+    }
+    Ok((pc.cloned(), vec![chain]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ff::Field;
+
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, Bn256EngineIPA},
+    spartan::snark::RelaxedR1CSSNARK,
+    supernova::TrivialCircuit,
+    traits::{snark::RelaxedR1CSSNARKTrait, CurveCycleEquipped, Dual, Engine},
+    PublicParams, RecursiveSNARK,
+  };
+
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  /// Proves the steps of `circuits` from `z0`, and returns the verified
+  /// outputs
+  fn prove<E1: CurveCycleEquipped, C: StepCircuit<E1::Scalar>>(
+    circuits: &[C],
+    z0: &[E1::Scalar],
+  ) -> Vec<E1::Scalar> {
+    let secondary = TrivialCircuit::default();
+    let pp = PublicParams::<E1>::setup(
+      &circuits[0],
+      &secondary,
+      &*S::<E1>::ck_floor(),
+      &*S::<Dual<E1>>::ck_floor(),
+    )
+    .unwrap();
+
+    let z0_secondary = vec![<Dual<E1> as Engine>::Scalar::ZERO];
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &circuits[0], &secondary, z0, &z0_secondary).unwrap();
+    for circuit in circuits {
+      recursive_snark.prove_step(&pp, circuit, &secondary).unwrap();
+    }
+    recursive_snark.verify(&pp, circuits.len(), z0, &z0_secondary).unwrap().0
+  }
+
+  fn test_circuits_with<E1: CurveCycleEquipped>() {
+    let num_steps = 3;
+
+    let counter = vec![CounterCircuit::default(); num_steps];
+    let z0 = vec![E1::Scalar::from(5)];
+    assert_eq!(prove::<E1, _>(&counter, &z0), vec![E1::Scalar::from(8)]);
+
+    let fibonacci = vec![FibonacciCircuit::new(4); num_steps];
+    let z0 = vec![E1::Scalar::ZERO, E1::Scalar::ONE];
+    assert_eq!(prove::<E1, _>(&fibonacci, &z0), vec![E1::Scalar::from(144), E1::Scalar::from(233)]);
+
+    let hash_chain: Vec<_> = (0..num_steps)
+      .map(|i| HashChainCircuit::new(vec![E1::Scalar::from(2 * i as u64), E1::Scalar::from(7)]))
+      .collect();
+    let z0 = vec![E1::Scalar::ZERO];
+    let zn = hash_chain.iter().fold(z0.clone(), |z, circuit| circuit.output(&z));
+    assert_eq!(prove::<E1, _>(&hash_chain, &z0), zn);
+
+    let (z0, minroot) =
+      MinRootCircuit::new(E1::Scalar::from(3), E1::Scalar::from(4), 2, num_steps).unwrap();
+    let zn = minroot.iter().fold(z0.clone(), |z, circuit| circuit.output(&z));
+    assert_eq!(prove::<E1, _>(&minroot, &z0), zn);
+  }
+
+  #[test]
+  fn test_circuits() { test_circuits_with::<Bn256EngineIPA>(); }
+}
@@ -6,11 +6,12 @@ use core::{
   ops::{Add, Mul, MulAssign},
 };
 
+use ff::Field;
 use serde::{Deserialize, Serialize};
 
 use crate::{
   errors::NovaError,
-  traits::{AbsorbInROTrait, Engine, TranscriptReprTrait},
+  traits::{AbsorbInROTrait, Engine, SetupProgress, TranscriptReprTrait},
 };
 
 /// A helper trait for types implementing scalar multiplication.
@@ -53,6 +54,19 @@ pub trait CommitmentTrait<E: Engine>:
 
   /// Decompresses a compressed commitment into a commitment
   fn decompress(c: &Self::CompressedCommitment) -> Result<Self, NovaError>;
+
+  /// Returns the commitment scaled by `scalar`, the commitment to the vector
+  /// scaled by `scalar` under the same key
+  fn scale(&self, scalar: &E::Scalar) -> Self { *self * *scalar }
+
+  /// Returns `sum_i scalars[i] * comms[i]`, the commitment to the same linear
+  /// combination of the vectors under the same key, e.g. to fold commitments
+  fn linear_combination(comms: &[Self], scalars: &[E::Scalar]) -> Result<Self, NovaError> {
+    if comms.len() != scalars.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(comms.iter().zip(scalars).fold(Self::default(), |acc, (c, s)| acc + c.scale(s)))
+  }
 }
 
 /// A trait that helps determine the length of a structure.
@@ -82,6 +96,33 @@ pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
   /// Samples a new commitment key of a specified size
   fn setup(label: &'static [u8], n: usize) -> Self::CommitmentKey;
 
+  /// Samples the same commitment key as `setup`, reporting the number of
+  /// generators computed so far to `progress` under the stage `stage`
+  fn setup_with_progress(
+    label: &'static [u8],
+    n: usize,
+    stage: &str,
+    progress: &dyn SetupProgress,
+  ) -> Result<Self::CommitmentKey, NovaError> {
+    if !progress.report(stage, 0, n) {
+      return Err(NovaError::Interrupted);
+    }
+    let ck = Self::setup(label, n);
+    if !progress.report(stage, n, n) {
+      return Err(NovaError::Interrupted);
+    }
+    Ok(ck)
+  }
+
   /// Commits to the provided vector using the provided generators
   fn commit(ck: &Self::CommitmentKey, v: &[E::Scalar]) -> Self::Commitment;
+
+  /// Commits to `v` with the generators from the `offset`-th on, so that the
+  /// commitments to consecutive parts of a vector add up to the commitment to
+  /// the vector
+  fn commit_at(ck: &Self::CommitmentKey, offset: usize, v: &[E::Scalar]) -> Self::Commitment {
+    let mut padded = vec![E::Scalar::ZERO; offset];
+    padded.extend_from_slice(v);
+    Self::commit(ck, &padded)
+  }
 }
@@ -27,6 +27,12 @@ pub trait EvaluationEngineTrait<E: Engine>: Clone + Send + Sync {
   /// A type that holds the evaluation argument
   type EvaluationArgument: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de>;
 
+  /// A type that holds the auxiliary data the prover keeps alongside a
+  /// commitment, e.g. the Merkle tree of a hash-based commitment, or tables
+  /// precomputed for the evaluation argument. Engines whose commitments are
+  /// entirely described by the commitment use `()`.
+  type ProverCommitmentState: Send + Sync;
+
   /// A method to perform any additional setup needed to produce proofs of
   /// evaluations
   ///
@@ -37,7 +43,18 @@ pub trait EvaluationEngineTrait<E: Engine>: Clone + Send + Sync {
     ck: Arc<<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey>,
   ) -> (Self::ProverKey, Self::VerifierKey);
 
+  /// A method to compute the auxiliary data of the commitment to `poly`, when
+  /// committing to it, to pass it to `prove_with_state` later on
+  fn commitment_state(
+    ck: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey,
+    pk: &Self::ProverKey,
+    poly: &[E::Scalar],
+  ) -> Self::ProverCommitmentState;
+
   /// A method to prove the evaluation of a multilinear polynomial
+  ///
+  /// Engines with auxiliary commitment data recompute it here: callers that
+  /// kept it should use `prove_with_state` instead.
   fn prove(
     ck: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey,
     pk: &Self::ProverKey,
@@ -48,6 +65,24 @@ pub trait EvaluationEngineTrait<E: Engine>: Clone + Send + Sync {
     eval: &E::Scalar,
   ) -> Result<Self::EvaluationArgument, NovaError>;
 
+  /// A method to prove the evaluation of a multilinear polynomial, given the
+  /// auxiliary data of its commitment returned by `commitment_state`
+  ///
+  /// The default implementation ignores `state` and calls `prove`.
+  #[allow(clippy::too_many_arguments)]
+  fn prove_with_state(
+    ck: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey,
+    pk: &Self::ProverKey,
+    _state: &Self::ProverCommitmentState,
+    transcript: &mut E::TE,
+    comm: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::Commitment,
+    poly: &[E::Scalar],
+    point: &[E::Scalar],
+    eval: &E::Scalar,
+  ) -> Result<Self::EvaluationArgument, NovaError> {
+    Self::prove(ck, pk, transcript, comm, poly, point, eval)
+  }
+
   /// A method to verify the purported evaluation of a multilinear polynomials
   fn verify(
     vk: &Self::VerifierKey,
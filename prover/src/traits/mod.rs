@@ -2,12 +2,15 @@
 //! implement.
 use core::fmt::Debug;
 
+#[cfg(feature = "prove")]
 use bellpepper_core::{boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, SynthesisError};
+/// Derives `TranscriptReprTrait` for structs, with length-prefixed fields
+pub use edge_macros::TranscriptRepr;
 use ff::{PrimeField, PrimeFieldBits};
 use num_bigint::BigInt;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 
-use crate::errors::NovaError;
+use crate::{constants::SecurityLevel, errors::NovaError};
 
 pub mod commitment;
 
@@ -49,6 +52,7 @@ pub trait Engine: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
   type RO: ROTrait<Self::Base, Self::Scalar>;
 
   /// An alternate implementation of `Self::RO` in the circuit model
+  #[cfg(feature = "prove")]
   type ROCircuit: ROCircuitTrait<Self::Base>;
 
   /// A type that provides a generic Fiat-Shamir transcript to be used when
@@ -57,6 +61,10 @@ pub trait Engine: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
 
   /// A type that defines a commitment engine over scalars in the group
   type CE: CommitmentEngineTrait<Self>;
+
+  /// The security level the Fiat-Shamir challenges of the transcript and of
+  /// the sum-checks over `Self::Scalar` must reach
+  const SECURITY_LEVEL: SecurityLevel = SecurityLevel::Bits128;
 }
 
 /// This is a convenience trait to pair engines which fields are in a curve
@@ -80,6 +88,7 @@ pub trait AbsorbInROTrait<E: Engine> {
 pub trait ROTrait<Base: PrimeField, Scalar> {
   /// The circuit alter ego of this trait impl - this constrains it to use the
   /// same constants
+  #[cfg(feature = "prove")]
   type CircuitRO: ROCircuitTrait<Base, Constants = Self::Constants>;
 
   /// A type representing constants/parameters associated with the hash
@@ -105,6 +114,7 @@ pub trait ROTrait<Base: PrimeField, Scalar> {
 
 /// A helper trait that defines the behavior of a hash function that we use as
 /// an RO in the circuit model
+#[cfg(feature = "prove")]
 pub trait ROCircuitTrait<Base: PrimeField> {
   /// the vanilla alter ego of this trait - this constrains it to use the same
   /// constants
@@ -140,9 +150,15 @@ pub type ROConstants<E> =
   <<E as Engine>::RO as ROTrait<<E as Engine>::Base, <E as Engine>::Scalar>>::Constants;
 
 /// An alias for constants associated with `E::ROCircuit`
+#[cfg(feature = "prove")]
 pub type ROConstantsCircuit<E> =
   <<E as Engine>::ROCircuit as ROCircuitTrait<<E as Engine>::Base>>::Constants;
 
+/// An alias for constants associated with `E::ROCircuit`, which are those of
+/// `E::RO`, for the public parameters a verifier deserializes
+#[cfg(not(feature = "prove"))]
+pub type ROConstantsCircuit<E> = ROConstants<E>;
+
 /// This trait allows types to implement how they want to be added to
 /// `TranscriptEngine`
 pub trait TranscriptReprTrait<G: Group>: Send + Sync {
@@ -153,12 +169,26 @@ pub trait TranscriptReprTrait<G: Group>: Send + Sync {
 /// This trait defines the behavior of a transcript engine compatible with
 /// Spartan
 pub trait TranscriptEngineTrait<E: Engine>: Send + Sync {
+  /// whether the transcript encodes its messages as before transcripts were
+  /// versioned, for the protocols whose messages changed since to absorb them
+  /// as they did, see `provider::keccak::LEGACY_TRANSCRIPT_VERSION`
+  const LEGACY: bool = false;
+
   /// initializes the transcript
   fn new(label: &'static [u8]) -> Self;
 
   /// returns a scalar element of the group as a challenge
   fn squeeze(&mut self, label: &'static [u8]) -> Result<E::Scalar, NovaError>;
 
+  /// returns as many challenges as it takes to reach `E::SECURITY_LEVEL`,
+  /// for a protocol that a single challenge makes sound up to the size of
+  /// the scalar field (see `SecurityLevel::challenge_repetitions`)
+  fn squeeze_challenges(&mut self, label: &'static [u8]) -> Result<Vec<E::Scalar>, NovaError> {
+    (0..E::SECURITY_LEVEL.challenge_repetitions::<E::Scalar>())
+      .map(|_| self.squeeze(label))
+      .collect()
+  }
+
   /// absorbs any type that implements `TranscriptReprTrait` under a label
   fn absorb<T: TranscriptReprTrait<E::GE>>(&mut self, label: &'static [u8], o: &T);
 
@@ -166,17 +196,110 @@ pub trait TranscriptEngineTrait<E: Engine>: Send + Sync {
   fn dom_sep(&mut self, bytes: &'static [u8]);
 }
 
+/// Maps bytes squeezed from a transcript to a challenge in the field `F`
+pub trait HashToField<F: PrimeField> {
+  /// hashes `msg` to a field element, domain-separated by `dst`
+  fn hash_to_field(dst: &[u8], msg: &[u8]) -> F;
+}
+
 /// Defines additional methods on `PrimeField` objects
 pub trait PrimeFieldExt: PrimeField {
   /// Returns a scalar representing the bytes
   fn from_uniform(bytes: &[u8]) -> Self;
 }
 
+/// Reports the progress of a long-running setup, e.g. to a UI while the setup
+/// runs on a background thread, and lets the caller interrupt it
+pub trait SetupProgress {
+  /// Reports that `done` out of `total` units of work of `stage` are
+  /// complete. Returning `false` interrupts the setup, which then fails with
+  /// `NovaError::Interrupted`.
+  fn report(&self, stage: &str, done: usize, total: usize) -> bool;
+}
+
+impl<F: Fn(&str, usize, usize) -> bool> SetupProgress for F {
+  fn report(&self, stage: &str, done: usize, total: usize) -> bool { self(stage, done, total) }
+}
+
 impl<G: Group, T: TranscriptReprTrait<G>> TranscriptReprTrait<G> for &[T] {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     self.iter().flat_map(|t| t.to_transcript_bytes()).collect::<Vec<u8>>()
   }
 }
 
+impl<G: Group, T: TranscriptReprTrait<G>> TranscriptReprTrait<G> for Vec<T> {
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.as_slice().to_transcript_bytes() }
+}
+
+/// A proof or argument checking the bounds a verifier assumes of it, e.g. the
+/// lengths of its vectors, or that its points are on the curve. The types
+/// implementing it validate when they are deserialized, so that a service
+/// rejects garbage before an expensive verification. The scalars and the
+/// affine points they hold are themselves checked by their deserialization,
+/// to be canonical and on the curve.
+pub trait Validate {
+  /// Checks the bounds of `self`
+  fn validate(&self) -> Result<(), NovaError>;
+}
+
+/// Validates `value`, in the `Deserialize` impl of a type implementing
+/// [`Validate`]: the type derives its serde as `#[serde(remote = "Self")]`,
+/// and its `Deserialize` validates the value its derived `deserialize`
+/// returns.
+pub(crate) fn validated<'de, T: Validate, D: Deserializer<'de>>(value: T) -> Result<T, D::Error> {
+  value.validate().map_err(D::Error::custom)?;
+  Ok(value)
+}
+
+/// Concatenates `parts`, each prefixed by its length in bytes as a
+/// little-endian `u64`, so that distinct sequences of parts never encode to
+/// the same bytes. This is the encoding of `#[derive(TranscriptRepr)]`.
+pub fn length_prefixed<I: IntoIterator<Item = Vec<u8>>>(parts: I) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  for part in parts {
+    bytes.extend((part.len() as u64).to_le_bytes());
+    bytes.extend(part);
+  }
+  bytes
+}
+
 pub mod evaluation;
 pub mod snark;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::Bn256EngineKZG;
+
+  type E = Bn256EngineKZG;
+  type F = <E as Engine>::Scalar;
+  type G = <E as Engine>::GE;
+
+  #[derive(TranscriptRepr)]
+  struct Instance {
+    a: Vec<F>,
+    b: Vec<F>,
+  }
+
+  #[derive(TranscriptRepr)]
+  #[transcript(group = G)]
+  struct Claim(F, #[transcript(skip)] Vec<F>);
+
+  fn repr<T: TranscriptReprTrait<G>>(t: &T) -> Vec<u8> { t.to_transcript_bytes() }
+
+  #[test]
+  fn test_derive_transcript_repr() {
+    let (one, two) = (F::from(1), F::from(2));
+
+    // the plain concatenations of the fields collide, the derived encodings do not
+    let left = Instance { a: vec![one], b: vec![two] };
+    let right = Instance { a: vec![one, two], b: vec![] };
+    assert_eq!([repr(&left.a), repr(&left.b)].concat(), [repr(&right.a), repr(&right.b)].concat());
+    assert_ne!(repr(&left), repr(&right));
+    assert_eq!(repr(&left), length_prefixed([repr(&left.a), repr(&left.b)]));
+
+    // skipped fields are not absorbed
+    assert_eq!(repr(&Claim(one, vec![two])), repr(&Claim(one, vec![])));
+    assert_eq!(repr(&Claim(one, vec![two])), length_prefixed([repr(&one)]));
+  }
+}
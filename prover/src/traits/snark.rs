@@ -1,16 +1,41 @@
 //! This module defines a collection of traits that define the behavior of a
 //! `zkSNARK` for `RelaxedR1CS`
-use std::sync::Arc;
+use std::{
+  ops::{Add, AddAssign},
+  sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
   errors::NovaError,
+  inspect::{Check, VerifyTrace},
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
-  traits::Engine,
+  traits::{Engine, Group, TranscriptEngineTrait, TranscriptReprTrait},
   CommitmentKey,
 };
 
+/// The context of the application a proof is made for, e.g. its name and
+/// version, absorbed in the transcripts of the SNARKs so that a proof made for
+/// one application does not verify for another, even with the same keys. The
+/// empty context, the default, absorbs nothing: the proofs made without a
+/// context are unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AppContext<'a>(pub &'a [u8]);
+
+impl AppContext<'_> {
+  /// Absorbs the context in `transcript`, unless it is empty
+  pub fn absorb_in<E: Engine>(&self, transcript: &mut E::TE) {
+    if !self.0.is_empty() {
+      transcript.absorb(b"context", self);
+    }
+  }
+}
+
+impl<G: Group> TranscriptReprTrait<G> for AppContext<'_> {
+  fn to_transcript_bytes(&self) -> Vec<u8> { self.0.to_vec() }
+}
+
 // NOTES: This function seems heavily reliant on dynamic allocation all to
 // return 0 in the end...
 
@@ -26,6 +51,55 @@ pub fn default_ck_hint<E: Engine>() -> Box<dyn for<'a> Fn(&'a R1CSShape<E>) -> u
   Box::new(|_shape: &R1CSShape<E>| 0)
 }
 
+/// The sizes in bytes of the components of a proof serialized with `bincode`,
+/// which add up to its size
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSize {
+  /// the messages of the sum-check protocols
+  pub sumcheck: usize,
+  /// the evaluation arguments of the polynomial commitment scheme
+  pub pcs:      usize,
+  /// the commitments and the claimed evaluations sent besides them
+  pub claims:   usize,
+  /// the instances the proof is about, and the data that binds them
+  pub instance: usize,
+}
+
+impl ProofSize {
+  /// The size of the whole proof
+  pub fn total(&self) -> usize { self.sumcheck + self.pcs + self.claims + self.instance }
+
+  /// The size of a SNARK of `total` bytes, of which `sumcheck` bytes are
+  /// sum-check messages and `pcs` bytes are evaluation arguments
+  pub(crate) fn of_snark(total: usize, sumcheck: usize, pcs: usize) -> Self {
+    let claims = total.saturating_sub(sumcheck + pcs);
+    Self { sumcheck, pcs, claims, instance: 0 }
+  }
+}
+
+impl Add for ProofSize {
+  type Output = Self;
+
+  fn add(mut self, other: Self) -> Self {
+    self += other;
+    self
+  }
+}
+
+impl AddAssign for ProofSize {
+  fn add_assign(&mut self, other: Self) {
+    self.sumcheck += other.sumcheck;
+    self.pcs += other.pcs;
+    self.claims += other.claims;
+    self.instance += other.instance;
+  }
+}
+
+/// The size of `value` serialized with `bincode`
+pub(crate) fn serialized_size<T: Serialize + ?Sized>(value: &T) -> usize {
+  bincode::serialized_size(value).map_or(0, |size| size as usize)
+}
+
 // NOTES: I'm not sure having a trait here is really necessary unless you're
 // wanting to have a much larger abstraction. I'd consider just gutting this and
 // forming one SNARK that we use.
@@ -70,10 +144,49 @@ pub trait RelaxedR1CSSNARKTrait<E: Engine>:
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
+  ) -> Result<Self, NovaError> {
+    Self::prove_with_context(ck, pk, S, U, W, AppContext::default())
+  }
+
+  /// Same as `prove`, absorbing `context` in the transcript, so that the SNARK
+  /// only verifies in the same context
+  fn prove_with_context(
+    ck: &CommitmentKey<E>,
+    pk: &Self::ProverKey,
+    S: &R1CSShape<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError>;
 
   /// Verifies a SNARK for a relaxed R1CS
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError>;
+  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+    self.verify_with_context(vk, U, AppContext::default())
+  }
+
+  /// Verifies a SNARK for a relaxed R1CS made in `context`
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError>;
+
+  /// Same as `verify_with_context`, recording its checks in `trace`. By
+  /// default, the whole verification is a single step.
+  fn verify_with_trace(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &RelaxedR1CSInstance<E>,
+    context: AppContext<'_>,
+    trace: &mut VerifyTrace,
+  ) -> Result<(), NovaError> {
+    trace.check_result(Check::step("snark"), self.verify_with_context(vk, U, context))
+  }
+
+  /// The sizes of the components of the SNARK. By default, the whole SNARK
+  /// is counted as claims.
+  fn size_breakdown(&self) -> ProofSize { ProofSize::of_snark(serialized_size(self), 0, 0) }
 }
 
 /// A trait that defines the behavior of a `zkSNARK` to prove knowledge of
@@ -122,14 +235,37 @@ pub trait BatchedRelaxedR1CSSNARKTrait<E: Engine>:
     S: Vec<&R1CSShape<E>>,
     U: &[RelaxedR1CSInstance<E>],
     W: &[RelaxedR1CSWitness<E>],
+  ) -> Result<Self, NovaError> {
+    Self::prove_with_context(ck, pk, S, U, W, AppContext::default())
+  }
+
+  /// Same as `prove`, absorbing `context` in the transcript, so that the SNARK
+  /// only verifies in the same context
+  fn prove_with_context(
+    ck: &CommitmentKey<E>,
+    pk: &Self::ProverKey,
+    S: Vec<&R1CSShape<E>>,
+    U: &[RelaxedR1CSInstance<E>],
+    W: &[RelaxedR1CSWitness<E>],
+    context: AppContext<'_>,
   ) -> Result<Self, NovaError>;
 
   /// Verifies a SNARK for a batch of relaxed R1CS
-  fn verify(&self, vk: &Self::VerifierKey, U: &[RelaxedR1CSInstance<E>]) -> Result<(), NovaError>;
+  fn verify(&self, vk: &Self::VerifierKey, U: &[RelaxedR1CSInstance<E>]) -> Result<(), NovaError> {
+    self.verify_with_context(vk, U, AppContext::default())
+  }
+
+  /// Verifies a SNARK for a batch of relaxed R1CS made in `context`
+  fn verify_with_context(
+    &self,
+    vk: &Self::VerifierKey,
+    U: &[RelaxedR1CSInstance<E>],
+    context: AppContext<'_>,
+  ) -> Result<(), NovaError>;
 }
 
 /// A helper trait that defines the behavior of a verifier key of `zkSNARK`
 pub trait DigestHelperTrait<E: Engine> {
   /// Returns the digest of the verifier's key
-  fn digest(&self) -> E::Scalar;
+  fn digest(&self) -> Result<E::Scalar, NovaError>;
 }